@@ -1,10 +1,10 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
+use lockbox_shared::error::{error_body, validation_error_body, FieldError, VALIDATION_CODE};
 use log::{error, info, warn};
-use serde_json::json;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -12,18 +12,45 @@ pub enum AppError {
     #[error("Unauthorized: {0}")]
     Unauthorized(String),
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("Not found: {0}")]
     NotFound(String),
 
     #[error("Bad request: {0}")]
     BadRequest(String),
 
+    #[error("Invalid fields: {}", field_names(.0))]
+    Validation(Vec<FieldError>),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+
     #[error("Internal server error: {0}")]
     InternalServerError(String),
 
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
 
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+
+    #[error("Too many requests: {message}")]
+    TooManyRequests {
+        message: String,
+        retry_after_secs: u64,
+    },
+
+    #[error("Too early: {message}")]
+    TooEarly {
+        message: String,
+        retry_after_secs: u64,
+    },
+
     // Add a specific variant for expired invitations with status 422
     #[error("Invitation expired: {0}")]
     InvitationExpired(String),
@@ -36,6 +63,11 @@ impl AppError {
         AppError::Unauthorized(msg)
     }
 
+    pub fn forbidden(msg: String) -> Self {
+        warn!("Forbidden error: {}", msg);
+        AppError::Forbidden(msg)
+    }
+
     pub fn not_found(msg: String) -> Self {
         warn!("Not found error: {}", msg);
         AppError::NotFound(msg)
@@ -46,11 +78,70 @@ impl AppError {
         AppError::BadRequest(msg)
     }
 
+    pub fn validation(fields: Vec<FieldError>) -> Self {
+        warn!("Validation error: {}", field_names(&fields));
+        AppError::Validation(fields)
+    }
+
+    pub fn conflict(msg: String) -> Self {
+        warn!("Conflict error: {}", msg);
+        AppError::Conflict(msg)
+    }
+
+    pub fn payload_too_large(msg: String) -> Self {
+        warn!("Payload too large: {}", msg);
+        AppError::PayloadTooLarge(msg)
+    }
+
+    pub fn too_many_requests(msg: String, retry_after_secs: u64) -> Self {
+        warn!(
+            "Too many requests: {} (retry after {}s)",
+            msg, retry_after_secs
+        );
+        AppError::TooManyRequests {
+            message: msg,
+            retry_after_secs,
+        }
+    }
+
+    pub fn too_early(msg: String, retry_after_secs: u64) -> Self {
+        warn!("Too early: {} (retry after {}s)", msg, retry_after_secs);
+        AppError::TooEarly {
+            message: msg,
+            retry_after_secs,
+        }
+    }
+
     pub fn internal_server_error(msg: String) -> Self {
         error!("Internal server error: {}", msg);
         AppError::InternalServerError(msg)
     }
 
+    /// The error's message without the kind prefix its `Display` adds, for
+    /// reporting next to other results rather than as the whole response
+    pub fn message(&self) -> String {
+        match self {
+            AppError::Unauthorized(msg)
+            | AppError::Forbidden(msg)
+            | AppError::NotFound(msg)
+            | AppError::BadRequest(msg)
+            | AppError::Conflict(msg)
+            | AppError::PayloadTooLarge(msg)
+            | AppError::InternalServerError(msg)
+            | AppError::ServiceUnavailable(msg)
+            | AppError::InvitationExpired(msg) => msg.clone(),
+            AppError::TooManyRequests { message, .. } | AppError::TooEarly { message, .. } => {
+                message.clone()
+            }
+            AppError::Validation(fields) => fields
+                .iter()
+                .map(|field| field.message.as_str())
+                .collect::<Vec<_>>()
+                .join("; "),
+            AppError::SerializationError(err) => err.to_string(),
+        }
+    }
+
     #[allow(dead_code)]
     pub fn internal_error<T: std::fmt::Display>(error: T) -> Self {
         AppError::InternalServerError(error.to_string())
@@ -62,41 +153,115 @@ impl AppError {
     }
 }
 
+fn field_names(fields: &[FieldError]) -> String {
+    fields
+        .iter()
+        .map(|field| field.field.as_str())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match self {
+        // Validation errors list each invalid field alongside the message
+        let invalid_fields = match &self {
+            AppError::Validation(fields) => Some(fields.clone()),
+            _ => None,
+        };
+
+        let retry_after = match &self {
+            AppError::TooManyRequests {
+                retry_after_secs, ..
+            }
+            | AppError::TooEarly {
+                retry_after_secs, ..
+            } => Some(*retry_after_secs),
+            _ => None,
+        };
+
+        let (status, code, error_message) = match self {
             AppError::Unauthorized(msg) => {
                 warn!("Unauthorized error: {}", msg);
-                (StatusCode::UNAUTHORIZED, msg.clone())
+                (StatusCode::UNAUTHORIZED, "UNAUTHORIZED", msg.clone())
+            }
+            AppError::Forbidden(msg) => {
+                warn!("Forbidden error: {}", msg);
+                (StatusCode::FORBIDDEN, "FORBIDDEN", msg.clone())
             }
             AppError::NotFound(msg) => {
                 warn!("Not found error: {}", msg);
-                (StatusCode::NOT_FOUND, msg.clone())
+                (StatusCode::NOT_FOUND, "NOT_FOUND", msg.clone())
             }
             AppError::BadRequest(msg) => {
                 warn!("Bad request error: {}", msg);
-                (StatusCode::BAD_REQUEST, msg.clone())
+                (StatusCode::BAD_REQUEST, "BAD_REQUEST", msg.clone())
+            }
+            AppError::Validation(fields) => (
+                StatusCode::BAD_REQUEST,
+                VALIDATION_CODE,
+                field_names(&fields),
+            ),
+            AppError::Conflict(msg) => {
+                warn!("Conflict error: {}", msg);
+                (StatusCode::CONFLICT, "CONFLICT", msg.clone())
+            }
+            AppError::PayloadTooLarge(msg) => {
+                warn!("Payload too large: {}", msg);
+                (
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    "PAYLOAD_TOO_LARGE",
+                    msg.clone(),
+                )
             }
             AppError::InternalServerError(msg) => {
                 error!("Internal server error: {}", msg);
-                (StatusCode::INTERNAL_SERVER_ERROR, msg.clone())
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "INTERNAL_ERROR",
+                    msg.clone(),
+                )
             }
             AppError::SerializationError(err) => {
                 warn!("Serialization error: {}", err);
-                (StatusCode::BAD_REQUEST, err.to_string())
+                (StatusCode::BAD_REQUEST, "BAD_REQUEST", err.to_string())
+            }
+            AppError::ServiceUnavailable(msg) => {
+                warn!("Service unavailable: {}", msg);
+                (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    "SERVICE_UNAVAILABLE",
+                    msg.clone(),
+                )
             }
+            AppError::TooManyRequests { message, .. } => {
+                (StatusCode::TOO_MANY_REQUESTS, "TOO_MANY_REQUESTS", message)
+            }
+            AppError::TooEarly { message, .. } => (StatusCode::TOO_EARLY, "TOO_EARLY", message),
             AppError::InvitationExpired(msg) => {
                 warn!("Invitation expired: {}", msg);
-                (StatusCode::UNPROCESSABLE_ENTITY, msg.clone())
+                (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "INVITATION_EXPIRED",
+                    msg.clone(),
+                )
             }
         };
 
-        let body = Json(json!({ "error": error_message }));
+        let body = Json(match invalid_fields {
+            Some(fields) => validation_error_body(&fields),
+            None => error_body(code, &error_message),
+        });
         info!(
             "Responding with error: status={}, message={:?}",
             status, body
         );
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
 
@@ -117,12 +282,62 @@ impl From<lockbox_shared::error::StoreError> for AppError {
             lockbox_shared::error::StoreError::AuthError(msg) => AppError::Unauthorized(msg),
             lockbox_shared::error::StoreError::VersionConflict(msg) => {
                 warn!("Concurrent modification detected: {}", msg);
-                AppError::BadRequest(
-                    format!("Concurrent modification detected, please retry: {}", msg).into(),
-                )
+                AppError::Conflict(format!(
+                    "Concurrent modification detected, please retry: {}",
+                    msg
+                ))
+            }
+            lockbox_shared::error::StoreError::AlreadyExists(msg) => {
+                warn!("Create collided with an existing item: {}", msg);
+                AppError::Conflict(format!("Id already in use, please retry: {}", msg))
+            }
+            lockbox_shared::error::StoreError::Throttled(msg) => {
+                AppError::ServiceUnavailable(format!("Storage is busy, please retry: {}", msg))
+            }
+            lockbox_shared::error::StoreError::Transport(msg) => {
+                warn!("Store unreachable: {}", msg);
+                AppError::ServiceUnavailable(format!(
+                    "Storage is unavailable, please retry: {}",
+                    msg
+                ))
+            }
+            lockbox_shared::error::StoreError::Serialization(msg) => {
+                error!("Store serialization error: {}", msg);
+                AppError::InternalServerError(msg)
             }
         }
     }
 }
 
 pub type Result<T> = std::result::Result<T, AppError>;
+
+// The gRPC gateway reports errors with the status codes conventionally paired
+// with the HTTP ones above
+impl From<AppError> for tonic::Status {
+    fn from(err: AppError) -> Self {
+        match err {
+            AppError::Unauthorized(msg) => tonic::Status::unauthenticated(msg),
+            AppError::Forbidden(msg) => tonic::Status::permission_denied(msg),
+            AppError::NotFound(msg) => tonic::Status::not_found(msg),
+            AppError::BadRequest(msg) => tonic::Status::invalid_argument(msg),
+            AppError::Validation(fields) => tonic::Status::invalid_argument(
+                fields
+                    .iter()
+                    .map(|field| field.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            ),
+            AppError::Conflict(msg) => tonic::Status::aborted(msg),
+            AppError::PayloadTooLarge(msg) => tonic::Status::resource_exhausted(msg),
+            AppError::InternalServerError(msg) => {
+                error!("Internal server error: {}", msg);
+                tonic::Status::internal(msg)
+            }
+            AppError::SerializationError(err) => tonic::Status::invalid_argument(err.to_string()),
+            AppError::ServiceUnavailable(msg) => tonic::Status::unavailable(msg),
+            AppError::TooManyRequests { message, .. } => tonic::Status::resource_exhausted(message),
+            AppError::TooEarly { message, .. } => tonic::Status::unavailable(message),
+            AppError::InvitationExpired(msg) => tonic::Status::failed_precondition(msg),
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! gRPC gateway to part of the owner API, described in `proto/box.proto`.
+//!
+//! It runs on its own port next to the HTTP server and shares its store, its
+//! store-level handler logic and its error mapping, so both transports behave
+//! the same. Callers send the same bearer token as over HTTP, in the
+//! `authorization` metadata entry.
+
+use chrono::{DateTime, Utc};
+use lockbox_shared::auth::authenticate;
+use lockbox_shared::clock::SystemClock;
+use lockbox_shared::maintenance::{ReadOnlyMode, MAINTENANCE_MESSAGE};
+use lockbox_shared::models::{Document, Guardian, UnlockApproval, UnlockRequest};
+use lockbox_shared::request_id::RequestId;
+use lockbox_shared::store::BoxStore;
+use log::{info, warn};
+use std::sync::Arc;
+use tonic::{Request, Response, Status};
+
+use crate::handlers::box_handlers::{self, UnacceptedGuardianPolicy};
+use crate::models::{self, OwnedBoxesQuery};
+
+pub mod proto {
+    tonic::include_proto!("lockbox.boxes.v1");
+}
+
+use proto::box_service_server::{BoxService, BoxServiceServer};
+
+/// Serves `lockbox.boxes.v1.BoxService` from a box store
+pub struct BoxGrpcService<S> {
+    store: Arc<S>,
+    lock_policy: UnacceptedGuardianPolicy,
+    read_only: ReadOnlyMode,
+}
+
+impl<S> BoxGrpcService<S>
+where
+    S: BoxStore,
+{
+    pub fn new(store: Arc<S>) -> Self {
+        Self {
+            store,
+            lock_policy: UnacceptedGuardianPolicy::default(),
+            read_only: ReadOnlyMode::default(),
+        }
+    }
+
+    /// Sets what locking does when some guardians haven't accepted yet
+    pub fn with_lock_policy(mut self, lock_policy: UnacceptedGuardianPolicy) -> Self {
+        self.lock_policy = lock_policy;
+        self
+    }
+
+    /// Sets whether writes are refused, as the HTTP API does in maintenance
+    pub fn with_read_only(mut self, read_only: ReadOnlyMode) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    // Refuses a write while the service is read-only for maintenance
+    #[allow(clippy::result_large_err)]
+    fn check_writable(&self, method: &str) -> Result<(), Status> {
+        if self.read_only.0 {
+            warn!("Refused gRPC {} during read-only maintenance", method);
+            return Err(Status::unavailable(MAINTENANCE_MESSAGE));
+        }
+        Ok(())
+    }
+
+    pub fn into_server(self) -> BoxServiceServer<Self> {
+        BoxServiceServer::new(self)
+    }
+}
+
+// The caller's user id, checked the same way as the HTTP auth middleware.
+// tonic::Status is large, but it's what every gRPC method returns anyway.
+#[allow(clippy::result_large_err)]
+async fn authenticated_user<T>(request: &Request<T>) -> Result<String, Status> {
+    let headers = request.metadata().clone().into_headers();
+    let auth = authenticate(&headers)
+        .await
+        .map_err(|rejection| Status::unauthenticated(rejection.message))?;
+    Ok(auth.user_id)
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_timestamp(field: &str, value: Option<String>) -> Result<Option<DateTime<Utc>>, Status> {
+    value
+        .map(|value| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|_| {
+                    Status::invalid_argument(format!("{} must be an RFC 3339 timestamp", field))
+                })
+        })
+        .transpose()
+}
+
+#[tonic::async_trait]
+impl<S> BoxService for BoxGrpcService<S>
+where
+    S: BoxStore,
+{
+    async fn get_box(
+        &self,
+        request: Request<proto::GetBoxRequest>,
+    ) -> Result<Response<proto::BoxResponse>, Status> {
+        let user_id = authenticated_user(&request).await?;
+        let id = request.into_inner().id;
+        info!("gRPC GetBox: box_id={}", id);
+
+        let box_rec = box_handlers::get_owned_box(&*self.store, &id, &user_id).await?;
+        Ok(Response::new(models::BoxResponse::from(box_rec).into()))
+    }
+
+    async fn create_box(
+        &self,
+        request: Request<proto::CreateBoxRequest>,
+    ) -> Result<Response<proto::BoxResponse>, Status> {
+        self.check_writable("CreateBox")?;
+        let user_id = authenticated_user(&request).await?;
+        let payload = request.into_inner();
+        info!("gRPC CreateBox");
+
+        let created_box = box_handlers::create_owned_box(
+            &*self.store,
+            &SystemClock,
+            &user_id,
+            models::CreateBoxRequest {
+                name: payload.name,
+                description: payload.description,
+                owner_name: payload.owner_name,
+            },
+        )
+        .await?;
+        Ok(Response::new(models::BoxResponse::from(created_box).into()))
+    }
+
+    async fn lock_box(
+        &self,
+        request: Request<proto::LockBoxRequest>,
+    ) -> Result<Response<proto::BoxResponse>, Status> {
+        self.check_writable("LockBox")?;
+        let user_id = authenticated_user(&request).await?;
+        let request_id = RequestId::generate();
+        let payload = request.into_inner();
+        info!(
+            "gRPC LockBox: request_id={}, box_id={}",
+            request_id, payload.id
+        );
+
+        let shards = payload
+            .shards
+            .into_iter()
+            .map(|shard| models::IncomingShard {
+                guardian_id: shard.guardian_id,
+                shard: shard.shard,
+                shard_hash: shard.shard_hash,
+            })
+            .collect();
+        let locked_box = box_handlers::lock_owned_box(
+            &*self.store,
+            &SystemClock,
+            &payload.id,
+            &user_id,
+            models::LockBoxRequest {
+                shard_threshold: payload.shard_threshold as usize,
+                shards,
+            },
+            self.lock_policy,
+            &request_id,
+        )
+        .await?;
+        Ok(Response::new(models::BoxResponse::from(locked_box).into()))
+    }
+
+    async fn list_owned_boxes(
+        &self,
+        request: Request<proto::ListOwnedBoxesRequest>,
+    ) -> Result<Response<proto::ListOwnedBoxesResponse>, Status> {
+        let user_id = authenticated_user(&request).await?;
+        let payload = request.into_inner();
+        let query = OwnedBoxesQuery {
+            locked_after: parse_timestamp("locked_after", payload.locked_after)?,
+            locked_before: parse_timestamp("locked_before", payload.locked_before)?,
+            ..Default::default()
+        };
+
+        let boxes = box_handlers::list_owned_boxes(&*self.store, &user_id, &query).await?;
+        Ok(Response::new(proto::ListOwnedBoxesResponse {
+            boxes: boxes
+                .into_iter()
+                .map(|box_rec| models::BoxResponse::from(box_rec).into())
+                .collect(),
+        }))
+    }
+}
+
+impl From<models::BoxResponse> for proto::BoxResponse {
+    fn from(box_response: models::BoxResponse) -> Self {
+        Self {
+            id: box_response.id,
+            name: box_response.name,
+            description: box_response.description,
+            created_at: box_response.created_at,
+            updated_at: box_response.updated_at,
+            unlock_instructions: box_response.unlock_instructions,
+            is_locked: box_response.is_locked,
+            locked_at: box_response.locked_at,
+            documents: box_response.documents.into_iter().map(Into::into).collect(),
+            guardians: box_response.guardians.into_iter().map(Into::into).collect(),
+            owner_id: box_response.owner_id,
+            owner_name: box_response.owner_name,
+            unlock_request: box_response.unlock_request.map(Into::into),
+            shard_threshold: box_response.shard_threshold,
+            shards_fetched: box_response.shards_fetched.map(|n| n as u64),
+            total_shards: box_response.total_shards.map(|n| n as u64),
+            shards_remaining: box_response.shards_remaining.map(|n| n as u64),
+            all_fetched: box_response.all_fetched,
+            shards_deleted_at: box_response.shards_deleted_at,
+            webhook_url: box_response.webhook_url,
+            version: box_response.version,
+            guardian_count: box_response.guardian_count as u64,
+            accepted_count: box_response.accepted_count as u64,
+            pending_guardian_names: box_response.pending_guardian_names,
+        }
+    }
+}
+
+impl From<Document> for proto::Document {
+    fn from(document: Document) -> Self {
+        Self {
+            id: document.id,
+            title: document.title,
+            encrypted_content: document.encrypted_content,
+            created_at: document.created_at,
+            content_type: document.content_type,
+            size_bytes: document.size_bytes,
+            version: document.version,
+            s3_key: document.s3_key,
+        }
+    }
+}
+
+impl From<Guardian> for proto::Guardian {
+    fn from(guardian: Guardian) -> Self {
+        Self {
+            id: guardian.id,
+            name: guardian.name,
+            lead_guardian: guardian.lead_guardian,
+            status: guardian.status.to_string(),
+            added_at: guardian.added_at,
+            invitation_id: guardian.invitation_id,
+            lock_data_received_at: guardian.lock_data_received_at,
+            encrypted_shard: guardian.encrypted_shard,
+            shard_hash: guardian.shard_hash,
+            shard_fetched_at: guardian.shard_fetched_at,
+            shard_accepted_at: guardian.shard_accepted_at,
+            shard_commit: guardian.shard_commit,
+            shard_first_fetched_at: guardian.shard_first_fetched_at,
+        }
+    }
+}
+
+impl From<UnlockRequest> for proto::UnlockRequest {
+    fn from(unlock_request: UnlockRequest) -> Self {
+        Self {
+            id: unlock_request.id,
+            requested_at: unlock_request.requested_at,
+            status: unlock_request.status.to_string(),
+            message: unlock_request.message,
+            reason: unlock_request.reason,
+            initiated_by: unlock_request.initiated_by,
+            approved_by: unlock_request.approved_by,
+            rejected_by: unlock_request.rejected_by,
+            approvals: unlock_request
+                .approvals
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
+
+impl From<UnlockApproval> for proto::UnlockApproval {
+    fn from(approval: UnlockApproval) -> Self {
+        Self {
+            guardian_id: approval.guardian_id,
+            approved: approval.approved,
+            at: approval.at,
+        }
+    }
+}
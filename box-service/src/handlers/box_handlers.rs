@@ -1,53 +1,530 @@
 use aws_sdk_sns::Client as SnsClient;
 use axum::{
-    extract::{Extension, Path, State},
-    http::StatusCode,
-    Json,
+    body::{Body, Bytes},
+    extract::{Extension, OriginalUri, Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
 };
-use lockbox_shared::store::BoxStore;
-use log::{debug, error, info};
+use futures::{future, stream, StreamExt};
+use lockbox_shared::auth::{AuthContext, RequireAdmin};
+use lockbox_shared::clock::{Clock, SharedClock};
+use lockbox_shared::extract::Json;
+use lockbox_shared::idempotency::{claim, idempotency_key, request_hash, scoped_key, Claim};
+use lockbox_shared::paging::pagination_headers;
+use lockbox_shared::push::{
+    group_by_badge, pending_action_badges, shard_reminder_content, NotificationOptions,
+    SharedPushSender, SHARD_REMINDER,
+};
+use lockbox_shared::request_id::RequestId;
+use lockbox_shared::storage::{document_key, SharedDocumentStorage};
+use lockbox_shared::store::{
+    watch, BoxStore, SharedIdempotencyStore, SharedInvitationStore, SharedPushTokenStore,
+    SharedShardChallengeStore,
+};
+use lockbox_shared::validation::{normalize_unlock_instructions, validate_name, MAX_NAME_CHARS};
+use lockbox_shared::webhook::{generate_webhook_secret, validate_webhook_url};
+use log::{debug, error, info, warn};
 use serde_json;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::OnceCell;
+use tokio::time::{self, Instant};
 use uuid::Uuid;
 
 use crate::error::{AppError, Result};
+use crate::handlers::guardian_handlers::publish_guardian_accepted;
+use crate::openapi::{
+    AcceptedGuardianEnvelope, BoxEnvelope, BoxListEnvelope, BoxMessageEnvelope, DocumentEnvelope,
+    DocumentMessageEnvelope, GuardianEnvelope, GuardianMessageEnvelope, PendingGuardiansEnvelope,
+    ReplayedEventEnvelope, ShardAcceptResponse, ShardAckResponse, ShardResponse,
+};
+use lockbox_shared::error::{ErrorBody, ValidationErrorBody};
 // Import models from shared crate
-use lockbox_shared::models::{now_str, BoxRecord, Document, Guardian};
+use lockbox_shared::models::events::{BoxLockedEvent, EVENT_SCHEMA_VERSION};
+use lockbox_shared::models::{
+    BoxRecord, Document, Guardian, GuardianStatus, Invitation, MessageResponse, ShardChallenge,
+};
 // Import request/response types from local models
 use crate::models::{
-    BoxResponse, CreateBoxRequest, DocumentUpdateRequest, DocumentUpdateResponse,
-    GuardianUpdateRequest, GuardianUpdateResponse, LockBoxRequest, OptionalField, UpdateBoxRequest,
+    BoxExport, BoxResponse, CreateBoxRequest, DocumentUpdateRequest, DocumentUpdateResponse,
+    DocumentsBulkUpdateRequest, DocumentsBulkUpdateResponse, DuplicateBoxQuery, ExportBoxesQuery,
+    ExportFormat, ExportedBox, ExportedDocument, GuardianIndexBackfillQuery,
+    GuardianIndexBackfillResponse, GuardianUpdateRequest, GuardianUpdateResponse,
+    GuardiansReplaceRequest, GuardiansReplaceResponse, ImportBoxesResponse, ImportFailure,
+    LockBoxRequest, LockPreviewQuery, LockPreviewResponse, OptionalField, OwnedBoxesQuery,
+    PendingGuardianResponse, RedeemedInvitationRequest, RejectedDocument, RemindAllResponse,
+    ShardChallengeResponse, UnlockInstructionsUpdateRequest, UpdateBoxRequest, WatchBoxQuery,
+    WebhookRegistrationRequest, WebhookRegistrationResponse,
 };
 
-// GET /boxes
+// Most owned boxes returned in one page
+const MAX_OWNED_BOXES_PAGE_SIZE: usize = 100;
+
+// GET /boxes/owned?lockedAfter=&lockedBefore=&limit=&cursor=
+#[utoipa::path(
+    get,
+    path = "/boxes/owned",
+    tag = "owner",
+    params(OwnedBoxesQuery),
+    responses(
+        (status = 200, description = "The caller's boxes", body = BoxListEnvelope,
+            headers(
+                ("Link" = String, description = "`rel=\"next\"` URL of the next page, when there is one"),
+                ("X-Total-Count" = usize, description = "Number of boxes, when no limit was given")
+            )),
+        (status = 400, description = "Invalid lock date range or cursor, or a limit given with a lock date range", body = ErrorBody)
+    )
+)]
 pub async fn get_boxes<S>(
     State(store): State<Arc<S>>,
     Extension(user_id): Extension<String>,
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<OwnedBoxesQuery>,
+) -> Result<(HeaderMap, Json<serde_json::Value>)>
+where
+    S: BoxStore,
+{
+    // Without a limit, keep returning every box in a single response
+    let Some(limit) = query.limit else {
+        let boxes = list_owned_boxes(&*store, &user_id, &query).await?;
+        let my_boxes: Vec<_> = boxes.into_iter().map(BoxResponse::from).collect();
+
+        return Ok((
+            pagination_headers(&uri, None, Some(my_boxes.len())),
+            Json(serde_json::json!({ "boxes": my_boxes })),
+        ));
+    };
+
+    // Lock date ranges are filtered over every box the owner has, so there
+    // is no page of them to resume from
+    if query.locked_after.is_some() || query.locked_before.is_some() {
+        return Err(AppError::bad_request(
+            "limit can't be combined with lockedAfter or lockedBefore.".into(),
+        ));
+    }
+
+    let limit = limit.clamp(1, MAX_OWNED_BOXES_PAGE_SIZE);
+    let page = store
+        .get_boxes_by_owner_paginated(&user_id, limit, query.cursor.as_deref())
+        .await?;
+    let my_boxes: Vec<_> = page.items.into_iter().map(BoxResponse::from).collect();
+
+    Ok((
+        pagination_headers(&uri, page.next_cursor.as_deref(), None),
+        Json(serde_json::json!({
+            "boxes": my_boxes,
+            "nextCursor": page.next_cursor,
+        })),
+    ))
+}
+
+/// The caller's boxes, narrowed to a lock date range when one is given.
+/// Shared by the HTTP and gRPC transports.
+pub(crate) async fn list_owned_boxes<S>(
+    store: &S,
+    user_id: &str,
+    query: &OwnedBoxesQuery,
+) -> Result<Vec<BoxRecord>>
+where
+    S: BoxStore + ?Sized,
+{
+    if query.locked_after.is_none() && query.locked_before.is_none() {
+        return Ok(store.get_boxes_by_owner(user_id).await?);
+    }
+
+    if let (Some(after), Some(before)) = (query.locked_after, query.locked_before) {
+        if after >= before {
+            return Err(AppError::bad_request(
+                "lockedAfter must be earlier than lockedBefore.".into(),
+            ));
+        }
+    }
+    Ok(store
+        .get_boxes_by_owner_locked_between(user_id, query.locked_after, query.locked_before)
+        .await?)
+}
+
+// Boxes read from the store at a time while collecting pending guardians
+const PENDING_GUARDIANS_PAGE_SIZE: usize = 100;
+
+// GET /boxes/owned/pending-guardians
+// Every guardian, across the caller's locked boxes, who hasn't accepted their
+// shard yet: the same guardians the reminder service reminds
+#[utoipa::path(
+    get,
+    path = "/boxes/owned/pending-guardians",
+    tag = "owner",
+    responses(
+        (status = 200, description = "Guardians still to accept their shard", body = PendingGuardiansEnvelope)
+    )
+)]
+pub async fn get_pending_guardians<S>(
+    State(store): State<Arc<S>>,
+    Extension(user_id): Extension<String>,
+    clock: SharedClock,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
-    // Get boxes from store
-    let boxes = store.get_boxes_by_owner(&user_id).await?;
+    let now = clock.now();
+    let mut pending = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = store
+            .get_boxes_by_owner_paginated(&user_id, PENDING_GUARDIANS_PAGE_SIZE, cursor.as_deref())
+            .await?;
+        for box_rec in &page.items {
+            let Some(locked_at) = box_rec.locked_at_time() else {
+                continue;
+            };
+            pending.extend(box_rec.guardians_awaiting_acceptance().map(|guardian| {
+                PendingGuardianResponse {
+                    box_id: box_rec.id.clone(),
+                    box_name: box_rec.name.clone(),
+                    guardian_id: guardian.id.clone(),
+                    guardian_name: guardian.name.clone(),
+                    hours_since_lock: (now - locked_at).num_hours(),
+                }
+            }));
+        }
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    Ok(Json(serde_json::json!({ "guardians": pending })))
+}
+
+// Shortest gap between two reminders the owner sends one guardian of a box
+const MANUAL_REMINDER_COOLDOWN_SECS: i64 = 60 * 60;
+
+// POST /boxes/owned/:id/remind-all
+// Reminds every guardian of a locked box who hasn't accepted their shard yet
+#[utoipa::path(
+    post,
+    path = "/boxes/owned/{id}/remind-all",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID")),
+    responses(
+        (status = 200, description = "How many guardians were reminded, and why others weren't", body = RemindAllResponse),
+        (status = 400, description = "The box isn't locked", body = ErrorBody),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody),
+        (status = 503, description = "Expo could not be reached", body = ErrorBody)
+    )
+)]
+pub async fn remind_pending_guardians<S>(
+    State(store): State<Arc<S>>,
+    Path(id): Path<String>,
+    Extension(user_id): Extension<String>,
+    Extension(push_tokens): Extension<SharedPushTokenStore>,
+    Extension(push_sender): Extension<SharedPushSender>,
+    clock: SharedClock,
+    request_id: RequestId,
+) -> Result<Json<RemindAllResponse>>
+where
+    S: BoxStore,
+{
+    let box_rec = store.get_box_consistent(&id).await?;
+
+    if box_rec.owner_id != user_id {
+        return Err(AppError::unauthorized(
+            "You don't have permission to send reminders for this box".into(),
+        ));
+    }
+
+    if !box_rec.is_locked {
+        return Err(AppError::bad_request(
+            "Reminders can only be sent for locked boxes.".into(),
+        ));
+    }
+
+    let now = clock.now();
+    let cooldown_start = now - chrono::Duration::seconds(MANUAL_REMINDER_COOLDOWN_SECS);
+    let mut summary = RemindAllResponse::default();
+    let mut guardian_ids = Vec::new();
+    // Invited placeholders have no user, so nothing to send to
+    for guardian in box_rec
+        .guardians_awaiting_acceptance()
+        .filter(|g| !g.id.is_empty())
+    {
+        let recently_reminded = guardian
+            .last_reminded_at
+            .as_deref()
+            .and_then(|at| chrono::DateTime::parse_from_rfc3339(at).ok())
+            .is_some_and(|at| at > cooldown_start);
+        if recently_reminded {
+            summary.rate_limited += 1;
+        } else {
+            guardian_ids.push(guardian.id.clone());
+        }
+    }
+
+    let mut tokens = Vec::new();
+    if !guardian_ids.is_empty() {
+        for token in push_tokens.get_push_tokens(&guardian_ids).await? {
+            if token
+                .notification_prefs
+                .as_ref()
+                .is_some_and(|prefs| !prefs.reminders_enabled)
+            {
+                summary.reminders_disabled += 1;
+            } else {
+                tokens.push(token);
+            }
+        }
+    }
+    summary.no_push_token = guardian_ids.len() - tokens.len() - summary.reminders_disabled;
+
+    if tokens.is_empty() {
+        return Ok(Json(summary));
+    }
+
+    let owner_name = box_rec.owner_name.as_deref().unwrap_or("Someone");
+    let (title, body, data) = shard_reminder_content(&box_rec.name, owner_name, &box_rec.id, 1);
+
+    // Each guardian's badge is their own pending-action count; guardians
+    // sharing a count are sent to together
+    let recipients: Vec<String> = tokens.iter().map(|t| t.user_id.clone()).collect();
+    let badges = pending_action_badges(&*store, &recipients).await;
+    let batches = group_by_badge(&tokens, &badges);
+    let reminded_at = now.to_rfc3339();
+    let mut attempted = 0;
+    for (badge, batch) in batches {
+        let tickets = push_sender
+            .send_push_notifications(
+                &batch,
+                title,
+                &body,
+                Some(data.clone()),
+                &NotificationOptions::for_type(SHARD_REMINDER, Some(badge)),
+            )
+            .await
+            .map_err(|e| AppError::ServiceUnavailable(e.to_string()))?;
+        attempted += batch.len();
+
+        let reminded: HashSet<&str> = batch
+            .iter()
+            .zip(&tickets)
+            .filter(|(_, ticket)| ticket.is_ok())
+            .map(|(token, _)| token.user_id.as_str())
+            .collect();
+        summary.sent += reminded.len();
+        if reminded.is_empty() {
+            continue;
+        }
+
+        // Record each batch as it goes out, so a later failure can't lose it.
+        // mutate_box rereads the box when another write got there first; the
+        // reminders have been sent, so a failed save is logged rather than
+        // returned, which could get them sent again on a retry.
+        let saved = store
+            .mutate_box(&id, |box_rec| {
+                for guardian in box_rec.guardians.iter_mut() {
+                    if reminded.contains(guardian.id.as_str()) {
+                        guardian.last_reminded_at = Some(reminded_at.clone());
+                    }
+                }
+                box_rec.updated_at = clock.now_str();
+                Ok::<(), AppError>(())
+            })
+            .await;
+        if let Err(e) = saved {
+            error!(
+                "Failed to record reminders: request_id={}, box_id={}, error={:?}",
+                request_id, id, e
+            );
+        }
+    }
+    summary.failed = attempted - summary.sent;
+
+    info!(
+        "Owner reminded pending guardians: request_id={}, box_id={}, sent={}, no_push_token={}, rate_limited={}",
+        request_id, id, summary.sent, summary.no_push_token, summary.rate_limited
+    );
+    Ok(Json(summary))
+}
+
+/// Header a guardian echoes a step-up challenge back in when fetching their shard
+pub const SHARD_CHALLENGE_HEADER: &str = "x-shard-challenge";
+
+// How long a step-up challenge can be redeemed for
+const SHARD_CHALLENGE_TTL_SECS: i64 = 60;
+
+fn step_up_unavailable() -> AppError {
+    AppError::ServiceUnavailable("Step-up challenges are not available right now".into())
+}
+
+// POST /boxes/guardian/:id/shard/challenge
+// Issues a single-use challenge for the caller to echo back in
+// X-Shard-Challenge when fetching their shard of a box that requires step-up
+#[utoipa::path(
+    post,
+    path = "/boxes/guardian/{id}/shard/challenge",
+    tag = "guardian",
+    params(("id" = String, Path, description = "Box ID")),
+    responses(
+        (status = 201, description = "The challenge, and when it stops being accepted", body = ShardChallengeResponse),
+        (status = 401, description = "Not a guardian of the box", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody),
+        (status = 503, description = "Challenges can't be issued", body = ErrorBody)
+    )
+)]
+pub async fn create_shard_challenge<S>(
+    State(store): State<Arc<S>>,
+    Path(id): Path<String>,
+    Extension(user_id): Extension<String>,
+    challenges: Option<Extension<SharedShardChallengeStore>>,
+    clock: SharedClock,
+) -> Result<(StatusCode, Json<serde_json::Value>)>
+where
+    S: BoxStore,
+{
+    let box_rec = store.get_box(&id).await?;
+    if !box_rec.guardians.iter().any(|g| g.id == user_id) {
+        return Err(AppError::unauthorized(
+            "You are not a guardian for this box.".into(),
+        ));
+    }
+    let Some(Extension(challenges)) = challenges else {
+        return Err(step_up_unavailable());
+    };
+
+    let expires_at = clock.now() + chrono::Duration::seconds(SHARD_CHALLENGE_TTL_SECS);
+    let challenge = challenges
+        .create_challenge(ShardChallenge {
+            challenge: Uuid::new_v4().simple().to_string(),
+            guardian_id: user_id.clone(),
+            box_id: id.clone(),
+            expires_at: expires_at.timestamp(),
+        })
+        .await?;
+    info!(
+        "Issued shard challenge: box_id={}, guardian_id={}",
+        id, user_id
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!(ShardChallengeResponse {
+            challenge: challenge.challenge,
+            expires_at: expires_at.to_rfc3339(),
+        })),
+    ))
+}
+
+// Redeems the challenge sent with a shard fetch. It must have been issued to
+// this guardian for this box, and not have expired or been used already.
+// Returns the redeemed challenge so it can be restored if the fetch fails.
+async fn redeem_shard_challenge(
+    challenges: Option<&SharedShardChallengeStore>,
+    headers: &HeaderMap,
+    box_id: &str,
+    guardian_id: &str,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<ShardChallenge> {
+    let Some(challenges) = challenges else {
+        return Err(step_up_unavailable());
+    };
+    let challenge = headers
+        .get(SHARD_CHALLENGE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .ok_or_else(|| {
+            AppError::forbidden(
+                "This box requires a step-up challenge; request one and send it in the X-Shard-Challenge header.".into(),
+            )
+        })?;
+
+    // Taken whatever the outcome, so a challenge is never tried twice
+    let issued = challenges.take_challenge(challenge).await?;
+    match issued {
+        Some(issued)
+            if issued.guardian_id == guardian_id
+                && issued.box_id == box_id
+                && now.timestamp() < issued.expires_at =>
+        {
+            Ok(issued)
+        }
+        _ => {
+            warn!(
+                "Rejected shard challenge: box_id={}, guardian_id={}",
+                box_id, guardian_id
+            );
+            Err(AppError::forbidden(
+                "The step-up challenge is invalid, expired or already used.".into(),
+            ))
+        }
+    }
+}
 
-    let my_boxes: Vec<_> = boxes.into_iter().map(BoxResponse::from).collect();
+// Sets the guardian's first-fetch time unless a concurrent fetch got there
+// first, and returns whichever time was stored
+async fn record_shard_first_fetch<S>(
+    store: &S,
+    box_id: &str,
+    guardian_id: &str,
+    now: String,
+) -> Result<String>
+where
+    S: BoxStore,
+{
+    let updated = store
+        .mutate_box(box_id, |box_rec| {
+            let guardian = box_rec
+                .guardians
+                .iter_mut()
+                .find(|g| g.id == guardian_id)
+                .ok_or_else(|| {
+                    AppError::unauthorized("You are not a guardian for this box.".into())
+                })?;
+            if guardian.shard_first_fetched_at.is_none() {
+                guardian.shard_first_fetched_at = Some(now.clone());
+                box_rec.updated_at = now.clone();
+            }
+            Ok::<(), AppError>(())
+        })
+        .await?;
 
-    Ok(Json(serde_json::json!({ "boxes": my_boxes })))
+    updated
+        .guardians
+        .iter()
+        .find(|g| g.id == guardian_id)
+        .and_then(|g| g.shard_first_fetched_at.clone())
+        .ok_or_else(|| AppError::internal_server_error("Failed to record shard fetch.".into()))
 }
 
 // GET /boxes/guardian/:id/shard
+#[utoipa::path(
+    get,
+    path = "/boxes/guardian/{id}/shard",
+    tag = "guardian",
+    params(("id" = String, Path, description = "Box ID")),
+    responses(
+        (status = 200, description = "The caller's shard", body = ShardResponse),
+        (status = 400, description = "The box isn't locked, or the shard was already removed", body = ErrorBody),
+        (status = 401, description = "Not a guardian of the box", body = ErrorBody),
+        (status = 403, description = "The box requires step-up and the challenge is missing, invalid, expired or used", body = ErrorBody),
+        (status = 404, description = "Box or shard not found", body = ErrorBody),
+        (status = 425, description = "The box was locked too recently; see Retry-After", body = ErrorBody)
+    )
+)]
 pub async fn fetch_guardian_shard<S>(
     State(store): State<Arc<S>>,
     Path(id): Path<String>,
     Extension(user_id): Extension<String>,
+    grace_period: Option<Extension<ShardFetchGracePeriod>>,
+    challenges: Option<Extension<SharedShardChallengeStore>>,
+    clock: SharedClock,
+    headers: HeaderMap,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
-    let mut box_rec = store.get_box(&id).await?;
+    let box_rec = store.get_box_consistent(&id).await?;
 
     if !box_rec.is_locked {
         return Err(AppError::bad_request(
@@ -61,12 +538,32 @@ where
         .position(|g| g.id == user_id)
         .ok_or_else(|| AppError::unauthorized("You are not a guardian for this box.".into()))?;
 
+    let grace_period = grace_period
+        .map(|Extension(grace_period)| grace_period)
+        .unwrap_or_default();
+    if let Some(retry_after) = grace_period.remaining(&box_rec, clock.now()) {
+        return Err(AppError::too_early(
+            "The box was locked moments ago; shards can be fetched shortly.".into(),
+            retry_after,
+        ));
+    }
+
+    let challenges = challenges.map(|Extension(challenges)| challenges);
+    let redeemed = if box_rec.require_step_up {
+        Some(
+            redeem_shard_challenge(challenges.as_ref(), &headers, &id, &user_id, clock.now())
+                .await?,
+        )
+    } else {
+        None
+    };
+
     let total_shards = box_rec.guardians.len();
     let shard_threshold = box_rec
         .shard_threshold
         .unwrap_or_else(|| total_shards as u32);
 
-    let guardian = &mut box_rec.guardians[guardian_index];
+    let guardian = &box_rec.guardians[guardian_index];
 
     if guardian.encrypted_shard.is_none() {
         return Err(AppError::bad_request(
@@ -74,31 +571,103 @@ where
         ));
     }
 
+    if !guardian.verify_shard_commit() {
+        error!(
+            "Shard commitment mismatch for guardian {} on box_id={}",
+            user_id, id
+        );
+        return Err(AppError::internal_server_error(
+            "Stored shard failed integrity verification.".into(),
+        ));
+    }
+
     let shard = guardian
         .encrypted_shard
         .clone()
         .ok_or_else(|| AppError::not_found("Shard not available for this guardian.".into()))?;
     let shard_hash = guardian.shard_hash.clone();
+    let shard_fetched_at = guardian.shard_fetched_at.clone();
+
+    // Record the first time the shard was served for the owner's audit trail
+    let shard_first_fetched_at = match guardian.shard_first_fetched_at.clone() {
+        Some(first_fetched_at) => first_fetched_at,
+        None => match record_shard_first_fetch(&*store, &id, &user_id, clock.now_str()).await {
+            Ok(first_fetched_at) => first_fetched_at,
+            Err(e) => {
+                // The challenge was spent on a fetch that didn't go through,
+                // so hand it back for the guardian to retry with
+                if let (Some(challenges), Some(issued)) = (challenges.as_ref(), redeemed) {
+                    if let Err(restore_err) = challenges.create_challenge(issued).await {
+                        warn!(
+                            "Failed to restore shard challenge: box_id={}, guardian_id={}, error={}",
+                            id, user_id, restore_err
+                        );
+                    }
+                }
+                return Err(e);
+            }
+        },
+    };
 
     Ok(Json(serde_json::json!({
         "encryptedShard": shard,
         "shardHash": shard_hash,
-        "shardFetchedAt": guardian.shard_fetched_at,
+        "shardFetchedAt": shard_fetched_at,
+        "shardFirstFetchedAt": shard_first_fetched_at,
         "shardThreshold": shard_threshold,
         "totalShards": total_shards
     })))
 }
 
+/// How long after a box is locked its guardians must wait before fetching
+/// their shards, so the owner has a window to catch an accidental lock. The
+/// default of zero lets them fetch straight away.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ShardFetchGracePeriod(pub Duration);
+
+impl ShardFetchGracePeriod {
+    /// From `SHARD_FETCH_GRACE_SECS`
+    pub fn from_env() -> Self {
+        let secs = env::var("SHARD_FETCH_GRACE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0);
+        Self(Duration::from_secs(secs))
+    }
+
+    /// Seconds left before the box's shards can be fetched, or None if they
+    /// can be fetched now
+    fn remaining(&self, box_rec: &BoxRecord, now: chrono::DateTime<chrono::Utc>) -> Option<u64> {
+        let locked_at = box_rec.locked_at_time()?;
+        let grace = chrono::Duration::from_std(self.0).ok()?;
+        let remaining_ms = (locked_at + grace - now).num_milliseconds();
+        // Rounded up so Retry-After never lands inside the grace period
+        (remaining_ms > 0).then(|| (remaining_ms as u64).div_ceil(1000))
+    }
+}
+
 // PATCH /boxes/guardian/:id/shard/ack
+#[utoipa::path(
+    patch,
+    path = "/boxes/guardian/{id}/shard/ack",
+    tag = "guardian",
+    params(("id" = String, Path, description = "Box ID")),
+    responses(
+        (status = 200, description = "Shard receipt recorded", body = ShardAckResponse),
+        (status = 401, description = "Not a guardian of the box", body = ErrorBody),
+        (status = 404, description = "Box or shard not found", body = ErrorBody)
+    )
+)]
 pub async fn acknowledge_guardian_shard<S>(
     State(store): State<Arc<S>>,
     Path(id): Path<String>,
     Extension(user_id): Extension<String>,
+    clock: SharedClock,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
-    let mut box_rec = store.get_box(&id).await?;
+    let mut box_rec = store.get_box_consistent(&id).await?;
 
     if !box_rec.is_locked {
         return Err(AppError::bad_request(
@@ -117,10 +686,13 @@ where
     let guardian = &mut box_rec.guardians[guardian_index];
 
     if guardian.shard_fetched_at.is_some() && guardian.encrypted_shard.is_none() {
+        let fetched_count = box_rec.shards_fetched.unwrap_or(0);
         return Ok(Json(serde_json::json!({
             "shardFetchedAt": guardian.shard_fetched_at.clone(),
             "totalShards": total_shards,
-            "shardsFetched": box_rec.shards_fetched.unwrap_or(0),
+            "shardsFetched": fetched_count,
+            "shardsRemaining": total_shards.saturating_sub(fetched_count),
+            "allFetched": fetched_count >= total_shards,
         })));
     }
 
@@ -130,7 +702,7 @@ where
         ));
     }
 
-    let fetched_at = now_str();
+    let fetched_at = clock.now_str();
     guardian.shard_fetched_at = Some(fetched_at.clone());
     guardian.encrypted_shard = None;
 
@@ -142,7 +714,14 @@ where
     box_rec.shards_fetched = Some(fetched_count);
     box_rec.total_shards = Some(total_shards);
     if fetched_count == total_shards {
-        box_rec.shards_deleted_at = Some(now_str());
+        let lingering = clear_remaining_shards(&mut box_rec);
+        if lingering > 0 {
+            error!(
+                "Shards still stored after every guardian fetched theirs, now cleared: box_id={}, count={}",
+                box_rec.id, lingering
+            );
+        }
+        box_rec.shards_deleted_at = Some(clock.now_str());
     }
 
     let _ = store.update_box(box_rec).await?;
@@ -150,22 +729,50 @@ where
     Ok(Json(serde_json::json!({
         "shardFetchedAt": fetched_at,
         "totalShards": total_shards,
-        "shardsFetched": fetched_count
+        "shardsFetched": fetched_count,
+        "shardsRemaining": total_shards.saturating_sub(fetched_count),
+        "allFetched": fetched_count >= total_shards,
     })))
 }
 
+// Clears the shards and shard hashes still stored for the box's guardians
+// once every shard has been fetched, returning how many shards were left.
+// Each ack removes its own shard, so any left over were missed.
+fn clear_remaining_shards(box_rec: &mut BoxRecord) -> usize {
+    let mut lingering = 0;
+    for guardian in box_rec.guardians.iter_mut() {
+        if guardian.encrypted_shard.take().is_some() {
+            lingering += 1;
+        }
+        guardian.shard_hash = None;
+    }
+    lingering
+}
+
 // POST /boxes/guardian/:id/shard/accept
 // "Accept" the shard - this is a placebo action for UX purposes.
 // The shard data is already stored/fetched; this just records user acknowledgment.
+#[utoipa::path(
+    post,
+    path = "/boxes/guardian/{id}/shard/accept",
+    tag = "guardian",
+    params(("id" = String, Path, description = "Box ID")),
+    responses(
+        (status = 200, description = "Shard accepted", body = ShardAcceptResponse),
+        (status = 401, description = "Not a guardian of the box", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
 pub async fn accept_guardian_shard<S>(
     State(store): State<Arc<S>>,
     Path(id): Path<String>,
     Extension(user_id): Extension<String>,
+    clock: SharedClock,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
-    let mut box_rec = store.get_box(&id).await?;
+    let mut box_rec = store.get_box_consistent(&id).await?;
 
     if !box_rec.is_locked {
         return Err(AppError::bad_request(
@@ -192,9 +799,10 @@ where
     }
 
     // Mark as accepted
-    let accepted_at = now_str();
+    let accepted_at = clock.now_str();
     guardian.shard_accepted_at = Some(accepted_at.clone());
-    box_rec.updated_at = now_str();
+    box_rec.updated_at = clock.now_str();
+    box_rec.refresh_all_shards_accepted();
 
     let box_name = box_rec.name.clone();
     let box_id = box_rec.id.clone();
@@ -212,6 +820,17 @@ where
 }
 
 // GET /boxes/:id
+#[utoipa::path(
+    get,
+    path = "/boxes/owned/{id}",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID")),
+    responses(
+        (status = 200, description = "The box", body = BoxEnvelope),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
 pub async fn get_box<S>(
     State(store): State<Arc<S>>,
     Path(id): Path<String>,
@@ -220,15 +839,7 @@ pub async fn get_box<S>(
 where
     S: BoxStore,
 {
-    // Get box from store
-    let box_rec = store.get_box(&id).await?;
-
-    // TODO: Is it safe to check here or should we do filter in the db query?
-    if box_rec.owner_id != user_id {
-        return Err(AppError::unauthorized(
-            "You don't have permission to view this box".into(),
-        ));
-    }
+    let box_rec = get_owned_box(&*store, &id, &user_id).await?;
 
     // Return full box info for owner
     Ok(Json(serde_json::json!({
@@ -236,321 +847,2205 @@ where
     })))
 }
 
-// POST /boxes
-pub async fn create_box<S>(
+// Boxes read from the store at a time while exporting
+const EXPORT_PAGE_SIZE: usize = 25;
+
+// GET /boxes/owned/export?format=json|csv
+// Streams every box the caller owns, a page of boxes at a time, so they can
+// keep a copy or move it elsewhere. Documents in locked boxes are exported
+// without their content, and nothing about guardians or shards is included.
+#[utoipa::path(
+    get,
+    path = "/boxes/owned/export",
+    tag = "owner",
+    params(ExportBoxesQuery),
+    responses(
+        (status = 200, description = "The caller's boxes and documents. CSV has one row per document.",
+            content((BoxExport = "application/json"), (String = "text/csv"))),
+        (status = 400, description = "Unknown format", body = ErrorBody)
+    )
+)]
+pub async fn export_boxes<S>(
     State(store): State<Arc<S>>,
     Extension(user_id): Extension<String>,
-    Json(payload): Json<CreateBoxRequest>,
-) -> Result<(StatusCode, Json<serde_json::Value>)>
+    Extension(document_storage): Extension<Option<SharedDocumentStorage>>,
+    Query(query): Query<ExportBoxesQuery>,
+) -> Result<Response>
 where
     S: BoxStore,
 {
-    let now = now_str();
-    let new_box = BoxRecord {
-        id: Uuid::new_v4().to_string(),
-        name: payload.name,
-        description: payload.description,
-        is_locked: false,
-        locked_at: None,
-        created_at: now.clone(),
-        updated_at: now.clone(),
-        owner_id: user_id,
-        owner_name: payload.owner_name,
-        documents: vec![],
-        guardians: vec![],
-        unlock_instructions: None,
-        unlock_request: None,
-        version: 0,
-        shard_threshold: None,
-        shards_fetched: None,
-        total_shards: None,
-        shards_deleted_at: None,
-    };
+    // Read the first page before answering, so a failing store still gets an
+    // error status rather than a truncated 200
+    let (first_boxes, next_cursor) =
+        export_page(&*store, &user_id, document_storage.as_ref(), None).await?;
+    info!(
+        "Exporting boxes: user_id={}, format={:?}",
+        user_id, query.format
+    );
 
-    // Create the box in store
-    let created_box = store.create_box(new_box).await?;
+    let remaining = stream::try_unfold(next_cursor, move |cursor| {
+        let store = store.clone();
+        let user_id = user_id.clone();
+        let document_storage = document_storage.clone();
+        async move {
+            let Some(cursor) = cursor else {
+                return Ok(None);
+            };
+            let page =
+                export_page(&*store, &user_id, document_storage.as_ref(), Some(&cursor)).await?;
+            Ok(Some(page))
+        }
+    });
+    let pages = stream::once(future::ready(Ok(first_boxes))).chain(remaining);
+
+    let (content_type, extension, body) = match query.format {
+        ExportFormat::Json => {
+            let mut first = true;
+            let boxes =
+                pages.map(move |page| page.and_then(|boxes| json_export_chunk(&boxes, &mut first)));
+            let body = stream::once(future::ready(Ok(Bytes::from_static(b"{\"boxes\":["))))
+                .chain(boxes)
+                .chain(stream::once(future::ready(Ok(Bytes::from_static(b"]}")))));
+            ("application/json", "json", Body::from_stream(body))
+        }
+        ExportFormat::Csv => {
+            let header = stream::once(future::ready(csv_chunk([EXPORT_CSV_COLUMNS.to_vec()])));
+            let rows = pages.map(|page| page.and_then(|boxes| csv_chunk(csv_export_rows(&boxes))));
+            ("text/csv", "csv", Body::from_stream(header.chain(rows)))
+        }
+    };
 
-    Ok((
-        StatusCode::CREATED,
-        Json(serde_json::json!({ "box": BoxResponse::from(created_box) })),
-    ))
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"lockbox-export.{}\"", extension),
+        )
+        .body(body)
+        .map_err(|e| AppError::internal_server_error(format!("Failed to build response: {}", e)))
 }
 
-// PATCH /boxes/:id
-pub async fn update_box<S>(
-    State(store): State<Arc<S>>,
-    Path(id): Path<String>,
-    Extension(user_id): Extension<String>,
-    Json(payload): Json<UpdateBoxRequest>,
-) -> Result<Json<serde_json::Value>>
+// Columns of a CSV export, which has a row per document
+const EXPORT_CSV_COLUMNS: [&str; 13] = [
+    "boxId",
+    "boxName",
+    "boxDescription",
+    "boxCreatedAt",
+    "boxUpdatedAt",
+    "isLocked",
+    "lockedAt",
+    "documentId",
+    "documentTitle",
+    "documentCreatedAt",
+    "contentType",
+    "sizeBytes",
+    "encryptedContent",
+];
+
+// Reads one page of the owner's boxes for export, returning the cursor of the
+// next page
+async fn export_page<S>(
+    store: &S,
+    user_id: &str,
+    document_storage: Option<&SharedDocumentStorage>,
+    cursor: Option<&str>,
+) -> Result<(Vec<ExportedBox>, Option<String>)>
 where
-    S: BoxStore,
+    S: BoxStore + ?Sized,
 {
-    // Get the current box from store
-    let mut box_rec = store.get_box(&id).await?;
-
-    // Check if the user is the owner
-    if box_rec.owner_id != user_id {
-        return Err(AppError::unauthorized(
-            "You don't have permission to update this box".into(),
-        ));
-    }
-
-    // Check if box is locked - prevent modifications
-    let has_other_updates = payload.name.is_some()
-        || payload.description.is_some()
-        || payload.unlock_instructions.is_some();
-
-    if box_rec.is_locked && has_other_updates {
-        return Err(AppError::bad_request(
-            "Cannot modify a locked box. Locked boxes are immutable.".into(),
-        ));
+    let page = store
+        .get_boxes_by_owner_paginated(user_id, EXPORT_PAGE_SIZE, cursor)
+        .await?;
+    let mut boxes = Vec::with_capacity(page.items.len());
+    for box_rec in page.items {
+        boxes.push(export_box(box_rec, document_storage).await?);
     }
+    Ok((boxes, page.next_cursor))
+}
 
-    // Update fields if provided
-    if let Some(name) = payload.name {
-        box_rec.name = name;
+// Converts a box for export, pulling offloaded document content back in
+async fn export_box(
+    box_rec: BoxRecord,
+    document_storage: Option<&SharedDocumentStorage>,
+) -> Result<ExportedBox> {
+    let mut documents = Vec::with_capacity(box_rec.documents.len());
+    for document in box_rec.documents {
+        let encrypted_content = match (box_rec.is_locked, &document.s3_key) {
+            (true, _) => None,
+            (false, Some(key)) => {
+                let storage = document_storage.ok_or_else(|| {
+                    AppError::internal_server_error(format!(
+                        "Document {} is offloaded but document storage is not configured",
+                        document.id
+                    ))
+                })?;
+                let content = storage.get_document(key).await?;
+                Some(String::from_utf8_lossy(&content).into_owned())
+            }
+            (false, None) => document.encrypted_content,
+        };
+        documents.push(ExportedDocument {
+            id: document.id,
+            title: document.title,
+            created_at: document.created_at,
+            content_type: document.content_type,
+            size_bytes: document.size_bytes,
+            encrypted_content,
+        });
     }
 
-    if let Some(description) = payload.description {
-        box_rec.description = description;
-    }
+    Ok(ExportedBox {
+        id: box_rec.id,
+        name: box_rec.name,
+        description: box_rec.description,
+        created_at: box_rec.created_at,
+        updated_at: box_rec.updated_at,
+        owner_name: box_rec.owner_name,
+        unlock_instructions: box_rec.unlock_instructions,
+        is_locked: box_rec.is_locked,
+        locked_at: box_rec.locked_at,
+        documents,
+    })
+}
 
-    // For unlock_instructions, we need to handle both the case of setting it to a value
-    // or explicitly clearing it by setting it to None
-    if let Some(field) = &payload.unlock_instructions {
-        match field {
-            OptionalField::Value(val) => box_rec.unlock_instructions = Some(val.clone()),
-            OptionalField::Null => box_rec.unlock_instructions = None,
+// The next elements of a JSON export's `boxes` array
+fn json_export_chunk(boxes: &[ExportedBox], first: &mut bool) -> Result<Bytes> {
+    let mut chunk = Vec::new();
+    for exported in boxes {
+        if !*first {
+            chunk.push(b',');
         }
+        *first = false;
+        serde_json::to_writer(&mut chunk, exported)?;
     }
+    Ok(Bytes::from(chunk))
+}
 
-    if let Some(is_locked) = payload.is_locked {
-        // Prevent unlocking a locked box
-        if box_rec.is_locked && !is_locked {
-            return Err(AppError::bad_request(
-                "Cannot unlock a locked box. Locked boxes are immutable.".into(),
-            ));
+// Flattens boxes to CSV rows, one per document. A box without documents still
+// gets a row, with the document columns left empty.
+fn csv_export_rows(boxes: &[ExportedBox]) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    for exported in boxes {
+        let box_columns = vec![
+            exported.id.clone(),
+            exported.name.clone(),
+            exported.description.clone(),
+            exported.created_at.clone(),
+            exported.updated_at.clone(),
+            exported.is_locked.to_string(),
+            exported.locked_at.clone().unwrap_or_default(),
+        ];
+        if exported.documents.is_empty() {
+            let mut row = box_columns.clone();
+            row.resize(EXPORT_CSV_COLUMNS.len(), String::new());
+            rows.push(row);
         }
-
-        // If locking the box for the first time, set locked_at timestamp
-        if is_locked && !box_rec.is_locked {
-            box_rec.locked_at = Some(now_str());
+        for document in &exported.documents {
+            let mut row = box_columns.clone();
+            row.extend([
+                document.id.clone(),
+                document.title.clone(),
+                document.created_at.clone(),
+                document.content_type.clone().unwrap_or_default(),
+                document
+                    .size_bytes
+                    .map(|size| size.to_string())
+                    .unwrap_or_default(),
+                document.encrypted_content.clone().unwrap_or_default(),
+            ]);
+            rows.push(row);
         }
-        box_rec.is_locked = is_locked;
     }
-
-    // Save the updated box
-    let updated_box = store.update_box(box_rec).await?;
-
-    Ok(Json(
-        serde_json::json!({ "box": BoxResponse::from(updated_box) }),
-    ))
+    rows
 }
 
-// POST /boxes/owned/:id/lock
-pub async fn lock_box<S>(
+// Encodes rows as CSV, quoting fields where needed
+fn csv_chunk<R, F>(rows: impl IntoIterator<Item = R>) -> Result<Bytes>
+where
+    R: IntoIterator<Item = F>,
+    F: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in rows {
+        writer
+            .write_record(row)
+            .map_err(|e| AppError::internal_server_error(format!("Failed to write CSV: {}", e)))?;
+    }
+    let chunk = writer
+        .into_inner()
+        .map_err(|e| AppError::internal_server_error(format!("Failed to write CSV: {}", e)))?;
+    Ok(Bytes::from(chunk))
+}
+
+// POST /boxes/owned/:id/duplicate?includeDocuments=true
+// Starts a new, unlocked box from one of the caller's boxes. Guardians are
+// carried over by name and role as fresh invitations, without shards; the
+// documents only when asked for.
+#[utoipa::path(
+    post,
+    path = "/boxes/owned/{id}/duplicate",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID"), DuplicateBoxQuery),
+    responses(
+        (status = 201, description = "The new box", body = BoxEnvelope),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
+pub async fn duplicate_box<S>(
+    State(store): State<Arc<S>>,
+    Path(id): Path<String>,
+    Extension(user_id): Extension<String>,
+    Extension(document_storage): Extension<Option<SharedDocumentStorage>>,
+    Extension(invitations): Extension<SharedInvitationStore>,
+    clock: SharedClock,
+    Query(query): Query<DuplicateBoxQuery>,
+) -> Result<(StatusCode, Json<serde_json::Value>)>
+where
+    S: BoxStore,
+{
+    let source = get_owned_box(&*store, &id, &user_id).await?;
+
+    let mut new_box = new_box_record(
+        &*clock,
+        &user_id,
+        copy_name(&source.name)?,
+        source.description,
+        source.owner_name,
+    );
+    new_box.unlock_instructions = source.unlock_instructions;
+    new_box.require_step_up = source.require_step_up;
+    let now = new_box.created_at.clone();
+    // Invitations expire 48 hours from now, as when guardians are invited
+    let expires_at = (clock.now() + chrono::Duration::hours(48)).to_rfc3339();
+    // Guardians who turned the original down aren't asked again
+    let (guardians, new_invitations): (Vec<_>, Vec<_>) = source
+        .guardians
+        .into_iter()
+        .filter(|g| g.status != GuardianStatus::Rejected)
+        .map(|g| {
+            let invitation = Invitation {
+                id: Uuid::new_v4().to_string(),
+                invite_code: Invitation::new_invite_code(),
+                invited_name: g.name.clone(),
+                box_id: new_box.id.clone(),
+                created_at: now.clone(),
+                expires_at: expires_at.clone(),
+                opened: false,
+                linked_user_id: None,
+                creator_id: user_id.clone(),
+                is_lead_guardian: g.lead_guardian,
+                box_name: Some(new_box.name.clone()),
+                owner_name: new_box.owner_name.clone(),
+                creator_name: new_box.owner_name.clone(),
+            };
+            // Nobody is a guardian of the copy until they accept its
+            // invitation, so each starts out as that invitation's placeholder
+            let guardian = Guardian {
+                id: String::new(),
+                name: g.name,
+                lead_guardian: g.lead_guardian,
+                status: GuardianStatus::Invited,
+                added_at: now.clone(),
+                invitation_id: invitation.id.clone(),
+                lock_data_received_at: None,
+                encrypted_shard: None,
+                shard_hash: None,
+                shard_fetched_at: None,
+                shard_accepted_at: None,
+                shard_commit: None,
+                shard_first_fetched_at: None,
+                last_reminded_at: None,
+            };
+            (guardian, invitation)
+        })
+        .unzip();
+    new_box.guardians = guardians;
+
+    let mut offloaded_keys = Vec::new();
+    let mut invitation_ids = Vec::new();
+    let mut result = Ok(());
+    for invitation in new_invitations {
+        let invitation_id = invitation.id.clone();
+        if let Err(e) = invitations.create_invitation(invitation).await {
+            result = Err(e.into());
+            break;
+        }
+        invitation_ids.push(invitation_id);
+    }
+    if query.include_documents && result.is_ok() {
+        for document in source.documents {
+            match copy_document(document_storage.as_ref(), &new_box.id, document).await {
+                Ok(document) => {
+                    offloaded_keys.extend(document.s3_key.clone());
+                    new_box.documents.push(document);
+                }
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+    }
+
+    let created_box = match result {
+        Ok(()) => store.create_box(new_box).await.map_err(AppError::from),
+        Err(e) => Err(e),
+    };
+    let created_box = match created_box {
+        Ok(created_box) => created_box,
+        Err(e) => {
+            discard_offloaded_content(document_storage.as_ref(), &offloaded_keys).await;
+            discard_invitations(&invitations, &invitation_ids).await;
+            return Err(e);
+        }
+    };
+    info!(
+        "Box duplicated: source_id={}, box_id={}, guardians={}, documents={}",
+        id,
+        created_box.id,
+        created_box.guardians.len(),
+        created_box.documents.len()
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({ "box": BoxResponse::from(created_box) })),
+    ))
+}
+
+// Suffix that marks a duplicated box's name
+const COPY_SUFFIX: &str = " Copy";
+
+// Name for a copy of the box, shortening the original so the suffix still
+// fits within the name limit
+fn copy_name(name: &str) -> Result<String> {
+    let kept: String = name
+        .chars()
+        .take(MAX_NAME_CHARS - COPY_SUFFIX.len())
+        .collect();
+    Ok(validate_name(
+        "name",
+        &format!("{}{}", kept.trim_end(), COPY_SUFFIX),
+    )?)
+}
+
+// A copy of a document for another box. Offloaded content is copied too, so
+// deleting either box's document leaves the other's intact.
+async fn copy_document(
+    document_storage: Option<&SharedDocumentStorage>,
+    box_id: &str,
+    document: Document,
+) -> Result<Document> {
+    let s3_key = match &document.s3_key {
+        Some(key) => {
+            let storage = document_storage.ok_or_else(|| {
+                AppError::internal_server_error(format!(
+                    "Document {} is offloaded but document storage is not configured",
+                    document.id
+                ))
+            })?;
+            let content = storage.get_document(key).await?;
+            let new_key = document_key(box_id, &document.id);
+            let content_type = document
+                .content_type
+                .clone()
+                .unwrap_or_else(|| "application/octet-stream".to_string());
+            storage
+                .put_document(&new_key, content, &content_type)
+                .await?;
+            Some(new_key)
+        }
+        None => None,
+    };
+
+    Ok(Document {
+        version: Some(1),
+        s3_key,
+        ..document
+    })
+}
+
+// Best-effort removal of the invitations to a box that was never created
+async fn discard_invitations(invitations: &SharedInvitationStore, ids: &[String]) {
+    for id in ids {
+        if let Err(e) = invitations.delete_invitation(id).await {
+            error!("Failed to delete unused invitation {}: {:?}", id, e);
+        }
+    }
+}
+
+// Removes offloaded content no box points at, such as content written for a
+// box or update that was never saved
+async fn discard_offloaded_content(
+    document_storage: Option<&SharedDocumentStorage>,
+    keys: &[String],
+) {
+    if let Some(storage) = document_storage {
+        for key in keys {
+            delete_offloaded_content(storage, key).await;
+        }
+    }
+}
+
+// Most boxes a single import may recreate
+const MAX_IMPORT_BOXES: usize = 50;
+
+// POST /boxes/owned/import
+// Recreates the boxes of a JSON export for the caller, under new ids. Boxes
+// come back unlocked and without guardians, and documents exported without
+// content (those of locked boxes) are left out. Each box is imported or
+// rejected on its own, so one bad box doesn't hold up the rest.
+#[utoipa::path(
+    post,
+    path = "/boxes/owned/import",
+    tag = "owner",
+    request_body = BoxExport,
+    responses(
+        (status = 200, description = "Old to new ids of the imported boxes, and the boxes that failed", body = ImportBoxesResponse),
+        (status = 400, description = "Malformed export or too many boxes", body = ErrorBody)
+    )
+)]
+pub async fn import_boxes<S>(
+    State(store): State<Arc<S>>,
+    Extension(user_id): Extension<String>,
+    Extension(document_storage): Extension<Option<SharedDocumentStorage>>,
+    clock: SharedClock,
+    Json(payload): Json<BoxExport>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    if payload.boxes.len() > MAX_IMPORT_BOXES {
+        return Err(AppError::bad_request(format!(
+            "An import may hold at most {} boxes, got {}",
+            MAX_IMPORT_BOXES,
+            payload.boxes.len()
+        )));
+    }
+
+    let mut box_ids = BTreeMap::new();
+    let mut failed = Vec::new();
+    let mut seen = HashSet::new();
+    for exported in payload.boxes {
+        let old_id = exported.id.clone();
+        let result = if seen.insert(old_id.clone()) {
+            import_box(
+                &*store,
+                document_storage.as_ref(),
+                &*clock,
+                &user_id,
+                exported,
+            )
+            .await
+        } else {
+            Err(AppError::bad_request(format!(
+                "Box {} appears more than once in the import",
+                old_id
+            )))
+        };
+        match result {
+            Ok(new_id) => {
+                box_ids.insert(old_id, new_id);
+            }
+            Err(e) => {
+                warn!("Box {} not imported for {}: {}", old_id, user_id, e);
+                failed.push(ImportFailure {
+                    id: old_id,
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+    info!(
+        "Imported boxes: user_id={}, imported={}, failed={}",
+        user_id,
+        box_ids.len(),
+        failed.len()
+    );
+
+    Ok(Json(serde_json::json!(ImportBoxesResponse {
+        box_ids,
+        failed
+    })))
+}
+
+// Recreates one exported box for the caller, returning its new id
+async fn import_box<S>(
+    store: &S,
+    document_storage: Option<&SharedDocumentStorage>,
+    clock: &dyn Clock,
+    user_id: &str,
+    exported: ExportedBox,
+) -> Result<String>
+where
+    S: BoxStore + ?Sized,
+{
+    validate_imported_box(&exported, document_storage.is_some())?;
+    let name = validate_name("name", &exported.name)?;
+    let owner_name = exported
+        .owner_name
+        .as_deref()
+        .map(|owner_name| validate_name("ownerName", owner_name))
+        .transpose()?;
+
+    let mut new_box = new_box_record(clock, user_id, name, exported.description, owner_name);
+    new_box.unlock_instructions = exported.unlock_instructions;
+
+    let mut offloaded_keys = Vec::new();
+    let mut result = Ok(());
+    for exported_document in exported.documents {
+        // Locked boxes are exported without content, which can't be restored
+        if exported_document.encrypted_content.is_none() {
+            continue;
+        }
+        let mut document = Document {
+            id: exported_document.id,
+            title: exported_document.title,
+            encrypted_content: exported_document.encrypted_content,
+            created_at: exported_document.created_at,
+            content_type: exported_document.content_type,
+            size_bytes: exported_document.size_bytes,
+            version: Some(1),
+            s3_key: None,
+        };
+        record_content_size(&mut document);
+        result = offload_oversized_content(document_storage, &new_box.id, &mut document).await;
+        if result.is_err() {
+            break;
+        }
+        offloaded_keys.extend(document.s3_key.clone());
+        new_box.documents.push(document);
+    }
+
+    let new_id = new_box.id.clone();
+    if result.is_ok() {
+        result = store
+            .create_box(new_box)
+            .await
+            .map(|_| ())
+            .map_err(Into::into);
+    }
+
+    // Don't leave content behind for a box that was never created
+    if result.is_err() {
+        discard_offloaded_content(document_storage, &offloaded_keys).await;
+    }
+    result.map(|_| new_id)
+}
+
+// Checks an exported box against the limits documents are held to when they
+// are added one at a time
+fn validate_imported_box(exported: &ExportedBox, can_offload: bool) -> Result<()> {
+    let mut document_ids = HashSet::new();
+    // Only documents exported with content are restored
+    let (mut restored_count, mut restored_bytes) = (0, 0);
+    for document in &exported.documents {
+        if document.id.trim().is_empty() {
+            return Err(AppError::bad_request(
+                "Document ids must not be empty".into(),
+            ));
+        }
+        if !document_ids.insert(document.id.as_str()) {
+            return Err(AppError::bad_request(format!(
+                "Document {} appears more than once",
+                document.id
+            )));
+        }
+
+        let mut declared = Document {
+            id: document.id.clone(),
+            title: document.title.clone(),
+            encrypted_content: None,
+            created_at: document.created_at.clone(),
+            content_type: document.content_type.clone(),
+            size_bytes: document.size_bytes,
+            version: None,
+            s3_key: None,
+        };
+        if let Some(content) = &document.encrypted_content {
+            declared.size_bytes = Some(content.len() as u64);
+        }
+        validate_document_metadata(&declared, can_offload).map_err(|e| match e {
+            AppError::BadRequest(msg) => {
+                AppError::BadRequest(format!("Document {}: {}", document.id, msg))
+            }
+            other => other,
+        })?;
+
+        if document.encrypted_content.is_some() {
+            restored_count += 1;
+            restored_bytes += document_size_bytes(&declared);
+        }
+    }
+
+    check_box_document_limits(restored_count, restored_bytes)
+}
+
+/// A box, provided the caller owns it. Shared by the HTTP and gRPC transports.
+pub(crate) async fn get_owned_box<S>(store: &S, id: &str, user_id: &str) -> Result<BoxRecord>
+where
+    S: BoxStore + ?Sized,
+{
+    let box_rec = store.get_box(id).await?;
+
+    // TODO: Is it safe to check here or should we do filter in the db query?
+    if box_rec.owner_id != user_id {
+        return Err(AppError::unauthorized(
+            "You don't have permission to view this box".into(),
+        ));
+    }
+
+    Ok(box_rec)
+}
+
+// Stays under API Gateway's 29 second integration timeout
+const WATCH_TIMEOUT: Duration = Duration::from_secs(25);
+
+// GET /boxes/owned/:id/watch?since=<version> - Long-polls until the box is
+// newer than `since`, answering 304 if nothing changes within WATCH_TIMEOUT.
+// Only writes handled by this instance wake the request early.
+#[utoipa::path(
+    get,
+    path = "/boxes/owned/{id}/watch",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID"), WatchBoxQuery),
+    responses(
+        (status = 200, description = "The box, once newer than `since`", body = BoxEnvelope),
+        (status = 304, description = "The box didn't change in time"),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
+pub async fn watch_box<S>(
+    State(store): State<Arc<S>>,
+    Path(id): Path<String>,
+    Extension(user_id): Extension<String>,
+    Query(query): Query<WatchBoxQuery>,
+) -> Result<Response>
+where
+    S: BoxStore,
+{
+    match wait_for_box_change(&*store, &id, &user_id, query.since, WATCH_TIMEOUT).await? {
+        Some(box_rec) => Ok(Json(serde_json::json!({
+            "box": BoxResponse::from(box_rec)
+        }))
+        .into_response()),
+        None => Ok(StatusCode::NOT_MODIFIED.into_response()),
+    }
+}
+
+// Waits up to `timeout` for the owner's box to pass version `since`, returning
+// None if it doesn't
+pub(crate) async fn wait_for_box_change<S>(
+    store: &S,
+    box_id: &str,
+    user_id: &str,
+    since: u64,
+    timeout: Duration,
+) -> Result<Option<BoxRecord>>
+where
+    S: BoxStore + ?Sized,
+{
+    let deadline = Instant::now() + timeout;
+    // Subscribe before the first read so a write in between still wakes us
+    let mut watcher = watch::watch_box(box_id);
+    loop {
+        let box_rec = store.get_box_consistent(box_id).await?;
+        if box_rec.owner_id != user_id {
+            return Err(AppError::unauthorized(
+                "You don't have permission to view this box".into(),
+            ));
+        }
+        if box_rec.version > since {
+            return Ok(Some(box_rec));
+        }
+        if time::timeout_at(deadline, watcher.changed()).await.is_err() {
+            return Ok(None);
+        }
+    }
+}
+
+// POST /boxes
+// Honors an Idempotency-Key header when the service has an idempotency store
+#[utoipa::path(
+    post,
+    path = "/boxes/owned",
+    tag = "owner",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Makes retries of this request return the box it created")),
+    request_body = CreateBoxRequest,
+    responses(
+        (status = 201, description = "Box created, or the box an earlier request with this Idempotency-Key created", body = BoxEnvelope),
+        (status = 400, description = "Invalid request; code VALIDATION lists every invalid field", body = ValidationErrorBody),
+        (status = 409, description = "Idempotency-Key reused with a different body, or still in progress", body = ErrorBody)
+    )
+)]
+pub async fn create_box<S>(
+    State(store): State<Arc<S>>,
+    Extension(user_id): Extension<String>,
+    idempotency_store: Option<Extension<SharedIdempotencyStore>>,
+    clock: SharedClock,
+    headers: HeaderMap,
+    Json(payload): Json<CreateBoxRequest>,
+) -> Result<(StatusCode, Json<serde_json::Value>)>
+where
+    S: BoxStore,
+{
+    let idempotency = match (idempotency_store, idempotency_key(&headers)?) {
+        (Some(Extension(keys)), Some(key)) => {
+            Some((keys, scoped_key("create_box", &user_id, &key)))
+        }
+        _ => None,
+    };
+
+    if let Some((keys, key)) = &idempotency {
+        match claim(&**keys, key, &request_hash(&payload)?).await? {
+            Claim::New => {}
+            Claim::Replay(box_id) => {
+                info!(
+                    "Replaying create_box for idempotency key: box_id={}",
+                    box_id
+                );
+                let existing = store.get_box(&box_id).await?;
+                return Ok((
+                    StatusCode::CREATED,
+                    Json(serde_json::json!({ "box": BoxResponse::from(existing) })),
+                ));
+            }
+            Claim::Mismatch => {
+                return Err(AppError::conflict(
+                    "Idempotency-Key was already used with a different request".into(),
+                ))
+            }
+            Claim::InProgress => {
+                return Err(AppError::conflict(
+                    "A request with this Idempotency-Key is still in progress".into(),
+                ))
+            }
+        }
+    }
+
+    // Create the box in store, freeing the idempotency key if that fails so
+    // the client can retry
+    let created_box = match create_owned_box(&*store, &*clock, &user_id, payload).await {
+        Ok(created_box) => created_box,
+        Err(e) => {
+            if let Some((keys, key)) = &idempotency {
+                if let Err(release_err) = keys.delete_record(key).await {
+                    error!("Failed to release idempotency key: {}", release_err);
+                }
+            }
+            return Err(e);
+        }
+    };
+
+    if let Some((keys, key)) = &idempotency {
+        // The box exists either way; a retry would only see the key in progress
+        if let Err(e) = keys.complete_record(key, &created_box.id).await {
+            error!(
+                "Failed to record idempotency key for box {}: {}",
+                created_box.id, e
+            );
+        }
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({ "box": BoxResponse::from(created_box) })),
+    ))
+}
+
+/// Creates an empty, unlocked box owned by the caller. Shared by the HTTP and
+/// gRPC transports.
+pub(crate) async fn create_owned_box<S>(
+    store: &S,
+    clock: &dyn Clock,
+    user_id: &str,
+    payload: CreateBoxRequest,
+) -> Result<BoxRecord>
+where
+    S: BoxStore + ?Sized,
+{
+    payload.validate().map_err(AppError::validation)?;
+    let name = validate_name("name", &payload.name)?;
+    let owner_name = payload
+        .owner_name
+        .as_deref()
+        .map(|owner_name| validate_name("ownerName", owner_name))
+        .transpose()?;
+    let new_box = new_box_record(clock, user_id, name, payload.description, owner_name);
+
+    Ok(store.create_box(new_box).await?)
+}
+
+// A new, empty and unlocked box
+fn new_box_record(
+    clock: &dyn Clock,
+    user_id: &str,
+    name: String,
+    description: String,
+    owner_name: Option<String>,
+) -> BoxRecord {
+    let now = clock.now_str();
+    BoxRecord {
+        id: Uuid::new_v4().to_string(),
+        name,
+        description,
+        is_locked: false,
+        locked_at: None,
+        created_at: now.clone(),
+        updated_at: now.clone(),
+        owner_id: user_id.to_string(),
+        owner_name,
+        documents: vec![],
+        guardians: vec![],
+        unlock_instructions: None,
+        unlock_request: None,
+        version: 0,
+        shard_threshold: None,
+        shards_fetched: None,
+        total_shards: None,
+        shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
+    }
+}
+
+// PATCH /boxes/:id
+#[utoipa::path(
+    patch,
+    path = "/boxes/owned/{id}",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID")),
+    request_body = UpdateBoxRequest,
+    responses(
+        (status = 200, description = "The updated box", body = BoxEnvelope),
+        (status = 400, description = "Invalid request, or the box is locked", body = ErrorBody),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
+pub async fn update_box<S>(
+    State(store): State<Arc<S>>,
+    Path(id): Path<String>,
+    Extension(user_id): Extension<String>,
+    clock: SharedClock,
+    Json(payload): Json<UpdateBoxRequest>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    // Get the current box from store
+    let mut box_rec = store.get_box_consistent(&id).await?;
+
+    // Check if the user is the owner
+    if box_rec.owner_id != user_id {
+        return Err(AppError::unauthorized(
+            "You don't have permission to update this box".into(),
+        ));
+    }
+
+    // Check if box is locked - prevent modifications
+    let has_other_updates = payload.name.is_some()
+        || payload.description.is_some()
+        || payload.unlock_instructions.is_some();
+
+    if box_rec.is_locked && has_other_updates {
+        return Err(AppError::bad_request(
+            "Cannot modify a locked box. Locked boxes are immutable.".into(),
+        ));
+    }
+
+    // Update fields if provided
+    if let Some(name) = payload.name {
+        box_rec.name = validate_name("name", &name)?;
+    }
+
+    if let Some(description) = payload.description {
+        box_rec.description = description;
+    }
+
+    // For unlock_instructions, we need to handle both the case of setting it to a value
+    // or explicitly clearing it by setting it to None
+    if let Some(field) = &payload.unlock_instructions {
+        match field {
+            OptionalField::Value(val) => {
+                let instructions = normalize_unlock_instructions("unlockInstructions", val)
+                    .map_err(|e| AppError::validation(vec![e]))?;
+                box_rec.unlock_instructions = Some(instructions);
+            }
+            OptionalField::Null => box_rec.unlock_instructions = None,
+        }
+    }
+
+    if let Some(is_locked) = payload.is_locked {
+        // Prevent unlocking a locked box
+        if box_rec.is_locked && !is_locked {
+            return Err(AppError::bad_request(
+                "Cannot unlock a locked box. Locked boxes are immutable.".into(),
+            ));
+        }
+
+        // If locking the box for the first time, set locked_at timestamp
+        if is_locked && !box_rec.is_locked {
+            box_rec.locked_at = Some(clock.now_str());
+        }
+        box_rec.is_locked = is_locked;
+    }
+
+    // Guards shard fetches rather than the box's contents, so it may change
+    // after locking
+    if let Some(require_step_up) = payload.require_step_up {
+        box_rec.require_step_up = require_step_up;
+    }
+
+    // Save the updated box
+    let updated_box = store.update_box(box_rec).await?;
+
+    Ok(Json(
+        serde_json::json!({ "box": BoxResponse::from(updated_box) }),
+    ))
+}
+
+// PATCH /boxes/owned/:id/unlock-instructions
+// Instructions aren't shards, so unlike the rest of a box they can change
+// after it's locked, e.g. to give guardians a new contact
+#[utoipa::path(
+    patch,
+    path = "/boxes/owned/{id}/unlock-instructions",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID")),
+    request_body = UnlockInstructionsUpdateRequest,
+    responses(
+        (status = 200, description = "The updated box", body = BoxEnvelope),
+        (status = 400, description = "Empty or overlong instructions; code VALIDATION names the field", body = ValidationErrorBody),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
+pub async fn update_unlock_instructions<S>(
+    State(store): State<Arc<S>>,
+    Path(id): Path<String>,
+    Extension(user_id): Extension<String>,
+    clock: SharedClock,
+    Json(payload): Json<UnlockInstructionsUpdateRequest>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    let unlock_instructions = payload
+        .unlock_instructions
+        .as_deref()
+        .map(|instructions| normalize_unlock_instructions("unlockInstructions", instructions))
+        .transpose()
+        .map_err(|e| AppError::validation(vec![e]))?;
+
+    let mut box_rec = store.get_box_consistent(&id).await?;
+
+    if box_rec.owner_id != user_id {
+        return Err(AppError::unauthorized(
+            "You don't have permission to update this box".into(),
+        ));
+    }
+
+    box_rec.unlock_instructions = unlock_instructions;
+    box_rec.updated_at = clock.now_str();
+
+    // The store bumps the version, so a concurrent update_box conflicts
+    let updated_box = store.update_box(box_rec).await?;
+    info!(
+        "Unlock instructions updated: box_id={}, locked={}",
+        updated_box.id, updated_box.is_locked
+    );
+
+    Ok(Json(
+        serde_json::json!({ "box": BoxResponse::from(updated_box) }),
+    ))
+}
+
+// POST /boxes/owned/:id/lock
+#[utoipa::path(
+    post,
+    path = "/boxes/owned/{id}/lock",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID")),
+    request_body = LockBoxRequest,
+    responses(
+        (status = 200, description = "The locked box", body = BoxEnvelope),
+        (status = 400, description = "Invalid shards or threshold; code VALIDATION lists every invalid field", body = ValidationErrorBody),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
+pub async fn lock_box<S>(
+    State(store): State<Arc<S>>,
+    Path(id): Path<String>,
+    Extension(user_id): Extension<String>,
+    policy: Option<Extension<UnacceptedGuardianPolicy>>,
+    clock: SharedClock,
+    request_id: RequestId,
+    Json(payload): Json<LockBoxRequest>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    let policy = policy.map(|Extension(policy)| policy).unwrap_or_default();
+    let updated_box = lock_owned_box(
+        &*store,
+        &*clock,
+        &id,
+        &user_id,
+        payload,
+        policy,
+        &request_id,
+    )
+    .await?;
+
+    Ok(Json(
+        serde_json::json!({ "box": BoxResponse::from(updated_box) }),
+    ))
+}
+
+/// What locking does when some guardians haven't accepted their invitation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnacceptedGuardianPolicy {
+    /// Lock anyway, logging a warning. Their shards wait until they accept.
+    #[default]
+    Warn,
+    /// Refuse to lock until every guardian has accepted
+    Reject,
+}
+
+impl UnacceptedGuardianPolicy {
+    /// `Reject` when `REQUIRE_ACCEPTED_GUARDIANS=true`
+    pub fn from_env() -> Self {
+        let require = env::var("REQUIRE_ACCEPTED_GUARDIANS")
+            .map(|v| v.to_lowercase() == "true")
+            .unwrap_or(false);
+        if require {
+            UnacceptedGuardianPolicy::Reject
+        } else {
+            UnacceptedGuardianPolicy::Warn
+        }
+    }
+}
+
+/// Why the box couldn't be locked so that any `threshold` of its guardians
+/// can recover it, or Ok if it could. Only looks at the box as it stands, so
+/// a lock preview gives the same answer the lock itself would.
+pub(crate) fn validate_lock_params(
+    box_rec: &BoxRecord,
+    threshold: usize,
+    policy: UnacceptedGuardianPolicy,
+) -> std::result::Result<(), String> {
+    validate_box_lockable(box_rec, policy)?;
+
+    if threshold < 1 || threshold > box_rec.guardians.len() {
+        return Err("Shard threshold must be between 1 and the number of guardians.".into());
+    }
+
+    Ok(())
+}
+
+/// Why the box couldn't be locked with any threshold, or Ok if it could. The
+/// lock checks these before the submitted shards, whose problems, the
+/// threshold's included, are reported together as field errors.
+fn validate_box_lockable(
+    box_rec: &BoxRecord,
+    policy: UnacceptedGuardianPolicy,
+) -> std::result::Result<(), String> {
+    if box_rec.is_locked {
+        return Err("Cannot lock an already locked box.".into());
+    }
+
+    // Nobody could ever recover the box's contents
+    if box_rec.guardians.is_empty() {
+        return Err("Add at least one guardian before locking.".into());
+    }
+
+    if policy == UnacceptedGuardianPolicy::Reject {
+        let unaccepted: Vec<&str> = box_rec
+            .guardians
+            .iter()
+            .filter(|g| g.status != GuardianStatus::Accepted)
+            .map(|g| g.name.as_str())
+            .collect();
+        if !unaccepted.is_empty() {
+            return Err(format!(
+                "All guardians must accept their invitation before the box is locked. Still waiting on: {}",
+                unaccepted.join(", ")
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// GET /boxes/owned/:id/lock-preview?threshold=K
+#[utoipa::path(
+    get,
+    path = "/boxes/owned/{id}/lock-preview",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID"), LockPreviewQuery),
+    responses(
+        (status = 200, description = "Whether the box could be locked with the threshold", body = LockPreviewResponse),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
+pub async fn preview_lock<S>(
+    State(store): State<Arc<S>>,
+    Path(id): Path<String>,
+    Extension(user_id): Extension<String>,
+    policy: Option<Extension<UnacceptedGuardianPolicy>>,
+    Query(query): Query<LockPreviewQuery>,
+) -> Result<Json<LockPreviewResponse>>
+where
+    S: BoxStore,
+{
+    let box_rec = store.get_box(&id).await?;
+    if box_rec.owner_id != user_id {
+        return Err(AppError::unauthorized(
+            "You don't have permission to lock this box".into(),
+        ));
+    }
+
+    let policy = policy.map(|Extension(policy)| policy).unwrap_or_default();
+    let total_shards = box_rec.guardians.len();
+    let (valid, message) = match validate_lock_params(&box_rec, query.threshold, policy) {
+        Ok(()) => (
+            true,
+            format!(
+                "Any {} of the box's {} guardians will be able to recover it.",
+                query.threshold, total_shards
+            ),
+        ),
+        Err(reason) => (false, reason),
+    };
+
+    Ok(Json(LockPreviewResponse {
+        valid,
+        total_shards,
+        threshold: query.threshold,
+        message,
+    }))
+}
+
+/// Locks a box with the guardians' shards and announces it, provided the
+/// caller owns it. Shared by the HTTP and gRPC transports.
+pub(crate) async fn lock_owned_box<S>(
+    store: &S,
+    clock: &dyn Clock,
+    id: &str,
+    user_id: &str,
+    payload: LockBoxRequest,
+    policy: UnacceptedGuardianPolicy,
+    request_id: &RequestId,
+) -> Result<BoxRecord>
+where
+    S: BoxStore + ?Sized,
+{
+    let mut box_rec = store.get_box_consistent(id).await?;
+
+    if box_rec.owner_id != user_id {
+        return Err(AppError::unauthorized(
+            "You don't have permission to lock this box".into(),
+        ));
+    }
+
+    validate_box_lockable(&box_rec, policy).map_err(AppError::bad_request)?;
+    payload.validate().map_err(AppError::validation)?;
+
+    let unaccepted = box_rec
+        .guardians
+        .iter()
+        .filter(|g| g.status != GuardianStatus::Accepted)
+        .count();
+    if unaccepted > 0 {
+        warn!(
+            "Locking with unaccepted guardians: request_id={}, box_id={}, unaccepted_count={}",
+            request_id, box_rec.id, unaccepted
+        );
+    }
+
+    if payload.shards.len() != box_rec.guardians.len() {
+        return Err(AppError::bad_request(
+            "Shard count must match the number of guardians.".into(),
+        ));
+    }
+
+    for guardian in box_rec.guardians.iter_mut() {
+        if let Some(shard) = payload.shards.iter().find(|s| s.guardian_id == guardian.id) {
+            guardian.encrypted_shard = Some(shard.shard.clone());
+            guardian.shard_hash = Some(shard.shard_hash.clone());
+            guardian.shard_fetched_at = None;
+            guardian.shard_first_fetched_at = None;
+            guardian.shard_commit = guardian.compute_shard_commit();
+        } else {
+            return Err(AppError::bad_request(format!(
+                "Missing shard for guardian {}",
+                guardian.id
+            )));
+        }
+    }
+
+    let now = clock.now_str();
+    box_rec.is_locked = true;
+    box_rec.locked_at = Some(now.clone());
+    box_rec.updated_at = now.clone();
+    box_rec.shard_threshold = Some(payload.shard_threshold as u32);
+    box_rec.total_shards = Some(payload.shards.len());
+    box_rec.shards_fetched = Some(0);
+    box_rec.shards_deleted_at = None;
+
+    // Capture data for SNS event before consuming box_rec
+    let box_id = box_rec.id.clone();
+    let box_name = box_rec.name.clone();
+    let owner_name = box_rec.owner_name.clone();
+    let guardian_ids = active_guardian_ids(&box_rec);
+
+    let updated_box = store.update_box(box_rec).await?;
+    info!(
+        "Box locked: request_id={}, box_id={}, guardian_count={}",
+        request_id,
+        box_id,
+        guardian_ids.len()
+    );
+
+    // Publish box_locked event to SNS (fire and forget)
+    if let Err(e) = publish_box_locked_event(
+        &box_id,
+        &box_name,
+        owner_name.as_deref(),
+        &guardian_ids,
+        &now,
+        Some(request_id),
+    )
+    .await
+    {
+        error!(
+            "Failed to publish box_locked event: request_id={}, error={:?}",
+            request_id, e
+        );
+    }
+
+    Ok(updated_box)
+}
+
+/// IDs of the guardians a box_locked event goes to. Placeholders still
+/// waiting on their invitation have no ID yet, so there is no one to notify.
+pub(crate) fn active_guardian_ids(box_rec: &BoxRecord) -> Vec<String> {
+    box_rec
+        .guardians
+        .iter()
+        .filter(|g| !g.id.is_empty())
+        .map(|g| g.id.clone())
+        .collect()
+}
+
+// POST /boxes/admin/:id/replay-locked - Admin action re-publishing a box's box_locked event
+#[utoipa::path(
+    post,
+    path = "/boxes/admin/{id}/replay-locked",
+    tag = "admin",
+    params(("id" = String, Path, description = "Box ID")),
+    responses(
+        (status = 200, description = "The box_locked event that was published again", body = ReplayedEventEnvelope),
+        (status = 403, description = "The caller isn't an admin", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody),
+        (status = 409, description = "The box isn't locked", body = ErrorBody)
+    )
+)]
+pub async fn replay_box_locked<S>(
+    State(store): State<Arc<S>>,
+    Path(id): Path<String>,
+    RequireAdmin(auth): RequireAdmin,
+    request_id: RequestId,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    let box_rec = store.get_box(&id).await?;
+    let Some(locked_at) = box_rec.locked_at.as_deref().filter(|_| box_rec.is_locked) else {
+        return Err(AppError::conflict(format!(
+            "Box {} isn't locked, so there is no box_locked event to replay",
+            id
+        )));
+    };
+
+    // Rebuilt from the box as it is now, keeping the original lock time; the
+    // request ID is this request's, so the replay can be traced on its own
+    let guardian_ids = active_guardian_ids(&box_rec);
+    let event = box_locked_event_payload(
+        &box_rec.id,
+        &box_rec.name,
+        box_rec.owner_name.as_deref(),
+        &guardian_ids,
+        locked_at,
+        Some(&request_id),
+    );
+    let event_payload = serde_json::to_value(&event).map_err(|e| {
+        AppError::internal_server_error(format!("Failed to serialize box_locked event: {}", e))
+    })?;
+
+    // Unlike locking, there is nothing else to do here, so a failed publish
+    // is the caller's to see
+    publish_box_event(
+        BoxLockedEvent::EVENT_TYPE,
+        "Box Locked",
+        &box_rec.id,
+        &event_payload,
+    )
+    .await?;
+    info!(
+        "Admin {} replayed box_locked: request_id={}, box_id={}, guardian_count={}",
+        auth.user_id,
+        request_id,
+        box_rec.id,
+        guardian_ids.len()
+    );
+
+    Ok(Json(serde_json::json!({
+        "boxId": box_rec.id,
+        "event": event_payload,
+    })))
+}
+
+// Boxes covered by one call of the guardian-box lookup backfill, small
+// enough to finish well within the API Gateway timeout
+const GUARDIAN_INDEX_BACKFILL_PAGE_SIZE: usize = 100;
+
+// POST /boxes/admin/guardian-index/backfill?cursor= - Admin action writing
+// guardian-box lookup rows for boxes saved before the lookup existed
+#[utoipa::path(
+    post,
+    path = "/boxes/admin/guardian-index/backfill",
+    tag = "admin",
+    params(GuardianIndexBackfillQuery),
+    responses(
+        (status = 200, description = "One page of boxes was indexed", body = GuardianIndexBackfillResponse),
+        (status = 400, description = "Invalid cursor", body = ErrorBody),
+        (status = 403, description = "The caller isn't an admin", body = ErrorBody)
+    )
+)]
+pub async fn backfill_guardian_index<S>(
+    State(store): State<Arc<S>>,
+    RequireAdmin(auth): RequireAdmin,
+    Query(query): Query<GuardianIndexBackfillQuery>,
+) -> Result<Json<GuardianIndexBackfillResponse>>
+where
+    S: BoxStore,
+{
+    let page = store
+        .backfill_guardian_index(GUARDIAN_INDEX_BACKFILL_PAGE_SIZE, query.cursor.as_deref())
+        .await?;
+    info!(
+        "Admin {} backfilled the guardian-box lookup: boxes={}, more={}",
+        auth.user_id,
+        page.items.len(),
+        page.next_cursor.is_some()
+    );
+
+    Ok(Json(GuardianIndexBackfillResponse {
+        boxes_indexed: page.items.len(),
+        next_cursor: page.next_cursor,
+    }))
+}
+
+// DELETE /boxes/:id
+#[utoipa::path(
+    delete,
+    path = "/boxes/owned/{id}",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID")),
+    responses(
+        (status = 200, description = "Box deleted", body = MessageResponse),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
+pub async fn delete_box<S>(
     State(store): State<Arc<S>>,
     Path(id): Path<String>,
     Extension(user_id): Extension<String>,
-    Json(payload): Json<LockBoxRequest>,
+    Extension(document_storage): Extension<Option<SharedDocumentStorage>>,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
-    let mut box_rec = store.get_box(&id).await?;
+    // Get the box to check ownership, and to see where its documents live
+    let box_rec = store.get_box_consistent(&id).await?;
 
+    // Check if the user is the owner
     if box_rec.owner_id != user_id {
         return Err(AppError::unauthorized(
-            "You don't have permission to lock this box".into(),
+            "You don't have permission to delete this box".into(),
         ));
     }
 
-    if box_rec.is_locked {
-        return Err(AppError::bad_request(
-            "Cannot lock an already locked box.".into(),
+    // Delete the box
+    store.delete_box(&id).await?;
+
+    // Then the offloaded content only it pointed at
+    let offloaded_keys: Vec<String> = box_rec
+        .documents
+        .iter()
+        .filter_map(|d| d.s3_key.clone())
+        .collect();
+    discard_offloaded_content(document_storage.as_ref(), &offloaded_keys).await;
+
+    Ok(Json(
+        serde_json::json!({ "message": "Box deleted successfully." }),
+    ))
+}
+
+// DELETE /boxes/owned/:id/unlock-request
+// Lets the owner veto an unlock request they didn't expect
+#[utoipa::path(
+    delete,
+    path = "/boxes/owned/{id}/unlock-request",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID")),
+    responses(
+        (status = 200, description = "Unlock request cancelled", body = BoxMessageEnvelope),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box not found, or no unlock request", body = ErrorBody)
+    )
+)]
+pub async fn cancel_unlock_request<S>(
+    State(store): State<Arc<S>>,
+    Path(id): Path<String>,
+    Extension(user_id): Extension<String>,
+    clock: SharedClock,
+    request_id: RequestId,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    let mut box_rec = store.get_box_consistent(&id).await?;
+
+    if box_rec.owner_id != user_id {
+        return Err(AppError::unauthorized(
+            "You don't have permission to cancel unlock requests for this box".into(),
         ));
     }
 
-    if payload.shards.len() != box_rec.guardians.len() {
-        return Err(AppError::bad_request(
-            "Shard count must match the number of guardians.".into(),
+    let unlock = box_rec
+        .unlock_request
+        .take()
+        .ok_or_else(|| AppError::not_found("No unlock request exists for this box".into()))?;
+
+    // Guardians who initiated or already responded to the request
+    let mut guardian_ids: Vec<String> = Vec::new();
+    for guardian_id in unlock
+        .initiated_by
+        .iter()
+        .chain(unlock.approved_by.iter())
+        .chain(unlock.rejected_by.iter())
+    {
+        if !guardian_ids.contains(guardian_id) {
+            guardian_ids.push(guardian_id.clone());
+        }
+    }
+
+    box_rec.updated_at = clock.now_str();
+    let updated_box = store.update_box(box_rec).await?;
+    info!(
+        "Unlock request cancelled: request_id={}, box_id={}, unlock_request_id={}",
+        request_id, updated_box.id, unlock.id
+    );
+
+    let event_payload = unlock_request_cancelled_event_payload(
+        &updated_box.id,
+        &updated_box.name,
+        &unlock.id,
+        &guardian_ids,
+        &updated_box.updated_at,
+        Some(&request_id),
+    );
+    if let Err(e) = publish_box_event(
+        "unlock_request_cancelled",
+        "Unlock Request Cancelled",
+        &updated_box.id,
+        &event_payload,
+    )
+    .await
+    {
+        error!(
+            "Failed to publish unlock_request_cancelled event: request_id={}, error={:?}",
+            request_id, e
+        );
+    }
+
+    Ok(Json(serde_json::json!({
+        "message": "Unlock request cancelled",
+        "box": BoxResponse::from(updated_box)
+    })))
+}
+
+/// Most guardians a box may have. Boxes already over the limit can still
+/// have guardians updated or removed, just not added.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaxGuardians(pub usize);
+
+impl Default for MaxGuardians {
+    fn default() -> Self {
+        Self(15)
+    }
+}
+
+impl MaxGuardians {
+    /// From `MAX_GUARDIANS`, falling back to the default when unset or invalid
+    pub fn from_env() -> Self {
+        env::var("MAX_GUARDIANS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|max| *max > 0)
+            .map(Self)
+            .unwrap_or_default()
+    }
+
+    // Whether a box that has `current` guardians may be given `proposed`
+    fn allows(&self, current: usize, proposed: usize) -> bool {
+        proposed <= self.0 || proposed <= current
+    }
+
+    fn exceeded(&self) -> AppError {
+        AppError::bad_request(format!("A box can have at most {} guardians.", self.0))
+    }
+}
+
+// Helper function to update a guardian in a box
+// Returns updated box, re-reading it if a concurrent update got there first
+async fn update_or_add_guardian<S>(
+    store: &S,
+    box_id: &str,
+    owner_id: &str,
+    guardian: &Guardian,
+    expected_status: Option<&GuardianStatus>,
+    max_guardians: MaxGuardians,
+) -> Result<BoxRecord>
+where
+    S: BoxStore,
+{
+    store
+        .mutate_box(box_id, |box_rec| {
+            apply_guardian_update(box_rec, owner_id, guardian, expected_status, max_guardians)
+        })
+        .await
+}
+
+// Adds or replaces the guardian in a box read from the store
+fn apply_guardian_update(
+    box_rec: &mut BoxRecord,
+    owner_id: &str,
+    guardian: &Guardian,
+    expected_status: Option<&GuardianStatus>,
+    max_guardians: MaxGuardians,
+) -> Result<()> {
+    // Check if the user is the owner
+    if box_rec.owner_id != owner_id {
+        return Err(AppError::unauthorized(
+            "You don't have permission to update this box".into(),
         ));
     }
 
-    if payload.shard_threshold < 1 || payload.shard_threshold > payload.shards.len() {
+    // Check if box is locked
+    if box_rec.is_locked {
         return Err(AppError::bad_request(
-            "Shard threshold must be between 1 and the number of guardians.".into(),
+            "Cannot modify guardians of a locked box. Locked boxes are immutable.".into(),
         ));
     }
 
-    for guardian in box_rec.guardians.iter_mut() {
-        if let Some(shard) = payload.shards.iter().find(|s| s.guardian_id == guardian.id) {
-            guardian.encrypted_shard = Some(shard.shard.clone());
-            guardian.shard_hash = Some(shard.shard_hash.clone());
-            guardian.shard_fetched_at = None;
-        } else {
-            return Err(AppError::bad_request(format!(
-                "Missing shard for guardian {}",
-                guardian.id
+    // An invitation's row belongs to whoever redeemed it first. Another user
+    // presenting the same invitation must not end up next to them.
+    let invitation_index = box_rec.guardians.iter().position(|g| {
+        !guardian.invitation_id.is_empty() && g.invitation_id == guardian.invitation_id
+    });
+    if let Some(index) = invitation_index {
+        let holder = &box_rec.guardians[index].id;
+        if !holder.is_empty() && *holder != guardian.id {
+            return Err(AppError::conflict(format!(
+                "Invitation {} has already been claimed by another guardian",
+                guardian.invitation_id
             )));
         }
     }
 
-    let now = now_str();
-    box_rec.is_locked = true;
-    box_rec.locked_at = Some(now.clone());
-    box_rec.updated_at = now.clone();
-    box_rec.shard_threshold = Some(payload.shard_threshold as u32);
-    box_rec.total_shards = Some(payload.shards.len());
-    box_rec.shards_fetched = Some(0);
-    box_rec.shards_deleted_at = None;
+    // Check if the guardian already exists in the box, either by id or as the
+    // placeholder an invitation created before anyone redeemed it
+    let guardian_index = box_rec
+        .guardians
+        .iter()
+        .position(|g| g.id == guardian.id)
+        .or(invitation_index);
+
+    // Reject stale updates when the caller states which status it expects
+    if let Some(expected) = expected_status {
+        let current = guardian_index.map(|index| &box_rec.guardians[index].status);
+        if current != Some(expected) {
+            return Err(AppError::conflict(format!(
+                "Guardian status is {}, expected {}",
+                current.map_or_else(|| "absent".to_string(), |s| s.to_string()),
+                expected
+            )));
+        }
+    }
 
-    // Capture data for SNS event before consuming box_rec
-    let box_id = box_rec.id.clone();
-    let box_name = box_rec.name.clone();
-    let owner_name = box_rec.owner_name.clone();
-    let guardian_ids: Vec<String> = box_rec
+    if let Some(index) = guardian_index {
+        // Update existing guardian
+        box_rec.guardians[index] = guardian.clone();
+    } else {
+        // Add new guardian
+        let current = box_rec.guardians.len();
+        if !max_guardians.allows(current, current + 1) {
+            return Err(max_guardians.exceeded());
+        }
+        box_rec.guardians.push(guardian.clone());
+    };
+
+    Ok(())
+}
+
+// PATCH /boxes/owned/:id/guardian
+// This is a dedicated endpoint for updating a single guardian
+#[utoipa::path(
+    patch,
+    path = "/boxes/owned/{id}/guardian",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID")),
+    request_body = GuardianUpdateRequest,
+    responses(
+        (status = 200, description = "Guardian added or updated", body = GuardianEnvelope),
+        (status = 400, description = "Invalid guardian, or the box already has the most guardians allowed", body = ErrorBody),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody),
+        (status = 409, description = "The guardian's status isn't `expectedStatus`", body = ErrorBody)
+    )
+)]
+pub async fn update_guardian<S>(
+    State(store): State<Arc<S>>,
+    Path(box_id): Path<String>,
+    Extension(user_id): Extension<String>,
+    max_guardians: Option<Extension<MaxGuardians>>,
+    Json(payload): Json<GuardianUpdateRequest>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    let max_guardians = max_guardians.map(|Extension(max)| max).unwrap_or_default();
+    let mut guardian = payload.guardian;
+    guardian.name = validate_name("name", &guardian.name)?;
+
+    // Let the helper function do the work
+    let updated_box = update_or_add_guardian(
+        &*store,
+        &box_id,
+        &user_id,
+        &guardian,
+        payload.expected_status.as_ref(),
+        max_guardians,
+    )
+    .await?;
+
+    // Find the updated guardian in the updated box
+    let updated_guardian = updated_box
         .guardians
         .iter()
-        .filter(|g| !g.id.is_empty())
-        .map(|g| g.id.clone())
-        .collect();
+        .find(|g| g.id == guardian.id)
+        .ok_or_else(|| {
+            AppError::internal_server_error("Updated guardian not found in response".into())
+        })?;
 
+    // Create a specialized response with the updated guardian and all guardians
+    let response = GuardianUpdateResponse {
+        id: updated_guardian.id.clone(),
+        name: updated_guardian.name.clone(),
+        status: updated_guardian.status.to_string(),
+        lead_guardian: updated_guardian.lead_guardian,
+        added_at: updated_guardian.added_at.clone(),
+        invitation_id: updated_guardian.invitation_id.clone(),
+        all_guardians: updated_box.guardians.clone(),
+        updated_at: updated_box.updated_at.clone(),
+    };
+
+    Ok(Json(serde_json::json!({ "guardian": response })))
+}
+
+// POST /boxes/owned/:id/webhook
+// Registers the URL to call when the box is unlocked, replacing any earlier
+// webhook. The signing secret is only ever returned here.
+#[utoipa::path(
+    post,
+    path = "/boxes/owned/{id}/webhook",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID")),
+    request_body = WebhookRegistrationRequest,
+    responses(
+        (status = 200, description = "Webhook registered", body = WebhookRegistrationResponse),
+        (status = 400, description = "Invalid, non-HTTPS or internal URL", body = ErrorBody),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
+pub async fn register_webhook<S>(
+    State(store): State<Arc<S>>,
+    Path(box_id): Path<String>,
+    Extension(user_id): Extension<String>,
+    clock: SharedClock,
+    Json(payload): Json<WebhookRegistrationRequest>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    validate_webhook_url(&payload.url)?;
+
+    let mut box_rec = store.get_box_consistent(&box_id).await?;
+    if box_rec.owner_id != user_id {
+        return Err(AppError::unauthorized(
+            "You don't have permission to update this box".into(),
+        ));
+    }
+
+    let secret = generate_webhook_secret()?;
+    box_rec.webhook_url = Some(payload.url.clone());
+    box_rec.webhook_secret = Some(secret.clone());
+    box_rec.updated_at = clock.now_str();
     let updated_box = store.update_box(box_rec).await?;
 
-    // Publish box_locked event to SNS (fire and forget)
-    if let Err(e) = publish_box_locked_event(
-        &box_id,
-        &box_name,
-        owner_name.as_deref(),
-        &guardian_ids,
-        &now,
+    info!("Webhook registered: box_id={}", updated_box.id);
+    Ok(Json(serde_json::json!(WebhookRegistrationResponse {
+        webhook_url: payload.url,
+        webhook_secret: secret,
+        updated_at: updated_box.updated_at,
+    })))
+}
+
+// POST /boxes/internal/:id/guardians/accept
+// Called by the invitation event consumer once an invitation is redeemed, with
+// a service token acting on behalf of the redeeming user. Makes that user an
+// accepted guardian, filling in the invitation's placeholder if there is one.
+#[utoipa::path(
+    post,
+    path = "/boxes/internal/{id}/guardians/accept",
+    tag = "internal",
+    params(("id" = String, Path, description = "Box ID"), ("X-On-Behalf-Of" = String, Header, description = "User who redeemed the invitation")),
+    request_body = RedeemedInvitationRequest,
+    responses(
+        (status = 200, description = "The accepted guardian", body = AcceptedGuardianEnvelope),
+        (status = 400, description = "Invalid request, or the box already has the most guardians allowed", body = ErrorBody),
+        (status = 403, description = "Caller isn't a service, or the invitation's creator doesn't own the box", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody),
+        (status = 409, description = "Another user already redeemed the invitation", body = ErrorBody)
     )
-    .await
-    {
-        error!("Failed to publish box_locked event: {:?}", e);
+)]
+pub async fn accept_redeemed_invitation<S>(
+    State(store): State<Arc<S>>,
+    Path(box_id): Path<String>,
+    Extension(auth): Extension<AuthContext>,
+    clock: SharedClock,
+    request_id: RequestId,
+    max_guardians: Option<Extension<MaxGuardians>>,
+    Json(payload): Json<RedeemedInvitationRequest>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    let max_guardians = max_guardians.map(|Extension(max)| max).unwrap_or_default();
+    let Some(service) = auth.service.as_deref() else {
+        return Err(AppError::forbidden(
+            "Only services may accept invitations on a user's behalf".into(),
+        ));
+    };
+    if auth.user_id.starts_with("service:") {
+        return Err(AppError::bad_request(
+            "Service request must name the redeeming user".into(),
+        ));
+    }
+
+    // Redelivered events usually find the guardian already accepted, which
+    // needs no write
+    let box_rec = store.get_box_consistent(&box_id).await?;
+    if box_rec.owner_id == payload.creator_id {
+        if let Some(g) = box_rec.guardians.iter().find(|g| {
+            g.invitation_id == payload.invitation_id
+                && g.id == auth.user_id
+                && g.status == GuardianStatus::Accepted
+        }) {
+            return Ok(Json(serde_json::json!({ "guardian": g })));
+        }
+    }
+
+    // Accepting happens with the owner's authority over the guardian list, so
+    // only invitations the owner created count; anyone can invite themselves
+    // onto someone else's box
+    let mut already_accepted = false;
+    let updated_box = store
+        .mutate_box(&box_id, |box_rec| {
+            if box_rec.owner_id != payload.creator_id {
+                return Err(AppError::forbidden(
+                    "The invitation wasn't created by the box's owner".into(),
+                ));
+            }
+
+            let existing = box_rec
+                .guardians
+                .iter()
+                .find(|g| g.invitation_id == payload.invitation_id);
+            // A redelivery may have raced this one
+            already_accepted = existing
+                .is_some_and(|g| g.id == auth.user_id && g.status == GuardianStatus::Accepted);
+            if already_accepted {
+                return Ok(());
+            }
+
+            let guardian = match existing {
+                Some(placeholder) => Guardian {
+                    id: auth.user_id.clone(),
+                    lead_guardian: payload.lead_guardian,
+                    status: GuardianStatus::Accepted,
+                    ..placeholder.clone()
+                },
+                None => Guardian {
+                    id: auth.user_id.clone(),
+                    name: payload
+                        .invited_name
+                        .clone()
+                        .unwrap_or_else(|| "Guardian".to_string()),
+                    lead_guardian: payload.lead_guardian,
+                    status: GuardianStatus::Accepted,
+                    added_at: clock.now_str(),
+                    invitation_id: payload.invitation_id.clone(),
+                    lock_data_received_at: None,
+                    encrypted_shard: None,
+                    shard_hash: None,
+                    shard_fetched_at: None,
+                    shard_accepted_at: None,
+                    shard_commit: None,
+                    shard_first_fetched_at: None,
+                    last_reminded_at: None,
+                },
+            };
+            let owner_id = box_rec.owner_id.clone();
+            apply_guardian_update(box_rec, &owner_id, &guardian, None, max_guardians)
+        })
+        .await?;
+    info!(
+        "Guardian accepted from redeemed invitation: request_id={}, box_id={}, invitation_id={}, user_id={}, service={}",
+        request_id, box_id, payload.invitation_id, auth.user_id, service
+    );
+
+    let accepted = updated_box
+        .guardians
+        .iter()
+        .find(|g| g.id == auth.user_id)
+        .ok_or_else(|| {
+            AppError::internal_server_error("Accepted guardian not found in response".into())
+        })?;
+    if !already_accepted {
+        publish_guardian_accepted(&updated_box, accepted, Some(&request_id)).await;
     }
 
-    Ok(Json(
-        serde_json::json!({ "box": BoxResponse::from(updated_box) }),
-    ))
+    Ok(Json(serde_json::json!({ "guardian": accepted })))
 }
 
-// DELETE /boxes/:id
-pub async fn delete_box<S>(
+// PUT /boxes/owned/:id/guardians
+// Replaces the whole guardian set in a single write
+#[utoipa::path(
+    put,
+    path = "/boxes/owned/{id}/guardians",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID")),
+    request_body = GuardiansReplaceRequest,
+    responses(
+        (status = 200, description = "Guardians replaced", body = GuardiansReplaceResponse),
+        (status = 400, description = "Invalid guardian list, or more guardians than allowed", body = ErrorBody),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
+pub async fn replace_guardians<S>(
     State(store): State<Arc<S>>,
-    Path(id): Path<String>,
+    Path(box_id): Path<String>,
     Extension(user_id): Extension<String>,
+    clock: SharedClock,
+    max_guardians: Option<Extension<MaxGuardians>>,
+    Json(payload): Json<GuardiansReplaceRequest>,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
-    // Get the box to check ownership
-    let box_rec = store.get_box(&id).await?;
+    let max_guardians = max_guardians.map(|Extension(max)| max).unwrap_or_default();
+    // mutate_box rereads the box when another write got there first, so the
+    // lock and guardian-limit checks see the box that gets replaced
+    let updated_box = store
+        .mutate_box(&box_id, |box_rec| {
+            if box_rec.owner_id != user_id {
+                return Err(AppError::unauthorized(
+                    "You don't have permission to update this box".into(),
+                ));
+            }
+
+            if box_rec.is_locked {
+                return Err(AppError::bad_request(
+                    "Cannot modify guardians of a locked box. Locked boxes are immutable.".into(),
+                ));
+            }
+
+            let now = clock.now_str();
+            let guardians = payload
+                .guardians
+                .iter()
+                .map(|guardian| replacement_guardian(&box_rec.guardians, guardian, &now))
+                .collect::<Result<Vec<_>>>()?;
+            validate_guardian_set(&guardians)?;
+            if !max_guardians.allows(box_rec.guardians.len(), guardians.len()) {
+                return Err(max_guardians.exceeded());
+            }
+
+            box_rec.guardians = guardians;
+            box_rec.updated_at = now;
+            Ok(())
+        })
+        .await?;
 
-    // Check if the user is the owner
-    if box_rec.owner_id != user_id {
-        return Err(AppError::unauthorized(
-            "You don't have permission to delete this box".into(),
-        ));
-    }
+    Ok(Json(serde_json::json!(GuardiansReplaceResponse {
+        guardians: updated_box.guardians,
+        updated_at: updated_box.updated_at,
+    })))
+}
 
-    // Delete the box
-    store.delete_box(&id).await?;
+// Builds a guardian for a replacement set from what the owner sent. The owner
+// only decides who the guardians are, their names and the lead; status and
+// shard state belong to the server. A guardian already on the box, matched by
+// id or invitation, keeps its stored state, and a new one starts out invited
+// with no shard.
+fn replacement_guardian(existing: &[Guardian], guardian: &Guardian, now: &str) -> Result<Guardian> {
+    let name = validate_name("name", &guardian.name)?;
+    let stored = existing.iter().find(|g| {
+        (!guardian.id.is_empty() && g.id == guardian.id)
+            || (!guardian.invitation_id.is_empty() && g.invitation_id == guardian.invitation_id)
+    });
 
-    Ok(Json(
-        serde_json::json!({ "message": "Box deleted successfully." }),
-    ))
+    Ok(match stored {
+        Some(stored) => Guardian {
+            name,
+            lead_guardian: guardian.lead_guardian,
+            ..stored.clone()
+        },
+        None => Guardian {
+            id: guardian.id.clone(),
+            name,
+            lead_guardian: guardian.lead_guardian,
+            status: GuardianStatus::Invited,
+            added_at: now.to_string(),
+            invitation_id: guardian.invitation_id.clone(),
+            lock_data_received_at: None,
+            encrypted_shard: None,
+            shard_hash: None,
+            shard_fetched_at: None,
+            shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
+        },
+    })
 }
 
-// Helper function to update a guardian in a box
-// Returns updated box
-async fn update_or_add_guardian<S>(
-    store: &S,
-    box_id: &str,
-    owner_id: &str,
-    guardian: &Guardian,
-) -> Result<BoxRecord>
-where
-    S: BoxStore,
-{
-    // Get the current box from store
-    let mut box_rec = store.get_box(box_id).await?;
+// Checks a complete guardian set for duplicates and more than one lead guardian.
+// Guardians who haven't linked an account yet have an empty id, so only
+// non-empty ids take part in the duplicate check.
+fn validate_guardian_set(guardians: &[Guardian]) -> Result<()> {
+    let mut ids = HashSet::new();
+    let mut invitation_ids = HashSet::new();
 
-    // Check if the user is the owner
-    if box_rec.owner_id != owner_id {
-        return Err(AppError::unauthorized(
-            "You don't have permission to update this box".into(),
-        ));
+    for guardian in guardians {
+        if !guardian.id.is_empty() && !ids.insert(guardian.id.as_str()) {
+            return Err(AppError::bad_request(format!(
+                "Duplicate guardian id: {}",
+                guardian.id
+            )));
+        }
+        if !guardian.invitation_id.is_empty()
+            && !invitation_ids.insert(guardian.invitation_id.as_str())
+        {
+            return Err(AppError::bad_request(format!(
+                "Duplicate guardian invitationId: {}",
+                guardian.invitation_id
+            )));
+        }
     }
 
-    // Check if box is locked
-    if box_rec.is_locked {
+    if guardians.iter().filter(|g| g.lead_guardian).count() > 1 {
         return Err(AppError::bad_request(
-            "Cannot modify guardians of a locked box. Locked boxes are immutable.".into(),
+            "A box can have at most one lead guardian.".into(),
         ));
     }
 
-    // Check if the guardian already exists in the box
-    let guardian_index = box_rec.guardians.iter().position(|g| g.id == guardian.id);
+    Ok(())
+}
 
-    if let Some(index) = guardian_index {
-        // Update existing guardian
-        box_rec.guardians[index] = guardian.clone();
+// MIME types a document may declare; parameters such as charset are ignored
+const ALLOWED_DOCUMENT_CONTENT_TYPES: &[&str] = &[
+    "text/plain",
+    "text/markdown",
+    "application/json",
+    "application/pdf",
+    "application/octet-stream",
+    "image/jpeg",
+    "image/png",
+    "image/heic",
+];
+
+// Largest document a box will hold inline; boxes are single DynamoDB items (400 KB)
+const MAX_DOCUMENT_SIZE_BYTES: u64 = 256 * 1024;
+
+// Largest document accepted when oversized content can be offloaded
+const MAX_OFFLOADED_DOCUMENT_SIZE_BYTES: u64 = 10 * 1024 * 1024;
+
+// Most documents a single box may hold
+const MAX_DOCUMENTS_PER_BOX: usize = 100;
+
+// Most document content a single box may hold, inline and offloaded together
+const MAX_BOX_DOCUMENT_BYTES: u64 = 50 * 1024 * 1024;
+
+// Stored content larger than this goes to document storage instead of the box
+const DEFAULT_DOCUMENT_OFFLOAD_THRESHOLD_BYTES: usize = 300 * 1024;
+
+// Offload threshold, overridable with DOCUMENT_OFFLOAD_THRESHOLD_BYTES
+fn document_offload_threshold() -> usize {
+    env::var("DOCUMENT_OFFLOAD_THRESHOLD_BYTES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(DEFAULT_DOCUMENT_OFFLOAD_THRESHOLD_BYTES)
+}
+
+// Largest document a box may hold, depending on whether content can be offloaded
+fn max_document_size_bytes(can_offload: bool) -> u64 {
+    if can_offload {
+        MAX_OFFLOADED_DOCUMENT_SIZE_BYTES
     } else {
-        // Add new guardian
-        box_rec.guardians.push(guardian.clone());
-    };
+        MAX_DOCUMENT_SIZE_BYTES
+    }
+}
 
-    // Save the updated box
-    let updated_box = store.update_box(box_rec).await?;
+// Size a document counts for against the limits: the length of its content,
+// or the declared size when the content isn't held inline
+fn document_size_bytes(document: &Document) -> u64 {
+    document
+        .encrypted_content
+        .as_ref()
+        .map(|content| content.len() as u64)
+        .or(document.size_bytes)
+        .unwrap_or(0)
+}
 
-    Ok(updated_box)
+// Records the length of the document's content as its size, whatever the
+// client declared
+fn record_content_size(document: &mut Document) {
+    if let Some(content) = &document.encrypted_content {
+        document.size_bytes = Some(content.len() as u64);
+    }
 }
 
-// PATCH /boxes/owned/:id/guardian
-// This is a dedicated endpoint for updating a single guardian
-pub async fn update_guardian<S>(
-    State(store): State<Arc<S>>,
-    Path(box_id): Path<String>,
-    Extension(user_id): Extension<String>,
-    Json(payload): Json<GuardianUpdateRequest>,
-) -> Result<Json<serde_json::Value>>
-where
-    S: BoxStore,
-{
-    // Let the helper function do the work
-    let updated_box = update_or_add_guardian(&*store, &box_id, &user_id, &payload.guardian).await?;
+// Checks the number and total size of a box's documents against its limits
+fn check_box_document_limits(count: usize, total_bytes: u64) -> Result<()> {
+    if count > MAX_DOCUMENTS_PER_BOX {
+        return Err(AppError::payload_too_large(format!(
+            "A box may hold at most {} documents",
+            MAX_DOCUMENTS_PER_BOX
+        )));
+    }
+    if total_bytes > MAX_BOX_DOCUMENT_BYTES {
+        return Err(AppError::payload_too_large(format!(
+            "A box may hold at most {} bytes of documents, this would be {} bytes",
+            MAX_BOX_DOCUMENT_BYTES, total_bytes
+        )));
+    }
+    Ok(())
+}
 
-    // Find the updated guardian in the updated box
-    let updated_guardian = updated_box
-        .guardians
-        .iter()
-        .find(|g| g.id == payload.guardian.id)
-        .ok_or_else(|| {
-            AppError::internal_server_error("Updated guardian not found in response".into())
-        })?;
+// Checks the optional content type and size a client declared for a document
+fn validate_document_metadata(document: &Document, can_offload: bool) -> Result<()> {
+    if let Some(content_type) = &document.content_type {
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        if !ALLOWED_DOCUMENT_CONTENT_TYPES.contains(&essence.as_str()) {
+            return Err(AppError::bad_request(format!(
+                "Unsupported document content type: {}",
+                content_type
+            )));
+        }
+    }
 
-    // Create a specialized response with the updated guardian and all guardians
-    let response = GuardianUpdateResponse {
-        id: updated_guardian.id.clone(),
-        name: updated_guardian.name.clone(),
-        status: updated_guardian.status.to_string(),
-        lead_guardian: updated_guardian.lead_guardian,
-        added_at: updated_guardian.added_at.clone(),
-        invitation_id: updated_guardian.invitation_id.clone(),
-        all_guardians: updated_box.guardians.clone(),
-        updated_at: updated_box.updated_at.clone(),
+    let max_size_bytes = max_document_size_bytes(can_offload);
+    let size_bytes = document_size_bytes(document);
+    if size_bytes > max_size_bytes {
+        return Err(AppError::bad_request(format!(
+            "Document is too large: {} bytes (limit {} bytes)",
+            size_bytes, max_size_bytes
+        )));
+    }
+
+    Ok(())
+}
+
+// Whether the document's content is too large to keep inline and there is
+// somewhere to put it instead
+fn needs_offload(document_storage: Option<&SharedDocumentStorage>, document: &Document) -> bool {
+    document_storage.is_some()
+        && document
+            .encrypted_content
+            .as_ref()
+            .is_some_and(|content| content.len() > document_offload_threshold())
+}
+
+// Oversized content goes to document storage, leaving a pointer in the box
+async fn offload_oversized_content(
+    document_storage: Option<&SharedDocumentStorage>,
+    box_id: &str,
+    document: &mut Document,
+) -> Result<()> {
+    let (Some(storage), Some(content)) = (document_storage, &document.encrypted_content) else {
+        return Ok(());
     };
+    if content.len() <= document_offload_threshold() {
+        return Ok(());
+    }
 
-    Ok(Json(serde_json::json!({ "guardian": response })))
+    let key = document_key(box_id, &document.id);
+    let content_type = document
+        .content_type
+        .clone()
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+    storage
+        .put_document(&key, content.clone().into_bytes(), &content_type)
+        .await?;
+    debug!(
+        "Offloaded document {} of box {} ({} bytes)",
+        document.id,
+        box_id,
+        content.len()
+    );
+    document.size_bytes = Some(content.len() as u64);
+    document.encrypted_content = None;
+    document.s3_key = Some(key);
+    Ok(())
 }
 
 // Helper function to update a document in a box
 // Returns updated box
 async fn update_or_add_document<S>(
     store: &S,
+    document_storage: Option<&SharedDocumentStorage>,
     box_id: &str,
     owner_id: &str,
     document: &Document,
@@ -558,9 +3053,45 @@ async fn update_or_add_document<S>(
 where
     S: BoxStore,
 {
-    // Get the current box from store
-    let mut box_rec = store.get_box(box_id).await?;
+    // Clients never choose where content is stored
+    let mut incoming = document.clone();
+    incoming.s3_key = None;
+    record_content_size(&mut incoming);
+    validate_document_metadata(&incoming, document_storage.is_some())?;
+
+    if needs_offload(document_storage, &incoming) {
+        // Don't upload content for an update that would be refused anyway
+        let mut current = store.get_box_consistent(box_id).await?;
+        apply_document_update(&mut current, owner_id, incoming.clone())?;
+        offload_oversized_content(document_storage, box_id, &mut incoming).await?;
+    }
+
+    let mut previous_s3_key = None;
+    let result = store
+        .mutate_box(box_id, |box_rec| {
+            apply_document_update(box_rec, owner_id, incoming.clone())
+                .map(|replaced| previous_s3_key = replaced)
+        })
+        .await;
+    let updated_box = match result {
+        Ok(updated_box) => updated_box,
+        Err(e) => {
+            // The box never pointed at the new upload
+            discard_offloaded_content(document_storage, incoming.s3_key.as_slice()).await;
+            return Err(e);
+        }
+    };
 
+    // The box no longer points at the replaced content
+    if let (Some(storage), Some(key)) = (document_storage, previous_s3_key) {
+        delete_offloaded_content(storage, &key).await;
+    }
+
+    Ok(updated_box)
+}
+
+// Checks the caller may change the documents of a box read from the store
+fn check_documents_editable(box_rec: &BoxRecord, owner_id: &str) -> Result<()> {
     // Check if the user is the owner
     if box_rec.owner_id != owner_id {
         return Err(AppError::unauthorized(
@@ -575,36 +3106,102 @@ where
         ));
     }
 
-    // Check if the document already exists in the box
-    let document_index = box_rec.documents.iter().position(|d| d.id == document.id);
+    Ok(())
+}
 
-    if let Some(index) = document_index {
-        // Update existing document
-        box_rec.documents[index] = document.clone();
-    } else {
-        // Add new document
-        box_rec.documents.push(document.clone());
-    };
+// Adds or replaces the document in a box read from the store, returning where
+// the replaced document's content was offloaded to, if anywhere
+fn apply_document_update(
+    box_rec: &mut BoxRecord,
+    owner_id: &str,
+    mut incoming: Document,
+) -> Result<Option<String>> {
+    check_documents_editable(box_rec, owner_id)?;
 
-    // Save the updated box
-    let updated_box = store.update_box(box_rec).await?;
+    // Check if the document already exists in the box
+    let document_index = box_rec.documents.iter().position(|d| d.id == incoming.id);
 
-    Ok(updated_box)
+    // The box has to stay within its limits with the new document in place
+    let (count, total_bytes) = box_rec
+        .documents
+        .iter()
+        .filter(|d| d.id != incoming.id)
+        .chain(std::iter::once(&incoming))
+        .fold((0, 0), |(count, total), d| {
+            (count + 1, total + document_size_bytes(d))
+        });
+    check_box_document_limits(count, total_bytes)?;
+
+    match document_index {
+        Some(index) => {
+            // Update existing document, unless the client edited an older copy
+            let stored_version = box_rec.documents[index].version.unwrap_or(0);
+            if let Some(incoming_version) = incoming.version {
+                if incoming_version != stored_version {
+                    return Err(AppError::conflict(format!(
+                        "Document {} has changed (version {} is current, got {}); reload it and retry",
+                        incoming.id, stored_version, incoming_version
+                    )));
+                }
+            }
+            incoming.version = Some(stored_version + 1);
+            let previous_s3_key = box_rec.documents[index].s3_key.clone();
+            box_rec.documents[index] = incoming;
+            Ok(previous_s3_key)
+        }
+        None => {
+            // Add new document
+            incoming.version = Some(1);
+            box_rec.documents.push(incoming);
+            Ok(None)
+        }
+    }
+}
+
+// Removes offloaded document content; a leftover object is only wasted space,
+// so failures are logged rather than returned
+async fn delete_offloaded_content(storage: &SharedDocumentStorage, key: &str) {
+    if let Err(e) = storage.delete_document(key).await {
+        error!("Failed to delete offloaded document {}: {:?}", key, e);
+    }
 }
 
 // PATCH /boxes/owned/:id/document
 // This is a dedicated endpoint for updating a single document
+#[utoipa::path(
+    patch,
+    path = "/boxes/owned/{id}/document",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID")),
+    request_body = DocumentUpdateRequest,
+    responses(
+        (status = 200, description = "Document added or updated", body = DocumentEnvelope),
+        (status = 400, description = "Invalid document", body = ErrorBody),
+        (status = 413, description = "The box would go over its document count or total size", body = ErrorBody),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody),
+        (status = 409, description = "Stale document version", body = ErrorBody)
+    )
+)]
 pub async fn update_document<S>(
     State(store): State<Arc<S>>,
     Path(box_id): Path<String>,
     Extension(user_id): Extension<String>,
+    Extension(document_storage): Extension<Option<SharedDocumentStorage>>,
     Json(payload): Json<DocumentUpdateRequest>,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
     // Let the helper function do the work
-    let updated_box = update_or_add_document(&*store, &box_id, &user_id, &payload.document).await?;
+    let updated_box = update_or_add_document(
+        &*store,
+        document_storage.as_ref(),
+        &box_id,
+        &user_id,
+        &payload.document,
+    )
+    .await?;
 
     // Create a specialized response with all documents
     let response = DocumentUpdateResponse {
@@ -615,10 +3212,232 @@ where
     Ok(Json(serde_json::json!({ "document": response })))
 }
 
+// Most documents a single bulk update may hold
+const MAX_BULK_DOCUMENTS: usize = 50;
+
+// PATCH /boxes/owned/:id/documents
+// Adds or updates several documents in one write. Each document is checked on
+// its own, so an invalid or stale one is reported back without holding up the
+// rest. Only problems with the box itself fail the whole request.
+#[utoipa::path(
+    patch,
+    path = "/boxes/owned/{id}/documents",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID")),
+    request_body = DocumentsBulkUpdateRequest,
+    responses(
+        (status = 200, description = "Ids of the applied documents, and the documents rejected with why", body = DocumentsBulkUpdateResponse),
+        (status = 400, description = "Too many documents, or the box is locked", body = ErrorBody),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
+pub async fn bulk_update_documents<S>(
+    State(store): State<Arc<S>>,
+    Path(box_id): Path<String>,
+    Extension(user_id): Extension<String>,
+    Extension(document_storage): Extension<Option<SharedDocumentStorage>>,
+    Json(payload): Json<DocumentsBulkUpdateRequest>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    if payload.documents.len() > MAX_BULK_DOCUMENTS {
+        return Err(AppError::bad_request(format!(
+            "A bulk update may hold at most {} documents, got {}",
+            MAX_BULK_DOCUMENTS,
+            payload.documents.len()
+        )));
+    }
+
+    let document_storage = document_storage.as_ref();
+    let mut rejected = Vec::new();
+    let mut valid = Vec::new();
+    let mut seen = HashSet::new();
+    for mut document in payload.documents {
+        record_content_size(&mut document);
+        let checked = if seen.insert(document.id.clone()) {
+            validate_document_metadata(&document, document_storage.is_some())
+        } else {
+            Err(AppError::bad_request(format!(
+                "Document {} appears more than once in the update",
+                document.id
+            )))
+        };
+        match checked {
+            Ok(()) => {
+                // Clients never choose where content is stored
+                let mut incoming = document;
+                incoming.s3_key = None;
+                valid.push(incoming);
+            }
+            Err(e) => rejected.push(RejectedDocument {
+                id: document.id,
+                reason: e.message(),
+            }),
+        }
+    }
+
+    if valid.iter().any(|d| needs_offload(document_storage, d)) {
+        // Only upload content for the documents the current box would accept
+        let mut current = store.get_box_consistent(&box_id).await?;
+        check_documents_editable(&current, &user_id)?;
+        let mut offloaded = Vec::with_capacity(valid.len());
+        for mut incoming in valid {
+            let result = match apply_document_update(&mut current, &user_id, incoming.clone()) {
+                Ok(_) => offload_oversized_content(document_storage, &box_id, &mut incoming).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => offloaded.push(incoming),
+                Err(e) => rejected.push(RejectedDocument {
+                    id: incoming.id,
+                    reason: e.message(),
+                }),
+            }
+        }
+        valid = offloaded;
+    }
+
+    let mut applied = Vec::new();
+    let mut stale = Vec::new();
+    // Content the box stops pointing at, whether replaced or never used
+    let mut unreferenced_keys = Vec::new();
+    if valid.is_empty() {
+        // Nothing to write, but the caller still has to own an unlocked box
+        let current = store.get_box(&box_id).await?;
+        check_documents_editable(&current, &user_id)?;
+    } else {
+        let result = store
+            .mutate_box(&box_id, |box_rec| {
+                applied.clear();
+                stale.clear();
+                unreferenced_keys.clear();
+                check_documents_editable(box_rec, &user_id)?;
+                for incoming in &valid {
+                    match apply_document_update(box_rec, &user_id, incoming.clone()) {
+                        Ok(replaced) => {
+                            applied.push(incoming.id.clone());
+                            unreferenced_keys.extend(replaced);
+                        }
+                        Err(e) => {
+                            unreferenced_keys.extend(incoming.s3_key.clone());
+                            stale.push(RejectedDocument {
+                                id: incoming.id.clone(),
+                                reason: e.message(),
+                            })
+                        }
+                    }
+                }
+                Ok::<(), AppError>(())
+            })
+            .await;
+        if let Err(e) = result {
+            // The box never pointed at any of the new uploads
+            let uploaded: Vec<String> = valid.iter().filter_map(|d| d.s3_key.clone()).collect();
+            discard_offloaded_content(document_storage, &uploaded).await;
+            return Err(e);
+        }
+    }
+    rejected.extend(stale);
+
+    discard_offloaded_content(document_storage, &unreferenced_keys).await;
+    info!(
+        "Bulk updated documents: box_id={}, applied={}, rejected={}",
+        box_id,
+        applied.len(),
+        rejected.len()
+    );
+
+    Ok(Json(serde_json::json!(DocumentsBulkUpdateResponse {
+        applied,
+        rejected
+    })))
+}
+
+// GET /boxes/owned/:id/document/:document_id
+// Returns one document's content without shipping the rest of the box. The
+// box is a single store item that is already in memory, so the body is sent
+// as-is rather than streamed. Offloaded content is fetched from document
+// storage first.
+#[utoipa::path(
+    get,
+    path = "/boxes/owned/{id}/document/{document_id}",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID"), ("document_id" = String, Path, description = "Document ID")),
+    responses(
+        (status = 200, description = "The document's content, with its content type", content_type = "application/octet-stream", body = Vec<u8>),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box or document not found", body = ErrorBody)
+    )
+)]
+pub async fn get_document<S>(
+    State(store): State<Arc<S>>,
+    Path((box_id, document_id)): Path<(String, String)>,
+    Extension(user_id): Extension<String>,
+    Extension(document_storage): Extension<Option<SharedDocumentStorage>>,
+) -> Result<Response>
+where
+    S: BoxStore,
+{
+    let box_rec = store.get_box(&box_id).await?;
+
+    // Owners can read their documents whether or not the box is locked
+    if box_rec.owner_id != user_id {
+        return Err(AppError::unauthorized(
+            "You don't have permission to view this box".into(),
+        ));
+    }
+
+    let document = box_rec
+        .documents
+        .into_iter()
+        .find(|d| d.id == document_id)
+        .ok_or_else(|| AppError::not_found(format!("Document {} not found", document_id)))?;
+
+    document_content_response(document, document_storage.as_ref()).await
+}
+
+/// Serves a document's content, reading offloaded content back from storage
+pub(crate) async fn document_content_response(
+    document: Document,
+    document_storage: Option<&SharedDocumentStorage>,
+) -> Result<Response> {
+    let document_id = document.id;
+    let content = match (&document.s3_key, document.encrypted_content) {
+        (Some(key), _) => {
+            let storage = document_storage.ok_or_else(|| {
+                AppError::internal_server_error(format!(
+                    "Document {} is offloaded but document storage is not configured",
+                    document_id
+                ))
+            })?;
+            storage.get_document(key).await?
+        }
+        (None, Some(content)) => content.into_bytes(),
+        (None, None) => {
+            return Err(AppError::not_found(format!(
+                "Document {} has no content",
+                document_id
+            )))
+        }
+    };
+    let content_type = document
+        .content_type
+        .unwrap_or_else(|| "application/octet-stream".to_string());
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Body::from(content))
+        .map_err(|e| AppError::internal_server_error(format!("Failed to build response: {}", e)))
+}
+
 // Helper function to delete a document from a box
 // Returns updated box after deletion
 async fn delete_document_from_box<S>(
     store: &S,
+    document_storage: Option<&SharedDocumentStorage>,
     box_id: &str,
     owner_id: &str,
     document_id: &str,
@@ -627,7 +3446,7 @@ where
     S: BoxStore,
 {
     // Get the current box from store
-    let mut box_rec = store.get_box(box_id).await?;
+    let mut box_rec = store.get_box_consistent(box_id).await?;
 
     // Check if the user is the owner
     if box_rec.owner_id != owner_id {
@@ -655,25 +3474,48 @@ where
     }
 
     // Remove the document
-    box_rec.documents.remove(document_index.unwrap());
+    let removed = box_rec.documents.remove(document_index.unwrap());
     // Save the updated box
     let updated_box = store.update_box(box_rec).await?;
 
+    if let (Some(storage), Some(key)) = (document_storage, removed.s3_key) {
+        delete_offloaded_content(storage, &key).await;
+    }
+
     Ok(updated_box)
 }
 
 // DELETE /boxes/owned/:id/document/:document_id
 // This is a dedicated endpoint for deleting a single document
+#[utoipa::path(
+    delete,
+    path = "/boxes/owned/{id}/document/{document_id}",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID"), ("document_id" = String, Path, description = "Document ID")),
+    responses(
+        (status = 200, description = "Document deleted", body = DocumentMessageEnvelope),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box or document not found", body = ErrorBody)
+    )
+)]
 pub async fn delete_document<S>(
     State(store): State<Arc<S>>,
     Path((box_id, document_id)): Path<(String, String)>,
     Extension(user_id): Extension<String>,
+    Extension(document_storage): Extension<Option<SharedDocumentStorage>>,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
     // Use the helper function to delete the document
-    let updated_box = delete_document_from_box(&*store, &box_id, &user_id, &document_id).await?;
+    let updated_box = delete_document_from_box(
+        &*store,
+        document_storage.as_ref(),
+        &box_id,
+        &user_id,
+        &document_id,
+    )
+    .await?;
 
     // Create a response with all remaining documents
     let response = DocumentUpdateResponse {
@@ -699,7 +3541,7 @@ where
     S: BoxStore,
 {
     // Get the current box from store
-    let mut box_rec = store.get_box(box_id).await?;
+    let mut box_rec = store.get_box_consistent(box_id).await?;
 
     // Check if the user is the owner
     if box_rec.owner_id != owner_id {
@@ -739,6 +3581,17 @@ where
 
 // DELETE /boxes/owned/:id/guardian/:guardian_id
 // This is a dedicated endpoint for deleting a single guardian
+#[utoipa::path(
+    delete,
+    path = "/boxes/owned/{id}/guardian/{guardian_id}",
+    tag = "owner",
+    params(("id" = String, Path, description = "Box ID"), ("guardian_id" = String, Path, description = "Guardian's user ID")),
+    responses(
+        (status = 200, description = "Guardian removed", body = GuardianMessageEnvelope),
+        (status = 401, description = "Not the box's owner", body = ErrorBody),
+        (status = 404, description = "Box or guardian not found", body = ErrorBody)
+    )
+)]
 pub async fn delete_guardian<S>(
     State(store): State<Arc<S>>,
     Path((box_id, guardian_id)): Path<(String, String)>,
@@ -772,6 +3625,61 @@ where
 // SNS Publishing for box events
 static SNS_CLIENT: OnceCell<SnsClient> = OnceCell::const_new();
 static TOPIC_ARN: OnceCell<String> = OnceCell::const_new();
+// Events that would have been published while TEST_SNS is set
+#[cfg(test)]
+static TEST_PUBLISHED_EVENTS: std::sync::Mutex<Vec<serde_json::Value>> =
+    std::sync::Mutex::new(Vec::new());
+
+/// Returns the events recorded instead of published while `TEST_SNS=true`
+#[cfg(test)]
+pub fn test_published_events() -> Vec<serde_json::Value> {
+    TEST_PUBLISHED_EVENTS
+        .lock()
+        .map(|events| events.clone())
+        .unwrap_or_default()
+}
+
+/// Builds the box_locked event payload. The request ID, when known, lets the
+/// notification service log the same correlation ID as the originating request.
+pub fn box_locked_event_payload(
+    box_id: &str,
+    box_name: &str,
+    owner_name: Option<&str>,
+    guardian_ids: &[String],
+    timestamp: &str,
+    request_id: Option<&RequestId>,
+) -> BoxLockedEvent {
+    BoxLockedEvent::new(
+        box_id,
+        box_name,
+        owner_name,
+        guardian_ids,
+        timestamp,
+        request_id.map(RequestId::as_str),
+    )
+}
+
+/// Builds the unlock_request_cancelled event payload, addressed to the
+/// guardians who took part in the cancelled request
+pub fn unlock_request_cancelled_event_payload(
+    box_id: &str,
+    box_name: &str,
+    unlock_request_id: &str,
+    guardian_ids: &[String],
+    timestamp: &str,
+    request_id: Option<&RequestId>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "event_type": "unlock_request_cancelled",
+        "schema_version": EVENT_SCHEMA_VERSION,
+        "box_id": box_id,
+        "box_name": box_name,
+        "unlock_request_id": unlock_request_id,
+        "guardian_ids": guardian_ids,
+        "timestamp": timestamp,
+        "request_id": request_id.map(RequestId::as_str)
+    })
+}
 
 /// Publishes a box_locked event to SNS
 pub async fn publish_box_locked_event(
@@ -780,6 +3688,7 @@ pub async fn publish_box_locked_event(
     owner_name: Option<&str>,
     guardian_ids: &[String],
     timestamp: &str,
+    request_id: Option<&RequestId>,
 ) -> Result<()> {
     debug!(
         "publish_box_locked_event called for box_id={}, guardian_count={}",
@@ -787,13 +3696,46 @@ pub async fn publish_box_locked_event(
         guardian_ids.len()
     );
 
+    let event = box_locked_event_payload(
+        box_id,
+        box_name,
+        owner_name,
+        guardian_ids,
+        timestamp,
+        request_id,
+    );
+    let event_payload = serde_json::to_value(&event).map_err(|e| {
+        AppError::internal_server_error(format!("Failed to serialize box_locked event: {}", e))
+    })?;
+
+    publish_box_event(
+        BoxLockedEvent::EVENT_TYPE,
+        "Box Locked",
+        box_id,
+        &event_payload,
+    )
+    .await
+}
+
+/// Publishes a box event to the SNS topic, tagged with an `eventType`
+/// attribute so subscribers can filter on it
+pub(crate) async fn publish_box_event(
+    event_type: &str,
+    subject: &str,
+    box_id: &str,
+    event_payload: &serde_json::Value,
+) -> Result<()> {
     // Check if we're in test mode
     if let Ok(test_sns) = env::var("TEST_SNS") {
         if test_sns == "true" {
             debug!(
-                "Test mode: Skipping SNS publishing for box_locked event, box_id={}",
-                box_id
+                "Test mode: Skipping SNS publishing for {} event, box_id={}",
+                event_type, box_id
             );
+            #[cfg(test)]
+            if let Ok(mut events) = TEST_PUBLISHED_EVENTS.lock() {
+                events.push(event_payload.clone());
+            }
             return Ok(());
         }
     }
@@ -818,24 +3760,14 @@ pub async fn publish_box_locked_event(
         })
         .await?;
 
-    // Create the event payload
-    let event_payload = serde_json::json!({
-        "event_type": "box_locked",
-        "box_id": box_id,
-        "box_name": box_name,
-        "owner_name": owner_name,
-        "guardian_ids": guardian_ids,
-        "timestamp": timestamp
-    });
-
-    let message = serde_json::to_string(&event_payload).map_err(|e| {
+    let message = serde_json::to_string(event_payload).map_err(|e| {
         AppError::internal_server_error(format!("Failed to serialize event payload: {}", e))
     })?;
 
     // Build message attributes for filtering
     let event_type_attr = aws_sdk_sns::types::MessageAttributeValue::builder()
         .data_type("String")
-        .string_value("box_locked")
+        .string_value(event_type)
         .build()
         .map_err(|e| {
             AppError::internal_server_error(format!("Failed to build message attribute: {}", e))
@@ -849,15 +3781,15 @@ pub async fn publish_box_locked_event(
         .publish()
         .topic_arn(topic_arn)
         .message(message)
-        .subject("Box Locked")
+        .subject(subject)
         .set_message_attributes(Some(message_attributes))
         .send()
         .await
         .map_err(|e| AppError::internal_server_error(format!("Failed to publish to SNS: {}", e)))?;
 
     info!(
-        "Successfully published box_locked event for box_id={}",
-        box_id
+        "Successfully published {} event for box_id={}",
+        event_type, box_id
     );
     Ok(())
 }
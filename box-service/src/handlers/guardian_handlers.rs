@@ -1,49 +1,238 @@
-use axum::{
-    extract::{Extension, Path, State},
-    Json,
-};
-use log::{debug, trace, warn};
+use axum::extract::{Extension, OriginalUri, Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::Response;
+use chrono::{DateTime, Duration, Utc};
+use lockbox_shared::extract::Json;
+use log::{debug, error, info, trace, warn};
+use std::env;
 use std::sync::Arc;
 use uuid::Uuid;
 
 use crate::{
     error::{AppError, Result},
-    models::{GuardianInvitationResponse, GuardianResponseRequest, LeadGuardianUpdateRequest},
+    handlers::box_handlers::{document_content_response, publish_box_event},
+    models::{
+        BoxPreviewResponse, DocumentPreview, GuardianBoxResponse, GuardianBoxStatusFilter,
+        GuardianBoxSummary, GuardianBoxesQuery, GuardianInvitationResponse,
+        GuardianResponseRequest, LeadGuardianUpdateRequest,
+    },
+    openapi::{GuardianBoxEnvelope, GuardianBoxPage, InvitationResponseEnvelope},
 };
 
 use lockbox_shared::{
-    models::{now_str, GuardianStatus, UnlockRequest, UnlockRequestStatus},
-    store::{convert_to_guardian_box, BoxStore},
+    clock::{Clock, SharedClock},
+    error::ErrorBody,
+    models::{
+        events::EVENT_SCHEMA_VERSION, BoxRecord, Guardian, GuardianStatus, UnlockRequest,
+        UnlockRequestStatus,
+    },
+    paging::pagination_headers,
+    request_id::RequestId,
+    storage::SharedDocumentStorage,
+    store::BoxStore,
+    webhook::{deliver_webhook, WebhookPayload, BOX_UNLOCKED_EVENT},
 };
 
-// GET /guardianBoxes
+// Upper bound on the page size a client can request for guardian boxes
+const MAX_GUARDIAN_BOXES_PAGE_SIZE: usize = 100;
+
+// Minimum gap between unlock requests on one box, overridable with
+// UNLOCK_REQUEST_COOLDOWN_SECS
+const DEFAULT_UNLOCK_REQUEST_COOLDOWN_SECS: i64 = 60 * 60;
+
+// Longest unlock reason a lead guardian can give, in characters
+const MAX_UNLOCK_REASON_CHARS: usize = 500;
+
+// How long an approval waits on the owner's unlock webhook, retries included
+const UNLOCK_WEBHOOK_DEADLINE: std::time::Duration = std::time::Duration::from_secs(10);
+
+fn unlock_request_cooldown() -> Duration {
+    let secs = env::var("UNLOCK_REQUEST_COOLDOWN_SECS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|v| *v >= 0)
+        .unwrap_or(DEFAULT_UNLOCK_REQUEST_COOLDOWN_SECS);
+    Duration::seconds(secs)
+}
+
+// The reason as stored: control characters removed and surrounding whitespace
+// trimmed, or None if nothing is left
+fn sanitize_unlock_reason(reason: Option<&str>) -> Result<Option<String>> {
+    let Some(reason) = reason else {
+        return Ok(None);
+    };
+    let cleaned: String = reason
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect();
+    let cleaned = cleaned.trim();
+    if cleaned.chars().count() > MAX_UNLOCK_REASON_CHARS {
+        return Err(AppError::bad_request(format!(
+            "reason must be at most {} characters",
+            MAX_UNLOCK_REASON_CHARS
+        )));
+    }
+    Ok((!cleaned.is_empty()).then(|| cleaned.to_string()))
+}
+
+// Seconds the caller still has to wait before another unlock request is
+// allowed, or None if they can go ahead. Completed unlocks don't count.
+fn unlock_cooldown_remaining(
+    box_rec: &BoxRecord,
+    cooldown: Duration,
+    now: DateTime<Utc>,
+) -> Option<u64> {
+    let completed = box_rec
+        .unlock_request
+        .as_ref()
+        .is_some_and(|u| u.status == UnlockRequestStatus::Approved);
+    if completed {
+        return None;
+    }
+
+    let last = box_rec
+        .last_unlock_request_at
+        .as_deref()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())?
+        .with_timezone(&Utc);
+    let remaining = (last + cooldown - now).num_seconds();
+    (remaining > 0).then_some(remaining as u64)
+}
+
+// Checks the caller's own guardian entry against the requested status filter
+fn matches_status_filter(
+    box_rec: &BoxRecord,
+    user_id: &str,
+    filter: GuardianBoxStatusFilter,
+) -> bool {
+    let accepted = box_rec
+        .guardians
+        .iter()
+        .find(|g| g.id == user_id)
+        .is_some_and(|g| g.shard_accepted_at.is_some());
+
+    match filter {
+        GuardianBoxStatusFilter::All => true,
+        GuardianBoxStatusFilter::Accepted => accepted,
+        GuardianBoxStatusFilter::Pending => box_rec.is_locked && !accepted,
+    }
+}
+
+// GET /guardianBoxes?status=pending|accepted|all&limit=&cursor=
+#[utoipa::path(
+    get,
+    path = "/boxes/guardian",
+    tag = "guardian",
+    params(GuardianBoxesQuery),
+    responses(
+        (status = 200, description = "Boxes the caller guards", body = GuardianBoxPage,
+            headers(
+                ("Link" = String, description = "`rel=\"next\"` URL of the next page, when there is one"),
+                ("X-Total-Count" = usize, description = "Number of boxes, when no limit was given")
+            )),
+        (status = 400, description = "Invalid cursor", body = ErrorBody)
+    )
+)]
 pub async fn get_guardian_boxes<S>(
     State(store): State<Arc<S>>,
     Extension(user_id): Extension<String>,
-) -> Result<Json<serde_json::Value>>
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<GuardianBoxesQuery>,
+) -> Result<(HeaderMap, Json<serde_json::Value>)>
 where
     S: BoxStore,
 {
-    // TODO: For now, we'd need to fetch all boxes and filter on the guardian
-    // In a real app, we'd want to add a secondary index in DynamoDB for guardian lookups
+    // Without a limit, keep returning every box in a single response
+    let Some(limit) = query.limit else {
+        // Resolve the caller's boxes through the guardian-box lookup
+        let box_ids = store.get_box_ids_for_guardian(&user_id).await?;
+        // Boxes deleted since the lookup was read are skipped by the batch get
+        let guardian_boxes = store.get_boxes_by_ids(&box_ids).await?;
+
+        // Each guardian sees their own view of a box, and rejected guardianships are dropped
+        let guardian_boxes: Vec<_> = guardian_boxes
+            .iter()
+            .filter(|b| matches_status_filter(b, &user_id, query.status))
+            .filter_map(|b| GuardianBoxResponse::for_guardian(b, &user_id))
+            .collect();
+
+        return Ok((
+            pagination_headers(&uri, None, Some(guardian_boxes.len())),
+            Json(serde_json::json!({ "boxes": guardian_boxes })),
+        ));
+    };
 
-    // This is a simplified approach - in production, you would want pagination or a GSI
-    let guardian_boxes = store
-        .get_boxes_by_guardian_id(&user_id)
-        .await
-        .unwrap_or_default();
+    let limit = limit.clamp(1, MAX_GUARDIAN_BOXES_PAGE_SIZE);
+    let page = store
+        .get_guardian_boxes_paginated(&user_id, limit, query.cursor.as_deref())
+        .await?;
 
-    // Convert BoxRecords to GuardianBox format
-    let guardian_boxes: Vec<_> = guardian_boxes
+    // The status filter applies within the page, so a filtered page may hold
+    // fewer than `limit` boxes while `nextCursor` is still set
+    let guardian_boxes: Vec<_> = page
+        .items
         .iter()
-        .filter_map(|b| convert_to_guardian_box(b, &user_id))
-        .map(crate::models::GuardianBoxResponse::from)
+        .filter(|b| matches_status_filter(b, &user_id, query.status))
+        .filter_map(|b| GuardianBoxResponse::for_guardian(b, &user_id))
         .collect();
 
-    Ok(Json(serde_json::json!({ "boxes": guardian_boxes })))
+    Ok((
+        pagination_headers(&uri, page.next_cursor.as_deref(), None),
+        Json(serde_json::json!({
+            "boxes": guardian_boxes,
+            "nextCursor": page.next_cursor,
+        })),
+    ))
+}
+
+// GET /boxes/guardian/summary
+// Where the caller stands in every box they guard, so a home screen needs one
+// request instead of one per box
+#[utoipa::path(
+    get,
+    path = "/boxes/guardian/summary",
+    tag = "guardian",
+    responses(
+        (status = 200, description = "A summary of each box the caller guards", body = [GuardianBoxSummary])
+    )
+)]
+pub async fn get_guardian_summary<S>(
+    State(store): State<Arc<S>>,
+    Extension(user_id): Extension<String>,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    let box_ids = store.get_box_ids_for_guardian(&user_id).await?;
+    // Boxes deleted since the lookup was read are skipped by the batch get
+    let boxes = store.get_boxes_by_ids(&box_ids).await?;
+
+    // Rejected guardianships are left out, as in the box list
+    let summaries: Vec<_> = boxes
+        .iter()
+        .filter_map(|b| GuardianBoxSummary::for_guardian(b, &user_id))
+        .collect();
+    debug!(
+        "Summarised {} guardian boxes for user {}",
+        summaries.len(),
+        user_id
+    );
+
+    Ok(Json(serde_json::json!(summaries)))
 }
 
 // GET /guardianBoxes/:id
+#[utoipa::path(
+    get,
+    path = "/boxes/guardian/{id}",
+    tag = "guardian",
+    params(("id" = String, Path, description = "Box ID")),
+    responses(
+        (status = 200, description = "The box", body = GuardianBoxEnvelope),
+        (status = 401, description = "Not a guardian of the box", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
 pub async fn get_guardian_box<S>(
     State(store): State<Arc<S>>,
     Path(id): Path<String>,
@@ -61,10 +250,8 @@ where
     );
 
     // TODO: query DB with filters instead
-    if let Some(guardian_box) = convert_to_guardian_box(&box_rec, &user_id) {
-        return Ok(Json(
-            serde_json::json!({ "box": crate::models::GuardianBoxResponse::from(guardian_box) }),
-        ));
+    if let Some(guardian_box) = GuardianBoxResponse::for_guardian(&box_rec, &user_id) {
+        return Ok(Json(serde_json::json!({ "box": guardian_box })));
     }
 
     Err(AppError::unauthorized(
@@ -72,195 +259,734 @@ where
     ))
 }
 
+// GET /boxes/guardian/:id/document/:document_id - A document's content, once the box is unlocked
+#[utoipa::path(
+    get,
+    path = "/boxes/guardian/{id}/document/{document_id}",
+    tag = "guardian",
+    params(("id" = String, Path, description = "Box ID"), ("document_id" = String, Path, description = "Document ID")),
+    responses(
+        (status = 200, description = "The document's content, with its content type", content_type = "application/octet-stream", body = Vec<u8>),
+        (status = 401, description = "Not a guardian of the box", body = ErrorBody),
+        (status = 403, description = "The box hasn't been unlocked", body = ErrorBody),
+        (status = 404, description = "Box or document not found", body = ErrorBody)
+    )
+)]
+pub async fn get_guardian_document<S>(
+    State(store): State<Arc<S>>,
+    Path((box_id, document_id)): Path<(String, String)>,
+    Extension(user_id): Extension<String>,
+    Extension(document_storage): Extension<Option<SharedDocumentStorage>>,
+) -> Result<Response>
+where
+    S: BoxStore,
+{
+    let box_rec = store.get_box(&box_id).await?;
+
+    // Guardians see exactly the documents their view of the box holds
+    let guardian_box = GuardianBoxResponse::for_guardian(&box_rec, &user_id)
+        .ok_or_else(|| AppError::unauthorized("Unauthorized or Box not found".into()))?;
+    let documents = guardian_box.documents.ok_or_else(|| {
+        AppError::forbidden("Documents are only available once the box is unlocked".into())
+    })?;
+    let document = documents
+        .into_iter()
+        .find(|d| d.id == document_id)
+        .ok_or_else(|| AppError::not_found(format!("Document {} not found", document_id)))?;
+
+    document_content_response(document, document_storage.as_ref()).await
+}
+
+// GET /boxes/guardian/:id/preview - Document metadata for guardians deciding on an open unlock request
+#[utoipa::path(
+    get,
+    path = "/boxes/guardian/{id}/preview",
+    tag = "guardian",
+    params(("id" = String, Path, description = "Box ID")),
+    responses(
+        (status = 200, description = "The box's document metadata, without contents", body = BoxPreviewResponse),
+        (status = 403, description = "Not a guardian holding a shard of the box, or no unlock request is open", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
+pub async fn preview_guardian_box<S>(
+    State(store): State<Arc<S>>,
+    Path(id): Path<String>,
+    Extension(user_id): Extension<String>,
+) -> Result<Json<BoxPreviewResponse>>
+where
+    S: BoxStore,
+{
+    let box_rec = store.get_box(&id).await?;
+
+    let Some(guardian) = box_rec
+        .guardians
+        .iter()
+        .find(|g| g.id == user_id && g.status != GuardianStatus::Rejected)
+    else {
+        warn!("User {} is not a guardian for box {}", user_id, id);
+        return Err(AppError::forbidden("Not a guardian for this box".into()));
+    };
+
+    // Only guardians whose shard counts towards the unlock have a decision to
+    // make, the same ones who can answer the request
+    if !guardian.holds_shard() {
+        warn!("Guardian {} holds no shard for box {}", user_id, id);
+        return Err(AppError::forbidden(
+            "Only guardians holding a shard can preview this box".into(),
+        ));
+    }
+
+    // Only offered while there is an unlock request to weigh up
+    let Some(unlock) = box_rec
+        .unlock_request
+        .as_ref()
+        .filter(|unlock| unlock.status == UnlockRequestStatus::Requested)
+    else {
+        return Err(AppError::forbidden(
+            "Box contents can only be previewed while an unlock request is open".into(),
+        ));
+    };
+
+    info!(
+        "Guardian {} previewed box {} for unlock request {}",
+        user_id, id, unlock.id
+    );
+    Ok(Json(BoxPreviewResponse {
+        box_id: box_rec.id.clone(),
+        box_name: box_rec.name.clone(),
+        unlock_request_id: unlock.id.clone(),
+        documents: box_rec
+            .documents
+            .iter()
+            .map(DocumentPreview::from)
+            .collect(),
+    }))
+}
+
 // PATCH /boxes/guardian/:id/request - For lead guardian to initiate unlock request
+#[utoipa::path(
+    patch,
+    path = "/boxes/guardian/{id}/request",
+    tag = "guardian",
+    params(("id" = String, Path, description = "Box ID")),
+    request_body = LeadGuardianUpdateRequest,
+    responses(
+        (status = 200, description = "Unlock requested", body = GuardianBoxEnvelope),
+        (status = 400, description = "Not the box's lead guardian, or the reason is too long", body = ErrorBody),
+        (status = 401, description = "Not a guardian of the box", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
 pub async fn request_unlock<S>(
     State(store): State<Arc<S>>,
     Path(box_id): Path<String>,
     Extension(user_id): Extension<String>,
+    clock: SharedClock,
+    request_id: RequestId,
     Json(payload): Json<LeadGuardianUpdateRequest>,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
-    // Get the box from store
-    let mut box_record = store.get_box(&box_id).await?;
+    // mutate_box rereads the box when another write got there first, so the
+    // checks below always see the request it's about to replace
+    let updated_box = store
+        .mutate_box(&box_id, |box_record| {
+            // TODO: query DB with filters instead
+            let is_guardian = box_record
+                .guardians
+                .iter()
+                .any(|g| g.id == user_id && g.status != GuardianStatus::Rejected);
+
+            if !is_guardian {
+                warn!("User {} is not a guardian for box {}", user_id, box_id);
+                return Err(AppError::unauthorized("Not a guardian for this box".into()));
+            }
 
-    // TODO: query DB with filters instead
-    let is_guardian = box_record
-        .guardians
-        .iter()
-        .find(|g| g.id == user_id && g.status != GuardianStatus::Rejected)
-        .is_some();
+            // Check if user is a lead guardian by checking the flag in the guardians list
+            let is_lead = box_record
+                .guardians
+                .iter()
+                .any(|g| g.id == user_id && g.lead_guardian);
 
-    if !is_guardian {
-        warn!("User {} is not a guardian for box {}", user_id, box_id);
-        return Err(AppError::unauthorized("Not a guardian for this box".into()));
-    }
+            if !is_lead {
+                return Err(AppError::bad_request(
+                    "User is not a lead guardian for this box".into(),
+                ));
+            }
 
-    // Check if user is a lead guardian by checking the flag in the guardians list
-    let is_lead = box_record
-        .guardians
-        .iter()
-        .any(|g| g.id == user_id && g.lead_guardian);
-
-    if is_lead {
-        // Lead guardian is initiating an unlock request
-        let new_unlock = UnlockRequest {
-            id: Uuid::new_v4().to_string(),
-            requested_at: now_str(),
-            status: UnlockRequestStatus::Requested,
-            message: Some(payload.message),
-            initiated_by: Some(user_id.clone()),
-            approved_by: vec![],
-            rejected_by: vec![],
-        };
-
-        box_record.unlock_request = Some(new_unlock);
-        box_record.updated_at = now_str();
-
-        // Update the box in store
-        let updated_box = store.update_box(box_record).await?;
-
-        if let Some(guard_box) = convert_to_guardian_box(&updated_box, &user_id) {
-            return Ok(Json(
-                serde_json::json!({ "box": crate::models::GuardianBoxResponse::from(guard_box) }),
-            ));
-        } else {
-            return Err(AppError::internal_server_error(
-                "Failed to render guardian box".into(),
-            ));
-        }
+            let reason = sanitize_unlock_reason(payload.reason.as_deref())?;
+            if let Some(retry_after) =
+                unlock_cooldown_remaining(box_record, unlock_request_cooldown(), clock.now())
+            {
+                return Err(AppError::too_many_requests(
+                    "An unlock request was made too recently for this box; try again later".into(),
+                    retry_after,
+                ));
+            }
+
+            // Lead guardian is initiating an unlock request
+            let new_unlock = UnlockRequest {
+                id: Uuid::new_v4().to_string(),
+                requested_at: clock.now_str(),
+                status: UnlockRequestStatus::Requested,
+                message: payload.message.clone(),
+                reason,
+                initiated_by: Some(user_id.clone()),
+                approved_by: vec![],
+                rejected_by: vec![],
+                approvals: vec![],
+            };
+
+            box_record.last_unlock_request_at = Some(new_unlock.requested_at.clone());
+            box_record.unlock_request = Some(new_unlock);
+            box_record.updated_at = clock.now_str();
+            Ok(())
+        })
+        .await?;
+    info!(
+        "Unlock requested: request_id={}, box_id={}, guardian_id={}",
+        request_id, updated_box.id, user_id
+    );
+
+    // Alert the owner; publishing failures must not undo the request
+    let event_payload = unlock_requested_event_payload(
+        &updated_box,
+        &user_id,
+        &updated_box.updated_at,
+        Some(&request_id),
+    );
+    if let Err(e) = publish_box_event(
+        "unlock_requested",
+        "Unlock Requested",
+        &updated_box.id,
+        &event_payload,
+    )
+    .await
+    {
+        error!(
+            "Failed to publish unlock_requested event: request_id={}, error={:?}",
+            request_id, e
+        );
     }
 
-    Err(AppError::bad_request(
-        "User is not a lead guardian for this box".into(),
-    ))
+    render_guardian_box(&updated_box, &user_id)
 }
 
 // PATCH /boxes/guardian/:id/respond - For guardians to respond to unlock request
+#[utoipa::path(
+    patch,
+    path = "/boxes/guardian/{id}/respond",
+    tag = "guardian",
+    params(("id" = String, Path, description = "Box ID")),
+    request_body = GuardianResponseRequest,
+    responses(
+        (status = 200, description = "Response recorded", body = GuardianBoxEnvelope),
+        (status = 400, description = "Invalid response", body = ErrorBody),
+        (status = 401, description = "Not a guardian of the box", body = ErrorBody),
+        (status = 403, description = "The guardian holds no shard", body = ErrorBody),
+        (status = 404, description = "Box not found, or no unlock request", body = ErrorBody),
+        (status = 409, description = "The unlock request was already denied, or is decided and the answer changed", body = ErrorBody)
+    )
+)]
 pub async fn respond_to_unlock_request<S>(
     State(store): State<Arc<S>>,
     Path(box_id): Path<String>,
     Extension(user_id): Extension<String>,
+    clock: SharedClock,
+    request_id: RequestId,
     Json(payload): Json<GuardianResponseRequest>,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
-    // Get the box from store
-    let mut box_record = store.get_box(&box_id).await?;
-
-    // TODO: query DB with filters instead
-    if box_record
-        .guardians
-        .iter()
-        .find(|g| g.id == user_id && g.status != GuardianStatus::Rejected)
-        .is_none()
-    {
-        return Err(AppError::unauthorized("Not a guardian for this box".into()));
-    }
-
-    // Check if there's an unlock request to respond to
-    if box_record.unlock_request.is_none() {
-        return Err(AppError::bad_request(
-            "No unlock request exists to update".into(),
-        ));
-    }
-
-    if let Some(unlock) = &mut box_record.unlock_request {
-        let mut updated = false;
+    // `approve: false` denies, as does the older `reject: true`
+    let approved = match (payload.approve, payload.reject) {
+        (Some(true), Some(true)) => {
+            return Err(AppError::bad_request(
+                "Can't approve and deny an unlock request at once".into(),
+            ))
+        }
+        (Some(approve), _) => approve,
+        (None, Some(true)) => false,
+        (None, _) => {
+            return Err(AppError::bad_request(
+                "No valid update field provided".into(),
+            ))
+        }
+    };
+
+    // mutate_box rereads the box when another guardian's answer got there
+    // first, so concurrent answers are all counted and only one of them can
+    // cross the threshold
+    let mut updated = false;
+    let mut threshold_crossed = false;
+    let mut threshold_unreachable = false;
+    let updated_box = store
+        .mutate_box(&box_id, |box_record| {
+            threshold_crossed = false;
+            threshold_unreachable = false;
+
+            // TODO: query DB with filters instead
+            let Some(guardian) = box_record
+                .guardians
+                .iter()
+                .find(|g| g.id == user_id && g.status != GuardianStatus::Rejected)
+            else {
+                return Err(AppError::unauthorized("Not a guardian for this box".into()));
+            };
+
+            // Only guardians whose shard counts towards the unlock can answer,
+            // the same ones who can preview the box
+            if !guardian.holds_shard() {
+                warn!("Guardian {} holds no shard for box {}", user_id, box_id);
+                return Err(AppError::forbidden(
+                    "Only guardians holding a shard can answer an unlock request".into(),
+                ));
+            }
 
-        if let Some(approve) = payload.approve {
-            if approve && !unlock.approved_by.contains(&user_id) {
-                unlock.approved_by.push(user_id.clone());
-                updated = true;
+            // Check if there's an unlock request to respond to
+            // A request the owner cancelled is gone, not merely invalid
+            let Some(unlock) = &mut box_record.unlock_request else {
+                return Err(AppError::not_found(
+                    "No unlock request exists to update".into(),
+                ));
+            };
+
+            // Answering the same way twice is a no-op, so retries aren't
+            // counted again, and changing an answer moves it rather than
+            // counting it on both sides
+            if unlock.status == UnlockRequestStatus::Rejected {
+                return Err(AppError::conflict(
+                    "This unlock request was denied and is closed".into(),
+                ));
+            }
+            // Answers can change while the request is open, not once it's decided
+            if unlock.status != UnlockRequestStatus::Requested
+                && unlock.answered_otherwise(&user_id, approved)
+            {
+                return Err(AppError::conflict(
+                    "This unlock request is already decided; your answer can't change".into(),
+                ));
+            }
+            updated = unlock.record_response(&user_id, approved, &clock.now_str());
+            if !updated {
+                return Ok(());
             }
-        }
 
-        if let Some(reject) = payload.reject {
-            if reject && !unlock.rejected_by.contains(&user_id) {
-                unlock.rejected_by.push(user_id.clone());
-                updated = true;
+            // Only the approval that first reaches the threshold completes the
+            // unlock, and only the denial that first puts it out of reach
+            // closes the request
+            let threshold = box_record.shard_threshold.unwrap_or(0) as usize;
+            let guardian_count = box_record
+                .guardians
+                .iter()
+                .filter(|g| g.holds_shard())
+                .count();
+            if let Some(unlock) = &mut box_record.unlock_request {
+                if threshold > 0 && unlock.status == UnlockRequestStatus::Requested {
+                    if unlock.approved_by.len() >= threshold {
+                        unlock.status = UnlockRequestStatus::Approved;
+                        threshold_crossed = true;
+                    } else if unlock.rejected_by.len() > guardian_count.saturating_sub(threshold) {
+                        unlock.status = UnlockRequestStatus::Rejected;
+                        threshold_unreachable = true;
+                    }
+                }
             }
-        }
 
-        if !updated {
-            return Err(AppError::bad_request(
-                "No valid update field provided".into(),
-            ));
+            box_record.updated_at = clock.now_str();
+            Ok(())
+        })
+        .await?;
+    if !updated {
+        info!(
+            "Unlock response already recorded: request_id={}, box_id={}, guardian_id={}",
+            request_id, box_id, user_id
+        );
+        return render_guardian_box(&updated_box, &user_id);
+    }
+
+    if threshold_crossed {
+        info!(
+            "Unlock threshold reached: request_id={}, box_id={}",
+            request_id, updated_box.id
+        );
+        let event_payload =
+            box_unlocked_event_payload(&updated_box, &updated_box.updated_at, Some(&request_id));
+        if let Err(e) = publish_box_event(
+            "box_unlocked",
+            "Box Unlocked",
+            &updated_box.id,
+            &event_payload,
+        )
+        .await
+        {
+            error!(
+                "Failed to publish box_unlocked event: request_id={}, error={:?}",
+                request_id, e
+            );
         }
+        notify_unlock_webhook(&updated_box, &*clock, &request_id).await;
     }
 
-    box_record.updated_at = now_str();
+    if threshold_unreachable {
+        info!(
+            "Unlock request denied: request_id={}, box_id={}",
+            request_id, updated_box.id
+        );
+        let event_payload = unlock_request_denied_event_payload(
+            &updated_box,
+            &updated_box.updated_at,
+            Some(&request_id),
+        );
+        if let Err(e) = publish_box_event(
+            "unlock_request_denied",
+            "Unlock Request Denied",
+            &updated_box.id,
+            &event_payload,
+        )
+        .await
+        {
+            error!(
+                "Failed to publish unlock_request_denied event: request_id={}, error={:?}",
+                request_id, e
+            );
+        }
+    }
 
-    // Update the box in store
-    let updated_box = store.update_box(box_record).await?;
+    render_guardian_box(&updated_box, &user_id)
+}
 
-    if let Some(guard_box) = convert_to_guardian_box(&updated_box, &user_id) {
-        return Ok(Json(
-            serde_json::json!({ "box": crate::models::GuardianBoxResponse::from(guard_box) }),
-        ));
-    } else {
-        return Err(AppError::internal_server_error(
+fn render_guardian_box(box_record: &BoxRecord, user_id: &str) -> Result<Json<serde_json::Value>> {
+    match GuardianBoxResponse::for_guardian(box_record, user_id) {
+        Some(guard_box) => Ok(Json(serde_json::json!({ "box": guard_box }))),
+        None => Err(AppError::internal_server_error(
             "Failed to render guardian box".into(),
-        ));
+        )),
     }
 }
 
-// PATCH /boxes/guardian/:id/invitation - For accepting/rejecting a guardian invitation
+// Webhook calls recorded instead of delivered by tests, whose endpoints live
+// on loopback where deliveries are refused
+#[cfg(test)]
+static TEST_WEBHOOK_CALLS: std::sync::Mutex<Vec<(String, WebhookPayload)>> =
+    std::sync::Mutex::new(Vec::new());
+
+/// Returns the unlock webhook calls recorded so far, as (url, payload) pairs
+#[cfg(test)]
+pub fn test_webhook_calls() -> Vec<(String, WebhookPayload)> {
+    TEST_WEBHOOK_CALLS
+        .lock()
+        .map(|calls| calls.clone())
+        .unwrap_or_default()
+}
+
+// Calls the owner's webhook, if any, once a box is unlocked. Delivery is best
+// effort: the approval has already been saved, so failures are only logged.
+// It finishes before the response is sent, since Lambda freezes anything
+// still running afterwards, and gives up after UNLOCK_WEBHOOK_DEADLINE so a
+// slow endpoint can't run the handler into the API Gateway timeout.
+async fn notify_unlock_webhook(box_record: &BoxRecord, clock: &dyn Clock, request_id: &RequestId) {
+    let (Some(url), Some(secret)) = (
+        box_record.webhook_url.clone(),
+        box_record.webhook_secret.clone(),
+    ) else {
+        return;
+    };
+    let payload = WebhookPayload {
+        box_id: box_record.id.clone(),
+        event: BOX_UNLOCKED_EVENT.to_string(),
+        timestamp: clock.now_str(),
+    };
+    #[cfg(test)]
+    if let Ok(mut calls) = TEST_WEBHOOK_CALLS.lock() {
+        calls.push((url, payload));
+        return;
+    }
+    match tokio::time::timeout(
+        UNLOCK_WEBHOOK_DEADLINE,
+        deliver_webhook(&url, &secret, &payload),
+    )
+    .await
+    {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => error!(
+            "Failed to deliver unlock webhook: request_id={}, box_id={}, error={}",
+            request_id, payload.box_id, e
+        ),
+        Err(_) => error!(
+            "Gave up delivering unlock webhook after {:?}: request_id={}, box_id={}",
+            UNLOCK_WEBHOOK_DEADLINE, request_id, payload.box_id
+        ),
+    }
+}
+
+// PATCH /boxes/guardian/:id/invitation - For accepting/declining a guardian invitation
+#[utoipa::path(
+    patch,
+    path = "/boxes/guardian/{id}/invitation",
+    tag = "guardian",
+    params(("id" = String, Path, description = "Box ID")),
+    request_body = GuardianInvitationResponse,
+    responses(
+        (status = 200, description = "Invitation accepted or declined", body = InvitationResponseEnvelope),
+        (status = 401, description = "No pending invitation for the caller", body = ErrorBody),
+        (status = 404, description = "Box not found", body = ErrorBody)
+    )
+)]
 pub async fn respond_to_invitation<S>(
     State(store): State<Arc<S>>,
     Path(box_id): Path<String>,
     Extension(user_id): Extension<String>,
+    clock: SharedClock,
+    request_id: RequestId,
     Json(payload): Json<GuardianInvitationResponse>,
 ) -> Result<Json<serde_json::Value>>
 where
     S: BoxStore,
 {
-    // Get the box from store
-    let mut box_record = store.get_box(&box_id).await?;
-
-    // Find if user is a guardian with a pending invitation
-    // Pending can be either Invited (not opened) or Viewed (opened/linked)
-    let guardian_index = box_record.guardians.iter().position(|g| {
-        g.id == user_id
-            && (g.status == GuardianStatus::Invited || g.status == GuardianStatus::Viewed)
-    });
-
-    if let Some(index) = guardian_index {
-        // Update the guardian status based on the acceptance
-        if payload.accept {
-            box_record.guardians[index].status = GuardianStatus::Accepted;
-            box_record.updated_at = now_str();
-
-            // Update the box in store
-            let updated_box = store.update_box(box_record).await?;
-
-            if let Some(guard_box) = convert_to_guardian_box(&updated_box, &user_id) {
-                return Ok(Json(serde_json::json!({
-                    "message": "Guardian invitation accepted successfully",
-                    "box": crate::models::GuardianBoxResponse::from(guard_box)
-                })));
-            } else {
-                return Err(AppError::internal_server_error(
-                    "Failed to render guardian box".into(),
+    if !payload.accept {
+        return decline_guardianship(&*store, &*clock, &box_id, &user_id, &request_id).await;
+    }
+
+    // mutate_box rereads the box when another write got there first, so the
+    // acceptance never overwrites a concurrent change to the box
+    let mut accepted = None;
+    let updated_box = store
+        .mutate_box(&box_id, |box_record| {
+            // Find if user is a guardian with a pending invitation
+            // Pending can be either Invited (not opened) or Viewed (opened/linked)
+            let guardian = box_record
+                .guardians
+                .iter_mut()
+                .find(|g| {
+                    g.id == user_id
+                        && (g.status == GuardianStatus::Invited
+                            || g.status == GuardianStatus::Viewed)
+                })
+                .ok_or_else(|| {
+                    AppError::bad_request("No pending invitation found for this user".into())
+                })?;
+
+            guardian.status = GuardianStatus::Accepted;
+            accepted = Some(guardian.clone());
+            box_record.updated_at = clock.now_str();
+            Ok::<(), AppError>(())
+        })
+        .await?;
+    let guardian = accepted.ok_or_else(|| {
+        AppError::internal_server_error("Accepted guardian not found in response".into())
+    })?;
+
+    publish_guardian_accepted(&updated_box, &guardian, Some(&request_id)).await;
+
+    let guard_box = GuardianBoxResponse::for_guardian(&updated_box, &user_id)
+        .ok_or_else(|| AppError::internal_server_error("Failed to render guardian box".into()))?;
+    Ok(Json(serde_json::json!({
+        "message": "Guardian invitation accepted successfully",
+        "box": guard_box
+    })))
+}
+
+// Removes a guardian who no longer wants the role from an unlocked box and
+// tells the owner. Once a box is locked the guardian already holds a shard,
+// so stepping down would silently weaken the recovery threshold.
+async fn decline_guardianship<S>(
+    store: &S,
+    clock: &dyn Clock,
+    box_id: &str,
+    user_id: &str,
+    request_id: &RequestId,
+) -> Result<Json<serde_json::Value>>
+where
+    S: BoxStore,
+{
+    // mutate_box rereads the box when another write got there first, so a
+    // box locked in the meantime is never declined
+    let mut declined = None;
+    let updated_box = store
+        .mutate_box(box_id, |box_record| {
+            let index = box_record
+                .guardians
+                .iter()
+                .position(|g| g.id == user_id && g.status != GuardianStatus::Rejected)
+                .ok_or_else(|| {
+                    AppError::bad_request("No pending invitation found for this user".into())
+                })?;
+
+            if box_record.is_locked {
+                return Err(AppError::conflict(
+                    "Cannot decline guardianship of a locked box; shards have already been distributed. Ask the owner to unlock the box and remove you instead.".into(),
                 ));
             }
-        } else {
-            // User is rejecting the invitation
-            box_record.guardians[index].status = GuardianStatus::Rejected;
-            box_record.updated_at = now_str();
 
-            // Update the box in store
-            let _updated_box = store.update_box(box_record).await?;
+            let mut guardian = box_record.guardians.remove(index);
+            guardian.status = GuardianStatus::Rejected;
+            declined = Some(guardian);
+            box_record.updated_at = clock.now_str();
+            Ok(())
+        })
+        .await?;
+    let guardian = declined.ok_or_else(|| {
+        AppError::internal_server_error("Declined guardian not found in response".into())
+    })?;
+
+    info!(
+        "Guardian declined: request_id={}, box_id={}, guardian_id={}",
+        request_id, updated_box.id, guardian.id
+    );
 
-            return Ok(Json(serde_json::json!({
-                "message": "Guardian invitation rejected successfully"
-            })));
-        }
+    let event_payload = guardian_declined_event_payload(
+        &updated_box,
+        &guardian,
+        &updated_box.updated_at,
+        Some(request_id),
+    );
+    // Fire and forget; the decline itself has already been saved
+    if let Err(e) = publish_box_event(
+        "guardian_declined",
+        "Guardian Declined",
+        &updated_box.id,
+        &event_payload,
+    )
+    .await
+    {
+        error!(
+            "Failed to publish guardian_declined event: request_id={}, error={:?}",
+            request_id, e
+        );
     }
 
-    Err(AppError::bad_request(
-        "No pending invitation found for this user".into(),
-    ))
+    Ok(Json(serde_json::json!({
+        "message": "Guardian invitation rejected successfully",
+        "guardian": guardian
+    })))
+}
+
+/// Tells the owner, through a guardian_accepted event, that a guardian took
+/// on the role. The acceptance has already been saved, so failures are only
+/// logged.
+pub(crate) async fn publish_guardian_accepted(
+    box_record: &BoxRecord,
+    guardian: &Guardian,
+    request_id: Option<&RequestId>,
+) {
+    let event_payload =
+        guardian_accepted_event_payload(box_record, guardian, &box_record.updated_at, request_id);
+    if let Err(e) = publish_box_event(
+        "guardian_accepted",
+        "Guardian Accepted",
+        &box_record.id,
+        &event_payload,
+    )
+    .await
+    {
+        error!(
+            "Failed to publish guardian_accepted event: request_id={}, error={:?}",
+            request_id.map(RequestId::as_str).unwrap_or("-"),
+            e
+        );
+    }
+}
+
+/// Builds the unlock_requested event payload, addressed to the box owner
+pub fn unlock_requested_event_payload(
+    box_record: &BoxRecord,
+    requesting_guardian_id: &str,
+    timestamp: &str,
+    request_id: Option<&RequestId>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "event_type": "unlock_requested",
+        "schema_version": EVENT_SCHEMA_VERSION,
+        "box_id": box_record.id,
+        "box_name": box_record.name,
+        "owner_id": box_record.owner_id,
+        "requesting_guardian_id": requesting_guardian_id,
+        "reason": box_record.unlock_request.as_ref().and_then(|u| u.reason.as_deref()),
+        "timestamp": timestamp,
+        "request_id": request_id.map(RequestId::as_str)
+    })
+}
+
+/// Builds the box_unlocked event payload, addressed to the box owner
+pub fn box_unlocked_event_payload(
+    box_record: &BoxRecord,
+    timestamp: &str,
+    request_id: Option<&RequestId>,
+) -> serde_json::Value {
+    let unlock = box_record.unlock_request.as_ref();
+    serde_json::json!({
+        "event_type": "box_unlocked",
+        "schema_version": EVENT_SCHEMA_VERSION,
+        "box_id": box_record.id,
+        "box_name": box_record.name,
+        "owner_id": box_record.owner_id,
+        "unlock_request_id": unlock.map(|u| u.id.as_str()),
+        "approved_by": unlock.map(|u| u.approved_by.clone()).unwrap_or_default(),
+        "timestamp": timestamp,
+        "request_id": request_id.map(RequestId::as_str)
+    })
+}
+
+/// Builds the unlock_request_denied event payload, addressed to the box owner
+pub fn unlock_request_denied_event_payload(
+    box_record: &BoxRecord,
+    timestamp: &str,
+    request_id: Option<&RequestId>,
+) -> serde_json::Value {
+    let unlock = box_record.unlock_request.as_ref();
+    serde_json::json!({
+        "event_type": "unlock_request_denied",
+        "schema_version": EVENT_SCHEMA_VERSION,
+        "box_id": box_record.id,
+        "box_name": box_record.name,
+        "owner_id": box_record.owner_id,
+        "unlock_request_id": unlock.map(|u| u.id.as_str()),
+        "denied_by": unlock.map(|u| u.rejected_by.clone()).unwrap_or_default(),
+        "timestamp": timestamp,
+        "request_id": request_id.map(RequestId::as_str)
+    })
+}
+
+/// Builds the guardian_accepted event payload, addressed to the box owner
+pub fn guardian_accepted_event_payload(
+    box_record: &BoxRecord,
+    guardian: &Guardian,
+    timestamp: &str,
+    request_id: Option<&RequestId>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "event_type": "guardian_accepted",
+        "schema_version": EVENT_SCHEMA_VERSION,
+        "box_id": box_record.id,
+        "box_name": box_record.name,
+        "owner_id": box_record.owner_id,
+        "guardian_id": guardian.id,
+        "guardian_name": guardian.name,
+        "timestamp": timestamp,
+        "request_id": request_id.map(RequestId::as_str)
+    })
+}
+
+/// Builds the guardian_declined event payload, addressed to the box owner
+pub fn guardian_declined_event_payload(
+    box_record: &BoxRecord,
+    guardian: &Guardian,
+    timestamp: &str,
+    request_id: Option<&RequestId>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "event_type": "guardian_declined",
+        "schema_version": EVENT_SCHEMA_VERSION,
+        "box_id": box_record.id,
+        "box_name": box_record.name,
+        "owner_id": box_record.owner_id,
+        "guardian_id": guardian.id,
+        "guardian_name": guardian.name,
+        "timestamp": timestamp,
+        "request_id": request_id.map(RequestId::as_str)
+    })
 }
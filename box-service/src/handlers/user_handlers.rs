@@ -1,25 +1,79 @@
-use axum::{Extension, Json};
-use lockbox_shared::models::{now_str, PushToken};
-use lockbox_shared::store::dynamo::DynamoPushTokenStore;
-use lockbox_shared::store::PushTokenStore;
-use log::info;
-use serde::Deserialize;
+use axum::extract::{Query, State};
+use axum::Extension;
+use chrono::{DateTime, Duration, Utc};
+use lockbox_shared::clock::SharedClock;
+use lockbox_shared::error::ErrorBody;
+use lockbox_shared::extract::Json;
+use lockbox_shared::models::{GuardianStatus, Invitation, NotificationPrefs, PushToken};
+use lockbox_shared::push::{
+    ExpoPushTicket, NotificationOptions, SharedPushSender, DEVICE_NOT_REGISTERED, TEST_NOTIFICATION,
+};
+use lockbox_shared::store::{BoxStore, SharedInvitationStore, SharedPushTokenStore};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
 
 use crate::error::{AppError, Result};
 
-#[derive(Deserialize)]
+const TEST_NOTIFICATION_TITLE: &str = "Lockbox";
+const TEST_NOTIFICATION_BODY: &str = "Lockbox notifications are working";
+
+// Minimum time between test notifications for the same user
+const TEST_NOTIFICATION_COOLDOWN_SECS: i64 = 60;
+
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct RegisterPushTokenRequest {
     pub push_token: String,
     pub platform: String,
+    /// The device's current offset from UTC in minutes, so reminders can
+    /// avoid the night
+    #[serde(default)]
+    pub utc_offset_minutes: Option<i32>,
 }
 
-/// PUT /users/push-token
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RegisterPushTokenQuery {
+    /// Send a silent push to the token to check Expo accepts it
+    #[serde(default)]
+    pub verify: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RegisterPushTokenResponse {
+    pub message: String,
+    /// Whether Expo accepted the verification push; only present with
+    /// `verify=true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verified: Option<bool>,
+    /// Expo's ticket for the verification push, if Expo could be reached
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ticket: Option<ExpoPushTicket>,
+}
+
+/// PUT /users/push-token?verify=true
 /// Register or update a user's push notification token
+#[utoipa::path(
+    put,
+    path = "/users/push-token",
+    tag = "users",
+    params(RegisterPushTokenQuery),
+    request_body = RegisterPushTokenRequest,
+    responses(
+        (status = 200, description = "Push token saved", body = RegisterPushTokenResponse),
+        (status = 400, description = "Invalid platform or token, or Expo no longer knows the device", body = ErrorBody)
+    )
+)]
 pub async fn register_push_token(
     Extension(user_id): Extension<String>,
+    Extension(store): Extension<SharedPushTokenStore>,
+    Extension(push_sender): Extension<SharedPushSender>,
+    clock: SharedClock,
+    Query(query): Query<RegisterPushTokenQuery>,
     Json(request): Json<RegisterPushTokenRequest>,
-) -> Result<Json<serde_json::Value>> {
+) -> Result<Json<RegisterPushTokenResponse>> {
     info!(
         "Registering push token for user: {}, platform: {}",
         user_id, request.platform
@@ -40,15 +94,45 @@ pub async fn register_push_token(
         ));
     }
 
-    // Create the push token store
-    let store = DynamoPushTokenStore::new().await;
+    if let Some(offset) = request.utc_offset_minutes {
+        validate_utc_offset(offset)?;
+    }
+
+    // Keep preferences and the test notification cooldown across
+    // re-registrations, and the last verification while the token itself is
+    // unchanged
+    let existing = store.get_push_token(&user_id).await?;
+    let last_test_notification_at = existing
+        .as_ref()
+        .and_then(|existing| existing.last_test_notification_at.clone());
+    let notification_prefs = existing
+        .as_ref()
+        .and_then(|existing| existing.notification_prefs.clone());
+    let last_verified_at = existing
+        .filter(|existing| existing.push_token == request.push_token)
+        .and_then(|existing| existing.last_verified_at);
 
     // Create the push token record
-    let token = PushToken {
+    let mut token = PushToken {
         user_id: user_id.clone(),
         push_token: request.push_token,
         platform: request.platform,
-        updated_at: now_str(),
+        updated_at: clock.now_str(),
+        last_test_notification_at,
+        last_verified_at,
+        notification_prefs,
+        utc_offset_minutes: request.utc_offset_minutes,
+    };
+
+    let (verified, ticket) = if query.verify {
+        let ticket = verify_push_token(&push_sender, &token).await?;
+        let verified = ticket.as_ref().is_some_and(ExpoPushTicket::is_ok);
+        if verified {
+            token.last_verified_at = Some(clock.now_str());
+        }
+        (Some(verified), ticket)
+    } else {
+        (None, None)
     };
 
     // Save the token
@@ -56,7 +140,280 @@ pub async fn register_push_token(
 
     info!("Successfully registered push token for user: {}", user_id);
 
-    Ok(Json(serde_json::json!({
-        "message": "Push token registered successfully"
-    })))
+    Ok(Json(RegisterPushTokenResponse {
+        message: "Push token registered successfully".into(),
+        verified,
+        ticket,
+    }))
+}
+
+// Sends a silent push to a token being registered, returning Expo's ticket.
+// A token Expo says no longer reaches a device is rejected; any other failure
+// leaves the token unverified but still registered, so an Expo outage doesn't
+// stop users saving their token.
+async fn verify_push_token(
+    push_sender: &SharedPushSender,
+    token: &PushToken,
+) -> Result<Option<ExpoPushTicket>> {
+    let ticket = match push_sender
+        .send_silent_push_notifications(
+            std::slice::from_ref(token),
+            Some(serde_json::json!({ "type": "verify" })),
+        )
+        .await
+    {
+        Ok(tickets) => tickets.into_iter().next(),
+        Err(e) => {
+            warn!(
+                "Could not verify push token: user_id={}, error={}",
+                token.user_id, e
+            );
+            None
+        }
+    };
+
+    if let Some(ticket) = &ticket {
+        if ticket.error_code() == Some(DEVICE_NOT_REGISTERED) {
+            return Err(AppError::bad_request(
+                "Push token is no longer registered with Expo".into(),
+            ));
+        }
+    }
+    Ok(ticket)
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationPrefsResponse {
+    pub notification_prefs: NotificationPrefs,
+}
+
+/// PUT /users/notification-prefs
+/// Sets which notifications the caller receives. Preferences are kept with
+/// the caller's push token, so one must be registered first.
+#[utoipa::path(
+    put,
+    path = "/users/notification-prefs",
+    tag = "users",
+    request_body = NotificationPrefs,
+    responses(
+        (status = 200, description = "Preferences saved", body = NotificationPrefsResponse),
+        (status = 400, description = "Invalid quiet hours", body = ErrorBody),
+        (status = 404, description = "No push token registered", body = ErrorBody)
+    )
+)]
+pub async fn update_notification_prefs(
+    Extension(user_id): Extension<String>,
+    Extension(store): Extension<SharedPushTokenStore>,
+    Json(prefs): Json<NotificationPrefs>,
+) -> Result<Json<NotificationPrefsResponse>> {
+    validate_notification_prefs(&prefs)?;
+
+    let mut token = store
+        .get_push_token(&user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("No push token registered".into()))?;
+    token.notification_prefs = Some(prefs.clone());
+    store.save_push_token(token).await?;
+    info!(
+        "Notification preferences updated: user_id={}, reminders_enabled={}, quiet_hours={}",
+        user_id,
+        prefs.reminders_enabled,
+        prefs.quiet_hours.is_some()
+    );
+
+    Ok(Json(NotificationPrefsResponse {
+        notification_prefs: prefs,
+    }))
+}
+
+// UTC offsets in use range from UTC-12 to UTC+14
+const MIN_UTC_OFFSET_MINUTES: i32 = -12 * 60;
+const MAX_UTC_OFFSET_MINUTES: i32 = 14 * 60;
+
+fn validate_notification_prefs(prefs: &NotificationPrefs) -> Result<()> {
+    let Some(quiet_hours) = &prefs.quiet_hours else {
+        return Ok(());
+    };
+    let (start, end) = quiet_hours.bounds().ok_or_else(|| {
+        AppError::bad_request("Quiet hours start and end must be HH:MM times".into())
+    })?;
+    if start == end {
+        return Err(AppError::bad_request(
+            "Quiet hours start and end must differ".into(),
+        ));
+    }
+    validate_utc_offset(quiet_hours.utc_offset_minutes)
+}
+
+fn validate_utc_offset(offset: i32) -> Result<()> {
+    if !(MIN_UTC_OFFSET_MINUTES..=MAX_UTC_OFFSET_MINUTES).contains(&offset) {
+        return Err(AppError::bad_request(format!(
+            "utcOffsetMinutes must be between {} and {}",
+            MIN_UTC_OFFSET_MINUTES, MAX_UTC_OFFSET_MINUTES
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TestNotificationResponse {
+    /// Expo's ticket for each message sent
+    pub tickets: Vec<ExpoPushTicket>,
+}
+
+/// POST /users/push-token/test
+/// Sends the caller a harmless push notification, so they can check their
+/// device receives alerts before one matters
+#[utoipa::path(
+    post,
+    path = "/users/push-token/test",
+    tag = "users",
+    responses(
+        (status = 200, description = "Notification handed to Expo", body = TestNotificationResponse),
+        (status = 404, description = "No push token registered", body = ErrorBody),
+        (status = 429, description = "A test notification was sent too recently", body = ErrorBody),
+        (status = 503, description = "Expo could not be reached", body = ErrorBody)
+    )
+)]
+pub async fn send_test_notification(
+    Extension(user_id): Extension<String>,
+    Extension(store): Extension<SharedPushTokenStore>,
+    Extension(push_sender): Extension<SharedPushSender>,
+    clock: SharedClock,
+) -> Result<Json<TestNotificationResponse>> {
+    let mut token = store
+        .get_push_token(&user_id)
+        .await?
+        .ok_or_else(|| AppError::not_found("No push token registered".into()))?;
+
+    let now = clock.now();
+    if let Some(retry_after) = test_notification_cooldown_remaining(&token, now) {
+        return Err(AppError::too_many_requests(
+            "A test notification was sent too recently; try again later".into(),
+            retry_after,
+        ));
+    }
+
+    // Record the attempt first, so failed sends count towards the limit too
+    token.last_test_notification_at = Some(now.to_rfc3339());
+    let token = store.save_push_token(token).await?;
+
+    let tickets = push_sender
+        .send_push_notifications(
+            std::slice::from_ref(&token),
+            TEST_NOTIFICATION_TITLE,
+            TEST_NOTIFICATION_BODY,
+            Some(serde_json::json!({ "type": TEST_NOTIFICATION })),
+            // Nothing is pending on a test, so the badge is left alone
+            &NotificationOptions::for_type(TEST_NOTIFICATION, None),
+        )
+        .await
+        .map_err(|e| AppError::ServiceUnavailable(e.to_string()))?;
+    info!(
+        "Test notification sent: user_id={}, tickets={}",
+        user_id,
+        tickets.len()
+    );
+
+    Ok(Json(TestNotificationResponse { tickets }))
+}
+
+// Seconds until the user may send another test notification, or None if
+// they can go ahead
+fn test_notification_cooldown_remaining(token: &PushToken, now: DateTime<Utc>) -> Option<u64> {
+    let last = token
+        .last_test_notification_at
+        .as_deref()
+        .and_then(|t| DateTime::parse_from_rfc3339(t).ok())?
+        .with_timezone(&Utc);
+    let remaining = (last + Duration::seconds(TEST_NOTIFICATION_COOLDOWN_SECS) - now).num_seconds();
+    (remaining > 0).then_some(remaining as u64)
+}
+
+// Boxes or invitations read per store call while counting
+const STATS_PAGE_SIZE: usize = 100;
+
+/// Counts across everything the caller owns
+#[derive(Serialize, Debug, Default, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UserStatsResponse {
+    pub total_boxes: usize,
+    pub locked_boxes: usize,
+    pub total_documents: usize,
+    /// Guardian places across all boxes, not counting guardians who declined
+    pub total_guardians: usize,
+    /// Invitations the caller sent that are still waiting to be accepted
+    pub pending_invitations: usize,
+}
+
+/// GET /users/me/stats
+/// Summarises the caller's boxes and the invitations they've sent
+#[utoipa::path(
+    get,
+    path = "/users/me/stats",
+    tag = "users",
+    responses(
+        (status = 200, description = "The caller's account summary", body = UserStatsResponse)
+    )
+)]
+pub async fn get_user_stats<S>(
+    State(store): State<Arc<S>>,
+    Extension(user_id): Extension<String>,
+    Extension(invitations): Extension<SharedInvitationStore>,
+    clock: SharedClock,
+) -> Result<Json<UserStatsResponse>>
+where
+    S: BoxStore,
+{
+    let mut stats = UserStatsResponse::default();
+
+    // The box figures need each box's guardians and lock state, so every box
+    // is read anyway and counted on the way
+    let mut cursor = None;
+    loop {
+        let page = store
+            .get_boxes_by_owner_paginated(&user_id, STATS_PAGE_SIZE, cursor.as_deref())
+            .await?;
+        stats.total_boxes += page.items.len();
+        for box_rec in &page.items {
+            stats.locked_boxes += usize::from(box_rec.is_locked);
+            stats.total_documents += box_rec.documents.len();
+            stats.total_guardians += box_rec
+                .guardians
+                .iter()
+                .filter(|g| g.status != GuardianStatus::Rejected)
+                .count();
+        }
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    let now = clock.now();
+    let mut cursor = None;
+    loop {
+        let page = invitations
+            .get_invitations_by_creator_id_paginated(&user_id, STATS_PAGE_SIZE, cursor.as_deref())
+            .await?;
+        stats.pending_invitations += page
+            .items
+            .iter()
+            .filter(|invitation| invitation_pending(invitation, now))
+            .count();
+        cursor = page.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+
+    Ok(Json(stats))
+}
+
+// Not yet opened or linked to a user, and not expired
+fn invitation_pending(invitation: &Invitation, now: DateTime<Utc>) -> bool {
+    let unexpired = DateTime::parse_from_rfc3339(&invitation.expires_at)
+        .is_ok_and(|expires_at| expires_at.with_timezone(&Utc) > now);
+    !invitation.opened && invitation.linked_user_id.is_none() && unexpired
 }
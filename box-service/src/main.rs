@@ -1,23 +1,30 @@
 mod error;
+mod grpc;
 mod handlers;
 // Keep models for request/response types
 mod models;
+mod openapi;
 mod routes;
 
 #[cfg(test)]
 mod tests;
 
 use axum::{body::Body, extract::Request, response::Response, Router};
-use env_logger;
+use grpc::BoxGrpcService;
+use handlers::box_handlers::UnacceptedGuardianPolicy;
 use http_body_util::BodyExt;
 use lambda_http::{
     run, service_fn, Body as LambdaBody, Error, Request as LambdaRequest,
     Response as LambdaResponse,
 };
+use lockbox_shared::maintenance::ReadOnlyMode;
+use lockbox_shared::shutdown::{drain_timeout, serve_with_graceful_shutdown, shutdown_signal};
+use lockbox_shared::store::dynamo::DynamoBoxStore;
 use log::{debug, error, info, trace};
 use once_cell::sync::OnceCell;
 use routes::create_router;
 use std::net::SocketAddr;
+use std::sync::Arc;
 use tokio::sync::Mutex;
 use tower::ServiceExt;
 
@@ -147,11 +154,29 @@ async fn response_to_lambda(response: Response) -> Result<LambdaResponse<LambdaB
     Ok(lambda_response)
 }
 
+/// Where the gRPC gateway listens: `GRPC_BIND_ADDR` when set (such as
+/// `0.0.0.0:50051` in a container), otherwise localhost at `GRPC_PORT`
+fn grpc_bind_addr() -> Result<SocketAddr, Error> {
+    if let Ok(addr) = std::env::var("GRPC_BIND_ADDR") {
+        return addr.parse().map_err(|e| {
+            format!("GRPC_BIND_ADDR {:?} is not a socket address: {}", addr, e).into()
+        });
+    }
+    let grpc_port = std::env::var("GRPC_PORT")
+        .ok()
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(50051);
+    Ok(SocketAddr::from(([127, 0, 0, 1], grpc_port)))
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    // Initialize env_logger instead of tracing_subscriber
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
-    info!("Logging initialized with env_logger");
+    lockbox_shared::logging::init();
+    info!("Logging initialized");
+
+    // Refuse to start without a usable cursor secret rather than failing on
+    // the first paginated request
+    lockbox_shared::store::pagination::check_cursor_secret()?;
 
     // Check if running in Lambda environment
     if let Ok(function_name) = std::env::var("AWS_LAMBDA_FUNCTION_NAME") {
@@ -167,9 +192,35 @@ async fn main() -> Result<(), Error> {
         let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
         info!("listening on {}", addr);
 
+        // Serve the gRPC gateway alongside the HTTP API
+        let grpc_addr = grpc_bind_addr()?;
+        let grpc_service = BoxGrpcService::new(Arc::new(DynamoBoxStore::new().await))
+            .with_lock_policy(UnacceptedGuardianPolicy::from_env())
+            .with_read_only(ReadOnlyMode::from_env());
+        info!("gRPC listening on {}", grpc_addr);
+        let grpc_server = tokio::spawn(async move {
+            if let Err(err) = tonic::transport::Server::builder()
+                .add_service(grpc_service.into_server())
+                .serve_with_shutdown(grpc_addr, shutdown_signal())
+                .await
+            {
+                error!("gRPC server failed: {:?}", err);
+            }
+        });
+
         let app = create_router().await;
         let listener = tokio::net::TcpListener::bind(&addr).await?;
-        axum::serve(listener, app.into_make_service()).await?;
+
+        // Both servers stop on the same signal; wait for the gRPC one to
+        // finish its in-flight calls too before the process exits
+        let (served, grpc_served) = tokio::join!(
+            serve_with_graceful_shutdown(listener, app, shutdown_signal(), drain_timeout()),
+            grpc_server
+        );
+        if let Err(err) = grpc_served {
+            error!("gRPC server task failed: {:?}", err);
+        }
+        served?;
     }
 
     info!("Service finished");
@@ -1,96 +1,294 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use utoipa::{IntoParams, ToSchema};
 
 // Import shared models for direct use in request/response types
-use lockbox_shared::models::{Document, Guardian, UnlockRequest};
+use lockbox_shared::error::FieldError;
+use lockbox_shared::models::{
+    Document, Guardian, GuardianStatus, UnlockRequest, UnlockRequestStatus,
+};
+use lockbox_shared::validation::normalize_name;
 
 // Request DTOs
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct CreateBoxRequest {
     pub name: String,
     pub description: String,
-    #[serde(rename = "ownerName", skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub owner_name: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+impl CreateBoxRequest {
+    /// Every problem with the request, not just the first
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        if let Err(e) = normalize_name("name", &self.name) {
+            errors.push(e);
+        }
+        if let Some(Err(e)) = self
+            .owner_name
+            .as_deref()
+            .map(|owner_name| normalize_name("ownerName", owner_name))
+        {
+            errors.push(e);
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct UpdateBoxRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    /// `null` clears the instructions; leaving the field out keeps them
     #[serde(
-        rename = "unlockInstructions",
         skip_serializing_if = "Option::is_none",
         default,
         with = "optional_field_serde"
     )]
+    #[schema(value_type = Option<String>)]
     pub unlock_instructions: Option<OptionalField<String>>,
-    #[serde(rename = "isLocked", skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub is_locked: Option<bool>,
+    /// Whether guardians must redeem a one-time challenge before fetching
+    /// their shard; can be changed after the box is locked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub require_step_up: Option<bool>,
 }
 
-#[derive(Deserialize, Debug)]
+/// New unlock instructions for a box, which may already be locked
+#[derive(Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UnlockInstructionsUpdateRequest {
+    /// `null` clears the instructions
+    pub unlock_instructions: Option<String>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct DocumentUpdateRequest {
     pub document: Document,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct GuardianUpdateRequest {
     pub guardian: Guardian,
+    /// When set, the update only applies if the stored guardian currently has
+    /// this status; otherwise it is rejected as a conflict
+    #[serde(default)]
+    pub expected_status: Option<GuardianStatus>,
+}
+
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct DocumentsBulkUpdateRequest {
+    pub documents: Vec<Document>,
+}
+
+/// The full guardian set. Only each guardian's id, invitation, name and lead
+/// flag are taken from the request; status and shard state stay as stored,
+/// and new guardians start out invited.
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct GuardiansReplaceRequest {
+    pub guardians: Vec<Guardian>,
 }
 
-#[derive(Deserialize, Debug)]
+/// Longest base64 shard accepted when locking a box. Shards hold a share of
+/// the box key, so real ones are a few hundred characters at most.
+pub const MAX_SHARD_CHARS: usize = 8 * 1024;
+
+#[derive(Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct IncomingShard {
-    #[serde(rename = "guardianId")]
     pub guardian_id: String,
+    /// Base64-encoded encrypted shard
     pub shard: String,
-    #[serde(rename = "shardHash")]
+    /// Hex SHA-256 of the base64-decoded shard, verified by the server
     pub shard_hash: String,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct LockBoxRequest {
-    #[serde(rename = "shardThreshold")]
     pub shard_threshold: usize,
     pub shards: Vec<IncomingShard>,
 }
 
+impl LockBoxRequest {
+    /// Every problem with the threshold and shards that can be found without
+    /// looking at the box, not just the first
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        if self.shard_threshold < 1 || self.shard_threshold > self.shards.len() {
+            errors.push(FieldError::new(
+                "shardThreshold",
+                "OUT_OF_RANGE",
+                "Shard threshold must be between 1 and the number of guardians.",
+            ));
+        }
+
+        let mut seen = HashSet::new();
+        for (i, shard) in self.shards.iter().enumerate() {
+            if shard.guardian_id.trim().is_empty() {
+                errors.push(FieldError::new(
+                    format!("shards[{}].guardianId", i),
+                    "EMPTY",
+                    format!("Shard {} has no guardian id.", i),
+                ));
+            } else if !seen.insert(shard.guardian_id.as_str()) {
+                errors.push(FieldError::new(
+                    format!("shards[{}].guardianId", i),
+                    "DUPLICATE",
+                    format!("Guardian {} has more than one shard.", shard.guardian_id),
+                ));
+            }
+
+            if shard.shard.is_empty() {
+                errors.push(FieldError::new(
+                    format!("shards[{}].shard", i),
+                    "EMPTY",
+                    format!("Shard for guardian {} is empty.", shard.guardian_id),
+                ));
+                continue;
+            }
+            if shard.shard.len() > MAX_SHARD_CHARS {
+                errors.push(FieldError::new(
+                    format!("shards[{}].shard", i),
+                    "TOO_LONG",
+                    format!(
+                        "Shard for guardian {} must be at most {} characters.",
+                        shard.guardian_id, MAX_SHARD_CHARS
+                    ),
+                ));
+                continue;
+            }
+
+            let shard_hash = shard.shard_hash.trim();
+            let hash_is_hex =
+                shard_hash.len() == 64 && shard_hash.chars().all(|c| c.is_ascii_hexdigit());
+            if !hash_is_hex {
+                errors.push(FieldError::new(
+                    format!("shards[{}].shardHash", i),
+                    "INVALID_FORMAT",
+                    format!(
+                        "Shard hash for guardian {} must be a hex SHA-256 digest.",
+                        shard.guardian_id
+                    ),
+                ));
+            }
+
+            // Recompute each shard hash so a guardian can never fetch a shard
+            // that doesn't match the hash stored alongside it
+            match Guardian::expected_shard_hash(&shard.shard) {
+                None => errors.push(FieldError::new(
+                    format!("shards[{}].shard", i),
+                    "INVALID_BASE64",
+                    format!(
+                        "Shard for guardian {} is not valid base64.",
+                        shard.guardian_id
+                    ),
+                )),
+                Some(expected) if hash_is_hex && !expected.eq_ignore_ascii_case(shard_hash) => {
+                    errors.push(FieldError::new(
+                        format!("shards[{}].shardHash", i),
+                        "HASH_MISMATCH",
+                        format!(
+                            "Shard hash for guardian {} does not match the submitted shard.",
+                            shard.guardian_id
+                        ),
+                    ))
+                }
+                Some(_) => {}
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
 // Response DTOs
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct BoxResponse {
     pub id: String,
     pub name: String,
     pub description: String,
-    #[serde(rename = "createdAt")]
     pub created_at: String,
-    #[serde(rename = "updatedAt")]
     pub updated_at: String,
-    #[serde(rename = "unlockInstructions")]
     pub unlock_instructions: Option<String>,
-    #[serde(rename = "isLocked")]
     pub is_locked: bool,
-    #[serde(rename = "lockedAt", skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub locked_at: Option<String>,
     pub documents: Vec<Document>,
     pub guardians: Vec<Guardian>,
-    #[serde(rename = "ownerId")]
     pub owner_id: String,
-    #[serde(rename = "ownerName")]
     pub owner_name: Option<String>,
-    #[serde(rename = "unlockRequest")]
     pub unlock_request: Option<UnlockRequest>,
-    #[serde(rename = "shardThreshold", skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub shard_threshold: Option<u32>,
-    #[serde(rename = "shardsFetched", skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub shards_fetched: Option<usize>,
-    #[serde(rename = "totalShards", skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub total_shards: Option<usize>,
-    #[serde(rename = "shardsDeletedAt", skip_serializing_if = "Option::is_none")]
+    /// Shards not yet fetched, present once the box has been locked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shards_remaining: Option<usize>,
+    /// Whether every guardian has fetched their shard, present once the box
+    /// has been locked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub all_fetched: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub shards_deleted_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    /// Whether guardians must redeem a one-time challenge before fetching
+    /// their shard
+    pub require_step_up: bool,
+    /// Bumped on every write; pass it as `since` to watch for changes
+    pub version: u64,
+    /// Guardians on the box, not counting those who declined. Derived from
+    /// `guardians`, like the two fields below.
+    pub guardian_count: usize,
+    pub accepted_count: usize,
+    /// Names of guardians who haven't answered their invitation yet
+    pub pending_guardian_names: Vec<String>,
 }
 
 impl From<lockbox_shared::models::BoxRecord> for BoxResponse {
     fn from(box_rec: lockbox_shared::models::BoxRecord) -> Self {
+        let guardian_count = box_rec
+            .guardians
+            .iter()
+            .filter(|g| g.status != GuardianStatus::Rejected)
+            .count();
+        let accepted_count = box_rec
+            .guardians
+            .iter()
+            .filter(|g| g.status == GuardianStatus::Accepted)
+            .count();
+        let pending_guardian_names = box_rec
+            .guardians
+            .iter()
+            .filter(|g| matches!(g.status, GuardianStatus::Invited | GuardianStatus::Viewed))
+            .map(|g| g.name.clone())
+            .collect();
+        let shards_fetched = box_rec.shards_fetched.unwrap_or(0);
+        let shards_remaining = box_rec
+            .total_shards
+            .map(|total| total.saturating_sub(shards_fetched));
+
         Self {
             id: box_rec.id,
             name: box_rec.name,
@@ -108,32 +306,59 @@ impl From<lockbox_shared::models::BoxRecord> for BoxResponse {
             shard_threshold: box_rec.shard_threshold,
             shards_fetched: box_rec.shards_fetched,
             total_shards: box_rec.total_shards,
+            shards_remaining,
+            all_fetched: shards_remaining.map(|remaining| remaining == 0),
             shards_deleted_at: box_rec.shards_deleted_at,
+            webhook_url: box_rec.webhook_url,
+            require_step_up: box_rec.require_step_up,
+            version: box_rec.version,
+            guardian_count,
+            accepted_count,
+            pending_guardian_names,
         }
     }
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct DocumentUpdateResponse {
     pub documents: Vec<Document>,
-    #[serde(rename = "updatedAt")]
     pub updated_at: String,
 }
 
-#[derive(Serialize, Debug)]
+/// Result of PATCH /boxes/owned/:id/documents. Each document is applied or
+/// rejected on its own, so both lists can be non-empty.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct DocumentsBulkUpdateResponse {
+    /// Ids of the documents added or updated
+    pub applied: Vec<String>,
+    pub rejected: Vec<RejectedDocument>,
+}
+
+/// A document from a bulk update that wasn't applied
+#[derive(Serialize, Debug, ToSchema)]
+pub struct RejectedDocument {
+    pub id: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardiansReplaceResponse {
+    pub guardians: Vec<Guardian>,
+    pub updated_at: String,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct GuardianUpdateResponse {
     pub id: String,
     pub name: String,
     pub status: String,
-    #[serde(rename = "leadGuardian")]
     pub lead_guardian: bool,
-    #[serde(rename = "addedAt")]
     pub added_at: String,
-    #[serde(rename = "invitationId")]
     pub invitation_id: String,
-    #[serde(rename = "allGuardians")]
     pub all_guardians: Vec<Guardian>,
-    #[serde(rename = "updatedAt")]
     pub updated_at: String,
 }
 
@@ -179,74 +404,419 @@ mod optional_field_serde {
 }
 
 // Additional request/response types
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct LeadGuardianUpdateRequest {
-    pub message: String,
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Why the unlock is needed, up to 500 characters
+    #[serde(default)]
+    pub reason: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct GuardianResponseRequest {
+    /// true approves the unlock, false denies it
     pub approve: Option<bool>,
+    /// Older way to deny; same as `approve: false`
     pub reject: Option<bool>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
 pub struct GuardianInvitationResponse {
     pub accept: bool,
 }
 
+#[derive(Deserialize, Debug, ToSchema)]
+pub struct WebhookRegistrationRequest {
+    /// HTTPS URL to call when the box is unlocked
+    pub url: String,
+}
+
+/// A step-up challenge for a shard fetch. Echo `challenge` back in the
+/// `X-Shard-Challenge` header before `expiresAt`; it works once.
+#[derive(Serialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ShardChallengeResponse {
+    pub challenge: String,
+    pub expires_at: String,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WebhookRegistrationResponse {
+    pub webhook_url: String,
+    /// Key the payload signatures are made with; only returned here
+    pub webhook_secret: String,
+    pub updated_at: String,
+}
+
+// Sent by the invitation event consumer once an invitation has been redeemed
+#[derive(Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RedeemedInvitationRequest {
+    pub invitation_id: String,
+    /// Who created the invitation; it's only honoured if they own the box
+    pub creator_id: String,
+    #[serde(default)]
+    pub lead_guardian: bool,
+    /// Name to show the owner if the box has no placeholder for the invitation
+    #[serde(default)]
+    pub invited_name: Option<String>,
+}
+
+/// A guardian of one of the caller's locked boxes who hasn't accepted their
+/// shard yet
+#[derive(Serialize, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingGuardianResponse {
+    pub box_id: String,
+    pub box_name: String,
+    pub guardian_id: String,
+    pub guardian_name: String,
+    /// Whole hours since the box was locked
+    pub hours_since_lock: i64,
+}
+
+/// What happened when the owner reminded a box's pending guardians
+#[derive(Serialize, Debug, Default, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RemindAllResponse {
+    /// Reminders Expo accepted
+    pub sent: usize,
+    /// Guardians without a registered device
+    pub no_push_token: usize,
+    /// Guardians already reminded within the last hour
+    pub rate_limited: usize,
+    /// Guardians who turned shard reminders off
+    pub reminders_disabled: usize,
+    /// Reminders Expo refused
+    pub failed: usize,
+}
+
+/// Whether a box could be locked with a given threshold, as it stands now
+#[derive(Serialize, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LockPreviewResponse {
+    pub valid: bool,
+    /// Shards the lock would hand out, one per guardian
+    pub total_shards: usize,
+    pub threshold: usize,
+    /// What the threshold means, or why the box couldn't be locked with it
+    pub message: String,
+}
+
+// Query parameters for GET /boxes/owned/:id/lock-preview
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LockPreviewQuery {
+    /// How many guardians would be needed to recover the box
+    pub threshold: usize,
+}
+
+// Query parameters for GET /boxes/owned
+#[derive(Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(rename_all = "camelCase")]
+pub struct OwnedBoxesQuery {
+    /// Only boxes locked at or after this time
+    pub locked_after: Option<DateTime<Utc>>,
+    /// Only boxes locked before this time
+    pub locked_before: Option<DateTime<Utc>>,
+    /// Page size; results are only paginated when this is set, and can't be
+    /// combined with a lock date range
+    pub limit: Option<usize>,
+    /// `nextCursor` from the previous page
+    pub cursor: Option<String>,
+}
+
+// Query parameters for GET /boxes/owned/:id/watch
+#[derive(Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WatchBoxQuery {
+    /// Version the client last saw; the request returns once the box is newer
+    #[serde(default)]
+    pub since: u64,
+}
+
+// Query parameters for GET /boxes/guardian
+#[derive(Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GuardianBoxesQuery {
+    #[serde(default)]
+    pub status: GuardianBoxStatusFilter,
+    /// Page size; results are only paginated when this is set
+    pub limit: Option<usize>,
+    /// `nextCursor` from the previous page
+    pub cursor: Option<String>,
+}
+
+// Query parameters for POST /boxes/admin/guardian-index/backfill
+#[derive(Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GuardianIndexBackfillQuery {
+    /// `nextCursor` from the previous call; omit it to start from the beginning
+    pub cursor: Option<String>,
+}
+
+/// One page of the guardian-box lookup backfill
+#[derive(Serialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardianIndexBackfillResponse {
+    /// Boxes whose guardians were written to the lookup by this call
+    pub boxes_indexed: usize,
+    /// Cursor to pass to the next call, `null` once every box is covered
+    pub next_cursor: Option<String>,
+}
+
+/// Filters guardian boxes by the caller's own shard acceptance state.
+/// `pending` means the box is locked and the caller has not yet accepted
+/// their shard; `accepted` means the caller has accepted it.
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GuardianBoxStatusFilter {
+    Pending,
+    Accepted,
+    #[default]
+    All,
+}
+
 // Use shared ErrorResponse and MessageResponse from lockbox_shared
 
-// GuardianBox DTO to exclude version
-#[derive(Serialize, Debug)]
+/// A box as one of its guardians sees it. Unlike the owner's view it carries
+/// only the caller's own guardian entry, and the documents and unlock
+/// instructions are held back until an unlock request has been approved. No
+/// shard is ever included; GET /boxes/guardian/:id/shard serves it.
+#[derive(Serialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct GuardianBoxResponse {
     pub id: String,
     pub name: String,
     pub description: String,
-    #[serde(rename = "isLocked")]
     pub is_locked: bool,
-    #[serde(rename = "createdAt")]
     pub created_at: String,
-    #[serde(rename = "updatedAt")]
     pub updated_at: String,
-    #[serde(rename = "ownerId")]
     pub owner_id: String,
-    #[serde(rename = "ownerName")]
     pub owner_name: Option<String>,
-    #[serde(rename = "unlockInstructions")]
-    pub unlock_instructions: Option<String>,
-    #[serde(rename = "unlockRequest")]
     pub unlock_request: Option<UnlockRequest>,
-    #[serde(rename = "pendingGuardianApproval")]
     pub pending_guardian_approval: Option<bool>,
-    #[serde(rename = "guardiansCount")]
     pub guardians_count: usize,
-    #[serde(rename = "isLeadGuardian")]
     pub is_lead_guardian: bool,
-    pub documents: Vec<Document>,
-    pub guardians: Vec<Guardian>,
+    /// The caller's own guardian entry, without their shard
+    pub guardian: Guardian,
+    /// Present once the box has been unlocked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unlock_instructions: Option<String>,
+    /// Present once the box has been unlocked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documents: Option<Vec<Document>>,
+}
+
+impl GuardianBoxResponse {
+    /// Builds the view of a box served to one of its guardians. Returns `None`
+    /// if `guardian_id` is not an active guardian of the box.
+    pub fn for_guardian(
+        box_rec: &lockbox_shared::models::BoxRecord,
+        guardian_id: &str,
+    ) -> Option<Self> {
+        let mut guardian = box_rec
+            .guardians
+            .iter()
+            .find(|g| g.id == guardian_id && g.status != GuardianStatus::Rejected)?
+            .clone();
+        // Shards are only served by the shard fetch, which enforces the grace
+        // period and step-up challenge
+        guardian.encrypted_shard = None;
+        guardian.shard_hash = None;
+        guardian.shard_commit = None;
+        let unlocked = box_rec.unlock_request.as_ref().is_some_and(|unlock| {
+            matches!(
+                unlock.status,
+                UnlockRequestStatus::Approved | UnlockRequestStatus::Completed
+            )
+        });
+
+        Some(Self {
+            id: box_rec.id.clone(),
+            name: box_rec.name.clone(),
+            description: box_rec.description.clone(),
+            is_locked: box_rec.is_locked,
+            created_at: box_rec.created_at.clone(),
+            updated_at: box_rec.updated_at.clone(),
+            owner_id: box_rec.owner_id.clone(),
+            owner_name: box_rec.owner_name.clone(),
+            unlock_request: box_rec.unlock_request.clone(),
+            pending_guardian_approval: Some(matches!(
+                guardian.status,
+                GuardianStatus::Invited | GuardianStatus::Viewed
+            )),
+            guardians_count: box_rec.guardians.len(),
+            is_lead_guardian: guardian.lead_guardian,
+            guardian,
+            unlock_instructions: box_rec.unlock_instructions.clone().filter(|_| unlocked),
+            documents: unlocked.then(|| box_rec.documents.clone()),
+        })
+    }
+}
+
+/// One box on a guardian's home screen: where the box stands and where the
+/// caller's own shard stands
+#[derive(Serialize, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct GuardianBoxSummary {
+    pub box_id: String,
+    pub box_name: String,
+    pub is_locked: bool,
+    /// The caller has fetched their shard
+    pub my_shard_fetched: bool,
+    /// The caller has confirmed they stored their shard
+    pub my_shard_accepted: bool,
+    /// An unlock request is waiting on guardians' answers
+    pub unlock_request_active: bool,
+}
+
+impl GuardianBoxSummary {
+    /// The summary of a box for one of its guardians, or None if they aren't
+    /// one or rejected the guardianship
+    pub fn for_guardian(
+        box_rec: &lockbox_shared::models::BoxRecord,
+        guardian_id: &str,
+    ) -> Option<Self> {
+        let guardian = box_rec
+            .guardians
+            .iter()
+            .find(|g| g.id == guardian_id && g.status != GuardianStatus::Rejected)?;
+        Some(Self {
+            box_id: box_rec.id.clone(),
+            box_name: box_rec.name.clone(),
+            is_locked: box_rec.is_locked,
+            my_shard_fetched: guardian.shard_first_fetched_at.is_some()
+                || guardian.shard_fetched_at.is_some(),
+            my_shard_accepted: guardian.shard_accepted_at.is_some(),
+            unlock_request_active: box_rec
+                .unlock_request
+                .as_ref()
+                .is_some_and(|unlock| unlock.status == UnlockRequestStatus::Requested),
+        })
+    }
 }
 
-impl From<lockbox_shared::models::GuardianBox> for GuardianBoxResponse {
-    fn from(guard_box: lockbox_shared::models::GuardianBox) -> Self {
+/// A document as previewed by a guardian weighing an unlock request: what it
+/// is, never its content
+#[derive(Serialize, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentPreview {
+    pub id: String,
+    pub title: String,
+    pub content_type: Option<String>,
+    pub size_bytes: Option<u64>,
+}
+
+impl From<&Document> for DocumentPreview {
+    fn from(document: &Document) -> Self {
         Self {
-            id: guard_box.id,
-            name: guard_box.name,
-            description: guard_box.description,
-            is_locked: guard_box.is_locked,
-            created_at: guard_box.created_at,
-            updated_at: guard_box.updated_at,
-            owner_id: guard_box.owner_id,
-            owner_name: guard_box.owner_name,
-            unlock_instructions: guard_box.unlock_instructions,
-            unlock_request: guard_box.unlock_request,
-            pending_guardian_approval: guard_box.pending_guardian_approval,
-            guardians_count: guard_box.guardians_count,
-            is_lead_guardian: guard_box.is_lead_guardian,
-            documents: guard_box.documents,
-            guardians: guard_box.guardians,
+            id: document.id.clone(),
+            title: document.title.clone(),
+            content_type: document.content_type.clone(),
+            size_bytes: document.size_bytes,
         }
     }
 }
 
+/// What a box holds, for its guardians while an unlock request is open
+#[derive(Serialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BoxPreviewResponse {
+    pub box_id: String,
+    pub box_name: String,
+    pub unlock_request_id: String,
+    pub documents: Vec<DocumentPreview>,
+}
+
 // Use shared now_str()
+
+// Query parameters for POST /boxes/owned/:id/duplicate
+#[derive(Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateBoxQuery {
+    /// Copy the box's documents as well
+    #[serde(default)]
+    pub include_documents: bool,
+}
+
+// Query parameters for GET /boxes/owned/export
+#[derive(Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportBoxesQuery {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Format of an export: one JSON document, or CSV with a row per document
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+}
+
+/// Body of a JSON export of the caller's boxes
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct BoxExport {
+    pub boxes: Vec<ExportedBox>,
+}
+
+/// A box as exported. Guardians, shards and unlock state stay behind.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedBox {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub created_at: String,
+    pub updated_at: String,
+    #[serde(default)]
+    pub owner_name: Option<String>,
+    #[serde(default)]
+    pub unlock_instructions: Option<String>,
+    #[serde(default)]
+    pub is_locked: bool,
+    #[serde(default)]
+    pub locked_at: Option<String>,
+    #[serde(default)]
+    pub documents: Vec<ExportedDocument>,
+}
+
+/// A document as exported. Documents in locked boxes carry no content.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedDocument {
+    pub id: String,
+    pub title: String,
+    pub created_at: String,
+    #[serde(default)]
+    pub content_type: Option<String>,
+    #[serde(default)]
+    pub size_bytes: Option<u64>,
+    #[serde(default)]
+    pub encrypted_content: Option<String>,
+}
+
+/// Result of POST /boxes/owned/import. Each box is imported or rejected on
+/// its own, so both lists can be non-empty.
+#[derive(Serialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportBoxesResponse {
+    /// New box id for each imported box, keyed by its id in the export
+    pub box_ids: BTreeMap<String, String>,
+    pub failed: Vec<ImportFailure>,
+}
+
+/// A box from the export that wasn't imported
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ImportFailure {
+    /// The box's id in the export
+    pub id: String,
+    pub error: String,
+}
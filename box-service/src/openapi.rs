@@ -0,0 +1,343 @@
+//! OpenAPI description of the box service, served at `/openapi.json`.
+//!
+//! Handlers build most response bodies with `json!`, wrapping the DTOs in
+//! `models` in a small envelope object. The envelope types below describe
+//! those shapes for the spec; they aren't used at runtime.
+
+use axum::response::Html;
+use lockbox_shared::error::{ErrorBody, FieldError, ValidationErrorBody};
+use lockbox_shared::extract::Json;
+use lockbox_shared::models::{
+    Document, Guardian, GuardianStatus, MessageResponse, NotificationPrefs, QuietHours,
+    UnlockApproval, UnlockRequest, UnlockRequestStatus,
+};
+use lockbox_shared::push::{ExpoPushTicket, ExpoPushTicketDetails};
+use serde::Serialize;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+use crate::handlers::{box_handlers, guardian_handlers, user_handlers};
+use crate::models::{
+    BoxExport, BoxPreviewResponse, BoxResponse, CreateBoxRequest, DocumentPreview,
+    DocumentUpdateRequest, DocumentUpdateResponse, DocumentsBulkUpdateRequest,
+    DocumentsBulkUpdateResponse, ExportFormat, ExportedBox, ExportedDocument, GuardianBoxResponse,
+    GuardianBoxStatusFilter, GuardianBoxSummary, GuardianIndexBackfillResponse,
+    GuardianInvitationResponse, GuardianResponseRequest, GuardianUpdateRequest,
+    GuardianUpdateResponse, GuardiansReplaceRequest, GuardiansReplaceResponse, ImportBoxesResponse,
+    ImportFailure, IncomingShard, LeadGuardianUpdateRequest, LockBoxRequest, LockPreviewResponse,
+    PendingGuardianResponse, RedeemedInvitationRequest, RejectedDocument, RemindAllResponse,
+    ShardChallengeResponse, UnlockInstructionsUpdateRequest, UpdateBoxRequest,
+    WebhookRegistrationRequest, WebhookRegistrationResponse,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Lockbox Box Service",
+        description = "Boxes, their guardians and documents, and unlock requests"
+    ),
+    paths(
+        box_handlers::get_boxes,
+        box_handlers::export_boxes,
+        box_handlers::get_pending_guardians,
+        box_handlers::import_boxes,
+        box_handlers::create_box,
+        box_handlers::get_box,
+        box_handlers::watch_box,
+        box_handlers::duplicate_box,
+        box_handlers::update_box,
+        box_handlers::update_unlock_instructions,
+        box_handlers::delete_box,
+        box_handlers::lock_box,
+        box_handlers::replay_box_locked,
+        box_handlers::backfill_guardian_index,
+        box_handlers::preview_lock,
+        box_handlers::remind_pending_guardians,
+        box_handlers::cancel_unlock_request,
+        box_handlers::update_guardian,
+        box_handlers::replace_guardians,
+        box_handlers::delete_guardian,
+        box_handlers::register_webhook,
+        box_handlers::update_document,
+        box_handlers::bulk_update_documents,
+        box_handlers::get_document,
+        box_handlers::delete_document,
+        box_handlers::accept_redeemed_invitation,
+        box_handlers::create_shard_challenge,
+        box_handlers::fetch_guardian_shard,
+        box_handlers::acknowledge_guardian_shard,
+        box_handlers::accept_guardian_shard,
+        guardian_handlers::get_guardian_boxes,
+        guardian_handlers::get_guardian_summary,
+        guardian_handlers::get_guardian_box,
+        guardian_handlers::preview_guardian_box,
+        guardian_handlers::get_guardian_document,
+        guardian_handlers::request_unlock,
+        guardian_handlers::respond_to_unlock_request,
+        guardian_handlers::respond_to_invitation,
+        user_handlers::register_push_token,
+        user_handlers::send_test_notification,
+        user_handlers::update_notification_prefs,
+        user_handlers::get_user_stats,
+    ),
+    components(schemas(
+        BoxResponse,
+        BoxExport,
+        ExportedBox,
+        ExportedDocument,
+        ExportFormat,
+        ImportBoxesResponse,
+        ImportFailure,
+        PendingGuardianResponse,
+        PendingGuardiansEnvelope,
+        RemindAllResponse,
+        CreateBoxRequest,
+        UpdateBoxRequest,
+        UnlockInstructionsUpdateRequest,
+        LockBoxRequest,
+        LockPreviewResponse,
+        IncomingShard,
+        GuardianUpdateRequest,
+        GuardianUpdateResponse,
+        GuardiansReplaceRequest,
+        GuardiansReplaceResponse,
+        DocumentUpdateRequest,
+        DocumentUpdateResponse,
+        DocumentsBulkUpdateRequest,
+        DocumentsBulkUpdateResponse,
+        RejectedDocument,
+        WebhookRegistrationRequest,
+        WebhookRegistrationResponse,
+        RedeemedInvitationRequest,
+        GuardianBoxResponse,
+        GuardianBoxSummary,
+        BoxPreviewResponse,
+        DocumentPreview,
+        GuardianBoxStatusFilter,
+        LeadGuardianUpdateRequest,
+        GuardianResponseRequest,
+        GuardianInvitationResponse,
+        user_handlers::RegisterPushTokenRequest,
+        user_handlers::RegisterPushTokenResponse,
+        user_handlers::TestNotificationResponse,
+        user_handlers::NotificationPrefsResponse,
+        user_handlers::UserStatsResponse,
+        NotificationPrefs,
+        QuietHours,
+        ExpoPushTicket,
+        ExpoPushTicketDetails,
+        Document,
+        Guardian,
+        GuardianStatus,
+        UnlockRequest,
+        UnlockRequestStatus,
+        UnlockApproval,
+        MessageResponse,
+        ErrorBody,
+        ValidationErrorBody,
+        FieldError,
+        BoxEnvelope,
+        BoxListEnvelope,
+        BoxMessageEnvelope,
+        GuardianEnvelope,
+        GuardianMessageEnvelope,
+        AcceptedGuardianEnvelope,
+        DocumentEnvelope,
+        DocumentMessageEnvelope,
+        GuardianBoxEnvelope,
+        GuardianBoxPage,
+        InvitationResponseEnvelope,
+        ShardResponse,
+        ShardChallengeResponse,
+        ShardAckResponse,
+        ShardAcceptResponse,
+        ReplayedEventEnvelope,
+        GuardianIndexBackfillResponse,
+    )),
+    modifiers(&BearerAuth),
+    security(("bearer" = [])),
+    tags(
+        (name = "owner", description = "Endpoints for box owners"),
+        (name = "guardian", description = "Endpoints for a box's guardians"),
+        (name = "internal", description = "Service-to-service endpoints"),
+        (name = "admin", description = "Operator endpoints, for the admin group or scope"),
+        (name = "users", description = "User settings")
+    )
+)]
+pub struct ApiDoc;
+
+// Adds the Cognito (or service) bearer token every endpoint expects
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+pub use envelopes::*;
+
+// Never constructed: these only describe bodies the handlers build with json!
+#[allow(dead_code)]
+mod envelopes {
+    use super::*;
+
+    /// A box, as seen by its owner
+    #[derive(Serialize, ToSchema)]
+    pub struct BoxEnvelope {
+        #[serde(rename = "box")]
+        pub box_: BoxResponse,
+    }
+
+    /// The caller's own boxes. `nextCursor` is only present when a `limit`
+    /// was given, and is null on the last page.
+    #[derive(Serialize, ToSchema)]
+    #[serde(rename_all = "camelCase")]
+    pub struct BoxListEnvelope {
+        pub boxes: Vec<BoxResponse>,
+        pub next_cursor: Option<String>,
+    }
+
+    #[derive(Serialize, ToSchema)]
+    pub struct BoxMessageEnvelope {
+        pub message: String,
+        #[serde(rename = "box")]
+        pub box_: BoxResponse,
+    }
+
+    /// Guardians of the caller's locked boxes still to accept their shard
+    #[derive(Serialize, ToSchema)]
+    pub struct PendingGuardiansEnvelope {
+        pub guardians: Vec<PendingGuardianResponse>,
+    }
+
+    #[derive(Serialize, ToSchema)]
+    pub struct GuardianEnvelope {
+        pub guardian: GuardianUpdateResponse,
+    }
+
+    #[derive(Serialize, ToSchema)]
+    pub struct GuardianMessageEnvelope {
+        pub message: String,
+        pub guardian: GuardianUpdateResponse,
+    }
+
+    /// The guardian a redeemed invitation was turned into
+    #[derive(Serialize, ToSchema)]
+    pub struct AcceptedGuardianEnvelope {
+        pub guardian: Guardian,
+    }
+
+    #[derive(Serialize, ToSchema)]
+    pub struct DocumentEnvelope {
+        pub document: DocumentUpdateResponse,
+    }
+
+    #[derive(Serialize, ToSchema)]
+    pub struct DocumentMessageEnvelope {
+        pub message: String,
+        pub document: DocumentUpdateResponse,
+    }
+
+    /// A box, as seen by one of its guardians
+    #[derive(Serialize, ToSchema)]
+    pub struct GuardianBoxEnvelope {
+        #[serde(rename = "box")]
+        pub box_: GuardianBoxResponse,
+    }
+
+    /// Boxes the caller guards. `nextCursor` is only present when a `limit` was
+    /// given, and is null on the last page.
+    #[derive(Serialize, ToSchema)]
+    #[serde(rename_all = "camelCase")]
+    pub struct GuardianBoxPage {
+        pub boxes: Vec<GuardianBoxResponse>,
+        pub next_cursor: Option<String>,
+    }
+
+    /// Accepting an invitation returns the box; declining returns the guardian
+    #[derive(Serialize, ToSchema)]
+    pub struct InvitationResponseEnvelope {
+        pub message: String,
+        #[serde(rename = "box")]
+        pub box_: Option<GuardianBoxResponse>,
+        pub guardian: Option<Guardian>,
+    }
+
+    /// The caller's encrypted shard
+    #[derive(Serialize, ToSchema)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ShardResponse {
+        pub encrypted_shard: String,
+        pub shard_hash: Option<String>,
+        pub shard_fetched_at: Option<String>,
+        pub shard_first_fetched_at: String,
+        pub shard_threshold: u32,
+        pub total_shards: usize,
+    }
+
+    #[derive(Serialize, ToSchema)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ShardAckResponse {
+        pub shard_fetched_at: Option<String>,
+        pub total_shards: usize,
+        pub shards_fetched: usize,
+        pub shards_remaining: usize,
+        /// Whether every guardian has fetched their shard
+        pub all_fetched: bool,
+    }
+
+    #[derive(Serialize, ToSchema)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ShardAcceptResponse {
+        pub message: String,
+        pub shard_accepted_at: Option<String>,
+        pub box_id: String,
+        pub box_name: String,
+    }
+
+    /// A box_locked event published again, in its snake_case wire format
+    #[derive(Serialize, ToSchema)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ReplayedEventEnvelope {
+        pub box_id: String,
+        #[schema(value_type = Object)]
+        pub event: serde_json::Value,
+    }
+}
+
+// GET /openapi.json
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+// GET /docs - Swagger UI over /openapi.json, only routed when
+// ENABLE_DEBUG_ROUTES=true
+pub async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI_PAGE)
+}
+
+const SWAGGER_UI_PAGE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+  <title>Lockbox Box Service</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    SwaggerUIBundle({ url: "openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
@@ -2,28 +2,56 @@ use axum::{
     extract::Request,
     middleware,
     routing::{get, patch, post, put},
-    Router,
+    Extension, Router,
 };
-use log::{info, warn};
+use lockbox_shared::paging::X_TOTAL_COUNT;
+use log::{error, info, warn};
 use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 
 use crate::handlers::{
     box_handlers::{
-        accept_guardian_shard, acknowledge_guardian_shard, create_box, delete_box, delete_document,
-        delete_guardian, fetch_guardian_shard, get_box, get_boxes, lock_box, update_box,
-        update_document, update_guardian,
+        accept_guardian_shard, accept_redeemed_invitation, acknowledge_guardian_shard,
+        backfill_guardian_index, bulk_update_documents, cancel_unlock_request, create_box,
+        create_shard_challenge, delete_box, delete_document, delete_guardian, duplicate_box,
+        export_boxes, fetch_guardian_shard, get_box, get_boxes, get_document,
+        get_pending_guardians, import_boxes, lock_box, preview_lock, register_webhook,
+        remind_pending_guardians, replace_guardians, replay_box_locked, update_box,
+        update_document, update_guardian, update_unlock_instructions, watch_box, MaxGuardians,
+        ShardFetchGracePeriod, UnacceptedGuardianPolicy,
     },
     guardian_handlers::{
-        get_guardian_box, get_guardian_boxes, request_unlock, respond_to_invitation,
-        respond_to_unlock_request,
+        get_guardian_box, get_guardian_boxes, get_guardian_document, get_guardian_summary,
+        preview_guardian_box, request_unlock, respond_to_invitation, respond_to_unlock_request,
     },
-    user_handlers::register_push_token,
+    user_handlers::{
+        get_user_stats, register_push_token, send_test_notification, update_notification_prefs,
+    },
+};
+use lockbox_shared::maintenance::{read_only_middleware, refuse_when_read_only, ReadOnlyMode};
+use lockbox_shared::push::{ExpoPushSender, SharedPushSender};
+use lockbox_shared::storage::{S3DocumentStorage, SharedDocumentStorage};
+use lockbox_shared::store::{
+    dynamo::{
+        DynamoBoxStore, DynamoIdempotencyStore, DynamoInvitationStore, DynamoPushTokenStore,
+        DynamoShardChallengeStore,
+    },
+    BoxStore, SharedIdempotencyStore, SharedInvitationStore, SharedPushTokenStore,
+    SharedShardChallengeStore,
 };
-use lockbox_shared::store::{dynamo::DynamoBoxStore, BoxStore};
+
+use crate::openapi::{openapi_json, swagger_ui};
 
 // Import shared auth middleware
 use lockbox_shared::auth::auth_middleware;
+use lockbox_shared::request_id::{RequestId, REQUEST_ID_HEADER};
+
+// Extra routes for local development, enabled with ENABLE_DEBUG_ROUTES=true
+fn debug_routes_enabled() -> bool {
+    std::env::var("ENABLE_DEBUG_ROUTES")
+        .map(|v| v.to_lowercase() == "true")
+        .unwrap_or(false)
+}
 
 /// Creates a router with the default store
 pub async fn create_router() -> Router {
@@ -41,11 +69,51 @@ pub async fn create_router() -> Router {
     let prefix = if remove_base_path { "" } else { "/Prod" };
     info!("Using API route prefix: {}", prefix);
 
-    create_router_with_store(dynamo_store, prefix)
+    // Offload oversized documents to S3 when a bucket is configured
+    let document_storage = S3DocumentStorage::from_env()
+        .await
+        .map(|storage| Arc::new(storage) as SharedDocumentStorage);
+
+    let push_tokens = Arc::new(DynamoPushTokenStore::new().await) as SharedPushTokenStore;
+    let push_sender = Arc::new(ExpoPushSender::from_env()) as SharedPushSender;
+    let invitations = Arc::new(DynamoInvitationStore::new().await) as SharedInvitationStore;
+    let shard_challenges =
+        Arc::new(DynamoShardChallengeStore::new().await) as SharedShardChallengeStore;
+
+    let router = create_router_with_store_and_storage(dynamo_store, document_storage, prefix)
+        .layer(Extension(push_tokens))
+        .layer(Extension(push_sender))
+        .layer(Extension(invitations))
+        .layer(Extension(shard_challenges))
+        .layer(Extension(ReadOnlyMode::from_env()))
+        .layer(Extension(UnacceptedGuardianPolicy::from_env()))
+        .layer(Extension(MaxGuardians::from_env()))
+        .layer(Extension(ShardFetchGracePeriod::from_env()));
+
+    // Honor Idempotency-Key headers when a key table is configured
+    match DynamoIdempotencyStore::from_env().await {
+        Some(keys) => router.layer(Extension(Arc::new(keys) as SharedIdempotencyStore)),
+        None => router,
+    }
 }
 
-/// Creates a router with a given store implementation
+/// Creates a router with a given store implementation, keeping all document
+/// content inline
+#[cfg(test)]
 pub fn create_router_with_store<S>(store: Arc<S>, prefix: &str) -> Router
+where
+    S: BoxStore + 'static,
+{
+    create_router_with_store_and_storage(store, None, prefix)
+}
+
+/// Creates a router with a given store and optional storage for oversized
+/// documents
+pub fn create_router_with_store_and_storage<S>(
+    store: Arc<S>,
+    document_storage: Option<SharedDocumentStorage>,
+    prefix: &str,
+) -> Router
 where
     S: BoxStore + 'static,
 {
@@ -55,44 +123,111 @@ where
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
-        .allow_headers(Any);
+        .allow_headers(Any)
+        // So browser clients can read the pagination headers
+        .expose_headers([axum::http::header::LINK, X_TOTAL_COUNT]);
 
     info!("CORS configured for all origins, methods and headers");
 
-    // Logging middleware to trace all requests
+    // Logging middleware to trace all requests, tagging each with a request ID
     async fn logging_middleware(
-        req: Request,
+        mut req: Request,
         next: axum::middleware::Next,
     ) -> impl axum::response::IntoResponse {
+        let request_id = RequestId::assign(&mut req);
         info!(
-            "Router received request: method={}, uri={}",
+            "Router received request: request_id={}, method={}, uri={}",
+            request_id,
             req.method(),
             req.uri()
         );
-        next.run(req).await
+
+        let mut response = next.run(req).await;
+        if response.status().is_server_error() {
+            error!(
+                "Request failed: request_id={}, status={}",
+                request_id,
+                response.status()
+            );
+        } else {
+            info!(
+                "Responding: request_id={}, status={}",
+                request_id,
+                response.status()
+            );
+        }
+
+        if let Some(value) = request_id.header_value() {
+            response.headers_mut().insert(REQUEST_ID_HEADER, value);
+        }
+        response
     }
 
     // Create the box API routes (require store state)
     let box_routes = Router::new()
         .route("/boxes/owned", get(get_boxes).post(create_box))
+        .route("/boxes/owned/export", get(export_boxes))
+        .route("/boxes/owned/import", post(import_boxes))
+        .route("/boxes/owned/pending-guardians", get(get_pending_guardians))
         .route(
             "/boxes/owned/:id",
             get(get_box).patch(update_box).delete(delete_box),
         )
+        .route("/boxes/owned/:id/watch", get(watch_box))
+        .route("/boxes/owned/:id/duplicate", post(duplicate_box))
         .route("/boxes/owned/:id/lock", post(lock_box))
+        .route("/boxes/owned/:id/lock-preview", get(preview_lock))
+        .route(
+            "/boxes/owned/:id/unlock-instructions",
+            patch(update_unlock_instructions),
+        )
+        .route(
+            "/boxes/owned/:id/remind-all",
+            post(remind_pending_guardians),
+        )
+        .route(
+            "/boxes/owned/:id/unlock-request",
+            axum::routing::delete(cancel_unlock_request),
+        )
         .route("/boxes/owned/:id/guardian", patch(update_guardian))
+        .route("/boxes/owned/:id/guardians", put(replace_guardians))
         .route(
             "/boxes/owned/:id/guardian/:guardian_id",
             axum::routing::delete(delete_guardian),
         )
+        .route("/boxes/owned/:id/webhook", post(register_webhook))
         .route("/boxes/owned/:id/document", patch(update_document))
+        .route("/boxes/owned/:id/documents", patch(bulk_update_documents))
         .route(
             "/boxes/owned/:id/document/:document_id",
-            axum::routing::delete(delete_document),
+            get(get_document).delete(delete_document),
+        )
+        .route("/boxes/admin/:id/replay-locked", post(replay_box_locked))
+        .route(
+            "/boxes/admin/guardian-index/backfill",
+            post(backfill_guardian_index),
+        )
+        .route(
+            "/boxes/internal/:id/guardians/accept",
+            post(accept_redeemed_invitation),
         )
         .route("/boxes/guardian", get(get_guardian_boxes))
+        .route("/boxes/guardian/summary", get(get_guardian_summary))
         .route("/boxes/guardian/:id", get(get_guardian_box))
-        .route("/boxes/guardian/:id/shard", get(fetch_guardian_shard))
+        .route("/boxes/guardian/:id/preview", get(preview_guardian_box))
+        .route(
+            "/boxes/guardian/:id/document/:document_id",
+            get(get_guardian_document),
+        )
+        // Records the first fetch, so it's a write despite being a GET
+        .route(
+            "/boxes/guardian/:id/shard",
+            get(fetch_guardian_shard).route_layer(middleware::from_fn(refuse_when_read_only)),
+        )
+        .route(
+            "/boxes/guardian/:id/shard/challenge",
+            post(create_shard_challenge),
+        )
         .route(
             "/boxes/guardian/:id/shard/ack",
             patch(acknowledge_guardian_shard),
@@ -110,16 +245,31 @@ where
             "/boxes/guardian/:id/invitation",
             patch(respond_to_invitation),
         )
+        // Lives with the box routes as it reads the caller's boxes
+        .route("/users/me/stats", get(get_user_stats))
         .layer(middleware::from_fn(auth_middleware))
+        // Only reads get through while READ_ONLY is set
+        .layer(middleware::from_fn(read_only_middleware))
+        .layer(Extension(document_storage))
         .with_state(store);
 
-    // Create the user API routes (no store state needed)
+    // Create the user API routes (no box store state needed; the push token
+    // store and sender come from extensions added by the caller)
     let user_routes = Router::new()
         .route("/users/push-token", put(register_push_token))
-        .layer(middleware::from_fn(auth_middleware));
+        .route("/users/push-token/test", post(send_test_notification))
+        .route("/users/notification-prefs", put(update_notification_prefs))
+        .layer(middleware::from_fn(auth_middleware))
+        .layer(middleware::from_fn(read_only_middleware));
+
+    // The API description is public, so clients can generate types from it
+    let mut doc_routes = Router::new().route("/openapi.json", get(openapi_json));
+    if debug_routes_enabled() {
+        doc_routes = doc_routes.route("/docs", get(swagger_ui));
+    }
 
     // Merge all API routes
-    let api_routes = box_routes.merge(user_routes);
+    let api_routes = box_routes.merge(user_routes).merge(doc_routes);
 
     // Create the main router
     let router = if prefix.is_empty() {
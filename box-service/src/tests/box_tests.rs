@@ -1,25 +1,49 @@
 use axum::{
     body::Body,
-    http::{Request, StatusCode},
-    Router,
+    http::{HeaderValue, Request, StatusCode},
+    Extension, Router,
 };
-use lockbox_shared::auth::create_test_request;
+use lockbox_shared::auth::{create_admin_test_request, create_test_request};
+use lockbox_shared::clock::SharedClock;
+use lockbox_shared::error::StoreError;
+use lockbox_shared::maintenance::{ReadOnlyMode, MAINTENANCE_CODE};
+use lockbox_shared::push::SharedPushSender;
+use lockbox_shared::request_id::RequestId;
 use lockbox_shared::store::dynamo::DynamoBoxStore;
-use lockbox_shared::store::BoxStore;
+use lockbox_shared::store::{
+    BoxStore, InvitationStore, PushTokenStore, SharedIdempotencyStore, SharedInvitationStore,
+    SharedPushTokenStore, SharedShardChallengeStore,
+};
 use lockbox_shared::test_utils::dynamo_test_utils::{
     clear_dynamo_table, create_box_table, create_dynamo_client, use_dynamodb,
 };
 use lockbox_shared::test_utils::http_test_utils::response_to_json;
 use lockbox_shared::test_utils::mock_box_store::MockBoxStore;
+use lockbox_shared::test_utils::mock_clock::MockClock;
+use lockbox_shared::test_utils::mock_document_storage::MockDocumentStorage;
+use lockbox_shared::test_utils::mock_idempotency_store::MockIdempotencyStore;
+use lockbox_shared::test_utils::mock_invitation_store::MockInvitationStore;
+use lockbox_shared::test_utils::mock_push_sender::MockPushSender;
+use lockbox_shared::test_utils::mock_push_token_store::MockPushTokenStore;
+use lockbox_shared::test_utils::mock_shard_challenge_store::MockShardChallengeStore;
+use lockbox_shared::validation::{MAX_NAME_CHARS, MAX_UNLOCK_INSTRUCTIONS_CHARS};
 use log::{debug, info, trace};
 use serde_json::json;
 use std::sync::Arc;
 use tower::ServiceExt;
 
-use crate::handlers::box_handlers::{acknowledge_guardian_shard, fetch_guardian_shard, lock_box};
-use crate::models::LockBoxRequest;
+use crate::handlers::box_handlers::{
+    acknowledge_guardian_shard, box_locked_event_payload, fetch_guardian_shard, lock_box,
+    test_published_events, wait_for_box_change, MaxGuardians, ShardFetchGracePeriod,
+    UnacceptedGuardianPolicy,
+};
+use crate::models::{BoxExport, LockBoxRequest, MAX_SHARD_CHARS};
 use crate::routes;
-use lockbox_shared::models::{now_str, BoxRecord, Guardian, GuardianStatus};
+use lockbox_shared::models::events::BoxLockedEvent;
+use lockbox_shared::models::{
+    now_str, BoxRecord, Document, Guardian, GuardianStatus, PushToken, UnlockRequest,
+    UnlockRequestStatus,
+};
 
 // Constants for DynamoDB tests
 const TEST_TABLE_NAME: &str = "box-test-table";
@@ -102,6 +126,17 @@ async fn add_test_data_to_store(store: &TestStore) {
     }
 }
 
+// Base64 shard payload for a guardian, as a client would submit it
+fn test_shard(guardian_id: &str) -> String {
+    use base64::Engine as _;
+    base64::engine::general_purpose::STANDARD.encode(format!("shard-{}", guardian_id))
+}
+
+// Hash of `test_shard` under the scheme lock_box verifies
+fn test_shard_hash(guardian_id: &str) -> String {
+    Guardian::expected_shard_hash(&test_shard(guardian_id)).unwrap()
+}
+
 // Helper function to create test box data
 fn create_test_boxes(now: &str) -> Vec<BoxRecord> {
     let mut boxes = Vec::new();
@@ -125,6 +160,11 @@ fn create_test_boxes(now: &str) -> Vec<BoxRecord> {
         shards_fetched: None,
         total_shards: None,
         shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
     };
 
     let box_2 = BoxRecord {
@@ -146,6 +186,11 @@ fn create_test_boxes(now: &str) -> Vec<BoxRecord> {
         shards_fetched: None,
         total_shards: None,
         shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
     };
 
     boxes.push(box_1);
@@ -174,6 +219,9 @@ async fn test_lock_and_acknowledge_shards_with_mock_store() {
         shard_hash: None,
         shard_fetched_at: None,
         shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
     };
     let g2 = Guardian {
         id: "g2".into(),
@@ -187,6 +235,9 @@ async fn test_lock_and_acknowledge_shards_with_mock_store() {
         shard_hash: None,
         shard_fetched_at: None,
         shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
     };
 
     let box_record = BoxRecord {
@@ -208,6 +259,11 @@ async fn test_lock_and_acknowledge_shards_with_mock_store() {
         shards_fetched: None,
         total_shards: None,
         shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
     };
 
     store.create_box(box_record).await.unwrap();
@@ -218,13 +274,13 @@ async fn test_lock_and_acknowledge_shards_with_mock_store() {
         shards: vec![
             crate::models::IncomingShard {
                 guardian_id: g1.id.clone(),
-                shard: "shard-g1".into(),
-                shard_hash: "hash-g1".into(),
+                shard: test_shard("g1"),
+                shard_hash: test_shard_hash("g1"),
             },
             crate::models::IncomingShard {
                 guardian_id: g2.id.clone(),
-                shard: "shard-g2".into(),
-                shard_hash: "hash-g2".into(),
+                shard: test_shard("g2"),
+                shard_hash: test_shard_hash("g2"),
             },
         ],
     };
@@ -233,7 +289,10 @@ async fn test_lock_and_acknowledge_shards_with_mock_store() {
         axum::extract::State(store.clone()),
         axum::extract::Path(box_id.to_string()),
         axum::Extension(owner_id.to_string()),
-        axum::Json(lock_payload),
+        None,
+        SharedClock::default(),
+        RequestId::generate(),
+        lockbox_shared::extract::Json(lock_payload),
     )
     .await
     .expect("lock should succeed");
@@ -248,22 +307,61 @@ async fn test_lock_and_acknowledge_shards_with_mock_store() {
         axum::extract::State(store.clone()),
         axum::extract::Path(box_id.to_string()),
         axum::Extension(g1.id.clone()),
+        None,
+        None,
+        SharedClock::default(),
+        axum::http::HeaderMap::new(),
     )
     .await
     .expect("fetch shard succeeds");
     let shard_json = shard_resp.0;
-    assert_eq!(shard_json["encryptedShard"], "shard-g1");
+    assert_eq!(shard_json["encryptedShard"], test_shard("g1"));
+    let first_fetched_at = shard_json["shardFirstFetchedAt"]
+        .as_str()
+        .expect("first fetch is timestamped")
+        .to_string();
+    assert!(shard_json["shardFetchedAt"].is_null());
+
+    // Re-fetching keeps the original first-fetch time
+    tokio::time::sleep(tokio::time::Duration::from_millis(5)).await;
+    let refetch_json = fetch_guardian_shard(
+        axum::extract::State(store.clone()),
+        axum::extract::Path(box_id.to_string()),
+        axum::Extension(g1.id.clone()),
+        None,
+        None,
+        SharedClock::default(),
+        axum::http::HeaderMap::new(),
+    )
+    .await
+    .expect("re-fetch shard succeeds")
+    .0;
+    assert_eq!(refetch_json["shardFirstFetchedAt"], first_fetched_at);
+    let stored_g1 = store
+        .get_box(box_id)
+        .await
+        .unwrap()
+        .guardians
+        .into_iter()
+        .find(|g| g.id == g1.id)
+        .unwrap();
+    assert_eq!(stored_g1.shard_first_fetched_at, Some(first_fetched_at));
+    assert!(stored_g1.shard_fetched_at.is_none());
 
     // Ack shard, should delete server copy for g1
     let ack_resp = acknowledge_guardian_shard(
         axum::extract::State(store.clone()),
         axum::extract::Path(box_id.to_string()),
         axum::Extension(g1.id.clone()),
+        SharedClock::default(),
     )
     .await
     .expect("ack succeed");
     let ack_json = ack_resp.0;
     assert!(ack_json["shardFetchedAt"].is_string());
+    assert_eq!(ack_json["shardsFetched"], 1);
+    assert_eq!(ack_json["shardsRemaining"], 1);
+    assert_eq!(ack_json["allFetched"], false);
 
     let after_ack = store.get_box(box_id).await.unwrap();
     assert_eq!(after_ack.shards_fetched, Some(1));
@@ -272,88 +370,2737 @@ async fn test_lock_and_acknowledge_shards_with_mock_store() {
     assert!(g1_after.shard_fetched_at.is_some());
 
     // Second guardian ack triggers cleanup marker
-    let _ = acknowledge_guardian_shard(
+    let ack_json = acknowledge_guardian_shard(
         axum::extract::State(store.clone()),
         axum::extract::Path(box_id.to_string()),
         axum::Extension(g2.id.clone()),
+        SharedClock::default(),
     )
     .await
-    .expect("ack succeed");
+    .expect("ack succeed")
+    .0;
+    assert_eq!(ack_json["shardsRemaining"], 0);
+    assert_eq!(ack_json["allFetched"], true);
 
     let after_all = store.get_box(box_id).await.unwrap();
     assert_eq!(after_all.shards_fetched, Some(2));
     assert!(after_all.shards_deleted_at.is_some());
+
+    let owner_view = crate::models::BoxResponse::from(after_all);
+    assert_eq!(owner_view.shards_remaining, Some(0));
+    assert_eq!(owner_view.all_fetched, Some(true));
 }
 
-async fn upsert_guardians(store: &TestStore, box_id: &str, guardians: Vec<Guardian>) {
-    let mut box_record = match store {
-        TestStore::Mock(mock) => mock.get_box(box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(box_id).await.unwrap(),
+#[tokio::test]
+async fn test_last_ack_clears_lingering_shards() {
+    let store = Arc::new(MockBoxStore::new());
+    let now = now_str();
+    let box_id = "lingering_shards";
+
+    let guardian = |id: &str| Guardian {
+        id: id.into(),
+        name: format!("Guardian {}", id),
+        lead_guardian: false,
+        status: GuardianStatus::Accepted,
+        added_at: now.clone(),
+        invitation_id: format!("inv-{}", id),
+        lock_data_received_at: None,
+        encrypted_shard: Some(test_shard(id)),
+        shard_hash: Some(test_shard_hash(id)),
+        shard_fetched_at: None,
+        shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
     };
+    // g1 is recorded as fetched, but its shard was never removed
+    let mut g1 = guardian("g1");
+    g1.shard_fetched_at = Some(now.clone());
+    let g2 = guardian("g2");
+
+    store
+        .create_box(BoxRecord {
+            id: box_id.into(),
+            name: "Lingering Shards".into(),
+            description: "A shard survived its ack".into(),
+            is_locked: true,
+            locked_at: Some(now.clone()),
+            created_at: now.clone(),
+            updated_at: now.clone(),
+            owner_id: "owner_lingering".into(),
+            owner_name: None,
+            documents: vec![],
+            guardians: vec![g1, g2],
+            unlock_instructions: None,
+            unlock_request: None,
+            version: 0,
+            shard_threshold: Some(2),
+            shards_fetched: Some(1),
+            total_shards: Some(2),
+            shards_deleted_at: None,
+            all_shards_accepted: false,
+            last_unlock_request_at: None,
+            webhook_url: None,
+            webhook_secret: None,
+            require_step_up: false,
+        })
+        .await
+        .unwrap();
 
-    box_record.guardians = guardians;
-    box_record.updated_at = now_str();
+    let ack_json = acknowledge_guardian_shard(
+        axum::extract::State(store.clone()),
+        axum::extract::Path(box_id.to_string()),
+        axum::Extension("g2".to_string()),
+        SharedClock::default(),
+    )
+    .await
+    .expect("ack succeed")
+    .0;
+    assert_eq!(ack_json["allFetched"], true);
 
-    match store {
-        TestStore::Mock(mock) => {
-            mock.update_box(box_record).await.unwrap();
-        }
-        TestStore::DynamoDB(dynamo) => {
-            let _ = dynamo.update_box(box_record).await.unwrap();
-            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+    let stored = store.get_box(box_id).await.unwrap();
+    assert!(stored.shards_deleted_at.is_some());
+    for guardian in &stored.guardians {
+        assert!(
+            guardian.encrypted_shard.is_none(),
+            "{} kept its shard",
+            guardian.id
+        );
+        assert!(
+            guardian.shard_hash.is_none(),
+            "{} kept its hash",
+            guardian.id
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_shard_fetch_waits_for_grace_period() {
+    let store = Arc::new(MockBoxStore::new());
+    let clock = MockClock::at_str("2030-01-01T00:05:00Z");
+    let app = routes::create_router_with_store(store.clone(), "")
+        .layer(Extension(ShardFetchGracePeriod(
+            std::time::Duration::from_secs(600),
+        )))
+        .layer(Extension(SharedClock::new(clock.clone())));
+    let box_id = "grace_box";
+
+    store
+        .create_box(BoxRecord {
+            id: box_id.into(),
+            name: "Grace Box".into(),
+            description: "Recently locked".into(),
+            is_locked: true,
+            locked_at: Some("2030-01-01T00:00:00+00:00".into()),
+            created_at: "2030-01-01T00:00:00+00:00".into(),
+            updated_at: "2030-01-01T00:00:00+00:00".into(),
+            owner_id: "owner_grace".into(),
+            owner_name: None,
+            documents: vec![],
+            guardians: vec![Guardian {
+                id: "g1".into(),
+                name: "G One".into(),
+                lead_guardian: false,
+                status: GuardianStatus::Accepted,
+                added_at: "2030-01-01T00:00:00+00:00".into(),
+                invitation_id: "inv1".into(),
+                lock_data_received_at: Some("2030-01-01T00:00:00+00:00".into()),
+                encrypted_shard: Some(test_shard("g1")),
+                shard_hash: Some(test_shard_hash("g1")),
+                shard_fetched_at: None,
+                shard_accepted_at: None,
+                shard_commit: None,
+                shard_first_fetched_at: None,
+                last_reminded_at: None,
+            }],
+            unlock_instructions: None,
+            unlock_request: None,
+            version: 0,
+            shard_threshold: Some(1),
+            shards_fetched: None,
+            total_shards: Some(1),
+            shards_deleted_at: None,
+            all_shards_accepted: false,
+            last_unlock_request_at: None,
+            webhook_url: None,
+            webhook_secret: None,
+            require_step_up: false,
+        })
+        .await
+        .unwrap();
+
+    let fetch = || {
+        app.clone().oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/guardian/{}/shard", box_id),
+            "g1",
+            None,
+        ))
+    };
+
+    // Five minutes into a ten minute grace period
+    let response = fetch().await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_EARLY);
+    assert_eq!(response.headers()["retry-after"], "300");
+    let body = response_to_json(response).await;
+    assert_eq!(body["code"], "TOO_EARLY");
+
+    // No other guardian route hands the shard out during the grace period
+    for path in [
+        format!("/boxes/guardian/{}", box_id),
+        "/boxes/guardian".into(),
+        "/boxes/guardian/summary".into(),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(create_test_request("GET", &path, "g1", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response_to_json(response).await.to_string();
+        assert!(
+            !body.contains(&test_shard("g1")),
+            "{} served the shard during the grace period",
+            path
+        );
+    }
+
+    clock.advance(chrono::Duration::minutes(5));
+    let response = fetch().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["encryptedShard"], test_shard("g1"));
+}
+
+#[tokio::test]
+async fn test_step_up_box_requires_one_time_challenge() {
+    let store = Arc::new(MockBoxStore::new());
+    let challenges = Arc::new(MockShardChallengeStore::new());
+    let clock = MockClock::at_str("2030-01-01T00:05:00Z");
+    let app = routes::create_router_with_store(store.clone(), "")
+        .layer(Extension(challenges.clone() as SharedShardChallengeStore))
+        .layer(Extension(SharedClock::new(clock.clone())));
+    let box_id = "step_up_box";
+
+    store
+        .create_box(BoxRecord {
+            id: box_id.into(),
+            name: "Step-up Box".into(),
+            description: "Needs a challenge".into(),
+            is_locked: true,
+            locked_at: Some("2030-01-01T00:00:00+00:00".into()),
+            created_at: "2030-01-01T00:00:00+00:00".into(),
+            updated_at: "2030-01-01T00:00:00+00:00".into(),
+            owner_id: "owner_step_up".into(),
+            owner_name: None,
+            documents: vec![],
+            guardians: vec![Guardian {
+                id: "g1".into(),
+                name: "G One".into(),
+                lead_guardian: false,
+                status: GuardianStatus::Accepted,
+                added_at: "2030-01-01T00:00:00+00:00".into(),
+                invitation_id: "inv1".into(),
+                lock_data_received_at: Some("2030-01-01T00:00:00+00:00".into()),
+                encrypted_shard: Some(test_shard("g1")),
+                shard_hash: Some(test_shard_hash("g1")),
+                shard_fetched_at: None,
+                shard_accepted_at: None,
+                shard_commit: None,
+                shard_first_fetched_at: None,
+                last_reminded_at: None,
+            }],
+            unlock_instructions: None,
+            unlock_request: None,
+            version: 0,
+            shard_threshold: Some(1),
+            shards_fetched: None,
+            total_shards: Some(1),
+            shards_deleted_at: None,
+            all_shards_accepted: false,
+            last_unlock_request_at: None,
+            webhook_url: None,
+            webhook_secret: None,
+            require_step_up: true,
+        })
+        .await
+        .unwrap();
+
+    let issue = |user: &str| {
+        app.clone().oneshot(create_test_request(
+            "POST",
+            &format!("/boxes/guardian/{}/shard/challenge", box_id),
+            user,
+            None,
+        ))
+    };
+    let fetch = |challenge: Option<String>| {
+        let mut request = create_test_request(
+            "GET",
+            &format!("/boxes/guardian/{}/shard", box_id),
+            "g1",
+            None,
+        );
+        if let Some(challenge) = challenge {
+            request
+                .headers_mut()
+                .insert("x-shard-challenge", challenge.parse().unwrap());
         }
+        app.clone().oneshot(request)
+    };
+
+    // The guardian box views never carry the shard, so they can't be used to
+    // sidestep the challenge
+    for path in [
+        format!("/boxes/guardian/{}", box_id),
+        "/boxes/guardian".into(),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(create_test_request("GET", &path, "g1", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response_to_json(response).await.to_string();
+        assert!(
+            !body.contains(&test_shard("g1")),
+            "{} served the shard without a challenge",
+            path
+        );
     }
+
+    // Only guardians can ask for a challenge
+    let response = issue("stranger").await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Without a challenge the shard stays put
+    let response = fetch(None).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // A fresh challenge unlocks exactly one fetch
+    let response = issue("g1").await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response_to_json(response).await;
+    assert_eq!(body["expiresAt"], "2030-01-01T00:06:00+00:00");
+    let challenge = body["challenge"].as_str().unwrap().to_string();
+
+    // A fetch that fails to record itself hands the challenge back
+    store.fail_next_update(StoreError::InternalError("unavailable".into()));
+    let response = fetch(Some(challenge.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(challenges.challenges().len(), 1);
+
+    let response = fetch(Some(challenge.clone())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["encryptedShard"], test_shard("g1"));
+    assert!(challenges.challenges().is_empty());
+
+    let response = fetch(Some(challenge)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // An expired challenge is refused, and used up by the attempt
+    let body = response_to_json(issue("g1").await.unwrap()).await;
+    let challenge = body["challenge"].as_str().unwrap().to_string();
+    clock.advance(chrono::Duration::seconds(61));
+    let response = fetch(Some(challenge)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert!(challenges.challenges().is_empty());
 }
 
 #[tokio::test]
-async fn test_get_boxes() {
-    let (app, store) = create_test_app().await;
+async fn test_fetch_guardian_shard_detects_corrupted_storage() {
+    let store = Arc::new(MockBoxStore::new());
+    let now = now_str();
 
-    // Add test data directly to the store
-    add_test_data_to_store(&store).await;
+    let box_id = "box_corrupt";
+    let owner_id = "owner_corrupt";
+    let guardian = Guardian {
+        id: "g1".into(),
+        name: "G One".into(),
+        lead_guardian: false,
+        status: GuardianStatus::Accepted,
+        added_at: now.clone(),
+        invitation_id: "inv1".into(),
+        lock_data_received_at: None,
+        encrypted_shard: None,
+        shard_hash: None,
+        shard_fetched_at: None,
+        shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
+    };
+    store
+        .create_box(BoxRecord {
+            id: box_id.into(),
+            name: "Corrupt Box".into(),
+            description: "Shard integrity test".into(),
+            is_locked: false,
+            locked_at: None,
+            created_at: now.clone(),
+            updated_at: now.clone(),
+            owner_id: owner_id.into(),
+            owner_name: None,
+            documents: vec![],
+            guardians: vec![guardian],
+            unlock_instructions: None,
+            unlock_request: None,
+            version: 0,
+            shard_threshold: None,
+            shards_fetched: None,
+            total_shards: None,
+            shards_deleted_at: None,
+            all_shards_accepted: false,
+            last_unlock_request_at: None,
+            webhook_url: None,
+            webhook_secret: None,
+            require_step_up: false,
+        })
+        .await
+        .unwrap();
+
+    let _ = lock_box(
+        axum::extract::State(store.clone()),
+        axum::extract::Path(box_id.to_string()),
+        axum::Extension(owner_id.to_string()),
+        None,
+        SharedClock::default(),
+        RequestId::generate(),
+        lockbox_shared::extract::Json(LockBoxRequest {
+            shard_threshold: 1,
+            shards: vec![crate::models::IncomingShard {
+                guardian_id: "g1".into(),
+                shard: test_shard("g1"),
+                shard_hash: test_shard_hash("g1"),
+            }],
+        }),
+    )
+    .await
+    .expect("lock should succeed");
+
+    let locked_box = store.get_box(box_id).await.unwrap();
+    assert!(
+        locked_box.guardians[0].shard_commit.is_some(),
+        "lock_box should write a shard commitment"
+    );
+
+    // Untampered shard verifies
+    let _ = fetch_guardian_shard(
+        axum::extract::State(store.clone()),
+        axum::extract::Path(box_id.to_string()),
+        axum::Extension("g1".to_string()),
+        None,
+        None,
+        SharedClock::default(),
+        axum::http::HeaderMap::new(),
+    )
+    .await
+    .expect("fetch of an intact shard succeeds");
+
+    // Mutate the stored shard behind the service's back (re-read, as the
+    // first fetch stamped the guardian)
+    let mut locked_box = store.get_box(box_id).await.unwrap();
+    locked_box.guardians[0].encrypted_shard = Some("tampered-shard".into());
+    store.update_box(locked_box).await.unwrap();
+
+    let err = fetch_guardian_shard(
+        axum::extract::State(store.clone()),
+        axum::extract::Path(box_id.to_string()),
+        axum::Extension("g1".to_string()),
+        None,
+        None,
+        SharedClock::default(),
+        axum::http::HeaderMap::new(),
+    )
+    .await
+    .expect_err("fetch of a tampered shard must fail");
+    assert_eq!(
+        axum::response::IntoResponse::into_response(err).status(),
+        StatusCode::INTERNAL_SERVER_ERROR
+    );
+}
+
+#[tokio::test]
+async fn test_lock_box_without_guardians_is_rejected() {
+    let (app, _store) = create_test_app().await;
+    let owner_id = "owner_no_guardians";
 
-    // Get all boxes for user_1 from the API
     let response = app
         .clone()
-        .oneshot(create_test_request("GET", "/boxes/owned", "user_1", None))
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned",
+            owner_id,
+            Some(json!({ "name": "Lonely Box", "description": "No guardians yet" })),
+        ))
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let box_id = response_to_json(response).await["box"]["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
 
-    // Verify response status
-    assert_eq!(response.status(), StatusCode::OK);
+    let lock_uri = format!("/boxes/owned/{}/lock", box_id);
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            &lock_uri,
+            owner_id,
+            Some(json!({ "shardThreshold": 0, "shards": [] })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = response_to_json(response).await;
+    assert_eq!(body["error"], "Add at least one guardian before locking.");
 
-    // Parse the response body
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned/{}", box_id),
+            owner_id,
+            None,
+        ))
+        .await
+        .unwrap();
     let body = response_to_json(response).await;
-    assert!(body.get("boxes").is_some());
-    assert!(body["boxes"].is_array());
+    assert_eq!(body["box"]["isLocked"], false);
+}
 
-    let boxes = body["boxes"].as_array().unwrap();
-    let box_ids: Vec<&str> = boxes
+#[tokio::test]
+async fn test_lock_box_rejects_mismatched_shard_hash() {
+    let (app, store) = create_test_app().await;
+    let box_id = "hash-check-box";
+    let owner_id = "owner_hash";
+
+    let box_record = BoxRecord {
+        id: box_id.into(),
+        name: "Hash Check".into(),
+        description: "Shard hash verification".into(),
+        is_locked: false,
+        locked_at: None,
+        created_at: now_str(),
+        updated_at: now_str(),
+        owner_id: owner_id.into(),
+        owner_name: None,
+        documents: vec![],
+        guardians: vec![Guardian {
+            id: "g1".into(),
+            name: "Guardian One".into(),
+            lead_guardian: false,
+            status: GuardianStatus::Accepted,
+            added_at: now_str(),
+            invitation_id: "inv-hash-1".into(),
+            lock_data_received_at: None,
+            encrypted_shard: None,
+            shard_hash: None,
+            shard_fetched_at: None,
+            shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
+        }],
+        unlock_instructions: None,
+        unlock_request: None,
+        version: 0,
+        shard_threshold: None,
+        shards_fetched: None,
+        total_shards: None,
+        shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
+    };
+    match &store {
+        TestStore::Mock(mock) => {
+            mock.create_box(box_record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.create_box(box_record).await.unwrap();
+        }
+    }
+
+    let lock = |shard: String, shard_hash: String| {
+        let app = app.clone();
+        async move {
+            app.oneshot(create_test_request(
+                "POST",
+                &format!("/boxes/owned/{}/lock", box_id),
+                owner_id,
+                Some(json!({
+                    "shardThreshold": 1,
+                    "shards": [{ "guardianId": "g1", "shard": shard, "shardHash": shard_hash }]
+                })),
+            ))
+            .await
+            .unwrap()
+            .status()
+        }
+    };
+
+    // Hash of a different shard
+    assert_eq!(
+        lock(test_shard("g1"), test_shard_hash("g2")).await,
+        StatusCode::BAD_REQUEST
+    );
+    // Shard that isn't base64 at all
+    assert_eq!(
+        lock("not base64!".into(), test_shard_hash("g1")).await,
+        StatusCode::BAD_REQUEST
+    );
+
+    // Every problem with the request is reported at once
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            &format!("/boxes/owned/{}/lock", box_id),
+            owner_id,
+            Some(json!({
+                "shardThreshold": 0,
+                "shards": [{ "guardianId": "g1", "shard": test_shard("g1"), "shardHash": test_shard_hash("g2") }]
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = response_to_json(response).await;
+    assert_eq!(body["code"], "VALIDATION");
+    let fields: Vec<(&str, &str)> = body["fields"]
+        .as_array()
+        .unwrap()
         .iter()
-        .map(|b| b.get("id").unwrap().as_str().unwrap())
+        .map(|f| (f["field"].as_str().unwrap(), f["code"].as_str().unwrap()))
         .collect();
+    assert_eq!(
+        fields,
+        [
+            ("shardThreshold", "OUT_OF_RANGE"),
+            ("shards[0].shardHash", "HASH_MISMATCH")
+        ]
+    );
 
-    // Directly verify with the store
-    let store_boxes = match &store {
-        TestStore::Mock(mock) => mock.get_boxes_by_owner("user_1").await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_boxes_by_owner("user_1").await.unwrap(),
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
     };
+    assert!(!stored.is_locked, "a rejected lock must not change the box");
 
-    // Check that both sources report the same number of boxes
+    // The matching hash is accepted, in either case
     assert_eq!(
-        boxes.len(),
-        store_boxes.len(),
-        "API and store should return same number of boxes"
+        lock(test_shard("g1"), test_shard_hash("g1").to_uppercase()).await,
+        StatusCode::OK
     );
+}
 
-    // Check that each box ID from the API response exists in the store results
-    for id in box_ids {
-        assert!(
-            store_boxes.iter().any(|b| b.id == id),
-            "Box ID {} from API should exist in store",
-            id
-        );
-    }
+#[tokio::test]
+async fn test_lock_box_checks_shard_format() {
+    let (app, store) = create_test_app().await;
+    let box_id = "shard-format-box";
+    let owner_id = "owner_shard_format";
+
+    let guardian = |id: &str| Guardian {
+        id: id.into(),
+        name: format!("Guardian {}", id),
+        lead_guardian: false,
+        status: GuardianStatus::Accepted,
+        added_at: now_str(),
+        invitation_id: format!("inv-{}", id),
+        lock_data_received_at: None,
+        encrypted_shard: None,
+        shard_hash: None,
+        shard_fetched_at: None,
+        shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
+    };
+    let box_record = BoxRecord {
+        id: box_id.into(),
+        name: "Shard Format".into(),
+        description: "Shard format checks".into(),
+        is_locked: false,
+        locked_at: None,
+        created_at: now_str(),
+        updated_at: now_str(),
+        owner_id: owner_id.into(),
+        owner_name: None,
+        documents: vec![],
+        guardians: vec![guardian("g1"), guardian("g2")],
+        unlock_instructions: None,
+        unlock_request: None,
+        version: 0,
+        shard_threshold: None,
+        shards_fetched: None,
+        total_shards: None,
+        shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
+    };
+    match &store {
+        TestStore::Mock(mock) => {
+            mock.create_box(box_record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.create_box(box_record).await.unwrap();
+        }
+    }
+
+    let lock = |shards: serde_json::Value| {
+        let app = app.clone();
+        async move {
+            app.oneshot(create_test_request(
+                "POST",
+                &format!("/boxes/owned/{}/lock", box_id),
+                owner_id,
+                Some(json!({ "shardThreshold": 2, "shards": shards })),
+            ))
+            .await
+            .unwrap()
+        }
+    };
+
+    // g1's hash isn't a digest at all and g2's shard is far too long
+    let oversized = "A".repeat(MAX_SHARD_CHARS + 4);
+    let response = lock(json!([
+        { "guardianId": "g1", "shard": test_shard("g1"), "shardHash": "not-a-hash" },
+        {
+            "guardianId": "g2",
+            "shard": oversized,
+            "shardHash": Guardian::expected_shard_hash(&oversized).unwrap()
+        }
+    ]))
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = response_to_json(response).await;
+    let fields: Vec<(&str, &str)> = body["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| (f["field"].as_str().unwrap(), f["code"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            ("shards[0].shardHash", "INVALID_FORMAT"),
+            ("shards[1].shard", "TOO_LONG")
+        ]
+    );
+    let error = body["error"].as_str().unwrap();
+    assert!(error.contains("guardian g1") && error.contains("guardian g2"));
+
+    // A well-formed set locks the box
+    let response = lock(json!([
+        { "guardianId": "g1", "shard": test_shard("g1"), "shardHash": test_shard_hash("g1") },
+        { "guardianId": "g2", "shard": test_shard("g2"), "shardHash": test_shard_hash("g2") }
+    ]))
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_lock_box_with_unaccepted_guardian() {
+    let (app, store) = create_test_app().await;
+    let box_id = "unaccepted-guardian-box";
+    let owner_id = "owner_unaccepted";
+
+    let guardian = |id: &str, name: &str, status: GuardianStatus| Guardian {
+        id: id.into(),
+        name: name.into(),
+        lead_guardian: false,
+        status,
+        added_at: now_str(),
+        invitation_id: format!("inv-{}", id),
+        lock_data_received_at: None,
+        encrypted_shard: None,
+        shard_hash: None,
+        shard_fetched_at: None,
+        shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
+    };
+    let box_record = BoxRecord {
+        id: box_id.into(),
+        name: "Unaccepted Guardian".into(),
+        description: "Locking before every guardian accepted".into(),
+        is_locked: false,
+        locked_at: None,
+        created_at: now_str(),
+        updated_at: now_str(),
+        owner_id: owner_id.into(),
+        owner_name: None,
+        documents: vec![],
+        guardians: vec![
+            guardian("g1", "Guardian One", GuardianStatus::Accepted),
+            guardian("g2", "Guardian Two", GuardianStatus::Invited),
+        ],
+        unlock_instructions: None,
+        unlock_request: None,
+        version: 0,
+        shard_threshold: None,
+        shards_fetched: None,
+        total_shards: None,
+        shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
+    };
+    match &store {
+        TestStore::Mock(mock) => {
+            mock.create_box(box_record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.create_box(box_record).await.unwrap();
+        }
+    }
+    let stored_box = || async {
+        match &store {
+            TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+            TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+        }
+    };
+
+    let lock_request = || {
+        create_test_request(
+            "POST",
+            &format!("/boxes/owned/{}/lock", box_id),
+            owner_id,
+            Some(json!({
+                "shardThreshold": 2,
+                "shards": [
+                    { "guardianId": "g1", "shard": test_shard("g1"), "shardHash": test_shard_hash("g1") },
+                    { "guardianId": "g2", "shard": test_shard("g2"), "shardHash": test_shard_hash("g2") }
+                ]
+            })),
+        )
+    };
+
+    // Requiring accepted guardians refuses the lock and names who's pending
+    let response = app
+        .clone()
+        .layer(Extension(UnacceptedGuardianPolicy::Reject))
+        .oneshot(lock_request())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = response_to_json(response).await;
+    let message = body["error"].as_str().unwrap();
+    assert!(message.contains("Guardian Two"), "got {}", message);
+    assert!(!message.contains("Guardian One"), "got {}", message);
+    assert!(!stored_box().await.is_locked);
+
+    // By default the lock goes ahead and every guardian still gets a shard
+    let response = app.clone().oneshot(lock_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let stored = stored_box().await;
+    assert!(stored.is_locked);
+    assert!(stored.guardians.iter().all(|g| g.encrypted_shard.is_some()));
+}
+
+#[tokio::test]
+async fn test_lock_preview_checks_threshold_against_guardians() {
+    let (app, store) = create_test_app().await;
+    let box_id = "lock-preview-box";
+    let owner_id = "owner_preview";
+
+    let guardian = |id: &str| Guardian {
+        id: id.into(),
+        name: format!("Guardian {}", id),
+        lead_guardian: false,
+        status: GuardianStatus::Accepted,
+        added_at: now_str(),
+        invitation_id: format!("inv-{}", id),
+        lock_data_received_at: None,
+        encrypted_shard: None,
+        shard_hash: None,
+        shard_fetched_at: None,
+        shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
+    };
+    let box_record = BoxRecord {
+        id: box_id.into(),
+        name: "Lock Preview".into(),
+        description: "Previewing thresholds".into(),
+        is_locked: false,
+        locked_at: None,
+        created_at: now_str(),
+        updated_at: now_str(),
+        owner_id: owner_id.into(),
+        owner_name: None,
+        documents: vec![],
+        guardians: vec![guardian("g1"), guardian("g2"), guardian("g3")],
+        unlock_instructions: None,
+        unlock_request: None,
+        version: 0,
+        shard_threshold: None,
+        shards_fetched: None,
+        total_shards: None,
+        shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
+    };
+    match &store {
+        TestStore::Mock(mock) => {
+            mock.create_box(box_record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.create_box(box_record).await.unwrap();
+        }
+    }
+
+    let preview = |threshold: usize, user_id: &str| {
+        create_test_request(
+            "GET",
+            &format!(
+                "/boxes/owned/{}/lock-preview?threshold={}",
+                box_id, threshold
+            ),
+            user_id,
+            None,
+        )
+    };
+
+    let response = app.clone().oneshot(preview(2, owner_id)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["valid"], true);
+    assert_eq!(body["totalShards"], 3);
+    assert_eq!(body["threshold"], 2);
+    assert_eq!(
+        body["message"],
+        "Any 2 of the box's 3 guardians will be able to recover it."
+    );
+
+    for threshold in [0, 4] {
+        let response = app
+            .clone()
+            .oneshot(preview(threshold, owner_id))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response_to_json(response).await;
+        assert_eq!(body["valid"], false, "threshold {}", threshold);
+        assert_eq!(body["totalShards"], 3);
+        assert_eq!(body["threshold"], threshold);
+        assert_eq!(
+            body["message"],
+            "Shard threshold must be between 1 and the number of guardians."
+        );
+    }
+
+    let response = app.clone().oneshot(preview(2, "intruder")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Previewing changes nothing
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert!(!stored.is_locked);
+    assert_eq!(stored.version, 0);
+}
+
+#[tokio::test]
+async fn test_lock_box_stamps_injected_time() {
+    let (app, store) = create_test_app().await;
+    let box_id = "frozen-clock-box";
+    let owner_id = "owner_frozen_clock";
+
+    let box_record = BoxRecord {
+        id: box_id.into(),
+        name: "Frozen Clock".into(),
+        description: "Timestamps from an injected clock".into(),
+        is_locked: false,
+        locked_at: None,
+        created_at: now_str(),
+        updated_at: now_str(),
+        owner_id: owner_id.into(),
+        owner_name: None,
+        documents: vec![],
+        guardians: vec![Guardian {
+            id: "g1".into(),
+            name: "Guardian One".into(),
+            lead_guardian: false,
+            status: GuardianStatus::Accepted,
+            added_at: now_str(),
+            invitation_id: "inv-clock-1".into(),
+            lock_data_received_at: None,
+            encrypted_shard: None,
+            shard_hash: None,
+            shard_fetched_at: None,
+            shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
+        }],
+        unlock_instructions: None,
+        unlock_request: None,
+        version: 0,
+        shard_threshold: None,
+        shards_fetched: None,
+        total_shards: None,
+        shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
+    };
+    match &store {
+        TestStore::Mock(mock) => {
+            mock.create_box(box_record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.create_box(box_record).await.unwrap();
+        }
+    }
+
+    let clock = MockClock::at_str("2030-01-02T03:04:05Z");
+    let app = app.layer(Extension(SharedClock::new(clock.clone())));
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            &format!("/boxes/owned/{}/lock", box_id),
+            owner_id,
+            Some(json!({
+                "shardThreshold": 1,
+                "shards": [{ "guardianId": "g1", "shard": test_shard("g1"), "shardHash": test_shard_hash("g1") }]
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["box"]["lockedAt"], "2030-01-02T03:04:05+00:00");
+    assert_eq!(body["box"]["updatedAt"], "2030-01-02T03:04:05+00:00");
+
+    // Later handlers read the same clock
+    clock.advance(chrono::Duration::minutes(5));
+    let response = app
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/shard/ack", box_id),
+            "g1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["shardFetchedAt"], "2030-01-02T03:09:05+00:00");
+}
+
+#[tokio::test]
+async fn test_pending_guardians_across_locked_boxes() {
+    let (app, store) = create_test_app().await;
+    let owner_id = "owner_pending_guardians";
+
+    let guardian = |id: &str, accepted: bool| Guardian {
+        id: id.into(),
+        name: format!("Guardian {}", id),
+        lead_guardian: false,
+        status: GuardianStatus::Accepted,
+        added_at: "2030-01-01T00:00:00+00:00".into(),
+        invitation_id: format!("inv-{}", id),
+        lock_data_received_at: None,
+        encrypted_shard: None,
+        shard_hash: None,
+        shard_fetched_at: None,
+        shard_accepted_at: accepted.then(|| "2030-01-02T00:00:00+00:00".to_string()),
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
+    };
+    let box_record = |id: &str, locked_at: Option<&str>, guardians: Vec<Guardian>| BoxRecord {
+        id: id.into(),
+        name: format!("Box {}", id),
+        description: "Pending guardian test".into(),
+        is_locked: locked_at.is_some(),
+        locked_at: locked_at.map(String::from),
+        created_at: "2030-01-01T00:00:00+00:00".into(),
+        updated_at: "2030-01-01T00:00:00+00:00".into(),
+        owner_id: owner_id.into(),
+        owner_name: None,
+        documents: vec![],
+        guardians,
+        unlock_instructions: None,
+        unlock_request: None,
+        version: 0,
+        shard_threshold: None,
+        shards_fetched: None,
+        total_shards: None,
+        shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
+    };
+    let boxes = vec![
+        box_record(
+            "pending_a",
+            Some("2030-01-01T00:00:00+00:00"),
+            vec![guardian("a1", true), guardian("a2", false)],
+        ),
+        box_record(
+            "pending_b",
+            Some("2030-01-02T12:00:00+00:00"),
+            vec![guardian("b1", false), guardian("b2", false)],
+        ),
+        // Not locked, so nobody has a shard to accept
+        box_record("pending_c", None, vec![guardian("c1", false)]),
+    ];
+    for box_rec in boxes {
+        match &store {
+            TestStore::Mock(mock) => {
+                mock.create_box(box_rec).await.unwrap();
+            }
+            TestStore::DynamoDB(dynamo) => {
+                dynamo.create_box(box_rec).await.unwrap();
+            }
+        }
+    }
+
+    let clock = MockClock::at_str("2030-01-03T00:00:00Z");
+    let response = app
+        .layer(Extension(SharedClock::new(clock)))
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/owned/pending-guardians",
+            owner_id,
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+
+    let mut pending: Vec<(String, i64)> = body["guardians"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|g| {
+            assert_eq!(
+                g["boxName"],
+                format!("Box {}", g["boxId"].as_str().unwrap())
+            );
+            (
+                g["guardianId"].as_str().unwrap().to_string(),
+                g["hoursSinceLock"].as_i64().unwrap(),
+            )
+        })
+        .collect();
+    pending.sort();
+    assert_eq!(
+        pending,
+        vec![
+            ("a2".to_string(), 48),
+            ("b1".to_string(), 12),
+            ("b2".to_string(), 12),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_remind_all_reaches_only_pending_guardians() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+    let store = Arc::new(MockBoxStore::new());
+    let push_tokens = Arc::new(MockPushTokenStore::new());
+    let push_sender = Arc::new(MockPushSender::new());
+    let clock = MockClock::at_str("2030-01-03T00:00:00Z");
+    let app = routes::create_router_with_store(store.clone(), "")
+        .layer(Extension(push_tokens.clone() as SharedPushTokenStore))
+        .layer(Extension(push_sender.clone() as SharedPushSender))
+        .layer(Extension(SharedClock::new(clock.clone())));
+    let box_id = "remind_all_box";
+    let owner_id = "owner_remind_all";
+
+    let guardian = |id: &str, accepted: bool, last_reminded_at: Option<&str>| Guardian {
+        id: id.into(),
+        name: format!("Guardian {}", id),
+        lead_guardian: false,
+        status: GuardianStatus::Accepted,
+        added_at: "2030-01-01T00:00:00+00:00".into(),
+        invitation_id: format!("inv-{}", id),
+        lock_data_received_at: None,
+        encrypted_shard: None,
+        shard_hash: None,
+        shard_fetched_at: None,
+        shard_accepted_at: accepted.then(|| "2030-01-02T00:00:00+00:00".to_string()),
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: last_reminded_at.map(String::from),
+    };
+    store
+        .create_box(BoxRecord {
+            id: box_id.into(),
+            name: "Remind All".into(),
+            description: "Nudging pending guardians".into(),
+            is_locked: true,
+            locked_at: Some("2030-01-01T00:00:00+00:00".into()),
+            created_at: "2030-01-01T00:00:00+00:00".into(),
+            updated_at: "2030-01-01T00:00:00+00:00".into(),
+            owner_id: owner_id.into(),
+            owner_name: Some("Olive".into()),
+            documents: vec![],
+            guardians: vec![
+                guardian("accepted", true, None),
+                guardian("pending", false, None),
+                guardian("no_device", false, None),
+                guardian("recent", false, Some("2030-01-02T23:30:00+00:00")),
+                // An invited placeholder with no user yet is left out
+                guardian("", false, None),
+            ],
+            unlock_instructions: None,
+            unlock_request: None,
+            version: 0,
+            shard_threshold: Some(2),
+            shards_fetched: None,
+            total_shards: Some(4),
+            shards_deleted_at: None,
+            all_shards_accepted: false,
+            last_unlock_request_at: None,
+            webhook_url: None,
+            webhook_secret: None,
+            require_step_up: false,
+        })
+        .await
+        .unwrap();
+    for user_id in ["accepted", "pending", "recent"] {
+        push_tokens
+            .save_push_token(PushToken {
+                user_id: user_id.into(),
+                push_token: format!("ExponentPushToken[{}]", user_id),
+                platform: "ios".into(),
+                updated_at: now_str(),
+                last_test_notification_at: None,
+                last_verified_at: None,
+                notification_prefs: None,
+                utc_offset_minutes: None,
+            })
+            .await
+            .unwrap();
+    }
+
+    let remind_all = || {
+        app.clone().oneshot(create_test_request(
+            "POST",
+            &format!("/boxes/owned/{}/remind-all", box_id),
+            owner_id,
+            None,
+        ))
+    };
+
+    let response = remind_all().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["sent"], 1);
+    assert_eq!(body["noPushToken"], 1);
+    assert_eq!(body["rateLimited"], 1);
+    assert_eq!(body["failed"], 0);
+
+    let sent = push_sender.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].to, "ExponentPushToken[pending]");
+    assert_eq!(sent[0].data.as_ref().unwrap()["type"], "shard_reminder");
+    assert!(sent[0].body.contains("Olive"));
+
+    let stored = store.get_box(box_id).await.unwrap();
+    let pending = stored.guardians.iter().find(|g| g.id == "pending").unwrap();
+    assert_eq!(
+        pending.last_reminded_at.as_deref(),
+        Some("2030-01-03T00:00:00+00:00")
+    );
+
+    // Both guardians with a device were reminded within the last hour
+    clock.advance(chrono::Duration::minutes(20));
+    let body = response_to_json(remind_all().await.unwrap()).await;
+    assert_eq!(body["sent"], 0);
+    assert_eq!(body["rateLimited"], 2);
+    assert_eq!(body["noPushToken"], 1);
+    assert_eq!(push_sender.sent().len(), 1);
+
+    // Once the hour is up they can be reminded again. Losing the write to a
+    // concurrent update rereads the box instead of failing, so the reminders
+    // are recorded and a retry won't send them twice.
+    clock.advance(chrono::Duration::minutes(41));
+    store.fail_next_update(StoreError::VersionConflict("changed".into()));
+    let body = response_to_json(remind_all().await.unwrap()).await;
+    assert_eq!(body["sent"], 2);
+    assert_eq!(body["rateLimited"], 0);
+
+    let body = response_to_json(remind_all().await.unwrap()).await;
+    assert_eq!(body["sent"], 0);
+    assert_eq!(body["rateLimited"], 2);
+    assert_eq!(push_sender.sent().len(), 3);
+}
+
+#[tokio::test]
+async fn test_remind_all_badges_pending_action_count() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+    let store = Arc::new(MockBoxStore::new());
+    let push_tokens = Arc::new(MockPushTokenStore::new());
+    let push_sender = Arc::new(MockPushSender::new());
+    let app = routes::create_router_with_store(store.clone(), "")
+        .layer(Extension(push_tokens.clone() as SharedPushTokenStore))
+        .layer(Extension(push_sender.clone() as SharedPushSender));
+
+    let guardian = |id: &str, accepted: bool| Guardian {
+        id: id.into(),
+        name: format!("Guardian {}", id),
+        lead_guardian: id == "lead",
+        status: GuardianStatus::Accepted,
+        added_at: "2030-01-01T00:00:00+00:00".into(),
+        invitation_id: format!("inv-{}", id),
+        lock_data_received_at: None,
+        encrypted_shard: None,
+        shard_hash: None,
+        shard_fetched_at: None,
+        shard_accepted_at: accepted.then(|| "2030-01-02T00:00:00+00:00".to_string()),
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
+    };
+    let locked_box =
+        |id: &str, guardians: Vec<Guardian>, unlock_request: Option<UnlockRequest>| BoxRecord {
+            id: id.into(),
+            name: format!("Box {}", id),
+            description: "Badge counting".into(),
+            is_locked: true,
+            locked_at: Some("2030-01-01T00:00:00+00:00".into()),
+            created_at: "2030-01-01T00:00:00+00:00".into(),
+            updated_at: "2030-01-01T00:00:00+00:00".into(),
+            owner_id: "owner_badge".into(),
+            owner_name: Some("Olive".into()),
+            documents: vec![],
+            guardians,
+            unlock_instructions: None,
+            unlock_request,
+            version: 0,
+            shard_threshold: Some(1),
+            shards_fetched: None,
+            total_shards: Some(2),
+            shards_deleted_at: None,
+            all_shards_accepted: false,
+            last_unlock_request_at: None,
+            webhook_url: None,
+            webhook_secret: None,
+            require_step_up: false,
+        };
+
+    // "busy" has a shard to accept here, and an unlock request to answer on
+    // another box; "quiet" only has the shard
+    store
+        .create_box(locked_box(
+            "badge_box",
+            vec![guardian("busy", false), guardian("quiet", false)],
+            None,
+        ))
+        .await
+        .unwrap();
+    store
+        .create_box(locked_box(
+            "badge_unlock_box",
+            vec![guardian("lead", true), guardian("busy", true)],
+            Some(UnlockRequest {
+                id: "unlock-1".into(),
+                requested_at: "2030-01-02T00:00:00+00:00".into(),
+                status: UnlockRequestStatus::Requested,
+                message: None,
+                reason: None,
+                initiated_by: Some("lead".into()),
+                approved_by: vec![],
+                rejected_by: vec![],
+                approvals: vec![],
+            }),
+        ))
+        .await
+        .unwrap();
+    for user_id in ["busy", "quiet"] {
+        push_tokens
+            .save_push_token(PushToken {
+                user_id: user_id.into(),
+                push_token: format!("ExponentPushToken[{}]", user_id),
+                platform: "ios".into(),
+                updated_at: now_str(),
+                last_test_notification_at: None,
+                last_verified_at: None,
+                notification_prefs: None,
+                utc_offset_minutes: None,
+            })
+            .await
+            .unwrap();
+    }
+
+    let response = app
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned/badge_box/remind-all",
+            "owner_badge",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["sent"], 2);
+
+    let mut badges: Vec<(String, Option<u32>)> = push_sender
+        .sent()
+        .into_iter()
+        .map(|push| (push.to, push.badge))
+        .collect();
+    badges.sort();
+    assert_eq!(
+        badges,
+        [
+            ("ExponentPushToken[busy]".to_string(), Some(2)),
+            ("ExponentPushToken[quiet]".to_string(), Some(1)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_request_id_is_echoed_or_generated() {
+    let (app, _store) = create_test_app().await;
+
+    // A caller-supplied ID is kept
+    let mut request = create_test_request("GET", "/boxes/owned", "user_1", None);
+    request
+        .headers_mut()
+        .insert("x-request-id", "client-trace-123".parse().unwrap());
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get("x-request-id").unwrap(),
+        "client-trace-123"
+    );
+
+    // Otherwise one is generated
+    let response = app
+        .oneshot(create_test_request("GET", "/boxes/owned", "user_1", None))
+        .await
+        .unwrap();
+    let generated = response.headers().get("x-request-id").unwrap();
+    assert!(!generated.is_empty());
+}
+
+#[test]
+fn test_box_locked_event_carries_request_id() {
+    let request_id = RequestId("trace-abc".into());
+    let payload = box_locked_event_payload(
+        "box_1",
+        "Box",
+        Some("Owner"),
+        &["g1".to_string()],
+        "2024-01-01T00:00:00Z",
+        Some(&request_id),
+    );
+    assert_eq!(payload.event_type, "box_locked");
+    assert_eq!(payload.request_id.as_deref(), Some("trace-abc"));
+}
+
+#[test]
+fn test_box_locked_event_round_trips_through_shared_model() {
+    let request_id = RequestId("trace-xyz".into());
+    let published = box_locked_event_payload(
+        "box_1",
+        "Family",
+        None,
+        &["g1".to_string(), "g2".to_string()],
+        "2024-01-01T00:00:00Z",
+        Some(&request_id),
+    );
+
+    // What goes over SNS, read back the way the notification service does
+    let message = serde_json::to_string(&published).unwrap();
+    let received: BoxLockedEvent = serde_json::from_str(&message).unwrap();
+    assert_eq!(received, published);
+    assert_eq!(received.guardian_ids, ["g1", "g2"]);
+    assert_eq!(received.owner_name, None);
+
+    let wire: serde_json::Value = serde_json::from_str(&message).unwrap();
+    assert_eq!(wire["event_type"], "box_locked");
+    assert_eq!(wire["box_name"], "Family");
+}
+
+#[tokio::test]
+async fn test_create_box_malformed_json_returns_bad_request() {
+    let (app, _store) = create_test_app().await;
+
+    let (auth_key, auth_value) = lockbox_shared::auth::create_auth_header("user_1");
+    let request = Request::builder()
+        .method("POST")
+        .uri("/boxes/owned")
+        .header(auth_key, auth_value)
+        .header("content-type", "application/json")
+        .body(Body::from("{\"name\": \"Broken\", "))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = response_to_json(response).await;
+    assert_eq!(body["code"], "INVALID_JSON");
+    assert!(body["error"].is_string());
+}
+
+#[tokio::test]
+async fn test_create_box_idempotency_key() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+    let store = Arc::new(MockBoxStore::new());
+    let keys = Arc::new(MockIdempotencyStore::new());
+    let app = routes::create_router_with_store(store.clone(), "")
+        .layer(Extension(keys.clone() as SharedIdempotencyStore));
+
+    let create = |key: &str, name: &str| {
+        let mut request = create_test_request(
+            "POST",
+            "/boxes/owned",
+            "idempotent_owner",
+            Some(json!({ "name": name, "description": "Retried on a flaky network" })),
+        );
+        request
+            .headers_mut()
+            .insert("idempotency-key", HeaderValue::from_str(key).unwrap());
+        request
+    };
+
+    // First call creates the box
+    let response = app.clone().oneshot(create("key-1", "Box")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let first = response_to_json(response).await;
+    let box_id = first["box"]["id"].as_str().unwrap().to_string();
+
+    // An identical retry returns the same box without creating another
+    let response = app.clone().oneshot(create("key-1", "Box")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let retry = response_to_json(response).await;
+    assert_eq!(retry["box"]["id"], box_id);
+    assert_eq!(
+        store
+            .get_boxes_by_owner("idempotent_owner")
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+
+    // Reusing the key for a different box is a conflict
+    let response = app
+        .clone()
+        .oneshot(create("key-1", "Other box"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // Another key creates another box
+    let response = app.clone().oneshot(create("key-2", "Box")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let second = response_to_json(response).await;
+    assert_ne!(second["box"]["id"], box_id);
+
+    // The same key from another user doesn't replay someone else's box
+    let mut request = create_test_request(
+        "POST",
+        "/boxes/owned",
+        "other_owner",
+        Some(json!({ "name": "Box", "description": "Retried on a flaky network" })),
+    );
+    request
+        .headers_mut()
+        .insert("idempotency-key", HeaderValue::from_static("key-1"));
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let other = response_to_json(response).await;
+    assert_ne!(other["box"]["id"], box_id);
+}
+
+#[tokio::test]
+async fn test_create_box_wrong_shape_returns_bad_request() {
+    let (app, _store) = create_test_app().await;
+
+    // Valid JSON, but `description` is missing and `name` has the wrong type
+    let response = app
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned",
+            "user_1",
+            Some(json!({ "name": 42 })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = response_to_json(response).await;
+    assert_eq!(body["code"], "INVALID_BODY");
+    assert!(
+        body["error"].as_str().unwrap().contains("name"),
+        "error should name the offending field: {}",
+        body["error"]
+    );
+}
+
+async fn upsert_guardians(store: &TestStore, box_id: &str, guardians: Vec<Guardian>) {
+    let mut box_record = match store {
+        TestStore::Mock(mock) => mock.get_box(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box(box_id).await.unwrap(),
+    };
+
+    box_record.guardians = guardians;
+    box_record.updated_at = now_str();
+
+    match store {
+        TestStore::Mock(mock) => {
+            mock.update_box(box_record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            let _ = dynamo.update_box(box_record).await.unwrap();
+            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_openapi_spec() {
+    let (app, _store) = create_test_app().await;
+
+    // No Authorization header: the spec is public
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/openapi.json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let spec = response_to_json(response).await;
+    std::fs::write(
+        "/tmp/box_spec.json",
+        serde_json::to_string_pretty(&spec).unwrap(),
+    )
+    .unwrap();
+
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+    assert!(spec["paths"]["/boxes/owned"]["get"].is_object());
+    assert!(spec["paths"]["/boxes/owned"]["post"].is_object());
+    assert!(spec["paths"]["/boxes/owned/{id}/watch"]["get"].is_object());
+    let box_response = &spec["components"]["schemas"]["BoxResponse"];
+    assert_eq!(box_response["type"], "object");
+    assert!(box_response["properties"]["shardThreshold"].is_object());
+    assert!(box_response["properties"].get("webhookSecret").is_none());
+}
+
+#[tokio::test]
+async fn test_get_boxes() {
+    let (app, store) = create_test_app().await;
+
+    // Add test data directly to the store
+    add_test_data_to_store(&store).await;
+
+    // Get all boxes for user_1 from the API
+    let response = app
+        .clone()
+        .oneshot(create_test_request("GET", "/boxes/owned", "user_1", None))
+        .await
+        .unwrap();
+
+    // Verify response status
+    assert_eq!(response.status(), StatusCode::OK);
+    let total_count = response.headers()["x-total-count"].clone();
+    assert!(response.headers().get("link").is_none());
+
+    // Parse the response body
+    let body = response_to_json(response).await;
+    assert!(body.get("boxes").is_some());
+    assert!(body["boxes"].is_array());
+
+    let boxes = body["boxes"].as_array().unwrap();
+    assert_eq!(total_count, boxes.len().to_string().as_str());
+    let box_ids: Vec<&str> = boxes
+        .iter()
+        .map(|b| b.get("id").unwrap().as_str().unwrap())
+        .collect();
+
+    // Directly verify with the store
+    let store_boxes = match &store {
+        TestStore::Mock(mock) => mock.get_boxes_by_owner("user_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_boxes_by_owner("user_1").await.unwrap(),
+    };
+
+    // Check that both sources report the same number of boxes
+    assert_eq!(
+        boxes.len(),
+        store_boxes.len(),
+        "API and store should return same number of boxes"
+    );
+
+    // Check that each box ID from the API response exists in the store results
+    for id in box_ids {
+        assert!(
+            store_boxes.iter().any(|b| b.id == id),
+            "Box ID {} from API should exist in store",
+            id
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_get_boxes_paginated() {
+    let (app, store) = create_test_app().await;
+    for i in 0..3 {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "POST",
+                "/boxes/owned",
+                "paging_owner",
+                Some(json!({ "name": format!("Paged Box {}", i), "description": "Paging" })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+    let store_boxes = match &store {
+        TestStore::Mock(mock) => mock.get_boxes_by_owner("paging_owner").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_boxes_by_owner("paging_owner").await.unwrap(),
+    };
+
+    let mut seen = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let uri = match &cursor {
+            Some(cursor) => format!("/boxes/owned?limit=1&cursor={}", cursor),
+            None => "/boxes/owned?limit=1".to_string(),
+        };
+        let response = app
+            .clone()
+            .oneshot(create_test_request("GET", &uri, "paging_owner", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get("x-total-count").is_none());
+        let link = response
+            .headers()
+            .get(axum::http::header::LINK)
+            .map(|value| value.to_str().unwrap().to_string());
+
+        let json = response_to_json(response).await;
+        let boxes = json["boxes"].as_array().unwrap();
+        assert!(boxes.len() <= 1);
+        seen.extend(boxes.iter().map(|b| b["id"].as_str().unwrap().to_string()));
+
+        // The Link header mirrors the body's cursor
+        match json["nextCursor"].as_str() {
+            Some(next) => {
+                assert_eq!(
+                    link.as_deref(),
+                    Some(format!("</boxes/owned?limit=1&cursor={}>; rel=\"next\"", next).as_str())
+                );
+                cursor = Some(next.to_string());
+            }
+            None => {
+                assert!(link.is_none(), "last page has a Link header: {:?}", link);
+                break;
+            }
+        }
+    }
+
+    seen.sort();
+    let mut expected: Vec<String> = store_boxes.into_iter().map(|b| b.id).collect();
+    expected.sort();
+    assert_eq!(seen, expected);
+
+    // Lock date ranges aren't paged
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/owned?limit=1&lockedAfter=2020-01-01T00:00:00Z",
+            "paging_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+// Seeds an unlocked and a locked box for "export_owner", each with a document,
+// plus an empty box and one belonging to someone else
+async fn add_export_boxes_to_store(store: &TestStore) {
+    let document = |id: &str, content: &str| Document {
+        id: id.into(),
+        title: format!("Title, \"{}\"", id),
+        encrypted_content: Some(content.into()),
+        created_at: "2024-01-01T00:00:00Z".into(),
+        content_type: Some("text/plain".into()),
+        size_bytes: Some(5),
+        version: Some(1),
+        s3_key: None,
+    };
+    let seeded = [
+        (
+            "export_open",
+            "export_owner",
+            false,
+            vec![document("doc_open", "b3Blbg==")],
+        ),
+        (
+            "export_locked",
+            "export_owner",
+            true,
+            vec![document("doc_locked", "c2VjcmV0")],
+        ),
+        ("export_empty", "export_owner", false, vec![]),
+        (
+            "export_other",
+            "someone_else",
+            false,
+            vec![document("doc_other", "b3RoZXI=")],
+        ),
+    ];
+    for (id, owner_id, is_locked, documents) in seeded {
+        let box_record = BoxRecord {
+            id: id.into(),
+            name: format!("Box {}", id),
+            description: "Exported box".into(),
+            is_locked,
+            locked_at: is_locked.then(now_str),
+            created_at: now_str(),
+            updated_at: now_str(),
+            owner_id: owner_id.into(),
+            owner_name: Some("Exporter".into()),
+            documents,
+            guardians: vec![],
+            unlock_instructions: None,
+            unlock_request: None,
+            version: 0,
+            shard_threshold: is_locked.then_some(1),
+            shards_fetched: None,
+            total_shards: None,
+            shards_deleted_at: None,
+            all_shards_accepted: false,
+            last_unlock_request_at: None,
+            webhook_url: None,
+            webhook_secret: None,
+            require_step_up: false,
+        };
+        match store {
+            TestStore::Mock(mock) => {
+                mock.create_box(box_record).await.unwrap();
+            }
+            TestStore::DynamoDB(dynamo) => {
+                dynamo.create_box(box_record).await.unwrap();
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_export_boxes_json() {
+    let (app, store) = create_test_app().await;
+    add_export_boxes_to_store(&store).await;
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/owned/export?format=json",
+            "export_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/json"
+    );
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+
+    // The body round-trips through the export types unchanged
+    let raw: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    let export: BoxExport = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(serde_json::to_value(&export).unwrap(), raw);
+    let reparsed: BoxExport =
+        serde_json::from_str(&serde_json::to_string(&export).unwrap()).unwrap();
+    assert_eq!(reparsed, export);
+
+    let mut ids: Vec<&str> = export.boxes.iter().map(|b| b.id.as_str()).collect();
+    ids.sort();
+    assert_eq!(ids, ["export_empty", "export_locked", "export_open"]);
+
+    // Unlocked boxes carry their documents' content; locked ones only metadata
+    let open = export.boxes.iter().find(|b| b.id == "export_open").unwrap();
+    assert_eq!(open.documents.len(), 1);
+    assert_eq!(
+        open.documents[0].encrypted_content.as_deref(),
+        Some("b3Blbg==")
+    );
+    let locked = export
+        .boxes
+        .iter()
+        .find(|b| b.id == "export_locked")
+        .unwrap();
+    assert!(locked.is_locked);
+    assert_eq!(locked.documents.len(), 1);
+    assert_eq!(locked.documents[0].title, "Title, \"doc_locked\"");
+    assert_eq!(locked.documents[0].encrypted_content, None);
+    assert!(!String::from_utf8_lossy(&bytes).contains("c2VjcmV0"));
+
+    // JSON is the default, and an owner without boxes gets an empty export
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/owned/export",
+            "nobody",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body, json!({ "boxes": [] }));
+}
+
+#[tokio::test]
+async fn test_export_boxes_csv() {
+    let (app, store) = create_test_app().await;
+    add_export_boxes_to_store(&store).await;
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/owned/export?format=csv",
+            "export_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get("content-type").unwrap(), "text/csv");
+    assert_eq!(
+        response.headers().get("content-disposition").unwrap(),
+        "attachment; filename=\"lockbox-export.csv\""
+    );
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let csv = String::from_utf8(bytes.to_vec()).unwrap();
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next().unwrap(),
+        "boxId,boxName,boxDescription,boxCreatedAt,boxUpdatedAt,isLocked,lockedAt,\
+         documentId,documentTitle,documentCreatedAt,contentType,sizeBytes,encryptedContent"
+    );
+
+    // One row per document, and one for the box without documents
+    let rows: Vec<&str> = lines.collect();
+    assert_eq!(rows.len(), 3);
+    let open_row = rows.iter().find(|r| r.starts_with("export_open,")).unwrap();
+    assert!(open_row.contains(",doc_open,\"Title, \"\"doc_open\"\"\","));
+    assert!(open_row.ends_with(",text/plain,5,b3Blbg=="));
+    let locked_row = rows
+        .iter()
+        .find(|r| r.starts_with("export_locked,"))
+        .unwrap();
+    assert!(locked_row.ends_with(",text/plain,5,"));
+    let empty_row = rows
+        .iter()
+        .find(|r| r.starts_with("export_empty,"))
+        .unwrap();
+    assert!(empty_row.ends_with(",false,,,,,,,"));
+
+    // Unknown formats are rejected
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/owned/export?format=xml",
+            "export_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_import_boxes_from_export() {
+    let (app, store) = create_test_app().await;
+    add_export_boxes_to_store(&store).await;
+
+    // Export one account's boxes and import them into another
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/owned/export",
+            "export_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    let export = response_to_json(response).await;
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned/import",
+            "import_owner",
+            Some(export),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["failed"], json!([]));
+    let box_ids = body["boxIds"].as_object().unwrap();
+    let mut old_ids: Vec<&str> = box_ids.keys().map(String::as_str).collect();
+    old_ids.sort();
+    assert_eq!(old_ids, ["export_empty", "export_locked", "export_open"]);
+
+    // New ids, owned by the importer, unlocked, with the content that was exported
+    let new_open_id = box_ids["export_open"].as_str().unwrap();
+    assert_ne!(new_open_id, "export_open");
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned/{}", new_open_id),
+            "import_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let imported = response_to_json(response).await;
+    assert_eq!(imported["box"]["ownerId"], "import_owner");
+    assert_eq!(imported["box"]["name"], "Box export_open");
+    assert_eq!(imported["box"]["documents"][0]["id"], "doc_open");
+    assert_eq!(
+        imported["box"]["documents"][0]["encryptedContent"],
+        "b3Blbg=="
+    );
+
+    // The locked box comes back unlocked, without its content-less document
+    let new_locked_id = box_ids["export_locked"].as_str().unwrap();
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned/{}", new_locked_id),
+            "import_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    let imported = response_to_json(response).await;
+    assert_eq!(imported["box"]["isLocked"], false);
+    assert!(imported["box"].get("shardThreshold").is_none());
+    assert_eq!(imported["box"]["documents"], json!([]));
+
+    // The originals are untouched
+    let originals = match &store {
+        TestStore::Mock(mock) => mock.get_boxes_by_owner("export_owner").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_boxes_by_owner("export_owner").await.unwrap(),
+    };
+    assert_eq!(originals.len(), 3);
+}
+
+#[tokio::test]
+async fn test_import_boxes_reports_document_limit_failures() {
+    let (app, store) = create_test_app().await;
+
+    let document = |id: &str, content_type: &str, size_bytes: u64| {
+        json!({
+            "id": id,
+            "title": id,
+            "createdAt": "2024-01-01T00:00:00Z",
+            "contentType": content_type,
+            "sizeBytes": size_bytes,
+            "encryptedContent": "Y29udGVudA=="
+        })
+    };
+    let payload = json!({
+        "boxes": [
+            {
+                "id": "ok_box",
+                "name": "Fine",
+                "description": "Within limits",
+                "createdAt": "2024-01-01T00:00:00Z",
+                "updatedAt": "2024-01-01T00:00:00Z",
+                "documents": [document("small", "text/plain", 8)]
+            },
+            {
+                "id": "huge_box",
+                "name": "Too big",
+                "description": "Understates a document over the size limit",
+                "createdAt": "2024-01-01T00:00:00Z",
+                "updatedAt": "2024-01-01T00:00:00Z",
+                "documents": [
+                    document("small", "text/plain", 8),
+                    {
+                        "id": "huge",
+                        "title": "huge",
+                        "createdAt": "2024-01-01T00:00:00Z",
+                        "contentType": "text/plain",
+                        "sizeBytes": 8,
+                        "encryptedContent": "A".repeat(300 * 1024)
+                    }
+                ]
+            },
+            {
+                "id": "odd_box",
+                "name": "Odd type",
+                "description": "Has a content type boxes don't accept",
+                "createdAt": "2024-01-01T00:00:00Z",
+                "updatedAt": "2024-01-01T00:00:00Z",
+                "documents": [document("script", "application/x-sh", 8)]
+            },
+            {
+                "id": "crowded_box",
+                "name": "Crowded",
+                "description": "Holds more documents than a box may",
+                "createdAt": "2024-01-01T00:00:00Z",
+                "updatedAt": "2024-01-01T00:00:00Z",
+                "documents": (0..101)
+                    .map(|i| document(&format!("doc_{}", i), "text/plain", 8))
+                    .collect::<Vec<_>>()
+            },
+            {
+                "id": "ok_box",
+                "name": "Again",
+                "description": "Same id twice",
+                "createdAt": "2024-01-01T00:00:00Z",
+                "updatedAt": "2024-01-01T00:00:00Z"
+            }
+        ]
+    });
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned/import",
+            "limit_owner",
+            Some(payload),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+
+    // The valid box is imported; the others are reported without stopping it
+    let box_ids = body["boxIds"].as_object().unwrap();
+    assert_eq!(box_ids.len(), 1);
+    assert!(box_ids.contains_key("ok_box"));
+    let failed = body["failed"].as_array().unwrap();
+    let failed_ids: Vec<&str> = failed.iter().map(|f| f["id"].as_str().unwrap()).collect();
+    assert_eq!(failed_ids, ["huge_box", "odd_box", "crowded_box", "ok_box"]);
+    assert!(failed[0]["error"]
+        .as_str()
+        .unwrap()
+        .contains("Document huge: Document is too large"));
+    assert!(failed[1]["error"]
+        .as_str()
+        .unwrap()
+        .contains("Unsupported document content type"));
+    assert!(failed[2]["error"]
+        .as_str()
+        .unwrap()
+        .contains("at most 100 documents"));
+    assert!(failed[3]["error"]
+        .as_str()
+        .unwrap()
+        .contains("more than once"));
+
+    // Nothing of the rejected boxes was created
+    let owned = match &store {
+        TestStore::Mock(mock) => mock.get_boxes_by_owner("limit_owner").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_boxes_by_owner("limit_owner").await.unwrap(),
+    };
+    assert_eq!(owned.len(), 1);
+    assert_eq!(owned[0].name, "Fine");
+
+    // Payloads that aren't an export are rejected outright
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned/import",
+            "limit_owner",
+            Some(json!({ "boxes": [{ "name": "No id" }] })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+// A locked box with documents and guardians in every state worth copying
+async fn add_duplicate_source_to_store(store: &TestStore) {
+    let guardian = |id: &str, name: &str, status: GuardianStatus, invitation_id: &str| Guardian {
+        id: id.into(),
+        name: name.into(),
+        lead_guardian: id == "dup_lead",
+        status,
+        added_at: "2024-01-01T00:00:00Z".into(),
+        invitation_id: invitation_id.into(),
+        lock_data_received_at: Some("2024-01-02T00:00:00Z".into()),
+        encrypted_shard: Some("c2hhcmQ=".into()),
+        shard_hash: Some("hash".into()),
+        shard_fetched_at: None,
+        shard_accepted_at: Some("2024-01-03T00:00:00Z".into()),
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
+    };
+    let box_record = BoxRecord {
+        id: "dup_source".into(),
+        name: "Will".into(),
+        description: "Last will".into(),
+        is_locked: true,
+        locked_at: Some(now_str()),
+        created_at: now_str(),
+        updated_at: now_str(),
+        owner_id: "dup_owner".into(),
+        owner_name: Some("Duplicator".into()),
+        documents: vec![Document {
+            id: "dup_doc".into(),
+            title: "Letter".into(),
+            encrypted_content: Some("bGV0dGVy".into()),
+            created_at: "2024-01-01T00:00:00Z".into(),
+            content_type: Some("text/plain".into()),
+            size_bytes: Some(6),
+            version: Some(4),
+            s3_key: None,
+        }],
+        guardians: vec![
+            guardian("dup_lead", "Lead", GuardianStatus::Accepted, "inv_lead"),
+            guardian(
+                "dup_rejected",
+                "Rejected",
+                GuardianStatus::Rejected,
+                "inv_rej",
+            ),
+            guardian("", "Placeholder", GuardianStatus::Invited, "inv_open"),
+        ],
+        unlock_instructions: Some("Call the lawyer".into()),
+        unlock_request: None,
+        version: 3,
+        shard_threshold: Some(1),
+        shards_fetched: None,
+        total_shards: Some(1),
+        shards_deleted_at: None,
+        all_shards_accepted: true,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
+    };
+    match store {
+        TestStore::Mock(mock) => {
+            mock.create_box(box_record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.create_box(box_record).await.unwrap();
+        }
+    }
+}
+
+// The app with an in-memory invitation store, for handlers that issue invitations
+fn with_invitations(app: Router) -> (Router, Arc<MockInvitationStore>) {
+    let invitations = Arc::new(MockInvitationStore::new());
+    let app = app.layer(Extension(invitations.clone() as SharedInvitationStore));
+    (app, invitations)
+}
+
+#[tokio::test]
+async fn test_duplicate_box() {
+    let (app, store) = create_test_app().await;
+    let (app, invitations) = with_invitations(app);
+    add_duplicate_source_to_store(&store).await;
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned/dup_source/duplicate",
+            "dup_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response_to_json(response).await;
+    let copy = &body["box"];
+
+    assert_ne!(copy["id"], "dup_source");
+    assert_eq!(copy["name"], "Will Copy");
+    assert_eq!(copy["description"], "Last will");
+    assert_eq!(copy["unlockInstructions"], "Call the lawyer");
+    assert_eq!(copy["ownerId"], "dup_owner");
+    assert_eq!(copy["isLocked"], false);
+    assert!(copy["documents"].as_array().unwrap().is_empty());
+
+    // Guardians come back as placeholders for fresh invitations, without
+    // shards; those who declined the original are left out
+    let guardians = copy["guardians"].as_array().unwrap();
+    let names: Vec<&str> = guardians
+        .iter()
+        .map(|g| g["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["Lead", "Placeholder"]);
+    assert_eq!(guardians[0]["leadGuardian"], true);
+    for guardian in guardians {
+        assert_eq!(guardian["id"], "");
+        assert_eq!(guardian["status"], "invited");
+        assert!(guardian["encryptedShard"].is_null());
+        assert!(guardian["shardAcceptedAt"].is_null());
+
+        let invitation_id = guardian["invitationId"].as_str().unwrap();
+        assert!(!["", "inv_lead", "inv_open"].contains(&invitation_id));
+        let invitation = invitations.get_invitation(invitation_id).await.unwrap();
+        assert_eq!(invitation.box_id, copy["id"].as_str().unwrap());
+        assert_eq!(invitation.invited_name, guardian["name"].as_str().unwrap());
+        assert_eq!(
+            invitation.is_lead_guardian,
+            guardian["leadGuardian"] == true
+        );
+        assert_eq!(invitation.creator_id, "dup_owner");
+        assert_eq!(invitation.box_name.as_deref(), Some("Will Copy"));
+    }
+
+    // The original's guardians aren't guardians of the copy until they accept
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/guardian",
+            "dup_lead",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    let box_ids: Vec<&str> = body["boxes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| b["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(box_ids, ["dup_source"]);
+
+    // A name at the limit is shortened to leave room for the suffix
+    let long_name = "W".repeat(MAX_NAME_CHARS);
+    match &store {
+        TestStore::Mock(mock) => {
+            let mut source = mock.get_box("dup_source").await.unwrap();
+            source.name = long_name.clone();
+            mock.update_box(source).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            let mut source = dynamo.get_box_consistent("dup_source").await.unwrap();
+            source.name = long_name.clone();
+            dynamo.update_box(source).await.unwrap();
+        }
+    }
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned/dup_source/duplicate",
+            "dup_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response_to_json(response).await;
+    let name = body["box"]["name"].as_str().unwrap();
+    assert_eq!(name.chars().count(), MAX_NAME_CHARS);
+    assert_eq!(name, format!("{} Copy", &long_name[..MAX_NAME_CHARS - 5]));
+
+    // The original is untouched
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/owned/dup_source",
+            "dup_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    let original = response_to_json(response).await;
+    assert_eq!(original["box"]["isLocked"], true);
+    assert_eq!(original["box"]["guardians"].as_array().unwrap().len(), 3);
+
+    // Only the owner can duplicate a box
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned/dup_source/duplicate",
+            "someone_else",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned/missing_box/duplicate",
+            "dup_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_replay_box_locked() {
+    std::env::set_var("TEST_SNS", "true");
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    add_duplicate_source_to_store(&store).await;
+    let locked_at = match &store {
+        TestStore::Mock(mock) => mock.get_box("dup_source").await.unwrap().locked_at,
+        TestStore::DynamoDB(dynamo) => dynamo.get_box("dup_source").await.unwrap().locked_at,
+    };
+
+    // Owners can't re-drive notifications, only admins
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/admin/dup_source/replay-locked",
+            "dup_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let mut request = create_admin_test_request(
+        "POST",
+        "/boxes/admin/dup_source/replay-locked",
+        "operator",
+        None,
+    );
+    request
+        .headers_mut()
+        .insert("x-request-id", HeaderValue::from_static("replay-trace"));
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["boxId"], "dup_source");
+
+    // The event is rebuilt from the stored box, keeping the original lock
+    // time; the placeholder guardian has no one to notify
+    let published = test_published_events()
+        .into_iter()
+        .find(|e| e["request_id"] == "replay-trace")
+        .expect("box_locked event should be published again");
+    assert_eq!(published, body["event"]);
+    let event: BoxLockedEvent = serde_json::from_value(published).unwrap();
+    assert_eq!(event.event_type, BoxLockedEvent::EVENT_TYPE);
+    assert_eq!(event.box_id, "dup_source");
+    assert_eq!(event.box_name, "Will");
+    assert_eq!(event.owner_name.as_deref(), Some("Duplicator"));
+    assert_eq!(event.guardian_ids, ["dup_lead", "dup_rejected"]);
+    assert_eq!(Some(event.timestamp), locked_at);
+
+    // An unlocked box never sent one
+    let response = app
+        .clone()
+        .oneshot(create_admin_test_request(
+            "POST",
+            "/boxes/admin/box_1/replay-locked",
+            "operator",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let response = app
+        .oneshot(create_admin_test_request(
+            "POST",
+            "/boxes/admin/no_such_box/replay-locked",
+            "operator",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_backfill_guardian_index() {
+    // Only the mock can drop lookup rows to stand in for boxes saved before
+    // the lookup existed
+    let store = Arc::new(MockBoxStore::new());
+    add_test_data_to_store(&TestStore::Mock(store.clone())).await;
+    add_duplicate_source_to_store(&TestStore::Mock(store.clone())).await;
+    store.clear_guardian_index();
+    let app = routes::create_router_with_store(store.clone(), "");
+
+    let guardian_box_count = |app: Router| async move {
+        let response = app
+            .oneshot(create_test_request(
+                "GET",
+                "/boxes/guardian",
+                "dup_lead",
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        response_to_json(response).await["boxes"]
+            .as_array()
+            .unwrap()
+            .len()
+    };
+    assert_eq!(guardian_box_count(app.clone()).await, 0);
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/admin/guardian-index/backfill",
+            "dup_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .clone()
+        .oneshot(create_admin_test_request(
+            "POST",
+            "/boxes/admin/guardian-index/backfill",
+            "operator",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["boxesIndexed"], 3);
+    assert!(body["nextCursor"].is_null());
+    assert_eq!(guardian_box_count(app.clone()).await, 1);
+
+    // Running it again is harmless
+    let response = app
+        .clone()
+        .oneshot(create_admin_test_request(
+            "POST",
+            "/boxes/admin/guardian-index/backfill",
+            "operator",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(guardian_box_count(app.clone()).await, 1);
+
+    let response = app
+        .oneshot(create_admin_test_request(
+            "POST",
+            "/boxes/admin/guardian-index/backfill?cursor=not-a-cursor",
+            "operator",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_duplicate_box_with_documents() {
+    let (app, store) = create_test_app().await;
+    let (app, _) = with_invitations(app);
+    add_duplicate_source_to_store(&store).await;
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned/dup_source/duplicate?includeDocuments=true",
+            "dup_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response_to_json(response).await;
+    let copy_id = body["box"]["id"].as_str().unwrap().to_string();
+
+    let documents = body["box"]["documents"].as_array().unwrap();
+    assert_eq!(documents.len(), 1);
+    assert_eq!(documents[0]["id"], "dup_doc");
+    assert_eq!(documents[0]["title"], "Letter");
+    assert_eq!(documents[0]["encryptedContent"], "bGV0dGVy");
+    assert_eq!(documents[0]["version"], 1);
+
+    // Both boxes now hold the document
+    let owned = match &store {
+        TestStore::Mock(mock) => mock.get_boxes_by_owner("dup_owner").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_boxes_by_owner("dup_owner").await.unwrap(),
+    };
+    assert_eq!(owned.len(), 2);
+    let stored_copy = owned.iter().find(|b| b.id == copy_id).unwrap();
+    assert_eq!(stored_copy.documents.len(), 1);
+    assert!(!stored_copy.is_locked);
+    assert_eq!(stored_copy.shard_threshold, None);
+}
+
+#[tokio::test]
+async fn test_get_boxes_locked_range() {
+    let (app, store) = create_test_app().await;
+
+    // One unlocked box and three locked 30, 10 and 1 days ago
+    let now = chrono::Utc::now();
+    let days_ago = |d: i64| now - chrono::Duration::days(d);
+    let seeded = [
+        ("range_unlocked", None),
+        ("range_30d", Some(days_ago(30))),
+        ("range_10d", Some(days_ago(10))),
+        ("range_1d", Some(days_ago(1))),
+    ];
+    for (id, locked_at) in seeded {
+        let box_record = BoxRecord {
+            id: id.into(),
+            name: id.into(),
+            description: "Dashboard box".into(),
+            is_locked: locked_at.is_some(),
+            locked_at: locked_at.map(|at| at.to_rfc3339()),
+            created_at: now_str(),
+            updated_at: now_str(),
+            owner_id: "dashboard_owner".into(),
+            owner_name: None,
+            documents: vec![],
+            guardians: vec![],
+            unlock_instructions: None,
+            unlock_request: None,
+            version: 0,
+            shard_threshold: None,
+            shards_fetched: None,
+            total_shards: None,
+            shards_deleted_at: None,
+            all_shards_accepted: false,
+            last_unlock_request_at: None,
+            webhook_url: None,
+            webhook_secret: None,
+            require_step_up: false,
+        };
+        match &store {
+            TestStore::Mock(mock) => {
+                mock.create_box(box_record).await.unwrap();
+            }
+            TestStore::DynamoDB(dynamo) => {
+                dynamo.create_box(box_record).await.unwrap();
+            }
+        }
+    }
+
+    // Timestamps in Z form so they need no URL encoding
+    let param = |d: i64| days_ago(d).to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+    let list = |query: String| {
+        let app = app.clone();
+        async move {
+            let response = app
+                .oneshot(create_test_request(
+                    "GET",
+                    &format!("/boxes/owned{}", query),
+                    "dashboard_owner",
+                    None,
+                ))
+                .await
+                .unwrap();
+            let status = response.status();
+            if status != StatusCode::OK {
+                return (status, vec![]);
+            }
+            let body = response_to_json(response).await;
+            let mut ids: Vec<String> = body["boxes"]
+                .as_array()
+                .map(|boxes| {
+                    boxes
+                        .iter()
+                        .map(|b| b["id"].as_str().unwrap().to_string())
+                        .collect()
+                })
+                .unwrap_or_default();
+            ids.sort();
+            (status, ids)
+        }
+    };
+
+    // No bounds returns everything, including the unlocked box
+    let (status, ids) = list(String::new()).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ids.len(), 4);
+
+    let (status, ids) = list(format!("?lockedAfter={}", param(15))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ids, vec!["range_10d", "range_1d"]);
+
+    let (status, ids) = list(format!("?lockedBefore={}", param(5))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ids, vec!["range_10d", "range_30d"]);
+
+    let (status, ids) = list(format!(
+        "?lockedAfter={}&lockedBefore={}",
+        param(15),
+        param(5)
+    ))
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ids, vec!["range_10d"]);
+
+    // Inverted ranges and unparseable dates are rejected
+    let (status, _) = list(format!(
+        "?lockedAfter={}&lockedBefore={}",
+        param(5),
+        param(15)
+    ))
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = list("?lockedAfter=yesterday".to_string()).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
 }
 
 #[tokio::test]
@@ -384,8 +3131,8 @@ async fn test_get_box_success() {
 
     // Get the box directly from the store for comparison
     let store_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box(box_id).await.unwrap(),
     };
 
     // Verify that API and store data match
@@ -401,6 +3148,110 @@ async fn test_get_box_success() {
     );
 }
 
+#[tokio::test]
+async fn test_watch_box_returns_newer_box_immediately() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "box_1";
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}", box_id),
+            "user_1",
+            Some(json!({ "name": "Updated Before Watching" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned/{}/watch?since=0", box_id),
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_response = response_to_json(response).await;
+    assert_eq!(json_response["box"]["name"], "Updated Before Watching");
+    assert_eq!(json_response["box"]["version"], 1);
+}
+
+#[tokio::test]
+async fn test_watch_box_unblocks_on_update() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "box_1";
+
+    let current = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+
+    let waiter = tokio::spawn(app.clone().oneshot(create_test_request(
+        "GET",
+        &format!("/boxes/owned/{}/watch?since={}", box_id, current.version),
+        "user_1",
+        None,
+    )));
+
+    // Give the watch request time to find the box unchanged and start waiting
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    assert!(!waiter.is_finished());
+
+    let response = app
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}", box_id),
+            "user_1",
+            Some(json!({ "name": "Renamed While Watched" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = tokio::time::timeout(std::time::Duration::from_secs(5), waiter)
+        .await
+        .expect("watch request should return after the update")
+        .unwrap()
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_response = response_to_json(response).await;
+    assert_eq!(json_response["box"]["name"], "Renamed While Watched");
+    assert_eq!(json_response["box"]["version"], current.version + 1);
+}
+
+#[tokio::test]
+async fn test_watch_box_times_out_and_checks_owner() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "box_1";
+    let timeout = std::time::Duration::from_millis(50);
+
+    let unchanged = match &store {
+        TestStore::Mock(mock) => wait_for_box_change(&**mock, box_id, "user_1", 0, timeout).await,
+        TestStore::DynamoDB(dynamo) => {
+            wait_for_box_change(&**dynamo, box_id, "user_1", 0, timeout).await
+        }
+    };
+    assert!(unchanged.unwrap().is_none());
+
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned/{}/watch?since=0", box_id),
+            "user_2",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
 #[tokio::test]
 async fn test_get_box_not_found() {
     // Setup with test data
@@ -499,16 +3350,10 @@ async fn test_create_box() {
     let box_id = body["box"]["id"].as_str().unwrap().to_string();
     assert_eq!(body["box"]["name"].as_str().unwrap(), "New Test Box");
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Verify directly in the store
     let stored_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(&box_id).await.unwrap(),
     };
 
     assert_eq!(stored_box.name, "New Test Box");
@@ -516,6 +3361,30 @@ async fn test_create_box() {
     assert_eq!(stored_box.owner_id, "new_user");
 }
 
+#[tokio::test]
+async fn test_create_box_refuses_existing_id() {
+    let (_app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let mut colliding = create_test_boxes(&now_str()).remove(0);
+    colliding.name = "Impostor".into();
+    colliding.owner_id = "someone_else".into();
+
+    let result = match &store {
+        TestStore::Mock(mock) => mock.create_box(colliding).await,
+        TestStore::DynamoDB(dynamo) => dynamo.create_box(colliding).await,
+    };
+    assert!(matches!(result, Err(StoreError::AlreadyExists(_))));
+
+    // The original box is untouched
+    let stored_box = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert_eq!(stored_box.name, "Test Box 1");
+    assert_eq!(stored_box.owner_id, "user_1");
+}
+
 #[tokio::test]
 async fn test_create_box_invalid_payload() {
     // Setup
@@ -538,6 +3407,184 @@ async fn test_create_box_invalid_payload() {
     assert!(response.status().is_client_error());
 }
 
+#[tokio::test]
+async fn test_create_box_normalizes_names() {
+    let (app, _store) = create_test_app().await;
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned",
+            "new_user",
+            Some(json!({
+                "name": "  Family\u{7} Papers\n",
+                "description": "Kept as written",
+                "ownerName": "\tAlice "
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response_to_json(response).await;
+    assert_eq!(body["box"]["name"], "Family Papers");
+    assert_eq!(body["box"]["ownerName"], "Alice");
+
+    let too_long = "a".repeat(101);
+    for (payload, message) in [
+        (
+            json!({ "name": " \n ", "description": "" }),
+            "name must not be empty",
+        ),
+        (
+            json!({ "name": too_long, "description": "" }),
+            "name must be at most 100 characters",
+        ),
+        (
+            json!({ "name": "Box", "description": "", "ownerName": "\u{0}" }),
+            "ownerName must not be empty",
+        ),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "POST",
+                "/boxes/owned",
+                "new_user",
+                Some(payload),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = response_to_json(response).await;
+        assert_eq!(body["error"], message);
+    }
+}
+
+#[tokio::test]
+async fn test_box_names_are_normalized_on_every_write() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let too_long = "a".repeat(101);
+    let send = |method: &'static str, path: &'static str, payload: serde_json::Value| {
+        let app = app.clone();
+        async move {
+            app.oneshot(create_test_request(method, path, "user_1", Some(payload)))
+                .await
+                .unwrap()
+        }
+    };
+
+    // Renaming a box
+    let response = send(
+        "PATCH",
+        "/boxes/owned/box_1",
+        json!({ "name": " Renamed\u{7} Box\n" }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["box"]["name"], "Renamed Box");
+    let response = send("PATCH", "/boxes/owned/box_1", json!({ "name": too_long })).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Replacing the guardians
+    let guardian = |name: &str| {
+        json!({
+            "id": "",
+            "name": name,
+            "leadGuardian": false,
+            "status": "invited",
+            "addedAt": "2023-01-01T12:00:00Z",
+            "invitationId": "inv-normalized"
+        })
+    };
+    let response = send(
+        "PUT",
+        "/boxes/owned/box_1/guardians",
+        json!({ "guardians": [guardian("\tGuardian\u{0} C ")] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["guardians"][0]["name"], "Guardian C");
+    let response = send(
+        "PUT",
+        "/boxes/owned/box_1/guardians",
+        json!({ "guardians": [guardian(&too_long)] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Importing boxes
+    let exported = |id: &str, name: &str| {
+        json!({
+            "id": id,
+            "name": name,
+            "description": "Imported",
+            "createdAt": "2024-01-01T00:00:00Z",
+            "updatedAt": "2024-01-01T00:00:00Z"
+        })
+    };
+    let response = send(
+        "POST",
+        "/boxes/owned/import",
+        json!({
+            "boxes": [
+                exported("messy_box", " Messy\u{1b} Name "),
+                exported("long_box", &too_long)
+            ]
+        }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["failed"][0]["id"], "long_box");
+    let new_id = body["boxIds"]["messy_box"].as_str().unwrap();
+    let imported = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(new_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(new_id).await.unwrap(),
+    };
+    assert_eq!(imported.name, "Messy Name");
+}
+
+#[tokio::test]
+async fn test_create_box_reports_every_invalid_field() {
+    let (app, _store) = create_test_app().await;
+
+    let response = app
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned",
+            "new_user",
+            Some(json!({
+                "name": "\t",
+                "description": "",
+                "ownerName": "o".repeat(101)
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = response_to_json(response).await;
+    assert_eq!(body["code"], "VALIDATION");
+    assert_eq!(
+        body["fields"],
+        json!([
+            { "field": "name", "code": "EMPTY", "message": "name must not be empty" },
+            {
+                "field": "ownerName",
+                "code": "TOO_LONG",
+                "message": "ownerName must be at most 100 characters"
+            }
+        ])
+    );
+    assert_eq!(
+        body["error"],
+        "name must not be empty; ownerName must be at most 100 characters"
+    );
+}
+
 #[tokio::test]
 async fn test_get_box_not_owned() {
     let (app, store) = create_test_app().await;
@@ -608,16 +3655,10 @@ async fn test_update_box() {
     );
     assert_eq!(update_body["box"]["isLocked"].as_bool().unwrap(), true);
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Verify directly in the store
     let stored_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
     };
 
     assert_eq!(stored_box.name, "Updated Box Name");
@@ -636,8 +3677,8 @@ async fn test_update_box_partial() {
     // Get a box to update directly from the store
     let box_id = "box_1";
     let initial_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box(box_id).await.unwrap(),
     };
 
     let initial_description = initial_box.description.clone();
@@ -661,16 +3702,10 @@ async fn test_update_box_partial() {
 
     assert_eq!(response.status(), StatusCode::OK);
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Get the box directly from store to confirm partial update
     let updated_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
     };
 
     // Name should be updated, description should remain the same
@@ -691,8 +3726,8 @@ async fn test_update_box_not_owned() {
 
     // Get the initial state directly from store
     let initial_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box(box_id).await.unwrap(),
     };
 
     let initial_name = initial_box.name.clone();
@@ -717,24 +3752,119 @@ async fn test_update_box_not_owned() {
         ))
         .await
         .unwrap();
-
+
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Verify the box is unchanged directly from the store
+    let final_box = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+
+    // Box should remain unchanged
+    assert_eq!(final_box.name, initial_name);
+    assert_eq!(final_box.description, initial_description);
+}
+
+#[tokio::test]
+async fn test_register_webhook() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "box_1";
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            &format!("/boxes/owned/{}/webhook", box_id),
+            "user_1",
+            Some(json!({ "url": "https://hooks.example.com/lockbox" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_response = response_to_json(response).await;
+    assert_eq!(
+        json_response["webhookUrl"],
+        "https://hooks.example.com/lockbox"
+    );
+    let secret = json_response["webhookSecret"].as_str().unwrap().to_string();
+    assert_eq!(secret.len(), 64);
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert_eq!(stored.webhook_secret.as_deref(), Some(secret.as_str()));
+
+    // The secret is only returned at registration
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned/{}", box_id),
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_response = response_to_json(response).await;
+    assert_eq!(
+        json_response["box"]["webhookUrl"],
+        "https://hooks.example.com/lockbox"
+    );
+    assert!(json_response["box"].get("webhookSecret").is_none());
+}
+
+#[tokio::test]
+async fn test_register_webhook_rejects_invalid_requests() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "box_1";
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            &format!("/boxes/owned/{}/webhook", box_id),
+            "user_1",
+            Some(json!({ "url": "http://hooks.example.com/lockbox" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            &format!("/boxes/owned/{}/webhook", box_id),
+            "user_1",
+            Some(json!({ "url": "https://169.254.169.254/latest/meta-data" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            &format!("/boxes/owned/{}/webhook", box_id),
+            "user_2",
+            Some(json!({ "url": "https://hooks.example.com/lockbox" })),
+        ))
+        .await
+        .unwrap();
     assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
-    // Verify the box is unchanged directly from the store
-    let final_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
     };
-
-    // Box should remain unchanged
-    assert_eq!(final_box.name, initial_name);
-    assert_eq!(final_box.description, initial_description);
+    assert!(stored.webhook_url.is_none());
+    assert!(stored.webhook_secret.is_none());
 }
 
 #[tokio::test]
@@ -769,14 +3899,14 @@ async fn test_delete_box() {
     // Verify box has been deleted by trying to get it from the store
     match &store {
         TestStore::Mock(mock) => {
-            let result = mock.get_box(&box_id).await;
+            let result = mock.get_box(box_id).await;
             assert!(
                 result.is_err() || result.unwrap().id.is_empty(),
                 "Box should not exist in store after deletion"
             );
         }
         TestStore::DynamoDB(dynamo) => {
-            let result = dynamo.get_box(&box_id).await;
+            let result = dynamo.get_box(box_id).await;
             assert!(
                 result.is_err() || result.unwrap().id.is_empty(),
                 "Box should not exist in store after deletion"
@@ -840,16 +3970,10 @@ async fn test_update_box_add_documents() {
 
     assert_eq!(response.status(), StatusCode::OK);
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Get the updated box directly from the store
     let updated_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
     };
 
     // Check that the document was added
@@ -906,18 +4030,12 @@ async fn test_update_box_add_guardians() {
         .await
         .unwrap();
 
-    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
-
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
     // Get the box from the store and verify no guardian was added
     let box_after_update = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
     };
 
     assert!(
@@ -971,16 +4089,10 @@ async fn test_update_box_lock() {
     // Verify update was successful with the real status code
     assert_eq!(status, StatusCode::OK);
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Get the box from store to verify the update was received
     let updated_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
     };
 
     // Verify is_locked was updated
@@ -1016,16 +4128,10 @@ async fn test_update_box_unlock_instructions() {
     // Verify update was successful
     assert_eq!(response.status(), StatusCode::OK);
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Get the box from store to verify the update
     let updated_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
     };
 
     // Verify unlock_instructions was updated
@@ -1036,6 +4142,94 @@ async fn test_update_box_unlock_instructions() {
     );
 }
 
+#[tokio::test]
+async fn test_update_unlock_instructions_on_locked_box() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "box_1";
+
+    let mut box_record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    box_record.is_locked = true;
+    box_record.locked_at = Some(now_str());
+    let locked = match &store {
+        TestStore::Mock(mock) => mock.update_box(box_record).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record).await.unwrap(),
+    };
+
+    let instructions = "Call Sam on 555-0100 before fetching your shard.";
+
+    // The general update still treats the locked box as immutable
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}", box_id),
+            "user_1",
+            Some(json!({ "unlockInstructions": instructions })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Blank and oversized instructions are refused without touching the box
+    let too_long = "a".repeat(MAX_UNLOCK_INSTRUCTIONS_CHARS + 1);
+    for (value, code) in [(" \n\t ", "EMPTY"), (too_long.as_str(), "TOO_LONG")] {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "PATCH",
+                &format!("/boxes/owned/{}/unlock-instructions", box_id),
+                "user_1",
+                Some(json!({ "unlockInstructions": value })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = response_to_json(response).await;
+        assert_eq!(body["code"], "VALIDATION");
+        assert_eq!(body["fields"][0]["field"], "unlockInstructions");
+        assert_eq!(body["fields"][0]["code"], code);
+    }
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}/unlock-instructions", box_id),
+            "user_1",
+            Some(json!({ "unlockInstructions": format!("  {}\n", instructions) })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["box"]["unlockInstructions"], instructions);
+
+    let updated = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert_eq!(updated.unlock_instructions.as_deref(), Some(instructions));
+    assert!(updated.is_locked);
+    assert_eq!(updated.name, locked.name);
+    assert!(updated.version > locked.version);
+
+    // Only the owner can change them
+    let response = app
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}/unlock-instructions", box_id),
+            "user_2",
+            Some(json!({ "unlockInstructions": null })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
 #[tokio::test]
 async fn test_update_box_clear_unlock_instructions() {
     // Setup with mock data
@@ -1049,8 +4243,8 @@ async fn test_update_box_clear_unlock_instructions() {
 
     // Get the initial box directly from store
     let mut box_record = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box(box_id).await.unwrap(),
     };
 
     // Directly set unlock instructions in the box record
@@ -1063,16 +4257,10 @@ async fn test_update_box_clear_unlock_instructions() {
         TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record.clone()).await.unwrap(),
     };
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Verify the instructions were set by checking directly in the store
     let box_with_instructions = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
     };
 
     assert!(box_with_instructions.unlock_instructions.is_some());
@@ -1098,16 +4286,10 @@ async fn test_update_box_clear_unlock_instructions() {
     // Verify update was successful
     assert_eq!(response.status(), StatusCode::OK);
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Get the box from store to verify the update
     let updated_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
     };
 
     // Verify unlock_instructions was cleared
@@ -1131,8 +4313,8 @@ async fn test_update_single_guardian() {
 
     // First get the box directly from store
     let mut box_record = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box(box_id).await.unwrap(),
     };
 
     // Add a guardian directly to the box
@@ -1149,6 +4331,9 @@ async fn test_update_single_guardian() {
         shard_hash: None,
         shard_fetched_at: None,
         shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
     };
 
     box_record.guardians.push(guardian_record);
@@ -1159,16 +4344,10 @@ async fn test_update_single_guardian() {
         TestStore::DynamoDB(dynamo) => dynamo.update_box(box_record.clone()).await.unwrap(),
     };
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Verify the guardian was added directly in the store
     let box_with_guardian = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
     };
 
     let initial_guardian = box_with_guardian
@@ -1189,28 +4368,444 @@ async fn test_update_single_guardian() {
             "addedAt": "2023-01-01T12:00:00Z",
             "invitationId": "inv-guardian-a"
         }
-    });
+    });
+
+    // Make the request to update the guardian
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}/guardian", box_id),
+            "user_1", // Box owner
+            Some(updated_guardian),
+        ))
+        .await
+        .unwrap();
+
+    // Verify update was successful
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Since the request is expected to fail, we don't need to check the response body or store contents
+    // Just return early
+    return;
+
+    // The rest of the test is skipped as the request is expected to fail
+}
+
+// Seeds box_1 with an accepted guardian and PATCHes it back to "invited",
+// optionally stating the status the client believes is stored
+async fn patch_guardian_with_expected_status(expected_status: Option<&str>) -> StatusCode {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "box_1";
+    upsert_guardians(
+        &store,
+        box_id,
+        vec![Guardian {
+            id: "guardian_cond".into(),
+            name: "Guardian Cond".into(),
+            lead_guardian: false,
+            status: GuardianStatus::Accepted,
+            added_at: "2023-01-01T12:00:00Z".into(),
+            invitation_id: "inv-guardian-cond".into(),
+            lock_data_received_at: None,
+            encrypted_shard: None,
+            shard_hash: None,
+            shard_fetched_at: None,
+            shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
+        }],
+    )
+    .await;
+
+    let mut payload = json!({
+        "guardian": {
+            "id": "guardian_cond",
+            "name": "Guardian Cond",
+            "leadGuardian": false,
+            "status": "invited",
+            "addedAt": "2023-01-01T12:00:00Z",
+            "invitationId": "inv-guardian-cond"
+        }
+    });
+    if let Some(expected) = expected_status {
+        payload["expectedStatus"] = json!(expected);
+    }
+
+    let response = app
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}/guardian", box_id),
+            "user_1",
+            Some(payload),
+        ))
+        .await
+        .unwrap();
+    response.status()
+}
+
+#[tokio::test]
+async fn test_update_guardian_expected_status_matches() {
+    assert_eq!(
+        patch_guardian_with_expected_status(Some("accepted")).await,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn test_update_guardian_expected_status_mismatch_conflicts() {
+    assert_eq!(
+        patch_guardian_with_expected_status(Some("viewed")).await,
+        StatusCode::CONFLICT
+    );
+}
+
+#[tokio::test]
+async fn test_update_guardian_without_expected_status_overwrites() {
+    assert_eq!(
+        patch_guardian_with_expected_status(None).await,
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn test_replace_guardians() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "box_1";
+    upsert_guardians(
+        &store,
+        box_id,
+        vec![Guardian {
+            id: "guardian_old".into(),
+            name: "Old Guardian".into(),
+            lead_guardian: true,
+            status: GuardianStatus::Accepted,
+            added_at: "2023-01-01T12:00:00Z".into(),
+            invitation_id: "inv-old".into(),
+            lock_data_received_at: None,
+            encrypted_shard: None,
+            shard_hash: None,
+            shard_fetched_at: None,
+            shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
+        }],
+    )
+    .await;
+
+    let payload = json!({
+        "guardians": [
+            {
+                "id": "guardian_a",
+                "name": "  Guardian A ",
+                "leadGuardian": true,
+                "status": "accepted",
+                "addedAt": "2023-01-01T12:00:00Z",
+                "invitationId": "inv-a"
+            },
+            {
+                "id": "",
+                "name": "Guardian B",
+                "leadGuardian": false,
+                "status": "invited",
+                "addedAt": "2023-01-01T12:00:00Z",
+                "invitationId": "inv-b"
+            },
+            {
+                "id": "",
+                "name": "Guardian C",
+                "leadGuardian": false,
+                "status": "invited",
+                "addedAt": "2023-01-01T12:00:00Z",
+                "invitationId": "inv-c"
+            }
+        ]
+    });
+
+    let response = app
+        .oneshot(create_test_request(
+            "PUT",
+            &format!("/boxes/owned/{}/guardians", box_id),
+            "user_1",
+            Some(payload),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = response_to_json(response).await;
+    let guardians = body["guardians"].as_array().unwrap();
+    assert_eq!(guardians.len(), 3);
+    assert_eq!(guardians[0]["name"], "Guardian A");
+    assert!(body["updatedAt"].is_string());
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box(box_id).await.unwrap(),
+    };
+    let invitation_ids: Vec<&str> = stored
+        .guardians
+        .iter()
+        .map(|g| g.invitation_id.as_str())
+        .collect();
+    assert_eq!(invitation_ids, vec!["inv-a", "inv-b", "inv-c"]);
+}
+
+#[tokio::test]
+async fn test_replace_guardians_keeps_server_owned_state() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let accepted_at = "2023-01-02T12:00:00Z".to_string();
+    upsert_guardians(
+        &store,
+        "box_1",
+        vec![Guardian {
+            id: "g1".into(),
+            name: "Guardian One".into(),
+            lead_guardian: false,
+            status: GuardianStatus::Accepted,
+            added_at: "2023-01-01T12:00:00Z".into(),
+            invitation_id: "inv-g1".into(),
+            lock_data_received_at: Some(accepted_at.clone()),
+            encrypted_shard: None,
+            shard_hash: None,
+            shard_fetched_at: None,
+            shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: Some(accepted_at.clone()),
+        }],
+    )
+    .await;
+
+    // The owner tries to reset the existing guardian and to hand the new one
+    // an accepted shard
+    let server_state = json!({
+        "lockDataReceivedAt": null,
+        "encryptedShard": test_shard("g2"),
+        "shardHash": test_shard_hash("g2"),
+        "shardFetchedAt": "2023-01-03T12:00:00Z",
+        "shardAcceptedAt": "2023-01-03T12:00:00Z",
+        "shardCommit": "forged",
+        "shardFirstFetchedAt": "2023-01-03T12:00:00Z",
+        "lastRemindedAt": null
+    });
+    let guardian = |id: &str, name: &str, status: &str| {
+        let mut guardian = json!({
+            "id": id,
+            "name": name,
+            "leadGuardian": id == "g1",
+            "status": status,
+            "addedAt": "2020-01-01T00:00:00Z",
+            "invitationId": format!("inv-{}", id)
+        });
+        guardian
+            .as_object_mut()
+            .unwrap()
+            .extend(server_state.as_object().unwrap().clone());
+        guardian
+    };
+
+    let response = app
+        .oneshot(create_test_request(
+            "PUT",
+            "/boxes/owned/box_1/guardians",
+            "user_1",
+            Some(json!({
+                "guardians": [
+                    guardian("g1", "Renamed", "invited"),
+                    guardian("g2", "Guardian Two", "accepted")
+                ]
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box("box_1").await.unwrap(),
+    };
+
+    // The owner's edits apply, the stored state stays
+    let g1 = &stored.guardians[0];
+    assert_eq!(g1.name, "Renamed");
+    assert!(g1.lead_guardian);
+    assert_eq!(g1.status, GuardianStatus::Accepted);
+    assert_eq!(g1.added_at, "2023-01-01T12:00:00Z");
+    assert_eq!(g1.lock_data_received_at, Some(accepted_at.clone()));
+    assert_eq!(g1.last_reminded_at, Some(accepted_at));
+    assert_eq!(g1.encrypted_shard, None);
+    assert_eq!(g1.shard_commit, None);
+
+    // The new guardian starts out invited with no shard
+    let g2 = &stored.guardians[1];
+    assert_eq!(g2.id, "g2");
+    assert_eq!(g2.name, "Guardian Two");
+    assert_eq!(g2.status, GuardianStatus::Invited);
+    assert_ne!(g2.added_at, "2020-01-01T00:00:00Z");
+    assert_eq!(g2.encrypted_shard, None);
+    assert_eq!(g2.shard_hash, None);
+    assert_eq!(g2.shard_fetched_at, None);
+    assert_eq!(g2.shard_accepted_at, None);
+    assert_eq!(g2.shard_commit, None);
+    assert_eq!(g2.shard_first_fetched_at, None);
+}
+
+#[tokio::test]
+async fn test_replace_guardians_rejects_invalid_sets() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let guardian = |id: &str, invitation_id: &str, lead: bool| {
+        json!({
+            "id": id,
+            "name": "Guardian",
+            "leadGuardian": lead,
+            "status": "invited",
+            "addedAt": "2023-01-01T12:00:00Z",
+            "invitationId": invitation_id
+        })
+    };
+
+    let invalid_sets = [
+        // Same user id twice
+        vec![
+            guardian("dup", "inv-1", false),
+            guardian("dup", "inv-2", false),
+        ],
+        // Same invitation twice
+        vec![guardian("a", "inv-1", false), guardian("b", "inv-1", false)],
+        // Two lead guardians
+        vec![guardian("a", "inv-1", true), guardian("b", "inv-2", true)],
+    ];
+
+    for guardians in invalid_sets {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "PUT",
+                "/boxes/owned/box_1/guardians",
+                "user_1",
+                Some(json!({ "guardians": guardians })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box("box_1").await.unwrap(),
+    };
+    assert!(
+        stored.guardians.is_empty(),
+        "Rejected sets must not be saved"
+    );
+}
+
+#[tokio::test]
+async fn test_guardian_limit() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let app = app.layer(Extension(MaxGuardians(3)));
+
+    let guardian = |id: &str| Guardian {
+        id: id.into(),
+        name: format!("Guardian {}", id),
+        lead_guardian: false,
+        status: GuardianStatus::Accepted,
+        added_at: now_str(),
+        invitation_id: format!("inv-{}", id),
+        lock_data_received_at: None,
+        encrypted_shard: None,
+        shard_hash: None,
+        shard_fetched_at: None,
+        shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
+    };
+    let send = |method: &'static str, uri: &'static str, body: Option<serde_json::Value>| {
+        let app = app.clone();
+        async move {
+            app.oneshot(create_test_request(method, uri, "user_1", body))
+                .await
+                .unwrap()
+        }
+    };
+    let add = |id: &str| Some(json!({ "guardian": guardian(id) }));
+
+    // A box from before the limit, already over it
+    upsert_guardians(
+        &store,
+        "box_1",
+        vec![
+            guardian("g1"),
+            guardian("g2"),
+            guardian("g3"),
+            guardian("g4"),
+        ],
+    )
+    .await;
 
-    // Make the request to update the guardian
-    let response = app
-        .clone()
-        .oneshot(create_test_request(
-            "PATCH",
-            &format!("/boxes/owned/{}/guardian", box_id),
-            "user_1", // Box owner
-            Some(updated_guardian),
-        ))
-        .await
-        .unwrap();
+    // No more can be added, one at a time or in bulk
+    let response = send("PATCH", "/boxes/owned/box_1/guardian", add("g5")).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = response_to_json(response).await;
+    assert_eq!(body["error"], "A box can have at most 3 guardians.");
+    let five: Vec<Guardian> = ["g1", "g2", "g3", "g4", "g5"]
+        .into_iter()
+        .map(guardian)
+        .collect();
+    let response = send(
+        "PUT",
+        "/boxes/owned/box_1/guardians",
+        Some(json!({ "guardians": five })),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
-    // Verify update was successful
-    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    // Existing guardians can still be edited and removed
+    let mut renamed = guardian("g1");
+    renamed.name = "Renamed".into();
+    let response = send(
+        "PATCH",
+        "/boxes/owned/box_1/guardian",
+        Some(json!({ "guardian": renamed })),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send("DELETE", "/boxes/owned/box_1/guardian/g4", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let two: Vec<Guardian> = ["g1", "g2"].into_iter().map(guardian).collect();
+    let response = send(
+        "PUT",
+        "/boxes/owned/box_1/guardians",
+        Some(json!({ "guardians": two })),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
 
-    // Since the request is expected to fail, we don't need to check the response body or store contents
-    // Just return early
-    return;
+    // Below the limit, guardians can be added up to it
+    let response = send("PATCH", "/boxes/owned/box_1/guardian", add("g3")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send("PATCH", "/boxes/owned/box_1/guardian", add("g4")).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
-    // The rest of the test is skipped as the request is expected to fail
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box("box_1").await.unwrap(),
+    };
+    let ids: Vec<&str> = stored.guardians.iter().map(|g| g.id.as_str()).collect();
+    assert_eq!(ids, ["g1", "g2", "g3"]);
 }
 
 #[tokio::test]
@@ -1239,8 +4834,8 @@ async fn test_update_guardian_invalid_payload() {
         .await
         .unwrap();
 
-    // Verify unprocessable entity status
-    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    // Verify bad request status
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
 
 #[tokio::test]
@@ -1261,6 +4856,9 @@ async fn test_delete_guardian_success() {
         shard_hash: None,
         shard_fetched_at: None,
         shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
     };
 
     upsert_guardians(&store, "box_1", vec![guardian.clone()]).await;
@@ -1308,6 +4906,9 @@ async fn test_delete_guardian_unauthorized() {
         shard_hash: None,
         shard_fetched_at: None,
         shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
     };
 
     upsert_guardians(&store, "box_1", vec![guardian]).await;
@@ -1357,8 +4958,8 @@ async fn test_update_document_invalid_payload() {
         .await
         .unwrap();
 
-    // Verify unprocessable entity status
-    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    // Verify bad request status
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
 
 #[tokio::test]
@@ -1439,6 +5040,416 @@ async fn test_update_existing_document() {
     );
 }
 
+#[tokio::test]
+async fn test_update_document_content_type() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "box_1";
+
+    let update = |document: serde_json::Value| {
+        let app = app.clone();
+        async move {
+            app.oneshot(create_test_request(
+                "PATCH",
+                &format!("/boxes/owned/{}/document", box_id),
+                "user_1",
+                Some(json!({ "document": document })),
+            ))
+            .await
+            .unwrap()
+        }
+    };
+
+    // Allowed type, with a charset parameter, is stored and echoed back
+    let response = update(json!({
+        "id": "typed_doc",
+        "title": "Notes",
+        "encryptedContent": "ciphertext",
+        "createdAt": "2023-01-01T12:00:00Z",
+        "contentType": "text/plain; charset=utf-8",
+        "sizeBytes": 1024
+    }))
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    let doc = body["document"]["documents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["id"] == "typed_doc")
+        .expect("Document should be in the response")
+        .clone();
+    assert_eq!(doc["contentType"], "text/plain; charset=utf-8");
+    // The size recorded is the content's, not the one declared
+    assert_eq!(doc["sizeBytes"], 10);
+
+    // Unknown MIME type
+    let response = update(json!({
+        "id": "bad_type_doc",
+        "title": "Script",
+        "createdAt": "2023-01-01T12:00:00Z",
+        "contentType": "application/x-msdownload"
+    }))
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Over the size limit
+    let response = update(json!({
+        "id": "big_doc",
+        "title": "Scan",
+        "createdAt": "2023-01-01T12:00:00Z",
+        "contentType": "image/png",
+        "sizeBytes": 10 * 1024 * 1024
+    }))
+    .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert!(stored.documents.iter().any(|d| d.id == "typed_doc"));
+    assert!(!stored
+        .documents
+        .iter()
+        .any(|d| d.id == "bad_type_doc" || d.id == "big_doc"));
+}
+
+#[tokio::test]
+async fn test_get_document() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "box_1";
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/owned/{}/document", box_id),
+            "user_1",
+            Some(json!({
+                "document": {
+                    "id": "fetch_doc",
+                    "title": "Photo",
+                    "encryptedContent": "ZW5jcnlwdGVk",
+                    "createdAt": "2023-01-01T12:00:00Z",
+                    "contentType": "image/png"
+                }
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Found: the raw content comes back with the document's content type
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned/{}/document/fetch_doc", box_id),
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers().get("content-type").unwrap(), "image/png");
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(&bytes[..], b"ZW5jcnlwdGVk");
+
+    // Not found
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned/{}/document/missing_doc", box_id),
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Wrong owner
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned/{}/document/fetch_doc", box_id),
+            "user_2",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_update_document_versioning() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "box_1";
+
+    let update = |document: serde_json::Value| {
+        let app = app.clone();
+        async move {
+            app.oneshot(create_test_request(
+                "PATCH",
+                &format!("/boxes/owned/{}/document", box_id),
+                "user_1",
+                Some(json!({ "document": document })),
+            ))
+            .await
+            .unwrap()
+        }
+    };
+    let version_of = |body: &serde_json::Value| {
+        body["document"]["documents"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|d| d["id"] == "versioned_doc")
+            .unwrap()["version"]
+            .as_u64()
+            .unwrap()
+    };
+
+    // New document without a version is inserted at version 1
+    let response = update(json!({
+        "id": "versioned_doc",
+        "title": "v1",
+        "createdAt": "2023-01-01T12:00:00Z"
+    }))
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(version_of(&response_to_json(response).await), 1);
+
+    // Update against the current version succeeds and bumps it
+    let response = update(json!({
+        "id": "versioned_doc",
+        "title": "v2",
+        "createdAt": "2023-01-01T12:00:00Z",
+        "version": 1
+    }))
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(version_of(&response_to_json(response).await), 2);
+
+    // A second client still holding version 1 is turned away
+    let response = update(json!({
+        "id": "versioned_doc",
+        "title": "stale edit",
+        "createdAt": "2023-01-01T12:00:00Z",
+        "version": 1
+    }))
+    .await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let doc = stored
+        .documents
+        .iter()
+        .find(|d| d.id == "versioned_doc")
+        .unwrap();
+    assert_eq!(doc.title, "v2");
+    assert_eq!(doc.version, Some(2));
+}
+
+#[tokio::test]
+async fn test_updates_retry_after_version_conflict() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+    let store = Arc::new(MockBoxStore::with_data(create_test_boxes(&now_str())));
+    let app = routes::create_router_with_store(store.clone(), "");
+    let version_before = store.get_box("box_1").await.unwrap().version;
+
+    // A concurrent write wins the first attempt; the guardian is still added
+    store.fail_next_update(StoreError::VersionConflict("stale".into()));
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1/guardian",
+            "user_1",
+            Some(json!({
+                "guardian": {
+                    "id": "guardian_retry",
+                    "name": "Guardian Retry",
+                    "leadGuardian": false,
+                    "status": "invited",
+                    "addedAt": "2023-01-01T12:00:00Z",
+                    "invitationId": "inv-retry"
+                }
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Likewise for a document
+    store.fail_next_update(StoreError::VersionConflict("stale".into()));
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1/document",
+            "user_1",
+            Some(json!({
+                "document": {
+                    "id": "doc_retry",
+                    "title": "Retried",
+                    "createdAt": "2023-01-01T12:00:00Z"
+                }
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let stored = store.get_box("box_1").await.unwrap();
+    assert!(stored.guardians.iter().any(|g| g.id == "guardian_retry"));
+    let document = stored
+        .documents
+        .iter()
+        .find(|d| d.id == "doc_retry")
+        .unwrap();
+    assert_eq!(document.version, Some(1));
+
+    // And for replacing the whole guardian set
+    store.fail_next_update(StoreError::VersionConflict("stale".into()));
+    let response = app
+        .oneshot(create_test_request(
+            "PUT",
+            "/boxes/owned/box_1/guardians",
+            "user_1",
+            Some(json!({
+                "guardians": [{
+                    "id": "guardian_replaced",
+                    "name": "Guardian Replaced",
+                    "leadGuardian": true,
+                    "status": "invited",
+                    "addedAt": "2023-01-01T12:00:00Z",
+                    "invitationId": "inv-replaced"
+                }]
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let stored = store.get_box("box_1").await.unwrap();
+    let ids: Vec<&str> = stored.guardians.iter().map(|g| g.id.as_str()).collect();
+    assert_eq!(ids, ["guardian_replaced"]);
+    // Only the successful attempts were written
+    assert_eq!(stored.version, version_before + 3);
+}
+
+#[tokio::test]
+async fn test_bulk_update_documents_reports_rejected() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+    let store = Arc::new(MockBoxStore::with_data(create_test_boxes(&now_str())));
+    let app = routes::create_router_with_store(store.clone(), "");
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1/document",
+            "user_1",
+            Some(json!({
+                "document": {
+                    "id": "bulk_existing",
+                    "title": "Existing",
+                    "createdAt": "2023-01-01T12:00:00Z"
+                }
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let existing = store.get_box("box_1").await.unwrap().documents[0].clone();
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1/documents",
+            "user_1",
+            Some(json!({
+                "documents": [
+                    {
+                        "id": "bulk_small",
+                        "title": "Small",
+                        "encryptedContent": "c21hbGw=",
+                        "createdAt": "2023-01-01T12:00:00Z",
+                        "sizeBytes": 5
+                    },
+                    {
+                        "id": "bulk_oversized",
+                        "title": "Oversized",
+                        "createdAt": "2023-01-01T12:00:00Z",
+                        "sizeBytes": 10 * 1024 * 1024
+                    },
+                    {
+                        "id": existing.id,
+                        "title": "Stale edit",
+                        "createdAt": "2023-01-01T12:00:00Z",
+                        "version": existing.version.unwrap_or(0) + 5
+                    },
+                    {
+                        "id": "bulk_text",
+                        "title": "Text",
+                        "encryptedContent": "dGV4dA==",
+                        "createdAt": "2023-01-01T12:00:00Z",
+                        "contentType": "text/plain"
+                    }
+                ]
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["applied"], json!(["bulk_small", "bulk_text"]));
+    let rejected = body["rejected"].as_array().unwrap();
+    let rejected_ids: Vec<&str> = rejected.iter().map(|r| r["id"].as_str().unwrap()).collect();
+    assert_eq!(rejected_ids, ["bulk_oversized", existing.id.as_str()]);
+    assert!(rejected[0]["reason"]
+        .as_str()
+        .unwrap()
+        .starts_with("Document is too large"));
+    assert!(rejected[1]["reason"]
+        .as_str()
+        .unwrap()
+        .contains("has changed"));
+
+    // Only the valid documents were stored, and the stale one is untouched
+    let stored = store.get_box("box_1").await.unwrap();
+    let ids: Vec<&str> = stored.documents.iter().map(|d| d.id.as_str()).collect();
+    assert!(ids.contains(&"bulk_small") && ids.contains(&"bulk_text"));
+    assert!(!ids.contains(&"bulk_oversized"));
+    let unchanged = stored
+        .documents
+        .iter()
+        .find(|d| d.id == existing.id)
+        .unwrap();
+    assert_eq!(unchanged.title, existing.title);
+
+    // Problems with the box itself still fail the whole request
+    let response = app
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1/documents",
+            "user_2",
+            Some(json!({ "documents": [] })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
 #[tokio::test]
 async fn test_update_document_unauthorized() {
     // Setup with mock data
@@ -1699,6 +5710,103 @@ async fn test_get_box_by_id() {
     assert!(box_obj.contains_key("ownerId"));
 }
 
+#[tokio::test]
+async fn test_box_response_guardian_summary() {
+    let (app, store) = create_test_app().await;
+    let guardian = |id: &str, name: &str, status: GuardianStatus| Guardian {
+        id: id.into(),
+        name: name.into(),
+        lead_guardian: false,
+        status,
+        added_at: now_str(),
+        invitation_id: format!("inv_{}", id),
+        lock_data_received_at: None,
+        encrypted_shard: None,
+        shard_hash: None,
+        shard_fetched_at: None,
+        shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
+    };
+    let box_record = BoxRecord {
+        id: "summary_box".into(),
+        name: "Summary".into(),
+        description: "Guardians in every state".into(),
+        is_locked: false,
+        locked_at: None,
+        created_at: now_str(),
+        updated_at: now_str(),
+        owner_id: "summary_owner".into(),
+        owner_name: None,
+        documents: vec![],
+        guardians: vec![
+            guardian("summary_g1", "Ada", GuardianStatus::Accepted),
+            guardian("summary_g2", "Grace", GuardianStatus::Accepted),
+            guardian("summary_g3", "Alan", GuardianStatus::Invited),
+            guardian("summary_g4", "Edsger", GuardianStatus::Viewed),
+            guardian("summary_g5", "Barbara", GuardianStatus::Rejected),
+        ],
+        unlock_instructions: None,
+        unlock_request: None,
+        version: 0,
+        shard_threshold: None,
+        shards_fetched: None,
+        total_shards: None,
+        shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
+    };
+    match &store {
+        TestStore::Mock(mock) => {
+            mock.create_box(box_record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.create_box(box_record).await.unwrap();
+        }
+    }
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/owned/summary_box",
+            "summary_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["box"]["guardianCount"], 4);
+    assert_eq!(body["box"]["acceptedCount"], 2);
+    assert_eq!(
+        body["box"]["pendingGuardianNames"],
+        json!(["Alan", "Edsger"])
+    );
+
+    // Guardians see the box without the owner's summary
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/guardian/summary_box",
+            "summary_g1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    let guardian_box = body["box"].as_object().unwrap();
+    assert!(!guardian_box.contains_key("guardianCount"));
+    assert!(!guardian_box.contains_key("acceptedCount"));
+    assert!(!guardian_box.contains_key("pendingGuardianNames"));
+}
+
 #[tokio::test]
 async fn test_locked_box_is_completely_immutable() {
     // Setup with mock data
@@ -2125,3 +6233,649 @@ async fn test_can_delete_locked_box() {
 
     assert_eq!(verify_response.status(), StatusCode::NOT_FOUND);
 }
+
+#[tokio::test]
+async fn test_box_store_with_custom_table_name() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+    if !use_dynamodb() {
+        info!("Skipping test_box_store_with_custom_table_name: DynamoDB is not enabled");
+        return;
+    }
+
+    // Point a store at its own table, separate from the shared test table
+    let client = create_dynamo_client().await;
+    let table_name = format!("box-custom-{}", uuid::Uuid::new_v4());
+    create_box_table(&client, &table_name)
+        .await
+        .expect("Failed to create custom box table");
+    let store = Arc::new(DynamoBoxStore::with_client_and_table(
+        client.clone(),
+        table_name.clone(),
+    ));
+    let app = routes::create_router_with_store(store.clone(), "");
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned",
+            "custom_table_user",
+            Some(json!({ "name": "Custom Table Box", "description": "" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response_to_json(response).await;
+    let box_id = body["box"]["id"].as_str().unwrap().to_string();
+
+    // The box lands in the custom table and nowhere else
+    let stored_box = store.get_box_consistent(&box_id).await.unwrap();
+    assert_eq!(stored_box.name, "Custom Table Box");
+
+    let default_store =
+        DynamoBoxStore::with_client_and_table(client.clone(), TEST_TABLE_NAME.to_string());
+    assert!(default_store.get_box_consistent(&box_id).await.is_err());
+
+    let _ = client.delete_table().table_name(&table_name).send().await;
+    let _ = client
+        .delete_table()
+        .table_name(DynamoBoxStore::guardian_box_table_for(&table_name))
+        .send()
+        .await;
+}
+
+#[tokio::test]
+async fn test_lock_box_store_failure_returns_500_without_event() {
+    std::env::set_var("TEST_SNS", "true");
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+
+    // Store failures can only be scripted on the mock store
+    let store = Arc::new(MockBoxStore::new());
+    let app = routes::create_router_with_store(store.clone(), "");
+    let box_id = "store-failure-box";
+    store
+        .create_box(BoxRecord {
+            id: box_id.into(),
+            name: "Store Failure".into(),
+            description: "Update is throttled".into(),
+            is_locked: false,
+            locked_at: None,
+            created_at: now_str(),
+            updated_at: now_str(),
+            owner_id: "owner_fail".into(),
+            owner_name: None,
+            documents: vec![],
+            guardians: vec![Guardian {
+                id: "g1".into(),
+                name: "Guardian One".into(),
+                lead_guardian: false,
+                status: GuardianStatus::Accepted,
+                added_at: now_str(),
+                invitation_id: "inv-fail-1".into(),
+                lock_data_received_at: None,
+                encrypted_shard: None,
+                shard_hash: None,
+                shard_fetched_at: None,
+                shard_accepted_at: None,
+                shard_commit: None,
+                shard_first_fetched_at: None,
+                last_reminded_at: None,
+            }],
+            unlock_instructions: None,
+            unlock_request: None,
+            version: 0,
+            shard_threshold: None,
+            shards_fetched: None,
+            total_shards: None,
+            shards_deleted_at: None,
+            all_shards_accepted: false,
+            last_unlock_request_at: None,
+            webhook_url: None,
+            webhook_secret: None,
+            require_step_up: false,
+        })
+        .await
+        .unwrap();
+
+    // The read succeeds and the update fails
+    store.fail_after_n_calls(1);
+    let mut request = create_test_request(
+        "POST",
+        &format!("/boxes/owned/{}/lock", box_id),
+        "owner_fail",
+        Some(json!({
+            "shardThreshold": 1,
+            "shards": [{ "guardianId": "g1", "shard": test_shard("g1"), "shardHash": test_shard_hash("g1") }]
+        })),
+    );
+    request.headers_mut().insert(
+        "x-request-id",
+        HeaderValue::from_static("lock-store-failure"),
+    );
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    // Nothing was locked, so nothing is announced
+    assert!(!test_published_events()
+        .iter()
+        .any(|e| e["request_id"] == "lock-store-failure"));
+}
+
+#[tokio::test]
+async fn test_store_errors_map_to_status_codes() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+    let store = Arc::new(MockBoxStore::with_data(create_test_boxes(&now_str())));
+    let app = routes::create_router_with_store(store.clone(), "");
+    let get_box = || create_test_request("GET", "/boxes/owned/box_1", "user_1", None);
+
+    store.set_next_error(StoreError::InternalError("throttled".into()));
+    let response = app.clone().oneshot(get_box()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    store.set_next_error(StoreError::NotFound("gone".into()));
+    let response = app.clone().oneshot(get_box()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    store.set_next_error(StoreError::VersionConflict("stale".into()));
+    let response = app.clone().oneshot(get_box()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    store.set_next_error(StoreError::Throttled("slow down".into()));
+    let response = app.clone().oneshot(get_box()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = response_to_json(response).await;
+    assert_eq!(body["code"], "SERVICE_UNAVAILABLE");
+
+    store.set_next_error(StoreError::Serialization("bad item".into()));
+    let response = app.clone().oneshot(get_box()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    // Injected errors are one-shot
+    let response = app.oneshot(get_box()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_document_size_is_measured_from_content() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let document = |id: &str, content: String| {
+        json!({
+            "id": id,
+            "title": id,
+            "encryptedContent": content,
+            "createdAt": "2023-01-01T12:00:00Z",
+            "sizeBytes": 1
+        })
+    };
+
+    // Content over the limit is refused whatever size is declared
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1/document",
+            "user_1",
+            Some(json!({ "document": document("understated", "A".repeat(300 * 1024)) })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1/documents",
+            "user_1",
+            Some(json!({
+                "documents": [
+                    document("bulk_understated", "A".repeat(300 * 1024)),
+                    document("bulk_small", "c21hbGw=".to_string())
+                ]
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["applied"], json!(["bulk_small"]));
+    assert_eq!(body["rejected"][0]["id"], "bulk_understated");
+
+    // Accepted documents record the size of their content
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent("box_1").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent("box_1").await.unwrap(),
+    };
+    assert!(!stored
+        .documents
+        .iter()
+        .any(|d| d.id.ends_with("understated")));
+    let small = stored
+        .documents
+        .iter()
+        .find(|d| d.id == "bulk_small")
+        .unwrap();
+    assert_eq!(small.size_bytes, Some(8));
+}
+
+#[tokio::test]
+async fn test_box_document_limits() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+    let store = Arc::new(MockBoxStore::with_data(create_test_boxes(&now_str())));
+    let storage = Arc::new(MockDocumentStorage::new());
+    let app = routes::create_router_with_store_and_storage(store.clone(), Some(storage), "");
+
+    let put_document = |box_id: &str, id: &str, size_bytes: u64| {
+        let app = app.clone();
+        let path = format!("/boxes/owned/{}/document", box_id);
+        let document = json!({
+            "id": id,
+            "title": id,
+            "createdAt": "2023-01-01T12:00:00Z",
+            "contentType": "application/pdf",
+            "sizeBytes": size_bytes
+        });
+        async move {
+            app.oneshot(create_test_request(
+                "PATCH",
+                &path,
+                "user_1",
+                Some(json!({ "document": document })),
+            ))
+            .await
+            .unwrap()
+        }
+    };
+    let fill_box = |box_id: &'static str, count: usize, size_bytes: u64| {
+        let store = store.clone();
+        async move {
+            let mut box_rec = store.get_box(box_id).await.unwrap();
+            box_rec.documents = (0..count)
+                .map(|i| Document {
+                    id: format!("filler_{}", i),
+                    title: "Filler".to_string(),
+                    encrypted_content: None,
+                    created_at: "2023-01-01T12:00:00Z".to_string(),
+                    content_type: Some("application/pdf".to_string()),
+                    size_bytes: Some(size_bytes),
+                    version: Some(1),
+                    s3_key: None,
+                })
+                .collect();
+            store.update_box(box_rec).await.unwrap();
+        }
+    };
+
+    // A box at its document count takes no new documents, but can still
+    // replace the ones it has
+    fill_box("box_1", 100, 8).await;
+    let response = put_document("box_1", "one_too_many", 8).await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body = response_to_json(response).await;
+    assert_eq!(body["code"], "PAYLOAD_TOO_LARGE");
+    let response = put_document("box_1", "filler_0", 16).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The total size counts every document, offloaded ones included
+    fill_box("box_1", 5, 10 * 1024 * 1024).await;
+    let response = put_document("box_1", "last_straw", 1).await;
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let response = put_document("box_1", "filler_0", 10 * 1024 * 1024 - 1).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let stored = store.get_box("box_1").await.unwrap();
+    assert_eq!(stored.documents.len(), 5);
+}
+
+#[tokio::test]
+async fn test_document_offload_to_storage() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+    let store = Arc::new(MockBoxStore::with_data(create_test_boxes(&now_str())));
+    let storage = Arc::new(MockDocumentStorage::new());
+    let app =
+        routes::create_router_with_store_and_storage(store.clone(), Some(storage.clone()), "");
+
+    let put_document = |content: String| {
+        let app = app.clone();
+        async move {
+            app.oneshot(create_test_request(
+                "PATCH",
+                "/boxes/owned/box_1/document",
+                "user_1",
+                Some(json!({
+                    "document": {
+                        "id": "scan",
+                        "title": "Scanned PDF",
+                        "encryptedContent": content,
+                        "createdAt": "2023-01-01T12:00:00Z",
+                        "contentType": "application/pdf"
+                    }
+                })),
+            ))
+            .await
+            .unwrap()
+        }
+    };
+    let stored_document = || async {
+        store
+            .get_box("box_1")
+            .await
+            .unwrap()
+            .documents
+            .into_iter()
+            .find(|d| d.id == "scan")
+    };
+
+    // Small content stays inline
+    let response = put_document("c21hbGw=".to_string()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let document = stored_document().await.unwrap();
+    assert_eq!(document.encrypted_content.as_deref(), Some("c21hbGw="));
+    assert!(document.s3_key.is_none());
+    assert!(storage.keys().is_empty());
+
+    // Oversized content is uploaded and only a pointer stays in the box
+    let large = "A".repeat(310 * 1024);
+    let response = put_document(large.clone()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    let returned = &body["document"]["documents"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["id"] == "scan")
+        .unwrap()
+        .clone();
+    assert!(returned.get("encryptedContent").is_none());
+
+    let document = stored_document().await.unwrap();
+    let key = document.s3_key.clone().unwrap();
+    assert!(key.starts_with("boxes/box_1/documents/scan/"));
+    assert_eq!(returned["s3Key"], key.as_str());
+    assert!(document.encrypted_content.is_none());
+    assert_eq!(document.size_bytes, Some(large.len() as u64));
+    let (object, content_type) = storage.object(&key).unwrap();
+    assert_eq!(object, large.as_bytes());
+    assert_eq!(content_type, "application/pdf");
+
+    // Fetching the document reads it back from storage
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/owned/box_1/document/scan",
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/pdf"
+    );
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(&bytes[..], large.as_bytes());
+
+    // Replacing offloaded content writes a new object and drops the old one
+    // once the box points at the new one
+    let larger = "B".repeat(320 * 1024);
+    let response = put_document(larger.clone()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let replaced_key = stored_document().await.unwrap().s3_key.unwrap();
+    assert_ne!(replaced_key, key);
+    assert_eq!(storage.keys(), vec![replaced_key.clone()]);
+    assert_eq!(storage.object(&replaced_key).unwrap().0, larger.as_bytes());
+
+    // Shrinking the document moves it back inline and drops the stored copy
+    let response = put_document("c21hbGw=".to_string()).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(stored_document().await.unwrap().s3_key.is_none());
+    assert!(storage.keys().is_empty());
+
+    // Deleting an offloaded document removes its content too
+    put_document(large).await;
+    assert_eq!(storage.keys().len(), 1);
+    let response = app
+        .oneshot(create_test_request(
+            "DELETE",
+            "/boxes/owned/box_1/document/scan",
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(stored_document().await.is_none());
+    assert!(storage.keys().is_empty());
+}
+
+#[tokio::test]
+async fn test_failed_document_update_keeps_stored_content() {
+    let store = Arc::new(MockBoxStore::with_data(create_test_boxes(&now_str())));
+    let storage = Arc::new(MockDocumentStorage::new());
+    let app =
+        routes::create_router_with_store_and_storage(store.clone(), Some(storage.clone()), "");
+    let put_document = |content: String| {
+        app.clone().oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1/document",
+            "user_1",
+            Some(json!({
+                "document": {
+                    "id": "scan",
+                    "title": "Scanned PDF",
+                    "encryptedContent": content,
+                    "createdAt": "2023-01-01T12:00:00Z"
+                }
+            })),
+        ))
+    };
+
+    let large = "A".repeat(310 * 1024);
+    let response = put_document(large.clone()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let key = store.get_box("box_1").await.unwrap().documents[0]
+        .s3_key
+        .clone()
+        .unwrap();
+
+    // Saving the box fails after the new content is uploaded
+    store.fail_next_update(StoreError::InternalError("write failed".into()));
+    let response = put_document("B".repeat(310 * 1024)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    // The box still points at the original content, and the upload it never
+    // saw is gone
+    let document = store.get_box("box_1").await.unwrap().documents[0].clone();
+    assert_eq!(document.s3_key.as_deref(), Some(key.as_str()));
+    assert_eq!(storage.keys(), vec![key.clone()]);
+    assert_eq!(storage.object(&key).unwrap().0, large.as_bytes());
+}
+
+#[tokio::test]
+async fn test_delete_box_removes_offloaded_content() {
+    let store = Arc::new(MockBoxStore::with_data(create_test_boxes(&now_str())));
+    let storage = Arc::new(MockDocumentStorage::new());
+    let app =
+        routes::create_router_with_store_and_storage(store.clone(), Some(storage.clone()), "");
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1/documents",
+            "user_1",
+            Some(json!({
+                "documents": [
+                    { "id": "a", "title": "A", "encryptedContent": "A".repeat(310 * 1024), "createdAt": "2023-01-01T12:00:00Z" },
+                    { "id": "b", "title": "B", "encryptedContent": "B".repeat(310 * 1024), "createdAt": "2023-01-01T12:00:00Z" },
+                    { "id": "c", "title": "C", "encryptedContent": "c21hbGw=", "createdAt": "2023-01-01T12:00:00Z" }
+                ]
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(storage.keys().len(), 2);
+
+    let response = app
+        .oneshot(create_test_request(
+            "DELETE",
+            "/boxes/owned/box_1",
+            "user_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(storage.keys().is_empty());
+}
+
+#[tokio::test]
+async fn test_guardian_reads_offloaded_document_once_unlocked() {
+    let store = Arc::new(MockBoxStore::with_data(create_test_boxes(&now_str())));
+    let storage = Arc::new(MockDocumentStorage::new());
+    let app =
+        routes::create_router_with_store_and_storage(store.clone(), Some(storage.clone()), "");
+
+    let large = "A".repeat(310 * 1024);
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/owned/box_1/document",
+            "user_1",
+            Some(json!({
+                "document": {
+                    "id": "scan",
+                    "title": "Scanned PDF",
+                    "encryptedContent": large,
+                    "createdAt": "2023-01-01T12:00:00Z",
+                    "contentType": "application/pdf"
+                }
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let set_unlock_status = |status: UnlockRequestStatus| {
+        let store = store.clone();
+        async move {
+            let mut box_rec = store.get_box("box_1").await.unwrap();
+            box_rec.guardians = vec![Guardian {
+                id: "g1".into(),
+                name: "G One".into(),
+                lead_guardian: false,
+                status: GuardianStatus::Accepted,
+                added_at: now_str(),
+                invitation_id: "inv1".into(),
+                lock_data_received_at: None,
+                encrypted_shard: None,
+                shard_hash: None,
+                shard_fetched_at: None,
+                shard_accepted_at: None,
+                shard_commit: None,
+                shard_first_fetched_at: None,
+                last_reminded_at: None,
+            }];
+            box_rec.unlock_request = Some(UnlockRequest {
+                id: "unlock-1".into(),
+                requested_at: now_str(),
+                status,
+                message: None,
+                reason: None,
+                initiated_by: Some("g1".into()),
+                approved_by: vec![],
+                rejected_by: vec![],
+                approvals: vec![],
+            });
+            store.update_box(box_rec).await.unwrap();
+        }
+    };
+    let get_document = |user_id: &str| {
+        app.clone().oneshot(create_test_request(
+            "GET",
+            "/boxes/guardian/box_1/document/scan",
+            user_id,
+            None,
+        ))
+    };
+
+    // Not while the request is still open
+    set_unlock_status(UnlockRequestStatus::Requested).await;
+    let response = get_document("g1").await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    set_unlock_status(UnlockRequestStatus::Approved).await;
+    let response = get_document("g1").await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/pdf"
+    );
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(&bytes[..], large.as_bytes());
+
+    // Only the box's guardians
+    let response = get_document("user_2").await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_read_only_mode_blocks_writes() {
+    let (app, _store) = create_test_app().await;
+    let app = app.layer(Extension(ReadOnlyMode(true)));
+
+    // Creating a box is refused
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/boxes/owned",
+            "maintenance_user",
+            Some(json!({ "name": "Blocked Box", "description": "" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = response_to_json(response).await;
+    assert_eq!(body["code"], MAINTENANCE_CODE);
+
+    // So is fetching a shard, a GET that records the fetch
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/guardian/any_box/shard",
+            "maintenance_user",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = response_to_json(response).await;
+    assert_eq!(body["code"], MAINTENANCE_CODE);
+
+    // but listing boxes still works
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/owned",
+            "maintenance_user",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["boxes"].as_array().unwrap().len(), 0);
+}
@@ -0,0 +1,116 @@
+use lockbox_shared::auth::create_jwt_token;
+use lockbox_shared::clock::SystemClock;
+use lockbox_shared::maintenance::ReadOnlyMode;
+use lockbox_shared::store::BoxStore;
+use lockbox_shared::test_utils::mock_box_store::MockBoxStore;
+use std::sync::Arc;
+use tonic::{Code, Request};
+
+use crate::grpc::proto::box_service_server::BoxService;
+use crate::grpc::proto::{self, GetBoxRequest};
+use crate::grpc::BoxGrpcService;
+use crate::handlers::box_handlers::create_owned_box;
+use crate::models::CreateBoxRequest;
+
+// A GetBox request carrying the given user's bearer token
+fn get_box_request(box_id: &str, user_id: Option<&str>) -> Request<GetBoxRequest> {
+    let mut request = Request::new(GetBoxRequest {
+        id: box_id.to_string(),
+    });
+    if let Some(user_id) = user_id {
+        let value = format!("Bearer {}", create_jwt_token(user_id));
+        request
+            .metadata_mut()
+            .insert("authorization", value.parse().unwrap());
+    }
+    request
+}
+
+#[tokio::test]
+async fn test_grpc_get_box() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+    let store = Arc::new(MockBoxStore::new());
+    let created = create_owned_box(
+        &*store,
+        &SystemClock,
+        "user_1",
+        CreateBoxRequest {
+            name: "gRPC Box".into(),
+            description: "Fetched over gRPC".into(),
+            owner_name: Some("User One".into()),
+        },
+    )
+    .await
+    .unwrap();
+    let service = BoxGrpcService::new(store);
+
+    let response = service
+        .get_box(get_box_request(&created.id, Some("user_1")))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.id, created.id);
+    assert_eq!(response.name, "gRPC Box");
+    assert_eq!(response.owner_id, "user_1");
+    assert_eq!(response.owner_name.as_deref(), Some("User One"));
+    assert!(!response.is_locked);
+
+    // Errors map onto gRPC codes the same way they map onto HTTP statuses
+    let status = service
+        .get_box(get_box_request(&created.id, Some("user_2")))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+
+    let status = service
+        .get_box(get_box_request("missing_box", Some("user_1")))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::NotFound);
+
+    let status = service
+        .get_box(get_box_request(&created.id, None))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn test_grpc_read_only_refuses_writes() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+    let store = Arc::new(MockBoxStore::new());
+    let created = create_owned_box(
+        &*store,
+        &SystemClock,
+        "user_1",
+        CreateBoxRequest {
+            name: "Existing Box".into(),
+            description: "Made before maintenance".into(),
+            owner_name: None,
+        },
+    )
+    .await
+    .unwrap();
+    let service = BoxGrpcService::new(store.clone()).with_read_only(ReadOnlyMode(true));
+
+    let mut request = Request::new(proto::CreateBoxRequest {
+        name: "Blocked Box".into(),
+        description: String::new(),
+        owner_name: None,
+    });
+    let value = format!("Bearer {}", create_jwt_token("user_1"));
+    request
+        .metadata_mut()
+        .insert("authorization", value.parse().unwrap());
+    let status = service.create_box(request).await.unwrap_err();
+    assert_eq!(status.code(), Code::Unavailable);
+    assert_eq!(store.get_boxes_by_owner("user_1").await.unwrap().len(), 1);
+
+    // Reads still work
+    let response = service
+        .get_box(get_box_request(&created.id, Some("user_1")))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.id, created.id);
+}
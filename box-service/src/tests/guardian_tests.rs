@@ -1,6 +1,7 @@
-use axum::http::StatusCode;
+use axum::http::{HeaderValue, StatusCode};
 use axum::Router;
-use lockbox_shared::auth::create_test_request;
+use lockbox_shared::auth::{create_service_test_request, create_test_request};
+use lockbox_shared::error::StoreError;
 use lockbox_shared::store::dynamo::DynamoBoxStore;
 use lockbox_shared::store::BoxStore;
 use lockbox_shared::test_utils::dynamo_test_utils::{
@@ -14,9 +15,11 @@ use serde_json::json;
 use std::sync::Arc;
 use tower::ServiceExt;
 
+use crate::handlers::box_handlers::test_published_events;
+use crate::handlers::guardian_handlers::{guardian_declined_event_payload, test_webhook_calls};
 use crate::routes;
 use lockbox_shared::models::{
-    now_str, BoxRecord, Guardian, GuardianStatus, UnlockRequest, UnlockRequestStatus,
+    now_str, BoxRecord, Document, Guardian, GuardianStatus, UnlockRequest, UnlockRequestStatus,
 };
 
 // Constants for DynamoDB tests
@@ -50,6 +53,9 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
                 shard_hash: None,
                 shard_fetched_at: None,
                 shard_accepted_at: None,
+                shard_commit: None,
+                shard_first_fetched_at: None,
+                last_reminded_at: None,
             },
             Guardian {
                 id: "guardian_2".into(),
@@ -63,6 +69,9 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
                 shard_hash: None,
                 shard_fetched_at: None,
                 shard_accepted_at: None,
+                shard_commit: None,
+                shard_first_fetched_at: None,
+                last_reminded_at: None,
             },
             Guardian {
                 id: "lead_guardian_1".into(),
@@ -76,6 +85,9 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
                 shard_hash: None,
                 shard_fetched_at: None,
                 shard_accepted_at: None,
+                shard_commit: None,
+                shard_first_fetched_at: None,
+                last_reminded_at: None,
             },
         ],
         unlock_instructions: Some("Contact all guardians".into()),
@@ -85,6 +97,11 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
         shards_fetched: None,
         total_shards: None,
         shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
     };
 
     // Box 2: With pending unlock request
@@ -94,11 +111,15 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
         requested_at: now.to_string(),
         status: UnlockRequestStatus::Requested,
         message: Some("Emergency access needed".into()),
+        reason: None,
         initiated_by: Some("lead_guardian_1".into()),
         approved_by: vec![],
         rejected_by: vec![],
+        approvals: vec![],
     };
 
+    // Its guardians have all acknowledged their shards, as they would have
+    // by the time an unlock is requested
     let box_2 = BoxRecord {
         id: box_2_id,
         name: "Guardian Test Box 2".into(),
@@ -121,8 +142,11 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
                 lock_data_received_at: None,
                 encrypted_shard: None,
                 shard_hash: None,
-                shard_fetched_at: None,
+                shard_fetched_at: Some(now.to_string()),
                 shard_accepted_at: None,
+                shard_commit: None,
+                shard_first_fetched_at: None,
+                last_reminded_at: None,
             },
             Guardian {
                 id: "guardian_3".into(),
@@ -134,8 +158,11 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
                 lock_data_received_at: None,
                 encrypted_shard: None,
                 shard_hash: None,
-                shard_fetched_at: None,
+                shard_fetched_at: Some(now.to_string()),
                 shard_accepted_at: None,
+                shard_commit: None,
+                shard_first_fetched_at: None,
+                last_reminded_at: None,
             },
             Guardian {
                 id: "lead_guardian_1".into(),
@@ -147,8 +174,11 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
                 lock_data_received_at: None,
                 encrypted_shard: None,
                 shard_hash: None,
-                shard_fetched_at: None,
+                shard_fetched_at: Some(now.to_string()),
                 shard_accepted_at: None,
+                shard_commit: None,
+                shard_first_fetched_at: None,
+                last_reminded_at: None,
             },
         ],
         unlock_instructions: Some("Call emergency contact".into()),
@@ -158,6 +188,11 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
         shards_fetched: None,
         total_shards: None,
         shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
     };
 
     // Box 3: Not associated with guardian_1
@@ -185,6 +220,9 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
             shard_hash: None,
             shard_fetched_at: None,
             shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
         }],
         unlock_instructions: None,
         unlock_request: None,
@@ -193,6 +231,11 @@ fn create_test_data(now: &str) -> Vec<BoxRecord> {
         shards_fetched: None,
         total_shards: None,
         shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
     };
 
     vec![box_1, box_2, box_3]
@@ -287,6 +330,25 @@ async fn replace_guardian(store: &TestStore, box_id: &str, guardian: Guardian) {
     }
 }
 
+async fn set_box_locked(store: &TestStore, box_id: &str, locked: bool) {
+    let mut record = match store {
+        TestStore::Mock(mock) => mock.get_box(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+
+    record.is_locked = locked;
+    record.updated_at = now_str();
+
+    match store {
+        TestStore::Mock(mock) => {
+            mock.update_box(record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.update_box(record).await.unwrap();
+        }
+    }
+}
+
 #[tokio::test]
 async fn test_get_guardian_boxes() {
     // Setup with test app
@@ -324,16 +386,18 @@ async fn test_get_guardian_boxes() {
     assert!(box_ids.contains(&"11111111-1111-1111-1111-111111111111"));
     assert!(box_ids.contains(&"22222222-2222-2222-2222-222222222222"));
 
-    // Check that the boxes have all the fields including the new ones
+    // Documents stay hidden until the box is unlocked, and only the caller's
+    // own guardian entry is included
     let first_box = &boxes[0];
     assert!(
-        first_box.get("documents").is_some(),
-        "Box should include documents"
+        first_box.get("documents").is_none(),
+        "Box should not include documents before an unlock"
     );
     assert!(
-        first_box.get("guardians").is_some(),
-        "Box should include guardians"
+        first_box.get("guardians").is_none(),
+        "Box should not include other guardians"
     );
+    assert_eq!(first_box["guardian"]["id"], "guardian_1");
 
     // Verify the guardian-specific fields
     assert!(
@@ -377,6 +441,389 @@ async fn test_get_guardian_boxes_empty_for_non_guardian() {
     assert!(boxes.is_empty());
 }
 
+#[tokio::test]
+async fn test_get_guardian_boxes_store_failure() {
+    init_test_logging();
+    let store = Arc::new(MockBoxStore::with_data(create_test_data(&now_str())));
+    let app = routes::create_router_with_store(store.clone(), "");
+
+    // A failed lookup isn't mistaken for holding no boxes
+    store.set_next_error(StoreError::Throttled("slow down".into()));
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/guardian",
+            "guardian_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+// Seeds guardian_1 with one pending (box 1), one accepted (box 2) and one
+// unlocked box, and returns the ids of the boxes listed for the given filter
+async fn list_guardian_box_ids(query: &str) -> Vec<String> {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let now = now_str();
+    let unlocked_box = BoxRecord {
+        id: "44444444-4444-4444-4444-444444444444".into(),
+        name: "Guardian Test Box 4".into(),
+        description: "Unlocked box guarded by guardian_1".into(),
+        is_locked: false,
+        locked_at: None,
+        created_at: now.clone(),
+        updated_at: now.clone(),
+        owner_id: "owner_2".into(),
+        owner_name: Some("Owner Two".into()),
+        documents: vec![],
+        guardians: vec![Guardian {
+            id: "guardian_1".into(),
+            name: "Guardian One".into(),
+            lead_guardian: false,
+            status: GuardianStatus::Accepted,
+            added_at: now.clone(),
+            invitation_id: "invitation_10".into(),
+            lock_data_received_at: None,
+            encrypted_shard: None,
+            shard_hash: None,
+            shard_fetched_at: None,
+            shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
+        }],
+        unlock_instructions: None,
+        unlock_request: None,
+        version: 0,
+        shard_threshold: None,
+        shards_fetched: None,
+        total_shards: None,
+        shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
+    };
+    match &store {
+        TestStore::Mock(mock) => {
+            mock.create_box(unlocked_box).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.create_box(unlocked_box).await.unwrap();
+        }
+    }
+
+    // guardian_1 has accepted their shard for box 2 only; guardian_3 has
+    // accepted theirs for box 1, which must not affect guardian_1's view
+    replace_guardian(
+        &store,
+        "22222222-2222-2222-2222-222222222222",
+        Guardian {
+            id: "guardian_1".into(),
+            name: "Guardian One".into(),
+            lead_guardian: false,
+            status: GuardianStatus::Accepted,
+            added_at: now.clone(),
+            invitation_id: "invitation_5".into(),
+            lock_data_received_at: None,
+            encrypted_shard: None,
+            shard_hash: None,
+            shard_fetched_at: None,
+            shard_accepted_at: Some(now.clone()),
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
+        },
+    )
+    .await;
+    replace_guardian(
+        &store,
+        "11111111-1111-1111-1111-111111111111",
+        Guardian {
+            id: "guardian_2".into(),
+            name: "Guardian Two".into(),
+            lead_guardian: false,
+            status: GuardianStatus::Accepted,
+            added_at: now.clone(),
+            invitation_id: "invitation_2".into(),
+            lock_data_received_at: None,
+            encrypted_shard: None,
+            shard_hash: None,
+            shard_fetched_at: None,
+            shard_accepted_at: Some(now.clone()),
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
+        },
+    )
+    .await;
+
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/guardian{}", query),
+            "guardian_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let json_response = response_to_json(response).await;
+    let mut ids: Vec<String> = json_response["boxes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| b["id"].as_str().unwrap().to_string())
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[tokio::test]
+async fn test_get_guardian_boxes_status_filter() {
+    let all = list_guardian_box_ids("").await;
+    assert_eq!(
+        all,
+        vec![
+            "11111111-1111-1111-1111-111111111111",
+            "22222222-2222-2222-2222-222222222222",
+            "44444444-4444-4444-4444-444444444444",
+        ]
+    );
+    assert_eq!(list_guardian_box_ids("?status=all").await, all);
+
+    // Only the locked box whose shard guardian_1 hasn't accepted is pending
+    assert_eq!(
+        list_guardian_box_ids("?status=pending").await,
+        vec!["11111111-1111-1111-1111-111111111111"]
+    );
+    assert_eq!(
+        list_guardian_box_ids("?status=accepted").await,
+        vec!["22222222-2222-2222-2222-222222222222"]
+    );
+}
+
+#[tokio::test]
+async fn test_get_guardian_boxes_invalid_status_filter() {
+    let (app, _store) = create_test_app().await;
+
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/guardian?status=bogus",
+            "guardian_1",
+            None,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_get_guardian_boxes_paginated() {
+    let (app, store) = create_test_app().await;
+
+    // Five boxes guarded by the same user, plus one they have rejected
+    let now = now_str();
+    for i in 0..6 {
+        let box_rec = BoxRecord {
+            id: format!("5555555{}-5555-5555-5555-555555555555", i),
+            name: format!("Paged Box {}", i),
+            description: "Box for pagination".into(),
+            is_locked: false,
+            locked_at: None,
+            created_at: now.clone(),
+            updated_at: now.clone(),
+            owner_id: "owner_1".into(),
+            owner_name: None,
+            documents: vec![],
+            guardians: vec![Guardian {
+                id: "paging_guardian".into(),
+                name: "Paging Guardian".into(),
+                lead_guardian: false,
+                status: if i == 5 {
+                    GuardianStatus::Rejected
+                } else {
+                    GuardianStatus::Accepted
+                },
+                added_at: now.clone(),
+                invitation_id: format!("paging_invitation_{}", i),
+                lock_data_received_at: None,
+                encrypted_shard: None,
+                shard_hash: None,
+                shard_fetched_at: None,
+                shard_accepted_at: None,
+                shard_commit: None,
+                shard_first_fetched_at: None,
+                last_reminded_at: None,
+            }],
+            unlock_instructions: None,
+            unlock_request: None,
+            version: 0,
+            shard_threshold: None,
+            shards_fetched: None,
+            total_shards: None,
+            shards_deleted_at: None,
+            all_shards_accepted: false,
+            last_unlock_request_at: None,
+            webhook_url: None,
+            webhook_secret: None,
+            require_step_up: false,
+        };
+        match &store {
+            TestStore::Mock(mock) => {
+                mock.create_box(box_rec).await.unwrap();
+            }
+            TestStore::DynamoDB(dynamo) => {
+                dynamo.create_box(box_rec).await.unwrap();
+            }
+        }
+    }
+
+    let mut seen = Vec::new();
+    let mut cursor: Option<String> = None;
+    let mut pages = 0;
+    loop {
+        let uri = match &cursor {
+            Some(cursor) => format!("/boxes/guardian?limit=2&cursor={}", cursor),
+            None => "/boxes/guardian?limit=2".to_string(),
+        };
+        let response = app
+            .clone()
+            .oneshot(create_test_request("GET", &uri, "paging_guardian", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let link = response
+            .headers()
+            .get(axum::http::header::LINK)
+            .map(|value| value.to_str().unwrap().to_string());
+
+        let json = response_to_json(response).await;
+        let boxes = json["boxes"].as_array().unwrap();
+        assert!(boxes.len() <= 2);
+        seen.extend(boxes.iter().map(|b| b["id"].as_str().unwrap().to_string()));
+        pages += 1;
+
+        // The Link header mirrors the body's cursor
+        match json["nextCursor"].as_str() {
+            Some(next) => {
+                assert_eq!(
+                    link.as_deref(),
+                    Some(
+                        format!("</boxes/guardian?limit=2&cursor={}>; rel=\"next\"", next).as_str()
+                    )
+                );
+                cursor = Some(next.to_string());
+            }
+            None => {
+                assert!(link.is_none(), "last page has a Link header: {:?}", link);
+                break;
+            }
+        }
+    }
+
+    seen.sort();
+    let expected: Vec<String> = (0..5)
+        .map(|i| format!("5555555{}-5555-5555-5555-555555555555", i))
+        .collect();
+    assert_eq!(seen, expected);
+    assert!(pages >= 3);
+}
+
+#[tokio::test]
+async fn test_get_guardian_boxes_invalid_cursor() {
+    let (app, _store) = create_test_app().await;
+
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/guardian?limit=2&cursor=not-a-cursor",
+            "guardian_1",
+            None,
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_get_guardian_box_redacts_shards() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "11111111-1111-1111-1111-111111111111";
+    for (guardian_id, invitation_id) in [
+        ("guardian_1", "invitation_1"),
+        ("guardian_2", "invitation_2"),
+    ] {
+        replace_guardian(
+            &store,
+            box_id,
+            Guardian {
+                id: guardian_id.into(),
+                name: guardian_id.into(),
+                lead_guardian: false,
+                status: GuardianStatus::Accepted,
+                added_at: now_str(),
+                invitation_id: invitation_id.into(),
+                lock_data_received_at: Some(now_str()),
+                encrypted_shard: Some(format!("shard-{}", guardian_id)),
+                shard_hash: Some(format!("hash-{}", guardian_id)),
+                shard_fetched_at: None,
+                shard_accepted_at: None,
+                shard_commit: None,
+                shard_first_fetched_at: None,
+                last_reminded_at: None,
+            },
+        )
+        .await;
+    }
+
+    for path in [
+        format!("/boxes/guardian/{}", box_id),
+        "/boxes/guardian".to_string(),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(create_test_request("GET", &path, "guardian_1", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let json_response = response_to_json(response).await;
+        let box_data = match json_response.get("box") {
+            Some(box_data) => box_data.clone(),
+            None => json_response["boxes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|b| b["id"] == box_id)
+                .unwrap()
+                .clone(),
+        };
+        assert_eq!(box_data["guardian"]["id"], "guardian_1");
+        // Not even the caller's own shard; only the shard fetch serves it
+        assert!(box_data["guardian"].get("encryptedShard").is_none());
+        assert!(box_data["guardian"].get("shardHash").is_none());
+        assert!(
+            box_data.get("guardians").is_none(),
+            "Other guardians must not be served to {}",
+            path
+        );
+        // Owner-only fields stay hidden until the box is unlocked
+        assert!(box_data.get("documents").is_none());
+        assert!(box_data.get("unlockInstructions").is_none());
+    }
+}
+
 #[tokio::test]
 async fn test_get_guardian_box_found() {
     // Setup with test data
@@ -411,6 +858,66 @@ async fn test_get_guardian_box_found() {
     );
 }
 
+#[tokio::test]
+async fn test_get_guardian_box_shows_documents_once_unlocked() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "22222222-2222-2222-2222-222222222222";
+    add_document(
+        &store,
+        box_id,
+        Document {
+            id: "doc-will".into(),
+            title: "Will".into(),
+            encrypted_content: Some("c2VjcmV0IHdpbGw=".into()),
+            created_at: now_str(),
+            content_type: None,
+            size_bytes: None,
+            version: Some(1),
+            s3_key: None,
+        },
+    )
+    .await;
+
+    let get_box = || async {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "GET",
+                &format!("/boxes/guardian/{}", box_id),
+                "guardian_1",
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        response_to_json(response).await["box"].clone()
+    };
+
+    // While the unlock request is still open the documents stay hidden
+    assert!(get_box().await.get("documents").is_none());
+
+    let mut record = match &store {
+        TestStore::Mock(mock) => mock.get_box(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    record.unlock_instructions = Some("Call the lawyer".into());
+    record.unlock_request.as_mut().unwrap().status = UnlockRequestStatus::Approved;
+    match &store {
+        TestStore::Mock(mock) => {
+            mock.update_box(record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.update_box(record).await.unwrap();
+        }
+    }
+
+    let box_data = get_box().await;
+    assert_eq!(box_data["documents"][0]["id"], "doc-will");
+    assert_eq!(box_data["unlockInstructions"], "Call the lawyer");
+}
+
 #[tokio::test]
 async fn test_get_guardian_box_unauthorized() {
     // Setup with test data
@@ -462,29 +969,278 @@ async fn test_get_guardian_box_not_found() {
 }
 
 #[tokio::test]
-async fn test_lead_guardian_unlock_request() {
-    // Set up the app and store
+async fn test_guardian_summary_across_boxes() {
     let (app, store) = create_test_app().await;
-
-    // Add test data directly to the store
     add_test_data_to_store(&store).await;
 
-    let box_id = "11111111-1111-1111-1111-111111111111";
-
-    // Verify box state before the test
-    let initial_box = match &store {
-        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
-    };
-    assert!(
-        initial_box.unlock_request.is_none(),
-        "Box should not have unlock request before test"
-    );
+    // guardian_1 has fetched and accepted their shard of box 1, and only
+    // acknowledged their shard of box 2
+    let box_1_id = "11111111-1111-1111-1111-111111111111";
+    let box_2_id = "22222222-2222-2222-2222-222222222222";
+    replace_guardian(
+        &store,
+        box_1_id,
+        Guardian {
+            id: "guardian_1".into(),
+            name: "Guardian One".into(),
+            lead_guardian: false,
+            status: GuardianStatus::Accepted,
+            added_at: now_str(),
+            invitation_id: "invitation_1".into(),
+            lock_data_received_at: None,
+            encrypted_shard: None,
+            shard_hash: None,
+            shard_fetched_at: Some(now_str()),
+            shard_accepted_at: Some(now_str()),
+            shard_commit: None,
+            shard_first_fetched_at: Some(now_str()),
+            last_reminded_at: None,
+        },
+    )
+    .await;
 
-    // Create unlock request payload
-    let request_payload = json!({
-        "message": "Emergency access needed for testing"
-    });
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/guardian/summary",
+            "guardian_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    let mut boxes = body.as_array().unwrap().clone();
+    boxes.sort_by_key(|b| b["boxId"].as_str().unwrap().to_string());
+    assert_eq!(
+        boxes,
+        vec![
+            json!({
+                "boxId": box_1_id,
+                "boxName": "Guardian Test Box 1",
+                "isLocked": true,
+                "myShardFetched": true,
+                "myShardAccepted": true,
+                "unlockRequestActive": false
+            }),
+            json!({
+                "boxId": box_2_id,
+                "boxName": "Guardian Test Box 2",
+                "isLocked": true,
+                "myShardFetched": true,
+                "myShardAccepted": false,
+                "unlockRequestActive": true
+            }),
+        ]
+    );
+}
+
+async fn add_document(store: &TestStore, box_id: &str, document: Document) {
+    let mut record = match store {
+        TestStore::Mock(mock) => mock.get_box(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+
+    record.documents.push(document);
+    record.updated_at = now_str();
+
+    match store {
+        TestStore::Mock(mock) => {
+            mock.update_box(record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.update_box(record).await.unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_preview_box_during_unlock() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "22222222-2222-2222-2222-222222222222"; // Box with existing unlock request
+    let guardian = Guardian {
+        id: "guardian_1".into(),
+        name: "Guardian One".into(),
+        lead_guardian: false,
+        status: GuardianStatus::Accepted,
+        added_at: now_str(),
+        invitation_id: "invitation_5".into(),
+        lock_data_received_at: None,
+        encrypted_shard: Some("shard_1".into()),
+        shard_hash: None,
+        shard_fetched_at: None,
+        shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
+    };
+    replace_guardian(&store, box_id, guardian).await;
+    let shardless = Guardian {
+        id: "guardian_3".into(),
+        name: "Guardian Three".into(),
+        lead_guardian: false,
+        status: GuardianStatus::Accepted,
+        added_at: now_str(),
+        invitation_id: "invitation_6".into(),
+        lock_data_received_at: None,
+        encrypted_shard: None,
+        shard_hash: None,
+        shard_fetched_at: None,
+        shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
+    };
+    replace_guardian(&store, box_id, shardless).await;
+    add_document(
+        &store,
+        box_id,
+        Document {
+            id: "doc-will".into(),
+            title: "Will".into(),
+            encrypted_content: Some("c2VjcmV0IHdpbGw=".into()),
+            created_at: now_str(),
+            content_type: Some("application/pdf".into()),
+            size_bytes: Some(2048),
+            version: Some(1),
+            s3_key: None,
+        },
+    )
+    .await;
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/guardian/{}/preview", box_id),
+            "guardian_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(
+        body,
+        json!({
+            "boxId": box_id,
+            "boxName": "Guardian Test Box 2",
+            "unlockRequestId": "unlock-111",
+            "documents": [{
+                "id": "doc-will",
+                "title": "Will",
+                "contentType": "application/pdf",
+                "sizeBytes": 2048
+            }]
+        })
+    );
+
+    // Acknowledging the shard clears the stored copy, but the guardian
+    // still holds it and can still preview
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/shard/ack", box_id),
+            "guardian_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/guardian/{}/preview", box_id),
+            "guardian_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Guardians without a shard have no say in the unlock, so can neither
+    // preview nor answer
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/guardian/{}/preview", box_id),
+            "guardian_3",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/respond", box_id),
+            "guardian_3",
+            Some(json!({ "approve": true })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Without an open unlock request there is nothing to preview for
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/guardian/11111111-1111-1111-1111-111111111111/preview",
+            "guardian_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_preview_box_non_guardian() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            "/boxes/guardian/22222222-2222-2222-2222-222222222222/preview",
+            "not_a_guardian",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_lead_guardian_unlock_request() {
+    // Set up the app and store
+    let (app, store) = create_test_app().await;
+
+    // Add test data directly to the store
+    add_test_data_to_store(&store).await;
+
+    let box_id = "11111111-1111-1111-1111-111111111111";
+
+    // Verify box state before the test
+    let initial_box = match &store {
+        TestStore::Mock(mock) => mock.get_box(&box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box(&box_id).await.unwrap(),
+    };
+    assert!(
+        initial_box.unlock_request.is_none(),
+        "Box should not have unlock request before test"
+    );
+
+    // Create unlock request payload
+    let request_payload = json!({
+        "message": "Emergency access needed for testing"
+    });
 
     // Execute the PATCH request to initiate unlock
     let response = app
@@ -684,6 +1440,110 @@ async fn test_accept_unlock_request() {
     );
 }
 
+#[tokio::test]
+async fn test_unlock_calls_survive_concurrent_update() {
+    init_test_logging();
+    let store = Arc::new(MockBoxStore::with_data(create_test_data(&now_str())));
+    let app = routes::create_router_with_store(store.clone(), "");
+
+    // Losing the write to a concurrent update rereads the box and tries
+    // again, rather than failing the request
+    store.fail_next_update(StoreError::VersionConflict("changed".into()));
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/guardian/11111111-1111-1111-1111-111111111111/request",
+            "lead_guardian_1",
+            Some(json!({ "message": "Emergency access needed" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let stored = store
+        .get_box("11111111-1111-1111-1111-111111111111")
+        .await
+        .unwrap();
+    let unlock = stored.unlock_request.unwrap();
+    assert_eq!(unlock.initiated_by.as_deref(), Some("lead_guardian_1"));
+
+    // The same goes for answering one
+    store.fail_next_update(StoreError::VersionConflict("changed".into()));
+    let response = app
+        .oneshot(create_test_request(
+            "PATCH",
+            "/boxes/guardian/22222222-2222-2222-2222-222222222222/respond",
+            "guardian_1",
+            Some(json!({ "approve": true })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let stored = store
+        .get_box("22222222-2222-2222-2222-222222222222")
+        .await
+        .unwrap();
+    assert_eq!(stored.unlock_request.unwrap().approved_by, ["guardian_1"]);
+}
+
+#[tokio::test]
+async fn test_invitation_answers_survive_concurrent_update() {
+    init_test_logging();
+    let store = Arc::new(MockBoxStore::with_data(create_test_data(&now_str())));
+    let app = routes::create_router_with_store(store.clone(), "");
+    let box_id = "11111111-1111-1111-1111-111111111111";
+
+    let invited = |id: &str| Guardian {
+        id: id.into(),
+        name: format!("Guardian {}", id),
+        lead_guardian: false,
+        status: GuardianStatus::Invited,
+        added_at: now_str(),
+        invitation_id: format!("inv-{}", id),
+        lock_data_received_at: None,
+        encrypted_shard: None,
+        shard_hash: None,
+        shard_fetched_at: None,
+        shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
+    };
+    let mut box_record = store.get_box(box_id).await.unwrap();
+    box_record.is_locked = false;
+    box_record.guardians.push(invited("accepting"));
+    box_record.guardians.push(invited("declining"));
+    store.update_box(box_record).await.unwrap();
+
+    let answer = |user: &str, accept: bool| {
+        app.clone().oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/invitation", box_id),
+            user,
+            Some(json!({ "accept": accept })),
+        ))
+    };
+
+    // Losing the write to a concurrent update rereads the box and tries
+    // again, for accepting and for declining
+    store.fail_next_update(StoreError::VersionConflict("changed".into()));
+    let response = answer("accepting", true).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    store.fail_next_update(StoreError::VersionConflict("changed".into()));
+    let response = answer("declining", false).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let stored = store.get_box(box_id).await.unwrap();
+    let accepted = stored
+        .guardians
+        .iter()
+        .find(|g| g.id == "accepting")
+        .unwrap();
+    assert_eq!(accepted.status, GuardianStatus::Accepted);
+    assert!(!stored.guardians.iter().any(|g| g.id == "declining"));
+}
+
 #[tokio::test]
 async fn test_reject_unlock_request() {
     // Setup with test data
@@ -834,6 +1694,7 @@ async fn test_respond_to_unlock_request_invalid_payload() {
 
 #[tokio::test]
 async fn test_accept_guardian_invitation() {
+    std::env::set_var("TEST_SNS", "true");
     let (app, store) = create_test_app().await;
     add_test_data_to_store(&store).await;
 
@@ -849,6 +1710,9 @@ async fn test_accept_guardian_invitation() {
         shard_hash: None,
         shard_fetched_at: None,
         shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
     };
 
     replace_guardian(
@@ -858,21 +1722,30 @@ async fn test_accept_guardian_invitation() {
     )
     .await;
 
-    let response = app
-        .clone()
-        .oneshot(create_test_request(
-            "PATCH",
-            "/boxes/guardian/11111111-1111-1111-1111-111111111111/invitation",
-            &pending_guardian.id,
-            Some(json!({ "accept": true })),
-        ))
-        .await
-        .unwrap();
+    let mut request = create_test_request(
+        "PATCH",
+        "/boxes/guardian/11111111-1111-1111-1111-111111111111/invitation",
+        &pending_guardian.id,
+        Some(json!({ "accept": true })),
+    );
+    request
+        .headers_mut()
+        .insert("x-request-id", HeaderValue::from_static("accept-trace"));
+    let response = app.clone().oneshot(request).await.unwrap();
 
     assert_eq!(response.status(), StatusCode::OK);
     let body = response_to_json(response).await;
     assert_eq!(body["message"], "Guardian invitation accepted successfully");
 
+    // The owner hears about the acceptance
+    let event = test_published_events()
+        .into_iter()
+        .find(|e| e["request_id"] == "accept-trace")
+        .expect("guardian_accepted event should be published");
+    assert_eq!(event["event_type"], "guardian_accepted");
+    assert_eq!(event["owner_id"], "owner_1");
+    assert_eq!(event["guardian_id"], "pending_guardian");
+
     let updated_box = match &store {
         TestStore::Mock(mock) => mock
             .get_box("11111111-1111-1111-1111-111111111111")
@@ -893,63 +1766,707 @@ async fn test_accept_guardian_invitation() {
 }
 
 #[tokio::test]
-async fn test_reject_guardian_invitation() {
+async fn test_redeemed_invitation_adds_accepted_guardian() {
     let (app, store) = create_test_app().await;
     add_test_data_to_store(&store).await;
+    let box_id = "11111111-1111-1111-1111-111111111111";
+    set_box_locked(&store, box_id, false).await;
 
-    let pending_guardian = Guardian {
-        id: "pending_guardian".into(),
-        name: "Pending Guardian".into(),
-        lead_guardian: false,
-        status: GuardianStatus::Invited,
-        added_at: now_str(),
-        invitation_id: "inv-pending".into(),
-        lock_data_received_at: None,
-        encrypted_shard: None,
-        shard_hash: None,
-        shard_fetched_at: None,
-        shard_accepted_at: None,
-    };
-
+    // The placeholder the invitation_created event leaves on the box
     replace_guardian(
         &store,
-        "11111111-1111-1111-1111-111111111111",
-        pending_guardian.clone(),
+        box_id,
+        Guardian {
+            id: String::new(),
+            name: "Invited Friend".into(),
+            lead_guardian: false,
+            status: GuardianStatus::Invited,
+            added_at: now_str(),
+            invitation_id: "inv-redeemed".into(),
+            lock_data_received_at: None,
+            encrypted_shard: None,
+            shard_hash: None,
+            shard_fetched_at: None,
+            shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
+        },
     )
     .await;
 
+    let path = format!("/boxes/internal/{}/guardians/accept", box_id);
+    let payload = json!({
+        "invitationId": "inv-redeemed",
+        "leadGuardian": true,
+        "creatorId": "owner_1",
+    });
+
+    // Users can't call the internal endpoint, even for themselves
     let response = app
         .clone()
         .oneshot(create_test_request(
-            "PATCH",
-            "/boxes/guardian/11111111-1111-1111-1111-111111111111/invitation",
-            &pending_guardian.id,
-            Some(json!({ "accept": false })),
+            "POST",
+            &path,
+            "redeeming_user",
+            Some(payload.clone()),
         ))
         .await
         .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
 
-    assert_eq!(response.status(), StatusCode::OK);
-    let body = response_to_json(response).await;
-    assert_eq!(body["message"], "Guardian invitation rejected successfully");
+    // A service has to say which user redeemed the invitation
+    let response = app
+        .clone()
+        .oneshot(create_service_test_request(
+            "POST",
+            &path,
+            "invitation-event-handler",
+            None,
+            Some(payload.clone()),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
-    let updated_box = match &store {
-        TestStore::Mock(mock) => mock
-            .get_box("11111111-1111-1111-1111-111111111111")
-            .await
-            .unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo
-            .get_box("11111111-1111-1111-1111-111111111111")
+    // Only invitations the box's owner created are honoured
+    let mut self_invited = payload.clone();
+    self_invited["creatorId"] = json!("redeeming_user");
+    let response = app
+        .clone()
+        .oneshot(create_service_test_request(
+            "POST",
+            &path,
+            "invitation-event-handler",
+            Some("redeeming_user"),
+            Some(self_invited),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Redelivered events leave a single accepted guardian
+    for _ in 0..2 {
+        let response = app
+            .clone()
+            .oneshot(create_service_test_request(
+                "POST",
+                &path,
+                "invitation-event-handler",
+                Some("redeeming_user"),
+                Some(payload.clone()),
+            ))
             .await
-            .unwrap(),
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response_to_json(response).await;
+        assert_eq!(body["guardian"]["id"], "redeeming_user");
+    }
+
+    let updated_box = match &store {
+        TestStore::Mock(mock) => mock.get_box(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
     };
+    let matching: Vec<_> = updated_box
+        .guardians
+        .iter()
+        .filter(|g| g.invitation_id == "inv-redeemed")
+        .collect();
+    assert_eq!(matching.len(), 1);
+    assert_eq!(matching[0].id, "redeeming_user");
+    assert_eq!(matching[0].name, "Invited Friend");
 
-    let guardian = updated_box
+    // Someone else presenting the same invitation isn't added alongside
+    let response = app
+        .clone()
+        .oneshot(create_service_test_request(
+            "POST",
+            &path,
+            "invitation-event-handler",
+            Some("second_user"),
+            Some(payload.clone()),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let updated_box = match &store {
+        TestStore::Mock(mock) => mock.get_box(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let matching: Vec<_> = updated_box
         .guardians
+        .iter()
+        .filter(|g| g.invitation_id == "inv-redeemed")
+        .collect();
+    assert_eq!(matching.len(), 1);
+    assert_eq!(matching[0].id, "redeeming_user");
+    assert_eq!(matching[0].status, GuardianStatus::Accepted);
+    assert!(matching[0].lead_guardian);
+
+    // The new guardian can now see the box
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/guardian/{}", box_id),
+            "redeeming_user",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_request_unlock_publishes_owner_event() {
+    std::env::set_var("TEST_SNS", "true");
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "11111111-1111-1111-1111-111111111111";
+    let mut request = create_test_request(
+        "PATCH",
+        &format!("/boxes/guardian/{}/request", box_id),
+        "lead_guardian_1",
+        Some(json!({ "message": "Please let me in" })),
+    );
+    request.headers_mut().insert(
+        "x-request-id",
+        HeaderValue::from_static("unlock-event-trace"),
+    );
+
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let event = test_published_events()
         .into_iter()
-        .find(|g| g.id == "pending_guardian")
-        .expect("Guardian should exist after rejection");
-    assert_eq!(guardian.status, GuardianStatus::Rejected);
+        .find(|e| e["request_id"] == "unlock-event-trace")
+        .expect("unlock_requested event should be published");
+    assert_eq!(event["event_type"], "unlock_requested");
+    assert_eq!(event["box_id"], box_id);
+    assert_eq!(event["box_name"], "Guardian Test Box 1");
+    assert_eq!(event["owner_id"], "owner_1");
+    assert_eq!(event["requesting_guardian_id"], "lead_guardian_1");
+    assert!(event["timestamp"].is_string());
+}
+
+#[tokio::test]
+async fn test_request_unlock_with_reason() {
+    std::env::set_var("TEST_SNS", "true");
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "11111111-1111-1111-1111-111111111111";
+    let mut request = create_test_request(
+        "PATCH",
+        &format!("/boxes/guardian/{}/request", box_id),
+        "lead_guardian_1",
+        Some(json!({ "reason": "  Owner is in hospital\u{0007}\n" })),
+    );
+    request.headers_mut().insert(
+        "x-request-id",
+        HeaderValue::from_static("unlock-reason-trace"),
+    );
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The owner sees the sanitized reason on the box
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/boxes/owned/{}", box_id),
+            "owner_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(
+        body["box"]["unlockRequest"]["reason"],
+        "Owner is in hospital"
+    );
+
+    let event = test_published_events()
+        .into_iter()
+        .find(|e| e["request_id"] == "unlock-reason-trace")
+        .expect("unlock_requested event should be published");
+    assert_eq!(event["reason"], "Owner is in hospital");
+}
+
+#[tokio::test]
+async fn test_request_unlock_without_reason() {
+    std::env::set_var("TEST_SNS", "true");
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "11111111-1111-1111-1111-111111111111";
+    let mut request = create_test_request(
+        "PATCH",
+        &format!("/boxes/guardian/{}/request", box_id),
+        "lead_guardian_1",
+        Some(json!({})),
+    );
+    request.headers_mut().insert(
+        "x-request-id",
+        HeaderValue::from_static("unlock-no-reason-trace"),
+    );
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let unlock = stored
+        .unlock_request
+        .expect("unlock request should be stored");
+    assert!(unlock.reason.is_none());
+    assert!(unlock.message.is_none());
+
+    let event = test_published_events()
+        .into_iter()
+        .find(|e| e["request_id"] == "unlock-no-reason-trace")
+        .expect("unlock_requested event should be published");
+    assert!(event["reason"].is_null());
+}
+
+#[tokio::test]
+async fn test_request_unlock_reason_too_long() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "11111111-1111-1111-1111-111111111111";
+    let response = app
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/request", box_id),
+            "lead_guardian_1",
+            Some(json!({ "reason": "x".repeat(501) })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert!(stored.unlock_request.is_none());
+}
+
+#[tokio::test]
+async fn test_owner_cancels_unlock_request() {
+    std::env::set_var("TEST_SNS", "true");
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    let mut request = create_test_request(
+        "DELETE",
+        &format!("/boxes/owned/{}/unlock-request", box_id),
+        "owner_1",
+        None,
+    );
+    request.headers_mut().insert(
+        "x-request-id",
+        HeaderValue::from_static("cancel-unlock-trace"),
+    );
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert!(body["box"]["unlockRequest"].is_null());
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert!(stored.unlock_request.is_none());
+
+    // The initiating guardian is told the request was cancelled
+    let event = test_published_events()
+        .into_iter()
+        .find(|e| e["request_id"] == "cancel-unlock-trace")
+        .expect("unlock_request_cancelled event should be published");
+    assert_eq!(event["event_type"], "unlock_request_cancelled");
+    assert_eq!(event["unlock_request_id"], "unlock-111");
+    assert_eq!(event["guardian_ids"], json!(["lead_guardian_1"]));
+
+    // Approving the cancelled request no longer works
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/respond", box_id),
+            "guardian_1",
+            Some(json!({ "approve": true })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Cancelling twice finds nothing to cancel
+    let response = app
+        .oneshot(create_test_request(
+            "DELETE",
+            &format!("/boxes/owned/{}/unlock-request", box_id),
+            "owner_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_non_owner_cannot_cancel_unlock_request() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    let response = app
+        .oneshot(create_test_request(
+            "DELETE",
+            &format!("/boxes/owned/{}/unlock-request", box_id),
+            "lead_guardian_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert_eq!(stored.unlock_request.unwrap().id, "unlock-111");
+}
+
+#[tokio::test]
+async fn test_request_unlock_cooldown() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "11111111-1111-1111-1111-111111111111";
+
+    let request_unlock = || {
+        let app = app.clone();
+        async move {
+            app.oneshot(create_test_request(
+                "PATCH",
+                &format!("/boxes/guardian/{}/request", box_id),
+                "lead_guardian_1",
+                Some(json!({ "message": "Let me in" })),
+            ))
+            .await
+            .unwrap()
+        }
+    };
+
+    assert_eq!(request_unlock().await.status(), StatusCode::OK);
+
+    // A second request straight away is throttled
+    let response = request_unlock().await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response
+        .headers()
+        .get("retry-after")
+        .expect("429 should carry Retry-After")
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(retry_after > 0 && retry_after <= 3600);
+    let body = response_to_json(response).await;
+    assert_eq!(body["code"], "TOO_MANY_REQUESTS");
+
+    // Cancelling the request doesn't lift the cooldown
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "DELETE",
+            &format!("/boxes/owned/{}/unlock-request", box_id),
+            "owner_1",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        request_unlock().await.status(),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+
+    // Once the window has passed a new request is allowed
+    let mut record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    record.last_unlock_request_at =
+        Some((chrono::Utc::now() - chrono::Duration::hours(2)).to_rfc3339());
+    match &store {
+        TestStore::Mock(mock) => {
+            mock.update_box(record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.update_box(record).await.unwrap();
+        }
+    }
+    assert_eq!(request_unlock().await.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_completed_unlock_resets_cooldown() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    let mut record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let mut unlock = record.unlock_request.take().unwrap();
+    unlock.status = UnlockRequestStatus::Approved;
+    record.last_unlock_request_at = Some(unlock.requested_at.clone());
+    record.unlock_request = Some(unlock);
+    match &store {
+        TestStore::Mock(mock) => {
+            mock.update_box(record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.update_box(record).await.unwrap();
+        }
+    }
+
+    let response = app
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/request", box_id),
+            "lead_guardian_1",
+            Some(json!({ "message": "Again" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_unlock_threshold_publishes_single_event() {
+    std::env::set_var("TEST_SNS", "true");
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "22222222-2222-2222-2222-222222222222";
+
+    let mut record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    record.shard_threshold = Some(2);
+    match &store {
+        TestStore::Mock(mock) => {
+            mock.update_box(record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.update_box(record).await.unwrap();
+        }
+    }
+
+    let trace_ids = [
+        "threshold-trace-1",
+        "threshold-trace-2",
+        "threshold-trace-3",
+    ];
+    for (guardian_id, trace_id) in ["guardian_1", "guardian_3", "lead_guardian_1"]
+        .into_iter()
+        .zip(trace_ids)
+    {
+        let mut request = create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/respond", box_id),
+            guardian_id,
+            Some(json!({ "approve": true })),
+        );
+        request
+            .headers_mut()
+            .insert("x-request-id", HeaderValue::from_static(trace_id));
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // Only the second approval crosses the threshold
+    let events: Vec<_> = test_published_events()
+        .into_iter()
+        .filter(|e| e["event_type"] == "box_unlocked")
+        .filter(|e| trace_ids.iter().any(|id| e["request_id"] == *id))
+        .collect();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["request_id"], "threshold-trace-2");
+    assert_eq!(events[0]["owner_id"], "owner_1");
+    assert_eq!(events[0]["unlock_request_id"], "unlock-111");
+    assert_eq!(
+        events[0]["approved_by"],
+        json!(["guardian_1", "guardian_3"])
+    );
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert_eq!(
+        stored.unlock_request.unwrap().status,
+        UnlockRequestStatus::Approved
+    );
+}
+
+#[tokio::test]
+async fn test_unlock_threshold_calls_webhook() {
+    std::env::set_var("TEST_SNS", "true");
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "22222222-2222-2222-2222-222222222222";
+    let url = "https://hooks.example.com/lockbox";
+
+    let mut record = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    record.shard_threshold = Some(2);
+    record.webhook_url = Some(url.into());
+    record.webhook_secret = Some("test-webhook-secret".into());
+    match &store {
+        TestStore::Mock(mock) => {
+            mock.update_box(record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.update_box(record).await.unwrap();
+        }
+    }
+
+    for guardian_id in ["guardian_1", "guardian_3", "lead_guardian_1"] {
+        let request = create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/respond", box_id),
+            guardian_id,
+            Some(json!({ "approve": true })),
+        );
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    // Only the approval that crosses the threshold calls the webhook
+    let calls: Vec<_> = test_webhook_calls()
+        .into_iter()
+        .filter(|(_, payload)| payload.box_id == box_id)
+        .collect();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].0, url);
+    assert_eq!(calls[0].1.event, "box_unlocked");
+}
+
+#[tokio::test]
+async fn test_reject_guardian_invitation() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "11111111-1111-1111-1111-111111111111";
+
+    let pending_guardian = Guardian {
+        id: "pending_guardian".into(),
+        name: "Pending Guardian".into(),
+        lead_guardian: false,
+        status: GuardianStatus::Invited,
+        added_at: now_str(),
+        invitation_id: "inv-pending".into(),
+        lock_data_received_at: None,
+        encrypted_shard: None,
+        shard_hash: None,
+        shard_fetched_at: None,
+        shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
+    };
+
+    replace_guardian(&store, box_id, pending_guardian.clone()).await;
+    set_box_locked(&store, box_id, false).await;
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/invitation", box_id),
+            &pending_guardian.id,
+            Some(json!({ "accept": false })),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["message"], "Guardian invitation rejected successfully");
+    assert_eq!(body["guardian"]["status"], "rejected");
+
+    let updated_box = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+
+    // Declining before the box is locked removes the guardian entirely
+    assert!(
+        !updated_box
+            .guardians
+            .iter()
+            .any(|g| g.id == "pending_guardian"),
+        "Declined guardian should be removed from an unlocked box"
+    );
+    assert_eq!(updated_box.guardians.len(), 3);
+}
+
+#[tokio::test]
+async fn test_decline_guardianship_after_lock_is_rejected() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "11111111-1111-1111-1111-111111111111";
+
+    // Box 1 is locked and guardian_1 has already accepted
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/invitation", box_id),
+            "guardian_1",
+            Some(json!({ "accept": false })),
+        ))
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let body = response_to_json(response).await;
+    assert!(body["error"]
+        .as_str()
+        .unwrap()
+        .contains("shards have already been distributed"));
+
+    let stored_box = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let guardian = stored_box
+        .guardians
+        .iter()
+        .find(|g| g.id == "guardian_1")
+        .expect("Guardian should remain on a locked box");
+    assert_eq!(guardian.status, GuardianStatus::Accepted);
+}
+
+#[test]
+fn test_guardian_declined_event_addresses_owner() {
+    let now = now_str();
+    let box_record = create_test_data(&now).remove(0);
+    let guardian = box_record.guardians[0].clone();
+
+    let payload = guardian_declined_event_payload(&box_record, &guardian, &now, None);
+    assert_eq!(payload["event_type"], "guardian_declined");
+    assert_eq!(payload["owner_id"], "owner_1");
+    assert_eq!(payload["guardian_name"], "Guardian One");
+    assert_eq!(payload["box_name"], "Guardian Test Box 1");
 }
 
 #[tokio::test]
@@ -969,6 +2486,9 @@ async fn test_guardian_invitation_without_pending_status() {
         shard_hash: None,
         shard_fetched_at: None,
         shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
     };
 
     replace_guardian(
@@ -1041,6 +2561,216 @@ async fn test_respond_without_unlock_request() {
     );
 }
 
+#[tokio::test]
+async fn test_double_approval_is_recorded_once() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "22222222-2222-2222-2222-222222222222"; // Box with existing unlock request
+
+    for _ in 0..2 {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "PATCH",
+                &format!("/boxes/guardian/{}/respond", box_id),
+                "guardian_1",
+                Some(json!({ "approve": true })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let unlock = stored.unlock_request.expect("unlock request should remain");
+    assert_eq!(unlock.approved_by, vec!["guardian_1".to_string()]);
+    assert_eq!(unlock.approvals.len(), 1);
+    assert_eq!(unlock.approvals[0].guardian_id, "guardian_1");
+    assert!(unlock.approvals[0].approved);
+    assert!(chrono::DateTime::parse_from_rfc3339(&unlock.approvals[0].at).is_ok());
+}
+
+async fn deny_unlock(app: &Router, box_id: &str, guardian_id: &str, trace_id: &'static str) {
+    let mut request = create_test_request(
+        "PATCH",
+        &format!("/boxes/guardian/{}/respond", box_id),
+        guardian_id,
+        Some(json!({ "approve": false })),
+    );
+    request
+        .headers_mut()
+        .insert("x-request-id", HeaderValue::from_static(trace_id));
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+async fn set_threshold(store: &TestStore, box_id: &str, threshold: u32) {
+    let mut record = match store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    record.shard_threshold = Some(threshold);
+    match store {
+        TestStore::Mock(mock) => {
+            mock.update_box(record).await.unwrap();
+        }
+        TestStore::DynamoDB(dynamo) => {
+            dynamo.update_box(record).await.unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_denial_leaving_threshold_reachable_keeps_request_open() {
+    std::env::set_var("TEST_SNS", "true");
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "22222222-2222-2222-2222-222222222222";
+    set_threshold(&store, box_id, 2).await;
+
+    deny_unlock(&app, box_id, "guardian_1", "deny-reachable-trace").await;
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let unlock = stored.unlock_request.expect("unlock request should remain");
+    assert_eq!(unlock.status, UnlockRequestStatus::Requested);
+    assert_eq!(unlock.rejected_by, vec!["guardian_1".to_string()]);
+    assert!(!unlock.approvals[0].approved);
+    assert!(!test_published_events()
+        .iter()
+        .any(|e| e["request_id"] == "deny-reachable-trace"));
+
+    // The other two guardians can still approve
+    for guardian_id in ["guardian_3", "lead_guardian_1"] {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "PATCH",
+                &format!("/boxes/guardian/{}/respond", box_id),
+                guardian_id,
+                Some(json!({ "approve": true })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    assert_eq!(
+        stored.unlock_request.unwrap().status,
+        UnlockRequestStatus::Approved
+    );
+}
+
+#[tokio::test]
+async fn test_changed_answer_counts_once() {
+    std::env::set_var("TEST_SNS", "true");
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "22222222-2222-2222-2222-222222222222";
+    set_threshold(&store, box_id, 2).await;
+
+    let respond = |guardian_id: &'static str, approve: bool| {
+        app.clone().oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/respond", box_id),
+            guardian_id,
+            Some(json!({ "approve": approve })),
+        ))
+    };
+
+    // Approving then denying moves guardian_1 over rather than counting twice
+    assert_eq!(
+        respond("guardian_1", true).await.unwrap().status(),
+        StatusCode::OK
+    );
+    assert_eq!(
+        respond("guardian_1", false).await.unwrap().status(),
+        StatusCode::OK
+    );
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let unlock = stored.unlock_request.expect("unlock request should remain");
+    assert_eq!(unlock.status, UnlockRequestStatus::Requested);
+    assert!(unlock.approved_by.is_empty());
+    assert_eq!(unlock.rejected_by, vec!["guardian_1".to_string()]);
+
+    for guardian_id in ["guardian_3", "lead_guardian_1"] {
+        assert_eq!(
+            respond(guardian_id, true).await.unwrap().status(),
+            StatusCode::OK
+        );
+    }
+
+    // Once decided, answers can't change
+    assert_eq!(
+        respond("guardian_1", true).await.unwrap().status(),
+        StatusCode::CONFLICT
+    );
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let unlock = stored.unlock_request.unwrap();
+    assert_eq!(unlock.status, UnlockRequestStatus::Approved);
+    assert_eq!(unlock.approved_by.len(), 2);
+    assert_eq!(unlock.rejected_by, vec!["guardian_1".to_string()]);
+}
+
+#[tokio::test]
+async fn test_denial_making_threshold_unreachable_closes_request() {
+    std::env::set_var("TEST_SNS", "true");
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+    let box_id = "22222222-2222-2222-2222-222222222222";
+    set_threshold(&store, box_id, 2).await;
+
+    // With three guardians and a threshold of two, a second denial leaves too
+    // few guardians to approve
+    deny_unlock(&app, box_id, "guardian_1", "deny-first-trace").await;
+    deny_unlock(&app, box_id, "guardian_3", "deny-second-trace").await;
+
+    let stored = match &store {
+        TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+    };
+    let unlock = stored.unlock_request.expect("denied request is kept");
+    assert_eq!(unlock.status, UnlockRequestStatus::Rejected);
+
+    // The owner is told once, by the denial that put the threshold out of reach
+    let events: Vec<_> = test_published_events()
+        .into_iter()
+        .filter(|e| e["event_type"] == "unlock_request_denied")
+        .filter(|e| e["request_id"] == "deny-first-trace" || e["request_id"] == "deny-second-trace")
+        .collect();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0]["request_id"], "deny-second-trace");
+    assert_eq!(events[0]["owner_id"], "owner_1");
+    assert_eq!(events[0]["unlock_request_id"], "unlock-111");
+    assert_eq!(events[0]["denied_by"], json!(["guardian_1", "guardian_3"]));
+
+    // Nobody can answer a closed request
+    let response = app
+        .oneshot(create_test_request(
+            "PATCH",
+            &format!("/boxes/guardian/{}/respond", box_id),
+            "lead_guardian_1",
+            Some(json!({ "approve": true })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
 #[tokio::test]
 async fn test_non_guardian_cannot_respond() {
     // Setup with test data
@@ -1179,6 +2909,71 @@ async fn test_accept_guardian_shard_success() {
     );
 }
 
+#[tokio::test]
+async fn test_accept_guardian_shard_flags_fully_accepted_box() {
+    let (app, store) = create_test_app().await;
+    add_test_data_to_store(&store).await;
+
+    let box_id = "11111111-1111-1111-1111-111111111111";
+    let get_box = || async {
+        match &store {
+            TestStore::Mock(mock) => mock.get_box_consistent(box_id).await.unwrap(),
+            TestStore::DynamoDB(dynamo) => dynamo.get_box_consistent(box_id).await.unwrap(),
+        }
+    };
+    let in_reminder_scan = || async {
+        let locked = match &store {
+            TestStore::Mock(mock) => mock.scan_locked_boxes().await.unwrap(),
+            TestStore::DynamoDB(dynamo) => dynamo.scan_locked_boxes().await.unwrap(),
+        };
+        locked.iter().any(|b| b.id == box_id)
+    };
+
+    // Every guardian accepts their shard
+    for guardian_id in ["guardian_1", "guardian_2", "lead_guardian_1"] {
+        assert!(!get_box().await.all_shards_accepted);
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "POST",
+                &format!("/boxes/guardian/{}/shard/accept", box_id),
+                guardian_id,
+                None,
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    assert!(get_box().await.all_shards_accepted);
+    assert!(!in_reminder_scan().await);
+
+    // A guardian added after locking has not accepted yet, so the box is
+    // back in the reminder scan
+    replace_guardian(
+        &store,
+        box_id,
+        Guardian {
+            id: "late_guardian".into(),
+            name: "Late Guardian".into(),
+            lead_guardian: false,
+            status: GuardianStatus::Accepted,
+            added_at: now_str(),
+            invitation_id: "invitation_late".into(),
+            lock_data_received_at: Some(now_str()),
+            encrypted_shard: None,
+            shard_hash: None,
+            shard_fetched_at: None,
+            shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
+        },
+    )
+    .await;
+    assert!(!get_box().await.all_shards_accepted);
+    assert!(in_reminder_scan().await);
+}
+
 #[tokio::test]
 async fn test_accept_guardian_shard_already_accepted() {
     let (app, store) = create_test_app().await;
@@ -1199,6 +2994,9 @@ async fn test_accept_guardian_shard_already_accepted() {
         shard_hash: None,
         shard_fetched_at: None,
         shard_accepted_at: Some("2024-01-01T00:00:00Z".to_string()),
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
     };
     replace_guardian(&store, box_id, accepted_guardian).await;
 
@@ -1282,6 +3080,9 @@ async fn test_accept_guardian_shard_unlocked_box() {
             shard_hash: None,
             shard_fetched_at: None,
             shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
         }],
         unlock_instructions: None,
         unlock_request: None,
@@ -1290,6 +3091,11 @@ async fn test_accept_guardian_shard_unlocked_box() {
         shards_fetched: None,
         total_shards: None,
         shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
     };
 
     match &store {
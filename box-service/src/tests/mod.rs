@@ -1,2 +1,4 @@
 pub mod box_tests;
+pub mod grpc_tests;
 pub mod guardian_tests;
+pub mod user_tests;
@@ -0,0 +1,477 @@
+use axum::http::StatusCode;
+use axum::{Extension, Router};
+use chrono::{Duration, Utc};
+use lockbox_shared::auth::create_test_request;
+use lockbox_shared::maintenance::{ReadOnlyMode, MAINTENANCE_CODE};
+use lockbox_shared::models::{
+    now_str, BoxRecord, Document, Guardian, GuardianStatus, Invitation, PushToken,
+};
+use lockbox_shared::push::{SharedPushSender, DEVICE_NOT_REGISTERED};
+use lockbox_shared::store::{
+    BoxStore, PushTokenStore, SharedInvitationStore, SharedPushTokenStore,
+};
+use lockbox_shared::test_utils::http_test_utils::response_to_json;
+use lockbox_shared::test_utils::mock_box_store::MockBoxStore;
+use lockbox_shared::test_utils::mock_invitation_store::MockInvitationStore;
+use lockbox_shared::test_utils::mock_push_sender::MockPushSender;
+use lockbox_shared::test_utils::mock_push_token_store::MockPushTokenStore;
+use serde_json::json;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+use crate::routes;
+
+// The app with in-memory push tokens and a push sender that records messages
+fn create_push_test_app() -> (Router, Arc<MockPushTokenStore>, Arc<MockPushSender>) {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+    let push_tokens = Arc::new(MockPushTokenStore::new());
+    let push_sender = Arc::new(MockPushSender::new());
+    let app = routes::create_router_with_store(Arc::new(MockBoxStore::new()), "")
+        .layer(Extension(push_tokens.clone() as SharedPushTokenStore))
+        .layer(Extension(push_sender.clone() as SharedPushSender));
+    (app, push_tokens, push_sender)
+}
+
+#[tokio::test]
+async fn test_send_test_notification() {
+    let (app, push_tokens, push_sender) = create_push_test_app();
+    push_tokens
+        .save_push_token(PushToken {
+            user_id: "push_user".into(),
+            push_token: "ExponentPushToken[push_user]".into(),
+            platform: "ios".into(),
+            updated_at: now_str(),
+            last_test_notification_at: None,
+            last_verified_at: None,
+            notification_prefs: None,
+            utc_offset_minutes: None,
+        })
+        .await
+        .unwrap();
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/users/push-token/test",
+            "push_user",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    let tickets = body["tickets"].as_array().unwrap();
+    assert_eq!(tickets.len(), 1);
+    assert_eq!(tickets[0]["status"], "ok");
+
+    // One test message went to the caller's own token
+    let sent = push_sender.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].to, "ExponentPushToken[push_user]");
+    assert_eq!(sent[0].body, "Lockbox notifications are working");
+    assert_eq!(sent[0].data.as_ref().unwrap()["type"], "test");
+    assert_eq!(sent[0].sound.as_deref(), Some("default"));
+    assert_eq!(sent[0].badge, None);
+
+    // A second attempt straight away is rate limited
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/users/push-token/test",
+            "push_user",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key("retry-after"));
+    assert_eq!(push_sender.sent().len(), 1);
+
+    // Users without a registered token have nothing to test
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/users/push-token/test",
+            "no_token_user",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(push_sender.sent().len(), 1);
+}
+
+#[tokio::test]
+async fn test_register_push_token_with_verification() {
+    let (app, push_tokens, push_sender) = create_push_test_app();
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PUT",
+            "/users/push-token?verify=true",
+            "verified_user",
+            Some(json!({
+                "pushToken": "ExponentPushToken[verified]",
+                "platform": "android"
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["verified"], true);
+    assert_eq!(body["ticket"]["status"], "ok");
+
+    // The check is a silent push
+    let sent = push_sender.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].to, "ExponentPushToken[verified]");
+    assert!(sent[0].title.is_empty() && sent[0].body.is_empty());
+
+    let token = push_tokens
+        .get_push_token("verified_user")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(token.last_verified_at.is_some());
+
+    // Without verify=true nothing is sent and the response is unchanged
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PUT",
+            "/users/push-token",
+            "unverified_user",
+            Some(json!({
+                "pushToken": "ExponentPushToken[unverified]",
+                "platform": "ios"
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert!(body.get("verified").is_none());
+    assert_eq!(push_sender.sent().len(), 1);
+    let token = push_tokens
+        .get_push_token("unverified_user")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(token.last_verified_at.is_none());
+}
+
+#[tokio::test]
+async fn test_register_push_token_verification_rejects_unregistered_device() {
+    let (app, push_tokens, push_sender) = create_push_test_app();
+    push_sender.reject_token("ExponentPushToken[stale]", DEVICE_NOT_REGISTERED);
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PUT",
+            "/users/push-token?verify=true",
+            "stale_user",
+            Some(json!({
+                "pushToken": "ExponentPushToken[stale]",
+                "platform": "ios"
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    assert!(push_tokens
+        .get_push_token("stale_user")
+        .await
+        .unwrap()
+        .is_none());
+
+    // Other refusals leave the token registered but unverified
+    push_sender.reject_token("ExponentPushToken[limited]", "MessageRateExceeded");
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PUT",
+            "/users/push-token?verify=true",
+            "limited_user",
+            Some(json!({
+                "pushToken": "ExponentPushToken[limited]",
+                "platform": "ios"
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["verified"], false);
+    assert_eq!(body["ticket"]["details"]["error"], "MessageRateExceeded");
+    let token = push_tokens
+        .get_push_token("limited_user")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(token.last_verified_at.is_none());
+}
+
+#[tokio::test]
+async fn test_read_only_mode_blocks_user_writes() {
+    let (app, push_tokens, _) = create_push_test_app();
+    let app = app.layer(Extension(ReadOnlyMode(true)));
+
+    let response = app
+        .oneshot(create_test_request(
+            "PUT",
+            "/users/push-token",
+            "maintenance_user",
+            Some(json!({
+                "pushToken": "ExponentPushToken[maintenance]",
+                "platform": "ios"
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = response_to_json(response).await;
+    assert_eq!(body["code"], MAINTENANCE_CODE);
+    assert!(push_tokens
+        .get_push_token("maintenance_user")
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_update_notification_prefs() {
+    let (app, push_tokens, _) = create_push_test_app();
+    let prefs = json!({
+        "remindersEnabled": false,
+        "quietHours": { "start": "22:00", "end": "07:00", "utcOffsetMinutes": -300 }
+    });
+
+    // Preferences live with the push token, so one is needed first
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PUT",
+            "/users/notification-prefs",
+            "prefs_user",
+            Some(prefs.clone()),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PUT",
+            "/users/push-token",
+            "prefs_user",
+            Some(json!({
+                "pushToken": "ExponentPushToken[prefs]",
+                "platform": "ios"
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PUT",
+            "/users/notification-prefs",
+            "prefs_user",
+            Some(prefs.clone()),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["notificationPrefs"], prefs);
+
+    // Re-registering the token keeps them
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PUT",
+            "/users/push-token",
+            "prefs_user",
+            Some(json!({
+                "pushToken": "ExponentPushToken[new_device]",
+                "platform": "android"
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let token = push_tokens
+        .get_push_token("prefs_user")
+        .await
+        .unwrap()
+        .unwrap();
+    let stored = token.notification_prefs.unwrap();
+    assert!(!stored.reminders_enabled);
+    assert_eq!(stored.quiet_hours.unwrap().utc_offset_minutes, -300);
+
+    // Quiet hours must be well-formed
+    for invalid in [
+        json!({ "quietHours": { "start": "10pm", "end": "07:00", "utcOffsetMinutes": 0 } }),
+        json!({ "quietHours": { "start": "07:00", "end": "07:00", "utcOffsetMinutes": 0 } }),
+        json!({ "quietHours": { "start": "22:00", "end": "07:00", "utcOffsetMinutes": 1000 } }),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "PUT",
+                "/users/notification-prefs",
+                "prefs_user",
+                Some(invalid),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+fn stats_box(id: &str, owner_id: &str, is_locked: bool, documents: usize) -> BoxRecord {
+    let guardian = |id: &str, status: GuardianStatus| Guardian {
+        id: id.into(),
+        name: id.into(),
+        lead_guardian: false,
+        status,
+        added_at: now_str(),
+        invitation_id: format!("inv_{}", id),
+        lock_data_received_at: None,
+        encrypted_shard: None,
+        shard_hash: None,
+        shard_fetched_at: None,
+        shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
+    };
+    BoxRecord {
+        id: id.into(),
+        name: id.into(),
+        description: "Stats box".into(),
+        is_locked,
+        locked_at: is_locked.then(now_str),
+        created_at: now_str(),
+        updated_at: now_str(),
+        owner_id: owner_id.into(),
+        owner_name: None,
+        documents: (0..documents)
+            .map(|n| Document {
+                id: format!("{}_doc_{}", id, n),
+                title: "Doc".into(),
+                encrypted_content: Some("ZG9j".into()),
+                created_at: now_str(),
+                content_type: None,
+                size_bytes: None,
+                version: Some(1),
+                s3_key: None,
+            })
+            .collect(),
+        guardians: vec![
+            guardian("accepted_guardian", GuardianStatus::Accepted),
+            guardian("declined_guardian", GuardianStatus::Rejected),
+        ],
+        unlock_instructions: None,
+        unlock_request: None,
+        version: 0,
+        shard_threshold: None,
+        shards_fetched: None,
+        total_shards: None,
+        shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
+    }
+}
+
+fn stats_invitation(id: &str, creator_id: &str, opened: bool, expires_in_hours: i64) -> Invitation {
+    Invitation {
+        id: id.into(),
+        invite_code: format!("CODE_{}", id),
+        invited_name: "Guardian".into(),
+        box_id: "stats_open".into(),
+        created_at: now_str(),
+        expires_at: (Utc::now() + Duration::hours(expires_in_hours)).to_rfc3339(),
+        opened,
+        linked_user_id: opened.then(|| "linked_user".to_string()),
+        creator_id: creator_id.into(),
+        is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
+    }
+}
+
+#[tokio::test]
+async fn test_get_user_stats() {
+    lockbox_shared::test_utils::test_logging::init_test_logging();
+    let store = Arc::new(MockBoxStore::new());
+    for box_rec in [
+        stats_box("stats_open", "stats_owner", false, 2),
+        stats_box("stats_locked", "stats_owner", true, 1),
+        stats_box("stats_empty", "stats_owner", false, 0),
+        stats_box("stats_other", "someone_else", true, 4),
+    ] {
+        store.create_box(box_rec).await.unwrap();
+    }
+    let invitations = Arc::new(MockInvitationStore::with_data(vec![
+        stats_invitation("pending_1", "stats_owner", false, 24),
+        stats_invitation("pending_2", "stats_owner", false, 1),
+        stats_invitation("accepted", "stats_owner", true, 24),
+        stats_invitation("expired", "stats_owner", false, -1),
+        stats_invitation("not_mine", "someone_else", false, 24),
+    ]));
+    let app = routes::create_router_with_store(store, "")
+        .layer(Extension(invitations as SharedInvitationStore));
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/users/me/stats",
+            "stats_owner",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(
+        body,
+        json!({
+            "totalBoxes": 3,
+            "lockedBoxes": 1,
+            "totalDocuments": 3,
+            // Declined guardians aren't counted
+            "totalGuardians": 3,
+            "pendingInvitations": 2
+        })
+    );
+
+    // Someone who owns nothing gets zeroes
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/users/me/stats",
+            "newcomer",
+            None,
+        ))
+        .await
+        .unwrap();
+    let body = response_to_json(response).await;
+    assert_eq!(body["totalBoxes"], 0);
+    assert_eq!(body["pendingInvitations"], 0);
+}
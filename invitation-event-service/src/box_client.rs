@@ -0,0 +1,108 @@
+use lockbox_shared::auth::service::{create_service_token, service_secret, ON_BEHALF_OF_HEADER};
+use lockbox_shared::models::events::InvitationEvent;
+use log::{info, warn};
+use serde_json::json;
+use std::env;
+use std::time::Duration;
+
+use crate::errors::AppError;
+
+// Name this consumer presents in its service tokens
+const SERVICE_NAME: &str = "invitation-event-handler";
+// Tokens only need to outlive a single request
+const SERVICE_TOKEN_TTL_SECS: usize = 60;
+// A hung box-service fails the invocation, so SNS retries it, rather than
+// holding the Lambda until its own timeout
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Calls box-service's internal endpoints with a service token
+pub struct BoxServiceClient {
+    base_url: String,
+    secret: String,
+    http: reqwest::Client,
+}
+
+impl BoxServiceClient {
+    pub fn new(base_url: impl Into<String>, secret: impl Into<String>) -> Self {
+        BoxServiceClient {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            secret: secret.into(),
+            http: reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("Failed to build HTTP client"),
+        }
+    }
+
+    /// Client for the box-service at `BOX_SERVICE_URL`, or None when that or
+    /// `SERVICE_AUTH_SECRET` isn't configured
+    pub fn from_env() -> Option<Self> {
+        let base_url = env::var("BOX_SERVICE_URL").ok()?;
+        let secret = service_secret()?;
+        Some(Self::new(base_url, secret))
+    }
+
+    /// Asks box-service to make the user who redeemed the invitation an
+    /// accepted guardian of its box
+    pub async fn accept_redeemed_invitation(
+        &self,
+        event: &InvitationEvent,
+        user_id: &str,
+        creator_id: &str,
+    ) -> Result<(), AppError> {
+        let url = format!(
+            "{}/boxes/internal/{}/guardians/accept",
+            self.base_url, event.box_id
+        );
+        let token = create_service_token(SERVICE_NAME, &self.secret, SERVICE_TOKEN_TTL_SECS);
+
+        let response = self
+            .http
+            .post(&url)
+            .bearer_auth(token)
+            .header(ON_BEHALF_OF_HEADER, user_id)
+            .json(&json!({
+                "invitationId": event.invitation_id,
+                "leadGuardian": event.is_lead_guardian,
+                "invitedName": event.invited_name,
+                "creatorId": creator_id,
+            }))
+            .send()
+            .await
+            .map_err(|e| AppError::InternalError(format!("Box service request failed: {}", e)))?;
+
+        let status = response.status();
+        if status.is_success() {
+            info!(
+                "Box service accepted guardian: box_id={}, invitation_id={}, user_id={}",
+                event.box_id, event.invitation_id, user_id
+            );
+            return Ok(());
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(AppError::BoxNotFound(format!(
+                "Box not found: {}",
+                event.box_id
+            )));
+        }
+        warn!(
+            "Box service refused guardian acceptance: box_id={}, status={}, body={}",
+            event.box_id, status, body
+        );
+        // Only failures on box-service's side may go away on a retry
+        if status.is_client_error() {
+            return Err(AppError::Rejected(format!(
+                "Box service returned {}: {}",
+                status, body
+            )));
+        }
+        Err(AppError::InternalError(format!(
+            "Box service returned {}: {}",
+            status, body
+        )))
+    }
+}
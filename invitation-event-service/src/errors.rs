@@ -21,6 +21,9 @@ pub enum AppError {
     VersionConflict(String),
     GuardianNotFound(String),
     BoxNotFound(String),
+    /// box-service refused the request for good, e.g. because the box is
+    /// locked. Redelivering the event would only be refused again.
+    Rejected(String),
     InternalError(String),
 }
 
@@ -30,6 +33,7 @@ impl fmt::Display for AppError {
             AppError::VersionConflict(msg) => write!(f, "Version conflict: {}", msg),
             AppError::GuardianNotFound(msg) => write!(f, "Guardian not found: {}", msg),
             AppError::BoxNotFound(msg) => write!(f, "Box not found: {}", msg),
+            AppError::Rejected(msg) => write!(f, "Rejected: {}", msg),
             AppError::InternalError(msg) => write!(f, "Internal error: {}", msg),
         }
     }
@@ -45,6 +49,7 @@ impl From<anyhow::Error> for AppError {
             match app_err {
                 AppError::GuardianNotFound(msg) => AppError::GuardianNotFound(msg.clone()),
                 AppError::BoxNotFound(msg) => AppError::BoxNotFound(msg.clone()),
+                AppError::Rejected(msg) => AppError::Rejected(msg.clone()),
                 AppError::VersionConflict(msg) => AppError::VersionConflict(msg.clone()),
                 AppError::InternalError(msg) => AppError::InternalError(msg.clone()),
             }
@@ -7,6 +7,7 @@ use std::sync::Arc; // Add Arc for shared state
 use log::{error, info, warn}; // Add log import
 
 // Import our custom error type
+use crate::box_client::BoxServiceClient;
 use crate::errors::AppError;
 use crate::errors::InvitationEventError; // Add AppError import
 
@@ -25,7 +26,7 @@ pub async fn handle_invitation_created(
     );
 
     // Get the box
-    let mut box_record = state.get_box(&event.box_id).await.map_err(|e| {
+    let mut box_record = state.get_box_consistent(&event.box_id).await.map_err(|e| {
         error!("Failed to get box {}: {}", event.box_id, e);
         AppError::BoxNotFound(format!("Box not found: {}", event.box_id))
     })?;
@@ -63,6 +64,9 @@ pub async fn handle_invitation_created(
         shard_hash: None,
         shard_fetched_at: None,
         shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
     };
 
     // Add guardian to box
@@ -125,6 +129,56 @@ pub async fn handle_invitation_opened(
     }
 }
 
+// Handler for invitation_accepted events, published when an invitation is
+// redeemed. The guardian is added by box-service, which we call with a service
+// token on behalf of the redeeming user.
+pub async fn handle_invitation_accepted(
+    client: &BoxServiceClient,
+    event: &InvitationEvent,
+) -> Result<(), AppError> {
+    info!(
+        "Processing invitation_accepted event for box_id={}, invitation_id={}",
+        event.box_id, event.invitation_id
+    );
+
+    let user_id = match &event.user_id {
+        Some(id) if !id.is_empty() => id,
+        _ => {
+            error!("User ID is missing in the event");
+            return Err(InvitationEventError::MissingField("user_id".to_string()).into());
+        }
+    };
+
+    // box-service only honours invitations the box's owner created. Events
+    // from before the creator was recorded can't show that, so the guardian
+    // is left to accept from the app instead.
+    let Some(creator_id) = event.creator_id.as_deref().filter(|id| !id.is_empty()) else {
+        warn!(
+            "Ignoring invitation_accepted event without a creator: box_id={}, invitation_id={}",
+            event.box_id, event.invitation_id
+        );
+        return Ok(());
+    };
+
+    match client
+        .accept_redeemed_invitation(event, user_id, creator_id)
+        .await
+    {
+        Err(AppError::BoxNotFound(msg)) => {
+            warn!("Ignoring event for non-existent box: {}", msg);
+            Ok(())
+        }
+        Err(AppError::Rejected(msg)) => {
+            warn!(
+                "Dropping invitation_accepted event box-service won't apply: box_id={}, invitation_id={}, {}",
+                event.box_id, event.invitation_id, msg
+            );
+            Ok(())
+        }
+        result => result,
+    }
+}
+
 // Reasonable retry limit
 const MAX_RETRIES: usize = 5;
 
@@ -239,7 +293,7 @@ async fn update_specific_guardian(
     user_id: &str,
 ) -> anyhow::Result<()> {
     // Get the current box state
-    let mut box_record = store.get_box(box_id).await?;
+    let mut box_record = store.get_box_consistent(box_id).await?;
 
     // Find the guardian matching the invitation ID
     let guardian_idx = box_record
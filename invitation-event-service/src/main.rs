@@ -1,13 +1,14 @@
 use aws_lambda_events::event::sns::SnsEvent;
-use env_logger;
 use lambda_runtime::{service_fn, Error, LambdaEvent};
-use lockbox_shared::models::events::InvitationEvent;
+use lockbox_shared::models::events::{EventEnvelope, InvitationEvent, EVENT_SCHEMA_VERSION};
 use lockbox_shared::store::{dynamo::DynamoBoxStore, BoxStore};
-use log::{error, info};
+use log::{error, info, warn};
 use std::sync::Arc;
 
 // Import the handlers module
 mod handlers;
+// Client for box-service's internal endpoints
+mod box_client;
 // Add the errors module
 mod errors;
 
@@ -16,10 +17,8 @@ mod tests;
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    // Initialize env_logger
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
-
-    info!("Logging initialized with env_logger");
+    lockbox_shared::logging::init();
+    info!("Logging initialized");
 
     info!("Starting Box Invitation Handler Lambda");
 
@@ -44,6 +43,17 @@ where
         // Extract and parse the SNS message
         let message = record.sns;
 
+        // Leave events from a newer producer for a consumer that understands them
+        if let Ok(envelope) = serde_json::from_str::<EventEnvelope>(&message.message) {
+            if !envelope.is_supported() {
+                warn!(
+                    "Skipping {} event with schema_version={}, newest understood is {}",
+                    envelope.event_type, envelope.schema_version, EVENT_SCHEMA_VERSION
+                );
+                continue;
+            }
+        }
+
         // Try to parse the message as an InvitationEvent
         if let Ok(invitation_event) = serde_json::from_str::<InvitationEvent>(&message.message) {
             match invitation_event.event_type.as_str() {
@@ -53,6 +63,17 @@ where
                 "invitation_viewed" => {
                     handlers::handle_invitation_opened(store.clone(), &invitation_event).await?
                 }
+                "invitation_accepted" => match box_client::BoxServiceClient::from_env() {
+                    Some(client) => {
+                        handlers::handle_invitation_accepted(&client, &invitation_event).await?
+                    }
+                    None => {
+                        error!(
+                            "Cannot accept invitation {}: BOX_SERVICE_URL or SERVICE_AUTH_SECRET is not set",
+                            invitation_event.invitation_id
+                        );
+                    }
+                },
                 _ => {
                     error!("Unknown event type: {}", invitation_event.event_type);
                 }
@@ -5,7 +5,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use lockbox_shared::error::Result as StoreResult;
-use lockbox_shared::models::events::InvitationEvent;
+use lockbox_shared::models::events::{InvitationEvent, EVENT_SCHEMA_VERSION};
 use lockbox_shared::models::GuardianStatus;
 use lockbox_shared::store::dynamo::DynamoBoxStore;
 use lockbox_shared::store::BoxStore;
@@ -15,7 +15,9 @@ use lockbox_shared::test_utils::dynamo_test_utils::{
 use lockbox_shared::test_utils::mock_box_store::MockBoxStore;
 use lockbox_shared::test_utils::test_logging;
 
+use crate::box_client::BoxServiceClient;
 use crate::handler;
+use crate::handlers::handle_invitation_accepted;
 
 // Constants for DynamoDB tests
 const TEST_TABLE_NAME: &str = "box-invitation-test-table";
@@ -130,6 +132,7 @@ fn create_test_sns_event_full(
     // Create invitation event
     let invitation_event = InvitationEvent {
         event_type: event_type.to_string(),
+        schema_version: EVENT_SCHEMA_VERSION,
         invitation_id: invitation_id.to_string(),
         box_id: box_id.to_string(),
         timestamp: chrono::Utc::now().to_rfc3339(),
@@ -141,11 +144,16 @@ fn create_test_sns_event_full(
         invite_code: "test-code".to_string(),
         invited_name,
         is_lead_guardian,
+        creator_id: Some("test_owner".to_string()),
     };
 
     // Serialize to JSON
     let event_json = serde_json::to_string(&invitation_event).unwrap();
+    sns_event_from_message(event_json)
+}
 
+// Wraps a raw message body in an SNS event
+fn sns_event_from_message(event_json: String) -> LambdaEvent<SnsEvent> {
     // Create SNS message with correct field names
     let sns_message = SnsMessage {
         signature: "test-signature".to_string(),
@@ -218,6 +226,11 @@ async fn test_invitation_created_handler() {
         shards_fetched: None,
         total_shards: None,
         shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
         version: 0,
     };
 
@@ -309,6 +322,11 @@ async fn test_invitation_created_handler_without_name() {
         shards_fetched: None,
         total_shards: None,
         shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
         version: 0,
     };
 
@@ -359,6 +377,9 @@ async fn test_invitation_created_idempotency() {
         shard_hash: None,
         shard_fetched_at: None,
         shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
     };
 
     let box_record = lockbox_shared::models::BoxRecord {
@@ -379,6 +400,11 @@ async fn test_invitation_created_idempotency() {
         shards_fetched: None,
         total_shards: None,
         shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
         version: 0,
     };
 
@@ -454,6 +480,11 @@ async fn test_invitation_created_lead_guardian() {
         shards_fetched: None,
         total_shards: None,
         shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
         version: 0,
     };
 
@@ -481,6 +512,78 @@ async fn test_invitation_created_lead_guardian() {
     assert_eq!(guardian.invitation_id, invitation_id);
 }
 
+#[tokio::test]
+async fn test_handler_checks_event_schema_version() {
+    let store = create_test_store().await;
+    let box_id = "test_box_schema_version";
+
+    let box_record = lockbox_shared::models::BoxRecord {
+        id: box_id.to_string(),
+        name: "Test Box".to_string(),
+        description: "Test Description".to_string(),
+        is_locked: false,
+        locked_at: None,
+        created_at: "2023-01-01T00:00:00Z".to_string(),
+        updated_at: "2023-01-01T00:00:00Z".to_string(),
+        owner_id: "test_owner".to_string(),
+        owner_name: None,
+        documents: vec![],
+        guardians: vec![],
+        unlock_instructions: None,
+        unlock_request: None,
+        shard_threshold: None,
+        shards_fetched: None,
+        total_shards: None,
+        shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
+        version: 0,
+    };
+    store.create_box(box_record).await.unwrap();
+
+    let created = |invitation_id: &str| {
+        serde_json::json!({
+            "event_type": "invitation_created",
+            "invitation_id": invitation_id,
+            "box_id": box_id,
+            "user_id": null,
+            "invite_code": "test-code",
+            "invited_name": "Alice",
+            "timestamp": Utc::now().to_rfc3339()
+        })
+    };
+
+    // A version from a newer producer is skipped without failing the batch
+    let mut future = created("inv-future");
+    future["schema_version"] = serde_json::json!(EVENT_SCHEMA_VERSION + 1);
+    let result = store
+        .handle_event(sns_event_from_message(future.to_string()))
+        .await;
+    assert!(result.is_ok(), "Handler failed: {:?}", result.err());
+    assert!(store.get_box(box_id).await.unwrap().guardians.is_empty());
+
+    // Events from before the field existed are read as version 1
+    let result = store
+        .handle_event(sns_event_from_message(created("inv-v1").to_string()))
+        .await;
+    assert!(result.is_ok(), "Handler failed: {:?}", result.err());
+
+    // As are events that give it explicitly
+    let mut explicit = created("inv-explicit");
+    explicit["schema_version"] = serde_json::json!(1);
+    let result = store
+        .handle_event(sns_event_from_message(explicit.to_string()))
+        .await;
+    assert!(result.is_ok(), "Handler failed: {:?}", result.err());
+
+    let guardians = store.get_box(box_id).await.unwrap().guardians;
+    let invitation_ids: Vec<&str> = guardians.iter().map(|g| g.invitation_id.as_str()).collect();
+    assert_eq!(invitation_ids, ["inv-v1", "inv-explicit"]);
+}
+
 #[tokio::test]
 async fn test_invitation_viewed_handler() {
     // Create test store
@@ -511,6 +614,11 @@ async fn test_invitation_viewed_handler() {
         shards_fetched: None,
         total_shards: None,
         shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
         version: 0,
     };
 
@@ -528,6 +636,9 @@ async fn test_invitation_viewed_handler() {
         shard_hash: None,
         shard_fetched_at: None,
         shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
     };
 
     box_record.guardians.push(guardian);
@@ -605,6 +716,9 @@ async fn test_no_matching_guardian() {
             shard_hash: None,
             shard_fetched_at: None,
             shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
         }],
         unlock_instructions: None,
         unlock_request: None,
@@ -612,6 +726,11 @@ async fn test_no_matching_guardian() {
         shards_fetched: None,
         total_shards: None,
         shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
         version: 0,
     };
 
@@ -679,6 +798,11 @@ async fn test_concurrent_updates() {
         shards_fetched: None,
         total_shards: None,
         shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
         version: 0,
     };
 
@@ -695,6 +819,9 @@ async fn test_concurrent_updates() {
         shard_hash: None,
         shard_fetched_at: None,
         shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
     };
 
     let guardian2 = lockbox_shared::models::Guardian {
@@ -709,6 +836,9 @@ async fn test_concurrent_updates() {
         shard_hash: None,
         shard_fetched_at: None,
         shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
     };
 
     let guardian3 = lockbox_shared::models::Guardian {
@@ -723,6 +853,9 @@ async fn test_concurrent_updates() {
         shard_hash: None,
         shard_fetched_at: None,
         shard_accepted_at: None,
+        shard_commit: None,
+        shard_first_fetched_at: None,
+        last_reminded_at: None,
     };
 
     box_record.guardians.push(guardian1);
@@ -829,6 +962,9 @@ async fn test_malformed_event() {
             shard_hash: None,
             shard_fetched_at: None,
             shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
         }],
         unlock_instructions: None,
         unlock_request: None,
@@ -836,6 +972,11 @@ async fn test_malformed_event() {
         shards_fetched: None,
         total_shards: None,
         shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
         version: 0,
     };
 
@@ -909,3 +1050,97 @@ async fn test_malformed_event() {
     assert_eq!(box_record.description, original_box.description);
     assert_eq!(box_record.is_locked, original_box.is_locked);
 }
+
+fn accepted_event(user_id: Option<&str>) -> InvitationEvent {
+    InvitationEvent {
+        event_type: "invitation_accepted".to_string(),
+        schema_version: EVENT_SCHEMA_VERSION,
+        invitation_id: "test_invitation_accepted".to_string(),
+        box_id: "test_box_accepted".to_string(),
+        user_id: user_id.map(str::to_string),
+        invite_code: "test-code".to_string(),
+        invited_name: Some("Invited Friend".to_string()),
+        is_lead_guardian: true,
+        creator_id: Some("box_owner".to_string()),
+        timestamp: Utc::now().to_rfc3339(),
+    }
+}
+
+#[tokio::test]
+async fn test_invitation_accepted_calls_box_service() {
+    test_logging::init_test_logging();
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/boxes/internal/test_box_accepted/guardians/accept")
+        .match_header(
+            "authorization",
+            mockito::Matcher::Regex("^Bearer .+".into()),
+        )
+        .match_header("x-on-behalf-of", "redeeming_user")
+        .match_body(mockito::Matcher::Json(serde_json::json!({
+            "invitationId": "test_invitation_accepted",
+            "leadGuardian": true,
+            "invitedName": "Invited Friend",
+            "creatorId": "box_owner",
+        })))
+        .with_status(200)
+        .with_body(r#"{"guardian":{}}"#)
+        .create_async()
+        .await;
+
+    let client = BoxServiceClient::new(server.url(), "service-secret-for-tests-0123456789abcdef");
+    handle_invitation_accepted(&client, &accepted_event(Some("redeeming_user")))
+        .await
+        .unwrap();
+    mock.assert_async().await;
+
+    // Events without a user can't be applied
+    assert!(handle_invitation_accepted(&client, &accepted_event(None))
+        .await
+        .is_err());
+
+    // Nor can events that don't say who created the invitation, but
+    // retrying them wouldn't help
+    let mut event = accepted_event(Some("redeeming_user"));
+    event.creator_id = None;
+    handle_invitation_accepted(&client, &event).await.unwrap();
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_invitation_accepted_box_service_failures() {
+    test_logging::init_test_logging();
+    let mut server = mockito::Server::new_async().await;
+    let client = BoxServiceClient::new(server.url(), "service-secret-for-tests-0123456789abcdef");
+    let event = accepted_event(Some("redeeming_user"));
+    let path = "/boxes/internal/test_box_accepted/guardians/accept";
+
+    // A deleted box is dropped like for the other events
+    let missing = server
+        .mock("POST", path)
+        .with_status(404)
+        .create_async()
+        .await;
+    handle_invitation_accepted(&client, &event).await.unwrap();
+    missing.remove_async().await;
+
+    // Refusals such as a locked box won't change on a retry, so they're
+    // acked too
+    for status in [400, 403, 409] {
+        let refused = server
+            .mock("POST", path)
+            .with_status(status)
+            .create_async()
+            .await;
+        handle_invitation_accepted(&client, &event).await.unwrap();
+        refused.remove_async().await;
+    }
+
+    // Failures on box-service's side fail the invocation so SNS retries it
+    server
+        .mock("POST", path)
+        .with_status(500)
+        .create_async()
+        .await;
+    assert!(handle_invitation_accepted(&client, &event).await.is_err());
+}
@@ -3,8 +3,8 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use lockbox_shared::error::error_body;
 use log::{error, warn};
-use serde_json::json;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -30,6 +30,12 @@ pub enum AppError {
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Service unavailable: {0}")]
+    ServiceUnavailable(String),
+
     #[error("Bad gateway: {0}")]
     #[allow(dead_code)]
     BadGateway(String),
@@ -81,21 +87,31 @@ impl AppError {
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
-            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
-            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-            AppError::InvitationExpired => (StatusCode::GONE, "Invitation has expired".to_string()),
-            AppError::InternalServerError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
-            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+        let (status, code, error_message) = match self {
+            AppError::NotFound(msg) => (StatusCode::NOT_FOUND, "NOT_FOUND", msg),
+            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, "UNAUTHORIZED", msg),
+            AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, "BAD_REQUEST", msg),
+            AppError::InvitationExpired => (
+                StatusCode::GONE,
+                "INVITATION_EXPIRED",
+                "Invitation has expired".to_string(),
+            ),
+            AppError::InternalServerError(msg) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR", msg)
+            }
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, "FORBIDDEN", msg),
             AppError::SerializationError(err) => {
                 warn!("Serialization error: {}", err);
-                (StatusCode::BAD_REQUEST, err.to_string())
+                (StatusCode::BAD_REQUEST, "BAD_REQUEST", err.to_string())
             }
-            AppError::BadGateway(msg) => (StatusCode::BAD_GATEWAY, msg),
+            AppError::Conflict(msg) => (StatusCode::CONFLICT, "CONFLICT", msg),
+            AppError::ServiceUnavailable(msg) => {
+                (StatusCode::SERVICE_UNAVAILABLE, "SERVICE_UNAVAILABLE", msg)
+            }
+            AppError::BadGateway(msg) => (StatusCode::BAD_GATEWAY, "BAD_GATEWAY", msg),
         };
 
-        let body = Json(json!({ "error": error_message }));
+        let body = Json(error_body(code, &error_message));
         (status, body).into_response()
     }
 }
@@ -118,7 +134,25 @@ impl From<lockbox_shared::error::StoreError> for AppError {
             lockbox_shared::error::StoreError::InvitationExpired => AppError::InvitationExpired,
             lockbox_shared::error::StoreError::AuthError(msg) => AppError::Unauthorized(msg),
             lockbox_shared::error::StoreError::VersionConflict(msg) => {
-                AppError::BadRequest(format!("Concurrent modification detected: {}", msg))
+                AppError::Conflict(format!("Concurrent modification detected: {}", msg))
+            }
+            lockbox_shared::error::StoreError::AlreadyExists(msg) => {
+                AppError::Conflict(format!("Id already in use: {}", msg))
+            }
+            lockbox_shared::error::StoreError::Throttled(msg) => {
+                warn!("Store throttled: {}", msg);
+                AppError::ServiceUnavailable(format!("Storage is busy, please retry: {}", msg))
+            }
+            lockbox_shared::error::StoreError::Transport(msg) => {
+                warn!("Store unreachable: {}", msg);
+                AppError::ServiceUnavailable(format!(
+                    "Storage is unavailable, please retry: {}",
+                    msg
+                ))
+            }
+            lockbox_shared::error::StoreError::Serialization(msg) => {
+                error!("Store serialization error: {}", msg);
+                AppError::InternalServerError(msg)
             }
         }
     }
@@ -1,9 +1,8 @@
 use aws_sdk_sns::Client as SnsClient;
-use axum::{
-    extract::{Extension, Path, State},
-    Json,
-};
+use axum::extract::{Extension, OriginalUri, Path, Query, State};
+use axum::http::HeaderMap;
 use chrono::{Duration, Utc};
+use lockbox_shared::extract::Json;
 use log::{debug, error, info};
 use serde_json::json;
 use std::collections::HashMap;
@@ -13,29 +12,100 @@ use tokio::sync::OnceCell;
 use uuid::Uuid;
 
 use lockbox_shared::{
-    models::{Invitation, MessageResponse},
-    store::InvitationStore,
+    auth::RequireAdmin,
+    error::ErrorBody,
+    idempotency::{claim, idempotency_key, request_hash, scoped_key, Claim},
+    models::{
+        events::{InvitationEvent, EVENT_SCHEMA_VERSION},
+        Invitation, MessageResponse,
+    },
+    paging::pagination_headers,
+    store::{InvitationStore, SharedIdempotencyStore},
+    validation::validate_name,
 };
 
 use crate::{
     error::{map_dynamo_error, AppError, Result},
-    models::{ConnectToUserRequest, CreateInvitationRequest},
+    models::{
+        ConnectToUserRequest, CreateInvitationRequest, ExpireInvitationsRequest, MyInvitationsQuery,
+    },
+    openapi::{ExpiredInvitations, InvitationPage, InvitationView},
 };
 
-// Alphabet for user-friendly invitation codes (uppercase letters only)
-const CODE_ALPHABET: [char; 26] = [
-    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
-    'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
-];
+// Page size for GET /invitations/me when no limit is given
+const DEFAULT_MY_INVITATIONS_PAGE_SIZE: usize = 50;
+const MAX_MY_INVITATIONS_PAGE_SIZE: usize = 100;
 
 // POST /invitations/new - Create a new invitation
+// Honors an Idempotency-Key header when the service has an idempotency store
+#[utoipa::path(
+    post,
+    path = "/invitations/new",
+    tag = "invitations",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Makes retries of this request return the invitation it created")),
+    request_body = CreateInvitationRequest,
+    responses(
+        (status = 200, description = "The new invitation", body = Invitation),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 409, description = "The person already has an open invitation to the box, or the Idempotency-Key was reused with a different body or is still in progress", body = ErrorBody)
+    )
+)]
 pub async fn create_invitation<S: InvitationStore + ?Sized>(
     State(store): State<Arc<S>>,
     Extension(user_id): Extension<String>,
-    Json(create_request): Json<CreateInvitationRequest>,
+    idempotency_store: Option<Extension<SharedIdempotencyStore>>,
+    headers: HeaderMap,
+    Json(mut create_request): Json<CreateInvitationRequest>,
 ) -> Result<Json<Invitation>> {
+    create_request.invited_name = validate_name("invitedName", &create_request.invited_name)?;
+    create_request.box_name = create_request
+        .box_name
+        .as_deref()
+        .map(|box_name| validate_name("boxName", box_name))
+        .transpose()?;
+    create_request.owner_name = create_request
+        .owner_name
+        .as_deref()
+        .map(|owner_name| validate_name("ownerName", owner_name))
+        .transpose()?;
+    create_request.creator_name = create_request
+        .creator_name
+        .as_deref()
+        .map(|creator_name| validate_name("creatorName", creator_name))
+        .transpose()?;
+
+    let idempotency = match (idempotency_store, idempotency_key(&headers)?) {
+        (Some(Extension(keys)), Some(key)) => {
+            Some((keys, scoped_key("create_invitation", &user_id, &key)))
+        }
+        _ => None,
+    };
+
+    if let Some((keys, key)) = &idempotency {
+        match claim(&**keys, key, &request_hash(&create_request)?).await? {
+            Claim::New => {}
+            Claim::Replay(invitation_id) => {
+                info!(
+                    "Replaying create_invitation for idempotency key: invitation_id={}",
+                    invitation_id
+                );
+                return Ok(Json(store.get_invitation(&invitation_id).await?));
+            }
+            Claim::Mismatch => {
+                return Err(AppError::Conflict(
+                    "Idempotency-Key was already used with a different request".into(),
+                ))
+            }
+            Claim::InProgress => {
+                return Err(AppError::Conflict(
+                    "A request with this Idempotency-Key is still in progress".into(),
+                ))
+            }
+        }
+    }
+
     // Generate a user-friendly code for the invitation (8 characters)
-    let invite_code = nanoid::nanoid!(8, &CODE_ALPHABET);
+    let invite_code = Invitation::new_invite_code();
 
     // Set expiration to 48 hours from now
     let created_at = Utc::now().to_rfc3339();
@@ -53,13 +123,43 @@ pub async fn create_invitation<S: InvitationStore + ?Sized>(
         linked_user_id: None,
         creator_id: user_id,
         is_lead_guardian: create_request.is_lead_guardian,
+        box_name: create_request.box_name,
+        owner_name: create_request.owner_name,
+        creator_name: create_request.creator_name,
     };
 
-    // Save to database
-    let saved_invitation = store
-        .create_invitation(invitation)
-        .await
-        .map_err(|e| map_dynamo_error("create_invitation", e))?;
+    // Save to database unless the person already has an open invitation to
+    // the box, freeing the idempotency key if either fails so the client can
+    // retry
+    let created = match ensure_not_already_invited(&*store, &invitation).await {
+        Ok(()) => store
+            .create_invitation(invitation)
+            .await
+            .map_err(AppError::from),
+        Err(e) => Err(e),
+    };
+    let saved_invitation = match created {
+        Ok(saved_invitation) => saved_invitation,
+        Err(e) => {
+            if let Some((keys, key)) = &idempotency {
+                if let Err(release_err) = keys.delete_record(key).await {
+                    error!("Failed to release idempotency key: {}", release_err);
+                }
+            }
+            return Err(e);
+        }
+    };
+
+    if let Some((keys, key)) = &idempotency {
+        // The invitation exists either way; a retry would only see the key
+        // in progress
+        if let Err(e) = keys.complete_record(key, &saved_invitation.id).await {
+            error!(
+                "Failed to record idempotency key for invitation {}: {}",
+                saved_invitation.id, e
+            );
+        }
+    }
 
     // Publish event to SNS
     if let Err(err) = publish_invitation_event(&saved_invitation, "invitation_created").await {
@@ -70,7 +170,49 @@ pub async fn create_invitation<S: InvitationStore + ?Sized>(
     Ok(Json(saved_invitation))
 }
 
+// Refuses a second invitation for someone who already has an unexpired,
+// unopened one to the same box; the creator should refresh that one instead.
+// Names are compared case-insensitively.
+async fn ensure_not_already_invited<S: InvitationStore + ?Sized>(
+    store: &S,
+    invitation: &Invitation,
+) -> Result<()> {
+    let now = Utc::now();
+    let invited_name = invitation.invited_name.to_lowercase();
+    let existing = store
+        .get_invitations_by_box_id(&invitation.box_id)
+        .await?
+        .into_iter()
+        .find(|existing| {
+            !existing.opened
+                && existing.linked_user_id.is_none()
+                && existing.invited_name.to_lowercase() == invited_name
+                && chrono::DateTime::parse_from_rfc3339(&existing.expires_at)
+                    .is_ok_and(|expires_at| expires_at > now)
+        });
+
+    match existing {
+        Some(existing) => Err(AppError::Conflict(format!(
+            "{} already has an open invitation to this box ({}); refresh it instead",
+            invitation.invited_name, existing.id
+        ))),
+        None => Ok(()),
+    }
+}
+
 // PUT /invitation/handle - Connect invitation to user
+#[utoipa::path(
+    put,
+    path = "/invitations/handle",
+    tag = "invitations",
+    request_body = ConnectToUserRequest,
+    responses(
+        (status = 200, description = "The caller is linked to the invitation", body = MessageResponse),
+        (status = 403, description = "The invitation was already used", body = ErrorBody),
+        (status = 404, description = "No invitation with this code", body = ErrorBody),
+        (status = 410, description = "The invitation has expired", body = ErrorBody)
+    )
+)]
 pub async fn handle_invitation<S: InvitationStore + ?Sized>(
     State(store): State<Arc<S>>,
     Extension(auth_user_id): Extension<String>,
@@ -95,9 +237,12 @@ pub async fn handle_invitation<S: InvitationStore + ?Sized>(
     // Save the updated invitation
     let updated_invitation = store.update_invitation(invitation.clone()).await?;
 
-    // Publish event to SNS
-    if let Err(err) = publish_invitation_event(&updated_invitation, "invitation_viewed").await {
-        error!("Failed to publish invitation event: {:?}", err);
+    // Publish events to SNS. Redeeming an invitation also accepts it, which
+    // makes the user a guardian of the box.
+    for event_type in ["invitation_viewed", "invitation_accepted"] {
+        if let Err(err) = publish_invitation_event(&updated_invitation, event_type).await {
+            error!("Failed to publish {} event: {:?}", event_type, err);
+        }
     }
 
     // Return response with box_id to help frontend
@@ -107,6 +252,7 @@ pub async fn handle_invitation<S: InvitationStore + ?Sized>(
             updated_invitation.box_id
         ),
         box_id: Some(updated_invitation.box_id),
+        creator_name: updated_invitation.creator_name,
     };
 
     Ok(Json(response))
@@ -205,27 +351,40 @@ fn build_event_message_attributes(
     Ok(attributes)
 }
 
-fn build_event_payload(invitation: &Invitation, event_type: &str) -> Result<serde_json::Value> {
-    Ok(json!({
-        "event_type": event_type,
-        "invitation_id": invitation.id,
-        "box_id": invitation.box_id,
-        "user_id": invitation.linked_user_id,
-        "invite_code": invitation.invite_code,
-        "invited_name": invitation.invited_name,
-        "is_lead_guardian": invitation.is_lead_guardian,
-        "timestamp": Utc::now().to_rfc3339()
-    }))
+fn build_event_payload(invitation: &Invitation, event_type: &str) -> Result<InvitationEvent> {
+    Ok(InvitationEvent {
+        event_type: event_type.to_string(),
+        schema_version: EVENT_SCHEMA_VERSION,
+        invitation_id: invitation.id.clone(),
+        box_id: invitation.box_id.clone(),
+        user_id: invitation.linked_user_id.clone(),
+        invite_code: invitation.invite_code.clone(),
+        invited_name: Some(invitation.invited_name.clone()),
+        is_lead_guardian: invitation.is_lead_guardian,
+        creator_id: Some(invitation.creator_id.clone()),
+        timestamp: Utc::now().to_rfc3339(),
+    })
 }
 
 // PATCH /invitations/:inviteId/refresh - Refresh the invitation
+#[utoipa::path(
+    patch,
+    path = "/invitations/{inviteId}/refresh",
+    tag = "invitations",
+    params(("inviteId" = String, Path, description = "Invitation ID")),
+    responses(
+        (status = 200, description = "The invitation, with a new code and expiry", body = Invitation),
+        (status = 403, description = "Not the caller's invitation, or already used", body = ErrorBody),
+        (status = 404, description = "Invitation not found", body = ErrorBody)
+    )
+)]
 pub async fn refresh_invitation<S: InvitationStore + ?Sized>(
     State(store): State<Arc<S>>,
     Extension(user_id): Extension<String>,
     Path(invite_id): Path<String>,
 ) -> Result<Json<Invitation>> {
     // Directly fetch the invitation by ID
-    let mut invitation = store.get_invitation(&invite_id).await?;
+    let mut invitation = store.get_invitation_consistent(&invite_id).await?;
 
     // Verify that the current user is the creator of this invitation
     if invitation.creator_id != user_id {
@@ -244,7 +403,7 @@ pub async fn refresh_invitation<S: InvitationStore + ?Sized>(
     }
 
     // Generate a new user-friendly invite code (8 characters)
-    invitation.invite_code = nanoid::nanoid!(8, &CODE_ALPHABET);
+    invitation.invite_code = Invitation::new_invite_code();
 
     // Set new expiration date (48 hours from now)
     invitation.expires_at = (Utc::now() + Duration::hours(48)).to_rfc3339();
@@ -256,70 +415,136 @@ pub async fn refresh_invitation<S: InvitationStore + ?Sized>(
     Ok(Json(updated_invitation))
 }
 
-// GET /invitations/me - Get all invitations created by the current user
+// POST /invitations/expire - Admin action expiring every open invitation for a box
+#[utoipa::path(
+    post,
+    path = "/invitations/expire",
+    tag = "invitations",
+    request_body = ExpireInvitationsRequest,
+    responses(
+        (status = 200, description = "Open invitations for the box expired", body = ExpiredInvitations),
+        (status = 403, description = "The caller isn't an admin", body = ErrorBody)
+    )
+)]
+pub async fn expire_box_invitations<S: InvitationStore + ?Sized>(
+    State(store): State<Arc<S>>,
+    RequireAdmin(auth): RequireAdmin,
+    Json(request): Json<ExpireInvitationsRequest>,
+) -> Result<Json<serde_json::Value>> {
+    // Only unexpired invitations are returned, so repeating the call is a no-op.
+    // Each is expired only while still pending, so one redeemed in the
+    // meantime is skipped rather than overwritten with the copy read here.
+    let now = Utc::now().to_rfc3339();
+    let invitations = store.get_invitations_by_box_id(&request.box_id).await?;
+    let mut expired = 0;
+    let mut skipped = 0;
+    for invitation in invitations
+        .iter()
+        .filter(|invitation| !invitation.opened && invitation.linked_user_id.is_none())
+    {
+        if store
+            .expire_pending_invitation(&invitation.id, &now)
+            .await?
+        {
+            expired += 1;
+        } else {
+            skipped += 1;
+        }
+    }
+    info!(
+        "Admin {} expired {} invitations for box {}, skipped {} redeemed meanwhile",
+        auth.user_id, expired, request.box_id, skipped
+    );
+
+    Ok(Json(json!({
+        "boxId": request.box_id,
+        "expiredCount": expired,
+    })))
+}
+
+// GET /invitations/me?limit=&cursor= - Get a page of invitations created by the current user
+#[utoipa::path(
+    get,
+    path = "/invitations/me",
+    tag = "invitations",
+    params(MyInvitationsQuery),
+    responses(
+        (status = 200, description = "Invitations the caller created", body = InvitationPage,
+            headers(("Link" = String, description = "`rel=\"next\"` URL of the next page, when there is one"))),
+        (status = 400, description = "Invalid cursor", body = ErrorBody)
+    )
+)]
 pub async fn get_my_invitations<S: InvitationStore + ?Sized>(
     State(store): State<Arc<S>>,
     Extension(user_id): Extension<String>,
-) -> Result<Json<Vec<Invitation>>> {
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<MyInvitationsQuery>,
+) -> Result<(HeaderMap, Json<serde_json::Value>)> {
     info!("get_my_invitations called with user_id: {}", user_id);
 
-    // Fetch all invitations created by this user
-    let invitations = store
-        .get_invitations_by_creator_id(&user_id)
-        .await
-        .map_err(|e| map_dynamo_error("get_invitations_by_creator_id", e))?;
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_MY_INVITATIONS_PAGE_SIZE)
+        .clamp(1, MAX_MY_INVITATIONS_PAGE_SIZE);
+    let page = store
+        .get_invitations_by_creator_id_paginated(&user_id, limit, query.cursor.as_deref())
+        .await?;
 
     info!(
         "get_my_invitations returning {} invitations for user_id: {}",
-        invitations.len(),
+        page.items.len(),
         user_id
     );
 
-    Ok(Json(invitations))
+    // Counting every invitation would mean reading them all, so only the
+    // next link is sent
+    Ok((
+        pagination_headers(&uri, page.next_cursor.as_deref(), None),
+        Json(json!({
+            "invitations": page.items,
+            "nextCursor": page.next_cursor,
+        })),
+    ))
 }
 
 // GET /invitations/view/:code - View invitation details by code without consuming it
+#[utoipa::path(
+    get,
+    path = "/invitations/view/{code}",
+    tag = "invitations",
+    params(("code" = String, Path, description = "Invite code")),
+    security(()),
+    responses(
+        (status = 200, description = "The invitation", body = InvitationView),
+        (status = 404, description = "No invitation with this code", body = ErrorBody),
+        (status = 410, description = "The invitation has expired", body = ErrorBody)
+    )
+)]
 pub async fn view_invitation_by_code<S: InvitationStore + ?Sized>(
     State(store): State<Arc<S>>,
     Path(code): Path<String>,
 ) -> Result<Json<serde_json::Value>> {
     info!("view_invitation_by_code called with code: {}", code);
 
-    // Fetch the invitation by code
+    // Fetch the invitation by code; the store refuses expired ones with 410
     let invitation = store.get_invitation_by_code(&code).await?;
 
-    // Check if invitation is expired
-    let expires_at = chrono::DateTime::parse_from_rfc3339(&invitation.expires_at).map_err(|e| {
-        AppError::InternalServerError(format!("Failed to parse expiration date: {}", e))
-    })?;
-
-    if Utc::now() > expires_at {
-        return Err(AppError::NotFound(format!(
-            "Invitation with code {} has expired",
-            code
-        )));
-    }
-
-    // We need to get box information from the box service
-    // For now, return invitation with a note that box data should be fetched separately
-    // In a full implementation, we'd either:
-    // 1. Make a cross-service call to box-service
-    // 2. Store denormalized box data in the invitation
-    // 3. Have the client make a separate call to get box details
-
-    // Create an enhanced response with invitation data
+    // The box summary is whatever the creator stamped on the invitation;
+    // invitations created before it was recorded have none, and the client
+    // falls back to fetching the box by id
     let response = json!({
         "id": invitation.id,
         "inviteCode": invitation.invite_code,
         "invitedName": invitation.invited_name,
         "boxId": invitation.box_id,
+        "boxName": invitation.box_name,
+        "ownerName": invitation.owner_name,
         "createdAt": invitation.created_at,
         "expiresAt": invitation.expires_at,
         "opened": invitation.opened,
         "linkedUserId": invitation.linked_user_id,
         "creatorId": invitation.creator_id,
-        // Note: Box owner name would need to be fetched from box service
-        // For now, frontend can use creatorId or fetch box details separately
+        "creatorName": invitation.creator_name,
         "note": "Box details should be fetched using boxId"
     });
 
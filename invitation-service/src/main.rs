@@ -1,17 +1,18 @@
 mod error;
 mod handlers;
 mod models;
+mod openapi;
 mod routes;
 #[cfg(test)]
 mod tests;
 
 use axum::{body::Body, extract::Request, response::Response, Router};
-use env_logger;
 use http_body_util::BodyExt;
 use lambda_http::{
     run, service_fn, Body as LambdaBody, Error, Request as LambdaRequest,
     Response as LambdaResponse,
 };
+use lockbox_shared::shutdown::{drain_timeout, serve_with_graceful_shutdown, shutdown_signal};
 use log::{debug, error, info, trace};
 use std::net::SocketAddr;
 use tokio::sync::OnceCell;
@@ -130,9 +131,12 @@ async fn response_to_lambda(response: Response) -> Result<LambdaResponse<LambdaB
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    // Initialize env_logger
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
-    info!("Logging initialized with env_logger");
+    lockbox_shared::logging::init();
+    info!("Logging initialized");
+
+    // Refuse to start without a usable cursor secret rather than failing on
+    // the first paginated request
+    lockbox_shared::store::pagination::check_cursor_secret()?;
 
     if let Ok(function_name) = std::env::var("AWS_LAMBDA_FUNCTION_NAME") {
         info!(
@@ -148,7 +152,7 @@ async fn main() -> Result<(), Error> {
 
         let app = routes::create_router().await;
         let listener = tokio::net::TcpListener::bind(&addr).await?;
-        axum::serve(listener, app.into_make_service()).await?;
+        serve_with_graceful_shutdown(listener, app, shutdown_signal(), drain_timeout()).await?;
     }
 
     info!("Service finished");
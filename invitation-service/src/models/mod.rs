@@ -1,20 +1,44 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 // Request DTOs
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct CreateInvitationRequest {
-    #[serde(rename = "invitedName")]
     pub invited_name: String,
-    #[serde(rename = "boxId")]
     pub box_id: String,
-    #[serde(rename = "isLeadGuardian", default)]
+    #[serde(default)]
     pub is_lead_guardian: bool,
+    /// Summary of the box for the invitee to see before accepting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub box_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_name: Option<String>,
+    /// How the creator wants to be named to the invitee
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creator_name: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct ConnectToUserRequest {
-    #[serde(rename = "inviteCode")]
     pub invite_code: String,
 }
 
+#[derive(Deserialize, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ExpireInvitationsRequest {
+    pub box_id: String,
+}
+
+// Query parameters for GET /invitations/me
+#[derive(Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MyInvitationsQuery {
+    /// Page size, 50 if not given
+    pub limit: Option<usize>,
+    /// `nextCursor` from the previous page
+    pub cursor: Option<String>,
+}
+
 // Use shared MessageResponse from lockbox_shared
@@ -0,0 +1,137 @@
+//! OpenAPI description of the invitation service, served at
+//! `/invitations/openapi.json`.
+//!
+//! Some handlers build their response bodies with `json!`; the envelope types
+//! below describe those shapes for the spec and aren't used at runtime.
+
+use axum::response::Html;
+use lockbox_shared::error::ErrorBody;
+use lockbox_shared::extract::Json;
+use lockbox_shared::models::{Invitation, MessageResponse};
+use serde::Serialize;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+use crate::handlers::invitation_handlers;
+use crate::models::{ConnectToUserRequest, CreateInvitationRequest, ExpireInvitationsRequest};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Lockbox Invitation Service",
+        description = "Invitations that let owners bring guardians onto their boxes"
+    ),
+    paths(
+        invitation_handlers::create_invitation,
+        invitation_handlers::handle_invitation,
+        invitation_handlers::refresh_invitation,
+        invitation_handlers::get_my_invitations,
+        invitation_handlers::expire_box_invitations,
+        invitation_handlers::view_invitation_by_code,
+    ),
+    components(schemas(
+        Invitation,
+        CreateInvitationRequest,
+        ConnectToUserRequest,
+        ExpireInvitationsRequest,
+        MessageResponse,
+        ErrorBody,
+        InvitationPage,
+        ExpiredInvitations,
+        InvitationView,
+    )),
+    modifiers(&BearerAuth),
+    security(("bearer" = [])),
+    tags((name = "invitations", description = "Guardian invitations"))
+)]
+pub struct ApiDoc;
+
+// Adds the Cognito bearer token the authenticated endpoints expect
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+pub use envelopes::*;
+
+// Never constructed: these only describe bodies the handlers build with json!
+#[allow(dead_code)]
+mod envelopes {
+    use super::*;
+
+    /// A page of the caller's invitations; `nextCursor` is null on the last page
+    #[derive(Serialize, ToSchema)]
+    #[serde(rename_all = "camelCase")]
+    pub struct InvitationPage {
+        pub invitations: Vec<Invitation>,
+        pub next_cursor: Option<String>,
+    }
+
+    #[derive(Serialize, ToSchema)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ExpiredInvitations {
+        pub box_id: String,
+        pub expired_count: usize,
+    }
+
+    /// An invitation as shown to someone holding its code
+    #[derive(Serialize, ToSchema)]
+    #[serde(rename_all = "camelCase")]
+    pub struct InvitationView {
+        pub id: String,
+        pub invite_code: String,
+        pub invited_name: String,
+        pub box_id: String,
+        /// Name of the box, if the invitation's creator recorded one
+        pub box_name: Option<String>,
+        /// Name the box's owner goes by, if recorded
+        pub owner_name: Option<String>,
+        pub created_at: String,
+        pub expires_at: String,
+        pub opened: bool,
+        pub linked_user_id: Option<String>,
+        pub creator_id: String,
+        /// Name the invitation's creator goes by, if they gave one
+        pub creator_name: Option<String>,
+        pub note: String,
+    }
+}
+
+// GET /invitations/openapi.json
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+// GET /invitations/docs - Swagger UI over the spec, only routed when
+// ENABLE_DEBUG_ROUTES=true
+pub async fn swagger_ui() -> Html<&'static str> {
+    Html(SWAGGER_UI_PAGE)
+}
+
+const SWAGGER_UI_PAGE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+  <title>Lockbox Invitation Service</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    SwaggerUIBundle({ url: "openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
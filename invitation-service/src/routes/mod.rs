@@ -2,19 +2,32 @@ use axum::{
     extract::Request,
     middleware,
     routing::{get, patch, post, put},
-    Router,
+    Extension, Router,
 };
+use lockbox_shared::paging::X_TOTAL_COUNT;
 use log::{debug, info, warn};
 use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 
 use crate::handlers::invitation_handlers::{
-    create_invitation, get_my_invitations, handle_invitation, refresh_invitation,
-    view_invitation_by_code,
+    create_invitation, expire_box_invitations, get_my_invitations, handle_invitation,
+    refresh_invitation, view_invitation_by_code,
 };
+use crate::openapi::{openapi_json, swagger_ui};
 // Import shared auth middleware
 use lockbox_shared::auth::auth_middleware;
-use lockbox_shared::store::{dynamo::DynamoInvitationStore, InvitationStore};
+use lockbox_shared::maintenance::{read_only_middleware, ReadOnlyMode};
+use lockbox_shared::store::{
+    dynamo::{DynamoIdempotencyStore, DynamoInvitationStore},
+    InvitationStore, SharedIdempotencyStore,
+};
+
+// Extra routes for local development, enabled with ENABLE_DEBUG_ROUTES=true
+fn debug_routes_enabled() -> bool {
+    std::env::var("ENABLE_DEBUG_ROUTES")
+        .map(|v| v.to_lowercase() == "true")
+        .unwrap_or(false)
+}
 
 /// Creates a router with the default store
 pub async fn create_router() -> Router {
@@ -32,7 +45,14 @@ pub async fn create_router() -> Router {
     let prefix = if remove_base_path { "" } else { "/Prod" };
     info!("Using API route prefix: {}", prefix);
 
-    create_router_with_store(dynamo_store, prefix)
+    let router =
+        create_router_with_store(dynamo_store, prefix).layer(Extension(ReadOnlyMode::from_env()));
+
+    // Honor Idempotency-Key headers when a key table is configured
+    match DynamoIdempotencyStore::from_env().await {
+        Some(keys) => router.layer(Extension(Arc::new(keys) as SharedIdempotencyStore)),
+        None => router,
+    }
 }
 
 /// Creates a router with a given store implementation
@@ -46,7 +66,9 @@ where
     let cors = CorsLayer::new()
         .allow_origin(Any)
         .allow_methods(Any)
-        .allow_headers(Any);
+        .allow_headers(Any)
+        // So browser clients can read the pagination headers
+        .expose_headers([axum::http::header::LINK, X_TOTAL_COUNT]);
 
     debug!("CORS configured for all origins, methods and headers");
 
@@ -73,11 +95,18 @@ where
         .route("/invitations/handle", put(handle_invitation))
         .route("/invitations/:inviteId/refresh", patch(refresh_invitation))
         .route("/invitations/me", get(get_my_invitations))
-        .layer(middleware::from_fn(auth_middleware));
+        .route("/invitations/expire", post(expire_box_invitations))
+        .layer(middleware::from_fn(auth_middleware))
+        // Only reads get through while READ_ONLY is set
+        .layer(middleware::from_fn(read_only_middleware));
 
     // Public routes (no auth required)
-    let public_routes =
-        Router::new().route("/invitations/view/:code", get(view_invitation_by_code));
+    let mut public_routes = Router::new()
+        .route("/invitations/view/:code", get(view_invitation_by_code))
+        .route("/invitations/openapi.json", get(openapi_json));
+    if debug_routes_enabled() {
+        public_routes = public_routes.route("/invitations/docs", get(swagger_ui));
+    }
 
     // Merge routes with state
     let api_routes = Router::new()
@@ -1,4 +1,8 @@
-use axum::{http::StatusCode, Router};
+use axum::{
+    body::Body,
+    http::{HeaderValue, Request, StatusCode},
+    Extension, Router,
+};
 use log::{debug, error, info, trace};
 use serde_json::json;
 use std::sync::Arc;
@@ -6,14 +10,17 @@ use tower::ServiceExt;
 
 use crate::routes::create_router_with_store;
 use chrono::{DateTime, Duration, Utc};
-use lockbox_shared::auth::create_test_request;
+use lockbox_shared::auth::{create_admin_test_request, create_test_request};
+use lockbox_shared::error::StoreError;
+use lockbox_shared::maintenance::{ReadOnlyMode, MAINTENANCE_CODE};
 use lockbox_shared::models::Invitation;
 use lockbox_shared::store::dynamo::DynamoInvitationStore;
-use lockbox_shared::store::InvitationStore;
+use lockbox_shared::store::{InvitationStore, SharedIdempotencyStore};
 use lockbox_shared::test_utils::dynamo_test_utils::{
     clear_dynamo_table, create_dynamo_client, create_invitation_table, use_dynamodb,
 };
 use lockbox_shared::test_utils::http_test_utils::response_to_json;
+use lockbox_shared::test_utils::mock_idempotency_store::MockIdempotencyStore;
 use lockbox_shared::test_utils::mock_invitation_store::MockInvitationStore;
 use lockbox_shared::test_utils::test_logging::init_test_logging;
 use std::env;
@@ -22,6 +29,9 @@ use uuid::Uuid;
 // Constants for DynamoDB tests
 const TEST_TABLE_NAME: &str = "invitation-test-table";
 
+// Reads straight after a write use the store's consistent getters. Code and
+// creator lookups go through indexes, which local DynamoDB updates along with
+// the write, so the tests don't wait for them either.
 enum TestStore {
     Mock(Arc<MockInvitationStore>),
     DynamoDB(Arc<DynamoInvitationStore>),
@@ -153,20 +163,17 @@ async fn test_create_invitation() {
     assert_eq!(json_resp["opened"], false);
     assert!(json_resp["linkedUserId"].is_null());
 
-    // Add a small delay to allow for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
-    }
-
     // Verify stored invitation - First try to get the ID from the response
     let invitation_id = json_resp["id"].as_str().unwrap();
 
     let invitation = match &store {
-        TestStore::Mock(mock) => mock.get_invitation(invitation_id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_invitation_consistent(invitation_id).await.unwrap(),
         TestStore::DynamoDB(dynamo) => {
             info!("About to get invitation by ID: {}", invitation_id);
-            let inv = dynamo.get_invitation(invitation_id).await.unwrap();
+            let inv = dynamo
+                .get_invitation_consistent(invitation_id)
+                .await
+                .unwrap();
             info!(
                 "Found invitation with id={}, creator_id={}",
                 inv.id, inv.creator_id
@@ -193,6 +200,204 @@ async fn test_create_invitation() {
     assert!(invitation.linked_user_id.is_none());
 }
 
+#[tokio::test]
+async fn test_create_invitation_idempotency_key() {
+    let (app, _) = create_test_app().await;
+    let app = app.layer(Extension(
+        Arc::new(MockIdempotencyStore::new()) as SharedIdempotencyStore
+    ));
+
+    let create = |key: &str, invited_name: &str| {
+        let mut request = create_test_request(
+            "POST",
+            "/invitations/new",
+            "idempotent-creator",
+            Some(json!({ "invitedName": invited_name, "boxId": "box-idempotent" })),
+        );
+        request
+            .headers_mut()
+            .insert("idempotency-key", HeaderValue::from_str(key).unwrap());
+        request
+    };
+
+    // First call creates the invitation
+    let response = app.clone().oneshot(create("key-1", "Alice")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let first = response_to_json(response).await;
+
+    // An identical retry returns the same invitation
+    let response = app.clone().oneshot(create("key-1", "Alice")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let retry = response_to_json(response).await;
+    assert_eq!(retry["id"], first["id"]);
+    assert_eq!(retry["inviteCode"], first["inviteCode"]);
+
+    // Reusing the key for someone else is a conflict
+    let response = app.clone().oneshot(create("key-1", "Bob")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    // Only one invitation was created
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            "/invitations/me",
+            "idempotent-creator",
+            None,
+        ))
+        .await
+        .unwrap();
+    let mine = response_to_json(response).await;
+    assert_eq!(mine["invitations"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_create_invitation_invalid_body() {
+    let (app, _) = create_test_app().await;
+
+    // Malformed JSON
+    let (auth_key, auth_value) = lockbox_shared::auth::create_auth_header("user_1");
+    let request = axum::http::Request::builder()
+        .method("POST")
+        .uri("/invitations/new")
+        .header(auth_key, auth_value)
+        .header("content-type", "application/json")
+        .body(axum::body::Body::from("{\"invitedName\": "))
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = response_to_json(response).await;
+    assert_eq!(body["code"], "INVALID_JSON");
+
+    // Well-formed JSON with the wrong shape
+    let response = app
+        .oneshot(create_test_request(
+            "POST",
+            "/invitations/new",
+            "user_1",
+            Some(json!({ "invitedName": "Guardian" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = response_to_json(response).await;
+    assert_eq!(body["code"], "INVALID_BODY");
+    assert!(body["error"].as_str().unwrap().contains("boxId"));
+}
+
+#[tokio::test]
+async fn test_create_invitation_validates_invited_name() {
+    let (app, _) = create_test_app().await;
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/invitations/new",
+            "user_1",
+            Some(json!({ "invitedName": "  Bob\u{0} Jones\r\n", "boxId": "box-123" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response_to_json(response).await;
+    assert_eq!(body["invitedName"], "Bob Jones");
+
+    for (invited_name, message) in [
+        ("   ".to_string(), "invitedName must not be empty"),
+        ("\u{1b}\t".to_string(), "invitedName must not be empty"),
+        (
+            "b".repeat(101),
+            "invitedName must be at most 100 characters",
+        ),
+    ] {
+        let response = app
+            .clone()
+            .oneshot(create_test_request(
+                "POST",
+                "/invitations/new",
+                "user_1",
+                Some(json!({ "invitedName": invited_name, "boxId": "box-123" })),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = response_to_json(response).await;
+        assert_eq!(body["code"], "BAD_REQUEST");
+        assert_eq!(body["error"], message);
+    }
+}
+
+#[tokio::test]
+async fn test_create_invitation_rejects_duplicate() {
+    let (app, _) = create_test_app().await;
+    let box_id = format!("box-{}", Uuid::new_v4());
+
+    let create = |invited_name: &str| {
+        create_test_request(
+            "POST",
+            "/invitations/new",
+            "user_1",
+            Some(json!({ "invitedName": invited_name, "boxId": box_id })),
+        )
+    };
+
+    let response = app.clone().oneshot(create("Carol")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let first = response_to_json(response).await;
+
+    // The same person, however their name is capitalised
+    let response = app.clone().oneshot(create(" CAROL ")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let body = response_to_json(response).await;
+    assert_eq!(body["code"], "CONFLICT");
+    assert!(body["error"]
+        .as_str()
+        .unwrap()
+        .contains(first["id"].as_str().unwrap()));
+
+    // Someone else can still be invited to the box
+    let response = app.clone().oneshot(create("Dave")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_create_invitation_allowed_after_prior_expired() {
+    let (app, store) = create_test_app().await;
+    let box_id = format!("box-{}", Uuid::new_v4());
+
+    let now = Utc::now();
+    let expired = Invitation {
+        id: Uuid::new_v4().to_string(),
+        invite_code: "EXPIREDA".to_string(),
+        invited_name: "Carol".to_string(),
+        box_id: box_id.clone(),
+        created_at: (now - Duration::hours(50)).to_rfc3339(),
+        expires_at: (now - Duration::hours(2)).to_rfc3339(),
+        opened: false,
+        linked_user_id: None,
+        creator_id: "user_1".to_string(),
+        is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
+    };
+    match &store {
+        TestStore::Mock(mock) => mock.create_invitation(expired).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.create_invitation(expired).await.unwrap(),
+    };
+
+    let response = app
+        .oneshot(create_test_request(
+            "POST",
+            "/invitations/new",
+            "user_1",
+            Some(json!({ "invitedName": "Carol", "boxId": box_id })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
 #[tokio::test]
 async fn test_handle_invitation() {
     let (app, store) = create_test_app().await;
@@ -212,6 +417,9 @@ async fn test_handle_invitation() {
         linked_user_id: None,
         creator_id: "creator-id".to_string(),
         is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
     };
 
     debug!("Creating test invitation with code: {}", invite_code);
@@ -285,6 +493,9 @@ async fn test_handle_invitation_expired_code() {
         linked_user_id: None,
         creator_id: "creator-id".to_string(),
         is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
     };
 
     debug!(
@@ -337,6 +548,9 @@ async fn test_refresh_invitation() {
         linked_user_id: None,
         creator_id: "test-user-id".to_string(),
         is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
     };
 
     debug!(
@@ -348,12 +562,6 @@ async fn test_refresh_invitation() {
         TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation.clone()).await.unwrap(),
     };
 
-    // Add a delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(5000)).await;
-    }
-
     let path = format!("/invitations/{}/refresh", id);
     let response = app
         .clone()
@@ -386,15 +594,9 @@ async fn test_refresh_invitation() {
     assert_eq!(json_resp["opened"], false);
     assert!(json_resp["linkedUserId"].is_null());
 
-    // Add a delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     let refreshed = match &store {
-        TestStore::Mock(mock) => mock.get_invitation(&id).await.unwrap(),
-        TestStore::DynamoDB(dynamo) => dynamo.get_invitation(&id).await.unwrap(),
+        TestStore::Mock(mock) => mock.get_invitation_consistent(&id).await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_invitation_consistent(&id).await.unwrap(),
     };
 
     assert_eq!(refreshed.invite_code, new_code.to_string());
@@ -419,6 +621,9 @@ async fn test_refresh_invitation_invalid_id() {
         linked_user_id: None,
         creator_id: "owner-id".to_string(),
         is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
     };
 
     debug!("Creating test invitation with different owner id: {}", id);
@@ -454,6 +659,9 @@ async fn test_handle_invitation_invalid_code() {
         linked_user_id: None,
         creator_id: "creator-id".to_string(),
         is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
     };
 
     debug!("Creating test invitation with code VALID123");
@@ -514,6 +722,9 @@ async fn test_get_my_invitations() {
             linked_user_id: None,
             creator_id: creator.to_string(),
             is_lead_guardian: false,
+            box_name: None,
+            owner_name: None,
+            creator_name: None,
         };
 
         trace!(
@@ -532,12 +743,6 @@ async fn test_get_my_invitations() {
         ids.push((id, creator.to_string()));
     }
 
-    // Add a delay to allow for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(2000)).await;
-    }
-
     let response = app
         .clone()
         .oneshot(create_test_request(
@@ -551,7 +756,7 @@ async fn test_get_my_invitations() {
 
     assert_eq!(response.status(), StatusCode::OK);
     let json_resp = response_to_json(response).await;
-    let arr = json_resp.as_array().unwrap();
+    let arr = json_resp["invitations"].as_array().unwrap();
 
     // Both mock and DynamoDB should work correctly now that GSI is fixed
     // We should get only the invitations where test-user-id is the creator
@@ -580,7 +785,110 @@ async fn test_get_my_invitations_empty() {
 
     assert_eq!(response.status(), StatusCode::OK);
     let json_resp = response_to_json(response).await;
-    assert!(json_resp.as_array().unwrap().is_empty());
+    assert!(json_resp["invitations"].as_array().unwrap().is_empty());
+    assert!(json_resp["nextCursor"].is_null());
+}
+
+#[tokio::test]
+async fn test_get_my_invitations_paginated() {
+    let (app, store) = create_test_app().await;
+
+    // Seed seven invitations so a page size of three gives pages of 3, 3 and 1
+    let mut seeded = Vec::new();
+    for i in 0..7 {
+        let now = Utc::now();
+        let invitation = Invitation {
+            id: Uuid::new_v4().to_string(),
+            invite_code: format!("PAGE{:04}", i),
+            invited_name: format!("Paged User {}", i),
+            box_id: format!("box-page-{}", i),
+            created_at: now.to_rfc3339(),
+            expires_at: (now + Duration::hours(48)).to_rfc3339(),
+            opened: false,
+            linked_user_id: None,
+            creator_id: "paging-user".to_string(),
+            is_lead_guardian: false,
+            box_name: None,
+            owner_name: None,
+            creator_name: None,
+        };
+        match &store {
+            TestStore::Mock(mock) => mock.create_invitation(invitation.clone()).await.unwrap(),
+            TestStore::DynamoDB(dynamo) => {
+                dynamo.create_invitation(invitation.clone()).await.unwrap()
+            }
+        };
+        seeded.push(invitation.id);
+    }
+
+    let mut returned = Vec::new();
+    let mut page_sizes = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let uri = match &cursor {
+            Some(cursor) => format!("/invitations/me?limit=3&cursor={}", cursor),
+            None => "/invitations/me?limit=3".to_string(),
+        };
+        let response = app
+            .clone()
+            .oneshot(create_test_request("GET", &uri, "paging-user", None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let link = response
+            .headers()
+            .get(axum::http::header::LINK)
+            .map(|value| value.to_str().unwrap().to_string());
+
+        let json_resp = response_to_json(response).await;
+        let page = json_resp["invitations"].as_array().unwrap();
+        page_sizes.push(page.len());
+        returned.extend(
+            page.iter()
+                .map(|inv| inv["id"].as_str().unwrap().to_string()),
+        );
+
+        // The Link header mirrors the body's cursor
+        match json_resp["nextCursor"].as_str() {
+            Some(next) => {
+                assert_eq!(
+                    link.as_deref(),
+                    Some(
+                        format!("</invitations/me?limit=3&cursor={}>; rel=\"next\"", next).as_str()
+                    )
+                );
+                cursor = Some(next.to_string());
+            }
+            None => {
+                assert!(link.is_none(), "last page has a Link header: {:?}", link);
+                break;
+            }
+        }
+        assert!(page_sizes.len() < 10, "pagination did not terminate");
+    }
+
+    // DynamoDB can hand back a cursor for an empty final page
+    page_sizes.retain(|&size| size > 0);
+    assert_eq!(page_sizes, vec![3, 3, 1]);
+
+    returned.sort();
+    seeded.sort();
+    assert_eq!(
+        returned, seeded,
+        "every invitation should appear exactly once"
+    );
+
+    // A cursor that wasn't issued by the service is rejected
+    let response = app
+        .oneshot(create_test_request(
+            "GET",
+            "/invitations/me?cursor=not-a-cursor",
+            "paging-user",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
 
 // New tests for view invitation endpoint
@@ -602,6 +910,9 @@ async fn test_view_invitation_by_code_success() {
         linked_user_id: None,
         creator_id: "creator-view-id".to_string(),
         is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
     };
 
     debug!(
@@ -613,12 +924,6 @@ async fn test_view_invitation_by_code_success() {
         TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation.clone()).await.unwrap(),
     };
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     let path = format!("/invitations/view/{}", invite_code);
     let response = app
         .clone()
@@ -635,12 +940,110 @@ async fn test_view_invitation_by_code_success() {
     assert_eq!(json_resp["invitedName"], "View Test User");
     assert_eq!(json_resp["boxId"], "box-view-123");
     assert_eq!(json_resp["creatorId"], "creator-view-id");
+    assert!(json_resp["boxName"].is_null());
+    assert!(json_resp["ownerName"].is_null());
+    assert!(json_resp["creatorName"].is_null());
     assert_eq!(json_resp["opened"], false);
     assert!(json_resp["linkedUserId"].is_null());
     assert!(!json_resp["createdAt"].as_str().unwrap().is_empty());
     assert!(!json_resp["expiresAt"].as_str().unwrap().is_empty());
 }
 
+#[tokio::test]
+async fn test_view_invitation_includes_box_summary() {
+    let (app, _) = create_test_app().await;
+    let box_id = format!("box-{}", Uuid::new_v4());
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/invitations/new",
+            "owner-1",
+            Some(json!({
+                "invitedName": "Erin",
+                "boxId": box_id,
+                "boxName": " Family Papers ",
+                "ownerName": "Alice"
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let created = response_to_json(response).await;
+    assert_eq!(created["boxName"], "Family Papers");
+    assert_eq!(created["ownerName"], "Alice");
+
+    // Viewing doesn't need the invitee to be signed in or consume the code
+    let path = format!(
+        "/invitations/view/{}",
+        created["inviteCode"].as_str().unwrap()
+    );
+    let response = app
+        .clone()
+        .oneshot(create_test_request("GET", &path, "invitee", None))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let view = response_to_json(response).await;
+    assert_eq!(view["boxId"], box_id.as_str());
+    assert_eq!(view["boxName"], "Family Papers");
+    assert_eq!(view["ownerName"], "Alice");
+    assert_eq!(view["opened"], false);
+}
+
+#[tokio::test]
+async fn test_creator_name_round_trips() {
+    let (app, _) = create_test_app().await;
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/invitations/new",
+            "owner-1",
+            Some(json!({
+                "invitedName": "Frank",
+                "boxId": format!("box-{}", Uuid::new_v4()),
+                "creatorName": "Alice"
+            })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let created = response_to_json(response).await;
+    assert_eq!(created["creatorName"], "Alice");
+    let invite_code = created["inviteCode"].as_str().unwrap().to_string();
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            &format!("/invitations/view/{}", invite_code),
+            "invitee",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let view = response_to_json(response).await;
+    assert_eq!(view["creatorName"], "Alice");
+    assert_eq!(view["creatorId"], "owner-1");
+
+    let response = app
+        .oneshot(create_test_request(
+            "PUT",
+            "/invitations/handle",
+            "invitee",
+            Some(json!({ "inviteCode": invite_code })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let handled = response_to_json(response).await;
+    assert_eq!(handled["creatorName"], "Alice");
+}
+
 #[tokio::test]
 async fn test_view_invitation_by_code_not_found() {
     let (app, _store) = create_test_app().await;
@@ -673,6 +1076,9 @@ async fn test_view_invitation_by_code_expired() {
         linked_user_id: None,
         creator_id: "creator-expired-id".to_string(),
         is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
     };
 
     debug!(
@@ -684,12 +1090,6 @@ async fn test_view_invitation_by_code_expired() {
         TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation.clone()).await.unwrap(),
     };
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     let path = format!("/invitations/view/{}", invite_code);
     let response = app
         .clone()
@@ -719,6 +1119,9 @@ async fn test_view_invitation_does_not_consume_code() {
         linked_user_id: None,
         creator_id: "creator-noconsum-id".to_string(),
         is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
     };
 
     debug!(
@@ -730,12 +1133,6 @@ async fn test_view_invitation_does_not_consume_code() {
         TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation.clone()).await.unwrap(),
     };
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // View the invitation
     let path = format!("/invitations/view/{}", invite_code);
     let response = app
@@ -775,6 +1172,9 @@ async fn test_concurrent_code_redemption_second_fails() {
         linked_user_id: None,
         creator_id: "creator-concurrent-id".to_string(),
         is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
     };
 
     debug!(
@@ -786,12 +1186,6 @@ async fn test_concurrent_code_redemption_second_fails() {
         TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation.clone()).await.unwrap(),
     };
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // First user redeems the code
     let handle_payload_1 = json!({
         "inviteCode": invite_code
@@ -811,12 +1205,6 @@ async fn test_concurrent_code_redemption_second_fails() {
     let json_resp1 = response_to_json(response1).await;
     assert_eq!(json_resp1["boxId"], "box-concurrent-123");
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Second user tries to redeem the same code
     let handle_payload_2 = json!({
         "inviteCode": invite_code
@@ -863,6 +1251,9 @@ async fn test_concurrent_code_redemption_truly_concurrent() {
         linked_user_id: None,
         creator_id: "creator-concurrent-id".to_string(),
         is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
     };
 
     debug!(
@@ -874,12 +1265,6 @@ async fn test_concurrent_code_redemption_truly_concurrent() {
         TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation.clone()).await.unwrap(),
     };
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Simulate truly concurrent requests using tokio::spawn
     let handle_payload_1 = json!({
         "inviteCode": invite_code.clone()
@@ -966,6 +1351,9 @@ async fn test_invitation_expires_at_exact_48_hour_mark() {
         linked_user_id: None,
         creator_id: "creator-48h".to_string(),
         is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
     };
 
     debug!("Creating invitation that expires exactly now");
@@ -974,12 +1362,6 @@ async fn test_invitation_expires_at_exact_48_hour_mark() {
         TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation.clone()).await.unwrap(),
     };
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Try to handle invitation (should fail as expired)
     let handle_payload = json!({
         "inviteCode": invite_code
@@ -1021,6 +1403,9 @@ async fn test_invitation_just_before_expiry() {
         linked_user_id: None,
         creator_id: "creator-before".to_string(),
         is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
     };
 
     debug!("Creating invitation that expires in 1 minute");
@@ -1029,12 +1414,6 @@ async fn test_invitation_just_before_expiry() {
         TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation.clone()).await.unwrap(),
     };
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Try to handle invitation (should succeed)
     let handle_payload = json!({
         "inviteCode": invite_code
@@ -1076,6 +1455,9 @@ async fn test_invitation_timezone_handling() {
         linked_user_id: None,
         creator_id: "creator-tz".to_string(),
         is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
     };
 
     debug!("Creating invitation with UTC timezone");
@@ -1084,12 +1466,6 @@ async fn test_invitation_timezone_handling() {
         TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation.clone()).await.unwrap(),
     };
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // Verify invitation can be retrieved and expiry is correctly parsed
     let retrieved_inv = match &store {
         TestStore::Mock(mock) => mock.get_invitation_by_code(&invite_code).await.unwrap(),
@@ -1123,6 +1499,9 @@ async fn test_refresh_resets_expiry_correctly() {
         linked_user_id: None,
         creator_id: "creator-reset".to_string(),
         is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
     };
 
     debug!("Creating invitation with old expiry time");
@@ -1131,12 +1510,6 @@ async fn test_refresh_resets_expiry_correctly() {
         TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation.clone()).await.unwrap(),
     };
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(5000)).await;
-    }
-
     let path = format!("/invitations/{}/refresh", id);
     let response = app
         .clone()
@@ -1182,6 +1555,9 @@ async fn test_expiry_persists_across_view_operations() {
         linked_user_id: None,
         creator_id: "creator-persist".to_string(),
         is_lead_guardian: false,
+        box_name: None,
+        owner_name: None,
+        creator_name: None,
     };
 
     debug!("Creating invitation to test expiry persistence");
@@ -1190,12 +1566,6 @@ async fn test_expiry_persists_across_view_operations() {
         TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation.clone()).await.unwrap(),
     };
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await;
-    }
-
     // View invitation (should not modify expiry)
     let path = format!("/invitations/view/{}", invite_code);
     let response = app
@@ -1426,6 +1796,9 @@ async fn test_code_lookup_performance_active_codes() {
             linked_user_id: None,
             creator_id: format!("creator-{}", i % 5), // 5 different creators
             is_lead_guardian: false,
+            box_name: None,
+            owner_name: None,
+            creator_name: None,
         };
 
         match &store {
@@ -1434,12 +1807,6 @@ async fn test_code_lookup_performance_active_codes() {
         };
     }
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(3000)).await;
-    }
-
     // Measure lookup time for each code
     let mut total_duration = tokio::time::Duration::from_millis(0);
     for code in &codes {
@@ -1507,6 +1874,9 @@ async fn test_code_lookup_performance_mixed_dataset() {
             linked_user_id: None,
             creator_id: "creator-mixed".to_string(),
             is_lead_guardian: false,
+            box_name: None,
+            owner_name: None,
+            creator_name: None,
         };
 
         match &store {
@@ -1530,6 +1900,9 @@ async fn test_code_lookup_performance_mixed_dataset() {
             linked_user_id: None,
             creator_id: "creator-mixed".to_string(),
             is_lead_guardian: false,
+            box_name: None,
+            owner_name: None,
+            creator_name: None,
         };
 
         match &store {
@@ -1538,12 +1911,6 @@ async fn test_code_lookup_performance_mixed_dataset() {
         };
     }
 
-    // Add delay for DynamoDB consistency
-    if matches!(store, TestStore::DynamoDB(_)) {
-        debug!("Adding delay for DynamoDB consistency");
-        tokio::time::sleep(tokio::time::Duration::from_millis(3000)).await;
-    }
-
     // Test lookup performance on active codes
     let start = tokio::time::Instant::now();
     let handle_payload = json!({
@@ -1623,10 +1990,6 @@ async fn test_gsi_query_performance() {
         assert_eq!(response.status(), StatusCode::OK);
     }
 
-    // Add delay for DynamoDB consistency
-    debug!("Adding delay for DynamoDB consistency");
-    tokio::time::sleep(tokio::time::Duration::from_millis(3000)).await;
-
     // Query all invitations for creator (uses GSI)
     let start = tokio::time::Instant::now();
     let response = app
@@ -1643,7 +2006,7 @@ async fn test_gsi_query_performance() {
 
     assert_eq!(response.status(), StatusCode::OK);
     let json_resp = response_to_json(response).await;
-    let invitations = json_resp.as_array().unwrap();
+    let invitations = json_resp["invitations"].as_array().unwrap();
 
     info!(
         "GSI query returned {} invitations in {:?}",
@@ -1661,3 +2024,219 @@ async fn test_gsi_query_performance() {
         query_duration
     );
 }
+
+#[tokio::test]
+async fn test_create_invitation_store_failure() {
+    init_test_logging();
+    env::set_var("TEST_SNS", "true");
+
+    // Store failures can only be scripted on the mock store
+    let store = Arc::new(MockInvitationStore::new());
+    let app = create_router_with_store(store.clone(), "");
+    let create = || {
+        create_test_request(
+            "POST",
+            "/invitations/new",
+            "test-user-id",
+            Some(json!({ "invitedName": "Test User", "boxId": "box-123" })),
+        )
+    };
+
+    store.set_next_error(StoreError::InternalError("throttled".into()));
+    let response = app.clone().oneshot(create()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+    store.set_next_error(StoreError::Throttled("slow down".into()));
+    let response = app.clone().oneshot(create()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    // Once the store recovers the same request goes through
+    let response = app.oneshot(create()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_expire_box_invitations() {
+    let (app, store) = create_test_app().await;
+
+    // Two open invitations for the affected box, one it already redeemed,
+    // and one for another box
+    let now = Utc::now();
+    let seeds = [
+        ("EXPBOX01", "box-incident", None),
+        ("EXPBOX02", "box-incident", None),
+        ("EXPBOX04", "box-incident", Some("redeemer")),
+        ("EXPBOX03", "box-unaffected", None),
+    ];
+    for (code, box_id, linked_user_id) in seeds {
+        let invitation = Invitation {
+            id: Uuid::new_v4().to_string(),
+            invite_code: code.to_string(),
+            invited_name: format!("Guardian {}", code),
+            box_id: box_id.to_string(),
+            created_at: now.to_rfc3339(),
+            expires_at: (now + Duration::hours(48)).to_rfc3339(),
+            opened: linked_user_id.is_some(),
+            linked_user_id: linked_user_id.map(str::to_string),
+            creator_id: "box-owner".to_string(),
+            is_lead_guardian: false,
+            box_name: None,
+            owner_name: None,
+            creator_name: None,
+        };
+        match &store {
+            TestStore::Mock(mock) => mock.create_invitation(invitation).await.unwrap(),
+            TestStore::DynamoDB(dynamo) => dynamo.create_invitation(invitation).await.unwrap(),
+        };
+    }
+
+    // Regular users can't expire invitations
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/invitations/expire",
+            "box-owner",
+            Some(json!({ "boxId": "box-incident" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .clone()
+        .oneshot(create_admin_test_request(
+            "POST",
+            "/invitations/expire",
+            "operator",
+            Some(json!({ "boxId": "box-incident" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json_resp = response_to_json(response).await;
+    assert_eq!(json_resp["boxId"], "box-incident");
+    assert_eq!(json_resp["expiredCount"], 2);
+
+    // Both codes for the box are now gone, whether redeemed or viewed
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "PUT",
+            "/invitations/handle",
+            "guardian-user",
+            Some(json!({ "inviteCode": "EXPBOX01" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::GONE);
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/invitations/view/EXPBOX02",
+            "guardian-user",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::GONE);
+
+    // The redeemed invitation keeps its expiry and its guardian
+    let redeemed = match &store {
+        TestStore::Mock(mock) => mock.get_invitation_by_code("EXPBOX04").await.unwrap(),
+        TestStore::DynamoDB(dynamo) => dynamo.get_invitation_by_code("EXPBOX04").await.unwrap(),
+    };
+    assert_eq!(redeemed.linked_user_id.as_deref(), Some("redeemer"));
+    assert_eq!(
+        redeemed.expires_at,
+        (now + Duration::hours(48)).to_rfc3339()
+    );
+
+    // Invitations for other boxes are untouched
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/invitations/view/EXPBOX03",
+            "guardian-user",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Running it again finds nothing left to expire
+    let response = app
+        .oneshot(create_admin_test_request(
+            "POST",
+            "/invitations/expire",
+            "operator",
+            Some(json!({ "boxId": "box-incident" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response_to_json(response).await["expiredCount"], 0);
+}
+
+#[tokio::test]
+async fn test_openapi_spec() {
+    let (app, _store) = create_test_app().await;
+
+    // No Authorization header: the spec is public
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("GET")
+                .uri("/invitations/openapi.json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let spec = response_to_json(response).await;
+
+    assert!(spec["paths"]["/invitations/new"]["post"].is_object());
+    assert!(spec["paths"]["/invitations/view/{code}"]["get"]["security"]
+        .as_array()
+        .is_some());
+    let invitation = &spec["components"]["schemas"]["Invitation"];
+    assert_eq!(invitation["type"], "object");
+    assert!(invitation["properties"]["inviteCode"].is_object());
+}
+
+#[tokio::test]
+async fn test_read_only_mode_blocks_writes() {
+    let (app, _store) = create_test_app().await;
+    let app = app.layer(Extension(ReadOnlyMode(true)));
+
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "POST",
+            "/invitations/new",
+            "maintenance-user",
+            Some(json!({ "invitedName": "Blocked", "boxId": "box-123" })),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body = response_to_json(response).await;
+    assert_eq!(body["code"], MAINTENANCE_CODE);
+
+    // Reads are unaffected
+    let response = app
+        .clone()
+        .oneshot(create_test_request(
+            "GET",
+            "/invitations/me",
+            "maintenance-user",
+            None,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
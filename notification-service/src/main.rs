@@ -1,31 +1,112 @@
-use aws_lambda_events::event::sns::SnsEvent;
-use env_logger;
+use aws_lambda_events::event::sns::{SnsEvent, SnsMessage};
 use lambda_runtime::{service_fn, Error, LambdaEvent};
-use lockbox_shared::push::send_shard_notification;
-use lockbox_shared::store::dynamo::DynamoPushTokenStore;
+use lockbox_shared::clock::{Clock, SystemClock};
+use lockbox_shared::models::events::{BoxLockedEvent, EventEnvelope, EVENT_SCHEMA_VERSION};
+use lockbox_shared::models::PushToken;
+use lockbox_shared::poison::{parse_or_dead_letter, record_poison_message};
+use lockbox_shared::push::{
+    group_by_badge, pending_action_badge, pending_action_badges, record_push_failures,
+    send_box_unlocked_notification, send_guardian_declined_notification, send_shard_notification,
+    send_unlock_cancelled_notification, send_unlock_denied_notification,
+    send_unlock_requested_notification, ExpoPushSender, ExpoPushTicket, NotificationOptions,
+    PushError, BOX_UNLOCKED, GUARDIAN_DECLINED, PROGRESS_UPDATED, SHARD_RECEIVED, UNLOCK_REQUESTED,
+    UNLOCK_REQUEST_CANCELLED, UNLOCK_REQUEST_DENIED,
+};
+use lockbox_shared::store::dynamo::{
+    DynamoBoxStore, DynamoNotificationThrottleStore, DynamoPoisonMessageStore,
+    DynamoPushFailureStore, DynamoPushTokenStore,
+};
 use lockbox_shared::store::PushTokenStore;
+use lockbox_shared::throttle::{send_owner_progress_notification, OwnerProgressThrottle};
 use log::{error, info, warn};
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
 
 mod errors;
 
-/// Event payload for box_locked events
+/// Recorded as the source of the messages this service dead-letters
+const SOURCE: &str = "notification-service";
+
+// Event payloads are snake_case, unlike the camelCase HTTP bodies; see
+// lockbox_shared::models::events
+
+/// Event payload for guardian_accepted events
 #[derive(Deserialize, Debug)]
-struct BoxLockedEvent {
-    event_type: String,
+struct GuardianAcceptedEvent {
     box_id: String,
     box_name: String,
-    owner_name: Option<String>,
-    guardian_ids: Vec<String>,
+    owner_id: String,
+    guardian_id: String,
+    #[serde(default)]
+    request_id: Option<String>,
+}
+
+/// Event payload for guardian_declined events
+#[derive(Deserialize, Debug)]
+struct GuardianDeclinedEvent {
+    box_id: String,
+    box_name: String,
+    owner_id: String,
+    guardian_id: String,
+    guardian_name: String,
+    timestamp: String,
+    #[serde(default)]
+    request_id: Option<String>,
+}
+
+/// Event payload for unlock_requested events
+#[derive(Deserialize, Debug)]
+struct UnlockRequestedEvent {
+    box_id: String,
+    box_name: String,
+    owner_id: String,
+    requesting_guardian_id: String,
+    #[serde(default)]
+    reason: Option<String>,
     timestamp: String,
+    #[serde(default)]
+    request_id: Option<String>,
+}
+
+/// Event payload for unlock_request_cancelled events
+#[derive(Deserialize, Debug)]
+struct UnlockRequestCancelledEvent {
+    box_id: String,
+    box_name: String,
+    unlock_request_id: String,
+    guardian_ids: Vec<String>,
+    #[serde(default)]
+    request_id: Option<String>,
+}
+
+/// Event payload for unlock_request_denied events
+#[derive(Deserialize, Debug)]
+struct UnlockRequestDeniedEvent {
+    box_id: String,
+    box_name: String,
+    owner_id: String,
+    #[serde(default)]
+    denied_by: Vec<String>,
+    #[serde(default)]
+    request_id: Option<String>,
+}
+
+/// Event payload for box_unlocked events
+#[derive(Deserialize, Debug)]
+struct BoxUnlockedEvent {
+    box_id: String,
+    box_name: String,
+    owner_id: String,
+    #[serde(default)]
+    approved_by: Vec<String>,
+    #[serde(default)]
+    request_id: Option<String>,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    // Initialize env_logger
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
-
-    info!("Logging initialized with env_logger");
+    lockbox_shared::logging::init();
+    info!("Logging initialized");
     info!("Starting Notification Service Lambda");
 
     // Create the PushToken Store
@@ -36,17 +117,63 @@ async fn main() -> Result<(), Error> {
     Ok(())
 }
 
-/// Wrapper to make the store cloneable for Lambda
+/// Wrapper to make the stores cloneable for Lambda
 #[derive(Clone)]
 struct PushTokenStoreWrapper {
     inner: std::sync::Arc<DynamoPushTokenStore>,
+    /// Boxes, read for each recipient's pending-action badge
+    boxes: std::sync::Arc<DynamoBoxStore>,
+    /// Dead-letter table for pushes that couldn't be delivered
+    failures: std::sync::Arc<DynamoPushFailureStore>,
+    /// Dead-letter table for event messages that couldn't be parsed
+    poison: std::sync::Arc<DynamoPoisonMessageStore>,
+    /// Coalesces bursts of owner progress pushes
+    progress: std::sync::Arc<OwnerProgressThrottle>,
 }
 
 impl PushTokenStoreWrapper {
     async fn new() -> Self {
+        let throttle_store = std::sync::Arc::new(DynamoNotificationThrottleStore::new().await);
         Self {
             inner: std::sync::Arc::new(DynamoPushTokenStore::new().await),
+            boxes: std::sync::Arc::new(DynamoBoxStore::new().await),
+            failures: std::sync::Arc::new(DynamoPushFailureStore::new().await),
+            poison: std::sync::Arc::new(DynamoPoisonMessageStore::new().await),
+            progress: std::sync::Arc::new(OwnerProgressThrottle::from_env(throttle_store)),
+        }
+    }
+
+    /// Parses an event message, dead-lettering it when it doesn't parse
+    async fn parse<T: DeserializeOwned>(&self, message: &SnsMessage) -> Option<T> {
+        parse_or_dead_letter(&*self.poison, SOURCE, &message.message_id, &message.message).await
+    }
+
+    /// Dead-letters the recipients a send didn't reach, then passes its
+    /// result on
+    async fn check_sent(
+        &self,
+        tokens: &[PushToken],
+        result: Result<Vec<ExpoPushTicket>, PushError>,
+        box_id: &str,
+        notification_type: &str,
+    ) -> Result<(), errors::NotificationError> {
+        let failed = record_push_failures(
+            &*self.failures,
+            tokens,
+            &result,
+            Some(box_id),
+            notification_type,
+        )
+        .await;
+        if failed > 0 {
+            warn!(
+                "Recorded {} undelivered {} pushes for box_id={}",
+                failed, notification_type, box_id
+            );
         }
+        result
+            .map(|_| ())
+            .map_err(|e| errors::NotificationError::SendFailed(e.to_string()))
     }
 }
 
@@ -63,37 +190,169 @@ async fn handler(
 
         info!("Processing SNS message: {:?}", message.message_id);
 
-        // Try to parse the message as a BoxLockedEvent
-        match serde_json::from_str::<BoxLockedEvent>(&message.message) {
-            Ok(box_event) => {
-                if box_event.event_type != "box_locked" {
-                    warn!("Unexpected event type: {}", box_event.event_type);
-                    continue;
-                }
-
-                info!(
-                    "Processing box_locked event for box_id={}, guardian_count={}",
-                    box_event.box_id,
-                    box_event.guardian_ids.len()
+        let event_type = match serde_json::from_str::<EventEnvelope>(&message.message) {
+            Ok(envelope) if !envelope.is_supported() => {
+                warn!(
+                    "Skipping {} event with schema_version={}, newest understood is {}",
+                    envelope.event_type, envelope.schema_version, EVENT_SCHEMA_VERSION
                 );
-
-                // Handle the box locked event
-                if let Err(e) = handle_box_locked(&push_store, &box_event).await {
-                    error!(
-                        "Failed to handle box_locked event for box_id={}: {:?}",
-                        box_event.box_id, e
-                    );
-                    // Continue processing other records
-                }
+                continue;
             }
+            Ok(envelope) => envelope.event_type,
             Err(e) => {
-                error!(
-                    "Failed to parse SNS message: {}, error: {}",
-                    message.message, e
-                );
+                record_poison_message(
+                    &*push_store.poison,
+                    SOURCE,
+                    &message.message_id,
+                    &message.message,
+                    &e.to_string(),
+                )
+                .await;
                 // Continue processing remaining records
                 continue;
             }
+        };
+
+        match event_type.as_str() {
+            BoxLockedEvent::EVENT_TYPE => {
+                if let Some(box_event) = push_store.parse::<BoxLockedEvent>(&message).await {
+                    info!(
+                        "Processing box_locked event for box_id={}, guardian_count={}, request_id={}",
+                        box_event.box_id,
+                        box_event.guardian_ids.len(),
+                        box_event.request_id.as_deref().unwrap_or("-")
+                    );
+
+                    // Handle the box locked event
+                    if let Err(e) = handle_box_locked(&push_store, &box_event).await {
+                        error!(
+                            "Failed to handle box_locked event for box_id={}, request_id={}: {:?}",
+                            box_event.box_id,
+                            box_event.request_id.as_deref().unwrap_or("-"),
+                            e
+                        );
+                        // Continue processing other records
+                    }
+                }
+            }
+            "guardian_accepted" => {
+                if let Some(accepted) = push_store.parse::<GuardianAcceptedEvent>(&message).await {
+                    info!(
+                            "Processing guardian_accepted event for box_id={}, guardian_id={}, request_id={}",
+                            accepted.box_id,
+                            accepted.guardian_id,
+                            accepted.request_id.as_deref().unwrap_or("-")
+                        );
+
+                    if let Err(e) = handle_guardian_accepted(&push_store, &accepted).await {
+                        error!(
+                                "Failed to handle guardian_accepted event for box_id={}, request_id={}: {:?}",
+                                accepted.box_id,
+                                accepted.request_id.as_deref().unwrap_or("-"),
+                                e
+                            );
+                    }
+                }
+            }
+            "guardian_declined" => {
+                if let Some(declined) = push_store.parse::<GuardianDeclinedEvent>(&message).await {
+                    info!(
+                            "Processing guardian_declined event for box_id={}, guardian_id={}, request_id={}",
+                            declined.box_id,
+                            declined.guardian_id,
+                            declined.request_id.as_deref().unwrap_or("-")
+                        );
+
+                    if let Err(e) = handle_guardian_declined(&push_store, &declined).await {
+                        error!(
+                                "Failed to handle guardian_declined event for box_id={}, request_id={}: {:?}",
+                                declined.box_id,
+                                declined.request_id.as_deref().unwrap_or("-"),
+                                e
+                            );
+                    }
+                }
+            }
+            "unlock_requested" => {
+                if let Some(unlock) = push_store.parse::<UnlockRequestedEvent>(&message).await {
+                    info!(
+                            "Processing unlock_requested event for box_id={}, guardian_id={}, request_id={}",
+                            unlock.box_id,
+                            unlock.requesting_guardian_id,
+                            unlock.request_id.as_deref().unwrap_or("-")
+                        );
+
+                    if let Err(e) = handle_unlock_requested(&push_store, &unlock).await {
+                        error!(
+                                "Failed to handle unlock_requested event for box_id={}, request_id={}: {:?}",
+                                unlock.box_id,
+                                unlock.request_id.as_deref().unwrap_or("-"),
+                                e
+                            );
+                    }
+                }
+            }
+            "unlock_request_cancelled" => {
+                if let Some(cancelled) = push_store
+                    .parse::<UnlockRequestCancelledEvent>(&message)
+                    .await
+                {
+                    info!(
+                            "Processing unlock_request_cancelled event for box_id={}, guardian_count={}, request_id={}",
+                            cancelled.box_id,
+                            cancelled.guardian_ids.len(),
+                            cancelled.request_id.as_deref().unwrap_or("-")
+                        );
+
+                    if let Err(e) = handle_unlock_request_cancelled(&push_store, &cancelled).await {
+                        error!(
+                                "Failed to handle unlock_request_cancelled event for box_id={}, request_id={}: {:?}",
+                                cancelled.box_id,
+                                cancelled.request_id.as_deref().unwrap_or("-"),
+                                e
+                            );
+                    }
+                }
+            }
+            "unlock_request_denied" => {
+                if let Some(denied) = push_store.parse::<UnlockRequestDeniedEvent>(&message).await {
+                    info!(
+                            "Processing unlock_request_denied event for box_id={}, denials={}, request_id={}",
+                            denied.box_id,
+                            denied.denied_by.len(),
+                            denied.request_id.as_deref().unwrap_or("-")
+                        );
+
+                    if let Err(e) = handle_unlock_request_denied(&push_store, &denied).await {
+                        error!(
+                                "Failed to handle unlock_request_denied event for box_id={}, request_id={}: {:?}",
+                                denied.box_id,
+                                denied.request_id.as_deref().unwrap_or("-"),
+                                e
+                            );
+                    }
+                }
+            }
+            "box_unlocked" => {
+                if let Some(unlocked) = push_store.parse::<BoxUnlockedEvent>(&message).await {
+                    info!(
+                        "Processing box_unlocked event for box_id={}, approvals={}, request_id={}",
+                        unlocked.box_id,
+                        unlocked.approved_by.len(),
+                        unlocked.request_id.as_deref().unwrap_or("-")
+                    );
+
+                    if let Err(e) = handle_box_unlocked(&push_store, &unlocked).await {
+                        error!(
+                            "Failed to handle box_unlocked event for box_id={}, request_id={}: {:?}",
+                            unlocked.box_id,
+                            unlocked.request_id.as_deref().unwrap_or("-"),
+                            e
+                        );
+                    }
+                }
+            }
+            other => warn!("Unexpected event type: {}", other),
         }
     }
 
@@ -140,14 +399,301 @@ async fn handle_box_locked(
     // Send push notifications
     let owner_name = event.owner_name.as_deref().unwrap_or("Someone");
 
-    send_shard_notification(&tokens, &event.box_name, owner_name, &event.box_id)
+    // Each guardian's badge is their own pending-action count, read from the
+    // boxes they guard; guardians sharing a count are sent to together
+    let recipients: Vec<String> = tokens.iter().map(|t| t.user_id.clone()).collect();
+    let badges = pending_action_badges(&*push_store.boxes, &recipients).await;
+    let mut sent = Ok(());
+    for (badge, batch) in group_by_badge(&tokens, &badges) {
+        let options = NotificationOptions::for_type(SHARD_RECEIVED, Some(badge));
+        let result =
+            send_shard_notification(&batch, &event.box_name, owner_name, &event.box_id, &options)
+                .await;
+        if let Err(e) = push_store
+            .check_sent(&batch, result, &event.box_id, SHARD_RECEIVED)
+            .await
+        {
+            sent = Err(e);
+        }
+    }
+    sent?;
+
+    info!(
+        "Successfully sent notifications to {} guardians for box_id={}, request_id={}",
+        tokens.len(),
+        event.box_id,
+        event.request_id.as_deref().unwrap_or("-")
+    );
+
+    Ok(())
+}
+
+/// Handle a guardian_accepted event by letting the box owner know, at most
+/// once per box per throttle window
+async fn handle_guardian_accepted(
+    push_store: &PushTokenStoreWrapper,
+    event: &GuardianAcceptedEvent,
+) -> Result<(), errors::NotificationError> {
+    let tokens = push_store
+        .inner
+        .get_push_tokens(std::slice::from_ref(&event.owner_id))
         .await
-        .map_err(|e| errors::NotificationError::SendFailed(e))?;
+        .map_err(|e| {
+            errors::NotificationError::TokenLookupFailed(format!(
+                "Failed to get push tokens: {:?}",
+                e
+            ))
+        })?;
+
+    if tokens.is_empty() {
+        info!("No push tokens found for owner of box_id={}", event.box_id);
+        return Ok(());
+    }
+
+    let badge = pending_action_badge(&*push_store.boxes, &event.owner_id).await;
+    let options = NotificationOptions::for_type(PROGRESS_UPDATED, Some(badge));
+    let Some(result) = send_owner_progress_notification(
+        &push_store.progress,
+        &ExpoPushSender::from_env(),
+        &tokens,
+        &event.box_name,
+        &event.box_id,
+        SystemClock.now(),
+        &options,
+    )
+    .await
+    else {
+        info!(
+            "Coalesced guardian_accepted for box_id={} into a recent progress push, request_id={}",
+            event.box_id,
+            event.request_id.as_deref().unwrap_or("-")
+        );
+        return Ok(());
+    };
+    push_store
+        .check_sent(&tokens, result, &event.box_id, PROGRESS_UPDATED)
+        .await?;
 
     info!(
-        "Successfully sent notifications to {} guardians for box_id={}",
+        "Notified owner of box_id={} about progress, request_id={}",
+        event.box_id,
+        event.request_id.as_deref().unwrap_or("-")
+    );
+
+    Ok(())
+}
+
+/// Handle a guardian_declined event by letting the box owner know
+async fn handle_guardian_declined(
+    push_store: &PushTokenStoreWrapper,
+    event: &GuardianDeclinedEvent,
+) -> Result<(), errors::NotificationError> {
+    let tokens = push_store
+        .inner
+        .get_push_tokens(std::slice::from_ref(&event.owner_id))
+        .await
+        .map_err(|e| {
+            errors::NotificationError::TokenLookupFailed(format!(
+                "Failed to get push tokens: {:?}",
+                e
+            ))
+        })?;
+
+    if tokens.is_empty() {
+        info!("No push tokens found for owner of box_id={}", event.box_id);
+        return Ok(());
+    }
+
+    let badge = pending_action_badge(&*push_store.boxes, &event.owner_id).await;
+    let result = send_guardian_declined_notification(
+        &tokens,
+        &event.guardian_name,
+        &event.box_name,
+        &event.box_id,
+        &NotificationOptions::for_type(GUARDIAN_DECLINED, Some(badge)),
+    )
+    .await;
+    push_store
+        .check_sent(&tokens, result, &event.box_id, GUARDIAN_DECLINED)
+        .await?;
+
+    info!(
+        "Notified owner of box_id={} that guardian_id={} declined at {}, request_id={}",
+        event.box_id,
+        event.guardian_id,
+        event.timestamp,
+        event.request_id.as_deref().unwrap_or("-")
+    );
+
+    Ok(())
+}
+
+/// Handle an unlock_requested event by alerting the box owner
+async fn handle_unlock_requested(
+    push_store: &PushTokenStoreWrapper,
+    event: &UnlockRequestedEvent,
+) -> Result<(), errors::NotificationError> {
+    let tokens = push_store
+        .inner
+        .get_push_tokens(std::slice::from_ref(&event.owner_id))
+        .await
+        .map_err(|e| {
+            errors::NotificationError::TokenLookupFailed(format!(
+                "Failed to get push tokens: {:?}",
+                e
+            ))
+        })?;
+
+    if tokens.is_empty() {
+        info!("No push tokens found for owner of box_id={}", event.box_id);
+        return Ok(());
+    }
+
+    let badge = pending_action_badge(&*push_store.boxes, &event.owner_id).await;
+    let options = NotificationOptions::for_type(UNLOCK_REQUESTED, Some(badge));
+    let result = send_unlock_requested_notification(
+        &tokens,
+        &event.box_name,
+        &event.box_id,
+        event.reason.as_deref(),
+        &options,
+    )
+    .await;
+    push_store
+        .check_sent(&tokens, result, &event.box_id, UNLOCK_REQUESTED)
+        .await?;
+
+    info!(
+        "Notified owner of box_id={} about unlock requested by guardian_id={} at {}, request_id={}",
+        event.box_id,
+        event.requesting_guardian_id,
+        event.timestamp,
+        event.request_id.as_deref().unwrap_or("-")
+    );
+
+    Ok(())
+}
+
+/// Handle an unlock_request_cancelled event by telling the guardians involved
+async fn handle_unlock_request_cancelled(
+    push_store: &PushTokenStoreWrapper,
+    event: &UnlockRequestCancelledEvent,
+) -> Result<(), errors::NotificationError> {
+    if event.guardian_ids.is_empty() {
+        info!("No guardians to notify for box_id={}", event.box_id);
+        return Ok(());
+    }
+
+    let tokens = push_store
+        .inner
+        .get_push_tokens(&event.guardian_ids)
+        .await
+        .map_err(|e| {
+            errors::NotificationError::TokenLookupFailed(format!(
+                "Failed to get push tokens: {:?}",
+                e
+            ))
+        })?;
+
+    if tokens.is_empty() {
+        info!(
+            "No push tokens found for {} guardians of box_id={}",
+            event.guardian_ids.len(),
+            event.box_id
+        );
+        return Ok(());
+    }
+
+    // Nothing is left to do, so the badge is left as it is
+    let options = NotificationOptions::for_type(UNLOCK_REQUEST_CANCELLED, None);
+    let result =
+        send_unlock_cancelled_notification(&tokens, &event.box_name, &event.box_id, &options).await;
+    push_store
+        .check_sent(&tokens, result, &event.box_id, UNLOCK_REQUEST_CANCELLED)
+        .await?;
+
+    info!(
+        "Notified {} guardians that unlock_request_id={} on box_id={} was cancelled, request_id={}",
         tokens.len(),
-        event.box_id
+        event.unlock_request_id,
+        event.box_id,
+        event.request_id.as_deref().unwrap_or("-")
+    );
+
+    Ok(())
+}
+
+/// Handle an unlock_request_denied event by telling the box owner
+async fn handle_unlock_request_denied(
+    push_store: &PushTokenStoreWrapper,
+    event: &UnlockRequestDeniedEvent,
+) -> Result<(), errors::NotificationError> {
+    let tokens = push_store
+        .inner
+        .get_push_tokens(std::slice::from_ref(&event.owner_id))
+        .await
+        .map_err(|e| {
+            errors::NotificationError::TokenLookupFailed(format!(
+                "Failed to get push tokens: {:?}",
+                e
+            ))
+        })?;
+
+    if tokens.is_empty() {
+        info!("No push tokens found for owner of box_id={}", event.box_id);
+        return Ok(());
+    }
+
+    let badge = pending_action_badge(&*push_store.boxes, &event.owner_id).await;
+    let options = NotificationOptions::for_type(UNLOCK_REQUEST_DENIED, Some(badge));
+    let result =
+        send_unlock_denied_notification(&tokens, &event.box_name, &event.box_id, &options).await;
+    push_store
+        .check_sent(&tokens, result, &event.box_id, UNLOCK_REQUEST_DENIED)
+        .await?;
+
+    info!(
+        "Notified owner that the unlock of box_id={} was denied, request_id={}",
+        event.box_id,
+        event.request_id.as_deref().unwrap_or("-")
+    );
+
+    Ok(())
+}
+
+/// Handle a box_unlocked event by telling the box owner
+async fn handle_box_unlocked(
+    push_store: &PushTokenStoreWrapper,
+    event: &BoxUnlockedEvent,
+) -> Result<(), errors::NotificationError> {
+    let tokens = push_store
+        .inner
+        .get_push_tokens(std::slice::from_ref(&event.owner_id))
+        .await
+        .map_err(|e| {
+            errors::NotificationError::TokenLookupFailed(format!(
+                "Failed to get push tokens: {:?}",
+                e
+            ))
+        })?;
+
+    if tokens.is_empty() {
+        info!("No push tokens found for owner of box_id={}", event.box_id);
+        return Ok(());
+    }
+
+    let badge = pending_action_badge(&*push_store.boxes, &event.owner_id).await;
+    let options = NotificationOptions::for_type(BOX_UNLOCKED, Some(badge));
+    let result =
+        send_box_unlocked_notification(&tokens, &event.box_name, &event.box_id, &options).await;
+    push_store
+        .check_sent(&tokens, result, &event.box_id, BOX_UNLOCKED)
+        .await?;
+
+    info!(
+        "Notified owner that box_id={} was unlocked, request_id={}",
+        event.box_id,
+        event.request_id.as_deref().unwrap_or("-")
     );
 
     Ok(())
@@ -1,12 +1,18 @@
 use aws_lambda_events::event::cloudwatch_events::CloudWatchEvent;
-use chrono::{DateTime, Utc};
-use env_logger;
+use chrono::{DateTime, Duration, Utc};
+use futures::stream::{self, StreamExt};
 use lambda_runtime::{service_fn, Error, LambdaEvent};
-use lockbox_shared::models::BoxRecord;
-use lockbox_shared::push::send_shard_reminder_notification;
-use lockbox_shared::store::dynamo::{DynamoBoxStore, DynamoPushTokenStore};
-use lockbox_shared::store::{BoxStore, PushTokenStore};
+use lockbox_shared::error::StoreError;
+use lockbox_shared::models::{BoxRecord, NotificationPrefs, PushToken, QuietHours};
+use lockbox_shared::push::{
+    pending_action_badge, record_push_failures, send_shard_digest_notification,
+    send_shard_reminder_notification, NotificationOptions, SHARD_DIGEST, SHARD_REMINDER,
+};
+use lockbox_shared::store::dynamo::{DynamoBoxStore, DynamoPushFailureStore, DynamoPushTokenStore};
+use lockbox_shared::store::{BoxStore, PushFailureStore, PushTokenStore, MAX_SCAN_SEGMENTS};
 use log::{error, info, warn};
+use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
 
 /// Reminder intervals in hours
@@ -14,20 +20,45 @@ const REMINDER_1_HOURS: i64 = 24;
 const REMINDER_2_HOURS: i64 = 72;
 const REMINDER_3_HOURS: i64 = 168; // 1 week
 
+/// Length of each reminder window; matches the 6 hour schedule so each
+/// reminder is sent exactly once
+const REMINDER_WINDOW_HOURS: i64 = 6;
+
 /// Grace period before first reminder (give user time to see initial notification)
 const GRACE_PERIOD_HOURS: i64 = 1;
 
+/// Most runs a reminder can be held back by a guardian's quiet hours, which
+/// last less than a day
+const MAX_DEFERRED_RUNS: i64 = 24 / REMINDER_WINDOW_HOURS;
+
+/// Parallel scan segments used when `REMINDER_SCAN_SEGMENTS` is unset
+const DEFAULT_SCAN_SEGMENTS: u32 = 1;
+
+/// Boxes processed, and notifications sent, at once when
+/// `REMINDER_CONCURRENCY` is unset
+const DEFAULT_CONCURRENCY: usize = 10;
+
+/// Local quiet hours for guardians whose timezone is known but who haven't
+/// set their own, used when `REMINDER_QUIET_HOURS` is unset or invalid
+const DEFAULT_QUIET_HOURS: &str = "22:00-08:00";
+
 #[tokio::main]
 async fn main() -> Result<(), Error> {
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    lockbox_shared::logging::init();
 
     info!("Starting Reminder Service Lambda");
 
     let box_store = Arc::new(DynamoBoxStore::new().await);
     let push_store = Arc::new(DynamoPushTokenStore::new().await);
+    let failure_store = Arc::new(DynamoPushFailureStore::new().await);
 
     lambda_runtime::run(service_fn(|event| {
-        handler(event, box_store.clone(), push_store.clone())
+        handler(
+            event,
+            box_store.clone(),
+            push_store.clone(),
+            failure_store.clone(),
+        )
     }))
     .await?;
 
@@ -38,13 +69,15 @@ async fn handler(
     _event: LambdaEvent<CloudWatchEvent>,
     box_store: Arc<DynamoBoxStore>,
     push_store: Arc<DynamoPushTokenStore>,
+    failure_store: Arc<DynamoPushFailureStore>,
 ) -> Result<(), Error> {
     info!("Reminder service triggered");
 
     let now = Utc::now();
 
-    // Get all locked boxes
-    let boxes = match box_store.scan_locked_boxes().await {
+    // Only boxes locked recently enough to still fall in a reminder window
+    let cutoff = reminder_cutoff(now);
+    let boxes = match scan_reminder_boxes(&box_store, cutoff, scan_segments()).await {
         Ok(boxes) => boxes,
         Err(e) => {
             error!("Failed to scan locked boxes: {:?}", e);
@@ -55,112 +88,471 @@ async fn handler(
     let box_count = boxes.len();
     info!("Found {} locked boxes to check", box_count);
 
-    let mut reminders_sent = 0;
-
-    for box_rec in &boxes {
-        if let Err(e) = process_box(box_rec, &push_store, now).await {
-            error!("Failed to process box {}: {:?}", box_rec.id, e);
-            // Continue processing other boxes
-        } else {
-            reminders_sent += 1;
+    let default_quiet = default_quiet_hours();
+    let concurrency = reminder_concurrency();
+
+    let results = for_each_bounded(&boxes, concurrency, |box_rec| {
+        process_box(box_rec, &push_store, default_quiet.as_ref(), now)
+    })
+    .await;
+
+    let mut due = Vec::new();
+    for (box_rec, result) in boxes.iter().zip(results) {
+        match result {
+            Ok(reminders) => due.extend(reminders),
+            Err(e) => {
+                error!("Failed to process box {}: {:?}", box_rec.id, e);
+                // Continue processing other boxes
+            }
         }
     }
 
+    let due_count = due.len();
+    let notifications = plan_notifications(due, digest_enabled());
+    let notifications_sent = for_each_bounded(&notifications, concurrency, |notification| {
+        send_reminder_notification(notification, &*box_store, &*failure_store)
+    })
+    .await
+    .into_iter()
+    .filter(|sent| *sent)
+    .count();
+
     info!(
-        "Reminder service completed. Processed {} boxes, sent reminders for {} guardians",
-        box_count, reminders_sent
+        "Reminder service completed. Processed {} boxes, {} reminders due, sent {} notifications",
+        box_count, due_count, notifications_sent
     );
 
     Ok(())
 }
 
+/// Whether reminders for several boxes are coalesced into one notification
+/// per guardian, from `REMINDER_DIGEST`
+fn digest_enabled() -> bool {
+    std::env::var("REMINDER_DIGEST")
+        .map(|v| v.to_lowercase() == "true")
+        .unwrap_or(false)
+}
+
+/// A reminder a guardian is due for one box
+#[derive(Debug, Clone)]
+struct DueReminder {
+    guardian_id: String,
+    tokens: Vec<PushToken>,
+    box_id: String,
+    box_name: String,
+    owner_name: String,
+    reminder_number: u32,
+}
+
+/// A push to send for one or more due reminders
+#[derive(Debug)]
+enum ReminderNotification {
+    /// One box's reminder
+    Single(DueReminder),
+    /// Reminders for several boxes of the same guardian
+    Digest(Vec<DueReminder>),
+}
+
+/// Turns due reminders into notifications. With `digest` set, a guardian due
+/// reminders for several boxes gets one digest for them all.
+fn plan_notifications(due: Vec<DueReminder>, digest: bool) -> Vec<ReminderNotification> {
+    if !digest {
+        return due.into_iter().map(ReminderNotification::Single).collect();
+    }
+
+    // Group by guardian, keeping the order guardians were first seen in
+    let mut groups: Vec<Vec<DueReminder>> = Vec::new();
+    let mut group_index: HashMap<String, usize> = HashMap::new();
+    for reminder in due {
+        match group_index.get(&reminder.guardian_id) {
+            Some(&index) => groups[index].push(reminder),
+            None => {
+                group_index.insert(reminder.guardian_id.clone(), groups.len());
+                groups.push(vec![reminder]);
+            }
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|mut group| {
+            if group.len() == 1 {
+                ReminderNotification::Single(group.remove(0))
+            } else {
+                ReminderNotification::Digest(group)
+            }
+        })
+        .collect()
+}
+
+/// Sends a planned notification, returning whether it went out. Recipients
+/// it didn't reach are dead-lettered against each box it was about.
+async fn send_reminder_notification(
+    notification: &ReminderNotification,
+    box_store: &dyn BoxStore,
+    failure_store: &dyn PushFailureStore,
+) -> bool {
+    let (guardian_id, tokens, box_ids, notification_type, result) = match notification {
+        ReminderNotification::Single(reminder) => {
+            info!(
+                "Sending reminder {} to guardian {} for box {}",
+                reminder.reminder_number, reminder.guardian_id, reminder.box_id
+            );
+            let badge = pending_action_badge(box_store, &reminder.guardian_id).await;
+            let result = send_shard_reminder_notification(
+                &reminder.tokens,
+                &reminder.box_name,
+                &reminder.owner_name,
+                &reminder.box_id,
+                reminder.reminder_number,
+                &NotificationOptions::for_type(SHARD_REMINDER, Some(badge)),
+            )
+            .await;
+            (
+                &reminder.guardian_id,
+                &reminder.tokens,
+                vec![reminder.box_id.as_str()],
+                SHARD_REMINDER,
+                result,
+            )
+        }
+        ReminderNotification::Digest(reminders) => {
+            let first = &reminders[0];
+            let box_names: Vec<String> = reminders.iter().map(|r| r.box_name.clone()).collect();
+            info!(
+                "Sending digest of {} reminders to guardian {}",
+                reminders.len(),
+                first.guardian_id
+            );
+            let options = NotificationOptions::for_type(SHARD_DIGEST, Some(reminders.len() as u32));
+            let result = send_shard_digest_notification(
+                &first.tokens,
+                reminders.len(),
+                &box_names,
+                &options,
+            )
+            .await;
+            (
+                &first.guardian_id,
+                &first.tokens,
+                reminders.iter().map(|r| r.box_id.as_str()).collect(),
+                SHARD_DIGEST,
+                result,
+            )
+        }
+    };
+
+    for box_id in box_ids {
+        record_push_failures(
+            failure_store,
+            tokens,
+            &result,
+            Some(box_id),
+            notification_type,
+        )
+        .await;
+    }
+
+    match result {
+        Ok(_) => {
+            info!(
+                "Successfully sent reminder notification to guardian {}",
+                guardian_id
+            );
+            true
+        }
+        Err(e) => {
+            error!("Failed to send reminder to guardian {}: {}", guardian_id, e);
+            false
+        }
+    }
+}
+
+/// Scans for boxes that may be due a reminder. With more than one segment the
+/// table is scanned in parallel and the cutoff is applied to the results.
+async fn scan_reminder_boxes(
+    box_store: &Arc<DynamoBoxStore>,
+    cutoff: DateTime<Utc>,
+    segments: u32,
+) -> Result<Vec<BoxRecord>, StoreError> {
+    if segments <= 1 {
+        return box_store.scan_locked_boxes_since(cutoff, true).await;
+    }
+
+    info!("Scanning locked boxes across {} segments", segments);
+    let handles: Vec<_> = (0..segments)
+        .map(|segment| {
+            let box_store = box_store.clone();
+            tokio::spawn(
+                async move { box_store.scan_locked_boxes_segment(segment, segments).await },
+            )
+        })
+        .collect();
+
+    let mut boxes = Vec::new();
+    for handle in handles {
+        let segment_boxes = handle
+            .await
+            .map_err(|e| StoreError::InternalError(format!("Scan task failed: {}", e)))??;
+        boxes.extend(
+            segment_boxes
+                .into_iter()
+                .filter(|b| b.shards_deleted_at.is_none() && b.locked_or_updated_since(cutoff)),
+        );
+    }
+    Ok(boxes)
+}
+
+/// Number of parallel scan segments, from `REMINDER_SCAN_SEGMENTS`
+fn scan_segments() -> u32 {
+    std::env::var("REMINDER_SCAN_SEGMENTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|segments| (1..=MAX_SCAN_SEGMENTS).contains(segments))
+        .unwrap_or(DEFAULT_SCAN_SEGMENTS)
+}
+
+/// Boxes processed, and notifications sent, at once, from
+/// `REMINDER_CONCURRENCY`
+fn reminder_concurrency() -> usize {
+    std::env::var("REMINDER_CONCURRENCY")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|concurrency| *concurrency > 0)
+        .unwrap_or(DEFAULT_CONCURRENCY)
+}
+
+/// Runs `task` for every item with at most `limit` in flight, returning the
+/// outputs in item order. Tasks report their own failures through their
+/// output, so one failing doesn't stop the rest.
+async fn for_each_bounded<'a, T, O, F, Fut>(items: &'a [T], limit: usize, task: F) -> Vec<O>
+where
+    F: Fn(&'a T) -> Fut,
+    Fut: Future<Output = O>,
+{
+    let mut outputs: Vec<(usize, O)> = stream::iter(items.iter().enumerate())
+        .map(|(index, item)| {
+            let output = task(item);
+            async move { (index, output.await) }
+        })
+        .buffer_unordered(limit.max(1))
+        .collect()
+        .await;
+    outputs.sort_by_key(|(index, _)| *index);
+    outputs.into_iter().map(|(_, output)| output).collect()
+}
+
+/// Default quiet hours from `REMINDER_QUIET_HOURS` ("HH:MM-HH:MM" in the
+/// guardian's local time, or "off"). The offset is filled in per guardian.
+fn default_quiet_hours() -> Option<QuietHours> {
+    let configured = std::env::var("REMINDER_QUIET_HOURS").ok();
+    if configured.as_deref() == Some("off") {
+        return None;
+    }
+    configured
+        .as_deref()
+        .and_then(parse_quiet_hours)
+        .or_else(|| parse_quiet_hours(DEFAULT_QUIET_HOURS))
+}
+
+fn parse_quiet_hours(value: &str) -> Option<QuietHours> {
+    let (start, end) = value.split_once('-')?;
+    let quiet_hours = QuietHours {
+        start: start.trim().to_string(),
+        end: end.trim().to_string(),
+        utc_offset_minutes: 0,
+    };
+    let (start, end) = quiet_hours.bounds()?;
+    (start != end).then_some(quiet_hours)
+}
+
+/// The preferences reminders follow for a guardian. Their own quiet hours
+/// win; otherwise the default quiet hours apply in their device's timezone,
+/// if it's known. Without either, reminders go out whenever they fall due.
+fn effective_prefs(
+    token: &PushToken,
+    default_quiet: Option<&QuietHours>,
+) -> Option<NotificationPrefs> {
+    let prefs = token.notification_prefs.clone();
+    if prefs.as_ref().is_some_and(|p| p.quiet_hours.is_some()) {
+        return prefs;
+    }
+    let (Some(offset), Some(default_quiet)) = (token.utc_offset_minutes, default_quiet) else {
+        return prefs;
+    };
+
+    let mut prefs = prefs.unwrap_or_default();
+    prefs.quiet_hours = Some(QuietHours {
+        utc_offset_minutes: offset,
+        ..default_quiet.clone()
+    });
+    Some(prefs)
+}
+
+/// Decides which of a box's guardians are due a reminder on this run
 async fn process_box(
     box_rec: &BoxRecord,
     push_store: &Arc<DynamoPushTokenStore>,
+    default_quiet: Option<&QuietHours>,
     now: DateTime<Utc>,
-) -> Result<(), String> {
-    let locked_at = box_rec
-        .locked_at
-        .as_ref()
-        .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
-        .map(|dt| dt.with_timezone(&Utc));
-
-    let locked_at = match locked_at {
+) -> Result<Vec<DueReminder>, String> {
+    let locked_at = match box_rec.locked_at_time() {
         Some(dt) => dt,
         None => {
             warn!(
                 "Box {} is locked but has no locked_at timestamp",
                 box_rec.id
             );
-            return Ok(());
+            return Ok(Vec::new());
         }
     };
 
     let owner_name = box_rec.owner_name.as_deref().unwrap_or("Someone");
+    let mut due = Vec::new();
 
-    for guardian in &box_rec.guardians {
-        // Skip if already accepted
-        if guardian.shard_accepted_at.is_some() {
-            continue;
-        }
-
-        // Use lock_data_received_at if available, otherwise fall back to locked_at
-        let shard_sent_at = guardian
-            .lock_data_received_at
-            .as_ref()
-            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
-            .map(|dt| dt.with_timezone(&Utc))
-            .unwrap_or(locked_at);
-
-        let hours_since_shard = (now - shard_sent_at).num_hours();
+    for guardian in box_rec.guardians_awaiting_acceptance() {
+        let shard_sent_at = guardian.shard_sent_at(locked_at);
 
-        // Determine which reminder to send (if any)
-        let reminder_number = determine_reminder_number(hours_since_shard);
-
-        if reminder_number == 0 {
-            // No reminder needed yet
+        if !reminder_possible(shard_sent_at, now) {
+            // No reminder needed yet, or none held back
             continue;
         }
 
-        info!(
-            "Sending reminder {} to guardian {} for box {} (hours since shard: {})",
-            reminder_number, guardian.id, box_rec.id, hours_since_shard
-        );
-
         // Get push token for this guardian
         let tokens = push_store
             .get_push_tokens(&[guardian.id.clone()])
             .await
             .map_err(|e| format!("Failed to get push token: {:?}", e))?;
 
-        if tokens.is_empty() {
+        let Some(token) = tokens.first() else {
             warn!(
                 "No push token found for guardian {} of box {}",
                 guardian.id, box_rec.id
             );
             continue;
-        }
+        };
+
+        // Determine which reminder to send (if any)
+        let reminder_number = match guardian_reminder(
+            shard_sent_at,
+            now,
+            effective_prefs(token, default_quiet).as_ref(),
+        ) {
+            Reminder::Send(number) => number,
+            Reminder::Muted => {
+                info!(
+                    "Guardian {} has muted reminders, skipping box {}",
+                    guardian.id, box_rec.id
+                );
+                continue;
+            }
+            Reminder::Deferred(number) => {
+                info!(
+                    "Deferring reminder {} to guardian {} for box {} until their quiet hours end",
+                    number, guardian.id, box_rec.id
+                );
+                continue;
+            }
+            Reminder::None => continue,
+        };
 
-        // Send reminder notification
-        if let Err(e) = send_shard_reminder_notification(
-            &tokens,
-            &box_rec.name,
-            owner_name,
-            &box_rec.id,
+        info!(
+            "Reminder {} due for guardian {} of box {} (hours since shard: {})",
             reminder_number,
-        )
-        .await
-        {
-            error!("Failed to send reminder to guardian {}: {}", guardian.id, e);
+            guardian.id,
+            box_rec.id,
+            (now - shard_sent_at).num_hours()
+        );
+        due.push(DueReminder {
+            guardian_id: guardian.id.clone(),
+            tokens: tokens.clone(),
+            box_id: box_rec.id.clone(),
+            box_name: box_rec.name.clone(),
+            owner_name: owner_name.to_string(),
+            reminder_number,
+        });
+    }
+
+    Ok(due)
+}
+
+/// Earliest shard send time that can still be inside the last reminder
+/// window, allowing for that reminder being held back by quiet hours
+fn reminder_cutoff(now: DateTime<Utc>) -> DateTime<Utc> {
+    now - Duration::hours(
+        REMINDER_3_HOURS + REMINDER_WINDOW_HOURS + MAX_DEFERRED_RUNS * REMINDER_WINDOW_HOURS,
+    )
+}
+
+/// What to do about a guardian's reminders on this run
+#[derive(Debug, PartialEq)]
+enum Reminder {
+    None,
+    Send(u32),
+    /// Due, but held back until the guardian's quiet hours end
+    Deferred(u32),
+    /// The guardian turned reminders off
+    Muted,
+}
+
+fn reminder_number_at(shard_sent_at: DateTime<Utc>, run_at: DateTime<Utc>) -> u32 {
+    determine_reminder_number((run_at - shard_sent_at).num_hours())
+}
+
+/// Whether this run, or one recent enough to have been deferred, falls in a
+/// reminder window. Checked before looking up the guardian's preferences.
+fn reminder_possible(shard_sent_at: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    (0..=MAX_DEFERRED_RUNS).any(|run| {
+        let run_at = now - Duration::hours(run * REMINDER_WINDOW_HOURS);
+        reminder_number_at(shard_sent_at, run_at) != 0
+    })
+}
+
+/// Decides a guardian's reminder for the run at `now`.
+///
+/// Runs are `REMINDER_WINDOW_HOURS` apart, so each reminder window covers a
+/// single run. A reminder whose run fell in the guardian's quiet hours is sent
+/// by the first run after they end, found by walking back over the quiet runs
+/// before it.
+fn guardian_reminder(
+    shard_sent_at: DateTime<Utc>,
+    now: DateTime<Utc>,
+    prefs: Option<&NotificationPrefs>,
+) -> Reminder {
+    let due_now = reminder_number_at(shard_sent_at, now);
+    let Some(prefs) = prefs else {
+        return if due_now == 0 {
+            Reminder::None
         } else {
-            info!(
-                "Successfully sent reminder {} to guardian {}",
-                reminder_number, guardian.id
-            );
-        }
+            Reminder::Send(due_now)
+        };
+    };
+
+    if !prefs.reminders_enabled {
+        return Reminder::Muted;
+    }
+    if prefs.is_quiet_at(now) {
+        return if due_now == 0 {
+            Reminder::None
+        } else {
+            Reminder::Deferred(due_now)
+        };
+    }
+    if due_now != 0 {
+        return Reminder::Send(due_now);
     }
 
-    Ok(())
+    for run in 1..=MAX_DEFERRED_RUNS {
+        let run_at = now - Duration::hours(run * REMINDER_WINDOW_HOURS);
+        if !prefs.is_quiet_at(run_at) {
+            break;
+        }
+        let deferred = reminder_number_at(shard_sent_at, run_at);
+        if deferred != 0 {
+            return Reminder::Send(deferred);
+        }
+    }
+    Reminder::None
 }
 
 /// Determines which reminder number to send based on hours since shard was sent.
@@ -181,17 +573,17 @@ fn determine_reminder_number(hours_since_shard: i64) -> u32 {
 
     // Reminder windows (6 hour windows to account for service running every 6 hours)
     // Reminder 1: 24-30 hours
-    if hours_since_shard >= REMINDER_1_HOURS && hours_since_shard < REMINDER_1_HOURS + 6 {
+    if (REMINDER_1_HOURS..REMINDER_1_HOURS + REMINDER_WINDOW_HOURS).contains(&hours_since_shard) {
         return 1;
     }
 
     // Reminder 2: 72-78 hours
-    if hours_since_shard >= REMINDER_2_HOURS && hours_since_shard < REMINDER_2_HOURS + 6 {
+    if (REMINDER_2_HOURS..REMINDER_2_HOURS + REMINDER_WINDOW_HOURS).contains(&hours_since_shard) {
         return 2;
     }
 
     // Reminder 3: 168-174 hours (1 week)
-    if hours_since_shard >= REMINDER_3_HOURS && hours_since_shard < REMINDER_3_HOURS + 6 {
+    if (REMINDER_3_HOURS..REMINDER_3_HOURS + REMINDER_WINDOW_HOURS).contains(&hours_since_shard) {
         return 3;
     }
 
@@ -239,4 +631,277 @@ mod tests {
         assert_eq!(determine_reminder_number(174), 0);
         assert_eq!(determine_reminder_number(200), 0);
     }
+    #[test]
+    fn test_reminder_cutoff_covers_last_window() {
+        let now = Utc::now();
+        let cutoff = reminder_cutoff(now);
+
+        // A shard sent at the cutoff was at the very end of the reminder 3
+        // window on the earliest run that can still be deferred to now
+        let earliest_run = now - Duration::hours(MAX_DEFERRED_RUNS * REMINDER_WINDOW_HOURS);
+        assert_eq!(
+            reminder_number_at(cutoff + Duration::hours(1), earliest_run),
+            3
+        );
+        assert_eq!(reminder_number_at(cutoff, earliest_run), 0);
+    }
+
+    fn prefs(reminders_enabled: bool, start: &str, end: &str) -> NotificationPrefs {
+        NotificationPrefs {
+            reminders_enabled,
+            quiet_hours: Some(QuietHours {
+                start: start.into(),
+                end: end.into(),
+                utc_offset_minutes: 0,
+            }),
+        }
+    }
+
+    fn at(time: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(time)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_muted_guardian_is_skipped() {
+        let now = at("2024-01-02T12:00:00Z");
+        // 24 hours after the shard: reminder 1 is due
+        let shard_sent_at = now - Duration::hours(REMINDER_1_HOURS);
+        assert_eq!(
+            guardian_reminder(shard_sent_at, now, None),
+            Reminder::Send(1)
+        );
+
+        let muted = prefs(false, "00:00", "01:00");
+        assert_eq!(
+            guardian_reminder(shard_sent_at, now, Some(&muted)),
+            Reminder::Muted
+        );
+    }
+
+    #[test]
+    fn test_reminder_in_quiet_hours_is_deferred() {
+        // Quiet from 22:00 to 07:00 UTC, past midnight
+        let quiet = prefs(true, "22:00", "07:00");
+        let now = at("2024-01-03T00:00:00Z");
+        let shard_sent_at = now - Duration::hours(REMINDER_1_HOURS);
+        assert_eq!(
+            guardian_reminder(shard_sent_at, now, Some(&quiet)),
+            Reminder::Deferred(1)
+        );
+
+        // Still quiet on the next run, at 06:00
+        let next_run = now + Duration::hours(REMINDER_WINDOW_HOURS);
+        assert!(reminder_possible(shard_sent_at, next_run));
+        assert_eq!(
+            guardian_reminder(shard_sent_at, next_run, Some(&quiet)),
+            Reminder::None
+        );
+
+        // Sent by the first run after quiet hours, at 12:00, and only that one
+        let after_quiet = next_run + Duration::hours(REMINDER_WINDOW_HOURS);
+        assert!(reminder_possible(shard_sent_at, after_quiet));
+        assert_eq!(
+            guardian_reminder(shard_sent_at, after_quiet, Some(&quiet)),
+            Reminder::Send(1)
+        );
+        let later = after_quiet + Duration::hours(REMINDER_WINDOW_HOURS);
+        assert_eq!(
+            guardian_reminder(shard_sent_at, later, Some(&quiet)),
+            Reminder::None
+        );
+
+        // Outside quiet hours nothing changes
+        let daytime = at("2024-01-03T12:00:00Z");
+        let shard_sent_at = daytime - Duration::hours(REMINDER_1_HOURS);
+        assert_eq!(
+            guardian_reminder(shard_sent_at, daytime, Some(&quiet)),
+            Reminder::Send(1)
+        );
+    }
+
+    fn token(
+        utc_offset_minutes: Option<i32>,
+        notification_prefs: Option<NotificationPrefs>,
+    ) -> PushToken {
+        PushToken {
+            user_id: "guardian".into(),
+            push_token: "ExponentPushToken[guardian]".into(),
+            platform: "ios".into(),
+            updated_at: "2024-01-01T00:00:00Z".into(),
+            last_test_notification_at: None,
+            last_verified_at: None,
+            notification_prefs,
+            utc_offset_minutes,
+        }
+    }
+
+    #[test]
+    fn test_default_quiet_hours_follow_guardian_timezone() {
+        let default_quiet = parse_quiet_hours(DEFAULT_QUIET_HOURS).unwrap();
+        // 18:00 UTC is 03:00 in Tokyo (UTC+9)
+        let now = at("2024-01-02T18:00:00Z");
+        let shard_sent_at = now - Duration::hours(REMINDER_1_HOURS);
+
+        let in_tokyo = token(Some(9 * 60), None);
+        let prefs = effective_prefs(&in_tokyo, Some(&default_quiet));
+        assert_eq!(
+            guardian_reminder(shard_sent_at, now, prefs.as_ref()),
+            Reminder::Deferred(1)
+        );
+
+        // ...but 10:00 in California (UTC-8), so that guardian is reminded
+        let in_california = token(Some(-8 * 60), None);
+        let prefs = effective_prefs(&in_california, Some(&default_quiet));
+        assert_eq!(
+            guardian_reminder(shard_sent_at, now, prefs.as_ref()),
+            Reminder::Send(1)
+        );
+
+        // Without a known timezone reminders go out as before
+        let unknown = token(None, None);
+        assert_eq!(effective_prefs(&unknown, Some(&default_quiet)), None);
+        assert_eq!(
+            guardian_reminder(shard_sent_at, now, None),
+            Reminder::Send(1)
+        );
+
+        // A guardian's own quiet hours take precedence over the default
+        let own = token(
+            Some(9 * 60),
+            Some(prefs_with_offset("12:00", "13:00", 9 * 60)),
+        );
+        let prefs = effective_prefs(&own, Some(&default_quiet));
+        assert_eq!(
+            guardian_reminder(shard_sent_at, now, prefs.as_ref()),
+            Reminder::Send(1)
+        );
+    }
+
+    fn prefs_with_offset(start: &str, end: &str, utc_offset_minutes: i32) -> NotificationPrefs {
+        NotificationPrefs {
+            reminders_enabled: true,
+            quiet_hours: Some(QuietHours {
+                start: start.into(),
+                end: end.into(),
+                utc_offset_minutes,
+            }),
+        }
+    }
+
+    fn due(guardian_id: &str, box_id: &str) -> DueReminder {
+        DueReminder {
+            guardian_id: guardian_id.into(),
+            tokens: vec![token(None, None)],
+            box_id: box_id.into(),
+            box_name: format!("Box {}", box_id),
+            owner_name: "Owner".into(),
+            reminder_number: 1,
+        }
+    }
+
+    #[test]
+    fn test_digest_coalesces_pending_boxes_per_guardian() {
+        let reminders = vec![
+            due("g1", "a"),
+            due("g2", "a"),
+            due("g1", "b"),
+            due("g1", "c"),
+        ];
+
+        // Without the digest each box gets its own push
+        let notifications = plan_notifications(reminders.clone(), false);
+        assert_eq!(notifications.len(), 4);
+        let boxes: Vec<(&str, &str)> = notifications
+            .iter()
+            .map(|notification| match notification {
+                ReminderNotification::Single(reminder) => {
+                    (reminder.guardian_id.as_str(), reminder.box_id.as_str())
+                }
+                other => panic!("expected a single reminder, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(boxes, [("g1", "a"), ("g2", "a"), ("g1", "b"), ("g1", "c")]);
+
+        // With it, the guardian with 3 pending boxes gets one digest
+        let notifications = plan_notifications(reminders, true);
+        assert_eq!(notifications.len(), 2);
+        match &notifications[0] {
+            ReminderNotification::Digest(group) => {
+                let boxes: Vec<&str> = group.iter().map(|r| r.box_id.as_str()).collect();
+                assert_eq!(boxes, ["a", "b", "c"]);
+                assert!(group.iter().all(|r| r.guardian_id == "g1"));
+            }
+            other => panic!("expected a digest, got {:?}", other),
+        }
+        match &notifications[1] {
+            ReminderNotification::Single(reminder) => {
+                assert_eq!(reminder.guardian_id, "g2");
+            }
+            other => panic!("expected a single reminder, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_quiet_hours() {
+        let quiet = parse_quiet_hours("21:30 - 06:45").unwrap();
+        assert_eq!(
+            (quiet.start.as_str(), quiet.end.as_str()),
+            ("21:30", "06:45")
+        );
+        assert!(parse_quiet_hours("22:00").is_none());
+        assert!(parse_quiet_hours("late-early").is_none());
+        assert!(parse_quiet_hours("08:00-08:00").is_none());
+    }
+
+    #[test]
+    fn test_quiet_hours_use_local_time() {
+        // 23:00-07:00 at UTC+2 is 21:00-05:00 UTC
+        let quiet = QuietHours {
+            start: "23:00".into(),
+            end: "07:00".into(),
+            utc_offset_minutes: 120,
+        };
+        assert!(quiet.contains(at("2024-01-01T21:30:00Z")));
+        assert!(quiet.contains(at("2024-01-02T04:59:00Z")));
+        assert!(!quiet.contains(at("2024-01-02T05:00:00Z")));
+        assert!(!quiet.contains(at("2024-01-01T20:59:00Z")));
+    }
+
+    #[tokio::test]
+    async fn test_for_each_bounded_processes_every_box() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let box_ids: Vec<String> = (0..7).map(|i| format!("box-{}", i)).collect();
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+
+        let results = for_each_bounded(&box_ids, 2, |box_id| {
+            let in_flight = &in_flight;
+            let max_in_flight = &max_in_flight;
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(now, Ordering::SeqCst);
+                tokio::task::yield_now().await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                // One box failing mustn't stop the others
+                if box_id == "box-3" {
+                    Err(format!("failed {}", box_id))
+                } else {
+                    Ok(box_id.clone())
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(results.len(), box_ids.len());
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 2);
+        for (box_id, result) in box_ids.iter().zip(&results) {
+            match result {
+                Ok(processed) => assert_eq!(processed, box_id),
+                Err(_) => assert_eq!(box_id, "box-3"),
+            }
+        }
+    }
 }
@@ -0,0 +1,177 @@
+use jsonwebtoken::jwk::{AlgorithmParameters, Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, DecodingKey};
+use log::{debug, info, warn};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+
+use crate::error::{Result, StoreError};
+
+/// How long a fetched key set is trusted before it is refreshed
+const DEFAULT_JWKS_TTL: Duration = Duration::from_secs(60 * 60);
+/// Shortest gap between fetches, so a stream of tokens with unknown key ids
+/// or a failing endpoint can't make us hammer the identity provider
+const DEFAULT_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+struct CachedJwks {
+    keys: JwkSet,
+    fetched_at: Instant,
+}
+
+/// Caches the identity provider's signing keys.
+///
+/// - Keys are fetched on first use and kept for the TTL
+/// - Once the TTL has passed, lookups keep using the cached set while it is
+///   refreshed in the background
+/// - A failed refresh keeps the last good set
+/// - A key id missing from the cached set triggers an immediate refetch, so
+///   rotated keys are picked up without waiting for the TTL
+pub struct JwksCache {
+    url: String,
+    ttl: Duration,
+    min_refresh_interval: Duration,
+    client: reqwest::Client,
+    keys: RwLock<Option<CachedJwks>>,
+    // Time of the last fetch attempt, successful or not
+    last_attempt: Mutex<Option<Instant>>,
+    refreshing: AtomicBool,
+}
+
+impl JwksCache {
+    /// Creates a cache for the key set published at `url`
+    pub fn new(url: impl Into<String>) -> Arc<Self> {
+        Self::with_intervals(url, DEFAULT_JWKS_TTL, DEFAULT_MIN_REFRESH_INTERVAL)
+    }
+
+    /// Creates a cache with a custom TTL and minimum gap between fetches
+    pub fn with_intervals(
+        url: impl Into<String>,
+        ttl: Duration,
+        min_refresh_interval: Duration,
+    ) -> Arc<Self> {
+        Arc::new(JwksCache {
+            url: url.into(),
+            ttl,
+            min_refresh_interval,
+            client: reqwest::Client::new(),
+            keys: RwLock::new(None),
+            last_attempt: Mutex::new(None),
+            refreshing: AtomicBool::new(false),
+        })
+    }
+
+    /// Looks up the key with the given id, along with the algorithm it signs with
+    pub async fn signing_key(self: &Arc<Self>, kid: &str) -> Result<(DecodingKey, Algorithm)> {
+        let cached = {
+            let keys = self.keys.read().await;
+            keys.as_ref().map(|c| {
+                (
+                    c.keys.find(kid).cloned(),
+                    c.fetched_at.elapsed() >= self.ttl,
+                )
+            })
+        };
+
+        match cached {
+            // First use: nothing to fall back on, so fetch inline
+            None => {
+                self.refresh(true).await?;
+            }
+            Some((Some(jwk), stale)) => {
+                if stale {
+                    self.spawn_refresh();
+                }
+                return decoding_key(&jwk);
+            }
+            // Unknown key id: the provider may have rotated its keys
+            Some((None, _)) => {
+                debug!("Key {} not in cached JWKS, refetching", kid);
+                if let Err(e) = self.refresh(false).await {
+                    warn!("JWKS refetch for key {} failed: {}", kid, e);
+                }
+            }
+        }
+
+        let keys = self.keys.read().await;
+        let jwk = keys
+            .as_ref()
+            .and_then(|c| c.keys.find(kid))
+            .ok_or_else(|| {
+                warn!("No JWKS key found for kid {}", kid);
+                StoreError::AuthError(format!("Unknown signing key: {}", kid))
+            })?;
+        decoding_key(jwk)
+    }
+
+    /// Starts a background refresh unless one is already running
+    fn spawn_refresh(self: &Arc<Self>) {
+        if self.refreshing.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let cache = Arc::clone(self);
+        tokio::spawn(async move {
+            if let Err(e) = cache.refresh(false).await {
+                warn!(
+                    "Background JWKS refresh failed, keeping last good keys: {}",
+                    e
+                );
+            }
+            cache.refreshing.store(false, Ordering::Release);
+        });
+    }
+
+    /// Fetches the key set and replaces the cached one. Unless `force` is set,
+    /// does nothing when the last attempt was under the minimum interval ago.
+    async fn refresh(&self, force: bool) -> Result<()> {
+        {
+            let mut last_attempt = self.last_attempt.lock().await;
+            if !force && last_attempt.is_some_and(|at| at.elapsed() < self.min_refresh_interval) {
+                debug!("Skipping JWKS refresh, last attempt was too recent");
+                return Ok(());
+            }
+            *last_attempt = Some(Instant::now());
+        }
+
+        let keys = self.fetch().await?;
+        *self.keys.write().await = Some(CachedJwks {
+            keys,
+            fetched_at: Instant::now(),
+        });
+        Ok(())
+    }
+
+    async fn fetch(&self) -> Result<JwkSet> {
+        info!("Fetching JWKS from {}", self.url);
+        let response = self
+            .client
+            .get(&self.url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| StoreError::Transport(format!("Failed to fetch JWKS: {}", e)))?;
+        response
+            .json::<JwkSet>()
+            .await
+            .map_err(|e| StoreError::InternalError(format!("Failed to parse JWKS: {}", e)))
+    }
+}
+
+/// Builds the decoding key for a JWK along with the algorithm it signs with.
+/// Keys that don't name their algorithm get the usual one for their type.
+pub fn decoding_key(jwk: &Jwk) -> Result<(DecodingKey, Algorithm)> {
+    let algorithm = match jwk.common.key_algorithm {
+        Some(key_algorithm) => Algorithm::from_str(&key_algorithm.to_string())
+            .map_err(|e| StoreError::AuthError(format!("Unsupported key algorithm: {}", e)))?,
+        None => match jwk.algorithm {
+            AlgorithmParameters::RSA(_) => Algorithm::RS256,
+            AlgorithmParameters::EllipticCurve(_) => Algorithm::ES256,
+            AlgorithmParameters::OctetKeyPair(_) => Algorithm::EdDSA,
+            AlgorithmParameters::OctetKey(_) => Algorithm::HS256,
+        },
+    };
+    let key = DecodingKey::from_jwk(jwk)
+        .map_err(|e| StoreError::AuthError(format!("Invalid JWKS key: {}", e)))?;
+    Ok((key, algorithm))
+}
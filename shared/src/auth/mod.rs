@@ -1,12 +1,117 @@
-use axum::{extract::Request, middleware::Next, response::Response};
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request},
+    http::{request::Parts, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
 use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
-use log::{debug, info, warn};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
+use std::env;
+use std::sync::{Arc, OnceLock};
 
-use crate::error::{Result, StoreError};
+use crate::error::{error_body, Result, StoreError};
+
+pub mod jwks;
+pub mod service;
+
+use jwks::JwksCache;
+
+// Signing keys for the configured issuer, shared by every request
+static JWKS_CACHE: OnceLock<Arc<JwksCache>> = OnceLock::new();
+
+/// Cognito group whose members may use admin-only maintenance endpoints
+pub const ADMIN_GROUP: &str = "admin";
+
+/// OAuth scope granting the same access as the admin group, for access
+/// tokens issued to machine clients
+pub const ADMIN_SCOPE: &str = "lockbox/admin";
+
+// Issuer, audience and HS256 secret of the tokens minted by the test helpers.
+// Only test builds know the secret, and even they only accept such tokens
+// when AUTH_TEST_MODE is on.
+#[cfg(any(test, feature = "test_utils"))]
+pub const TEST_ISSUER: &str = "https://cognito-idp.eu-west-2.amazonaws.com/eu-west-2_rdkfPgGg4";
+#[cfg(any(test, feature = "test_utils"))]
+pub const TEST_AUDIENCE: &str = "5pgt5gkfulqs0tkdi279c895gp";
+#[cfg(any(test, feature = "test_utils"))]
+const TEST_JWT_SECRET: &str = "test_secret_key_for_jwt_encoding_in_tests";
+#[cfg(any(test, feature = "test_utils"))]
+const TEST_SERVICE_SECRET: &str = "service-secret-for-tests-0123456789abcdef";
+
+/// Whether tokens are checked against the test secret instead of the
+/// identity provider's keys. Set by the workspace's cargo config for test
+/// runs; release builds don't have the test secret to check against.
+#[cfg(any(test, feature = "test_utils"))]
+pub fn auth_test_mode() -> bool {
+    env::var("AUTH_TEST_MODE")
+        .map(|v| v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Who may issue the tokens the services accept, and for which app clients
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    pub issuer: String,
+    pub audiences: Vec<String>,
+    pub jwks_url: String,
+}
+
+impl AuthConfig {
+    /// Reads the issuer from `JWT_ISSUER`, or builds the Cognito issuer from
+    /// `COGNITO_USER_POOL_ID` and `AWS_REGION`. Audiences come from the
+    /// comma-separated `JWT_AUDIENCE`, or `COGNITO_APP_CLIENT_ID`. The keys
+    /// are read from `JWKS_URL`, defaulting to the issuer's well-known set.
+    pub fn from_env() -> Result<Self> {
+        let issuer = match env::var("JWT_ISSUER") {
+            Ok(issuer) => issuer,
+            Err(_) => {
+                let pool_id = env::var("COGNITO_USER_POOL_ID").map_err(|_| {
+                    StoreError::AuthError("JWT_ISSUER or COGNITO_USER_POOL_ID must be set".into())
+                })?;
+                let region = env::var("AWS_REGION")
+                    .map_err(|_| StoreError::AuthError("AWS_REGION must be set".into()))?;
+                format!("https://cognito-idp.{}.amazonaws.com/{}", region, pool_id)
+            }
+        };
+
+        let audiences: Vec<String> = env::var("JWT_AUDIENCE")
+            .or_else(|_| env::var("COGNITO_APP_CLIENT_ID"))
+            .map_err(|_| {
+                StoreError::AuthError("JWT_AUDIENCE or COGNITO_APP_CLIENT_ID must be set".into())
+            })?
+            .split(',')
+            .map(|aud| aud.trim().to_string())
+            .filter(|aud| !aud.is_empty())
+            .collect();
+        if audiences.is_empty() {
+            return Err(StoreError::AuthError("No JWT audience configured".into()));
+        }
+
+        let jwks_url = env::var("JWKS_URL")
+            .unwrap_or_else(|_| format!("{}/.well-known/jwks.json", issuer.trim_end_matches('/')));
+
+        Ok(AuthConfig {
+            issuer,
+            audiences,
+            jwks_url,
+        })
+    }
+
+    #[cfg(any(test, feature = "test_utils"))]
+    fn test() -> Self {
+        AuthConfig {
+            issuer: TEST_ISSUER.to_string(),
+            audiences: vec![TEST_AUDIENCE.to_string()],
+            jwks_url: String::new(),
+        }
+    }
+}
 
 // JWT claims structure - combines both services' implementations
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     #[serde(
@@ -24,7 +129,12 @@ pub struct Claims {
     pub cognito_username: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub origin_jti: Option<String>,
-    pub aud: String,
+    /// App client the token was issued to. Only ID tokens carry it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    /// App client the token was issued to. Only access tokens carry it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub event_id: Option<String>,
     #[serde(rename = "token_use", default, skip_serializing_if = "Option::is_none")]
@@ -37,9 +147,238 @@ pub struct Claims {
     pub jti: Option<String>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub email: Option<String>,
+    #[serde(
+        rename = "cognito:groups",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub groups: Option<Vec<String>>,
+    /// Space-separated OAuth scopes, present on access tokens
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+impl Claims {
+    /// Whether the token belongs to a member of the admin group
+    pub fn is_admin(&self) -> bool {
+        self.groups
+            .as_ref()
+            .is_some_and(|groups| groups.iter().any(|g| g == ADMIN_GROUP))
+    }
+
+    /// The OAuth scopes granted to the token
+    pub fn scopes(&self) -> Vec<String> {
+        self.scope
+            .as_deref()
+            .map(|scope| scope.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Who is making a request, stored in the request extensions by
+/// `auth_middleware` alongside the bare `user_id`
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    pub user_id: String,
+    pub is_admin: bool,
+    pub scopes: Vec<String>,
+    /// The calling service when the request carried a service token. Its
+    /// `user_id` is then the user named in `X-On-Behalf-Of`, or
+    /// `service:<name>` when it isn't acting for anyone.
+    pub service: Option<String>,
+}
+
+impl AuthContext {
+    pub fn from_claims(claims: &Claims) -> Self {
+        let scopes = claims.scopes();
+        AuthContext {
+            user_id: claims.sub.clone(),
+            is_admin: claims.is_admin() || scopes.iter().any(|s| s == ADMIN_SCOPE),
+            scopes,
+            service: None,
+        }
+    }
+
+    /// Context for a trusted service, optionally acting for a user. Services
+    /// never get admin rights this way.
+    pub fn for_service(service: &str, on_behalf_of: Option<String>) -> Self {
+        AuthContext {
+            user_id: on_behalf_of.unwrap_or_else(|| format!("service:{}", service)),
+            is_admin: false,
+            scopes: Vec::new(),
+            service: Some(service.to_string()),
+        }
+    }
+
+    pub fn is_service(&self) -> bool {
+        self.service.is_some()
+    }
+
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+/// Extractor for admin-only handlers. Rejects with 403 unless the caller is
+/// an admin, or 401 if the route isn't behind `auth_middleware`.
+#[derive(Debug, Clone)]
+pub struct RequireAdmin(pub AuthContext);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequireAdmin
+where
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        _state: &S,
+    ) -> std::result::Result<Self, Self::Rejection> {
+        let Some(auth) = parts.extensions.get::<AuthContext>() else {
+            warn!("Admin route reached without an auth context");
+            return Err(
+                AuthRejection::new(AuthFailure::Missing, "Authentication required").into_response(),
+            );
+        };
+
+        if !auth.is_admin {
+            warn!("User {} denied access to admin route", auth.user_id);
+            return Err((
+                StatusCode::FORBIDDEN,
+                axum::Json(error_body("FORBIDDEN", "Admin access required")),
+            )
+                .into_response());
+        }
+
+        Ok(RequireAdmin(auth.clone()))
+    }
+}
+
+/// Why a request failed authentication
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthFailure {
+    /// No bearer token was sent
+    Missing,
+    /// The token was valid but has expired; the client should refresh it
+    Expired,
+    /// The token is malformed, wrongly signed or meant for someone else; the
+    /// client should sign in again
+    Invalid,
+    /// The token couldn't be checked because the identity provider or the
+    /// service itself failed; the client should retry later
+    Unavailable,
+}
+
+impl AuthFailure {
+    pub fn code(self) -> &'static str {
+        match self {
+            AuthFailure::Missing => "AUTH_MISSING",
+            AuthFailure::Expired => "AUTH_EXPIRED",
+            AuthFailure::Invalid => "AUTH_INVALID",
+            AuthFailure::Unavailable => "AUTH_UNAVAILABLE",
+        }
+    }
+
+    pub fn status(self) -> StatusCode {
+        match self {
+            AuthFailure::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+}
+
+/// A 401 from the auth layer, in the services' error shape with a
+/// `WWW-Authenticate` challenge as described in RFC 6750. Failures on our
+/// side are a 503 instead, so clients retry rather than sign out.
+#[derive(Debug)]
+pub struct AuthRejection {
+    pub failure: AuthFailure,
+    pub message: String,
+}
+
+impl AuthRejection {
+    pub fn new(failure: AuthFailure, message: impl Into<String>) -> Self {
+        AuthRejection {
+            failure,
+            message: message.into(),
+        }
+    }
+
+    fn challenge(&self) -> String {
+        match self.failure {
+            // Requests without credentials get a bare challenge
+            AuthFailure::Missing | AuthFailure::Unavailable => "Bearer".to_string(),
+            AuthFailure::Expired | AuthFailure::Invalid => format!(
+                "Bearer error=\"invalid_token\", error_description=\"{}\"",
+                self.message.replace('"', "'")
+            ),
+        }
+    }
+}
+
+impl From<jsonwebtoken::errors::Error> for AuthRejection {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        use jsonwebtoken::errors::ErrorKind;
+        match err.kind() {
+            ErrorKind::ExpiredSignature => {
+                AuthRejection::new(AuthFailure::Expired, "Token has expired")
+            }
+            ErrorKind::ImmatureSignature => {
+                AuthRejection::new(AuthFailure::Invalid, "Token is not valid yet")
+            }
+            ErrorKind::InvalidAudience => {
+                AuthRejection::new(AuthFailure::Invalid, "Token was issued for another client")
+            }
+            ErrorKind::InvalidIssuer => AuthRejection::new(
+                AuthFailure::Invalid,
+                "Token was issued by an unknown issuer",
+            ),
+            ErrorKind::InvalidSignature => {
+                AuthRejection::new(AuthFailure::Invalid, "Token signature is invalid")
+            }
+            _ => AuthRejection::new(AuthFailure::Invalid, format!("Invalid token: {}", err)),
+        }
+    }
+}
+
+impl From<StoreError> for AuthRejection {
+    fn from(err: StoreError) -> Self {
+        match err {
+            StoreError::Transport(_) | StoreError::Throttled(_) | StoreError::InternalError(_) => {
+                error!("Could not check token: {}", err);
+                AuthRejection::new(
+                    AuthFailure::Unavailable,
+                    "Authentication is temporarily unavailable",
+                )
+            }
+            _ => AuthRejection::new(AuthFailure::Invalid, err.to_string()),
+        }
+    }
 }
 
-// JWT decoder without verification - used since API Gateway already validated the token
+impl IntoResponse for AuthRejection {
+    fn into_response(self) -> Response {
+        warn!(
+            "Rejected request: {} ({})",
+            self.message,
+            self.failure.code()
+        );
+        let body = axum::Json(error_body(self.failure.code(), &self.message));
+        if self.failure == AuthFailure::Unavailable {
+            return (self.failure.status(), body).into_response();
+        }
+        let challenge = self.challenge();
+        (
+            self.failure.status(),
+            [(http::header::WWW_AUTHENTICATE, challenge)],
+            body,
+        )
+            .into_response()
+    }
+}
+
+// JWT decoder without verification - only for inspecting tokens, never for authenticating them
 pub fn decode_jwt_payload(token: &str) -> Result<Claims> {
     debug!("Decoding JWT payload");
 
@@ -80,31 +419,111 @@ pub fn decode_jwt_payload(token: &str) -> Result<Claims> {
     }
 }
 
-// Auth middleware for both services
-pub async fn auth_middleware(mut request: Request, next: Next) -> Response {
-    // Allow only health checks without authentication
-    let path = request.uri().path();
-    if path == "/health" {
-        return next.run(request).await;
+/// Checks a token's signature against `key` and its `exp`, `nbf` and `iss`
+/// claims against `config`, returning the claims if all pass. The app client
+/// is checked by `check_client`.
+pub fn validate_token(
+    token: &str,
+    key: &DecodingKey,
+    algorithm: Algorithm,
+    config: &AuthConfig,
+) -> std::result::Result<Claims, AuthRejection> {
+    let mut validation = Validation::new(algorithm);
+    validation.set_issuer(&[&config.issuer]);
+    // Access tokens have no `aud`, so the audience is checked per token type
+    validation.validate_aud = false;
+    validation.set_required_spec_claims(&["exp", "iss", "sub"]);
+    validation.validate_nbf = true;
+
+    let claims = decode::<Claims>(token, key, &validation)
+        .map(|data| data.claims)
+        .map_err(|e| {
+            warn!("JWT validation failed: {}", e);
+            AuthRejection::from(e)
+        })?;
+
+    check_client(&claims, config)?;
+    Ok(claims)
+}
+
+/// Checks that the token was issued to one of the configured app clients.
+/// Cognito names the client in `aud` on ID tokens and in `client_id` on
+/// access tokens; tokens of any other kind are rejected.
+fn check_client(claims: &Claims, config: &AuthConfig) -> std::result::Result<(), AuthRejection> {
+    let client = match claims.token_use.as_deref() {
+        Some("access") => claims.client_id.as_deref(),
+        Some("id") => claims.aud.as_deref(),
+        other => {
+            warn!("Rejected token with token_use {:?}", other);
+            return Err(AuthRejection::new(
+                AuthFailure::Invalid,
+                "Token is neither an ID nor an access token",
+            ));
+        }
+    };
+
+    match client {
+        Some(client) if config.audiences.iter().any(|aud| aud == client) => Ok(()),
+        _ => {
+            warn!(
+                "JWT validation failed: token issued for client {:?}",
+                client
+            );
+            Err(AuthRejection::new(
+                AuthFailure::Invalid,
+                "Token was issued for another client",
+            ))
+        }
     }
+}
 
-    // Log request details
-    info!(
-        "Auth middleware: method={:?}, path={:?}, query_params={:?}",
-        request.method(),
-        request.uri().path(),
-        request.uri().query()
-    );
+/// Verifies a bearer token. Outside AUTH_TEST_MODE the signing key is looked
+/// up by the token's `kid` in the identity provider's JWKS.
+pub async fn verify_token(token: &str) -> std::result::Result<Claims, AuthRejection> {
+    #[cfg(any(test, feature = "test_utils"))]
+    if auth_test_mode() {
+        debug!("AUTH_TEST_MODE is on, checking token against the test secret");
+        let key = DecodingKey::from_secret(TEST_JWT_SECRET.as_bytes());
+        return validate_token(token, &key, Algorithm::HS256, &AuthConfig::test());
+    }
+
+    let config = AuthConfig::from_env().map_err(|e| {
+        error!("Auth is not configured: {}", e);
+        StoreError::InternalError(e.to_string())
+    })?;
 
+    let header = decode_header(token)?;
+    let kid = header
+        .kid
+        .ok_or_else(|| AuthRejection::new(AuthFailure::Invalid, "Token has no key id"))?;
+    let cache = JWKS_CACHE.get_or_init(|| JwksCache::new(config.jwks_url.clone()));
+    let (key, algorithm) = cache.signing_key(&kid).await?;
+    if header.alg != algorithm {
+        warn!(
+            "Token algorithm {:?} does not match key {} ({:?})",
+            header.alg, kid, algorithm
+        );
+        return Err(AuthRejection::new(
+            AuthFailure::Invalid,
+            "Token algorithm mismatch",
+        ));
+    }
+
+    validate_token(token, &key, algorithm, &config)
+}
+
+/// Authenticates a request from its `Authorization` header, accepting either
+/// a user's token or another service's (optionally acting for a user via
+/// `X-On-Behalf-Of`). Shared by the HTTP middleware and the gRPC gateway.
+pub async fn authenticate(headers: &HeaderMap) -> std::result::Result<AuthContext, AuthRejection> {
     // Extract the JWT from the Authorization header
-    let auth_header = match request.headers().get("authorization") {
+    let auth_header = match headers.get("authorization") {
         Some(header) => header,
         None => {
-            warn!("Missing authorization header in request");
-            return Response::builder()
-                .status(http::StatusCode::UNAUTHORIZED)
-                .body(axum::body::Body::from("Missing authorization header"))
-                .unwrap();
+            return Err(AuthRejection::new(
+                AuthFailure::Missing,
+                "Missing authorization header",
+            ));
         }
     };
 
@@ -112,51 +531,86 @@ pub async fn auth_middleware(mut request: Request, next: Next) -> Response {
     let bearer_token = match auth_header.to_str() {
         Ok(token) => token,
         Err(err) => {
-            warn!("Invalid authorization header format: {:?}", err);
-            return Response::builder()
-                .status(http::StatusCode::UNAUTHORIZED)
-                .body(axum::body::Body::from(
-                    "Invalid authorization header format",
-                ))
-                .unwrap();
+            debug!("Unreadable authorization header: {:?}", err);
+            return Err(AuthRejection::new(
+                AuthFailure::Invalid,
+                "Invalid authorization header format",
+            ));
         }
     };
 
-    if !bearer_token.starts_with("Bearer ") {
-        warn!("Authorization header doesn't start with 'Bearer '");
-        return Response::builder()
-            .status(http::StatusCode::UNAUTHORIZED)
-            .body(axum::body::Body::from(
-                "Invalid authorization format. Expected 'Bearer <token>'",
-            ))
-            .unwrap();
-    }
-
-    let token = &bearer_token[7..]; // Skip "Bearer " prefix
+    let Some(token) = bearer_token.strip_prefix("Bearer ") else {
+        return Err(AuthRejection::new(
+            AuthFailure::Missing,
+            "Invalid authorization format. Expected 'Bearer <token>'",
+        ));
+    };
     debug!("JWT token length: {}", token.len());
 
-    // Simple decode without verification - box service approach
-    // API Gateway already verified the token
-    let claims = match decode_jwt_payload(token) {
-        Ok(claims) => claims,
-        Err(_) => {
-            return Response::builder()
-                .status(http::StatusCode::UNAUTHORIZED)
-                .body(axum::body::Body::from("Could not decode JWT payload"))
-                .unwrap();
-        }
+    // Other services authenticate with their own tokens and may act for a user
+    let auth = if service::is_service_token(token) {
+        let service_claims = service::verify_service_token(token)?;
+        let on_behalf_of = match headers.get(service::ON_BEHALF_OF_HEADER) {
+            None => None,
+            Some(value) => match value.to_str().map(str::trim) {
+                Ok(user_id) if !user_id.is_empty() => Some(user_id.to_string()),
+                _ => {
+                    return Err(AuthRejection::new(
+                        AuthFailure::Invalid,
+                        "Invalid X-On-Behalf-Of header",
+                    ));
+                }
+            },
+        };
+        info!(
+            "Authenticated service {} acting for {:?}",
+            service_claims.sub, on_behalf_of
+        );
+        AuthContext::for_service(&service_claims.sub, on_behalf_of)
+    } else {
+        // Verify the signature and standard claims ourselves rather than relying
+        // on API Gateway, so a misrouted or direct invocation can't skip auth
+        let claims = verify_token(token).await?;
+
+        info!("Authenticated user ID: {}", claims.sub);
+        info!(
+            "JWT claims: sub={}, email={:?}, token_use={:?}, groups={:?}, scope={:?}",
+            claims.sub, claims.email, claims.token_use, claims.groups, claims.scope
+        );
+        AuthContext::from_claims(&claims)
     };
 
-    let user_id = claims.sub;
-    info!("Authenticated user ID: {}", user_id);
+    Ok(auth)
+}
+
+// Auth middleware for both services
+pub async fn auth_middleware(mut request: Request, next: Next) -> Response {
+    // Allow only health checks without authentication
+    let path = request.uri().path();
+    if path == "/health" {
+        return next.run(request).await;
+    }
+
+    // Log request details
     info!(
-        "JWT claims: sub={}, email={:?}, aud={}",
-        user_id, claims.email, claims.aud
+        "Auth middleware: method={:?}, path={:?}, query_params={:?}",
+        request.method(),
+        request.uri().path(),
+        request.uri().query()
     );
 
+    let auth = match authenticate(request.headers()).await {
+        Ok(auth) => auth,
+        Err(rejection) => return rejection.into_response(),
+    };
+
     // Store the user_id in the request extensions for later retrieval
-    request.extensions_mut().insert(user_id.clone());
-    info!("Stored user_id in request extensions: {}", user_id);
+    request.extensions_mut().insert(auth.user_id.clone());
+    info!("Stored user_id in request extensions: {}", auth.user_id);
+
+    // Handlers that need roles, scopes or the calling service extract the
+    // full auth context
+    request.extensions_mut().insert(auth);
 
     // Continue to the handler
     debug!("Forwarding authenticated request to handler");
@@ -167,8 +621,21 @@ pub async fn auth_middleware(mut request: Request, next: Next) -> Response {
 }
 
 // Helper function to get the auth headers for testing
+#[cfg(any(test, feature = "test_utils"))]
 pub fn create_jwt_token(user_id: &str) -> String {
-    use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+    create_jwt_token_with_groups(user_id, &[])
+}
+
+// Helper function to get a test token for a user in the given Cognito groups
+#[cfg(any(test, feature = "test_utils"))]
+pub fn create_jwt_token_with_groups(user_id: &str, groups: &[&str]) -> String {
+    sign_test_token(&test_claims(user_id, groups))
+}
+
+/// Claims for a valid test token, for tests that need to tweak them before
+/// signing with [`sign_test_token`]
+#[cfg(any(test, feature = "test_utils"))]
+pub fn test_claims(user_id: &str, groups: &[&str]) -> Claims {
     use std::time::{SystemTime, UNIX_EPOCH};
 
     let now = SystemTime::now()
@@ -178,13 +645,14 @@ pub fn create_jwt_token(user_id: &str) -> String {
 
     let exp = now + 3600; // 1 hour in the future
 
-    let claims = Claims {
+    Claims {
         sub: user_id.to_string(),
         email_verified: Some(true),
-        iss: "https://cognito-idp.eu-west-2.amazonaws.com/eu-west-2_rdkfPgGg4".to_string(),
+        iss: TEST_ISSUER.to_string(),
         cognito_username: Some(user_id.to_string()),
         origin_jti: Some("2961a64b-e7ec-4885-994a-d650cc7a7c2d".to_string()),
-        aud: "5pgt5gkfulqs0tkdi279c895gp".to_string(),
+        aud: Some(TEST_AUDIENCE.to_string()),
+        client_id: None,
         event_id: Some("2096030a-d0cb-480a-9318-6f255408c66c".to_string()),
         token_use: Some("id".to_string()),
         auth_time: Some(now - 100),
@@ -192,38 +660,95 @@ pub fn create_jwt_token(user_id: &str) -> String {
         iat: now - 100,
         jti: Some("021ba19b-7fce-4bc0-b246-852346c43d4e".to_string()),
         email: Some("test@example.com".to_string()),
-    };
+        groups: (!groups.is_empty()).then(|| groups.iter().map(|g| g.to_string()).collect()),
+        scope: None,
+    }
+}
 
-    // Create JWT header
-    let header = Header::new(Algorithm::HS256);
+/// Signs claims with the test secret. The middleware only accepts such
+/// tokens in AUTH_TEST_MODE, which `.cargo/config.toml` turns on for cargo
+/// runs.
+#[cfg(any(test, feature = "test_utils"))]
+pub fn sign_test_token(claims: &Claims) -> String {
+    use jsonwebtoken::{encode, EncodingKey, Header};
 
     // In a real scenario, Cognito would use RS256 with a proper key pair
     // For testing purposes, we use HS256 with a simple secret
-    let secret = "test_secret_key_for_jwt_encoding_in_tests";
-    let encoding_key = EncodingKey::from_secret(secret.as_bytes());
+    let encoding_key = EncodingKey::from_secret(TEST_JWT_SECRET.as_bytes());
 
     // Generate the JWT
-    encode(&header, &claims, &encoding_key).expect("Failed to create JWT")
+    encode(&Header::new(Algorithm::HS256), claims, &encoding_key).expect("Failed to create JWT")
 }
 
 /// Helper function to create an authorization header with a bearer token for tests
+#[cfg(any(test, feature = "test_utils"))]
 pub fn create_auth_header(user_id: &str) -> (String, String) {
     let token = create_jwt_token(user_id);
     ("authorization".to_string(), format!("Bearer {}", token))
 }
 
 /// Helper function to create a test request with authentication headers
+#[cfg(any(test, feature = "test_utils"))]
 pub fn create_test_request(
     method: &str,
     path: &str,
     user_id: &str,
     body: Option<serde_json::Value>,
+) -> http::Request<axum::body::Body> {
+    build_test_request(method, path, create_jwt_token(user_id), body)
+}
+
+/// Helper function to create a test request from a member of the admin group
+#[cfg(any(test, feature = "test_utils"))]
+pub fn create_admin_test_request(
+    method: &str,
+    path: &str,
+    user_id: &str,
+    body: Option<serde_json::Value>,
+) -> http::Request<axum::body::Body> {
+    build_test_request(
+        method,
+        path,
+        create_jwt_token_with_groups(user_id, &[ADMIN_GROUP]),
+        body,
+    )
+}
+
+/// Helper function to create a test request from another service, optionally
+/// acting on behalf of a user. Switches on service auth with the test secret
+/// for the rest of the test process.
+#[cfg(any(test, feature = "test_utils"))]
+pub fn create_service_test_request(
+    method: &str,
+    path: &str,
+    service: &str,
+    on_behalf_of: Option<&str>,
+    body: Option<serde_json::Value>,
+) -> http::Request<axum::body::Body> {
+    env::set_var("SERVICE_AUTH_SECRET", TEST_SERVICE_SECRET);
+    let token = service::create_service_token(service, TEST_SERVICE_SECRET, 300);
+
+    let mut request = build_test_request(method, path, token, body);
+    if let Some(user_id) = on_behalf_of {
+        request.headers_mut().insert(
+            service::ON_BEHALF_OF_HEADER,
+            http::HeaderValue::from_str(user_id).expect("Invalid user id header"),
+        );
+    }
+    request
+}
+
+#[cfg(any(test, feature = "test_utils"))]
+fn build_test_request(
+    method: &str,
+    path: &str,
+    token: String,
+    body: Option<serde_json::Value>,
 ) -> http::Request<axum::body::Body> {
     let mut builder = http::Request::builder().method(method).uri(path);
 
     // Add authorization header with JWT
-    let (auth_key, auth_value) = create_auth_header(user_id);
-    builder = builder.header(auth_key, auth_value);
+    builder = builder.header("authorization", format!("Bearer {}", token));
 
     // Add content type if there is a body
     if body.is_some() {
@@ -322,4 +847,377 @@ mod tests {
         // Assert: Middleware returns unauthorized
         assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
+
+    #[test]
+    fn test_claims_admin_group() {
+        let token = create_jwt_token_with_groups("admin-user", &[ADMIN_GROUP]);
+        let claims = decode_jwt_payload(&token).unwrap();
+        assert!(claims.is_admin());
+
+        let token = create_jwt_token("regular-user");
+        let claims = decode_jwt_payload(&token).unwrap();
+        assert!(claims.groups.is_none());
+        assert!(!claims.is_admin());
+    }
+
+    async fn admin_handler(RequireAdmin(auth): RequireAdmin) -> impl IntoResponse {
+        auth.user_id
+    }
+
+    #[tokio::test]
+    async fn test_require_admin_allows_admin() {
+        let app = Router::new()
+            .route("/admin", get(admin_handler))
+            .layer(axum::middleware::from_fn(auth_middleware));
+
+        let token = create_jwt_token_with_groups("admin-user", &[ADMIN_GROUP]);
+        let request = HttpRequest::builder()
+            .uri("/admin")
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"admin-user");
+    }
+
+    #[tokio::test]
+    async fn test_require_admin_denies_non_admin() {
+        let app = Router::new()
+            .route("/admin", get(admin_handler))
+            .layer(axum::middleware::from_fn(auth_middleware));
+
+        let token = create_jwt_token("regular-user");
+        let request = HttpRequest::builder()
+            .uri("/admin")
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_auth_context_scopes() {
+        let token = create_jwt_token("machine-client");
+        let mut claims = decode_jwt_payload(&token).unwrap();
+        assert!(!AuthContext::from_claims(&claims).is_admin);
+
+        claims.scope = Some(format!("openid {}", ADMIN_SCOPE));
+        let auth = AuthContext::from_claims(&claims);
+        assert_eq!(auth.user_id, "machine-client");
+        assert!(auth.is_admin);
+        assert!(auth.has_scope("openid"));
+        assert!(!auth.has_scope("email"));
+    }
+
+    // Sends a request through the middleware, returning the status, error
+    // code and WWW-Authenticate challenge
+    async fn rejection_for(authorization: Option<String>) -> (StatusCode, String, String) {
+        let app = Router::new()
+            .route("/", get(check_user_id_handler))
+            .layer(axum::middleware::from_fn(auth_middleware));
+
+        let mut request = HttpRequest::builder().uri("/");
+        if let Some(authorization) = authorization {
+            request = request.header("authorization", authorization);
+        }
+        let response = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let challenge = response
+            .headers()
+            .get(http::header::WWW_AUTHENTICATE)
+            .map(|v| v.to_str().unwrap().to_string())
+            .unwrap_or_default();
+        let body = crate::test_utils::http_test_utils::response_to_json(response).await;
+        (
+            status,
+            body["code"].as_str().unwrap_or_default().to_string(),
+            challenge,
+        )
+    }
+
+    async fn rejection_for_token(token: &str) -> (StatusCode, String, String) {
+        rejection_for(Some(format!("Bearer {}", token))).await
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_missing_token_code() {
+        let (status, code, challenge) = rejection_for(None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code, "AUTH_MISSING");
+        assert_eq!(challenge, "Bearer");
+
+        // Other schemes don't carry a bearer token either
+        let (status, code, _) = rejection_for(Some("Basic dXNlcjpwYXNz".to_string())).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code, "AUTH_MISSING");
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_malformed_token_code() {
+        let (status, code, challenge) = rejection_for_token("invalid.token.format").await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code, "AUTH_INVALID");
+        assert!(challenge.starts_with("Bearer error=\"invalid_token\""));
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_expired_token() {
+        let mut claims = test_claims("expired-user", &[]);
+        claims.iat -= 7200;
+        claims.exp = claims.iat + 3600;
+
+        let (status, code, challenge) = rejection_for_token(&sign_test_token(&claims)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code, "AUTH_EXPIRED");
+        assert_eq!(
+            challenge,
+            "Bearer error=\"invalid_token\", error_description=\"Token has expired\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_wrong_audience() {
+        let mut claims = test_claims("other-app-user", &[]);
+        claims.aud = Some("some-other-client".to_string());
+
+        let (status, code, _) = rejection_for_token(&sign_test_token(&claims)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code, "AUTH_INVALID");
+    }
+
+    // Claims shaped like a Cognito access token: no `aud`, the app client in
+    // `client_id`, and the granted scopes
+    fn access_token_claims(client_id: &str, scope: &str) -> serde_json::Value {
+        let claims = test_claims("machine-client", &[]);
+        serde_json::json!({
+            "sub": claims.sub,
+            "iss": claims.iss,
+            "client_id": client_id,
+            "origin_jti": claims.origin_jti,
+            "event_id": claims.event_id,
+            "token_use": "access",
+            "scope": scope,
+            "auth_time": claims.auth_time,
+            "exp": claims.exp,
+            "iat": claims.iat,
+            "jti": claims.jti,
+            "username": "machine-client",
+        })
+    }
+
+    fn sign_test_value(claims: &serde_json::Value) -> String {
+        use jsonwebtoken::{encode, EncodingKey, Header};
+        encode(
+            &Header::new(Algorithm::HS256),
+            claims,
+            &EncodingKey::from_secret(TEST_JWT_SECRET.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_access_token_with_admin_scope_reaches_admin_route() {
+        let app = Router::new()
+            .route("/admin", get(admin_handler))
+            .layer(axum::middleware::from_fn(auth_middleware));
+
+        let token = sign_test_value(&access_token_claims(TEST_AUDIENCE, ADMIN_SCOPE));
+        let request = HttpRequest::builder()
+            .uri("/admin")
+            .header("authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+
+        let response = app.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"machine-client");
+    }
+
+    #[tokio::test]
+    async fn test_access_token_for_other_client_rejected() {
+        let token = sign_test_value(&access_token_claims("some-other-client", ADMIN_SCOPE));
+
+        let (status, code, _) = rejection_for_token(&token).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code, "AUTH_INVALID");
+    }
+
+    #[tokio::test]
+    async fn test_token_without_known_token_use_rejected() {
+        // An access token's client id doesn't count as an ID token's audience
+        let mut claims = access_token_claims(TEST_AUDIENCE, ADMIN_SCOPE);
+        claims["token_use"] = serde_json::json!("id");
+        let (status, code, _) = rejection_for_token(&sign_test_value(&claims)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code, "AUTH_INVALID");
+
+        for token_use in [Some("refresh"), None] {
+            let mut claims = test_claims("odd-token-user", &[]);
+            claims.token_use = token_use.map(str::to_string);
+            let (status, code, _) = rejection_for_token(&sign_test_token(&claims)).await;
+            assert_eq!(status, StatusCode::UNAUTHORIZED);
+            assert_eq!(code, "AUTH_INVALID");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_wrong_issuer() {
+        let mut claims = test_claims("other-pool-user", &[]);
+        claims.iss = "https://cognito-idp.eu-west-2.amazonaws.com/eu-west-2_other".to_string();
+
+        let (status, code, _) = rejection_for_token(&sign_test_token(&claims)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code, "AUTH_INVALID");
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_forged_signature() {
+        use jsonwebtoken::{encode, EncodingKey, Header};
+
+        // Only the key differs from a token the test mode accepts
+        let forged = encode(
+            &Header::new(Algorithm::HS256),
+            &test_claims("victim", &[ADMIN_GROUP]),
+            &EncodingKey::from_secret(b"not-the-test-secret"),
+        )
+        .unwrap();
+
+        let (status, code, _) = rejection_for_token(&forged).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(code, "AUTH_INVALID");
+    }
+
+    #[tokio::test]
+    async fn test_store_failures_are_service_unavailable() {
+        for err in [
+            StoreError::Transport("JWKS endpoint timed out".into()),
+            StoreError::Throttled("slow down".into()),
+            StoreError::InternalError("bad key set".into()),
+        ] {
+            let response = AuthRejection::from(err).into_response();
+            assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+            assert!(response
+                .headers()
+                .get(http::header::WWW_AUTHENTICATE)
+                .is_none());
+            let body = crate::test_utils::http_test_utils::response_to_json(response).await;
+            assert_eq!(body["code"], "AUTH_UNAVAILABLE");
+        }
+
+        // Problems with the token itself are still the client's to fix
+        let response = AuthRejection::from(StoreError::AuthError("Unknown signing key: k1".into()))
+            .into_response();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    async fn whoami_handler(
+        Extension(user_id): Extension<String>,
+        Extension(auth): Extension<AuthContext>,
+    ) -> String {
+        format!("{}|{}", user_id, auth.service.unwrap_or_default())
+    }
+
+    async fn whoami(token: &str, on_behalf_of: Option<&str>) -> (StatusCode, String) {
+        let app = Router::new()
+            .route("/", get(whoami_handler))
+            .layer(axum::middleware::from_fn(auth_middleware));
+
+        let mut request = HttpRequest::builder()
+            .uri("/")
+            .header("authorization", format!("Bearer {}", token));
+        if let Some(user_id) = on_behalf_of {
+            request = request.header(service::ON_BEHALF_OF_HEADER, user_id);
+        }
+        let response = app
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8_lossy(&body).to_string())
+    }
+
+    #[tokio::test]
+    async fn test_service_token_acts_on_behalf_of_user() {
+        std::env::set_var("SERVICE_AUTH_SECRET", TEST_SERVICE_SECRET);
+        let token = service::create_service_token("invitation-service", TEST_SERVICE_SECRET, 300);
+
+        let (status, body) = whoami(&token, Some("user-123")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "user-123|invitation-service");
+
+        // Without a user the service acts as itself
+        let (status, body) = whoami(&token, None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "service:invitation-service|invitation-service");
+    }
+
+    #[tokio::test]
+    async fn test_forged_service_token_rejected() {
+        std::env::set_var("SERVICE_AUTH_SECRET", TEST_SERVICE_SECRET);
+        let forged = service::create_service_token(
+            "invitation-service",
+            "an-attacker-guessed-this-secret-0000000",
+            300,
+        );
+
+        let (status, body) = whoami(&forged, Some("victim-user")).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(body.contains("AUTH_INVALID"));
+    }
+
+    #[tokio::test]
+    async fn test_user_token_cannot_act_on_behalf_of_others() {
+        let token = create_jwt_token("user-1");
+
+        let (status, body) = whoami(&token, Some("victim-user")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "user-1|");
+    }
+
+    #[test]
+    fn test_auth_config_from_cognito_env() {
+        temp_env::with_vars(
+            [
+                ("JWT_ISSUER", None),
+                ("JWT_AUDIENCE", None),
+                ("JWKS_URL", None),
+                ("COGNITO_USER_POOL_ID", Some("eu-west-2_pool")),
+                ("COGNITO_APP_CLIENT_ID", Some("client-id")),
+                ("AWS_REGION", Some("eu-west-2")),
+            ],
+            || {
+                let config = AuthConfig::from_env().unwrap();
+                assert_eq!(
+                    config.issuer,
+                    "https://cognito-idp.eu-west-2.amazonaws.com/eu-west-2_pool"
+                );
+                assert_eq!(config.audiences, vec!["client-id".to_string()]);
+                assert_eq!(
+                    config.jwks_url,
+                    "https://cognito-idp.eu-west-2.amazonaws.com/eu-west-2_pool/.well-known/jwks.json"
+                );
+            },
+        );
+    }
 }
@@ -0,0 +1,272 @@
+//! Server-side envelope encryption for shard fields stored in DynamoDB.
+//!
+//! Each value is sealed with AES-256-GCM under a fresh data key, and the data
+//! key itself is wrapped by KMS. The wrapped key travels with the ciphertext,
+//! so decrypting only needs KMS to unwrap it again. Sealed values are stored
+//! as strings with the [`ENVELOPE_PREFIX`] marker; anything without it is
+//! treated as legacy plaintext and passed through untouched.
+
+use async_trait::async_trait;
+use aws_sdk_kms::error::{DisplayErrorContext, SdkError};
+use aws_sdk_kms::primitives::Blob;
+use aws_sdk_kms::types::DataKeySpec;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use log::debug;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::{Result, StoreError};
+
+/// Marks a stored string as an envelope-encrypted value
+pub const ENVELOPE_PREFIX: &str = "kms:v1:";
+
+/// A data key from the key service: the plaintext to encrypt with and the
+/// wrapped form to store alongside the ciphertext
+pub struct DataKey {
+    pub plaintext: Vec<u8>,
+    pub wrapped: Vec<u8>,
+}
+
+/// Issues and unwraps 256-bit data keys. Implemented by [`KmsKeyService`] in
+/// production and by in-memory fakes in tests.
+#[async_trait]
+pub trait KeyService: Send + Sync {
+    async fn generate_data_key(&self) -> Result<DataKey>;
+
+    async fn decrypt_data_key(&self, wrapped: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Envelope encryption on top of a [`KeyService`]
+#[derive(Clone)]
+pub struct Envelope {
+    key_service: Arc<dyn KeyService>,
+}
+
+impl Envelope {
+    pub fn new(key_service: Arc<dyn KeyService>) -> Self {
+        Self { key_service }
+    }
+
+    /// Builds a KMS-backed envelope when `SHARD_KMS_KEY_ID` is set, otherwise
+    /// `None` so shard fields are stored as they are
+    pub fn from_env(config: &aws_config::SdkConfig) -> Option<Self> {
+        let key_id = std::env::var("SHARD_KMS_KEY_ID")
+            .ok()
+            .filter(|key_id| !key_id.is_empty())?;
+        debug!("Shard envelope encryption enabled with KMS key {}", key_id);
+        Some(Self::new(Arc::new(KmsKeyService::new(config, key_id))))
+    }
+
+    /// Encrypts `plaintext` as `len(wrapped key) || wrapped key || nonce || ciphertext`
+    /// under a fresh data key
+    pub async fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        self.record_sealer().encrypt(plaintext).await
+    }
+
+    /// Reverses [`Envelope::encrypt`]
+    pub async fn decrypt(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        self.record_opener().decrypt(sealed).await
+    }
+
+    /// Seals a string field for storage
+    pub async fn seal_string(&self, value: &str) -> Result<String> {
+        self.record_sealer().seal_string(value).await
+    }
+
+    /// Opens a stored string field; values without the envelope prefix were
+    /// written before encryption was enabled and are returned unchanged
+    pub async fn open_string(&self, value: &str) -> Result<String> {
+        self.record_opener().open_string(value).await
+    }
+
+    /// Seals the fields of one record under a single data key, so writing a
+    /// record costs one KMS call however many fields it has
+    pub fn record_sealer(&self) -> RecordSealer<'_> {
+        RecordSealer {
+            key_service: self.key_service.as_ref(),
+            data_key: None,
+        }
+    }
+
+    /// Opens the fields of one record, unwrapping each data key once
+    pub fn record_opener(&self) -> RecordOpener<'_> {
+        RecordOpener {
+            key_service: self.key_service.as_ref(),
+            data_keys: HashMap::new(),
+        }
+    }
+}
+
+/// Seals values with a data key generated on first use. See
+/// [`Envelope::record_sealer`].
+pub struct RecordSealer<'a> {
+    key_service: &'a dyn KeyService,
+    data_key: Option<DataKey>,
+}
+
+impl RecordSealer<'_> {
+    /// Encrypts `plaintext` as `len(wrapped key) || wrapped key || nonce || ciphertext`.
+    /// Every value gets its own nonce.
+    pub async fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let data_key = match &mut self.data_key {
+            Some(data_key) => data_key,
+            data_key => data_key.insert(self.key_service.generate_data_key().await?),
+        };
+        let key = aead_key(&data_key.plaintext)?;
+
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| StoreError::InternalError("Failed to generate nonce".into()))?;
+
+        let mut ciphertext = plaintext.to_vec();
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut ciphertext,
+        )
+        .map_err(|_| StoreError::InternalError("Failed to encrypt value".into()))?;
+
+        let wrapped_len = u16::try_from(data_key.wrapped.len())
+            .map_err(|_| StoreError::InternalError("Wrapped data key too long".into()))?;
+        let mut sealed =
+            Vec::with_capacity(2 + data_key.wrapped.len() + NONCE_LEN + ciphertext.len());
+        sealed.extend_from_slice(&wrapped_len.to_be_bytes());
+        sealed.extend_from_slice(&data_key.wrapped);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Seals a string field for storage
+    pub async fn seal_string(&mut self, value: &str) -> Result<String> {
+        let sealed = self.encrypt(value.as_bytes()).await?;
+        Ok(format!("{}{}", ENVELOPE_PREFIX, STANDARD.encode(sealed)))
+    }
+}
+
+/// Opens values, remembering the data keys it has unwrapped. See
+/// [`Envelope::record_opener`].
+pub struct RecordOpener<'a> {
+    key_service: &'a dyn KeyService,
+    data_keys: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+impl RecordOpener<'_> {
+    /// Reverses [`RecordSealer::encrypt`]
+    pub async fn decrypt(&mut self, sealed: &[u8]) -> Result<Vec<u8>> {
+        let malformed = || StoreError::InternalError("Malformed envelope".into());
+
+        let (len_bytes, rest) = sealed.split_at_checked(2).ok_or_else(malformed)?;
+        let wrapped_len = u16::from_be_bytes([len_bytes[0], len_bytes[1]]) as usize;
+        let (wrapped, rest) = rest.split_at_checked(wrapped_len).ok_or_else(malformed)?;
+        let (nonce, ciphertext) = rest.split_at_checked(NONCE_LEN).ok_or_else(malformed)?;
+
+        let data_key = match self.data_keys.get(wrapped) {
+            Some(data_key) => data_key,
+            None => {
+                let data_key = self.key_service.decrypt_data_key(wrapped).await?;
+                self.data_keys.entry(wrapped.to_vec()).or_insert(data_key)
+            }
+        };
+        let key = aead_key(data_key)?;
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| malformed())?;
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = key
+            .open_in_place(nonce, Aad::empty(), &mut in_out)
+            .map_err(|_| StoreError::InternalError("Failed to decrypt value".into()))?;
+        Ok(plaintext.to_vec())
+    }
+
+    /// Opens a stored string field; values without the envelope prefix were
+    /// written before encryption was enabled and are returned unchanged
+    pub async fn open_string(&mut self, value: &str) -> Result<String> {
+        let Some(encoded) = value.strip_prefix(ENVELOPE_PREFIX) else {
+            return Ok(value.to_string());
+        };
+        let sealed = STANDARD
+            .decode(encoded)
+            .map_err(|_| StoreError::InternalError("Malformed envelope".into()))?;
+        String::from_utf8(self.decrypt(&sealed).await?)
+            .map_err(|_| StoreError::InternalError("Decrypted value is not UTF-8".into()))
+    }
+}
+
+fn aead_key(key: &[u8]) -> Result<LessSafeKey> {
+    let key = UnboundKey::new(&AES_256_GCM, key)
+        .map_err(|_| StoreError::InternalError("Data key must be 256 bits".into()))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// [`KeyService`] backed by AWS KMS
+pub struct KmsKeyService {
+    client: aws_sdk_kms::Client,
+    key_id: String,
+}
+
+impl KmsKeyService {
+    pub fn new(config: &aws_config::SdkConfig, key_id: String) -> Self {
+        Self {
+            client: aws_sdk_kms::Client::new(config),
+            key_id,
+        }
+    }
+}
+
+/// Maps a failed KMS call. As with DynamoDB, calls that never got an answer
+/// are retryable transport errors.
+fn map_kms_error<E, R>(operation: &str, err: SdkError<E, R>) -> StoreError
+where
+    E: std::error::Error + 'static,
+    R: std::fmt::Debug,
+{
+    let message = format!("KMS {} failed: {}", operation, DisplayErrorContext(&err));
+    match &err {
+        SdkError::TimeoutError(_) => StoreError::Transport(message),
+        SdkError::DispatchFailure(failure) if failure.is_timeout() || failure.is_io() => {
+            StoreError::Transport(message)
+        }
+        _ => StoreError::InternalError(message),
+    }
+}
+
+#[async_trait]
+impl KeyService for KmsKeyService {
+    async fn generate_data_key(&self) -> Result<DataKey> {
+        let response = self
+            .client
+            .generate_data_key()
+            .key_id(&self.key_id)
+            .key_spec(DataKeySpec::Aes256)
+            .send()
+            .await
+            .map_err(|e| map_kms_error("GenerateDataKey", e))?;
+        let plaintext = response
+            .plaintext()
+            .ok_or_else(|| StoreError::InternalError("KMS returned no data key".into()))?;
+        let wrapped = response
+            .ciphertext_blob()
+            .ok_or_else(|| StoreError::InternalError("KMS returned no wrapped key".into()))?;
+        Ok(DataKey {
+            plaintext: plaintext.as_ref().to_vec(),
+            wrapped: wrapped.as_ref().to_vec(),
+        })
+    }
+
+    async fn decrypt_data_key(&self, wrapped: &[u8]) -> Result<Vec<u8>> {
+        let response = self
+            .client
+            .decrypt()
+            .key_id(&self.key_id)
+            .ciphertext_blob(Blob::new(wrapped))
+            .send()
+            .await
+            .map_err(|e| map_kms_error("Decrypt", e))?;
+        response
+            .plaintext()
+            .map(|plaintext| plaintext.as_ref().to_vec())
+            .ok_or_else(|| StoreError::InternalError("KMS returned no data key".into()))
+    }
+}
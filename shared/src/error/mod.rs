@@ -1,15 +1,30 @@
 // This file exists primarily to provide a Result type for trait interfaces
 // Each service should implement its own error handling
 
+use aws_sdk_dynamodb::error::{DisplayErrorContext, ProvideErrorMetadata, SdkError};
+use serde::Serialize;
+use utoipa::ToSchema;
+
 // Define a simple error type that services can map from
 #[derive(Debug)]
 pub enum StoreError {
     NotFound(String),
+    /// Any other backend failure
     InternalError(String),
     ValidationError(String),
     InvitationExpired,
     AuthError(String),
+    /// A conditional write lost to a concurrent update
     VersionConflict(String),
+    /// A create found an item with the same key already stored
+    AlreadyExists(String),
+    /// The backend is shedding load or briefly unavailable; the same call
+    /// may succeed if retried
+    Throttled(String),
+    /// The backend timed out or couldn't be reached; also worth retrying
+    Transport(String),
+    /// A stored item couldn't be converted to or from its model
+    Serialization(String),
 }
 
 impl std::fmt::Display for StoreError {
@@ -21,29 +36,149 @@ impl std::fmt::Display for StoreError {
             StoreError::InvitationExpired => write!(f, "Invitation expired"),
             StoreError::AuthError(msg) => write!(f, "Authentication error: {}", msg),
             StoreError::VersionConflict(msg) => write!(f, "Version conflict: {}", msg),
+            StoreError::AlreadyExists(msg) => write!(f, "Already exists: {}", msg),
+            StoreError::Throttled(msg) => write!(f, "Throttled: {}", msg),
+            StoreError::Transport(msg) => write!(f, "Transport error: {}", msg),
+            StoreError::Serialization(msg) => write!(f, "Serialization error: {}", msg),
         }
     }
 }
 
 impl std::error::Error for StoreError {}
 
+impl StoreError {
+    /// Whether the same call is worth retrying after a short wait
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, StoreError::Throttled(_) | StoreError::Transport(_))
+    }
+}
+
 // Define a result type for store interfaces
 pub type Result<T> = std::result::Result<T, StoreError>;
 
 // Useful conversions
 impl From<serde_dynamo::Error> for StoreError {
     fn from(err: serde_dynamo::Error) -> Self {
-        StoreError::InternalError(format!("DynamoDB serialization error: {}", err))
+        StoreError::Serialization(format!("DynamoDB serialization error: {}", err))
     }
 }
 
 impl From<serde_json::Error> for StoreError {
     fn from(err: serde_json::Error) -> Self {
-        StoreError::InternalError(format!("JSON serialization error: {}", err))
+        StoreError::Serialization(format!("JSON serialization error: {}", err))
     }
 }
 
+// DynamoDB error codes that mean the request was throttled rather than rejected
+const DYNAMO_THROTTLING_CODES: &[&str] = &[
+    "ProvisionedThroughputExceededException",
+    "ThrottlingException",
+    "RequestLimitExceeded",
+];
+
+// DynamoDB 5xx error codes for transient failures on the service side
+const DYNAMO_TRANSIENT_CODES: &[&str] = &["InternalServerError", "ServiceUnavailable"];
+
 // Basic error mapping functions that store implementations may use
-pub fn map_dynamo_error(operation: &str, err: impl std::fmt::Display) -> StoreError {
-    StoreError::InternalError(format!("DynamoDB {} error: {}", operation, err))
+pub fn map_dynamo_error<E>(operation: &str, err: E) -> StoreError
+where
+    E: ProvideErrorMetadata + std::fmt::Display,
+{
+    match err.code() {
+        Some(code) if DYNAMO_THROTTLING_CODES.contains(&code) => {
+            StoreError::Throttled(format!("DynamoDB {} throttled: {}", operation, code))
+        }
+        Some(code) if DYNAMO_TRANSIENT_CODES.contains(&code) => {
+            StoreError::Throttled(format!("DynamoDB {} unavailable: {}", operation, code))
+        }
+        Some("ConditionalCheckFailedException") => {
+            StoreError::VersionConflict(format!("DynamoDB {} condition failed", operation))
+        }
+        _ => StoreError::InternalError(format!("DynamoDB {} error: {}", operation, err)),
+    }
+}
+
+/// Maps a failed DynamoDB SDK call. Timeouts and connection failures never
+/// got an answer from DynamoDB, so they're reported as retryable transport
+/// errors; everything else is classified by its error code.
+pub fn map_sdk_error<E, R>(operation: &str, err: SdkError<E, R>) -> StoreError
+where
+    E: ProvideErrorMetadata + std::error::Error + 'static,
+    R: std::fmt::Debug,
+{
+    let transport_failure = match &err {
+        SdkError::TimeoutError(_) => true,
+        SdkError::DispatchFailure(failure) => failure.is_timeout() || failure.is_io(),
+        _ => false,
+    };
+    if transport_failure {
+        return StoreError::Transport(format!(
+            "DynamoDB {} failed: {}",
+            operation,
+            DisplayErrorContext(&err)
+        ));
+    }
+    map_dynamo_error(operation, err)
+}
+
+/// JSON body shared by all error responses: a human-readable `error` message
+/// plus a stable machine-readable `code`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorBody {
+    pub error: String,
+    pub code: String,
+}
+
+/// Builds an [`ErrorBody`]
+pub fn error_body(code: &str, message: &str) -> serde_json::Value {
+    serde_json::json!(ErrorBody {
+        error: message.to_string(),
+        code: code.to_string(),
+    })
+}
+
+/// Error code of requests rejected for one or more invalid fields
+pub const VALIDATION_CODE: &str = "VALIDATION";
+
+/// One problem with one field of a request
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, ToSchema)]
+pub struct FieldError {
+    /// The field as it's named in the request body, e.g. `shards[1].shardHash`
+    pub field: String,
+    /// Machine-readable kind of problem, e.g. `EMPTY` or `TOO_LONG`
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+/// An [`ErrorBody`] with code `VALIDATION` that also lists every invalid
+/// field, so a form can show all its problems at once
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ValidationErrorBody {
+    pub error: String,
+    pub code: String,
+    pub fields: Vec<FieldError>,
+}
+
+/// Builds a [`ValidationErrorBody`]; `error` joins the field messages
+pub fn validation_error_body(fields: &[FieldError]) -> serde_json::Value {
+    let error = fields
+        .iter()
+        .map(|field| field.message.as_str())
+        .collect::<Vec<_>>()
+        .join("; ");
+    serde_json::json!(ValidationErrorBody {
+        error,
+        code: VALIDATION_CODE.to_string(),
+        fields: fields.to_vec(),
+    })
 }
@@ -0,0 +1,69 @@
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use log::warn;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::error_body;
+
+/// Drop-in replacement for `axum::Json` whose rejection is a 400 in the
+/// services' error shape instead of axum's plain-text 400/415/422 responses.
+/// Responses serialize exactly like `axum::Json`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json<T>(pub T);
+
+/// Rejection produced when a request body can't be read as the expected JSON
+#[derive(Debug)]
+pub struct JsonBodyError {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl From<JsonRejection> for JsonBodyError {
+    fn from(rejection: JsonRejection) -> Self {
+        // axum's messages include the path to the offending field for data errors
+        let code = match &rejection {
+            JsonRejection::JsonSyntaxError(_) => "INVALID_JSON",
+            JsonRejection::MissingJsonContentType(_) => "INVALID_CONTENT_TYPE",
+            _ => "INVALID_BODY",
+        };
+        JsonBodyError {
+            code,
+            message: rejection.body_text(),
+        }
+    }
+}
+
+impl IntoResponse for JsonBodyError {
+    fn into_response(self) -> Response {
+        warn!("Rejected request body: {} ({})", self.message, self.code);
+        (
+            StatusCode::BAD_REQUEST,
+            axum::Json(error_body(self.code, &self.message)),
+        )
+            .into_response()
+    }
+}
+
+#[async_trait]
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = JsonBodyError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let axum::Json(value) = axum::Json::<T>::from_request(req, state).await?;
+        Ok(Json(value))
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
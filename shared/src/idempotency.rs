@@ -0,0 +1,89 @@
+//! Idempotency keys for create endpoints.
+//!
+//! Clients on flaky networks retry POSTs. When a request carries an
+//! `Idempotency-Key` header, the first request claims the key and records the
+//! resource it created; retries with the same key and body get that resource
+//! back instead of creating another one.
+
+use chrono::Utc;
+use http::HeaderMap;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::error::{Result, StoreError};
+use crate::models::IdempotencyRecord;
+use crate::store::IdempotencyStore;
+
+/// Header clients send their idempotency key in
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// How long a key is remembered after its first use
+pub const IDEMPOTENCY_KEY_TTL_SECS: i64 = 24 * 60 * 60;
+// Keys are client-generated, usually UUIDs; anything far longer is a mistake
+const MAX_KEY_LEN: usize = 255;
+
+/// Outcome of claiming an idempotency key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Claim {
+    /// First use of the key: create the resource, then complete the key with
+    /// its ID, or delete the key if creating it fails
+    New,
+    /// The same request already created this resource
+    Replay(String),
+    /// The key was first used with a different request body
+    Mismatch,
+    /// The request that claimed the key hasn't finished yet
+    InProgress,
+}
+
+/// Reads the idempotency key from the request headers, if one was sent
+pub fn idempotency_key(headers: &HeaderMap) -> Result<Option<String>> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    let key = value
+        .to_str()
+        .map(str::trim)
+        .map_err(|_| StoreError::ValidationError("Idempotency-Key must be ASCII".into()))?;
+    if key.is_empty() || key.len() > MAX_KEY_LEN {
+        return Err(StoreError::ValidationError(format!(
+            "Idempotency-Key must be between 1 and {} characters",
+            MAX_KEY_LEN
+        )));
+    }
+    Ok(Some(key.to_string()))
+}
+
+/// Scopes a client key to an operation and user, so keys from different
+/// users or endpoints never collide
+pub fn scoped_key(operation: &str, user_id: &str, key: &str) -> String {
+    format!("{}#{}#{}", operation, user_id, key)
+}
+
+/// Hex SHA-256 of the request body, to tell retries from key reuse
+pub fn request_hash<T: Serialize>(body: &T) -> Result<String> {
+    let bytes = serde_json::to_vec(body)?;
+    Ok(hex::encode(Sha256::digest(bytes)))
+}
+
+/// Claims `scoped_key` for a request with the given body hash
+pub async fn claim(
+    store: &dyn IdempotencyStore,
+    scoped_key: &str,
+    request_hash: &str,
+) -> Result<Claim> {
+    let record = IdempotencyRecord {
+        idempotency_key: scoped_key.to_string(),
+        request_hash: request_hash.to_string(),
+        resource_id: None,
+        expires_at: Utc::now().timestamp() + IDEMPOTENCY_KEY_TTL_SECS,
+    };
+
+    Ok(match store.insert_record(record).await? {
+        None => Claim::New,
+        Some(existing) if existing.request_hash != request_hash => Claim::Mismatch,
+        Some(existing) => match existing.resource_id {
+            Some(resource_id) => Claim::Replay(resource_id),
+            None => Claim::InProgress,
+        },
+    })
+}
@@ -1,8 +1,22 @@
 pub mod auth;
+pub mod clock;
+pub mod envelope;
 pub mod error;
+pub mod extract;
+pub mod idempotency;
+pub mod logging;
+pub mod maintenance;
 pub mod models;
+pub mod paging;
+pub mod poison;
 pub mod push;
+pub mod request_id;
+pub mod shutdown;
+pub mod storage;
 pub mod store;
+pub mod throttle;
+pub mod validation;
+pub mod webhook;
 
 #[cfg(test)]
 pub mod tests;
@@ -0,0 +1,61 @@
+//! Log setup shared by the service binaries.
+//!
+//! Logs are plain text by default. With `LOG_FORMAT=json` each record is
+//! written as one JSON object per line, which CloudWatch Logs Insights can
+//! query field by field. The level filter still comes from `RUST_LOG`.
+
+use chrono::{SecondsFormat, Utc};
+use log::Record;
+use serde_json::{Map, Value};
+use std::io::Write;
+
+/// Initializes the global logger, defaulting to the info level
+pub fn init() {
+    let mut builder =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"));
+    if json_enabled() {
+        builder.format(|buf, record| writeln!(buf, "{}", json_line(record)));
+    }
+    builder.init();
+}
+
+/// Whether `LOG_FORMAT` asks for JSON lines
+pub fn json_enabled() -> bool {
+    std::env::var("LOG_FORMAT").is_ok_and(|format| format.eq_ignore_ascii_case("json"))
+}
+
+/// A record as a JSON log line. Any `key=value` pairs in the message, like
+/// the `box_id=...` and `request_id=...` the services log, are also given as
+/// top-level fields.
+pub fn json_line(record: &Record) -> Value {
+    let message = record.args().to_string();
+    let mut line = message_fields(&message);
+    line.insert(
+        "timestamp".into(),
+        Utc::now()
+            .to_rfc3339_opts(SecondsFormat::Millis, true)
+            .into(),
+    );
+    line.insert("level".into(), record.level().as_str().into());
+    line.insert("target".into(), record.target().into());
+    line.insert("message".into(), message.into());
+    Value::Object(line)
+}
+
+// `key=value` pairs in a message. Values end at whitespace or a comma, and
+// the standard fields can't be overwritten by them.
+fn message_fields(message: &str) -> Map<String, Value> {
+    const RESERVED: [&str; 4] = ["timestamp", "level", "target", "message"];
+
+    message
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter_map(|word| word.split_once('='))
+        .filter(|(key, value)| {
+            !key.is_empty()
+                && !value.is_empty()
+                && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && !RESERVED.contains(key)
+        })
+        .map(|(key, value)| (key.to_string(), Value::String(value.to_string())))
+        .collect()
+}
@@ -0,0 +1,75 @@
+//! Read-only mode for maintenance windows, such as data migrations.
+//!
+//! While it's on, requests that could write are refused with 503 and the
+//! `MAINTENANCE` code, and reads carry on as usual. Routers turn it on by
+//! adding a [`ReadOnlyMode`] extension; without one, nothing is blocked. GET
+//! routes that write anyway are wrapped in [`refuse_when_read_only`].
+
+use axum::extract::Request;
+use axum::http::{Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use log::warn;
+
+use crate::error::error_body;
+
+/// Error code of requests refused during maintenance
+pub const MAINTENANCE_CODE: &str = "MAINTENANCE";
+
+/// What refused requests are told
+pub const MAINTENANCE_MESSAGE: &str = "The service is read-only for maintenance; try again later";
+
+/// Whether writes are currently refused
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReadOnlyMode(pub bool);
+
+impl ReadOnlyMode {
+    /// Read-only when `READ_ONLY=true`
+    pub fn from_env() -> Self {
+        Self(
+            std::env::var("READ_ONLY")
+                .map(|v| v.to_lowercase() == "true")
+                .unwrap_or(false),
+        )
+    }
+}
+
+fn is_read_only(req: &Request) -> bool {
+    req.extensions()
+        .get::<ReadOnlyMode>()
+        .is_some_and(|mode| mode.0)
+}
+
+fn maintenance_response(req: &Request) -> Response {
+    warn!(
+        "Refused {} {} during read-only maintenance",
+        req.method(),
+        req.uri()
+    );
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        axum::Json(error_body(MAINTENANCE_CODE, MAINTENANCE_MESSAGE)),
+    )
+        .into_response()
+}
+
+/// Refuses requests other than GET, HEAD and OPTIONS while the service is
+/// read-only
+pub async fn read_only_middleware(req: Request, next: Next) -> Response {
+    let is_read = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    if is_read_only(&req) && !is_read {
+        return maintenance_response(&req);
+    }
+
+    next.run(req).await
+}
+
+/// Refuses every request while the service is read-only, for routes that
+/// write even though they are reads by method
+pub async fn refuse_when_read_only(req: Request, next: Next) -> Response {
+    if is_read_only(&req) {
+        return maintenance_response(&req);
+    }
+
+    next.run(req).await
+}
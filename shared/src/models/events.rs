@@ -1,9 +1,62 @@
+//! Payloads published to SNS for other services to consume.
+//!
+//! Two naming conventions are in use, on purpose. HTTP request and response
+//! bodies are what clients see, and use camelCase (`#[serde(rename_all =
+//! "camelCase")]` on every DTO). Event payloads travel only between our own
+//! services and use snake_case, matching the Rust field names, so producers
+//! and consumers can share these types. Don't reuse a DTO as an event payload
+//! or the other way round.
+//!
+//! Every event carries a `schema_version`. Events published before the field
+//! existed don't have one and are read as version 1. Consumers skip events
+//! with a version newer than [`EVENT_SCHEMA_VERSION`] rather than guess at
+//! their meaning.
+//!
+//! Adding an optional field doesn't need a new version. A breaking change,
+//! such as renaming, removing or retyping a field, does:
+//!
+//! 1. Bump [`EVENT_SCHEMA_VERSION`] and list the change below.
+//! 2. Teach every consumer to read both the old and the new version, branching
+//!    on `schema_version`, and deploy the consumers.
+//! 3. Only then deploy the producers that publish the new version.
+//!
+//! Versions:
+//! - 1: the original payloads
+
 use serde::{Deserialize, Serialize};
 
+/// Schema version of the events this build publishes, and the newest it
+/// understands
+pub const EVENT_SCHEMA_VERSION: u32 = 1;
+
+fn default_schema_version() -> u32 {
+    1
+}
+
+/// The fields every event shares, enough to decide whether and how to
+/// handle it before reading the rest
+#[derive(Deserialize, Debug)]
+pub struct EventEnvelope {
+    pub event_type: String,
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u32,
+}
+
+impl EventEnvelope {
+    /// Whether this build knows how to read the event. Newer versions come
+    /// from producers deployed ahead of their consumers.
+    pub fn is_supported(&self) -> bool {
+        self.schema_version <= EVENT_SCHEMA_VERSION
+    }
+}
+
 /// Event for box invitations
 #[derive(Deserialize, Serialize, Debug, Clone)]
+#[serde(rename_all = "snake_case")]
 pub struct InvitationEvent {
     pub event_type: String,
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u32,
     pub invitation_id: String,
     pub box_id: String,
     pub user_id: Option<String>,
@@ -11,5 +64,52 @@ pub struct InvitationEvent {
     pub invited_name: Option<String>,
     #[serde(default)]
     pub is_lead_guardian: bool,
+    /// Who created the invitation. Missing from events published before it
+    /// was added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creator_id: Option<String>,
     pub timestamp: String,
 }
+
+/// Published by the box service when a box is locked, so the notification
+/// service can tell its guardians a shard is waiting
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub struct BoxLockedEvent {
+    pub event_type: String,
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u32,
+    pub box_id: String,
+    pub box_name: String,
+    pub owner_name: Option<String>,
+    pub guardian_ids: Vec<String>,
+    pub timestamp: String,
+    /// Correlation ID of the request that locked the box, if it was propagated
+    #[serde(default)]
+    pub request_id: Option<String>,
+}
+
+impl BoxLockedEvent {
+    /// `event_type` of every box_locked event
+    pub const EVENT_TYPE: &'static str = "box_locked";
+
+    pub fn new(
+        box_id: &str,
+        box_name: &str,
+        owner_name: Option<&str>,
+        guardian_ids: &[String],
+        timestamp: &str,
+        request_id: Option<&str>,
+    ) -> Self {
+        Self {
+            event_type: Self::EVENT_TYPE.to_string(),
+            schema_version: EVENT_SCHEMA_VERSION,
+            box_id: box_id.to_string(),
+            box_name: box_name.to_string(),
+            owner_name: owner_name.map(str::to_string),
+            guardian_ids: guardian_ids.to_vec(),
+            timestamp: timestamp.to_string(),
+            request_id: request_id.map(str::to_string),
+        }
+    }
+}
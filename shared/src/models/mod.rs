@@ -1,12 +1,15 @@
-use chrono::Utc;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chrono::{DateTime, Duration, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fmt;
 use std::str::FromStr;
+use utoipa::ToSchema;
 
 pub mod events;
 
 // Invitation statuses
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum InvitationStatus {
     Invited,
@@ -42,7 +45,7 @@ impl fmt::Display for InvitationStatus {
 }
 
 // Guardian statuses
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum GuardianStatus {
     Invited,
@@ -78,7 +81,7 @@ impl fmt::Display for GuardianStatus {
 }
 
 // Unlock request statuses
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum UnlockRequestStatus {
     Requested, // Initial state when request is created (was Invited)
@@ -115,172 +118,319 @@ impl fmt::Display for UnlockRequestStatus {
 }
 
 // Invitation-related models
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct Invitation {
     pub id: String,
-    #[serde(rename = "inviteCode")]
     pub invite_code: String, // Unique code for the deep link
-    #[serde(rename = "invitedName")]
     pub invited_name: String,
-    #[serde(rename = "boxId")]
     pub box_id: String, // Associated BoxRecord
-    #[serde(rename = "createdAt")]
     pub created_at: String,
-    #[serde(rename = "expiresAt")]
     pub expires_at: String, // 48-hour expiry time
     pub opened: bool,
-    #[serde(rename = "linkedUserId")]
     pub linked_user_id: Option<String>, // To be filled upon open
-    #[serde(rename = "creatorId")]
-    pub creator_id: String, // ID of the user who created the invitation
-    #[serde(rename = "isLeadGuardian", default)]
+    /// ID of the user who created the invitation
+    pub creator_id: String,
+    #[serde(default)]
     pub is_lead_guardian: bool, // Whether this guardian should be marked as lead
+    /// Name of the box when the invitation was created, shown to the invitee
+    /// before they accept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub box_name: Option<String>,
+    /// Name the box's owner goes by, as of when the invitation was created
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner_name: Option<String>,
+    /// Name the invitation's creator goes by, so the invitee sees who
+    /// invited them rather than an id
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creator_name: Option<String>,
+}
+
+// Alphabet for user-friendly invitation codes (uppercase letters only)
+const INVITE_CODE_ALPHABET: [char; 26] = [
+    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
+    'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
+];
+
+impl Invitation {
+    /// A new user-friendly code for the invitation's deep link (8 characters)
+    pub fn new_invite_code() -> String {
+        nanoid::nanoid!(8, &INVITE_CODE_ALPHABET)
+    }
 }
 
 // Box-related models
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct Document {
     pub id: String,
     pub title: String,
-    #[serde(rename = "encryptedContent", skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub encrypted_content: Option<String>,
-    #[serde(rename = "createdAt")]
     pub created_at: String,
+    /// MIME type of the plaintext content, e.g. `text/plain` or `image/png`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Size of the stored ciphertext in bytes: the length of
+    /// `encrypted_content` as sent, recorded by the server whenever the
+    /// content is held in the box. The plaintext size isn't known here.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_bytes: Option<u64>,
+    /// Bumped by the server on every write. Clients echo the version they
+    /// last read so a stale edit is rejected instead of overwriting a newer one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u64>,
+    /// Where the content lives when it was too large to keep in the box;
+    /// `encrypted_content` is empty for these documents
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub s3_key: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct Guardian {
     pub id: String, // user_id
     pub name: String,
-    #[serde(rename = "leadGuardian")]
     pub lead_guardian: bool,
     pub status: GuardianStatus,
-    #[serde(rename = "addedAt")]
     pub added_at: String,
-    #[serde(rename = "invitationId")]
     pub invitation_id: String,
-    #[serde(
-        rename = "lockDataReceivedAt",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
     pub lock_data_received_at: Option<String>,
-    #[serde(
-        rename = "encryptedShard",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
     pub encrypted_shard: Option<String>,
-    #[serde(rename = "shardHash", skip_serializing_if = "Option::is_none", default)]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
     pub shard_hash: Option<String>,
-    #[serde(
-        rename = "shardFetchedAt",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
     pub shard_fetched_at: Option<String>,
-    #[serde(
-        rename = "shardAcceptedAt",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
     pub shard_accepted_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub shard_commit: Option<String>,
+    /// When the encrypted shard was first served to the guardian, before they
+    /// acknowledged it; `shard_fetched_at` records the acknowledgement
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub shard_first_fetched_at: Option<String>,
+    /// When the owner last sent the guardian a reminder to accept their shard
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub last_reminded_at: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+impl Guardian {
+    /// When the guardian's shard was sent: when their lock data arrived, or
+    /// else when the box was locked
+    pub fn shard_sent_at(&self, locked_at: DateTime<Utc>) -> DateTime<Utc> {
+        self.lock_data_received_at
+            .as_deref()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+            .unwrap_or(locked_at)
+    }
+
+    /// Whether the guardian has accepted and was given a shard, whether it's
+    /// still stored or has been acknowledged (which clears the stored copy).
+    /// Only these guardians have a say in an unlock request.
+    pub fn holds_shard(&self) -> bool {
+        self.status == GuardianStatus::Accepted
+            && (self.encrypted_shard.is_some() || self.shard_fetched_at.is_some())
+    }
+
+    /// The shard hash scheme clients must follow: hex-encoded SHA-256 of the
+    /// base64-decoded (standard alphabet, padded) encrypted shard. Returns
+    /// `None` if the shard isn't valid base64.
+    pub fn expected_shard_hash(encrypted_shard: &str) -> Option<String> {
+        let ciphertext = STANDARD.decode(encrypted_shard).ok()?;
+        Some(hex::encode(Sha256::digest(&ciphertext)))
+    }
+
+    /// Server-side integrity commitment over the stored shard: hex-encoded
+    /// SHA-256 of `encrypted_shard || shard_hash || id`. Returns `None` when
+    /// the guardian holds no shard.
+    pub fn compute_shard_commit(&self) -> Option<String> {
+        let shard = self.encrypted_shard.as_ref()?;
+        let mut hasher = Sha256::new();
+        hasher.update(shard.as_bytes());
+        hasher.update(self.shard_hash.as_deref().unwrap_or_default().as_bytes());
+        hasher.update(self.id.as_bytes());
+        Some(hex::encode(hasher.finalize()))
+    }
+
+    /// Checks the stored shard against the commitment written at lock time.
+    /// Shards stored before commitments were introduced carry none and pass.
+    pub fn verify_shard_commit(&self) -> bool {
+        match &self.shard_commit {
+            Some(commit) => self.compute_shard_commit().as_ref() == Some(commit),
+            None => true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct UnlockRequest {
     pub id: String,
-    #[serde(rename = "requestedAt")]
     pub requested_at: String,
     pub status: UnlockRequestStatus,
     pub message: Option<String>,
-    #[serde(rename = "initiatedBy")]
+    /// Why the lead guardian asked to unlock the box, shown to the owner
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
     pub initiated_by: Option<String>,
-    #[serde(rename = "approvedBy")]
     pub approved_by: Vec<String>,
-    #[serde(rename = "rejectedBy")]
     pub rejected_by: Vec<String>,
+    /// Every guardian's answer, in the order they were given
+    #[serde(default)]
+    pub approvals: Vec<UnlockApproval>,
+}
+
+/// A guardian's answer to an unlock request, kept for the owner's audit trail
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UnlockApproval {
+    pub guardian_id: String,
+    pub approved: bool,
+    pub at: String,
+}
+
+impl UnlockRequest {
+    /// Records a guardian approving or rejecting the request. A guardian who
+    /// changes their answer is moved to the other list, so they're only ever
+    /// counted once. Returns false, changing nothing, if they already gave the
+    /// same answer.
+    pub fn record_response(&mut self, guardian_id: &str, approved: bool, at: &str) -> bool {
+        let (answered, other) = if approved {
+            (&mut self.approved_by, &mut self.rejected_by)
+        } else {
+            (&mut self.rejected_by, &mut self.approved_by)
+        };
+        if answered.iter().any(|id| id == guardian_id) {
+            return false;
+        }
+        other.retain(|id| id != guardian_id);
+        answered.push(guardian_id.to_string());
+        self.approvals.push(UnlockApproval {
+            guardian_id: guardian_id.to_string(),
+            approved,
+            at: at.to_string(),
+        });
+        true
+    }
+
+    /// Whether the guardian already answered the other way
+    pub fn answered_otherwise(&self, guardian_id: &str, approved: bool) -> bool {
+        let other = if approved {
+            &self.rejected_by
+        } else {
+            &self.approved_by
+        };
+        other.iter().any(|id| id == guardian_id)
+    }
+
+    /// Whether the request is still open and waiting on this guardian's
+    /// answer. The guardian who asked has nothing to answer.
+    pub fn awaits_answer_from(&self, guardian_id: &str) -> bool {
+        self.status == UnlockRequestStatus::Requested
+            && self.initiated_by.as_deref() != Some(guardian_id)
+            && !self.approved_by.iter().any(|id| id == guardian_id)
+            && !self.rejected_by.iter().any(|id| id == guardian_id)
+    }
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct BoxRecord {
     pub id: String,
     pub name: String,
     pub description: String,
-    #[serde(rename = "isLocked")]
     pub is_locked: bool,
-    #[serde(rename = "lockedAt", skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub locked_at: Option<String>,
-    #[serde(rename = "createdAt")]
     pub created_at: String,
-    #[serde(rename = "updatedAt")]
     pub updated_at: String,
-    #[serde(rename = "ownerId")]
     pub owner_id: String,
-    #[serde(rename = "ownerName")]
     pub owner_name: Option<String>,
     pub documents: Vec<Document>,
     pub guardians: Vec<Guardian>,
-    #[serde(rename = "unlockInstructions")]
     pub unlock_instructions: Option<String>,
-    #[serde(rename = "unlockRequest")]
     pub unlock_request: Option<UnlockRequest>,
     #[serde(default)]
     pub version: u64, // Version for optimistic concurrency control
-    #[serde(
-        rename = "shardThreshold",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
     pub shard_threshold: Option<u32>,
-    #[serde(
-        rename = "shardsFetched",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
     pub shards_fetched: Option<usize>,
-    #[serde(
-        rename = "totalShards",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
     pub total_shards: Option<usize>,
-    #[serde(
-        rename = "shardsDeletedAt",
-        skip_serializing_if = "Option::is_none",
-        default
-    )]
+    #[serde(skip_serializing_if = "Option::is_none", default)]
     pub shards_deleted_at: Option<String>,
+    /// Set once the box is locked and every guardian has accepted their shard,
+    /// so the reminder scan can skip it. Recomputed by the store on every write.
+    #[serde(default)]
+    pub all_shards_accepted: bool,
+    /// When the most recent unlock request was made, kept after the request
+    /// itself is cancelled so repeated requests can be rate limited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_unlock_request_at: Option<String>,
+    /// HTTPS URL the owner registered to be called when the box is unlocked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    /// Key the webhook payloads are signed with; only shown to the owner when
+    /// the webhook is registered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_secret: Option<String>,
+    /// Whether guardians must redeem a one-time challenge before fetching
+    /// their shard
+    #[serde(default)]
+    pub require_step_up: bool,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct GuardianBox {
-    pub id: String,
-    pub name: String,
-    pub description: String,
-    #[serde(rename = "isLocked")]
-    pub is_locked: bool,
-    #[serde(rename = "createdAt")]
-    pub created_at: String,
-    #[serde(rename = "updatedAt")]
-    pub updated_at: String,
-    #[serde(rename = "ownerId")]
-    pub owner_id: String,
-    #[serde(rename = "ownerName")]
-    pub owner_name: Option<String>,
-    #[serde(rename = "unlockInstructions")]
-    pub unlock_instructions: Option<String>,
-    #[serde(rename = "unlockRequest")]
-    pub unlock_request: Option<UnlockRequest>,
-    #[serde(rename = "pendingGuardianApproval")]
-    pub pending_guardian_approval: Option<bool>,
-    #[serde(rename = "guardiansCount")]
-    pub guardians_count: usize,
-    #[serde(rename = "isLeadGuardian")]
-    pub is_lead_guardian: bool,
-    // TODO we probably shouldn't be just returning them all for privacy reasons
-    pub documents: Vec<Document>,
-    pub guardians: Vec<Guardian>,
+impl BoxRecord {
+    /// When the box was locked, if it is locked and the time is readable
+    pub fn locked_at_time(&self) -> Option<DateTime<Utc>> {
+        self.locked_at
+            .as_deref()
+            .filter(|_| self.is_locked)
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|dt| dt.with_timezone(&Utc))
+    }
+
+    /// Guardians of a locked box who haven't accepted their shard yet
+    pub fn guardians_awaiting_acceptance(&self) -> impl Iterator<Item = &Guardian> {
+        self.guardians
+            .iter()
+            .filter(|g| self.is_locked && g.shard_accepted_at.is_none())
+    }
+
+    /// What a guardian of the box still has to do here: accept their shard,
+    /// and answer an open unlock request
+    pub fn pending_actions_for(&self, guardian_id: &str) -> usize {
+        let shard = self
+            .guardians_awaiting_acceptance()
+            .any(|g| g.id == guardian_id);
+        let unlock = self
+            .unlock_request
+            .as_ref()
+            .is_some_and(|request| request.awaits_answer_from(guardian_id));
+        usize::from(shard) + usize::from(unlock)
+    }
+
+    /// Recomputes `all_shards_accepted` from the guardian list. A box with no
+    /// guardians never qualifies, and a guardian added after locking clears
+    /// the flag until they accept too.
+    pub fn refresh_all_shards_accepted(&mut self) {
+        self.all_shards_accepted = self.is_locked
+            && !self.guardians.is_empty()
+            && self.guardians.iter().all(|g| g.shard_accepted_at.is_some());
+    }
+
+    /// Whether the box was locked or last updated at or after `cutoff`
+    pub fn locked_or_updated_since(&self, cutoff: DateTime<Utc>) -> bool {
+        [self.locked_at.as_deref(), Some(self.updated_at.as_str())]
+            .into_iter()
+            .flatten()
+            .filter_map(|s| DateTime::parse_from_rfc3339(s).ok())
+            .any(|at| at >= cutoff)
+    }
 }
 
 // Response DTOs for general use across services
@@ -289,12 +439,15 @@ pub struct ErrorResponse {
     pub error: String,
 }
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MessageResponse {
     pub message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub box_id: Option<String>,
+    /// Who created the invitation being answered, when they gave a name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub creator_name: Option<String>,
 }
 
 /// Push token record for storing Expo push tokens
@@ -309,6 +462,154 @@ pub struct PushToken {
     pub platform: String,
     /// When the token was registered/updated
     pub updated_at: String,
+    /// When the user last sent themselves a test notification
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_test_notification_at: Option<String>,
+    /// When Expo last accepted a push to this token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_verified_at: Option<String>,
+    /// Which notifications the user wants, and when
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notification_prefs: Option<NotificationPrefs>,
+    /// The device's offset from UTC in minutes when the token was last
+    /// registered, if the app sent it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub utc_offset_minutes: Option<i32>,
+}
+
+/// A user's notification preferences. Only reminders are affected; alerts a
+/// user has to act on, like unlock requests, are always sent.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationPrefs {
+    /// Whether shard reminders may be sent at all
+    #[serde(default = "default_reminders_enabled")]
+    pub reminders_enabled: bool,
+    /// A daily window in which reminders are held back until it ends
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_hours: Option<QuietHours>,
+}
+
+fn default_reminders_enabled() -> bool {
+    true
+}
+
+impl Default for NotificationPrefs {
+    fn default() -> Self {
+        Self {
+            reminders_enabled: default_reminders_enabled(),
+            quiet_hours: None,
+        }
+    }
+}
+
+impl NotificationPrefs {
+    /// Whether reminders are held back at `now`
+    pub fn is_quiet_at(&self, now: DateTime<Utc>) -> bool {
+        self.quiet_hours
+            .as_ref()
+            .is_some_and(|quiet| quiet.contains(now))
+    }
+}
+
+/// A daily window in the user's local time. It may run past midnight, e.g.
+/// from "22:00" to "07:00".
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+    /// Local start time, "HH:MM"
+    pub start: String,
+    /// Local end time, "HH:MM", exclusive
+    pub end: String,
+    /// The user's offset from UTC in minutes, e.g. 60 for UTC+1
+    pub utc_offset_minutes: i32,
+}
+
+impl QuietHours {
+    /// The start and end times, or None if either isn't "HH:MM"
+    pub fn bounds(&self) -> Option<(NaiveTime, NaiveTime)> {
+        let parse = |t: &str| NaiveTime::parse_from_str(t, "%H:%M").ok();
+        Some((parse(&self.start)?, parse(&self.end)?))
+    }
+
+    /// Whether `now` falls inside the window in the user's local time
+    pub fn contains(&self, now: DateTime<Utc>) -> bool {
+        let Some((start, end)) = self.bounds() else {
+            return false;
+        };
+        let local = (now + Duration::minutes(self.utc_offset_minutes.into())).time();
+        if start <= end {
+            start <= local && local < end
+        } else {
+            local >= start || local < end
+        }
+    }
+}
+
+/// Idempotency key claimed by a create request, and the resource it created
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct IdempotencyRecord {
+    /// Client key scoped to the operation and user (primary key)
+    pub idempotency_key: String,
+    /// Hash of the request body the key was first used with
+    pub request_hash: String,
+    /// ID of the created resource; unset while the first request is running
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resource_id: Option<String>,
+    /// Unix time after which the key may be reused (DynamoDB TTL attribute)
+    pub expires_at: i64,
+}
+
+/// A push that never reached its recipient, kept for triage
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PushFailure {
+    /// Unique ID (primary key)
+    pub id: String,
+    /// User the push was meant for
+    pub user_id: String,
+    /// Box the push was about, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub box_id: Option<String>,
+    /// Notification type, e.g. "shard_received"
+    #[serde(rename = "type")]
+    pub notification_type: String,
+    /// Why the push failed: Expo's error code, or the request error
+    pub error: String,
+    /// When the push failed
+    pub timestamp: String,
+}
+
+/// An event message a consumer couldn't parse, kept for inspection
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PoisonMessage {
+    /// SNS message ID (primary key), so redeliveries overwrite one record
+    pub message_id: String,
+    /// Consumer that gave up on the message, e.g. "notification-service"
+    pub source: String,
+    /// The message body exactly as delivered
+    pub body: String,
+    /// Why parsing failed
+    pub error: String,
+    /// When the message was received
+    pub received_at: String,
+}
+
+/// A one-time challenge a guardian must echo back to fetch their shard of a
+/// box that requires step-up
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ShardChallenge {
+    /// Random challenge value (primary key)
+    pub challenge: String,
+    /// Guardian the challenge was issued to
+    pub guardian_id: String,
+    /// Box whose shard it unlocks
+    pub box_id: String,
+    /// Unix time the challenge stops being accepted (DynamoDB TTL attribute)
+    pub expires_at: i64,
 }
 
 // Helper function to get current timestamp as string
@@ -328,6 +629,9 @@ mod tests {
             invite_code: "TESTCODE".to_string(),
             invited_name: "Test User".to_string(),
             is_lead_guardian: false,
+            box_name: None,
+            owner_name: None,
+            creator_name: None,
             box_id: "box-123".to_string(),
             created_at: "2024-01-01T00:00:00Z".to_string(),
             expires_at: "2024-01-02T00:00:00Z".to_string(),
@@ -0,0 +1,45 @@
+//! Pagination headers for list endpoints.
+//!
+//! List responses carry their `nextCursor` in the JSON body. Some clients use
+//! generic pagination libraries that read headers instead, so list handlers
+//! also send a `Link` header with `rel="next"` when there is another page,
+//! and `X-Total-Count` when the total is known without extra work.
+
+use axum::http::{header, HeaderMap, HeaderName, HeaderValue, Uri};
+
+/// Number of items across every page
+pub const X_TOTAL_COUNT: HeaderName = HeaderName::from_static("x-total-count");
+
+/// Headers describing a page served from `uri`, the request's full (not
+/// nested) URI. `next_cursor` becomes the `cursor` query parameter of the
+/// `rel="next"` link, replacing any cursor `uri` already had; cursors from
+/// the store are URL-safe, so it isn't escaped.
+pub fn pagination_headers(
+    uri: &Uri,
+    next_cursor: Option<&str>,
+    total_count: Option<usize>,
+) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+
+    if let Some(cursor) = next_cursor {
+        let mut params: Vec<&str> = uri
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter(|param| !param.is_empty() && param.split('=').next() != Some("cursor"))
+            .collect();
+        let cursor_param = format!("cursor={}", cursor);
+        params.push(&cursor_param);
+
+        let link = format!("<{}?{}>; rel=\"next\"", uri.path(), params.join("&"));
+        if let Ok(value) = HeaderValue::from_str(&link) {
+            headers.insert(header::LINK, value);
+        }
+    }
+
+    if let Some(total) = total_count {
+        headers.insert(X_TOTAL_COUNT, HeaderValue::from(total));
+    }
+
+    headers
+}
@@ -0,0 +1,62 @@
+//! Dead-lettering of event messages a consumer can't parse.
+//!
+//! SNS won't redeliver a message the Lambda accepted, so one that fails to
+//! parse would otherwise only survive as a log line. Consumers record it in
+//! the poison message store instead, with its SNS message ID and the body
+//! exactly as delivered, so it can be inspected and replayed once the cause
+//! is fixed.
+
+use log::{error, warn};
+use serde::de::DeserializeOwned;
+
+use crate::models::{now_str, PoisonMessage};
+use crate::store::PoisonMessageStore;
+
+/// Records a message that couldn't be parsed, returning whether the record
+/// was written. The message is already lost to the consumer, so errors
+/// writing the record are only logged.
+pub async fn record_poison_message(
+    store: &dyn PoisonMessageStore,
+    source: &str,
+    message_id: &str,
+    body: &str,
+    parse_error: &str,
+) -> bool {
+    error!(
+        "Dead-lettering unparseable message: message_id={}, source={}, error={}",
+        message_id, source, parse_error
+    );
+    let message = PoisonMessage {
+        message_id: message_id.to_string(),
+        source: source.to_string(),
+        body: body.to_string(),
+        error: parse_error.to_string(),
+        received_at: now_str(),
+    };
+    match store.record_poison_message(message).await {
+        Ok(_) => true,
+        Err(e) => {
+            warn!(
+                "Failed to record poison message: message_id={}, source={}, error={:?}",
+                message_id, source, e
+            );
+            false
+        }
+    }
+}
+
+/// Parses a message body, dead-lettering it when it doesn't parse as `T`
+pub async fn parse_or_dead_letter<T: DeserializeOwned>(
+    store: &dyn PoisonMessageStore,
+    source: &str,
+    message_id: &str,
+    body: &str,
+) -> Option<T> {
+    match serde_json::from_str::<T>(body) {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            record_poison_message(store, source, message_id, body, &e.to_string()).await;
+            None
+        }
+    }
+}
@@ -1,15 +1,70 @@
-use log::{error, info};
+use async_trait::async_trait;
+use log::{error, info, warn};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use utoipa::ToSchema;
 
-use crate::models::PushToken;
+use crate::error::Result as StoreResult;
+use crate::models::{now_str, PushFailure, PushToken};
+use crate::store::{BoxStore, PushFailureStore};
 
-const EXPO_PUSH_URL: &str = "https://exp.host/--/api/v2/push/send";
+/// Expo's push endpoint, used unless `EXPO_PUSH_URL` is set
+pub const DEFAULT_EXPO_PUSH_URL: &str = "https://exp.host/--/api/v2/push/send";
+
+// Expo normally answers within a second; a hung connection shouldn't hold the
+// Lambda until its own timeout
+const EXPO_CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+const EXPO_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Why a push request to Expo failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PushError {
+    /// Expo timed out or couldn't be reached
+    Transport(String),
+    /// Expo is rate limiting or having trouble (429 or 5xx)
+    Throttled(String),
+    /// Expo refused the request, or its answer couldn't be read
+    Failed(String),
+}
+
+impl std::fmt::Display for PushError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PushError::Transport(msg) => write!(f, "Transport error: {}", msg),
+            PushError::Throttled(msg) => write!(f, "Throttled: {}", msg),
+            PushError::Failed(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for PushError {}
+
+impl PushError {
+    /// Whether the same send is worth retrying after a short wait
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, PushError::Transport(_) | PushError::Throttled(_))
+    }
+
+    fn from_reqwest(context: &str, err: reqwest::Error) -> Self {
+        let message = format!("{}: {}", context, err);
+        if err.is_timeout() || err.is_connect() {
+            PushError::Transport(message)
+        } else {
+            PushError::Failed(message)
+        }
+    }
+}
 
 #[derive(Debug, Serialize)]
 pub struct ExpoPushMessage {
     pub to: String,
+    /// Left out when empty, as silent pushes have no title or body
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub title: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
     pub body: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub data: Option<serde_json::Value>,
@@ -27,13 +82,299 @@ pub struct ExpoPushResponse {
     pub data: Vec<ExpoPushTicket>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Expo's receipt for one message: `status` is "ok" or "error"
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ExpoPushTicket {
     pub status: String,
     #[serde(default)]
     pub id: Option<String>,
     #[serde(default)]
     pub message: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<ExpoPushTicketDetails>,
+}
+
+/// Why Expo refused a message
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ExpoPushTicketDetails {
+    /// Error code, e.g. "DeviceNotRegistered" for a token that is no longer
+    /// valid
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Ticket error Expo gives for a token that no longer reaches a device
+pub const DEVICE_NOT_REGISTERED: &str = "DeviceNotRegistered";
+
+impl ExpoPushTicket {
+    pub fn is_ok(&self) -> bool {
+        self.status == "ok"
+    }
+
+    /// The error code Expo gave, if the message was refused
+    pub fn error_code(&self) -> Option<&str> {
+        self.details.as_ref()?.error.as_deref()
+    }
+}
+
+/// Notification types, sent as the `type` field of a push's data
+pub const SHARD_RECEIVED: &str = "shard_received";
+pub const SHARD_REMINDER: &str = "shard_reminder";
+pub const SHARD_DIGEST: &str = "shard_digest";
+pub const GUARDIAN_DECLINED: &str = "guardian_declined";
+pub const PROGRESS_UPDATED: &str = "progress_updated";
+pub const UNLOCK_REQUESTED: &str = "unlock_requested";
+pub const UNLOCK_REQUEST_CANCELLED: &str = "unlock_request_cancelled";
+pub const UNLOCK_REQUEST_DENIED: &str = "unlock_request_denied";
+pub const BOX_UNLOCKED: &str = "box_unlocked";
+pub const TEST_NOTIFICATION: &str = "test";
+
+const DEFAULT_SOUND: &str = "default";
+
+/// How a visible push presents itself on the device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationOptions {
+    /// Sound to play, or `None` to deliver silently
+    pub sound: Option<String>,
+    /// App icon badge, usually the recipient's pending-action count. `None`
+    /// leaves the badge as it is.
+    pub badge: Option<u32>,
+}
+
+impl Default for NotificationOptions {
+    fn default() -> Self {
+        Self {
+            sound: Some(DEFAULT_SOUND.to_string()),
+            badge: Some(1),
+        }
+    }
+}
+
+impl NotificationOptions {
+    /// Options for a notification type, with the sound taken from
+    /// `PUSH_SOUND_<TYPE>` (e.g. `PUSH_SOUND_UNLOCK_REQUESTED=critical.wav`).
+    /// "none" makes the type silent; unset uses the default sound.
+    pub fn for_type(notification_type: &str, badge: Option<u32>) -> Self {
+        let var = format!("PUSH_SOUND_{}", notification_type.to_uppercase());
+        let sound = match std::env::var(var) {
+            Ok(sound) if sound.eq_ignore_ascii_case("none") => None,
+            Ok(sound) if !sound.trim().is_empty() => Some(sound.trim().to_string()),
+            _ => Some(DEFAULT_SOUND.to_string()),
+        };
+        Self { sound, badge }
+    }
+}
+
+/// How many things a user has to act on as a guardian: shards waiting for
+/// their acceptance, plus open unlock requests waiting for their answer.
+/// Used as the app badge on pushes to them.
+pub async fn pending_action_count<S>(store: &S, user_id: &str) -> StoreResult<u32>
+where
+    S: BoxStore + ?Sized,
+{
+    let count: usize = store
+        .get_boxes_by_guardian_id(user_id)
+        .await?
+        .iter()
+        .map(|box_rec| box_rec.pending_actions_for(user_id))
+        .sum();
+    Ok(u32::try_from(count).unwrap_or(u32::MAX))
+}
+
+/// The badge for a push to a user: their pending-action count, or 1 if it
+/// couldn't be read, so a push that needs acting on still shows on the icon
+pub async fn pending_action_badge<S>(store: &S, user_id: &str) -> u32
+where
+    S: BoxStore + ?Sized,
+{
+    pending_action_count(store, user_id)
+        .await
+        .unwrap_or_else(|e| {
+            warn!(
+                "Failed to count pending actions for badge, using 1: user_id={}, error={:?}",
+                user_id, e
+            );
+            1
+        })
+}
+
+/// [`pending_action_badge`] for each of several users
+pub async fn pending_action_badges<S>(store: &S, user_ids: &[String]) -> HashMap<String, u32>
+where
+    S: BoxStore + ?Sized,
+{
+    let mut badges = HashMap::new();
+    for user_id in user_ids {
+        badges.insert(user_id.clone(), pending_action_badge(store, user_id).await);
+    }
+    badges
+}
+
+/// Splits recipients into batches sharing a badge, in order of first
+/// appearance, so each batch can go out as one send. Users without a known
+/// badge get 1.
+pub fn group_by_badge(
+    tokens: &[PushToken],
+    badges: &HashMap<String, u32>,
+) -> Vec<(u32, Vec<PushToken>)> {
+    let mut groups: Vec<(u32, Vec<PushToken>)> = Vec::new();
+    for token in tokens {
+        let badge = badges.get(&token.user_id).copied().unwrap_or(1);
+        match groups.iter_mut().find(|(b, _)| *b == badge) {
+            Some((_, group)) => group.push(token.clone()),
+            None => groups.push((badge, vec![token.clone()])),
+        }
+    }
+    groups
+}
+
+/// Sends push notifications, so handlers can be tested without calling Expo
+#[async_trait]
+pub trait PushSender: Send + Sync {
+    async fn send_push_notifications(
+        &self,
+        tokens: &[PushToken],
+        title: &str,
+        body: &str,
+        data: Option<serde_json::Value>,
+        options: &NotificationOptions,
+    ) -> Result<Vec<ExpoPushTicket>, PushError>;
+
+    async fn send_silent_push_notifications(
+        &self,
+        tokens: &[PushToken],
+        data: Option<serde_json::Value>,
+    ) -> Result<Vec<ExpoPushTicket>, PushError>;
+}
+
+/// Push sender shared across handlers
+pub type SharedPushSender = Arc<dyn PushSender>;
+
+/// [`PushSender`] that calls the Expo push API, or an Expo-compatible
+/// endpoint standing in for it
+pub struct ExpoPushSender {
+    url: String,
+}
+
+impl ExpoPushSender {
+    /// Sends to [`expo_push_url`]
+    pub fn from_env() -> Self {
+        Self::with_url(expo_push_url())
+    }
+
+    /// Sends to `url` whatever `EXPO_PUSH_URL` says
+    pub fn with_url(url: impl Into<String>) -> Self {
+        Self { url: url.into() }
+    }
+}
+
+#[async_trait]
+impl PushSender for ExpoPushSender {
+    async fn send_push_notifications(
+        &self,
+        tokens: &[PushToken],
+        title: &str,
+        body: &str,
+        data: Option<serde_json::Value>,
+        options: &NotificationOptions,
+    ) -> Result<Vec<ExpoPushTicket>, PushError> {
+        if tokens.is_empty() {
+            info!("No push tokens provided, skipping push notification");
+            return Ok(Vec::new());
+        }
+
+        let messages = build_push_messages(tokens, title, body, data, options);
+        post_expo_messages(expo_client(), &self.url, &messages).await
+    }
+
+    async fn send_silent_push_notifications(
+        &self,
+        tokens: &[PushToken],
+        data: Option<serde_json::Value>,
+    ) -> Result<Vec<ExpoPushTicket>, PushError> {
+        if tokens.is_empty() {
+            info!("No push tokens provided, skipping silent push");
+            return Ok(Vec::new());
+        }
+
+        let messages: Vec<ExpoPushMessage> = tokens
+            .iter()
+            .map(|token| ExpoPushMessage {
+                to: token.push_token.clone(),
+                title: String::new(),
+                body: String::new(),
+                data: data.clone(),
+                sound: None,
+                badge: None,
+                content_available: Some(true),
+            })
+            .collect();
+
+        post_expo_messages(expo_client(), &self.url, &messages).await
+    }
+}
+
+/// Dead-letter records for the recipients a send didn't reach: all of them
+/// when the request itself failed, otherwise those whose ticket Expo refused.
+/// Expo answers with a ticket per message, in the order they were sent.
+pub fn push_failures(
+    tokens: &[PushToken],
+    result: &Result<Vec<ExpoPushTicket>, PushError>,
+    box_id: Option<&str>,
+    notification_type: &str,
+) -> Vec<PushFailure> {
+    let failed: Vec<(&PushToken, String)> = match result {
+        Err(e) => tokens.iter().map(|token| (token, e.to_string())).collect(),
+        Ok(tickets) => tokens
+            .iter()
+            .zip(tickets)
+            .filter(|(_, ticket)| !ticket.is_ok())
+            .map(|(token, ticket)| {
+                let error = ticket
+                    .error_code()
+                    .or(ticket.message.as_deref())
+                    .unwrap_or("Unknown error");
+                (token, error.to_string())
+            })
+            .collect(),
+    };
+
+    let timestamp = now_str();
+    failed
+        .into_iter()
+        .map(|(token, error)| PushFailure {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: token.user_id.clone(),
+            box_id: box_id.map(str::to_string),
+            notification_type: notification_type.to_string(),
+            error,
+            timestamp: timestamp.clone(),
+        })
+        .collect()
+}
+
+/// Records the recipients a send didn't reach in the dead-letter store,
+/// returning how many were recorded. The send has already failed, so errors
+/// writing a record are only logged.
+pub async fn record_push_failures(
+    store: &dyn PushFailureStore,
+    tokens: &[PushToken],
+    result: &Result<Vec<ExpoPushTicket>, PushError>,
+    box_id: Option<&str>,
+    notification_type: &str,
+) -> usize {
+    let mut recorded = 0;
+    for failure in push_failures(tokens, result, box_id, notification_type) {
+        let user_id = failure.user_id.clone();
+        match store.record_push_failure(failure).await {
+            Ok(_) => recorded += 1,
+            Err(e) => warn!(
+                "Failed to record push failure: user_id={}, type={}, error={:?}",
+                user_id, notification_type, e
+            ),
+        }
+    }
+    recorded
 }
 
 /// Sends push notifications to multiple tokens
@@ -42,39 +383,95 @@ pub async fn send_push_notifications(
     title: &str,
     body: &str,
     data: Option<serde_json::Value>,
-) -> Result<Vec<ExpoPushTicket>, String> {
-    if tokens.is_empty() {
-        info!("No push tokens provided, skipping push notification");
-        return Ok(Vec::new());
-    }
+    options: &NotificationOptions,
+) -> Result<Vec<ExpoPushTicket>, PushError> {
+    ExpoPushSender::from_env()
+        .send_push_notifications(tokens, title, body, data, options)
+        .await
+}
 
-    let messages: Vec<ExpoPushMessage> = tokens
+/// The Expo messages for a visible push, one per token
+pub fn build_push_messages(
+    tokens: &[PushToken],
+    title: &str,
+    body: &str,
+    data: Option<serde_json::Value>,
+    options: &NotificationOptions,
+) -> Vec<ExpoPushMessage> {
+    tokens
         .iter()
         .map(|token| ExpoPushMessage {
             to: token.push_token.clone(),
             title: title.to_string(),
             body: body.to_string(),
             data: data.clone(),
-            sound: Some("default".to_string()),
-            badge: Some(1),
+            sound: options.sound.clone(),
+            badge: options.badge,
             content_available: Some(true), // Enable background fetch on iOS
         })
-        .collect();
+        .collect()
+}
+
+/// Sends background pushes that wake the app without showing anything to the
+/// user
+pub async fn send_silent_push_notifications(
+    tokens: &[PushToken],
+    data: Option<serde_json::Value>,
+) -> Result<Vec<ExpoPushTicket>, PushError> {
+    ExpoPushSender::from_env()
+        .send_silent_push_notifications(tokens, data)
+        .await
+}
+
+/// HTTP client for Expo with connect and request timeouts, shared so
+/// connections are reused across sends
+pub fn expo_client() -> &'static Client {
+    static CLIENT: OnceLock<Client> = OnceLock::new();
+    CLIENT.get_or_init(|| build_expo_client(EXPO_REQUEST_TIMEOUT))
+}
+
+/// Builds a client for Expo that gives up on a request after `timeout`
+pub fn build_expo_client(timeout: Duration) -> Client {
+    Client::builder()
+        .connect_timeout(EXPO_CONNECT_TIMEOUT.min(timeout))
+        .timeout(timeout)
+        .build()
+        .unwrap_or_else(|e| {
+            error!("Failed to build Expo HTTP client, using defaults: {}", e);
+            Client::new()
+        })
+}
 
+/// The push endpoint to send to: `EXPO_PUSH_URL` when set, so tests and
+/// self-hosted Expo-compatible servers can stand in for Expo, otherwise
+/// [`DEFAULT_EXPO_PUSH_URL`]. Read whenever an [`ExpoPushSender`] is made
+/// with `from_env`.
+pub fn expo_push_url() -> String {
+    std::env::var("EXPO_PUSH_URL")
+        .ok()
+        .filter(|url| !url.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_EXPO_PUSH_URL.to_string())
+}
+
+/// Posts messages to an Expo-compatible push endpoint with `client`
+pub async fn post_expo_messages(
+    client: &Client,
+    url: &str,
+    messages: &[ExpoPushMessage],
+) -> Result<Vec<ExpoPushTicket>, PushError> {
     info!("Sending {} push notifications to Expo", messages.len());
 
-    let client = Client::new();
     let response = client
-        .post(EXPO_PUSH_URL)
+        .post(url)
         .header("Accept", "application/json")
         .header("Accept-Encoding", "gzip, deflate")
         .header("Content-Type", "application/json")
-        .json(&messages)
+        .json(messages)
         .send()
         .await
         .map_err(|e| {
             error!("Failed to send push notifications: {}", e);
-            format!("Failed to send push notifications: {}", e)
+            PushError::from_reqwest("Failed to send push notifications", e)
         })?;
 
     let status = response.status();
@@ -84,12 +481,19 @@ pub async fn send_push_notifications(
             "Expo push API returned error status {}: {}",
             status, error_text
         );
-        return Err(format!("Expo push API error: {} - {}", status, error_text));
+        let message = format!("Expo push API error: {} - {}", status, error_text);
+        return Err(
+            if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                PushError::Throttled(message)
+            } else {
+                PushError::Failed(message)
+            },
+        );
     }
 
     let push_response: ExpoPushResponse = response.json().await.map_err(|e| {
         error!("Failed to parse Expo push response: {}", e);
-        format!("Failed to parse push response: {}", e)
+        PushError::from_reqwest("Failed to parse push response", e)
     })?;
 
     info!(
@@ -115,7 +519,8 @@ pub async fn send_shard_notification(
     box_name: &str,
     owner_name: &str,
     box_id: &str,
-) -> Result<Vec<ExpoPushTicket>, String> {
+    options: &NotificationOptions,
+) -> Result<Vec<ExpoPushTicket>, PushError> {
     let title = "Action Required: Accept Key Shard";
     let body = format!(
         "{} has entrusted you with a key shard for \"{}\". Tap to accept and secure it.",
@@ -123,23 +528,23 @@ pub async fn send_shard_notification(
     );
 
     let data = serde_json::json!({
-        "type": "shard_received",
+        "type": SHARD_RECEIVED,
         "boxId": box_id,
         "boxName": box_name,
         "ownerName": owner_name
     });
 
-    send_push_notifications(tokens, title, &body, Some(data)).await
+    send_push_notifications(tokens, title, &body, Some(data), options).await
 }
 
-/// Sends a reminder notification for unaccepted shards
-pub async fn send_shard_reminder_notification(
-    tokens: &[PushToken],
+/// Title, body and data of a reminder to accept a shard. Later reminders are
+/// worded more urgently.
+pub fn shard_reminder_content(
     box_name: &str,
     owner_name: &str,
     box_id: &str,
     reminder_number: u32,
-) -> Result<Vec<ExpoPushTicket>, String> {
+) -> (&'static str, String, serde_json::Value) {
     let title = "Reminder: Accept Your Key Shard";
     let body = match reminder_number {
         1 => format!(
@@ -157,12 +562,169 @@ pub async fn send_shard_reminder_notification(
     };
 
     let data = serde_json::json!({
-        "type": "shard_reminder",
+        "type": SHARD_REMINDER,
         "boxId": box_id,
         "boxName": box_name,
         "ownerName": owner_name,
         "reminderNumber": reminder_number
     });
 
-    send_push_notifications(tokens, title, &body, Some(data)).await
+    (title, body, data)
+}
+
+/// Sends a reminder notification for unaccepted shards
+pub async fn send_shard_reminder_notification(
+    tokens: &[PushToken],
+    box_name: &str,
+    owner_name: &str,
+    box_id: &str,
+    reminder_number: u32,
+    options: &NotificationOptions,
+) -> Result<Vec<ExpoPushTicket>, PushError> {
+    let (title, body, data) = shard_reminder_content(box_name, owner_name, box_id, reminder_number);
+    send_push_notifications(tokens, title, &body, Some(data), options).await
+}
+
+/// Reminds a guardian of several unaccepted shards in one notification
+pub async fn send_shard_digest_notification(
+    tokens: &[PushToken],
+    count: usize,
+    box_names: &[String],
+    options: &NotificationOptions,
+) -> Result<Vec<ExpoPushTicket>, PushError> {
+    let title = "Reminder: Accept Your Key Shards";
+    let body = format!(
+        "You have {} pending key shards to accept. Tap to secure them.",
+        count
+    );
+
+    let data = serde_json::json!({
+        "type": SHARD_DIGEST,
+        "count": count,
+        "boxNames": box_names
+    });
+
+    send_push_notifications(tokens, title, &body, Some(data), options).await
+}
+
+/// Notifies a box owner that one of their guardians declined guardianship
+pub async fn send_guardian_declined_notification(
+    tokens: &[PushToken],
+    guardian_name: &str,
+    box_name: &str,
+    box_id: &str,
+    options: &NotificationOptions,
+) -> Result<Vec<ExpoPushTicket>, PushError> {
+    let title = "Guardian Declined";
+    let body = format!(
+        "{} declined to be a guardian for \"{}\". You may want to invite someone else.",
+        guardian_name, box_name
+    );
+
+    let data = serde_json::json!({
+        "type": GUARDIAN_DECLINED,
+        "boxId": box_id,
+        "boxName": box_name,
+        "guardianName": guardian_name
+    });
+
+    send_push_notifications(tokens, title, &body, Some(data), options).await
+}
+
+/// Alerts a box owner that a guardian has started an unlock request, quoting
+/// the guardian's reason when they gave one
+pub async fn send_unlock_requested_notification(
+    tokens: &[PushToken],
+    box_name: &str,
+    box_id: &str,
+    reason: Option<&str>,
+    options: &NotificationOptions,
+) -> Result<Vec<ExpoPushTicket>, PushError> {
+    let title = "Unlock Requested";
+    let body = match reason {
+        Some(reason) => format!(
+            "Someone requested to unlock \"{}\": \"{}\". If this wasn't expected, review it now.",
+            box_name, reason
+        ),
+        None => format!(
+            "Someone requested to unlock \"{}\". If this wasn't expected, review it now.",
+            box_name
+        ),
+    };
+
+    let data = serde_json::json!({
+        "type": UNLOCK_REQUESTED,
+        "boxId": box_id,
+        "boxName": box_name,
+        "reason": reason
+    });
+
+    send_push_notifications(tokens, title, &body, Some(data), options).await
+}
+
+/// Tells a box owner that guardians denied an unlock request, so it can no
+/// longer reach the threshold and was closed
+pub async fn send_unlock_denied_notification(
+    tokens: &[PushToken],
+    box_name: &str,
+    box_id: &str,
+    options: &NotificationOptions,
+) -> Result<Vec<ExpoPushTicket>, PushError> {
+    let title = "Unlock Request Denied";
+    let body = format!(
+        "Your guardians denied the request to unlock \"{}\". The box stays locked.",
+        box_name
+    );
+
+    let data = serde_json::json!({
+        "type": UNLOCK_REQUEST_DENIED,
+        "boxId": box_id,
+        "boxName": box_name
+    });
+
+    send_push_notifications(tokens, title, &body, Some(data), options).await
+}
+
+/// Tells guardians that the owner cancelled an unlock request they took part in
+pub async fn send_unlock_cancelled_notification(
+    tokens: &[PushToken],
+    box_name: &str,
+    box_id: &str,
+    options: &NotificationOptions,
+) -> Result<Vec<ExpoPushTicket>, PushError> {
+    let title = "Unlock Request Cancelled";
+    let body = format!(
+        "The owner of \"{}\" cancelled the unlock request. No further action is needed.",
+        box_name
+    );
+
+    let data = serde_json::json!({
+        "type": UNLOCK_REQUEST_CANCELLED,
+        "boxId": box_id,
+        "boxName": box_name
+    });
+
+    send_push_notifications(tokens, title, &body, Some(data), options).await
+}
+
+/// Tells a box owner that enough guardians approved an unlock request
+pub async fn send_box_unlocked_notification(
+    tokens: &[PushToken],
+    box_name: &str,
+    box_id: &str,
+    options: &NotificationOptions,
+) -> Result<Vec<ExpoPushTicket>, PushError> {
+    let title = "Box Unlocked";
+    let body = format!(
+        "Your box \"{}\" has been unlocked by your guardians.",
+        box_name
+    );
+
+    let data = serde_json::json!({
+        "type": BOX_UNLOCKED,
+        "boxId": box_id,
+        "boxName": box_name
+    });
+
+    send_push_notifications(tokens, title, &body, Some(data), options).await
 }
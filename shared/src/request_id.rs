@@ -0,0 +1,73 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request},
+    http::{request::Parts, HeaderMap, HeaderValue},
+};
+use std::convert::Infallible;
+use std::fmt;
+use uuid::Uuid;
+
+/// Header used to carry the correlation ID between clients, services and events
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Correlation ID for a single request. Read from `X-Request-Id` when the
+/// caller supplies one, otherwise generated, and stored as a request extension
+/// so handlers can log it and forward it into downstream events.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// Reads the ID from the request headers, generating a new one if absent or empty
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        headers
+            .get(REQUEST_ID_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| RequestId(v.to_string()))
+            .unwrap_or_else(RequestId::generate)
+    }
+
+    pub fn generate() -> Self {
+        RequestId(Uuid::new_v4().to_string())
+    }
+
+    /// Resolves the ID for an incoming request and stores it in its extensions
+    pub fn assign(req: &mut Request) -> Self {
+        let request_id = Self::from_headers(req.headers());
+        req.extensions_mut().insert(request_id.clone());
+        request_id
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn header_value(&self) -> Option<HeaderValue> {
+        HeaderValue::from_str(&self.0).ok()
+    }
+}
+
+impl fmt::Display for RequestId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestId
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        // The logging middleware normally assigns the ID; fall back to the
+        // headers so the extractor also works on routers without it
+        Ok(parts
+            .extensions
+            .get::<RequestId>()
+            .cloned()
+            .unwrap_or_else(|| Self::from_headers(&parts.headers)))
+    }
+}
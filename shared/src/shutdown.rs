@@ -0,0 +1,106 @@
+//! Graceful shutdown for services run as long-lived servers rather than on
+//! Lambda.
+//!
+//! On SIGTERM or Ctrl+C the server stops accepting connections and waits for
+//! requests already in flight, so a box update isn't cut off between its read
+//! and its write. Once the drain timeout runs out, serving returns anyway and
+//! requests still running are dropped when the process exits.
+
+use axum::Router;
+use log::{info, warn};
+use std::future::{Future, IntoFuture};
+use std::io;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+/// Drain timeout used when `SHUTDOWN_TIMEOUT_SECS` is unset or invalid
+pub const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long in-flight requests get to finish, from `SHUTDOWN_TIMEOUT_SECS`
+pub fn drain_timeout() -> Duration {
+    std::env::var("SHUTDOWN_TIMEOUT_SECS")
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_DRAIN_TIMEOUT)
+}
+
+/// Completes when the process is asked to stop, by SIGTERM (as sent by
+/// container runtimes) or Ctrl+C
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Serves `app` until `signal` completes, then stops accepting connections
+/// and gives in-flight requests up to `drain_timeout` to finish
+pub async fn serve_with_graceful_shutdown<F>(
+    listener: TcpListener,
+    app: Router,
+    signal: F,
+    drain_timeout: Duration,
+) -> io::Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let (draining_tx, draining_rx) = oneshot::channel();
+    let signal = async move {
+        signal.await;
+        info!("Shutting down, draining in-flight requests");
+        let _ = draining_tx.send(());
+    };
+    let mut server = tokio::spawn(
+        axum::serve(listener, app.into_make_service())
+            .with_graceful_shutdown(signal)
+            .into_future(),
+    );
+
+    tokio::select! {
+        result = &mut server => return server_result(result),
+        _ = draining_rx => {}
+    }
+
+    match tokio::time::timeout(drain_timeout, &mut server).await {
+        Ok(result) => {
+            info!("In-flight requests drained");
+            server_result(result)
+        }
+        Err(_) => {
+            warn!(
+                "Requests still running after {:?}, giving up on them",
+                drain_timeout
+            );
+            server.abort();
+            Ok(())
+        }
+    }
+}
+
+fn server_result(result: Result<io::Result<()>, tokio::task::JoinError>) -> io::Result<()> {
+    result.map_err(io::Error::other)?
+}
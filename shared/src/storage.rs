@@ -0,0 +1,111 @@
+//! Object storage for document content too large to keep inline in a box.
+//!
+//! Boxes live in single DynamoDB items, so large documents are uploaded
+//! here and the box only keeps a pointer to them (see `Document::s3_key`).
+
+use async_trait::async_trait;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+use log::debug;
+use std::env;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::error::{Result, StoreError};
+
+/// Stores document content by key
+#[async_trait]
+pub trait DocumentStorage: Send + Sync {
+    async fn put_document(&self, key: &str, content: Vec<u8>, content_type: &str) -> Result<()>;
+
+    async fn get_document(&self, key: &str) -> Result<Vec<u8>>;
+
+    async fn delete_document(&self, key: &str) -> Result<()>;
+}
+
+/// Document storage shared across handlers
+pub type SharedDocumentStorage = Arc<dyn DocumentStorage>;
+
+/// Key for one write of a document's content. Each write gets a fresh key, so
+/// the content a box points at is never overwritten before the box itself is
+/// updated; the replaced object is deleted once it no longer is.
+pub fn document_key(box_id: &str, document_id: &str) -> String {
+    format!(
+        "boxes/{}/documents/{}/{}",
+        box_id,
+        document_id,
+        Uuid::new_v4()
+    )
+}
+
+/// [`DocumentStorage`] backed by an S3 bucket
+pub struct S3DocumentStorage {
+    client: Client,
+    bucket: String,
+}
+
+impl S3DocumentStorage {
+    pub fn new(client: Client, bucket: String) -> Self {
+        Self { client, bucket }
+    }
+
+    /// Builds S3 storage for the bucket in `DOCUMENT_BUCKET`, or `None` when
+    /// it isn't set so documents stay inline
+    pub async fn from_env() -> Option<Self> {
+        let bucket = env::var("DOCUMENT_BUCKET")
+            .ok()
+            .filter(|bucket| !bucket.is_empty())?;
+        debug!("Offloading large documents to S3 bucket {}", bucket);
+        let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        Some(Self::new(Client::new(&config), bucket))
+    }
+}
+
+#[async_trait]
+impl DocumentStorage for S3DocumentStorage {
+    async fn put_document(&self, key: &str, content: Vec<u8>, content_type: &str) -> Result<()> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .body(ByteStream::from(content))
+            .send()
+            .await
+            .map_err(|e| StoreError::InternalError(format!("S3 put_object error: {}", e)))?;
+        Ok(())
+    }
+
+    async fn get_document(&self, key: &str) -> Result<Vec<u8>> {
+        let response = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| match e.as_service_error() {
+                Some(err) if err.is_no_such_key() => {
+                    StoreError::NotFound(format!("Document content not found: {}", key))
+                }
+                _ => StoreError::InternalError(format!("S3 get_object error: {}", e)),
+            })?;
+        let body = response
+            .body
+            .collect()
+            .await
+            .map_err(|e| StoreError::InternalError(format!("S3 read error: {}", e)))?;
+        Ok(body.into_bytes().to_vec())
+    }
+
+    async fn delete_document(&self, key: &str) -> Result<()> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| StoreError::InternalError(format!("S3 delete_object error: {}", e)))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use aws_sdk_dynamodb::primitives::Blob;
+use aws_sdk_dynamodb::types::AttributeValue;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_dynamo::to_attribute_value;
+
+use crate::error::{Result, StoreError};
+use crate::models::Document;
+
+/// Serialized size above which a box's documents are gzipped before writing,
+/// leaving headroom under DynamoDB's 400KB item limit
+pub const DOCUMENTS_COMPRESSION_THRESHOLD_BYTES: usize = 64 * 1024;
+
+const DOCUMENTS_ATTRIBUTE: &str = "documents";
+/// Set on items whose `documents` attribute holds gzipped JSON
+const COMPRESSED_MARKER_ATTRIBUTE: &str = "documentsCompressed";
+
+/// Replaces the `documents` attribute with a gzipped JSON blob when the
+/// documents are larger than the threshold
+pub(crate) fn compress_documents(
+    item: &mut HashMap<String, AttributeValue>,
+    documents: &[Document],
+) -> Result<()> {
+    let json = serde_json::to_vec(documents)?;
+    if json.len() <= DOCUMENTS_COMPRESSION_THRESHOLD_BYTES {
+        return Ok(());
+    }
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&json)
+        .and_then(|_| encoder.finish())
+        .map(|compressed| {
+            item.insert(
+                DOCUMENTS_ATTRIBUTE.to_string(),
+                AttributeValue::B(Blob::new(compressed)),
+            );
+            item.insert(
+                COMPRESSED_MARKER_ATTRIBUTE.to_string(),
+                AttributeValue::Bool(true),
+            );
+        })
+        .map_err(|e| StoreError::Serialization(format!("Failed to compress documents: {}", e)))
+}
+
+/// Restores a compressed `documents` attribute to the plain list form.
+/// Items without the marker, including every item written before
+/// compression existed, are returned unchanged.
+pub(crate) fn expand_documents(
+    mut item: HashMap<String, AttributeValue>,
+) -> Result<HashMap<String, AttributeValue>> {
+    if !matches!(
+        item.remove(COMPRESSED_MARKER_ATTRIBUTE),
+        Some(AttributeValue::Bool(true))
+    ) {
+        return Ok(item);
+    }
+
+    let Some(AttributeValue::B(blob)) = item.get(DOCUMENTS_ATTRIBUTE) else {
+        return Err(StoreError::Serialization(
+            "Compressed documents attribute is not binary".to_string(),
+        ));
+    };
+
+    let mut json = Vec::new();
+    GzDecoder::new(blob.as_ref())
+        .read_to_end(&mut json)
+        .map_err(|e| StoreError::Serialization(format!("Failed to decompress documents: {}", e)))?;
+    let documents: Vec<Document> = serde_json::from_slice(&json)?;
+    item.insert(
+        DOCUMENTS_ATTRIBUTE.to_string(),
+        to_attribute_value(documents)?,
+    );
+    Ok(item)
+}
@@ -1,19 +1,31 @@
 use async_trait::async_trait;
-use aws_config::BehaviorVersion;
+use aws_config::timeout::TimeoutConfig;
+use aws_config::{BehaviorVersion, SdkConfig};
 use aws_sdk_dynamodb::error::SdkError;
 use aws_sdk_dynamodb::operation::delete_item::DeleteItemError;
 use aws_sdk_dynamodb::operation::get_item::GetItemError;
 use aws_sdk_dynamodb::operation::query::QueryError;
 use aws_sdk_dynamodb::operation::scan::ScanError;
-use aws_sdk_dynamodb::types::AttributeValue;
+use aws_sdk_dynamodb::types::{
+    AttributeValue, KeysAndAttributes, ReturnConsumedCapacity, ReturnValue, Select,
+};
 use aws_sdk_dynamodb::Client;
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde_dynamo::{from_item, to_item};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
-
-use crate::error::{map_dynamo_error, Result, StoreError};
-use crate::models::{now_str, BoxRecord, Invitation, PushToken};
+use std::sync::Mutex;
+
+use crate::envelope::Envelope;
+use crate::error::{map_sdk_error, Result, StoreError};
+use crate::models::{
+    now_str, BoxRecord, IdempotencyRecord, Invitation, PoisonMessage, PushFailure, PushToken,
+    ShardChallenge,
+};
+use crate::store::compression::{compress_documents, expand_documents};
+use crate::store::pagination::{decode_cursor, encode_cursor, CursorScope, Page};
+use crate::store::retry::retry_with_backoff;
+use crate::store::watch::notify_box_changed;
 
 // Invitation Store Constants
 const TABLE_NAME: &str = "invitation-table";
@@ -24,10 +36,50 @@ const GSI_CREATOR_ID: &str = "creatorId-index";
 // Box Store Constants
 const BOX_TABLE_NAME: &str = "box-table";
 const GSI_OWNER_ID: &str = "owner_id-index";
+const GUARDIAN_BOX_TABLE_NAME: &str = "guardian-box-table";
+// BatchGetItem accepts at most 100 keys per request
+const BATCH_GET_MAX_KEYS: usize = 100;
+// Boxes written before allShardsAccepted existed lack the attribute and are kept
+const NOT_ALL_ACCEPTED_FILTER: &str =
+    "(attribute_not_exists(#all_accepted) OR #all_accepted <> :true)";
 
 // Push Token Store Constants
 const PUSH_TOKEN_TABLE_NAME: &str = "push-tokens-table";
 
+// Push Failure Store Constants
+const PUSH_FAILURE_TABLE_NAME: &str = "push-failures-table";
+const GSI_FAILURE_BOX_ID: &str = "boxId-index";
+
+// Poison Message Store Constants
+const POISON_MESSAGE_TABLE_NAME: &str = "poison-messages-table";
+
+// Shard Challenge Store Constants
+const SHARD_CHALLENGE_TABLE_NAME: &str = "shard-challenges-table";
+
+// Notification Throttle Store Constants
+const NOTIFICATION_THROTTLE_TABLE_NAME: &str = "notification-throttle-table";
+
+// A hung connection fails fast instead of holding the Lambda until its own
+// timeout; the resulting Transport errors are retried like throttling
+const DYNAMO_CONNECT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+const DYNAMO_READ_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+const DYNAMO_ATTEMPT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// AWS config for the DynamoDB stores, with connect, read and per-attempt
+/// timeouts on every call
+pub async fn dynamo_config() -> SdkConfig {
+    aws_config::defaults(BehaviorVersion::latest())
+        .timeout_config(
+            TimeoutConfig::builder()
+                .connect_timeout(DYNAMO_CONNECT_TIMEOUT)
+                .read_timeout(DYNAMO_READ_TIMEOUT)
+                .operation_attempt_timeout(DYNAMO_ATTEMPT_TIMEOUT)
+                .build(),
+        )
+        .load()
+        .await
+}
+
 // DynamoInvitationStore
 
 pub struct DynamoInvitationStore {
@@ -37,8 +89,8 @@ pub struct DynamoInvitationStore {
 
 impl DynamoInvitationStore {
     pub async fn new() -> Self {
-        // Use the recommended defaults() function with latest behavior version
-        let config = aws_config::defaults(BehaviorVersion::latest()).load().await;
+        // Latest behavior version, with request timeouts set
+        let config = dynamo_config().await;
 
         let client = Client::new(&config);
 
@@ -56,6 +108,39 @@ impl DynamoInvitationStore {
         Self { client, table_name }
     }
 
+    /// Reads an invitation item, optionally with a strongly consistent read
+    async fn read_invitation(&self, id: &str, consistent_read: bool) -> Result<Invitation> {
+        let key = HashMap::from([("id".to_string(), AttributeValue::S(id.to_string()))]);
+
+        let request = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .set_key(Some(key))
+            .consistent_read(consistent_read);
+        let result = retry_with_backoff("get_item", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(|e| map_sdk_error("get_item", e))
+        })
+        .await?;
+
+        let item = result
+            .item()
+            .ok_or_else(|| StoreError::NotFound(format!("Invitation with id {} not found", id)))?;
+
+        let invitation: Invitation = from_item(item.clone())?;
+
+        // Check if the invitation has expired
+        if self.is_expired(&invitation.expires_at)? {
+            return Err(StoreError::InvitationExpired);
+        }
+
+        Ok(invitation)
+    }
+
     /// Helper method to check if an invitation has expired
     fn is_expired(&self, expires_at_str: &str) -> Result<bool> {
         let expires_at = chrono::DateTime::parse_from_rfc3339(expires_at_str)
@@ -72,68 +157,468 @@ impl DynamoInvitationStore {
 pub struct DynamoBoxStore {
     client: Client,
     table_name: String,
+    /// Lookup table with one row per (guardianId, boxId) pair, since guardians
+    /// are nested in the box item and can't be indexed directly
+    guardian_box_table_name: String,
+    /// Wraps shard fields with a KMS data key when `SHARD_KMS_KEY_ID` is set
+    shard_envelope: Option<Envelope>,
 }
 
 impl DynamoBoxStore {
     /// Creates a new DynamoDB store
     pub async fn new() -> Self {
-        // Use the recommended defaults() function with latest behavior version
-        let config = aws_config::defaults(BehaviorVersion::latest()).load().await;
+        // Latest behavior version, with request timeouts set
+        let config = dynamo_config().await;
 
         let client = Client::new(&config);
 
-        // Use environment variable for table name if available
-        let table_name = env::var("DYNAMODB_TABLE").unwrap_or_else(|_| BOX_TABLE_NAME.to_string());
-
-        Self { client, table_name }
+        // Use environment variables for table names if available. BOX_TABLE_NAME
+        // takes precedence; DYNAMODB_TABLE is what the SAM template sets.
+        let table_name = env::var("BOX_TABLE_NAME")
+            .or_else(|_| env::var("DYNAMODB_TABLE"))
+            .unwrap_or_else(|_| BOX_TABLE_NAME.to_string());
+        let guardian_box_table_name = env::var("DYNAMODB_GUARDIAN_BOX_TABLE")
+            .unwrap_or_else(|_| GUARDIAN_BOX_TABLE_NAME.to_string());
+        let shard_envelope = Envelope::from_env(&config);
+
+        Self {
+            client,
+            table_name,
+            guardian_box_table_name,
+            shard_envelope,
+        }
     }
 
     /// Creates a new DynamoDB store with the specified client and table name.
+    /// The guardian-box lookup table is expected at `<table_name>-guardian-box`.
     /// This is mainly useful for testing with a local DynamoDB instance.
     #[allow(dead_code)]
     pub fn with_client_and_table(client: Client, table_name: String) -> Self {
-        Self { client, table_name }
+        let guardian_box_table_name = Self::guardian_box_table_for(&table_name);
+        Self {
+            client,
+            table_name,
+            guardian_box_table_name,
+            shard_envelope: None,
+        }
     }
-}
 
-#[async_trait]
-impl super::BoxStore for DynamoBoxStore {
-    /// Creates a new box record in DynamoDB
-    async fn create_box(&self, box_record: BoxRecord) -> Result<BoxRecord> {
-        let item = to_item(&box_record)?;
+    /// Encrypts shard fields at rest with the given envelope
+    pub fn with_shard_envelope(mut self, envelope: Envelope) -> Self {
+        self.shard_envelope = Some(envelope);
+        self
+    }
 
-        self.client
-            .put_item()
-            .table_name(&self.table_name)
-            .set_item(Some(item))
-            .send()
-            .await
-            .map_err(|e| map_dynamo_error("put_item", e))?;
+    /// Serializes a box, sealing each guardian's shard and shard hash when
+    /// envelope encryption is enabled and compressing large document lists.
+    /// All of a box's sealed fields share one data key.
+    async fn box_to_item(&self, box_record: &BoxRecord) -> Result<HashMap<String, AttributeValue>> {
+        let mut item = match &self.shard_envelope {
+            None => to_item(box_record)?,
+            Some(envelope) => {
+                let mut sealer = envelope.record_sealer();
+                let mut sealed = box_record.clone();
+                for guardian in sealed.guardians.iter_mut() {
+                    if let Some(shard) = &guardian.encrypted_shard {
+                        guardian.encrypted_shard = Some(sealer.seal_string(shard).await?);
+                    }
+                    if let Some(hash) = &guardian.shard_hash {
+                        guardian.shard_hash = Some(sealer.seal_string(hash).await?);
+                    }
+                }
+                to_item(&sealed)?
+            }
+        };
+        compress_documents(&mut item, &box_record.documents)?;
+        Ok(item)
+    }
+
+    /// Deserializes a box, expanding compressed documents and opening sealed
+    /// shard fields. Fields written before encryption was enabled are read as
+    /// they are.
+    async fn box_from_item(&self, item: &HashMap<String, AttributeValue>) -> Result<BoxRecord> {
+        let mut box_record: BoxRecord = from_item(expand_documents(item.clone())?)?;
+        let Some(envelope) = &self.shard_envelope else {
+            return Ok(box_record);
+        };
 
+        let mut opener = envelope.record_opener();
+        for guardian in box_record.guardians.iter_mut() {
+            if let Some(shard) = &guardian.encrypted_shard {
+                guardian.encrypted_shard = Some(opener.open_string(shard).await?);
+            }
+            if let Some(hash) = &guardian.shard_hash {
+                guardian.shard_hash = Some(opener.open_string(hash).await?);
+            }
+        }
         Ok(box_record)
     }
 
-    /// Gets a box by ID
-    async fn get_box(&self, id: &str) -> Result<BoxRecord> {
+    /// Name of the guardian-box lookup table paired with a box table in tests
+    pub fn guardian_box_table_for(table_name: &str) -> String {
+        format!("{}-guardian-box", table_name)
+    }
+
+    /// Brings the guardian-box lookup in line with a box write: rows are
+    /// removed for guardians no longer on the box and written for every
+    /// guardian still on it, so any later write repairs a row lost to a
+    /// failed sync. The lookup is written after the box itself, so readers
+    /// re-check membership on the box rather than trusting the lookup alone.
+    /// Invited placeholders have no user id yet and get no row. A failure is
+    /// logged rather than returned, since the box write has already committed.
+    async fn sync_guardian_index(
+        &self,
+        box_id: &str,
+        old: Option<&BoxRecord>,
+        new: Option<&BoxRecord>,
+    ) {
+        let guardian_ids = |b: &BoxRecord| -> HashSet<String> {
+            b.guardians
+                .iter()
+                .filter(|g| !g.id.is_empty())
+                .map(|g| g.id.clone())
+                .collect()
+        };
+        let old_ids = old.map(guardian_ids).unwrap_or_default();
+        let new_ids = new.map(guardian_ids).unwrap_or_default();
+
+        for guardian_id in old_ids.difference(&new_ids) {
+            let request = self
+                .client
+                .delete_item()
+                .table_name(&self.guardian_box_table_name)
+                .key("guardianId", AttributeValue::S(guardian_id.clone()))
+                .key("boxId", AttributeValue::S(box_id.to_string()));
+            let result = retry_with_backoff("delete_item", || async {
+                request
+                    .clone()
+                    .send()
+                    .await
+                    .map_err(map_delete_dynamo_error)
+            })
+            .await;
+            if let Err(e) = result {
+                log::error!(
+                    "Failed to remove guardian-box row, left for the next write to repair: box_id={}, guardian_id={}, error={}",
+                    box_id, guardian_id, e
+                );
+            }
+        }
+
+        for guardian_id in &new_ids {
+            if let Err(e) = self.put_guardian_box_row(guardian_id, box_id).await {
+                log::error!(
+                    "Failed to write guardian-box row, left for the next write to repair: box_id={}, guardian_id={}, error={}",
+                    box_id, guardian_id, e
+                );
+            }
+        }
+    }
+
+    /// Writes one guardian-box lookup row
+    async fn put_guardian_box_row(&self, guardian_id: &str, box_id: &str) -> Result<()> {
+        let request = self
+            .client
+            .put_item()
+            .table_name(&self.guardian_box_table_name)
+            .item("guardianId", AttributeValue::S(guardian_id.to_string()))
+            .item("boxId", AttributeValue::S(box_id.to_string()));
+        retry_with_backoff("put_item", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(|e| map_sdk_error("put_item", e))
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Reads a box item, optionally with a strongly consistent read
+    async fn read_box(&self, id: &str, consistent_read: bool) -> Result<BoxRecord> {
         let key = HashMap::from([("id".to_string(), AttributeValue::S(id.to_string()))]);
 
-        let response = self
+        let request = self
             .client
             .get_item()
             .table_name(&self.table_name)
             .set_key(Some(key))
-            .send()
-            .await
-            .map_err(|e| map_get_dynamo_error(e, id))?;
+            .consistent_read(consistent_read);
+        let response = retry_with_backoff("get_item", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(|e| map_get_dynamo_error(e, id))
+        })
+        .await?;
 
         let item = response
             .item()
             .ok_or_else(|| StoreError::NotFound(format!("Box not found: {}", id)))?;
 
-        let box_record = from_item(item.clone())?;
+        let box_record = self.box_from_item(item).await?;
+        Ok(box_record)
+    }
+
+    /// Scans the box table with a filter, following `LastEvaluatedKey`, and
+    /// optionally restricted to one segment of a parallel scan. Every page is
+    /// logged with the capacity it consumed, since scans read the whole table.
+    async fn scan_boxes(
+        &self,
+        filter_expression: &str,
+        expr_attr_names: HashMap<String, String>,
+        expr_attr_values: HashMap<String, AttributeValue>,
+        segment: Option<(u32, u32)>,
+    ) -> Result<Vec<BoxRecord>> {
+        let mut boxes = Vec::new();
+        let mut start_key = None;
+        loop {
+            let mut request = self
+                .client
+                .scan()
+                .table_name(&self.table_name)
+                .filter_expression(filter_expression)
+                .set_expression_attribute_names(Some(expr_attr_names.clone()))
+                .set_expression_attribute_values(Some(expr_attr_values.clone()))
+                .set_exclusive_start_key(start_key)
+                .return_consumed_capacity(ReturnConsumedCapacity::Total);
+            if let Some((segment, total_segments)) = segment {
+                request = request
+                    .segment(segment as i32)
+                    .total_segments(total_segments as i32);
+            }
+            let response = retry_with_backoff("scan", || async {
+                request.clone().send().await.map_err(map_scan_dynamo_error)
+            })
+            .await?;
+
+            log::warn!(
+                "Full scan of {}: segment={:?}, scanned={}, matched={}, consumed_capacity={:?}",
+                self.table_name,
+                segment,
+                response.scanned_count(),
+                response.count(),
+                response
+                    .consumed_capacity()
+                    .and_then(|capacity| capacity.capacity_units())
+            );
+
+            for item in response.items() {
+                boxes.push(self.box_from_item(item).await?);
+            }
+
+            match response.last_evaluated_key() {
+                Some(key) if !key.is_empty() => start_key = Some(key.clone()),
+                _ => break,
+            }
+        }
+
+        Ok(boxes)
+    }
+
+    /// Queries one page of the guardian-box lookup
+    async fn query_guardian_box_ids(
+        &self,
+        guardian_id: &str,
+        limit: Option<usize>,
+        start_key: Option<HashMap<String, AttributeValue>>,
+    ) -> Result<(Vec<String>, Option<HashMap<String, AttributeValue>>)> {
+        let request = self
+            .client
+            .query()
+            .table_name(&self.guardian_box_table_name)
+            .key_condition_expression("guardianId = :guardian_id")
+            .expression_attribute_values(":guardian_id", AttributeValue::S(guardian_id.to_string()))
+            .set_limit(limit.map(|l| l as i32))
+            .set_exclusive_start_key(start_key);
+        let response = retry_with_backoff("query", || async {
+            request.clone().send().await.map_err(map_query_dynamo_error)
+        })
+        .await?;
+
+        let box_ids = response
+            .items()
+            .iter()
+            .filter_map(|item| item.get("boxId").and_then(|v| v.as_s().ok()).cloned())
+            .collect();
+        let last_key = response
+            .last_evaluated_key()
+            .filter(|key| !key.is_empty())
+            .cloned();
+
+        Ok((box_ids, last_key))
+    }
+
+    /// Loads the boxes behind a set of lookup rows, keeping only those where
+    /// the guardian is still present and has not rejected. A lookup row can
+    /// briefly outlive its box, which the batch get simply skips.
+    async fn load_guardian_boxes(
+        &self,
+        guardian_id: &str,
+        box_ids: &[String],
+    ) -> Result<Vec<BoxRecord>> {
+        let boxes = super::BoxStore::get_boxes_by_ids(self, box_ids).await?;
+        Ok(boxes
+            .into_iter()
+            .filter(|box_record| {
+                box_record.guardians.iter().any(|guardian| {
+                    guardian.id == guardian_id
+                        && guardian.status != crate::models::GuardianStatus::Rejected
+                })
+            })
+            .collect())
+    }
+}
+
+// Whether a stored box is the one `written` created, rather than another box
+// that happens to share its id. It may have been updated since.
+fn is_same_box_write(stored: &BoxRecord, written: &BoxRecord) -> bool {
+    stored.owner_id == written.owner_id && stored.created_at == written.created_at
+}
+
+// Whether a stored box is exactly what an update wrote, rather than a
+// concurrent update that reached the same version
+fn is_same_box_update(stored: &BoxRecord, written: &BoxRecord) -> bool {
+    stored.version == written.version
+        && serde_json::to_value(stored).ok() == serde_json::to_value(written).ok()
+}
+
+#[async_trait]
+impl super::BoxStore for DynamoBoxStore {
+    /// Creates a new box record in DynamoDB
+    async fn create_box(&self, mut box_record: BoxRecord) -> Result<BoxRecord> {
+        box_record.refresh_all_shards_accepted();
+        let item = self.box_to_item(&box_record).await?;
+
+        // Never replace an existing box, whether from an id collision or a
+        // create that's run twice
+        let request = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item))
+            .condition_expression("attribute_not_exists(id)");
+        let mut attempts = 0;
+        let result = retry_with_backoff("put_item", || {
+            attempts += 1;
+            let request = request.clone();
+            let id = &box_record.id;
+            async move {
+                request.send().await.map_err(|err| {
+                    if let SdkError::ServiceError(service_err) = &err {
+                        if service_err.err().is_conditional_check_failed_exception() {
+                            return StoreError::AlreadyExists(format!(
+                                "Box already exists: id={}",
+                                id
+                            ));
+                        }
+                    }
+                    map_sdk_error("put_item", err)
+                })
+            }
+        })
+        .await;
+        match result {
+            // A retried put can trip over the write of an attempt whose
+            // response was lost; that box is this one, so the create succeeded
+            Err(StoreError::AlreadyExists(msg)) if attempts > 1 => {
+                let stored = self.read_box(&box_record.id, true).await?;
+                if !is_same_box_write(&stored, &box_record) {
+                    return Err(StoreError::AlreadyExists(msg));
+                }
+            }
+            result => {
+                result?;
+            }
+        }
+
+        self.sync_guardian_index(&box_record.id, None, Some(&box_record))
+            .await;
+
         Ok(box_record)
     }
 
+    /// Gets a box by ID (eventually consistent)
+    async fn get_box(&self, id: &str) -> Result<BoxRecord> {
+        self.read_box(id, false).await
+    }
+
+    /// Gets a box by ID with `ConsistentRead` set
+    async fn get_box_consistent(&self, id: &str) -> Result<BoxRecord> {
+        self.read_box(id, true).await
+    }
+
+    /// Gets boxes by id using BatchGetItem
+    ///
+    /// Implementation notes:
+    /// - Ids are deduplicated and requested in chunks of 100, the BatchGetItem limit
+    /// - Keys DynamoDB returns as unprocessed (throttling, 16MB response cap)
+    ///   are retried with `retry_with_backoff`, like a throttled call
+    async fn get_boxes_by_ids(&self, ids: &[String]) -> Result<Vec<BoxRecord>> {
+        let mut unique_ids: Vec<&String> = Vec::with_capacity(ids.len());
+        let mut seen = HashSet::new();
+        for id in ids {
+            if seen.insert(id.as_str()) {
+                unique_ids.push(id);
+            }
+        }
+
+        let mut found: HashMap<String, BoxRecord> = HashMap::with_capacity(unique_ids.len());
+        for chunk in unique_ids.chunks(BATCH_GET_MAX_KEYS) {
+            let keys = chunk
+                .iter()
+                .map(|id| HashMap::from([("id".to_string(), AttributeValue::S(id.to_string()))]))
+                .collect::<Vec<_>>();
+            // Each attempt asks only for the keys still outstanding
+            let pending = Mutex::new(HashMap::from([(
+                self.table_name.clone(),
+                KeysAndAttributes::builder()
+                    .set_keys(Some(keys))
+                    .build()
+                    .map_err(|e| StoreError::InternalError(e.to_string()))?,
+            )]));
+            let items = Mutex::new(Vec::new());
+
+            retry_with_backoff("batch_get_item", || async {
+                let request_items = pending.lock().unwrap().clone();
+                let response = self
+                    .client
+                    .batch_get_item()
+                    .set_request_items(Some(request_items))
+                    .send()
+                    .await
+                    .map_err(|e| map_sdk_error("batch_get_item", e))?;
+
+                if let Some(got) = response.responses().and_then(|r| r.get(&self.table_name)) {
+                    items.lock().unwrap().extend(got.iter().cloned());
+                }
+
+                match response.unprocessed_keys() {
+                    Some(unprocessed) if !unprocessed.is_empty() => {
+                        *pending.lock().unwrap() = unprocessed.clone();
+                        Err(StoreError::Throttled(
+                            "DynamoDB batch_get_item left keys unprocessed".to_string(),
+                        ))
+                    }
+                    _ => Ok(()),
+                }
+            })
+            .await?;
+
+            for item in items.into_inner().unwrap() {
+                let box_record: BoxRecord = self.box_from_item(&item).await?;
+                found.insert(box_record.id.clone(), box_record);
+            }
+        }
+
+        // Return boxes in the order they were requested
+        Ok(unique_ids
+            .into_iter()
+            .filter_map(|id| found.remove(id))
+            .collect())
+    }
+
     /// Gets all boxes owned by a user
     async fn get_boxes_by_owner(&self, owner_id: &str) -> Result<Vec<BoxRecord>> {
         let expr_attr_names = HashMap::from([("#owner_id".to_string(), "ownerId".to_string())]);
@@ -143,30 +628,133 @@ impl super::BoxStore for DynamoBoxStore {
             AttributeValue::S(owner_id.to_string()),
         )]);
 
-        let response = self
+        let request = self
             .client
             .query()
             .table_name(&self.table_name)
             .index_name(GSI_OWNER_ID) // Use the GSI
             .key_condition_expression("#owner_id = :owner_id")
             .set_expression_attribute_names(Some(expr_attr_names))
-            .set_expression_attribute_values(Some(expr_attr_values))
-            .send()
-            .await
-            .map_err(|e| map_query_dynamo_error(e))?;
+            .set_expression_attribute_values(Some(expr_attr_values));
+        let response = retry_with_backoff("query", || async {
+            request.clone().send().await.map_err(map_query_dynamo_error)
+        })
+        .await?;
 
         // items() returns a reference to a slice, which could be empty but not None
         let items = response.items();
 
         let mut boxes = Vec::new();
         for item in items {
-            let box_record = from_item(item.clone())?;
+            let box_record = self.box_from_item(item).await?;
             boxes.push(box_record);
         }
 
         Ok(boxes)
     }
 
+    /// Counts the boxes owned by a user
+    ///
+    /// Implementation notes:
+    /// - A `Select=COUNT` query on the owner GSI, so no items are read back
+    /// - Throttled or failed pages are retried with backoff
+    async fn count_boxes_by_owner(&self, owner_id: &str) -> Result<usize> {
+        let expr_attr_names = HashMap::from([("#owner_id".to_string(), "ownerId".to_string())]);
+        let expr_attr_values = HashMap::from([(
+            ":owner_id".to_string(),
+            AttributeValue::S(owner_id.to_string()),
+        )]);
+
+        let mut count = 0;
+        let mut start_key = None;
+        loop {
+            let request = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name(GSI_OWNER_ID)
+                .key_condition_expression("#owner_id = :owner_id")
+                .set_expression_attribute_names(Some(expr_attr_names.clone()))
+                .set_expression_attribute_values(Some(expr_attr_values.clone()))
+                .select(Select::Count)
+                .set_exclusive_start_key(start_key);
+            let response = retry_with_backoff("query", || async {
+                request
+                    .clone()
+                    .send()
+                    .await
+                    .map_err(|e| map_sdk_error("query", e))
+            })
+            .await?;
+
+            count += response.count() as usize;
+            match response.last_evaluated_key() {
+                Some(key) if !key.is_empty() => start_key = Some(key.clone()),
+                _ => break,
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Gets a page of the boxes owned by a user
+    ///
+    /// Implementation notes:
+    /// - Queries the owner GSI with a `Limit`; its `LastEvaluatedKey` is the cursor
+    async fn get_boxes_by_owner_paginated(
+        &self,
+        owner_id: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Page<BoxRecord>> {
+        let start_key = match cursor {
+            Some(cursor) => Some(
+                decode_cursor(cursor, CursorScope::OwnedBoxes, owner_id)?
+                    .into_iter()
+                    .map(|(k, v)| (k, AttributeValue::S(v)))
+                    .collect::<HashMap<_, _>>(),
+            ),
+            None => None,
+        };
+
+        let request = self
+            .client
+            .query()
+            .table_name(&self.table_name)
+            .index_name(GSI_OWNER_ID)
+            .key_condition_expression("#owner_id = :owner_id")
+            .expression_attribute_names("#owner_id", "ownerId")
+            .expression_attribute_values(":owner_id", AttributeValue::S(owner_id.to_string()))
+            .limit(limit as i32)
+            .set_exclusive_start_key(start_key);
+        let response = retry_with_backoff("query", || async {
+            request.clone().send().await.map_err(map_query_dynamo_error)
+        })
+        .await?;
+
+        let mut boxes = Vec::new();
+        for item in response.items() {
+            boxes.push(self.box_from_item(item).await?);
+        }
+
+        let next_cursor = response
+            .last_evaluated_key()
+            .filter(|key| !key.is_empty())
+            .map(|key| {
+                let key = key
+                    .iter()
+                    .filter_map(|(k, v)| v.as_s().ok().map(|v| (k.clone(), v.clone())))
+                    .collect();
+                encode_cursor(&key, CursorScope::OwnedBoxes, owner_id)
+            })
+            .transpose()?;
+
+        Ok(Page {
+            items: boxes,
+            next_cursor,
+        })
+    }
+
     /// Updates a box
     async fn update_box(&self, box_record: BoxRecord) -> Result<BoxRecord> {
         // Clone the box record for modification
@@ -179,8 +767,11 @@ impl super::BoxStore for DynamoBoxStore {
         let current_version = updated_box.version;
         updated_box.version = current_version + 1;
 
+        // Keep the derived reminder flag in step with the guardian list
+        updated_box.refresh_all_shards_accepted();
+
         // Convert to DynamoDB item
-        let item = to_item(&updated_box)?;
+        let item = self.box_to_item(&updated_box).await?;
 
         // Create a conditional expression to check the version
         let condition_expression = if current_version > 0 {
@@ -207,127 +798,394 @@ impl super::BoxStore for DynamoBoxStore {
             .set_item(Some(item))
             .condition_expression(condition_expression)
             .set_expression_attribute_values(Some(expr_attr_values))
-            .set_expression_attribute_names(Some(expr_attr_names));
-
-        // Execute the update
-        match request.send().await {
-            Ok(_) => Ok(updated_box),
-            Err(err) => {
-                // Check if it's a conditional check failure (version mismatch)
-                if let SdkError::ServiceError(service_err) = &err {
-                    if service_err.err().is_conditional_check_failed_exception() {
-                        // Version conflict - retry with the latest version
-                        return Err(StoreError::VersionConflict(format!(
-                            "Box update conflict: id={}, version={}",
-                            updated_box.id, current_version
-                        )));
+            .set_expression_attribute_names(Some(expr_attr_names))
+            .return_values(ReturnValue::AllOld);
+
+        // Execute the update. Throttled writes are retried; a version mismatch
+        // is left for the caller to resolve against the latest box.
+        let mut attempts = 0;
+        let result = retry_with_backoff("put_item", || {
+            attempts += 1;
+            let request = request.clone();
+            let id = &updated_box.id;
+            async move {
+                request.send().await.map_err(|err| {
+                    // Check if it's a conditional check failure (version mismatch)
+                    if let SdkError::ServiceError(service_err) = &err {
+                        if service_err.err().is_conditional_check_failed_exception() {
+                            return StoreError::VersionConflict(format!(
+                                "Box update conflict: id={}, version={}",
+                                id, current_version
+                            ));
+                        }
                     }
-                }
 
-                // Other error
-                Err(map_dynamo_error("put_item", err))
+                    // Other error
+                    map_sdk_error("put_item", err)
+                })
             }
-        }
+        })
+        .await;
+
+        // Diff against the replaced item to keep the guardian lookup in step
+        let previous: Option<BoxRecord> = match result {
+            Ok(response) => match response.attributes() {
+                Some(old) if !old.is_empty() => Some(from_item(expand_documents(old.clone())?)?),
+                _ => None,
+            },
+            // A retried put can trip over the write of an attempt whose
+            // response was lost; if the stored box is exactly this write, the
+            // update succeeded. The box it replaced is lost with the response,
+            // so lookup rows of removed guardians are left to the next write.
+            Err(StoreError::VersionConflict(msg)) if attempts > 1 => {
+                let stored = self.read_box(&updated_box.id, true).await?;
+                if !is_same_box_update(&stored, &updated_box) {
+                    return Err(StoreError::VersionConflict(msg));
+                }
+                None
+            }
+            Err(e) => return Err(e),
+        };
+        self.sync_guardian_index(&updated_box.id, previous.as_ref(), Some(&updated_box))
+            .await;
+        notify_box_changed(&updated_box.id);
+        Ok(updated_box)
     }
 
     /// Deletes a box
     async fn delete_box(&self, id: &str) -> Result<()> {
         let key = HashMap::from([("id".to_string(), AttributeValue::S(id.to_string()))]);
 
-        self.client
+        let request = self
+            .client
             .delete_item()
             .table_name(&self.table_name)
             .set_key(Some(key))
-            .send()
-            .await
-            .map_err(|e| map_delete_dynamo_error(e))?;
+            .return_values(ReturnValue::AllOld);
+        let response = retry_with_backoff("delete_item", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(map_delete_dynamo_error)
+        })
+        .await?;
+
+        if let Some(old) = response.attributes().filter(|old| !old.is_empty()) {
+            let previous: BoxRecord = from_item(expand_documents(old.clone())?)?;
+            self.sync_guardian_index(id, Some(&previous), None).await;
+        }
+        notify_box_changed(id);
 
         Ok(())
     }
 
-    /// Scans all locked boxes (for reminder service)
+    /// Scans locked boxes that still have unaccepted shards (for reminder service)
     ///
     /// Implementation notes:
-    /// - Uses a full table scan with filter expression for is_locked = true
+    /// - Uses a full table scan with filter expression for is_locked = true,
+    ///   skipping boxes flagged allShardsAccepted
     /// - For production systems with many boxes, consider adding a GSI on isLocked
     async fn scan_locked_boxes(&self) -> Result<Vec<BoxRecord>> {
-        let expr_attr_names = HashMap::from([("#is_locked".to_string(), "isLocked".to_string())]);
-        let expr_attr_values = HashMap::from([(":locked".to_string(), AttributeValue::Bool(true))]);
-
-        let response = self
-            .client
-            .scan()
-            .table_name(&self.table_name)
-            .filter_expression("#is_locked = :locked")
-            .set_expression_attribute_names(Some(expr_attr_names))
-            .set_expression_attribute_values(Some(expr_attr_values))
-            .send()
+        let (filter_expression, expr_attr_names, expr_attr_values) = locked_boxes_filter();
+        self.scan_boxes(&filter_expression, expr_attr_names, expr_attr_values, None)
             .await
-            .map_err(|e| map_scan_dynamo_error(e))?;
-
-        let items = response.items();
-
-        let mut boxes = Vec::new();
-        for item in items {
-            let box_record: BoxRecord = from_item(item.clone())?;
-            boxes.push(box_record);
-        }
+    }
 
-        Ok(boxes)
+    /// Scans one segment of the locked boxes with unaccepted shards
+    ///
+    /// Implementation notes:
+    /// - Uses DynamoDB's parallel scan, which splits the table by hash key, so
+    ///   segments never overlap
+    async fn scan_locked_boxes_segment(
+        &self,
+        segment: u32,
+        total_segments: u32,
+    ) -> Result<Vec<BoxRecord>> {
+        super::validate_scan_segment(segment, total_segments)?;
+        let (filter_expression, expr_attr_names, expr_attr_values) = locked_boxes_filter();
+        self.scan_boxes(
+            &filter_expression,
+            expr_attr_names,
+            expr_attr_values,
+            Some((segment, total_segments)),
+        )
+        .await
     }
 
-    /// Gets all boxes where the given user is a guardian (with status not rejected)
+    /// Scans locked boxes locked or updated at or after `cutoff` (for reminder service)
     ///
     /// Implementation notes:
-    /// - Currently uses a full table scan since guardians are stored in nested arrays within the BoxRecord
-    /// - For production systems with many boxes, this could be improved by:
-    ///   1. Creating a new GSI with a composite key or
-    ///   2. Creating a separate guardian-to-box mapping table with a GSI
-    ///   3. Using DynamoDB's new document path capabilities for filtering
-    async fn get_boxes_by_guardian_id(&self, guardian_id: &str) -> Result<Vec<BoxRecord>> {
-        // Currently we perform a full table scan as guardian information is stored in an array within
-        // the box document, not as a separate attribute that can be indexed. In the future, we could
-        // create a separate table or GSI for guardian relationships.
+    /// - `lockedAt` and `updatedAt` are stored as UTC RFC 3339 strings, so the
+    ///   filter expression can compare them lexically against the cutoff
+    /// - Still a scan, but DynamoDB drops filtered items before returning them,
+    ///   so the caller only deserializes boxes it may act on
+    /// - Follows `LastEvaluatedKey` so results aren't cut off at 1MB
+    async fn scan_locked_boxes_since(
+        &self,
+        cutoff: DateTime<Utc>,
+        exclude_shards_deleted: bool,
+    ) -> Result<Vec<BoxRecord>> {
+        let mut expr_attr_names = HashMap::from([
+            ("#is_locked".to_string(), "isLocked".to_string()),
+            ("#locked_at".to_string(), "lockedAt".to_string()),
+            ("#updated_at".to_string(), "updatedAt".to_string()),
+            ("#all_accepted".to_string(), "allShardsAccepted".to_string()),
+        ]);
+        let expr_attr_values = HashMap::from([
+            (":locked".to_string(), AttributeValue::Bool(true)),
+            (":true".to_string(), AttributeValue::Bool(true)),
+            (
+                ":cutoff".to_string(),
+                AttributeValue::S(cutoff.to_rfc3339()),
+            ),
+        ]);
+
+        let mut filter_expression = format!(
+            "#is_locked = :locked AND (#locked_at >= :cutoff OR #updated_at >= :cutoff) AND {}",
+            NOT_ALL_ACCEPTED_FILTER
+        );
+        if exclude_shards_deleted {
+            filter_expression.push_str(" AND attribute_not_exists(#shards_deleted_at)");
+            expr_attr_names.insert(
+                "#shards_deleted_at".to_string(),
+                "shardsDeletedAt".to_string(),
+            );
+        }
 
-        let response = self
-            .client
-            .scan()
-            .table_name(&self.table_name)
-            .send()
+        self.scan_boxes(&filter_expression, expr_attr_names, expr_attr_values, None)
             .await
-            .map_err(|e| map_scan_dynamo_error(e))?;
+    }
 
-        let items = response.items();
+    /// Gets the boxes owned by a user that were locked within a range
+    ///
+    /// Implementation notes:
+    /// - Queries the owner GSI with a filter expression on `lockedAt`, which is
+    ///   stored as a UTC RFC 3339 string and compares lexically
+    async fn get_boxes_by_owner_locked_between(
+        &self,
+        owner_id: &str,
+        locked_after: Option<DateTime<Utc>>,
+        locked_before: Option<DateTime<Utc>>,
+    ) -> Result<Vec<BoxRecord>> {
+        let expr_attr_names = HashMap::from([
+            ("#owner_id".to_string(), "ownerId".to_string()),
+            ("#is_locked".to_string(), "isLocked".to_string()),
+            ("#locked_at".to_string(), "lockedAt".to_string()),
+        ]);
+        let mut expr_attr_values = HashMap::from([
+            (
+                ":owner_id".to_string(),
+                AttributeValue::S(owner_id.to_string()),
+            ),
+            (":locked".to_string(), AttributeValue::Bool(true)),
+        ]);
+
+        let mut filter_expression =
+            "#is_locked = :locked AND attribute_exists(#locked_at)".to_string();
+        if let Some(after) = locked_after {
+            filter_expression.push_str(" AND #locked_at >= :locked_after");
+            expr_attr_values.insert(
+                ":locked_after".to_string(),
+                AttributeValue::S(after.to_rfc3339()),
+            );
+        }
+        if let Some(before) = locked_before {
+            filter_expression.push_str(" AND #locked_at < :locked_before");
+            expr_attr_values.insert(
+                ":locked_before".to_string(),
+                AttributeValue::S(before.to_rfc3339()),
+            );
+        }
 
         let mut boxes = Vec::new();
-        for item in items {
-            let box_record: BoxRecord = from_item(item.clone())?;
-
-            // Check if the user is a guardian for this box
-            let is_guardian = box_record.guardians.iter().any(|guardian| {
-                guardian.id == guardian_id
-                    && guardian.status != crate::models::GuardianStatus::Rejected
-            });
-
-            if is_guardian {
+        let mut start_key = None;
+        loop {
+            let request = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name(GSI_OWNER_ID)
+                .key_condition_expression("#owner_id = :owner_id")
+                .filter_expression(&filter_expression)
+                .set_expression_attribute_names(Some(expr_attr_names.clone()))
+                .set_expression_attribute_values(Some(expr_attr_values.clone()))
+                .set_exclusive_start_key(start_key);
+            let response = retry_with_backoff("query", || async {
+                request.clone().send().await.map_err(map_query_dynamo_error)
+            })
+            .await?;
+
+            for item in response.items() {
+                let box_record: BoxRecord = self.box_from_item(item).await?;
                 boxes.push(box_record);
             }
+
+            match response.last_evaluated_key() {
+                Some(key) if !key.is_empty() => start_key = Some(key.clone()),
+                _ => break,
+            }
         }
 
         Ok(boxes)
     }
-}
 
-// INVITATION STORE IMPLEMENTATION
-#[async_trait]
-impl super::InvitationStore for DynamoInvitationStore {
-    async fn create_invitation(&self, mut invitation: Invitation) -> Result<Invitation> {
-        // Set created_at and expires_at if not already set
-        if invitation.created_at.is_empty() {
-            invitation.created_at = Utc::now().to_rfc3339();
+    /// Gets the ids of every box listing the given user as a guardian
+    ///
+    /// Implementation notes:
+    /// - Guardians are stored in a nested array within the BoxRecord, which no
+    ///   GSI can key on, so this queries the guardian-box lookup table instead
+    /// - The lookup is maintained by create_box, update_box and delete_box
+    async fn get_box_ids_for_guardian(&self, guardian_id: &str) -> Result<Vec<String>> {
+        let mut box_ids = Vec::new();
+        let mut start_key = None;
+        loop {
+            let (page, last_key) = self
+                .query_guardian_box_ids(guardian_id, None, start_key)
+                .await?;
+            box_ids.extend(page);
+            match last_key {
+                Some(key) => start_key = Some(key),
+                None => break,
+            }
         }
+        Ok(box_ids)
+    }
 
-        if invitation.expires_at.is_empty() {
+    /// Gets all boxes where the given user is a guardian (with status not rejected)
+    async fn get_boxes_by_guardian_id(&self, guardian_id: &str) -> Result<Vec<BoxRecord>> {
+        let box_ids = self.get_box_ids_for_guardian(guardian_id).await?;
+        self.load_guardian_boxes(guardian_id, &box_ids).await
+    }
+
+    /// Gets a page of boxes where the given user is a guardian
+    ///
+    /// Implementation notes:
+    /// - Pages through the guardian-box lookup, whose `LastEvaluatedKey` is the cursor
+    /// - Boxes the guardian has since rejected are dropped after loading, so a
+    ///   page may hold fewer than `limit` boxes
+    async fn get_guardian_boxes_paginated(
+        &self,
+        guardian_id: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Page<BoxRecord>> {
+        let start_key = match cursor {
+            Some(cursor) => Some(
+                decode_cursor(cursor, CursorScope::GuardianBoxes, guardian_id)?
+                    .into_iter()
+                    .map(|(k, v)| (k, AttributeValue::S(v)))
+                    .collect::<HashMap<_, _>>(),
+            ),
+            None => None,
+        };
+
+        let (box_ids, last_key) = self
+            .query_guardian_box_ids(guardian_id, Some(limit), start_key)
+            .await?;
+        let boxes = self.load_guardian_boxes(guardian_id, &box_ids).await?;
+
+        let next_cursor = last_key
+            .map(|key| {
+                let key = key
+                    .into_iter()
+                    .filter_map(|(k, v)| v.as_s().ok().cloned().map(|v| (k, v)))
+                    .collect();
+                encode_cursor(&key, CursorScope::GuardianBoxes, guardian_id)
+            })
+            .transpose()?;
+
+        Ok(Page {
+            items: boxes,
+            next_cursor,
+        })
+    }
+
+    /// Writes the guardian-box lookup rows for a page of boxes
+    ///
+    /// Implementation notes:
+    /// - Scans the box table projecting only each box's id and guardian ids,
+    ///   so shards are never read or decrypted
+    /// - The scan's `LastEvaluatedKey` is the cursor. A page may cover fewer
+    ///   than `limit` boxes, or none, while the cursor is still set
+    /// - Rows are put rather than diffed, so running it again is harmless
+    async fn backfill_guardian_index(
+        &self,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Page<String>> {
+        let start_key = match cursor {
+            Some(cursor) => Some(
+                decode_cursor(cursor, CursorScope::GuardianIndexBackfill, "")?
+                    .into_iter()
+                    .map(|(k, v)| (k, AttributeValue::S(v)))
+                    .collect::<HashMap<_, _>>(),
+            ),
+            None => None,
+        };
+
+        let request = self
+            .client
+            .scan()
+            .table_name(&self.table_name)
+            .projection_expression("#id, #guardians")
+            .expression_attribute_names("#id", "id")
+            .expression_attribute_names("#guardians", "guardians")
+            .limit(limit as i32)
+            .set_exclusive_start_key(start_key);
+        let response = retry_with_backoff("scan", || async {
+            request.clone().send().await.map_err(map_scan_dynamo_error)
+        })
+        .await?;
+
+        let mut box_ids = Vec::new();
+        for item in response.items() {
+            let Some(box_id) = item.get("id").and_then(|v| v.as_s().ok()) else {
+                continue;
+            };
+            let guardian_ids: HashSet<&String> = item
+                .get("guardians")
+                .and_then(|v| v.as_l().ok())
+                .into_iter()
+                .flatten()
+                .filter_map(|guardian| guardian.as_m().ok()?.get("id")?.as_s().ok())
+                .filter(|id| !id.is_empty())
+                .collect();
+            for guardian_id in guardian_ids {
+                self.put_guardian_box_row(guardian_id, box_id).await?;
+            }
+            box_ids.push(box_id.clone());
+        }
+
+        let next_cursor = response
+            .last_evaluated_key()
+            .filter(|key| !key.is_empty())
+            .map(|key| {
+                let key = key
+                    .iter()
+                    .filter_map(|(k, v)| v.as_s().ok().map(|v| (k.clone(), v.clone())))
+                    .collect();
+                encode_cursor(&key, CursorScope::GuardianIndexBackfill, "")
+            })
+            .transpose()?;
+
+        Ok(Page {
+            items: box_ids,
+            next_cursor,
+        })
+    }
+}
+
+// INVITATION STORE IMPLEMENTATION
+#[async_trait]
+impl super::InvitationStore for DynamoInvitationStore {
+    async fn create_invitation(&self, mut invitation: Invitation) -> Result<Invitation> {
+        // Set created_at and expires_at if not already set
+        if invitation.created_at.is_empty() {
+            invitation.created_at = Utc::now().to_rfc3339();
+        }
+
+        if invitation.expires_at.is_empty() {
             // Set expiration to 48 hours from now
             invitation.expires_at = (Utc::now() + Duration::hours(48)).to_rfc3339();
         }
@@ -343,42 +1201,30 @@ impl super::InvitationStore for DynamoInvitationStore {
         // Convert to DynamoDB item
         let item = to_item(invitation.clone())?;
 
-        self.client
+        let request = self
+            .client
             .put_item()
             .table_name(&self.table_name)
-            .set_item(Some(item))
-            .send()
-            .await
-            .map_err(|e| map_dynamo_error("put_item", e))?;
+            .set_item(Some(item));
+        retry_with_backoff("put_item", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(|e| map_sdk_error("put_item", e))
+        })
+        .await?;
 
         log::debug!("Successfully stored invitation {}", invitation.id);
         Ok(invitation)
     }
 
     async fn get_invitation(&self, id: &str) -> Result<Invitation> {
-        let key = HashMap::from([("id".to_string(), AttributeValue::S(id.to_string()))]);
-
-        let result = self
-            .client
-            .get_item()
-            .table_name(&self.table_name)
-            .set_key(Some(key))
-            .send()
-            .await
-            .map_err(|e| map_dynamo_error("get_item", e))?;
-
-        let item = result
-            .item()
-            .ok_or_else(|| StoreError::NotFound(format!("Invitation with id {} not found", id)))?;
-
-        let invitation: Invitation = from_item(item.clone())?;
-
-        // Check if the invitation has expired
-        if self.is_expired(&invitation.expires_at)? {
-            return Err(StoreError::InvitationExpired);
-        }
+        self.read_invitation(id, false).await
+    }
 
-        Ok(invitation)
+    async fn get_invitation_consistent(&self, id: &str) -> Result<Invitation> {
+        self.read_invitation(id, true).await
     }
 
     async fn get_invitation_by_code(&self, invite_code: &str) -> Result<Invitation> {
@@ -388,16 +1234,17 @@ impl super::InvitationStore for DynamoInvitationStore {
             AttributeValue::S(invite_code.to_string()),
         )]);
 
-        let result = self
+        let request = self
             .client
             .query()
             .table_name(&self.table_name)
             .index_name(GSI_INVITE_CODE)
             .key_condition_expression("inviteCode = :invite_code")
-            .set_expression_attribute_values(Some(expr_attr_values))
-            .send()
-            .await
-            .map_err(|e| map_dynamo_error("query", e))?;
+            .set_expression_attribute_values(Some(expr_attr_values));
+        let result = retry_with_backoff("query", || async {
+            request.clone().send().await.map_err(map_query_dynamo_error)
+        })
+        .await?;
 
         let items = result.items();
 
@@ -432,30 +1279,79 @@ impl super::InvitationStore for DynamoInvitationStore {
         // Convert to DynamoDB item
         let item = to_item(invitation.clone())?;
 
-        self.client
+        let request = self
+            .client
             .put_item()
             .table_name(&self.table_name)
-            .set_item(Some(item))
-            .send()
-            .await
-            .map_err(|e| map_dynamo_error("put_item", e))?;
+            .set_item(Some(item));
+        retry_with_backoff("put_item", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(|e| map_sdk_error("put_item", e))
+        })
+        .await?;
 
         Ok(invitation)
     }
 
+    /// Expires an invitation with a conditional UpdateItem
+    ///
+    /// Implementation notes:
+    /// - Only `expiresAt` is written, so nothing else a concurrent redemption
+    ///   changed is overwritten
+    /// - A missing `linkedUserId` is stored as NULL, so either form counts as
+    ///   unlinked
+    /// - Retrying is safe: the condition doesn't depend on `expiresAt`
+    async fn expire_pending_invitation(&self, id: &str, expires_at: &str) -> Result<bool> {
+        let request = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("id", AttributeValue::S(id.to_string()))
+            .update_expression("SET expiresAt = :expires_at")
+            .condition_expression(
+                "attribute_exists(id) AND opened = :false \
+                 AND (attribute_not_exists(linkedUserId) OR attribute_type(linkedUserId, :null))",
+            )
+            .expression_attribute_values(":expires_at", AttributeValue::S(expires_at.to_string()))
+            .expression_attribute_values(":false", AttributeValue::Bool(false))
+            .expression_attribute_values(":null", AttributeValue::S("NULL".to_string()));
+
+        retry_with_backoff("update_item", || async {
+            match request.clone().send().await {
+                Ok(_) => Ok(true),
+                Err(SdkError::ServiceError(service_err))
+                    if service_err.err().is_conditional_check_failed_exception() =>
+                {
+                    Ok(false)
+                }
+                Err(e) => Err(map_sdk_error("update_item", e)),
+            }
+        })
+        .await
+    }
+
     async fn delete_invitation(&self, id: &str) -> Result<()> {
         // Verify invitation exists first
         self.get_invitation(id).await?;
 
         let key = HashMap::from([("id".to_string(), AttributeValue::S(id.to_string()))]);
 
-        self.client
+        let request = self
+            .client
             .delete_item()
             .table_name(&self.table_name)
-            .set_key(Some(key))
-            .send()
-            .await
-            .map_err(|e| map_dynamo_error("delete_item", e))?;
+            .set_key(Some(key));
+        retry_with_backoff("delete_item", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(map_delete_dynamo_error)
+        })
+        .await?;
 
         Ok(())
     }
@@ -465,16 +1361,17 @@ impl super::InvitationStore for DynamoInvitationStore {
         let expr_attr_values =
             HashMap::from([(":box_id".to_string(), AttributeValue::S(box_id.to_string()))]);
 
-        let result = self
+        let request = self
             .client
             .query()
             .table_name(&self.table_name)
             .index_name(GSI_BOX_ID)
             .key_condition_expression("boxId = :box_id")
-            .set_expression_attribute_values(Some(expr_attr_values))
-            .send()
-            .await
-            .map_err(|e| map_dynamo_error("query", e))?;
+            .set_expression_attribute_values(Some(expr_attr_values));
+        let result = retry_with_backoff("query", || async {
+            request.clone().send().await.map_err(map_query_dynamo_error)
+        })
+        .await?;
 
         let items = result.items();
 
@@ -490,6 +1387,54 @@ impl super::InvitationStore for DynamoInvitationStore {
         Ok(invitations)
     }
 
+    /// Counts the box's unexpired invitations
+    ///
+    /// Implementation notes:
+    /// - A `Select=COUNT` query on the box GSI, so no items are read back
+    /// - `expiresAt` is compared as a string, which orders correctly because
+    ///   every invitation is written with a UTC `to_rfc3339` timestamp
+    /// - Throttled or failed pages are retried with backoff
+    async fn count_invitations_by_box(&self, box_id: &str) -> Result<usize> {
+        let expr_attr_values = HashMap::from([
+            (":box_id".to_string(), AttributeValue::S(box_id.to_string())),
+            (
+                ":now".to_string(),
+                AttributeValue::S(Utc::now().to_rfc3339()),
+            ),
+        ]);
+
+        let mut count = 0;
+        let mut start_key = None;
+        loop {
+            let request = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name(GSI_BOX_ID)
+                .key_condition_expression("boxId = :box_id")
+                .filter_expression("expiresAt > :now")
+                .set_expression_attribute_values(Some(expr_attr_values.clone()))
+                .select(Select::Count)
+                .set_exclusive_start_key(start_key);
+            let response = retry_with_backoff("query", || async {
+                request
+                    .clone()
+                    .send()
+                    .await
+                    .map_err(|e| map_sdk_error("query", e))
+            })
+            .await?;
+
+            count += response.count() as usize;
+            match response.last_evaluated_key() {
+                Some(key) if !key.is_empty() => start_key = Some(key.clone()),
+                _ => break,
+            }
+        }
+
+        Ok(count)
+    }
+
     async fn get_invitations_by_creator_id(&self, creator_id: &str) -> Result<Vec<Invitation>> {
         log::info!(
             "Querying table {} for invitations with creator_id={}, using GSI: {}",
@@ -511,16 +1456,17 @@ impl super::InvitationStore for DynamoInvitationStore {
             expr_attr_values
         );
 
-        let result = self
+        let request = self
             .client
             .query()
             .table_name(&self.table_name)
             .index_name(GSI_CREATOR_ID)
             .key_condition_expression("creatorId = :creatorId")
-            .set_expression_attribute_values(Some(expr_attr_values))
-            .send()
-            .await
-            .map_err(|e| map_dynamo_error("query", e))?;
+            .set_expression_attribute_values(Some(expr_attr_values));
+        let result = retry_with_backoff("query", || async {
+            request.clone().send().await.map_err(map_query_dynamo_error)
+        })
+        .await?;
 
         let items = result.items();
         log::info!("Found {} items for creator_id={}", items.len(), creator_id);
@@ -528,14 +1474,11 @@ impl super::InvitationStore for DynamoInvitationStore {
         if items.is_empty() {
             log::info!("No items found. Checking if this is a case sensitivity issue or wrong attribute name...");
             // Let's try to scan a few items to see what creator_id values actually exist
-            let scan_result = self
-                .client
-                .scan()
-                .table_name(&self.table_name)
-                .limit(5)
-                .send()
-                .await
-                .map_err(|e| map_dynamo_error("scan", e))?;
+            let request = self.client.scan().table_name(&self.table_name).limit(5);
+            let scan_result = retry_with_backoff("scan", || async {
+                request.clone().send().await.map_err(map_scan_dynamo_error)
+            })
+            .await?;
 
             let scan_items = scan_result.items();
             log::info!("Sample scan found {} items in table", scan_items.len());
@@ -559,32 +1502,117 @@ impl super::InvitationStore for DynamoInvitationStore {
         }
         Ok(invitations)
     }
+
+    /// Gets a page of invitations created by the given user
+    ///
+    /// Implementation notes:
+    /// - Queries the creator GSI with `Limit`, whose `LastEvaluatedKey` is the cursor
+    /// - DynamoDB may return a `LastEvaluatedKey` even when no items remain, so
+    ///   the last page can be empty
+    async fn get_invitations_by_creator_id_paginated(
+        &self,
+        creator_id: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Page<Invitation>> {
+        let start_key = match cursor {
+            Some(cursor) => Some(
+                decode_cursor(cursor, CursorScope::CreatedInvitations, creator_id)?
+                    .into_iter()
+                    .map(|(k, v)| (k, AttributeValue::S(v)))
+                    .collect::<HashMap<_, _>>(),
+            ),
+            None => None,
+        };
+
+        let request = self
+            .client
+            .query()
+            .table_name(&self.table_name)
+            .index_name(GSI_CREATOR_ID)
+            .key_condition_expression("creatorId = :creatorId")
+            .expression_attribute_values(":creatorId", AttributeValue::S(creator_id.to_string()))
+            .limit(i32::try_from(limit).unwrap_or(i32::MAX))
+            .set_exclusive_start_key(start_key);
+        let result = retry_with_backoff("query", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(|e| map_sdk_error("query", e))
+        })
+        .await?;
+
+        let invitations = result
+            .items()
+            .iter()
+            .map(|item| from_item(item.clone()).map_err(StoreError::from))
+            .collect::<Result<Vec<Invitation>>>()?;
+
+        let next_cursor = result
+            .last_evaluated_key()
+            .map(|key| {
+                let key = key
+                    .iter()
+                    .filter_map(|(k, v)| v.as_s().ok().map(|v| (k.clone(), v.clone())))
+                    .collect();
+                encode_cursor(&key, CursorScope::CreatedInvitations, creator_id)
+            })
+            .transpose()?;
+
+        Ok(Page {
+            items: invitations,
+            next_cursor,
+        })
+    }
+}
+
+/// Filter for locked boxes that still have shards awaiting acceptance
+fn locked_boxes_filter() -> (
+    String,
+    HashMap<String, String>,
+    HashMap<String, AttributeValue>,
+) {
+    let expr_attr_names = HashMap::from([
+        ("#is_locked".to_string(), "isLocked".to_string()),
+        ("#all_accepted".to_string(), "allShardsAccepted".to_string()),
+    ]);
+    let expr_attr_values = HashMap::from([
+        (":locked".to_string(), AttributeValue::Bool(true)),
+        (":true".to_string(), AttributeValue::Bool(true)),
+    ]);
+    (
+        format!("#is_locked = :locked AND {}", NOT_ALL_ACCEPTED_FILTER),
+        expr_attr_names,
+        expr_attr_values,
+    )
 }
 
 // Helper functions for DynamoDB error mapping
 fn map_get_dynamo_error(err: SdkError<GetItemError>, id: &str) -> StoreError {
     match err {
-        SdkError::ServiceError(ref service_err) => {
-            if let GetItemError::ResourceNotFoundException(_) = service_err.err() {
-                StoreError::NotFound(format!("Box not found: {}", id))
-            } else {
-                StoreError::InternalError(format!("DynamoDB get_item error: {}", err))
-            }
+        SdkError::ServiceError(ref service_err)
+            if matches!(
+                service_err.err(),
+                GetItemError::ResourceNotFoundException(_)
+            ) =>
+        {
+            StoreError::NotFound(format!("Box not found: {}", id))
         }
-        _ => StoreError::InternalError(format!("DynamoDB get_item error: {}", err)),
+        _ => map_sdk_error("get_item", err),
     }
 }
 
 fn map_delete_dynamo_error(err: SdkError<DeleteItemError>) -> StoreError {
-    StoreError::InternalError(format!("DynamoDB delete_item error: {}", err))
+    map_sdk_error("delete_item", err)
 }
 
 fn map_query_dynamo_error(err: SdkError<QueryError>) -> StoreError {
-    StoreError::InternalError(format!("DynamoDB query error: {}", err))
+    map_sdk_error("query", err)
 }
 
 fn map_scan_dynamo_error(err: SdkError<ScanError>) -> StoreError {
-    StoreError::InternalError(format!("DynamoDB scan error: {}", err))
+    map_sdk_error("scan", err)
 }
 
 // PUSH TOKEN STORE
@@ -598,7 +1626,7 @@ pub struct DynamoPushTokenStore {
 impl DynamoPushTokenStore {
     /// Creates a new DynamoDB push token store
     pub async fn new() -> Self {
-        let config = aws_config::defaults(BehaviorVersion::latest()).load().await;
+        let config = dynamo_config().await;
         let client = Client::new(&config);
 
         let table_name = env::var("DYNAMODB_PUSH_TOKENS_TABLE")
@@ -619,13 +1647,19 @@ impl super::PushTokenStore for DynamoPushTokenStore {
     async fn save_push_token(&self, token: PushToken) -> Result<PushToken> {
         let item = to_item(&token)?;
 
-        self.client
+        let request = self
+            .client
             .put_item()
             .table_name(&self.table_name)
-            .set_item(Some(item))
-            .send()
-            .await
-            .map_err(|e| map_dynamo_error("put_item", e))?;
+            .set_item(Some(item));
+        retry_with_backoff("put_item", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(|e| map_sdk_error("put_item", e))
+        })
+        .await?;
 
         Ok(token)
     }
@@ -633,14 +1667,19 @@ impl super::PushTokenStore for DynamoPushTokenStore {
     async fn get_push_token(&self, user_id: &str) -> Result<Option<PushToken>> {
         let key = HashMap::from([("userId".to_string(), AttributeValue::S(user_id.to_string()))]);
 
-        let response = self
+        let request = self
             .client
             .get_item()
             .table_name(&self.table_name)
-            .set_key(Some(key))
-            .send()
-            .await
-            .map_err(|e| map_dynamo_error("get_item", e))?;
+            .set_key(Some(key));
+        let response = retry_with_backoff("get_item", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(|e| map_sdk_error("get_item", e))
+        })
+        .await?;
 
         match response.item() {
             Some(item) => {
@@ -670,13 +1709,18 @@ impl super::PushTokenStore for DynamoPushTokenStore {
                 .map_err(|e| StoreError::InternalError(format!("Failed to build keys: {}", e)))?,
         )]);
 
-        let response = self
+        let request = self
             .client
             .batch_get_item()
-            .set_request_items(Some(request_items))
-            .send()
-            .await
-            .map_err(|e| map_dynamo_error("batch_get_item", e))?;
+            .set_request_items(Some(request_items));
+        let response = retry_with_backoff("batch_get_item", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(|e| map_sdk_error("batch_get_item", e))
+        })
+        .await?;
 
         let mut tokens = Vec::new();
         if let Some(responses) = response.responses() {
@@ -694,16 +1738,441 @@ impl super::PushTokenStore for DynamoPushTokenStore {
     async fn delete_push_token(&self, user_id: &str) -> Result<()> {
         let key = HashMap::from([("userId".to_string(), AttributeValue::S(user_id.to_string()))]);
 
+        let request = self
+            .client
+            .delete_item()
+            .table_name(&self.table_name)
+            .set_key(Some(key));
+        retry_with_backoff("delete_item", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(|e| map_sdk_error("delete_item", e))
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+// PUSH FAILURE STORE
+
+/// DynamoDB store for pushes that could not be delivered. Failures are keyed
+/// by ID, with a `boxId-index` GSI (sorted by timestamp) for looking up the
+/// ones about a box.
+pub struct DynamoPushFailureStore {
+    client: Client,
+    table_name: String,
+}
+
+impl DynamoPushFailureStore {
+    /// Creates a new DynamoDB push failure store
+    pub async fn new() -> Self {
+        let config = dynamo_config().await;
+        let client = Client::new(&config);
+
+        let table_name = env::var("DYNAMODB_PUSH_FAILURES_TABLE")
+            .unwrap_or_else(|_| PUSH_FAILURE_TABLE_NAME.to_string());
+
+        Self { client, table_name }
+    }
+
+    /// Creates a new store with the specified client and table name (for testing)
+    #[allow(dead_code)]
+    pub fn with_client_and_table(client: Client, table_name: String) -> Self {
+        Self { client, table_name }
+    }
+}
+
+#[async_trait]
+impl super::PushFailureStore for DynamoPushFailureStore {
+    async fn record_push_failure(&self, failure: PushFailure) -> Result<PushFailure> {
+        let item = to_item(&failure)?;
+
+        let request = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item));
+        retry_with_backoff("put_item", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(|e| map_sdk_error("put_item", e))
+        })
+        .await?;
+
+        Ok(failure)
+    }
+
+    async fn get_push_failures_for_box(&self, box_id: &str) -> Result<Vec<PushFailure>> {
+        let mut failures = Vec::new();
+        let mut start_key = None;
+        loop {
+            let request = self
+                .client
+                .query()
+                .table_name(&self.table_name)
+                .index_name(GSI_FAILURE_BOX_ID)
+                .key_condition_expression("boxId = :box_id")
+                .expression_attribute_values(":box_id", AttributeValue::S(box_id.to_string()))
+                .set_exclusive_start_key(start_key);
+            let response = retry_with_backoff("query", || async {
+                request
+                    .clone()
+                    .send()
+                    .await
+                    .map_err(|e| map_sdk_error("query", e))
+            })
+            .await?;
+
+            for item in response.items() {
+                failures.push(from_item(item.clone())?);
+            }
+            start_key = response.last_evaluated_key;
+            if start_key.is_none() {
+                return Ok(failures);
+            }
+        }
+    }
+}
+
+// POISON MESSAGE STORE
+
+/// DynamoDB store for event messages a consumer couldn't parse, keyed by SNS
+/// message ID
+pub struct DynamoPoisonMessageStore {
+    client: Client,
+    table_name: String,
+}
+
+impl DynamoPoisonMessageStore {
+    /// Creates a new DynamoDB poison message store
+    pub async fn new() -> Self {
+        let config = dynamo_config().await;
+        let client = Client::new(&config);
+
+        let table_name = env::var("DYNAMODB_POISON_MESSAGES_TABLE")
+            .unwrap_or_else(|_| POISON_MESSAGE_TABLE_NAME.to_string());
+
+        Self { client, table_name }
+    }
+
+    /// Creates a new store with the specified client and table name (for testing)
+    #[allow(dead_code)]
+    pub fn with_client_and_table(client: Client, table_name: String) -> Self {
+        Self { client, table_name }
+    }
+}
+
+#[async_trait]
+impl super::PoisonMessageStore for DynamoPoisonMessageStore {
+    async fn record_poison_message(&self, message: PoisonMessage) -> Result<PoisonMessage> {
+        let item = to_item(&message)?;
+
+        let request = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item));
+        retry_with_backoff("put_item", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(|e| map_sdk_error("put_item", e))
+        })
+        .await?;
+
+        Ok(message)
+    }
+
+    async fn get_poison_message(&self, message_id: &str) -> Result<PoisonMessage> {
+        let key = HashMap::from([(
+            "messageId".to_string(),
+            AttributeValue::S(message_id.to_string()),
+        )]);
+
+        let request = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .set_key(Some(key));
+        let response = retry_with_backoff("get_item", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(|e| map_sdk_error("get_item", e))
+        })
+        .await?;
+
+        let item = response.item().ok_or_else(|| {
+            StoreError::NotFound(format!("Poison message {} not found", message_id))
+        })?;
+        let message: PoisonMessage = from_item(item.clone())?;
+
+        Ok(message)
+    }
+}
+
+// SHARD CHALLENGE STORE
+
+/// DynamoDB store of step-up challenges. The table's TTL is set on
+/// `expiresAt`, so stale challenges are eventually removed; callers check the
+/// expiry themselves until then.
+pub struct DynamoShardChallengeStore {
+    client: Client,
+    table_name: String,
+}
+
+impl DynamoShardChallengeStore {
+    /// Creates a new DynamoDB shard challenge store
+    pub async fn new() -> Self {
+        let config = dynamo_config().await;
+        let client = Client::new(&config);
+
+        let table_name = env::var("DYNAMODB_SHARD_CHALLENGES_TABLE")
+            .unwrap_or_else(|_| SHARD_CHALLENGE_TABLE_NAME.to_string());
+
+        Self { client, table_name }
+    }
+
+    /// Creates a new store with the specified client and table name (for testing)
+    #[allow(dead_code)]
+    pub fn with_client_and_table(client: Client, table_name: String) -> Self {
+        Self { client, table_name }
+    }
+}
+
+#[async_trait]
+impl super::ShardChallengeStore for DynamoShardChallengeStore {
+    async fn create_challenge(&self, challenge: ShardChallenge) -> Result<ShardChallenge> {
+        let item = to_item(&challenge)?;
+
         self.client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item))
+            .condition_expression("attribute_not_exists(challenge)")
+            .send()
+            .await
+            .map_err(|e| map_sdk_error("put_item", e))?;
+
+        Ok(challenge)
+    }
+
+    async fn take_challenge(&self, challenge: &str) -> Result<Option<ShardChallenge>> {
+        let key = HashMap::from([(
+            "challenge".to_string(),
+            AttributeValue::S(challenge.to_string()),
+        )]);
+
+        let response = self
+            .client
             .delete_item()
             .table_name(&self.table_name)
             .set_key(Some(key))
+            .return_values(ReturnValue::AllOld)
+            .send()
+            .await
+            .map_err(|e| map_sdk_error("delete_item", e))?;
+
+        match response.attributes {
+            Some(item) if !item.is_empty() => {
+                let challenge: ShardChallenge = from_item(item)?;
+                Ok(Some(challenge))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+// IDEMPOTENCY STORE
+// IDEMPOTENCY STORE
+
+/// DynamoDB store for idempotency keys. The table's TTL is set on
+/// `expiresAt`, so expired keys are eventually removed by DynamoDB; until
+/// then they are treated as absent.
+pub struct DynamoIdempotencyStore {
+    client: Client,
+    table_name: String,
+}
+
+impl DynamoIdempotencyStore {
+    /// Creates a store for the table named by `DYNAMODB_IDEMPOTENCY_TABLE`,
+    /// or None when idempotency keys aren't configured
+    pub async fn from_env() -> Option<Self> {
+        let table_name = env::var("DYNAMODB_IDEMPOTENCY_TABLE").ok()?;
+        let config = dynamo_config().await;
+        Some(Self {
+            client: Client::new(&config),
+            table_name,
+        })
+    }
+
+    /// Creates a new store with the specified client and table name (for testing)
+    #[allow(dead_code)]
+    pub fn with_client_and_table(client: Client, table_name: String) -> Self {
+        Self { client, table_name }
+    }
+
+    fn key(idempotency_key: &str) -> HashMap<String, AttributeValue> {
+        HashMap::from([(
+            "idempotencyKey".to_string(),
+            AttributeValue::S(idempotency_key.to_string()),
+        )])
+    }
+}
+
+#[async_trait]
+impl super::IdempotencyStore for DynamoIdempotencyStore {
+    async fn insert_record(&self, record: IdempotencyRecord) -> Result<Option<IdempotencyRecord>> {
+        let item = to_item(&record)?;
+
+        let result = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(item))
+            .condition_expression("attribute_not_exists(idempotencyKey) OR expiresAt < :now")
+            .expression_attribute_values(
+                ":now",
+                AttributeValue::N(Utc::now().timestamp().to_string()),
+            )
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(None),
+            Err(SdkError::ServiceError(service_err))
+                if service_err.err().is_conditional_check_failed_exception() =>
+            {
+                let response = self
+                    .client
+                    .get_item()
+                    .table_name(&self.table_name)
+                    .set_key(Some(Self::key(&record.idempotency_key)))
+                    .consistent_read(true)
+                    .send()
+                    .await
+                    .map_err(|e| map_sdk_error("get_item", e))?;
+                let existing = response
+                    .item
+                    .ok_or_else(|| {
+                        StoreError::InternalError(format!(
+                            "Idempotency key vanished after conflict: {}",
+                            record.idempotency_key
+                        ))
+                    })
+                    .and_then(|item| Ok(from_item(item)?))?;
+                Ok(Some(existing))
+            }
+            Err(e) => Err(map_sdk_error("put_item", e)),
+        }
+    }
+
+    async fn complete_record(&self, idempotency_key: &str, resource_id: &str) -> Result<()> {
+        self.client
+            .update_item()
+            .table_name(&self.table_name)
+            .set_key(Some(Self::key(idempotency_key)))
+            .update_expression("SET resourceId = :resource_id")
+            .condition_expression("attribute_exists(idempotencyKey)")
+            .expression_attribute_values(":resource_id", AttributeValue::S(resource_id.to_string()))
             .send()
             .await
-            .map_err(|e| map_dynamo_error("delete_item", e))?;
+            .map_err(|e| map_sdk_error("update_item", e))?;
 
         Ok(())
     }
+
+    async fn delete_record(&self, idempotency_key: &str) -> Result<()> {
+        let request = self
+            .client
+            .delete_item()
+            .table_name(&self.table_name)
+            .set_key(Some(Self::key(idempotency_key)));
+        retry_with_backoff("delete_item", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(|e| map_sdk_error("delete_item", e))
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+// NOTIFICATION THROTTLE STORE
+
+/// DynamoDB store of when each throttled notification was last sent. The
+/// table's TTL is set on `expiresAt`, so entries go away once their window
+/// has passed.
+pub struct DynamoNotificationThrottleStore {
+    client: Client,
+    table_name: String,
+}
+
+impl DynamoNotificationThrottleStore {
+    /// Creates a new DynamoDB notification throttle store
+    pub async fn new() -> Self {
+        let config = dynamo_config().await;
+        let client = Client::new(&config);
+
+        let table_name = env::var("DYNAMODB_NOTIFICATION_THROTTLE_TABLE")
+            .unwrap_or_else(|_| NOTIFICATION_THROTTLE_TABLE_NAME.to_string());
+
+        Self { client, table_name }
+    }
+
+    /// Creates a new store with the specified client and table name (for testing)
+    #[allow(dead_code)]
+    pub fn with_client_and_table(client: Client, table_name: String) -> Self {
+        Self { client, table_name }
+    }
+}
+
+#[async_trait]
+impl super::NotificationThrottleStore for DynamoNotificationThrottleStore {
+    async fn try_claim(&self, key: &str, now: DateTime<Utc>, window: Duration) -> Result<bool> {
+        // Conditional put, so two events racing for the same window can't
+        // both send
+        let result = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .item("throttleKey", AttributeValue::S(key.to_string()))
+            .item(
+                "lastSentAt",
+                AttributeValue::N(now.timestamp_millis().to_string()),
+            )
+            .item(
+                "expiresAt",
+                AttributeValue::N((now + window).timestamp().to_string()),
+            )
+            .condition_expression("attribute_not_exists(throttleKey) OR lastSentAt <= :cutoff")
+            .expression_attribute_values(
+                ":cutoff",
+                AttributeValue::N((now - window).timestamp_millis().to_string()),
+            )
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(SdkError::ServiceError(service_err))
+                if service_err.err().is_conditional_check_failed_exception() =>
+            {
+                Ok(false)
+            }
+            Err(e) => Err(map_sdk_error("put_item", e)),
+        }
+    }
 }
 
 // Builder pattern alternative
@@ -742,7 +2211,7 @@ impl DynamoInvitationStoreBuilder {
     }
 
     pub async fn build(self) -> DynamoInvitationStore {
-        let config = aws_config::defaults(BehaviorVersion::latest()).load().await;
+        let config = dynamo_config().await;
         let client = Client::new(&config);
 
         let table_name = self.table_name.unwrap_or_else(|| {
@@ -1,10 +1,46 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
 
-use crate::error::Result;
-use crate::models::{BoxRecord, GuardianStatus, Invitation, PushToken};
+use crate::error::{Result, StoreError};
+use crate::models::{
+    BoxRecord, IdempotencyRecord, Invitation, PoisonMessage, PushFailure, PushToken, ShardChallenge,
+};
+use log::warn;
+use std::sync::Arc;
 
 // Expose the DynamoDB store module
+pub mod compression;
 pub mod dynamo;
+pub mod pagination;
+pub mod retry;
+pub mod watch;
+
+pub use pagination::Page;
+pub use retry::retry_with_backoff;
+
+/// Read-modify-write cycles `BoxStore::mutate_box` runs before letting a
+/// version conflict through
+pub const MAX_MUTATE_ATTEMPTS: u32 = 3;
+
+/// Largest `TotalSegments` DynamoDB accepts for a parallel scan
+pub const MAX_SCAN_SEGMENTS: u32 = 1_000_000;
+
+/// Checks a parallel scan segment the way DynamoDB would
+pub fn validate_scan_segment(segment: u32, total_segments: u32) -> Result<()> {
+    if total_segments == 0 || total_segments > MAX_SCAN_SEGMENTS {
+        return Err(StoreError::ValidationError(format!(
+            "total_segments must be between 1 and {}, got {}",
+            MAX_SCAN_SEGMENTS, total_segments
+        )));
+    }
+    if segment >= total_segments {
+        return Err(StoreError::ValidationError(format!(
+            "segment {} is out of range for {} segments",
+            segment, total_segments
+        )));
+    }
+    Ok(())
+}
 
 /// InvitationStore trait defining the interface for invitation storage implementations
 #[async_trait]
@@ -15,22 +51,49 @@ pub trait InvitationStore: Send + Sync + 'static {
     /// Gets an invitation by ID
     async fn get_invitation(&self, id: &str) -> Result<Invitation>;
 
+    /// Gets an invitation by ID with a strongly consistent read, so a write
+    /// made just before is always visible. Stores without eventual
+    /// consistency can rely on the default.
+    async fn get_invitation_consistent(&self, id: &str) -> Result<Invitation> {
+        self.get_invitation(id).await
+    }
+
     /// Gets an invitation by invite code
     async fn get_invitation_by_code(&self, invite_code: &str) -> Result<Invitation>;
 
     /// Updates an invitation
     async fn update_invitation(&self, invitation: Invitation) -> Result<Invitation>;
 
+    /// Sets an invitation's `expires_at`, but only while it is still pending:
+    /// neither opened nor linked to a user. Returns false without writing
+    /// when it no longer is, or no longer exists.
+    async fn expire_pending_invitation(&self, id: &str, expires_at: &str) -> Result<bool>;
+
     /// Deletes an invitation
     async fn delete_invitation(&self, id: &str) -> Result<()>;
 
     /// Gets all invitations for a box
     async fn get_invitations_by_box_id(&self, box_id: &str) -> Result<Vec<Invitation>>;
 
+    /// Counts the unexpired invitations for a box without fetching them
+    async fn count_invitations_by_box(&self, box_id: &str) -> Result<usize>;
+
     /// Gets all invitations created by a specific user
     async fn get_invitations_by_creator_id(&self, creator_id: &str) -> Result<Vec<Invitation>>;
+
+    /// Gets up to `limit` invitations created by a specific user, starting
+    /// after `cursor` from a previous page
+    async fn get_invitations_by_creator_id_paginated(
+        &self,
+        creator_id: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Page<Invitation>>;
 }
 
+/// Invitation store shared across handlers
+pub type SharedInvitationStore = Arc<dyn InvitationStore>;
+
 /// BoxStore trait defining the interface for box storage implementations
 #[async_trait]
 pub trait BoxStore: Send + Sync + 'static {
@@ -40,20 +103,127 @@ pub trait BoxStore: Send + Sync + 'static {
     /// Gets a box by ID
     async fn get_box(&self, id: &str) -> Result<BoxRecord>;
 
+    /// Gets a box by ID with a strongly consistent read. Use this when the box
+    /// is about to be modified, or was just written, so the read can't return
+    /// a stale copy. Stores without eventual consistency can rely on the default.
+    async fn get_box_consistent(&self, id: &str) -> Result<BoxRecord> {
+        self.get_box(id).await
+    }
+
+    /// Gets the boxes with the given ids in one batch, in the order requested.
+    /// Ids with no matching box are skipped.
+    async fn get_boxes_by_ids(&self, ids: &[String]) -> Result<Vec<BoxRecord>>;
+
     /// Gets all boxes owned by a user
     async fn get_boxes_by_owner(&self, owner_id: &str) -> Result<Vec<BoxRecord>>;
 
+    /// Counts the boxes owned by a user without fetching them
+    async fn count_boxes_by_owner(&self, owner_id: &str) -> Result<usize>;
+
+    /// Gets up to `limit` boxes owned by a user, starting after `cursor` from a
+    /// previous page
+    async fn get_boxes_by_owner_paginated(
+        &self,
+        owner_id: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Page<BoxRecord>>;
+
+    /// Gets the boxes owned by a user that were locked within the given range.
+    /// `locked_after` is inclusive and `locked_before` exclusive; unlocked
+    /// boxes are never returned.
+    async fn get_boxes_by_owner_locked_between(
+        &self,
+        owner_id: &str,
+        locked_after: Option<DateTime<Utc>>,
+        locked_before: Option<DateTime<Utc>>,
+    ) -> Result<Vec<BoxRecord>>;
+
+    /// Gets the ids of every box listing the given user as a guardian, in any status
+    async fn get_box_ids_for_guardian(&self, guardian_id: &str) -> Result<Vec<String>>;
+
     /// Gets all boxes where the given user is a guardian (with status not rejected)
     async fn get_boxes_by_guardian_id(&self, guardian_id: &str) -> Result<Vec<BoxRecord>>;
 
+    /// Gets up to `limit` boxes where the given user is a guardian (with status
+    /// not rejected), starting after `cursor` from a previous page
+    async fn get_guardian_boxes_paginated(
+        &self,
+        guardian_id: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Page<BoxRecord>>;
+
+    /// Writes the guardian-box lookup rows for up to `limit` boxes, starting
+    /// after `cursor` from a previous page, and returns the ids of the boxes
+    /// covered. Fills in the lookup for boxes written before it existed.
+    async fn backfill_guardian_index(
+        &self,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Page<String>>;
+
     /// Updates a box
     async fn update_box(&self, box_record: BoxRecord) -> Result<BoxRecord>;
 
+    /// Reads a box with a consistent read, applies `mutate` and writes it
+    /// back. When the write loses to a concurrent update the whole cycle
+    /// starts again from a fresh read, up to `MAX_MUTATE_ATTEMPTS` times, so
+    /// `mutate` must be safe to run more than once. An error from `mutate`
+    /// stops straight away without writing.
+    async fn mutate_box<F, E>(&self, id: &str, mut mutate: F) -> std::result::Result<BoxRecord, E>
+    where
+        Self: Sized,
+        F: FnMut(&mut BoxRecord) -> std::result::Result<(), E> + Send,
+        E: From<StoreError> + Send,
+    {
+        let mut attempt = 1;
+        loop {
+            let mut box_record = self.get_box_consistent(id).await?;
+            mutate(&mut box_record)?;
+            match self.update_box(box_record).await {
+                Err(StoreError::VersionConflict(msg)) if attempt < MAX_MUTATE_ATTEMPTS => {
+                    warn!(
+                        "Box {} changed while updating it ({}), retry {}/{}",
+                        id,
+                        msg,
+                        attempt,
+                        MAX_MUTATE_ATTEMPTS - 1
+                    );
+                    attempt += 1;
+                }
+                result => return result.map_err(E::from),
+            }
+        }
+    }
+
     /// Deletes a box
     async fn delete_box(&self, id: &str) -> Result<()>;
 
-    /// Scans all locked boxes (for reminder service)
+    /// Scans locked boxes that still have shards awaiting acceptance (for
+    /// reminder service)
     async fn scan_locked_boxes(&self) -> Result<Vec<BoxRecord>>;
+
+    /// Scans one segment of the locked boxes with shards awaiting acceptance,
+    /// so callers can run `total_segments` scans in parallel. The segments are
+    /// disjoint and together cover the same boxes as `scan_locked_boxes`.
+    async fn scan_locked_boxes_segment(
+        &self,
+        segment: u32,
+        total_segments: u32,
+    ) -> Result<Vec<BoxRecord>>;
+
+    /// Scans locked boxes with shards awaiting acceptance that were locked at
+    /// or after `cutoff`, optionally leaving out
+    /// boxes whose shards have already been deleted. Boxes locked earlier but
+    /// updated since `cutoff` are included too: a guardian added after locking
+    /// receives their shard later than `locked_at`, and that write bumps
+    /// `updated_at`.
+    async fn scan_locked_boxes_since(
+        &self,
+        cutoff: DateTime<Utc>,
+        exclude_shards_deleted: bool,
+    ) -> Result<Vec<BoxRecord>>;
 }
 
 /// PushTokenStore trait defining the interface for push token storage
@@ -72,39 +242,72 @@ pub trait PushTokenStore: Send + Sync + 'static {
     async fn delete_push_token(&self, user_id: &str) -> Result<()>;
 }
 
-// Box store utility functions
-pub fn convert_to_guardian_box(
-    box_rec: &BoxRecord,
-    user_id: &str,
-) -> Option<crate::models::GuardianBox> {
-    if let Some(guardian) = box_rec
-        .guardians
-        .iter()
-        .find(|g| g.id == user_id && g.status != GuardianStatus::Rejected)
-    {
-        let pending = matches!(
-            guardian.status,
-            GuardianStatus::Invited | GuardianStatus::Viewed
-        );
-        let is_lead = guardian.lead_guardian;
-        Some(crate::models::GuardianBox {
-            id: box_rec.id.clone(),
-            name: box_rec.name.clone(),
-            description: box_rec.description.clone(),
-            is_locked: box_rec.is_locked,
-            created_at: box_rec.created_at.clone(),
-            updated_at: box_rec.updated_at.clone(),
-            owner_id: box_rec.owner_id.clone(),
-            owner_name: box_rec.owner_name.clone(),
-            unlock_instructions: box_rec.unlock_instructions.clone(),
-            unlock_request: box_rec.unlock_request.clone(),
-            pending_guardian_approval: Some(pending),
-            guardians_count: box_rec.guardians.len(),
-            is_lead_guardian: is_lead,
-            documents: box_rec.documents.clone(),
-            guardians: box_rec.guardians.clone(),
-        })
-    } else {
-        None
-    }
+/// Push token store shared across handlers
+pub type SharedPushTokenStore = Arc<dyn PushTokenStore>;
+
+/// PushFailureStore trait defining the interface for dead-lettered pushes
+#[async_trait]
+pub trait PushFailureStore: Send + Sync + 'static {
+    /// Records a push that could not be delivered
+    async fn record_push_failure(&self, failure: PushFailure) -> Result<PushFailure>;
+
+    /// Gets the failed pushes about a box, oldest first
+    async fn get_push_failures_for_box(&self, box_id: &str) -> Result<Vec<PushFailure>>;
+}
+
+/// Push failure store shared across handlers
+pub type SharedPushFailureStore = Arc<dyn PushFailureStore>;
+
+/// PoisonMessageStore trait defining the interface for dead-lettered event messages
+#[async_trait]
+pub trait PoisonMessageStore: Send + Sync + 'static {
+    /// Records a message that could not be parsed
+    async fn record_poison_message(&self, message: PoisonMessage) -> Result<PoisonMessage>;
+
+    /// Gets a recorded message by its SNS message ID
+    async fn get_poison_message(&self, message_id: &str) -> Result<PoisonMessage>;
+}
+
+/// IdempotencyStore trait defining the interface for idempotency key storage
+#[async_trait]
+pub trait IdempotencyStore: Send + Sync + 'static {
+    /// Saves the record unless an unexpired one already holds its key, in
+    /// which case that record is returned and nothing is written
+    async fn insert_record(&self, record: IdempotencyRecord) -> Result<Option<IdempotencyRecord>>;
+
+    /// Records the resource created by the request that claimed the key
+    async fn complete_record(&self, idempotency_key: &str, resource_id: &str) -> Result<()>;
+
+    /// Frees a key whose request failed, so a retry can claim it again
+    async fn delete_record(&self, idempotency_key: &str) -> Result<()>;
 }
+
+/// Idempotency store shared across handlers
+pub type SharedIdempotencyStore = Arc<dyn IdempotencyStore>;
+
+/// ShardChallengeStore trait defining the interface for step-up challenges
+/// issued before a shard fetch
+#[async_trait]
+pub trait ShardChallengeStore: Send + Sync + 'static {
+    /// Saves a newly issued challenge
+    async fn create_challenge(&self, challenge: ShardChallenge) -> Result<ShardChallenge>;
+
+    /// Removes the challenge and returns it, or None if there is no such
+    /// challenge. Removal is atomic, so a challenge can be redeemed only once.
+    async fn take_challenge(&self, challenge: &str) -> Result<Option<ShardChallenge>>;
+}
+
+/// Shard challenge store shared across handlers
+pub type SharedShardChallengeStore = Arc<dyn ShardChallengeStore>;
+
+/// NotificationThrottleStore trait defining the interface for throttling
+/// repeated notifications
+#[async_trait]
+pub trait NotificationThrottleStore: Send + Sync + 'static {
+    /// Records a send for `key` at `now`, unless one was already recorded
+    /// less than `window` earlier. Returns whether this send may go ahead.
+    async fn try_claim(&self, key: &str, now: DateTime<Utc>, window: Duration) -> Result<bool>;
+}
+
+/// Notification throttle store shared across handlers
+pub type SharedNotificationThrottleStore = Arc<dyn NotificationThrottleStore>;
@@ -0,0 +1,137 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use log::warn;
+use ring::hmac;
+use std::collections::HashMap;
+use std::env;
+
+use crate::error::{Result, StoreError};
+
+// Shorter secrets are too easy to brute-force offline from a captured cursor
+const MIN_SECRET_LEN: usize = 32;
+
+// Lets tests page without configuring a secret. Builds without the
+// `test_utils` feature always need `CURSOR_SIGNING_SECRET`.
+#[cfg(any(test, feature = "test_utils"))]
+const TEST_CURSOR_SECRET: &str = "lockbox-test-cursor-signing-secret-0123456789";
+
+/// The list a cursor pages through. A cursor only works for the list that
+/// issued it, so one from a user's owned boxes can't be replayed against the
+/// boxes they guard or the invitations they sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorScope {
+    OwnedBoxes,
+    GuardianBoxes,
+    CreatedInvitations,
+    /// The admin backfill of the guardian-box lookup, which pages through
+    /// every box rather than one user's
+    GuardianIndexBackfill,
+}
+
+impl CursorScope {
+    fn as_str(self) -> &'static str {
+        match self {
+            CursorScope::OwnedBoxes => "owned_boxes",
+            CursorScope::GuardianBoxes => "guardian_boxes",
+            CursorScope::CreatedInvitations => "created_invitations",
+            CursorScope::GuardianIndexBackfill => "guardian_index_backfill",
+        }
+    }
+}
+
+/// One page of results from a paginated store query
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Opaque cursor for the next page, `None` once the last page is reached
+    pub next_cursor: Option<String>,
+}
+
+/// Checks a cursor signing secret is set and long enough. Services call this
+/// with `CURSOR_SIGNING_SECRET` at startup, so a missing secret fails the
+/// deploy rather than the first paginated request.
+pub fn validate_cursor_secret(secret: Option<&str>) -> Result<()> {
+    match secret {
+        Some(secret) if secret.len() >= MIN_SECRET_LEN => Ok(()),
+        _ => Err(StoreError::InternalError(format!(
+            "CURSOR_SIGNING_SECRET must be set to at least {} bytes",
+            MIN_SECRET_LEN
+        ))),
+    }
+}
+
+/// [`validate_cursor_secret`] for the secret in the environment
+pub fn check_cursor_secret() -> Result<()> {
+    validate_cursor_secret(env::var("CURSOR_SIGNING_SECRET").ok().as_deref())
+}
+
+/// Key cursors are signed with, from `CURSOR_SIGNING_SECRET`
+fn cursor_key() -> Result<hmac::Key> {
+    let secret = env::var("CURSOR_SIGNING_SECRET").ok();
+    #[cfg(any(test, feature = "test_utils"))]
+    let secret = secret.or_else(|| Some(TEST_CURSOR_SECRET.to_string()));
+    validate_cursor_secret(secret.as_deref())?;
+    let secret = secret.unwrap_or_default();
+    Ok(hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()))
+}
+
+// What a cursor's signature covers: the list it pages through, the user it
+// was issued to and its key
+fn signed_message(scope: CursorScope, user_id: &str, payload: &str) -> Vec<u8> {
+    [
+        scope.as_str().as_bytes(),
+        &[0],
+        user_id.as_bytes(),
+        &[0],
+        payload.as_bytes(),
+    ]
+    .concat()
+}
+
+/// Encodes the key attributes of the last item served into an opaque cursor,
+/// signed so it only works for `user_id` on the list `scope` and can't be
+/// edited
+pub fn encode_cursor(
+    key: &HashMap<String, String>,
+    scope: CursorScope,
+    user_id: &str,
+) -> Result<String> {
+    // Serializing a map of strings can't fail
+    let json = serde_json::to_vec(key).unwrap_or_default();
+    let payload = URL_SAFE_NO_PAD.encode(json);
+    let tag = hmac::sign(&cursor_key()?, &signed_message(scope, user_id, &payload));
+    Ok(format!(
+        "{}.{}",
+        payload,
+        URL_SAFE_NO_PAD.encode(tag.as_ref())
+    ))
+}
+
+/// Decodes a cursor produced by [`encode_cursor`] for the same list and user.
+/// Cursors that were changed, or issued to someone else or for another list,
+/// are rejected.
+pub fn decode_cursor(
+    cursor: &str,
+    scope: CursorScope,
+    user_id: &str,
+) -> Result<HashMap<String, String>> {
+    let invalid = || StoreError::ValidationError("Invalid pagination cursor".into());
+
+    let (payload, tag) = cursor.split_once('.').ok_or_else(invalid)?;
+    let tag = URL_SAFE_NO_PAD.decode(tag).map_err(|_| invalid())?;
+    hmac::verify(
+        &cursor_key()?,
+        &signed_message(scope, user_id, payload),
+        &tag,
+    )
+    .map_err(|_| {
+        warn!(
+            "Rejected a pagination cursor not signed for user {} on {}",
+            user_id,
+            scope.as_str()
+        );
+        invalid()
+    })?;
+
+    let json = URL_SAFE_NO_PAD.decode(payload).map_err(|_| invalid())?;
+    serde_json::from_slice(&json).map_err(|_| invalid())
+}
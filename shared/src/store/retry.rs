@@ -0,0 +1,50 @@
+use std::future::Future;
+use std::time::Duration;
+
+use log::warn;
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::error::Result;
+
+/// Retries after the first attempt before a throttled call gives up
+pub const MAX_RETRIES: u32 = 3;
+const BASE_DELAY_MS: u64 = 50;
+const MAX_DELAY_MS: u64 = 1_000;
+
+/// Runs `call`, retrying retryable store errors (throttling and transient
+/// backend failures) with exponential backoff and full jitter. Any other
+/// error, such as a failed condition check, is returned straight away.
+pub async fn retry_with_backoff<T, F, Fut>(operation: &str, mut call: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match call().await {
+            Err(err) if err.is_retryable() && attempt < MAX_RETRIES => {
+                attempt += 1;
+                let wait = backoff_delay(attempt);
+                warn!(
+                    "DynamoDB {} failed ({}), retry {}/{} in {:?}",
+                    operation, err, attempt, MAX_RETRIES, wait
+                );
+                tokio::time::sleep(wait).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Full jitter: a random delay up to `BASE_DELAY_MS * 2^attempt`, capped
+fn backoff_delay(attempt: u32) -> Duration {
+    let ceiling = BASE_DELAY_MS
+        .saturating_mul(1 << attempt.min(16))
+        .min(MAX_DELAY_MS);
+    let mut bytes = [0u8; 8];
+    let random = match SystemRandom::new().fill(&mut bytes) {
+        Ok(()) => u64::from_le_bytes(bytes),
+        Err(_) => ceiling,
+    };
+    Duration::from_millis(random % (ceiling + 1))
+}
@@ -0,0 +1,67 @@
+//! In-process notifications of box writes.
+//!
+//! Both box stores report every successful update or delete here, so a
+//! long-polling request can wake as soon as its box changes instead of
+//! re-reading it on a timer. Only writes made by the same process are seen: a
+//! write handled by another Lambda instance or container goes unnoticed, and
+//! the waiter simply runs into its timeout.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use tokio::sync::watch;
+
+// Channels exist only while someone is watching the box
+static WATCHERS: OnceLock<Mutex<HashMap<String, watch::Sender<()>>>> = OnceLock::new();
+
+fn watchers() -> &'static Mutex<HashMap<String, watch::Sender<()>>> {
+    WATCHERS.get_or_init(Default::default)
+}
+
+/// Wakes everyone watching `box_id`
+pub fn notify_box_changed(box_id: &str) {
+    if let Some(sender) = watchers().lock().unwrap().get(box_id) {
+        sender.send_replace(());
+    }
+}
+
+/// Starts watching a box for writes. Subscribe before reading the box, so a
+/// write landing between the read and the wait isn't missed.
+pub fn watch_box(box_id: &str) -> BoxWatcher {
+    let receiver = watchers()
+        .lock()
+        .unwrap()
+        .entry(box_id.to_string())
+        .or_insert_with(|| watch::channel(()).0)
+        .subscribe();
+    BoxWatcher {
+        box_id: box_id.to_string(),
+        receiver,
+    }
+}
+
+/// A subscription to writes to one box
+pub struct BoxWatcher {
+    box_id: String,
+    receiver: watch::Receiver<()>,
+}
+
+impl BoxWatcher {
+    /// Waits for the next write to the box since the last call
+    pub async fn changed(&mut self) {
+        // The sender outlives every receiver, so this can't fail
+        let _ = self.receiver.changed().await;
+    }
+}
+
+impl Drop for BoxWatcher {
+    fn drop(&mut self) {
+        let mut watchers = watchers().lock().unwrap();
+        // Our own receiver is still counted until this returns
+        if watchers
+            .get(&self.box_id)
+            .is_some_and(|sender| sender.receiver_count() <= 1)
+        {
+            watchers.remove(&self.box_id);
+        }
+    }
+}
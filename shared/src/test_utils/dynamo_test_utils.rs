@@ -4,6 +4,8 @@ use aws_sdk_dynamodb::types::{
 };
 use aws_sdk_dynamodb::Client;
 use std::error::Error;
+
+use crate::store::dynamo::DynamoBoxStore;
 // Use log macros, but ensure test_logging::init_test_logging() is called in test files
 use log::{debug, error, info};
 // Reference to our test logging initialization
@@ -419,6 +421,74 @@ pub async fn create_box_table(client: &Client, table_name: &str) -> Result<(), B
         tokio::time::sleep(std::time::Duration::from_secs(1)).await;
     }
 
+    info!("Table '{}' is ready for testing!", table_name);
+
+    // DynamoBoxStore::with_client_and_table expects its guardian-box lookup alongside
+    create_guardian_box_table(client, &DynamoBoxStore::guardian_box_table_for(table_name)).await
+}
+
+// Helper to create the guardian-box lookup table for testing
+pub async fn create_guardian_box_table(
+    client: &Client,
+    table_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    info!(
+        "Creating guardian-box table '{}' for testing...",
+        table_name
+    );
+
+    // Recreate the table so every run starts empty
+    let tables = client.list_tables().send().await?;
+    if tables.table_names().contains(&table_name.to_string()) {
+        client.delete_table().table_name(table_name).send().await?;
+        loop {
+            let tables = client.list_tables().send().await?;
+            if !tables.table_names().contains(&table_name.to_string()) {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        }
+    }
+
+    let mut create_table_req = client.create_table().table_name(table_name);
+    for (attr_name, key_type) in [("guardianId", KeyType::Hash), ("boxId", KeyType::Range)] {
+        create_table_req = create_table_req
+            .attribute_definitions(
+                AttributeDefinition::builder()
+                    .attribute_name(attr_name)
+                    .attribute_type(ScalarAttributeType::S)
+                    .build()?,
+            )
+            .key_schema(
+                KeySchemaElement::builder()
+                    .attribute_name(attr_name)
+                    .key_type(key_type)
+                    .build()?,
+            );
+    }
+    create_table_req
+        .provisioned_throughput(
+            ProvisionedThroughput::builder()
+                .read_capacity_units(5)
+                .write_capacity_units(5)
+                .build()?,
+        )
+        .send()
+        .await?;
+
+    // Wait for the table to become ACTIVE
+    loop {
+        let resp = client
+            .describe_table()
+            .table_name(table_name)
+            .send()
+            .await?;
+        if resp.table().and_then(|t| t.table_status()) == Some(&TableStatus::Active) {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+    }
+
     info!("Table '{}' is ready for testing!", table_name);
     Ok(())
 }
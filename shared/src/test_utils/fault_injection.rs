@@ -0,0 +1,59 @@
+use std::sync::Mutex;
+
+use crate::error::{Result, StoreError};
+
+/// Scripted store failures for exercising handler error paths. The mock
+/// stores consult it once at the start of every trait call.
+#[derive(Default)]
+pub struct FaultInjector {
+    next_error: Mutex<Option<StoreError>>,
+    // Only taken by writes that replace an existing item
+    next_update_error: Mutex<Option<StoreError>>,
+    // Calls left to succeed before every call fails
+    calls_until_failure: Mutex<Option<usize>>,
+}
+
+impl FaultInjector {
+    /// Fail the next store call with `error`; later calls are unaffected
+    pub fn set_next_error(&self, error: StoreError) {
+        *self.next_error.lock().unwrap() = Some(error);
+    }
+
+    /// Fail the next update call with `error`, letting reads and creates
+    /// through; later updates are unaffected
+    pub fn fail_next_update(&self, error: StoreError) {
+        *self.next_update_error.lock().unwrap() = Some(error);
+    }
+
+    /// Let `n` more store calls succeed, then fail every call after that
+    pub fn fail_after_n_calls(&self, n: usize) {
+        *self.calls_until_failure.lock().unwrap() = Some(n);
+    }
+
+    /// Returns the scripted error for an update call, if any
+    pub fn check_update(&self) -> Result<()> {
+        if let Some(error) = self.next_update_error.lock().unwrap().take() {
+            return Err(error);
+        }
+        self.check()
+    }
+
+    /// Returns the scripted error for this call, if any
+    pub fn check(&self) -> Result<()> {
+        if let Some(error) = self.next_error.lock().unwrap().take() {
+            return Err(error);
+        }
+
+        let mut calls_until_failure = self.calls_until_failure.lock().unwrap();
+        match calls_until_failure.as_mut() {
+            Some(0) => Err(StoreError::InternalError(
+                "Injected store failure".to_string(),
+            )),
+            Some(remaining) => {
+                *remaining -= 1;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
@@ -1,15 +1,23 @@
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
 use std::sync::Mutex;
 
 use crate::error::{Result, StoreError};
 use crate::models::{BoxRecord, GuardianStatus};
-use crate::store::BoxStore;
+use crate::store::pagination::{decode_cursor, encode_cursor, CursorScope, Page};
+use crate::store::watch::notify_box_changed;
+use crate::store::{validate_scan_segment, BoxStore};
+use crate::test_utils::fault_injection::FaultInjector;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 /// MockBoxStore is a simple in-memory implementation of BoxStore for testing
 pub struct MockBoxStore {
     boxes: Mutex<HashMap<String, BoxRecord>>,
     owner_indexes: Mutex<HashMap<String, Vec<String>>>, // owner_id -> [box_id]
+    guardian_indexes: Mutex<HashMap<String, BTreeSet<String>>>, // guardian_id -> {box_id}
+    faults: FaultInjector,
 }
 
 impl MockBoxStore {
@@ -18,15 +26,102 @@ impl MockBoxStore {
         Self {
             boxes: Mutex::new(HashMap::new()),
             owner_indexes: Mutex::new(HashMap::new()),
+            guardian_indexes: Mutex::new(HashMap::new()),
+            faults: FaultInjector::default(),
         }
     }
 
+    /// Fail the next store call with `error`
+    pub fn set_next_error(&self, error: StoreError) {
+        self.faults.set_next_error(error);
+    }
+
+    /// Let `n` more store calls succeed, then fail every call after that
+    pub fn fail_after_n_calls(&self, n: usize) {
+        self.faults.fail_after_n_calls(n);
+    }
+
+    /// Make the next `update_box` call fail with `error` without touching the
+    /// stored box. Later updates behave normally again.
+    pub fn fail_next_update(&self, error: StoreError) {
+        self.faults.fail_next_update(error);
+    }
+
+    fn owner_boxes(&self, owner_id: &str) -> Vec<BoxRecord> {
+        // Lock boxes first to maintain consistent lock ordering with other methods
+        let boxes = self.boxes.lock().unwrap();
+
+        let owner_boxes = self
+            .owner_indexes
+            .lock()
+            .unwrap()
+            .get(owner_id)
+            .cloned()
+            .unwrap_or_default();
+
+        owner_boxes
+            .iter()
+            .filter_map(|id| boxes.get(id).cloned())
+            .collect()
+    }
+
+    fn guardian_boxes(&self, guardian_id: &str) -> Vec<BoxRecord> {
+        let box_ids = self
+            .guardian_indexes
+            .lock()
+            .unwrap()
+            .get(guardian_id)
+            .cloned()
+            .unwrap_or_default();
+        let boxes = self.boxes.lock().unwrap();
+
+        box_ids
+            .iter()
+            .filter_map(|id| boxes.get(id))
+            .filter(|b| {
+                b.guardians.iter().any(|guardian| {
+                    guardian.id == guardian_id && guardian.status != GuardianStatus::Rejected
+                })
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Mirrors the DynamoDB guardian-box lookup: drops the box from guardians
+    /// no longer on it and adds it for guardians that are, skipping invited
+    /// placeholders without a user id
+    fn sync_guardian_index(&self, old: Option<&BoxRecord>, new: Option<&BoxRecord>) {
+        let mut guardian_indexes = self.guardian_indexes.lock().unwrap();
+        if let Some(old) = old {
+            for guardian in &old.guardians {
+                if let Some(box_ids) = guardian_indexes.get_mut(&guardian.id) {
+                    box_ids.remove(&old.id);
+                }
+            }
+        }
+        if let Some(new) = new {
+            for guardian in new.guardians.iter().filter(|g| !g.id.is_empty()) {
+                guardian_indexes
+                    .entry(guardian.id.clone())
+                    .or_default()
+                    .insert(new.id.clone());
+            }
+        }
+    }
+
+    /// Drops every guardian-box lookup row, leaving the store as it was for
+    /// boxes written before the lookup existed
+    pub fn clear_guardian_index(&self) {
+        self.guardian_indexes.lock().unwrap().clear();
+    }
+
     /// Create a MockBoxStore with initial test data
     pub fn with_data(box_records: Vec<BoxRecord>) -> Self {
         let store = Self::new();
 
         // Initialize with data
-        for box_record in box_records {
+        for mut box_record in box_records {
+            box_record.refresh_all_shards_accepted();
             let owner_id = box_record.owner_id.clone();
             let box_id = box_record.id.clone();
 
@@ -44,7 +139,11 @@ impl MockBoxStore {
                 .unwrap()
                 .entry(owner_id)
                 .or_insert_with(Vec::new)
-                .push(box_id);
+                .push(box_id.clone());
+
+            // Add to guardian index
+            let box_record = store.boxes.lock().unwrap().get(&box_id).cloned();
+            store.sync_guardian_index(None, box_record.as_ref());
         }
 
         store
@@ -53,15 +152,24 @@ impl MockBoxStore {
 
 #[async_trait]
 impl BoxStore for MockBoxStore {
-    async fn create_box(&self, box_record: BoxRecord) -> Result<BoxRecord> {
+    async fn create_box(&self, mut box_record: BoxRecord) -> Result<BoxRecord> {
+        self.faults.check()?;
+        box_record.refresh_all_shards_accepted();
         let box_id = box_record.id.clone();
         let owner_id = box_record.owner_id.clone();
 
-        // Store the box
-        self.boxes
-            .lock()
-            .unwrap()
-            .insert(box_id.clone(), box_record.clone());
+        // Store the box, refusing to replace one with the same id as the
+        // DynamoDB store does
+        {
+            let mut boxes = self.boxes.lock().unwrap();
+            if boxes.contains_key(&box_id) {
+                return Err(StoreError::AlreadyExists(format!(
+                    "Box already exists: id={}",
+                    box_id
+                )));
+            }
+            boxes.insert(box_id.clone(), box_record.clone());
+        }
 
         // Update owner index
         self.owner_indexes
@@ -71,10 +179,14 @@ impl BoxStore for MockBoxStore {
             .or_insert_with(Vec::new)
             .push(box_id);
 
+        // Update guardian index
+        self.sync_guardian_index(None, Some(&box_record));
+
         Ok(box_record)
     }
 
     async fn get_box(&self, id: &str) -> Result<BoxRecord> {
+        self.faults.check()?;
         self.boxes
             .lock()
             .unwrap()
@@ -83,43 +195,198 @@ impl BoxStore for MockBoxStore {
             .ok_or_else(|| StoreError::NotFound(format!("Box with id {} not found", id)))
     }
 
-    async fn get_boxes_by_owner(&self, owner_id: &str) -> Result<Vec<BoxRecord>> {
-        // Lock boxes first to maintain consistent lock ordering with other methods
+    async fn get_boxes_by_ids(&self, ids: &[String]) -> Result<Vec<BoxRecord>> {
+        self.faults.check()?;
         let boxes = self.boxes.lock().unwrap();
+        let mut seen = HashSet::new();
+        Ok(ids
+            .iter()
+            .filter(|id| seen.insert(id.as_str()))
+            .filter_map(|id| boxes.get(id).cloned())
+            .collect())
+    }
 
-        let owner_boxes = self
+    async fn get_boxes_by_owner(&self, owner_id: &str) -> Result<Vec<BoxRecord>> {
+        self.faults.check()?;
+        Ok(self.owner_boxes(owner_id))
+    }
+
+    async fn count_boxes_by_owner(&self, owner_id: &str) -> Result<usize> {
+        self.faults.check()?;
+        let boxes = self.boxes.lock().unwrap();
+        Ok(self
             .owner_indexes
             .lock()
             .unwrap()
             .get(owner_id)
-            .cloned()
-            .unwrap_or_default();
+            .map_or(0, |ids| {
+                ids.iter().filter(|id| boxes.contains_key(*id)).count()
+            }))
+    }
 
-        let result: Vec<BoxRecord> = owner_boxes
-            .iter()
-            .filter_map(|id| boxes.get(id).cloned())
-            .collect();
+    async fn get_boxes_by_owner_paginated(
+        &self,
+        owner_id: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Page<BoxRecord>> {
+        self.faults.check()?;
+        let after_id = match cursor {
+            Some(cursor) => decode_cursor(cursor, CursorScope::OwnedBoxes, owner_id)?.remove("id"),
+            None => None,
+        };
 
-        Ok(result)
+        // Page in box id order so a cursor stays valid as boxes are added
+        let mut owner_boxes = self.owner_boxes(owner_id);
+        owner_boxes.sort_by(|a, b| a.id.cmp(&b.id));
+        owner_boxes.retain(|b| after_id.as_ref().is_none_or(|after| &b.id > after));
+
+        let has_more = owner_boxes.len() > limit;
+        owner_boxes.truncate(limit);
+        let next_cursor = match owner_boxes.last() {
+            Some(last) if has_more => Some(encode_cursor(
+                &HashMap::from([
+                    ("ownerId".to_string(), owner_id.to_string()),
+                    ("id".to_string(), last.id.clone()),
+                ]),
+                CursorScope::OwnedBoxes,
+                owner_id,
+            )?),
+            _ => None,
+        };
+
+        Ok(Page {
+            items: owner_boxes,
+            next_cursor,
+        })
+    }
+
+    async fn get_boxes_by_owner_locked_between(
+        &self,
+        owner_id: &str,
+        locked_after: Option<DateTime<Utc>>,
+        locked_before: Option<DateTime<Utc>>,
+    ) -> Result<Vec<BoxRecord>> {
+        self.faults.check()?;
+        Ok(self
+            .owner_boxes(owner_id)
+            .into_iter()
+            .filter(|b| {
+                let Some(locked_at) = b
+                    .locked_at
+                    .as_deref()
+                    .filter(|_| b.is_locked)
+                    .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+                else {
+                    return false;
+                };
+                locked_after.is_none_or(|after| locked_at >= after)
+                    && locked_before.is_none_or(|before| locked_at < before)
+            })
+            .collect())
+    }
+
+    async fn get_box_ids_for_guardian(&self, guardian_id: &str) -> Result<Vec<String>> {
+        self.faults.check()?;
+        Ok(self
+            .guardian_indexes
+            .lock()
+            .unwrap()
+            .get(guardian_id)
+            .map(|box_ids| box_ids.iter().cloned().collect())
+            .unwrap_or_default())
     }
 
     async fn get_boxes_by_guardian_id(&self, guardian_id: &str) -> Result<Vec<BoxRecord>> {
-        let boxes = self.boxes.lock().unwrap();
+        self.faults.check()?;
+        Ok(self.guardian_boxes(guardian_id))
+    }
+
+    async fn get_guardian_boxes_paginated(
+        &self,
+        guardian_id: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Page<BoxRecord>> {
+        self.faults.check()?;
+        let after_id = match cursor {
+            Some(cursor) => {
+                decode_cursor(cursor, CursorScope::GuardianBoxes, guardian_id)?.remove("boxId")
+            }
+            None => None,
+        };
 
-        let guardian_boxes: Vec<BoxRecord> = boxes
+        // The index is ordered by box id, like the DynamoDB table's sort key
+        let mut guardian_boxes = self.guardian_boxes(guardian_id);
+        guardian_boxes.retain(|b| after_id.as_ref().is_none_or(|after| &b.id > after));
+
+        let has_more = guardian_boxes.len() > limit;
+        guardian_boxes.truncate(limit);
+        let next_cursor = match guardian_boxes.last() {
+            Some(last) if has_more => Some(encode_cursor(
+                &HashMap::from([
+                    ("guardianId".to_string(), guardian_id.to_string()),
+                    ("boxId".to_string(), last.id.clone()),
+                ]),
+                CursorScope::GuardianBoxes,
+                guardian_id,
+            )?),
+            _ => None,
+        };
+
+        Ok(Page {
+            items: guardian_boxes,
+            next_cursor,
+        })
+    }
+
+    async fn backfill_guardian_index(
+        &self,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Page<String>> {
+        self.faults.check()?;
+        let after_id = match cursor {
+            Some(cursor) => {
+                decode_cursor(cursor, CursorScope::GuardianIndexBackfill, "")?.remove("id")
+            }
+            None => None,
+        };
+
+        let mut page: Vec<BoxRecord> = self
+            .boxes
+            .lock()
+            .unwrap()
             .values()
-            .filter(|b| {
-                b.guardians.iter().any(|guardian| {
-                    guardian.id == guardian_id && guardian.status != GuardianStatus::Rejected
-                })
-            })
+            .filter(|b| after_id.as_ref().is_none_or(|after| &b.id > after))
             .cloned()
             .collect();
+        page.sort_by(|a, b| a.id.cmp(&b.id));
+        let has_more = page.len() > limit;
+        page.truncate(limit);
+
+        for box_record in &page {
+            self.sync_guardian_index(None, Some(box_record));
+        }
 
-        Ok(guardian_boxes)
+        let next_cursor = match page.last() {
+            Some(last) if has_more => Some(encode_cursor(
+                &HashMap::from([("id".to_string(), last.id.clone())]),
+                CursorScope::GuardianIndexBackfill,
+                "",
+            )?),
+            _ => None,
+        };
+
+        Ok(Page {
+            items: page.into_iter().map(|b| b.id).collect(),
+            next_cursor,
+        })
     }
 
     async fn update_box(&self, box_record: BoxRecord) -> Result<BoxRecord> {
+        self.faults.check_update()?;
+
         let box_id = box_record.id.clone();
         let new_owner_id = box_record.owner_id.clone();
 
@@ -143,6 +410,7 @@ impl BoxStore for MockBoxStore {
         // Create a new box with incremented version
         let mut updated_box = box_record.clone();
         updated_box.version += 1;
+        updated_box.refresh_all_shards_accepted();
 
         // Update owner indexes if the owner has changed
         if current_box.owner_id != new_owner_id {
@@ -166,36 +434,93 @@ impl BoxStore for MockBoxStore {
             .unwrap()
             .insert(box_id, updated_box.clone());
 
+        // Update guardian index for any guardians added or removed
+        self.sync_guardian_index(Some(&current_box), Some(&updated_box));
+        notify_box_changed(&updated_box.id);
+
         Ok(updated_box)
     }
 
     async fn delete_box(&self, id: &str) -> Result<()> {
-        // Check if box exists and get owner_id
-        let owner_id = {
-            let boxes = self.boxes.lock().unwrap();
-            let box_record = boxes
-                .get(id)
-                .ok_or_else(|| StoreError::NotFound(format!("Box with id {} not found", id)))?;
-            box_record.owner_id.clone()
-        };
-
-        // Remove from boxes
-        self.boxes.lock().unwrap().remove(id);
+        self.faults.check()?;
+        // Check if box exists and remove it
+        let box_record = self
+            .boxes
+            .lock()
+            .unwrap()
+            .remove(id)
+            .ok_or_else(|| StoreError::NotFound(format!("Box with id {} not found", id)))?;
 
         // Update owner index
-        if let Some(box_ids) = self.owner_indexes.lock().unwrap().get_mut(&owner_id) {
+        if let Some(box_ids) = self
+            .owner_indexes
+            .lock()
+            .unwrap()
+            .get_mut(&box_record.owner_id)
+        {
             box_ids.retain(|box_id| box_id != id);
         }
 
+        // Update guardian index
+        self.sync_guardian_index(Some(&box_record), None);
+        notify_box_changed(id);
+
         Ok(())
     }
 
     async fn scan_locked_boxes(&self) -> Result<Vec<BoxRecord>> {
+        self.faults.check()?;
         let boxes = self.boxes.lock().unwrap();
 
-        let locked_boxes: Vec<BoxRecord> =
-            boxes.values().filter(|b| b.is_locked).cloned().collect();
+        let locked_boxes: Vec<BoxRecord> = boxes
+            .values()
+            .filter(|b| b.is_locked && !b.all_shards_accepted)
+            .cloned()
+            .collect();
 
         Ok(locked_boxes)
     }
+
+    async fn scan_locked_boxes_segment(
+        &self,
+        segment: u32,
+        total_segments: u32,
+    ) -> Result<Vec<BoxRecord>> {
+        self.faults.check()?;
+        validate_scan_segment(segment, total_segments)?;
+        let boxes = self.boxes.lock().unwrap();
+
+        Ok(boxes
+            .values()
+            .filter(|b| b.is_locked && !b.all_shards_accepted)
+            .filter(|b| segment_for(&b.id, total_segments) == segment)
+            .cloned()
+            .collect())
+    }
+
+    async fn scan_locked_boxes_since(
+        &self,
+        cutoff: DateTime<Utc>,
+        exclude_shards_deleted: bool,
+    ) -> Result<Vec<BoxRecord>> {
+        self.faults.check()?;
+        let boxes = self.boxes.lock().unwrap();
+
+        let locked_boxes: Vec<BoxRecord> = boxes
+            .values()
+            .filter(|b| b.is_locked && !b.all_shards_accepted)
+            .filter(|b| b.locked_or_updated_since(cutoff))
+            .filter(|b| !exclude_shards_deleted || b.shards_deleted_at.is_none())
+            .cloned()
+            .collect();
+
+        Ok(locked_boxes)
+    }
+}
+
+/// Stands in for DynamoDB's hash partitioning of a parallel scan
+fn segment_for(id: &str, total_segments: u32) -> u32 {
+    let mut hasher = DefaultHasher::new();
+    id.hash(&mut hasher);
+    (hasher.finish() % u64::from(total_segments)) as u32
 }
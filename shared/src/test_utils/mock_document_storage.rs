@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::error::{Result, StoreError};
+use crate::storage::DocumentStorage;
+
+/// In-memory DocumentStorage for testing
+#[derive(Default)]
+pub struct MockDocumentStorage {
+    objects: Mutex<HashMap<String, (Vec<u8>, String)>>, // key -> (content, content type)
+}
+
+impl MockDocumentStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The stored content and content type for a key, if any
+    pub fn object(&self, key: &str) -> Option<(Vec<u8>, String)> {
+        self.objects.lock().unwrap().get(key).cloned()
+    }
+
+    /// Keys of all stored objects
+    pub fn keys(&self) -> Vec<String> {
+        self.objects.lock().unwrap().keys().cloned().collect()
+    }
+}
+
+#[async_trait]
+impl DocumentStorage for MockDocumentStorage {
+    async fn put_document(&self, key: &str, content: Vec<u8>, content_type: &str) -> Result<()> {
+        self.objects
+            .lock()
+            .unwrap()
+            .insert(key.to_string(), (content, content_type.to_string()));
+        Ok(())
+    }
+
+    async fn get_document(&self, key: &str) -> Result<Vec<u8>> {
+        self.object(key)
+            .map(|(content, _)| content)
+            .ok_or_else(|| StoreError::NotFound(format!("Document content not found: {}", key)))
+    }
+
+    async fn delete_document(&self, key: &str) -> Result<()> {
+        self.objects.lock().unwrap().remove(key);
+        Ok(())
+    }
+}
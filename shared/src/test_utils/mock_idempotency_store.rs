@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::error::{Result, StoreError};
+use crate::models::IdempotencyRecord;
+use crate::store::IdempotencyStore;
+
+/// In-memory IdempotencyStore for testing
+#[derive(Default)]
+pub struct MockIdempotencyStore {
+    records: Mutex<HashMap<String, IdempotencyRecord>>,
+}
+
+impl MockIdempotencyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The stored record for a scoped key, if any
+    pub fn record(&self, idempotency_key: &str) -> Option<IdempotencyRecord> {
+        self.records.lock().unwrap().get(idempotency_key).cloned()
+    }
+}
+
+#[async_trait]
+impl IdempotencyStore for MockIdempotencyStore {
+    async fn insert_record(&self, record: IdempotencyRecord) -> Result<Option<IdempotencyRecord>> {
+        let mut records = self.records.lock().unwrap();
+        if let Some(existing) = records.get(&record.idempotency_key) {
+            if existing.expires_at >= Utc::now().timestamp() {
+                return Ok(Some(existing.clone()));
+            }
+        }
+        records.insert(record.idempotency_key.clone(), record);
+        Ok(None)
+    }
+
+    async fn complete_record(&self, idempotency_key: &str, resource_id: &str) -> Result<()> {
+        let mut records = self.records.lock().unwrap();
+        let record = records.get_mut(idempotency_key).ok_or_else(|| {
+            StoreError::NotFound(format!("Idempotency key not found: {}", idempotency_key))
+        })?;
+        record.resource_id = Some(resource_id.to_string());
+        Ok(())
+    }
+
+    async fn delete_record(&self, idempotency_key: &str) -> Result<()> {
+        self.records.lock().unwrap().remove(idempotency_key);
+        Ok(())
+    }
+}
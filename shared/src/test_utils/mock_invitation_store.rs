@@ -3,7 +3,9 @@ use std::sync::Mutex;
 
 use crate::error::{Result, StoreError};
 use crate::models::Invitation;
-use crate::store::InvitationStore;
+use crate::store::pagination::{decode_cursor, encode_cursor, CursorScope};
+use crate::store::{InvitationStore, Page};
+use crate::test_utils::fault_injection::FaultInjector;
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 
@@ -13,6 +15,7 @@ pub struct MockInvitationStore {
     invitation_codes: Mutex<HashMap<String, String>>, // Maps invite_code -> id
     error_mode: bool,
     enforce_expiry: bool,
+    faults: FaultInjector,
 }
 
 impl MockInvitationStore {
@@ -23,6 +26,7 @@ impl MockInvitationStore {
             invitation_codes: Mutex::new(HashMap::new()),
             error_mode: false,
             enforce_expiry: false,
+            faults: FaultInjector::default(),
         }
     }
 
@@ -59,9 +63,20 @@ impl MockInvitationStore {
             invitation_codes: Mutex::new(HashMap::new()),
             error_mode: true,
             enforce_expiry: false,
+            faults: FaultInjector::default(),
         }
     }
 
+    /// Fail the next store call with `error`
+    pub fn set_next_error(&self, error: StoreError) {
+        self.faults.set_next_error(error);
+    }
+
+    /// Let `n` more store calls succeed, then fail every call after that
+    pub fn fail_after_n_calls(&self, n: usize) {
+        self.faults.fail_after_n_calls(n);
+    }
+
     /// Create a new MockInvitationStore with expiry enforcement
     pub fn new_with_expiry() -> Self {
         Self {
@@ -69,13 +84,48 @@ impl MockInvitationStore {
             invitation_codes: Mutex::new(HashMap::new()),
             error_mode: false,
             enforce_expiry: true,
+            faults: FaultInjector::default(),
         }
     }
+
+    // Returns invitations for the box, optionally filtering out expired ones
+    fn box_invitations(&self, box_id: &str) -> Vec<Invitation> {
+        let now = Utc::now();
+        self.invitations
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|inv| inv.box_id == box_id)
+            .filter_map(|inv| {
+                if self.enforce_expiry {
+                    // parse expiration and include if not expired
+                    DateTime::parse_from_rfc3339(&inv.expires_at)
+                        .ok()
+                        .map(|dt| dt.with_timezone(&Utc))
+                        .filter(|dt| &now <= dt)
+                        .map(|_| inv.clone())
+                } else {
+                    Some(inv.clone())
+                }
+            })
+            .collect()
+    }
+
+    fn creator_invitations(&self, creator_id: &str) -> Vec<Invitation> {
+        self.invitations
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|inv| inv.creator_id == creator_id)
+            .cloned()
+            .collect()
+    }
 }
 
 #[async_trait]
 impl InvitationStore for MockInvitationStore {
     async fn create_invitation(&self, invitation: Invitation) -> Result<Invitation> {
+        self.faults.check()?;
         if self.error_mode {
             return Err(StoreError::InternalError("Mock".into()));
         }
@@ -103,6 +153,7 @@ impl InvitationStore for MockInvitationStore {
     }
 
     async fn get_invitation(&self, id: &str) -> Result<Invitation> {
+        self.faults.check()?;
         if self.error_mode {
             return Err(StoreError::InternalError("Mock".into()));
         }
@@ -127,6 +178,7 @@ impl InvitationStore for MockInvitationStore {
     }
 
     async fn get_invitation_by_code(&self, invite_code: &str) -> Result<Invitation> {
+        self.faults.check()?;
         if self.error_mode {
             return Err(StoreError::InternalError("Mock".into()));
         }
@@ -165,6 +217,7 @@ impl InvitationStore for MockInvitationStore {
     }
 
     async fn update_invitation(&self, invitation: Invitation) -> Result<Invitation> {
+        self.faults.check()?;
         if self.error_mode {
             return Err(StoreError::InternalError("Mock".into()));
         }
@@ -198,7 +251,22 @@ impl InvitationStore for MockInvitationStore {
         Ok(invitation)
     }
 
+    async fn expire_pending_invitation(&self, id: &str, expires_at: &str) -> Result<bool> {
+        self.faults.check()?;
+        if self.error_mode {
+            return Err(StoreError::InternalError("Mock".into()));
+        }
+        match self.invitations.lock().unwrap().get_mut(id) {
+            Some(invitation) if !invitation.opened && invitation.linked_user_id.is_none() => {
+                invitation.expires_at = expires_at.to_string();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
     async fn delete_invitation(&self, id: &str) -> Result<()> {
+        self.faults.check()?;
         if self.error_mode {
             return Err(StoreError::InternalError("Mock".into()));
         }
@@ -213,46 +281,69 @@ impl InvitationStore for MockInvitationStore {
     }
 
     async fn get_invitations_by_box_id(&self, box_id: &str) -> Result<Vec<Invitation>> {
+        self.faults.check()?;
         if self.error_mode {
             return Err(StoreError::InternalError("Mock".into()));
         }
-        // Return invitations for the box, optionally filtering out expired
-        let now = Utc::now();
-        let invitations = self
-            .invitations
-            .lock()
-            .unwrap()
-            .values()
-            .filter(|inv| inv.box_id == box_id)
-            .filter_map(|inv| {
-                if self.enforce_expiry {
-                    // parse expiration and include if not expired
-                    DateTime::parse_from_rfc3339(&inv.expires_at)
-                        .ok()
-                        .map(|dt| dt.with_timezone(&Utc))
-                        .filter(|dt| &now <= dt)
-                        .map(|_| inv.clone())
-                } else {
-                    Some(inv.clone())
-                }
-            })
-            .collect();
-        Ok(invitations)
+        Ok(self.box_invitations(box_id))
+    }
+
+    async fn count_invitations_by_box(&self, box_id: &str) -> Result<usize> {
+        self.faults.check()?;
+        if self.error_mode {
+            return Err(StoreError::InternalError("Mock".into()));
+        }
+        // Same expiry rules as get_invitations_by_box_id
+        Ok(self.box_invitations(box_id).len())
     }
 
     async fn get_invitations_by_creator_id(&self, creator_id: &str) -> Result<Vec<Invitation>> {
+        self.faults.check()?;
         if self.error_mode {
             return Err(StoreError::InternalError("Mock".into()));
         }
-        let invitations = self
-            .invitations
-            .lock()
-            .unwrap()
-            .values()
-            .filter(|inv| inv.creator_id == creator_id)
-            .cloned()
-            .collect();
+        Ok(self.creator_invitations(creator_id))
+    }
 
-        Ok(invitations)
+    async fn get_invitations_by_creator_id_paginated(
+        &self,
+        creator_id: &str,
+        limit: usize,
+        cursor: Option<&str>,
+    ) -> Result<Page<Invitation>> {
+        self.faults.check()?;
+        if self.error_mode {
+            return Err(StoreError::InternalError("Mock".into()));
+        }
+        let after_id = match cursor {
+            Some(cursor) => {
+                decode_cursor(cursor, CursorScope::CreatedInvitations, creator_id)?.remove("id")
+            }
+            None => None,
+        };
+
+        // Page in id order so cursors stay stable between calls
+        let mut invitations = self.creator_invitations(creator_id);
+        invitations.sort_by(|a, b| a.id.cmp(&b.id));
+        invitations.retain(|inv| after_id.as_ref().is_none_or(|after| &inv.id > after));
+
+        let has_more = invitations.len() > limit;
+        invitations.truncate(limit);
+        let next_cursor = match invitations.last() {
+            Some(last) if has_more => Some(encode_cursor(
+                &HashMap::from([
+                    ("creatorId".to_string(), creator_id.to_string()),
+                    ("id".to_string(), last.id.clone()),
+                ]),
+                CursorScope::CreatedInvitations,
+                creator_id,
+            )?),
+            _ => None,
+        };
+
+        Ok(Page {
+            items: invitations,
+            next_cursor,
+        })
     }
 }
@@ -0,0 +1,59 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::envelope::{DataKey, KeyService};
+use crate::error::{Result, StoreError};
+use async_trait::async_trait;
+use uuid::Uuid;
+
+/// Stand-in for KMS: data keys are "wrapped" by XOR with a fixed master key,
+/// and every call is counted so tests can check KMS is actually used
+pub struct MockKeyService {
+    master_key: [u8; 32],
+    pub generate_calls: AtomicUsize,
+    pub decrypt_calls: AtomicUsize,
+}
+
+impl MockKeyService {
+    pub fn new() -> Self {
+        let mut master_key = [0u8; 32];
+        master_key[..16].copy_from_slice(Uuid::new_v4().as_bytes());
+        master_key[16..].copy_from_slice(Uuid::new_v4().as_bytes());
+        Self {
+            master_key,
+            generate_calls: AtomicUsize::new(0),
+            decrypt_calls: AtomicUsize::new(0),
+        }
+    }
+
+    fn xor_with_master(&self, key: &[u8]) -> Vec<u8> {
+        key.iter()
+            .zip(self.master_key.iter())
+            .map(|(k, m)| k ^ m)
+            .collect()
+    }
+}
+
+impl Default for MockKeyService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl KeyService for MockKeyService {
+    async fn generate_data_key(&self) -> Result<DataKey> {
+        self.generate_calls.fetch_add(1, Ordering::SeqCst);
+        let mut plaintext = Uuid::new_v4().as_bytes().to_vec();
+        plaintext.extend_from_slice(Uuid::new_v4().as_bytes());
+        let wrapped = self.xor_with_master(&plaintext);
+        Ok(DataKey { plaintext, wrapped })
+    }
+
+    async fn decrypt_data_key(&self, wrapped: &[u8]) -> Result<Vec<u8>> {
+        self.decrypt_calls.fetch_add(1, Ordering::SeqCst);
+        if wrapped.len() != self.master_key.len() {
+            return Err(StoreError::InternalError("Invalid wrapped key".into()));
+        }
+        Ok(self.xor_with_master(wrapped))
+    }
+}
@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::error::Result;
+use crate::store::NotificationThrottleStore;
+
+/// In-memory NotificationThrottleStore for testing
+#[derive(Default)]
+pub struct MockNotificationThrottleStore {
+    last_sent: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl MockNotificationThrottleStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl NotificationThrottleStore for MockNotificationThrottleStore {
+    async fn try_claim(&self, key: &str, now: DateTime<Utc>, window: Duration) -> Result<bool> {
+        let mut last_sent = self.last_sent.lock().unwrap();
+        if last_sent.get(key).is_some_and(|sent| now - *sent < window) {
+            return Ok(false);
+        }
+        last_sent.insert(key.to_string(), now);
+        Ok(true)
+    }
+}
@@ -0,0 +1,45 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::error::{Result, StoreError};
+use crate::models::PoisonMessage;
+use crate::store::PoisonMessageStore;
+
+/// In-memory PoisonMessageStore for testing
+#[derive(Default)]
+pub struct MockPoisonMessageStore {
+    messages: Mutex<Vec<PoisonMessage>>,
+}
+
+impl MockPoisonMessageStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every message recorded so far, oldest first
+    pub fn messages(&self) -> Vec<PoisonMessage> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl PoisonMessageStore for MockPoisonMessageStore {
+    async fn record_poison_message(&self, message: PoisonMessage) -> Result<PoisonMessage> {
+        let mut messages = self.messages.lock().unwrap();
+        // Keyed by message ID, like the table: a redelivery replaces the record
+        messages.retain(|m| m.message_id != message.message_id);
+        messages.push(message.clone());
+        Ok(message)
+    }
+
+    async fn get_poison_message(&self, message_id: &str) -> Result<PoisonMessage> {
+        self.messages
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|m| m.message_id == message_id)
+            .cloned()
+            .ok_or_else(|| StoreError::NotFound(format!("Poison message {} not found", message_id)))
+    }
+}
@@ -0,0 +1,43 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::error::Result;
+use crate::models::PushFailure;
+use crate::store::PushFailureStore;
+
+/// In-memory PushFailureStore for testing
+#[derive(Default)]
+pub struct MockPushFailureStore {
+    failures: Mutex<Vec<PushFailure>>,
+}
+
+impl MockPushFailureStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every failure recorded so far, oldest first
+    pub fn failures(&self) -> Vec<PushFailure> {
+        self.failures.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl PushFailureStore for MockPushFailureStore {
+    async fn record_push_failure(&self, failure: PushFailure) -> Result<PushFailure> {
+        self.failures.lock().unwrap().push(failure.clone());
+        Ok(failure)
+    }
+
+    async fn get_push_failures_for_box(&self, box_id: &str) -> Result<Vec<PushFailure>> {
+        Ok(self
+            .failures
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|f| f.box_id.as_deref() == Some(box_id))
+            .cloned()
+            .collect())
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::models::PushToken;
+use crate::push::{
+    ExpoPushTicket, ExpoPushTicketDetails, NotificationOptions, PushError, PushSender,
+};
+
+/// A push notification the mock was asked to send
+#[derive(Debug, Clone)]
+pub struct SentPush {
+    pub to: String,
+    pub title: String,
+    pub body: String,
+    pub data: Option<serde_json::Value>,
+    pub sound: Option<String>,
+    pub badge: Option<u32>,
+}
+
+/// PushSender for testing that records messages instead of sending them,
+/// answering each with an "ok" ticket unless its token was rejected. Silent
+/// pushes are recorded with an empty title and body and no sound or badge.
+#[derive(Default)]
+pub struct MockPushSender {
+    sent: Mutex<Vec<SentPush>>,
+    rejected: Mutex<HashMap<String, String>>, // push token -> Expo error code
+}
+
+impl MockPushSender {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every message sent so far, oldest first
+    pub fn sent(&self) -> Vec<SentPush> {
+        self.sent.lock().unwrap().clone()
+    }
+
+    /// Makes Expo refuse messages to `push_token` with the given error code
+    pub fn reject_token(&self, push_token: &str, error: &str) {
+        self.rejected
+            .lock()
+            .unwrap()
+            .insert(push_token.to_string(), error.to_string());
+    }
+
+    fn ticket(&self, push_token: &str, ticket_number: usize) -> ExpoPushTicket {
+        match self.rejected.lock().unwrap().get(push_token) {
+            Some(error) => ExpoPushTicket {
+                status: "error".into(),
+                id: None,
+                message: Some(format!("{} was refused", push_token)),
+                details: Some(ExpoPushTicketDetails {
+                    error: Some(error.clone()),
+                }),
+            },
+            None => ExpoPushTicket {
+                status: "ok".into(),
+                id: Some(format!("ticket-{}", ticket_number)),
+                message: None,
+                details: None,
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl PushSender for MockPushSender {
+    async fn send_push_notifications(
+        &self,
+        tokens: &[PushToken],
+        title: &str,
+        body: &str,
+        data: Option<serde_json::Value>,
+        options: &NotificationOptions,
+    ) -> Result<Vec<ExpoPushTicket>, PushError> {
+        let mut sent = self.sent.lock().unwrap();
+        Ok(tokens
+            .iter()
+            .map(|token| {
+                sent.push(SentPush {
+                    to: token.push_token.clone(),
+                    title: title.to_string(),
+                    body: body.to_string(),
+                    data: data.clone(),
+                    sound: options.sound.clone(),
+                    badge: options.badge,
+                });
+                self.ticket(&token.push_token, sent.len())
+            })
+            .collect())
+    }
+
+    async fn send_silent_push_notifications(
+        &self,
+        tokens: &[PushToken],
+        data: Option<serde_json::Value>,
+    ) -> Result<Vec<ExpoPushTicket>, PushError> {
+        let options = NotificationOptions {
+            sound: None,
+            badge: None,
+        };
+        self.send_push_notifications(tokens, "", "", data, &options)
+            .await
+    }
+}
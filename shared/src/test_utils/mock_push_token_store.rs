@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::error::Result;
+use crate::models::PushToken;
+use crate::store::PushTokenStore;
+
+/// In-memory PushTokenStore for testing
+#[derive(Default)]
+pub struct MockPushTokenStore {
+    tokens: Mutex<HashMap<String, PushToken>>, // user id -> token
+}
+
+impl MockPushTokenStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl PushTokenStore for MockPushTokenStore {
+    async fn save_push_token(&self, token: PushToken) -> Result<PushToken> {
+        self.tokens
+            .lock()
+            .unwrap()
+            .insert(token.user_id.clone(), token.clone());
+        Ok(token)
+    }
+
+    async fn get_push_token(&self, user_id: &str) -> Result<Option<PushToken>> {
+        Ok(self.tokens.lock().unwrap().get(user_id).cloned())
+    }
+
+    async fn get_push_tokens(&self, user_ids: &[String]) -> Result<Vec<PushToken>> {
+        let tokens = self.tokens.lock().unwrap();
+        Ok(user_ids
+            .iter()
+            .filter_map(|id| tokens.get(id).cloned())
+            .collect())
+    }
+
+    async fn delete_push_token(&self, user_id: &str) -> Result<()> {
+        self.tokens.lock().unwrap().remove(user_id);
+        Ok(())
+    }
+}
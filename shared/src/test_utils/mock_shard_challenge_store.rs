@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::error::{Result, StoreError};
+use crate::models::ShardChallenge;
+use crate::store::ShardChallengeStore;
+
+/// In-memory ShardChallengeStore for testing
+#[derive(Default)]
+pub struct MockShardChallengeStore {
+    challenges: Mutex<HashMap<String, ShardChallenge>>,
+}
+
+impl MockShardChallengeStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Challenges issued and not yet redeemed
+    pub fn challenges(&self) -> Vec<ShardChallenge> {
+        self.challenges.lock().unwrap().values().cloned().collect()
+    }
+}
+
+#[async_trait]
+impl ShardChallengeStore for MockShardChallengeStore {
+    async fn create_challenge(&self, challenge: ShardChallenge) -> Result<ShardChallenge> {
+        let mut challenges = self.challenges.lock().unwrap();
+        if challenges.contains_key(&challenge.challenge) {
+            return Err(StoreError::AlreadyExists(format!(
+                "Shard challenge {} already exists",
+                challenge.challenge
+            )));
+        }
+        challenges.insert(challenge.challenge.clone(), challenge.clone());
+        Ok(challenge)
+    }
+
+    async fn take_challenge(&self, challenge: &str) -> Result<Option<ShardChallenge>> {
+        Ok(self.challenges.lock().unwrap().remove(challenge))
+    }
+}
@@ -1,6 +1,17 @@
 // Test utilities for shared components
 pub mod dynamo_test_utils;
+pub mod fault_injection;
 pub mod http_test_utils;
 pub mod mock_box_store;
+pub mod mock_clock;
+pub mod mock_document_storage;
+pub mod mock_idempotency_store;
 pub mod mock_invitation_store;
+pub mod mock_key_service;
+pub mod mock_notification_throttle_store;
+pub mod mock_poison_message_store;
+pub mod mock_push_failure_store;
+pub mod mock_push_sender;
+pub mod mock_push_token_store;
+pub mod mock_shard_challenge_store;
 pub mod test_logging;
@@ -0,0 +1,88 @@
+use aws_sdk_dynamodb::types::AttributeValue;
+use serde_dynamo::{from_item, to_item};
+
+use crate::models::{BoxRecord, Document};
+use crate::store::compression::{
+    compress_documents, expand_documents, DOCUMENTS_COMPRESSION_THRESHOLD_BYTES,
+};
+
+fn box_with_documents(count: usize, content_len: usize) -> BoxRecord {
+    let now = crate::models::now_str();
+    BoxRecord {
+        id: "compressed-box".to_string(),
+        name: "Many Documents".to_string(),
+        description: "Large enough to compress".to_string(),
+        is_locked: false,
+        locked_at: None,
+        created_at: now.clone(),
+        updated_at: now.clone(),
+        owner_id: "owner_1".to_string(),
+        owner_name: None,
+        documents: (0..count)
+            .map(|i| Document {
+                id: format!("doc-{}", i),
+                title: format!("Document {}", i),
+                encrypted_content: Some("x".repeat(content_len)),
+                created_at: now.clone(),
+                content_type: Some("text/plain".to_string()),
+                size_bytes: Some(content_len as u64),
+                version: Some(1),
+                s3_key: None,
+            })
+            .collect(),
+        guardians: vec![],
+        unlock_instructions: None,
+        unlock_request: None,
+        version: 3,
+        shard_threshold: None,
+        shards_fetched: None,
+        total_shards: None,
+        shards_deleted_at: None,
+        all_shards_accepted: false,
+        last_unlock_request_at: None,
+        webhook_url: None,
+        webhook_secret: None,
+        require_step_up: false,
+    }
+}
+
+#[test]
+fn test_large_documents_round_trip_compressed() {
+    let box_record = box_with_documents(40, 4 * 1024);
+    let mut item = to_item(&box_record).unwrap();
+    compress_documents(&mut item, &box_record.documents).unwrap();
+
+    // Stored as a binary blob with the marker, well under the raw size
+    let Some(AttributeValue::B(blob)) = item.get("documents") else {
+        panic!("documents should be stored as binary");
+    };
+    assert!(blob.as_ref().len() < DOCUMENTS_COMPRESSION_THRESHOLD_BYTES);
+    assert_eq!(
+        item.get("documentsCompressed"),
+        Some(&AttributeValue::Bool(true))
+    );
+
+    let restored: BoxRecord = from_item(expand_documents(item).unwrap()).unwrap();
+    assert_eq!(restored.documents.len(), 40);
+    assert_eq!(restored.documents[7].id, "doc-7");
+    assert_eq!(
+        restored.documents[7].encrypted_content,
+        box_record.documents[7].encrypted_content
+    );
+    assert_eq!(restored.documents[7].version, Some(1));
+    assert_eq!(restored.version, 3);
+}
+
+#[test]
+fn test_small_and_legacy_documents_are_left_alone() {
+    let box_record = box_with_documents(2, 16);
+    let mut item = to_item(&box_record).unwrap();
+    compress_documents(&mut item, &box_record.documents).unwrap();
+
+    assert!(matches!(item.get("documents"), Some(AttributeValue::L(_))));
+    assert!(!item.contains_key("documentsCompressed"));
+
+    // An uncompressed item reads back unchanged
+    let restored: BoxRecord = from_item(expand_documents(item).unwrap()).unwrap();
+    assert_eq!(restored.documents.len(), 2);
+}
@@ -0,0 +1,76 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::envelope::{Envelope, ENVELOPE_PREFIX};
+use crate::test_utils::mock_key_service::MockKeyService;
+
+#[tokio::test]
+async fn test_envelope_round_trip() {
+    let key_service = Arc::new(MockKeyService::new());
+    let envelope = Envelope::new(key_service.clone());
+
+    let shard = "c2hhcmQtZzE=";
+    let sealed = envelope.seal_string(shard).await.unwrap();
+    assert!(sealed.starts_with(ENVELOPE_PREFIX));
+    assert!(!sealed.contains(shard));
+
+    assert_eq!(envelope.open_string(&sealed).await.unwrap(), shard);
+    assert_eq!(key_service.generate_calls.load(Ordering::SeqCst), 1);
+    assert_eq!(key_service.decrypt_calls.load(Ordering::SeqCst), 1);
+
+    // Each seal uses a fresh data key and nonce
+    let sealed_again = envelope.seal_string(shard).await.unwrap();
+    assert_ne!(sealed, sealed_again);
+}
+
+#[tokio::test]
+async fn test_envelope_passes_through_legacy_plaintext() {
+    let key_service = Arc::new(MockKeyService::new());
+    let envelope = Envelope::new(key_service.clone());
+
+    assert_eq!(
+        envelope.open_string("plain-old-shard").await.unwrap(),
+        "plain-old-shard"
+    );
+    assert_eq!(key_service.decrypt_calls.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn test_envelope_rejects_tampered_ciphertext() {
+    let envelope = Envelope::new(Arc::new(MockKeyService::new()));
+
+    let mut sealed = envelope.encrypt(b"secret shard").await.unwrap();
+    let last = sealed.len() - 1;
+    sealed[last] ^= 0x01;
+    assert!(envelope.decrypt(&sealed).await.is_err());
+
+    // Values sealed under a different master key can't be opened
+    let other = Envelope::new(Arc::new(MockKeyService::new()));
+    let foreign = other.seal_string("secret shard").await.unwrap();
+    assert!(envelope.open_string(&foreign).await.is_err());
+}
+
+#[tokio::test]
+async fn test_record_fields_share_one_data_key() {
+    let key_service = Arc::new(MockKeyService::new());
+    let envelope = Envelope::new(key_service.clone());
+
+    let mut sealer = envelope.record_sealer();
+    let mut sealed = Vec::new();
+    for shard in ["c2hhcmQtZzE=", "c2hhcmQtZzI=", "c2hhcmQtZzM="] {
+        sealed.push(sealer.seal_string(shard).await.unwrap());
+    }
+    assert_eq!(key_service.generate_calls.load(Ordering::SeqCst), 1);
+    // Values sealed under the same key still get their own nonces
+    assert_ne!(sealed[0], sealed[1]);
+
+    let mut opener = envelope.record_opener();
+    for value in &sealed {
+        opener.open_string(value).await.unwrap();
+    }
+    assert_eq!(
+        opener.open_string(&sealed[2]).await.unwrap(),
+        "c2hhcmQtZzM="
+    );
+    assert_eq!(key_service.decrypt_calls.load(Ordering::SeqCst), 1);
+}
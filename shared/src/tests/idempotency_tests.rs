@@ -0,0 +1,69 @@
+use chrono::Utc;
+use http::{HeaderMap, HeaderValue};
+
+use crate::idempotency::{claim, idempotency_key, request_hash, scoped_key, Claim};
+use crate::models::IdempotencyRecord;
+use crate::store::IdempotencyStore;
+use crate::test_utils::mock_idempotency_store::MockIdempotencyStore;
+
+#[tokio::test]
+async fn test_claim_outcomes() {
+    let store = MockIdempotencyStore::new();
+    let key = scoped_key("create_box", "user-1", "key-1");
+    let hash = request_hash(&serde_json::json!({ "name": "Box" })).unwrap();
+    let other_hash = request_hash(&serde_json::json!({ "name": "Other" })).unwrap();
+
+    assert_eq!(claim(&store, &key, &hash).await.unwrap(), Claim::New);
+    // Until the first request completes, retries have to wait
+    assert_eq!(claim(&store, &key, &hash).await.unwrap(), Claim::InProgress);
+
+    store.complete_record(&key, "box-1").await.unwrap();
+    assert_eq!(
+        claim(&store, &key, &hash).await.unwrap(),
+        Claim::Replay("box-1".to_string())
+    );
+    assert_eq!(
+        claim(&store, &key, &other_hash).await.unwrap(),
+        Claim::Mismatch
+    );
+
+    // A released key can be claimed again
+    store.delete_record(&key).await.unwrap();
+    assert_eq!(claim(&store, &key, &other_hash).await.unwrap(), Claim::New);
+}
+
+#[tokio::test]
+async fn test_expired_key_can_be_reused() {
+    let store = MockIdempotencyStore::new();
+    let key = scoped_key("create_box", "user-1", "key-1");
+    store
+        .insert_record(IdempotencyRecord {
+            idempotency_key: key.clone(),
+            request_hash: "old".to_string(),
+            resource_id: Some("box-1".to_string()),
+            expires_at: Utc::now().timestamp() - 1,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(claim(&store, &key, "new").await.unwrap(), Claim::New);
+}
+
+#[test]
+fn test_idempotency_key_header() {
+    let mut headers = HeaderMap::new();
+    assert_eq!(idempotency_key(&headers).unwrap(), None);
+
+    headers.insert("idempotency-key", HeaderValue::from_static(" abc-123 "));
+    assert_eq!(
+        idempotency_key(&headers).unwrap(),
+        Some("abc-123".to_string())
+    );
+
+    headers.insert("idempotency-key", HeaderValue::from_static(""));
+    assert!(idempotency_key(&headers).is_err());
+
+    let too_long = "k".repeat(256);
+    headers.insert("idempotency-key", HeaderValue::from_str(&too_long).unwrap());
+    assert!(idempotency_key(&headers).is_err());
+}
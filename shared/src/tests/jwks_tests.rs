@@ -0,0 +1,192 @@
+use std::time::Duration;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use serde_json::json;
+
+use crate::auth::jwks::JwksCache;
+use crate::auth::{test_claims, validate_token, AuthConfig, TEST_AUDIENCE, TEST_ISSUER};
+
+// A symmetric JWK, so tests can sign tokens without an RSA key pair
+fn oct_jwk(kid: &str, secret: &[u8]) -> serde_json::Value {
+    json!({ "kty": "oct", "kid": kid, "alg": "HS256", "k": URL_SAFE_NO_PAD.encode(secret) })
+}
+
+fn jwks_body(keys: &[serde_json::Value]) -> String {
+    json!({ "keys": keys }).to_string()
+}
+
+fn test_config() -> AuthConfig {
+    AuthConfig {
+        issuer: TEST_ISSUER.to_string(),
+        audiences: vec![TEST_AUDIENCE.to_string()],
+        jwks_url: String::new(),
+    }
+}
+
+// Polls until the mock has received its expected number of requests
+async fn wait_until_matched(mock: &mockito::Mock) {
+    for _ in 0..100 {
+        if mock.matched_async().await {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("JWKS endpoint did not receive the expected requests");
+}
+
+#[tokio::test]
+async fn test_jwks_cache_hit_fetches_once() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/jwks.json")
+        .with_body(jwks_body(&[oct_jwk("key-a", b"secret-a")]))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let cache = JwksCache::new(format!("{}/jwks.json", server.url()));
+    for _ in 0..3 {
+        let (_, algorithm) = cache.signing_key("key-a").await.unwrap();
+        assert_eq!(algorithm, Algorithm::HS256);
+    }
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_jwks_cache_verifies_tokens_by_kid() {
+    let mut server = mockito::Server::new_async().await;
+    server
+        .mock("GET", "/jwks.json")
+        .with_body(jwks_body(&[oct_jwk("key-a", b"secret-a")]))
+        .create_async()
+        .await;
+
+    let cache = JwksCache::new(format!("{}/jwks.json", server.url()));
+    let mut header = Header::new(Algorithm::HS256);
+    header.kid = Some("key-a".to_string());
+    let token = encode(
+        &header,
+        &test_claims("jwks-user", &[]),
+        &EncodingKey::from_secret(b"secret-a"),
+    )
+    .unwrap();
+
+    let (key, algorithm) = cache.signing_key("key-a").await.unwrap();
+    let claims = validate_token(&token, &key, algorithm, &test_config()).unwrap();
+    assert_eq!(claims.sub, "jwks-user");
+}
+
+#[tokio::test]
+async fn test_jwks_cache_refreshes_in_background_after_ttl() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/jwks.json")
+        .with_body(jwks_body(&[oct_jwk("key-a", b"secret-a")]))
+        .expect(2)
+        .create_async()
+        .await;
+
+    let cache = JwksCache::with_intervals(
+        format!("{}/jwks.json", server.url()),
+        Duration::from_millis(50),
+        Duration::ZERO,
+    );
+    cache.signing_key("key-a").await.unwrap();
+
+    // Once the TTL has passed the stale key is still served, and a refresh
+    // is started behind it
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    cache.signing_key("key-a").await.unwrap();
+
+    wait_until_matched(&mock).await;
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_jwks_cache_keeps_last_good_keys_when_refresh_fails() {
+    let mut server = mockito::Server::new_async().await;
+    let good = server
+        .mock("GET", "/jwks.json")
+        .with_body(jwks_body(&[oct_jwk("key-a", b"secret-a")]))
+        .create_async()
+        .await;
+
+    let cache = JwksCache::with_intervals(
+        format!("{}/jwks.json", server.url()),
+        Duration::from_millis(50),
+        Duration::ZERO,
+    );
+    cache.signing_key("key-a").await.unwrap();
+
+    good.remove_async().await;
+    let failing = server
+        .mock("GET", "/jwks.json")
+        .with_status(500)
+        .expect_at_least(1)
+        .create_async()
+        .await;
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    cache.signing_key("key-a").await.unwrap();
+    wait_until_matched(&failing).await;
+
+    // The failed refresh didn't drop the cached key
+    cache.signing_key("key-a").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_jwks_cache_refetches_for_rotated_key() {
+    let mut server = mockito::Server::new_async().await;
+    let original = server
+        .mock("GET", "/jwks.json")
+        .with_body(jwks_body(&[oct_jwk("key-a", b"secret-a")]))
+        .create_async()
+        .await;
+
+    let cache = JwksCache::with_intervals(
+        format!("{}/jwks.json", server.url()),
+        Duration::from_secs(3600),
+        Duration::ZERO,
+    );
+    cache.signing_key("key-a").await.unwrap();
+    assert!(cache.signing_key("key-b").await.is_err());
+
+    // The provider rotates in a new key
+    original.remove_async().await;
+    let rotated = server
+        .mock("GET", "/jwks.json")
+        .with_body(jwks_body(&[
+            oct_jwk("key-a", b"secret-a"),
+            oct_jwk("key-b", b"secret-b"),
+        ]))
+        .expect(1)
+        .create_async()
+        .await;
+
+    // A token signed with the new key triggers a refetch well before the TTL
+    cache.signing_key("key-b").await.unwrap();
+    cache.signing_key("key-a").await.unwrap();
+    rotated.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_jwks_cache_rate_limits_unknown_kid_refetches() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("GET", "/jwks.json")
+        .with_body(jwks_body(&[oct_jwk("key-a", b"secret-a")]))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let cache = JwksCache::new(format!("{}/jwks.json", server.url()));
+    cache.signing_key("key-a").await.unwrap();
+
+    // Bogus key ids right after a fetch don't reach the provider
+    for kid in ["forged-1", "forged-2", "forged-3"] {
+        assert!(cache.signing_key(kid).await.is_err());
+    }
+    mock.assert_async().await;
+}
@@ -1,3 +1,18 @@
 // Tests for shared crate functionality
+pub mod compression_tests;
+pub mod envelope_tests;
+pub mod idempotency_tests;
+pub mod jwks_tests;
+pub mod logging_tests;
 pub mod mock_store_tests;
+pub mod models_tests;
+pub mod pagination_tests;
+pub mod paging_tests;
+pub mod poison_tests;
+pub mod push_tests;
+pub mod retry_tests;
+pub mod shutdown_tests;
 pub mod store_tests;
+pub mod throttle_tests;
+pub mod validation_tests;
+pub mod webhook_tests;
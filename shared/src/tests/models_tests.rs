@@ -0,0 +1,158 @@
+use serde_json::json;
+
+use crate::models::events::{EventEnvelope, InvitationEvent, EVENT_SCHEMA_VERSION};
+use crate::models::{Invitation, UnlockRequest, UnlockRequestStatus};
+
+fn keys(value: &serde_json::Value) -> Vec<&str> {
+    let mut keys: Vec<&str> = value
+        .as_object()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    keys.sort_unstable();
+    keys
+}
+
+#[test]
+fn test_http_models_serialize_camel_case() {
+    let invitation = Invitation {
+        id: "inv-1".to_string(),
+        invite_code: "ABCDEFGH".to_string(),
+        invited_name: "Alice".to_string(),
+        box_id: "box-1".to_string(),
+        created_at: "2030-01-01T00:00:00Z".to_string(),
+        expires_at: "2030-01-03T00:00:00Z".to_string(),
+        opened: false,
+        linked_user_id: None,
+        creator_id: "owner".to_string(),
+        is_lead_guardian: true,
+        box_name: Some("Family".to_string()),
+        owner_name: Some("Bob".to_string()),
+        creator_name: Some("Bob".to_string()),
+    };
+
+    let value = serde_json::to_value(&invitation).unwrap();
+    assert_eq!(
+        keys(&value),
+        [
+            "boxId",
+            "boxName",
+            "createdAt",
+            "creatorId",
+            "creatorName",
+            "expiresAt",
+            "id",
+            "inviteCode",
+            "invitedName",
+            "isLeadGuardian",
+            "linkedUserId",
+            "opened",
+            "ownerName"
+        ]
+    );
+}
+
+#[test]
+fn test_events_serialize_snake_case() {
+    let event: InvitationEvent = serde_json::from_value(json!({
+        "event_type": "invitation_viewed",
+        "invitation_id": "inv-1",
+        "box_id": "box-1",
+        "user_id": "user-1",
+        "invite_code": "ABCDEFGH",
+        "invited_name": "Alice",
+        "is_lead_guardian": true,
+        "timestamp": "2030-01-01T00:00:00Z"
+    }))
+    .unwrap();
+    assert_eq!(event.box_id, "box-1");
+    assert!(event.is_lead_guardian);
+    // Published before events were versioned
+    assert_eq!(event.schema_version, 1);
+
+    let value = serde_json::to_value(&event).unwrap();
+    assert_eq!(
+        keys(&value),
+        [
+            "box_id",
+            "event_type",
+            "invitation_id",
+            "invite_code",
+            "invited_name",
+            "is_lead_guardian",
+            "schema_version",
+            "timestamp",
+            "user_id"
+        ]
+    );
+}
+
+#[test]
+fn test_event_envelope_schema_version() {
+    let unversioned: EventEnvelope =
+        serde_json::from_value(json!({ "event_type": "box_locked", "box_id": "box-1" })).unwrap();
+    assert_eq!(unversioned.schema_version, 1);
+    assert!(unversioned.is_supported());
+
+    let current: EventEnvelope = serde_json::from_value(
+        json!({ "event_type": "box_locked", "schema_version": EVENT_SCHEMA_VERSION }),
+    )
+    .unwrap();
+    assert!(current.is_supported());
+
+    let future: EventEnvelope = serde_json::from_value(
+        json!({ "event_type": "box_locked", "schema_version": EVENT_SCHEMA_VERSION + 1 }),
+    )
+    .unwrap();
+    assert!(!future.is_supported());
+}
+
+#[test]
+fn test_unlock_request_awaits_answer_from() {
+    let mut request = UnlockRequest {
+        id: "unlock-1".to_string(),
+        requested_at: "2024-01-01T00:00:00Z".to_string(),
+        status: UnlockRequestStatus::Requested,
+        message: None,
+        reason: None,
+        initiated_by: Some("lead".to_string()),
+        approved_by: vec![],
+        rejected_by: vec![],
+        approvals: vec![],
+    };
+    assert!(request.awaits_answer_from("g1"));
+    // The guardian who asked has nothing to answer
+    assert!(!request.awaits_answer_from("lead"));
+
+    request.record_response("g1", false, "2024-01-02T00:00:00Z");
+    assert!(!request.awaits_answer_from("g1"));
+    assert!(request.awaits_answer_from("g2"));
+
+    request.status = UnlockRequestStatus::Approved;
+    assert!(!request.awaits_answer_from("g2"));
+}
+
+#[test]
+fn test_unlock_request_changed_answer_counts_once() {
+    let mut request = UnlockRequest {
+        id: "unlock-1".to_string(),
+        requested_at: "2024-01-01T00:00:00Z".to_string(),
+        status: UnlockRequestStatus::Requested,
+        message: None,
+        reason: None,
+        initiated_by: Some("lead".to_string()),
+        approved_by: vec![],
+        rejected_by: vec![],
+        approvals: vec![],
+    };
+    assert!(request.record_response("g1", true, "2024-01-02T00:00:00Z"));
+    assert!(request.answered_otherwise("g1", false));
+    assert!(!request.record_response("g1", true, "2024-01-02T00:01:00Z"));
+
+    assert!(request.record_response("g1", false, "2024-01-02T00:02:00Z"));
+    assert!(request.approved_by.is_empty());
+    assert_eq!(request.rejected_by, vec!["g1".to_string()]);
+    // Both answers stay in the history
+    assert_eq!(request.approvals.len(), 2);
+}
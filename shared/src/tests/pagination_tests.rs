@@ -0,0 +1,70 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use std::collections::HashMap;
+
+use crate::error::StoreError;
+use crate::store::pagination::{decode_cursor, encode_cursor, validate_cursor_secret, CursorScope};
+
+fn last_key() -> HashMap<String, String> {
+    HashMap::from([
+        ("ownerId".to_string(), "user_a".to_string()),
+        ("id".to_string(), "box_7".to_string()),
+    ])
+}
+
+#[test]
+fn test_cursor_round_trip() {
+    let cursor = encode_cursor(&last_key(), CursorScope::OwnedBoxes, "user_a").unwrap();
+    assert_eq!(
+        decode_cursor(&cursor, CursorScope::OwnedBoxes, "user_a").unwrap(),
+        last_key()
+    );
+}
+
+#[test]
+fn test_tampered_cursor_rejected() {
+    let cursor = encode_cursor(&last_key(), CursorScope::OwnedBoxes, "user_a").unwrap();
+    let (_, tag) = cursor.split_once('.').unwrap();
+
+    // Point the cursor at another partition, keeping the old signature
+    let mut edited = last_key();
+    edited.insert("ownerId".to_string(), "user_b".to_string());
+    let payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&edited).unwrap());
+    let tampered = format!("{}.{}", payload, tag);
+    assert!(matches!(
+        decode_cursor(&tampered, CursorScope::OwnedBoxes, "user_a"),
+        Err(StoreError::ValidationError(_))
+    ));
+
+    // Unsigned cursors, as issued before signing, are refused too
+    let (payload, _) = cursor.split_once('.').unwrap();
+    assert!(decode_cursor(payload, CursorScope::OwnedBoxes, "user_a").is_err());
+    assert!(decode_cursor("not-a-cursor", CursorScope::OwnedBoxes, "user_a").is_err());
+}
+
+#[test]
+fn test_cursor_rejected_for_other_user() {
+    let cursor = encode_cursor(&last_key(), CursorScope::OwnedBoxes, "user_a").unwrap();
+    assert!(matches!(
+        decode_cursor(&cursor, CursorScope::OwnedBoxes, "user_b"),
+        Err(StoreError::ValidationError(_))
+    ));
+}
+
+#[test]
+fn test_cursor_rejected_for_other_list() {
+    let cursor = encode_cursor(&last_key(), CursorScope::OwnedBoxes, "user_a").unwrap();
+    for scope in [CursorScope::GuardianBoxes, CursorScope::CreatedInvitations] {
+        assert!(matches!(
+            decode_cursor(&cursor, scope, "user_a"),
+            Err(StoreError::ValidationError(_))
+        ));
+    }
+}
+
+#[test]
+fn test_cursor_secret_required() {
+    assert!(validate_cursor_secret(None).is_err());
+    assert!(validate_cursor_secret(Some("")).is_err());
+    assert!(validate_cursor_secret(Some(&"s".repeat(31))).is_err());
+    assert!(validate_cursor_secret(Some(&"s".repeat(32))).is_ok());
+}
@@ -0,0 +1,37 @@
+use axum::http::{header, Uri};
+
+use crate::paging::{pagination_headers, X_TOTAL_COUNT};
+
+#[test]
+fn test_next_link_replaces_cursor_and_keeps_other_params() {
+    let uri: Uri = "/boxes/guardian?status=pending&cursor=old&limit=10"
+        .parse()
+        .unwrap();
+    let headers = pagination_headers(&uri, Some("bmV4dA"), None);
+
+    assert_eq!(
+        headers[header::LINK],
+        "</boxes/guardian?status=pending&limit=10&cursor=bmV4dA>; rel=\"next\""
+    );
+    assert!(headers.get(X_TOTAL_COUNT).is_none());
+}
+
+#[test]
+fn test_last_page_has_no_link() {
+    let uri: Uri = "/invitations/me?limit=10".parse().unwrap();
+    let headers = pagination_headers(&uri, None, Some(3));
+
+    assert!(headers.get(header::LINK).is_none());
+    assert_eq!(headers[X_TOTAL_COUNT], "3");
+}
+
+#[test]
+fn test_next_link_without_query() {
+    let uri: Uri = "/prod/invitations/me".parse().unwrap();
+    let headers = pagination_headers(&uri, Some("abc"), None);
+
+    assert_eq!(
+        headers[header::LINK],
+        "</prod/invitations/me?cursor=abc>; rel=\"next\""
+    );
+}
@@ -0,0 +1,41 @@
+use crate::models::events::BoxLockedEvent;
+use crate::poison::parse_or_dead_letter;
+use crate::store::PoisonMessageStore;
+use crate::test_utils::mock_poison_message_store::MockPoisonMessageStore;
+
+const SOURCE: &str = "notification-service";
+
+#[tokio::test]
+async fn test_malformed_message_is_dead_lettered() {
+    let store = MockPoisonMessageStore::new();
+    // A box_locked record whose guardian_ids isn't a list
+    let body = r#"{"event_type":"box_locked","schema_version":1,"box_id":"box-1","box_name":"Box","owner_name":"Owner","guardian_ids":"guardian-1","timestamp":"2024-01-01T00:00:00Z"}"#;
+
+    let parsed = parse_or_dead_letter::<BoxLockedEvent>(&store, SOURCE, "msg-1", body).await;
+
+    assert!(parsed.is_none());
+    let poison = store.get_poison_message("msg-1").await.unwrap();
+    assert_eq!(poison.message_id, "msg-1");
+    assert_eq!(poison.source, SOURCE);
+    assert_eq!(poison.body, body);
+    assert!(poison.error.contains("expected a sequence"));
+}
+
+#[tokio::test]
+async fn test_valid_message_is_not_dead_lettered() {
+    let store = MockPoisonMessageStore::new();
+    let event = BoxLockedEvent::new(
+        "box-1",
+        "Box",
+        Some("Owner"),
+        &["guardian-1".to_string()],
+        "2024-01-01T00:00:00Z",
+        None,
+    );
+    let body = serde_json::to_string(&event).unwrap();
+
+    let parsed = parse_or_dead_letter::<BoxLockedEvent>(&store, SOURCE, "msg-1", &body).await;
+
+    assert_eq!(parsed.unwrap().box_id, "box-1");
+    assert!(store.messages().is_empty());
+}
@@ -0,0 +1,242 @@
+use crate::models::PushToken;
+use crate::push::{
+    build_expo_client, build_push_messages, post_expo_messages, record_push_failures,
+    ExpoPushSender, ExpoPushTicket, ExpoPushTicketDetails, NotificationOptions, PushError,
+    PushSender, DEVICE_NOT_REGISTERED, SHARD_DIGEST, SHARD_RECEIVED,
+};
+use crate::store::PushFailureStore;
+use crate::test_utils::mock_push_failure_store::MockPushFailureStore;
+use serde_json::json;
+
+fn token(push_token: &str) -> PushToken {
+    PushToken {
+        user_id: format!("user-{}", push_token),
+        push_token: push_token.to_string(),
+        platform: "ios".to_string(),
+        updated_at: "2024-01-01T00:00:00Z".to_string(),
+        last_test_notification_at: None,
+        last_verified_at: None,
+        notification_prefs: None,
+        utc_offset_minutes: None,
+    }
+}
+
+#[test]
+fn test_options_flow_into_messages() {
+    let tokens = [token("ExponentPushToken[a]"), token("ExponentPushToken[b]")];
+    let options = NotificationOptions {
+        sound: Some("critical.wav".to_string()),
+        badge: Some(4),
+    };
+
+    let messages = build_push_messages(&tokens, "Title", "Body", None, &options);
+
+    assert_eq!(messages.len(), 2);
+    for message in &messages {
+        assert_eq!(message.sound.as_deref(), Some("critical.wav"));
+        assert_eq!(message.badge, Some(4));
+    }
+    assert_eq!(messages[1].to, "ExponentPushToken[b]");
+}
+
+#[test]
+fn test_silent_options_leave_sound_and_badge_out() {
+    let options = NotificationOptions {
+        sound: None,
+        badge: None,
+    };
+
+    let messages = build_push_messages(&[token("t")], "Title", "Body", None, &options);
+    let json = serde_json::to_value(&messages[0]).unwrap();
+
+    assert!(json.get("sound").is_none());
+    assert!(json.get("badge").is_none());
+}
+
+#[test]
+fn test_default_options_keep_previous_behavior() {
+    let messages = build_push_messages(
+        &[token("t")],
+        "Title",
+        "Body",
+        None,
+        &NotificationOptions::default(),
+    );
+
+    assert_eq!(messages[0].sound.as_deref(), Some("default"));
+    assert_eq!(messages[0].badge, Some(1));
+}
+
+#[test]
+fn test_sound_per_notification_type() {
+    // Types no other test uses, so setting their variables can't race
+    std::env::set_var("PUSH_SOUND_TEST_CRITICAL", "critical.wav");
+    std::env::set_var("PUSH_SOUND_TEST_MUTED", "none");
+
+    let critical = NotificationOptions::for_type("test_critical", Some(2));
+    assert_eq!(critical.sound.as_deref(), Some("critical.wav"));
+    assert_eq!(critical.badge, Some(2));
+
+    let muted = NotificationOptions::for_type("test_muted", None);
+    assert_eq!(muted.sound, None);
+
+    let unset = NotificationOptions::for_type("test_unconfigured", Some(1));
+    assert_eq!(unset.sound.as_deref(), Some("default"));
+}
+
+#[tokio::test]
+async fn test_failed_send_is_dead_lettered() {
+    let store = MockPushFailureStore::new();
+    let tokens = [token("a"), token("b")];
+    let result: Result<Vec<ExpoPushTicket>, PushError> = Err(PushError::Transport(
+        "Failed to send push notifications: connection refused".to_string(),
+    ));
+
+    let recorded =
+        record_push_failures(&store, &tokens, &result, Some("box-1"), SHARD_RECEIVED).await;
+
+    assert_eq!(recorded, 2);
+    let failures = store.get_push_failures_for_box("box-1").await.unwrap();
+    assert_eq!(failures.len(), 2);
+    assert_eq!(failures[0].user_id, "user-a");
+    assert_eq!(failures[1].user_id, "user-b");
+    assert_eq!(failures[0].notification_type, SHARD_RECEIVED);
+    assert!(failures[0].error.contains("connection refused"));
+}
+
+#[tokio::test]
+async fn test_refused_ticket_is_dead_lettered() {
+    let store = MockPushFailureStore::new();
+    let tokens = [token("a"), token("b")];
+    let result = Ok(vec![
+        ExpoPushTicket {
+            status: "ok".to_string(),
+            id: Some("ticket-1".to_string()),
+            message: None,
+            details: None,
+        },
+        ExpoPushTicket {
+            status: "error".to_string(),
+            id: None,
+            message: Some("b is not a registered push notification recipient".to_string()),
+            details: Some(ExpoPushTicketDetails {
+                error: Some(DEVICE_NOT_REGISTERED.to_string()),
+            }),
+        },
+    ]);
+
+    record_push_failures(&store, &tokens, &result, None, SHARD_DIGEST).await;
+
+    // Only the refused recipient is recorded, with Expo's error code
+    let failures = store.failures();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].user_id, "user-b");
+    assert_eq!(failures[0].box_id, None);
+    assert_eq!(failures[0].error, DEVICE_NOT_REGISTERED);
+}
+
+#[tokio::test]
+async fn test_push_send_times_out_as_transport_error() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/push/send")
+        .with_status(200)
+        .with_chunked_body(|_| {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            Ok(())
+        })
+        .create_async()
+        .await;
+
+    let client = build_expo_client(std::time::Duration::from_millis(100));
+    let messages = build_push_messages(
+        &[token("ExponentPushToken[a]")],
+        "title",
+        "body",
+        None,
+        &NotificationOptions::default(),
+    );
+    let result =
+        post_expo_messages(&client, &format!("{}/push/send", server.url()), &messages).await;
+
+    let err = result.expect_err("a slow Expo response should time out");
+    assert!(matches!(err, PushError::Transport(_)), "got {:?}", err);
+    assert!(err.is_retryable());
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_push_send_classifies_error_statuses() {
+    let mut server = mockito::Server::new_async().await;
+    let url = format!("{}/push/send", server.url());
+    let client = build_expo_client(std::time::Duration::from_secs(5));
+    let messages = build_push_messages(
+        &[token("ExponentPushToken[a]")],
+        "title",
+        "body",
+        None,
+        &NotificationOptions::default(),
+    );
+
+    let throttled = server
+        .mock("POST", "/push/send")
+        .with_status(429)
+        .create_async()
+        .await;
+    let err = post_expo_messages(&client, &url, &messages)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PushError::Throttled(_)), "got {:?}", err);
+    assert!(err.is_retryable());
+    throttled.remove_async().await;
+
+    server
+        .mock("POST", "/push/send")
+        .with_status(400)
+        .create_async()
+        .await;
+    let err = post_expo_messages(&client, &url, &messages)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PushError::Failed(_)), "got {:?}", err);
+    assert!(!err.is_retryable());
+}
+
+#[tokio::test]
+async fn test_push_send_uses_configured_url() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/--/api/v2/push/send")
+        .match_header("content-type", "application/json")
+        .match_body(mockito::Matcher::Json(json!([
+            {
+                "to": "ExponentPushToken[a]",
+                "title": "Box locked",
+                "body": "You are now a guardian",
+                "data": { "boxId": "box-1" },
+                "sound": "default",
+                "badge": 1,
+                "_contentAvailable": true
+            }
+        ])))
+        .with_status(200)
+        .with_body(r#"{"data":[{"status":"ok","id":"ticket-1"}]}"#)
+        .create_async()
+        .await;
+
+    let sender = ExpoPushSender::with_url(format!("{}/--/api/v2/push/send", server.url()));
+    let tickets = sender
+        .send_push_notifications(
+            &[token("ExponentPushToken[a]")],
+            "Box locked",
+            "You are now a guardian",
+            Some(json!({ "boxId": "box-1" })),
+            &NotificationOptions::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(tickets.len(), 1);
+    assert_eq!(tickets[0].id.as_deref(), Some("ticket-1"));
+    mock.assert_async().await;
+}
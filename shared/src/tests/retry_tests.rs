@@ -0,0 +1,93 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use aws_sdk_dynamodb::config::http::HttpResponse;
+use aws_sdk_dynamodb::error::{ErrorMetadata, SdkError};
+use aws_sdk_dynamodb::operation::put_item::PutItemError;
+
+use crate::error::{map_dynamo_error, map_sdk_error, StoreError};
+use crate::store::retry::{retry_with_backoff, MAX_RETRIES};
+
+// A service error as the DynamoDB client would report it
+fn dynamo_error(code: &str) -> PutItemError {
+    PutItemError::generic(ErrorMetadata::builder().code(code).build())
+}
+
+#[test]
+fn test_map_dynamo_error_classifies_codes() {
+    for code in [
+        "ProvisionedThroughputExceededException",
+        "ThrottlingException",
+        "RequestLimitExceeded",
+        "InternalServerError",
+    ] {
+        let err = map_dynamo_error("put_item", dynamo_error(code));
+        assert!(err.is_retryable(), "{} should be retryable", code);
+    }
+
+    let err = map_dynamo_error("put_item", dynamo_error("ConditionalCheckFailedException"));
+    assert!(matches!(err, StoreError::VersionConflict(_)));
+    assert!(!err.is_retryable());
+
+    let err = map_dynamo_error("put_item", dynamo_error("ValidationException"));
+    assert!(matches!(err, StoreError::InternalError(_)));
+    assert!(!err.is_retryable());
+}
+
+#[test]
+fn test_map_sdk_error_treats_timeouts_as_transport() {
+    let err = map_sdk_error(
+        "put_item",
+        SdkError::<PutItemError, HttpResponse>::timeout_error("attempt timed out"),
+    );
+    assert!(matches!(err, StoreError::Transport(_)), "got {:?}", err);
+    assert!(err.is_retryable());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_retry_recovers_after_throttling() {
+    let calls = AtomicU32::new(0);
+
+    // Throttles twice, then succeeds
+    let result = retry_with_backoff("put_item", || async {
+        if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+            Err(map_dynamo_error(
+                "put_item",
+                dynamo_error("ProvisionedThroughputExceededException"),
+            ))
+        } else {
+            Ok("stored")
+        }
+    })
+    .await;
+
+    assert_eq!(result.unwrap(), "stored");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_retry_gives_up_after_max_retries() {
+    let calls = AtomicU32::new(0);
+
+    let result: crate::error::Result<()> = retry_with_backoff("get_item", || async {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Err(StoreError::Throttled("still busy".into()))
+    })
+    .await;
+
+    assert!(matches!(result, Err(StoreError::Throttled(_))));
+    assert_eq!(calls.load(Ordering::SeqCst), MAX_RETRIES + 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_retry_skips_non_retryable_errors() {
+    let calls = AtomicU32::new(0);
+
+    let result: crate::error::Result<()> = retry_with_backoff("put_item", || async {
+        calls.fetch_add(1, Ordering::SeqCst);
+        Err(StoreError::VersionConflict("stale version".into()))
+    })
+    .await;
+
+    assert!(matches!(result, Err(StoreError::VersionConflict(_))));
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+}
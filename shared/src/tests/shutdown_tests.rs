@@ -0,0 +1,83 @@
+use axum::routing::get;
+use axum::Router;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpListener;
+use tokio::sync::{oneshot, Notify};
+
+use crate::shutdown::serve_with_graceful_shutdown;
+
+// A server whose only route waits `delay` after telling `started` it began
+async fn spawn_server(
+    delay: Duration,
+    drain_timeout: Duration,
+) -> (
+    String,
+    Arc<Notify>,
+    oneshot::Sender<()>,
+    tokio::task::JoinHandle<std::io::Result<()>>,
+) {
+    let started = Arc::new(Notify::new());
+    let notify = started.clone();
+    let app = Router::new().route(
+        "/slow",
+        get(move || {
+            let notify = notify.clone();
+            async move {
+                notify.notify_one();
+                tokio::time::sleep(delay).await;
+                "done"
+            }
+        }),
+    );
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/slow", listener.local_addr().unwrap());
+    let (stop_tx, stop_rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(serve_with_graceful_shutdown(
+        listener,
+        app,
+        async move {
+            let _ = stop_rx.await;
+        },
+        drain_timeout,
+    ));
+    (url, started, stop_tx, server)
+}
+
+#[tokio::test]
+async fn test_in_flight_request_completes_after_shutdown() {
+    let (url, started, stop, server) =
+        spawn_server(Duration::from_millis(300), Duration::from_secs(5)).await;
+
+    let request = tokio::spawn({
+        let url = url.clone();
+        async move { reqwest::get(url).await?.text().await }
+    });
+    started.notified().await;
+    stop.send(()).unwrap();
+
+    // The request that was already running still gets its response
+    assert_eq!(request.await.unwrap().unwrap(), "done");
+    server.await.unwrap().unwrap();
+
+    // and once drained, nothing new is accepted
+    assert!(reqwest::get(url).await.is_err());
+}
+
+#[tokio::test]
+async fn test_drain_gives_up_after_timeout() {
+    let (url, started, stop, server) =
+        spawn_server(Duration::from_secs(60), Duration::from_millis(100)).await;
+
+    let request = tokio::spawn(async move { reqwest::get(url).await });
+    started.notified().await;
+    let stopped_at = Instant::now();
+    stop.send(()).unwrap();
+
+    // Serving returns without waiting for the stuck request, so the process
+    // can exit
+    server.await.unwrap().unwrap();
+    assert!(stopped_at.elapsed() < Duration::from_secs(5));
+    request.abort();
+}
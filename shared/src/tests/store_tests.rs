@@ -1,11 +1,16 @@
 #[cfg(test)]
 mod dynamo_tests {
+    use crate::envelope::{Envelope, ENVELOPE_PREFIX};
     use crate::models::BoxRecord;
     use crate::models::GuardianStatus;
     use crate::store::{dynamo::DynamoBoxStore, BoxStore};
+    use crate::test_utils::dynamo_test_utils::create_guardian_box_table;
+    use crate::test_utils::mock_key_service::MockKeyService;
     use crate::test_utils::test_logging::init_test_logging;
+    use aws_sdk_dynamodb::types::AttributeValue;
     use aws_sdk_dynamodb::Client;
     use log::info;
+    use std::sync::Arc;
     use uuid::Uuid;
 
     fn create_test_box(name: &str, owner_id: &str) -> BoxRecord {
@@ -29,6 +34,11 @@ mod dynamo_tests {
             shards_fetched: None,
             total_shards: None,
             shards_deleted_at: None,
+            all_shards_accepted: false,
+            last_unlock_request_at: None,
+            webhook_url: None,
+            webhook_secret: None,
+            require_step_up: false,
         }
     }
 
@@ -158,15 +168,22 @@ mod dynamo_tests {
         table_name: &str,
     ) -> Result<(), Box<dyn std::error::Error>> {
         client.delete_table().table_name(table_name).send().await?;
+        let guardian_box_table = DynamoBoxStore::guardian_box_table_for(table_name);
+        client
+            .delete_table()
+            .table_name(&guardian_box_table)
+            .send()
+            .await?;
 
-        // Wait for the table to be deleted
+        // Wait for the tables to be deleted
         let mut is_deleted = false;
         while !is_deleted {
             tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
             let tables = client.list_tables().send().await?;
             let names = tables.table_names();
-            is_deleted = !names.contains(&table_name.to_string());
+            is_deleted =
+                !names.contains(&table_name.to_string()) && !names.contains(&guardian_box_table);
         }
 
         Ok(())
@@ -177,10 +194,16 @@ mod dynamo_tests {
         let client = create_local_dynamo_client().await;
         let table_name = format!("test-table-{}", Uuid::new_v4());
 
-        // Create the test table
+        // Create the test table and its guardian-box lookup
         create_test_table(&client, &table_name)
             .await
             .expect("Failed to create test table");
+        create_guardian_box_table(
+            &client,
+            &DynamoBoxStore::guardian_box_table_for(&table_name),
+        )
+        .await
+        .expect("Failed to create guardian-box table");
 
         // Create the store with our client and table
         let store = DynamoBoxStore::with_client_and_table(client.clone(), table_name.clone());
@@ -296,6 +319,104 @@ mod dynamo_tests {
             .expect("Failed to delete test table");
     }
 
+    // Test that a consistent read sees a write made immediately before it
+    #[tokio::test]
+    async fn dynamo_store_get_box_consistent_reads_own_writes() {
+        init_test_logging();
+        if !is_dynamodb_local_running() {
+            info!("Skipping test dynamo_store_get_box_consistent_reads_own_writes: DynamoDB Local is not running");
+            return;
+        }
+
+        let (store, client, table_name) = create_test_store().await;
+
+        let test_box = create_test_box("Before", "test_owner");
+        store.create_box(test_box.clone()).await.unwrap();
+
+        // Read back each write straight away, with no sleep in between
+        let mut current = store.get_box_consistent(&test_box.id).await.unwrap();
+        for i in 0..5 {
+            current.name = format!("After {}", i);
+            let written = store.update_box(current).await.unwrap();
+
+            current = store.get_box_consistent(&test_box.id).await.unwrap();
+            assert_eq!(current.name, format!("After {}", i));
+            assert_eq!(current.version, written.version);
+        }
+
+        // Clean up
+        delete_test_table(&client, &table_name)
+            .await
+            .expect("Failed to delete test table");
+    }
+
+    #[tokio::test]
+    async fn dynamo_store_encrypts_shards_at_rest() {
+        init_test_logging();
+        if !is_dynamodb_local_running() {
+            info!(
+                "Skipping test dynamo_store_encrypts_shards_at_rest: DynamoDB Local is not running"
+            );
+            return;
+        }
+
+        let (store, client, table_name) = create_test_store().await;
+        let store = store.with_shard_envelope(Envelope::new(Arc::new(MockKeyService::new())));
+
+        let mut test_box = create_test_box("Sealed", "test_owner");
+        test_box.guardians.push(crate::models::Guardian {
+            id: "g1".into(),
+            name: "Guardian One".into(),
+            lead_guardian: false,
+            status: GuardianStatus::Accepted,
+            added_at: crate::models::now_str(),
+            invitation_id: "inv-1".into(),
+            lock_data_received_at: None,
+            encrypted_shard: Some("c2hhcmQtZzE=".into()),
+            shard_hash: Some("abc123".into()),
+            shard_fetched_at: None,
+            shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
+        });
+        store.create_box(test_box.clone()).await.unwrap();
+
+        // The raw item holds only sealed values
+        let raw = client
+            .get_item()
+            .table_name(&table_name)
+            .key("id", AttributeValue::S(test_box.id.clone()))
+            .send()
+            .await
+            .unwrap();
+        let raw_box: BoxRecord = serde_dynamo::from_item(raw.item().unwrap().clone()).unwrap();
+        let raw_guardian = &raw_box.guardians[0];
+        assert!(raw_guardian
+            .encrypted_shard
+            .as_deref()
+            .unwrap()
+            .starts_with(ENVELOPE_PREFIX));
+        assert!(raw_guardian
+            .shard_hash
+            .as_deref()
+            .unwrap()
+            .starts_with(ENVELOPE_PREFIX));
+
+        // Reads through the store see the plaintext again
+        let read = store.get_box_consistent(&test_box.id).await.unwrap();
+        assert_eq!(
+            read.guardians[0].encrypted_shard.as_deref(),
+            Some("c2hhcmQtZzE=")
+        );
+        assert_eq!(read.guardians[0].shard_hash.as_deref(), Some("abc123"));
+
+        // Clean up
+        delete_test_table(&client, &table_name)
+            .await
+            .expect("Failed to delete test table");
+    }
+
     // Test for updating a box
     #[tokio::test]
     async fn dynamo_store_update_box() {
@@ -361,6 +482,102 @@ mod dynamo_tests {
             .expect("Failed to delete test table");
     }
 
+    // Test that the guardian-box lookup follows guardians being added and removed
+    #[tokio::test]
+    async fn dynamo_store_guardian_index_tracks_guardians() {
+        init_test_logging();
+        if !is_dynamodb_local_running() {
+            info!("Skipping test dynamo_store_guardian_index_tracks_guardians: DynamoDB Local is not running");
+            return;
+        }
+
+        let (store, client, table_name) = create_test_store().await;
+        let guardian_id = "indexed_guardian";
+
+        let test_box = store
+            .create_box(create_test_box("Indexed Box", "test_owner"))
+            .await
+            .unwrap();
+        assert!(store
+            .get_box_ids_for_guardian(guardian_id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Adding the guardian writes a lookup row
+        let mut with_guardian = test_box.clone();
+        with_guardian.guardians.push(crate::models::Guardian {
+            id: guardian_id.to_string(),
+            name: "Indexed Guardian".to_string(),
+            status: GuardianStatus::Invited,
+            lead_guardian: false,
+            added_at: crate::models::now_str(),
+            invitation_id: Uuid::new_v4().to_string(),
+            lock_data_received_at: None,
+            encrypted_shard: None,
+            shard_hash: None,
+            shard_fetched_at: None,
+            shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
+        });
+        // An invited placeholder has no user id yet and gets no row, which
+        // DynamoDB would refuse as an empty key
+        let placeholder = crate::models::Guardian {
+            id: String::new(),
+            name: "Invited Guardian".to_string(),
+            ..with_guardian.guardians[0].clone()
+        };
+        with_guardian.guardians.push(placeholder);
+        let updated = store.update_box(with_guardian).await.unwrap();
+        assert_eq!(
+            store.get_box_ids_for_guardian(guardian_id).await.unwrap(),
+            vec![test_box.id.clone()]
+        );
+
+        // Removing the guardian deletes it again
+        let mut without_guardian = updated.clone();
+        without_guardian.guardians.clear();
+        let updated = store.update_box(without_guardian).await.unwrap();
+        assert!(store
+            .get_box_ids_for_guardian(guardian_id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Deleting a box drops its rows
+        let mut with_guardian = updated.clone();
+        with_guardian.guardians.push(crate::models::Guardian {
+            id: guardian_id.to_string(),
+            name: "Indexed Guardian".to_string(),
+            status: GuardianStatus::Accepted,
+            lead_guardian: false,
+            added_at: crate::models::now_str(),
+            invitation_id: Uuid::new_v4().to_string(),
+            lock_data_received_at: None,
+            encrypted_shard: None,
+            shard_hash: None,
+            shard_fetched_at: None,
+            shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
+        });
+        store.update_box(with_guardian).await.unwrap();
+        store.delete_box(&test_box.id).await.unwrap();
+        assert!(store
+            .get_box_ids_for_guardian(guardian_id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Clean up
+        delete_test_table(&client, &table_name)
+            .await
+            .expect("Failed to delete test table");
+    }
+
     // Test for getting boxes by guardian ID
     #[tokio::test]
     async fn dynamo_store_get_boxes_by_guardian_id() {
@@ -391,6 +608,9 @@ mod dynamo_tests {
             shard_hash: None,
             shard_fetched_at: None,
             shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
         });
 
         // Box 2 - has test_guardian as a rejected guardian (shouldn't show up)
@@ -407,6 +627,9 @@ mod dynamo_tests {
             shard_hash: None,
             shard_fetched_at: None,
             shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
         });
 
         // Box 3 - different guardian
@@ -423,6 +646,9 @@ mod dynamo_tests {
             shard_hash: None,
             shard_fetched_at: None,
             shard_accepted_at: None,
+            shard_commit: None,
+            shard_first_fetched_at: None,
+            last_reminded_at: None,
         });
 
         store.create_box(test_box1.clone()).await.unwrap();
@@ -444,6 +670,84 @@ mod dynamo_tests {
             .expect("Failed to delete test table");
     }
 
+    // Test for fetching more boxes than fit in one BatchGetItem request
+    #[tokio::test]
+    async fn dynamo_store_get_boxes_by_ids() {
+        init_test_logging();
+        if !is_dynamodb_local_running() {
+            info!("Skipping test dynamo_store_get_boxes_by_ids: DynamoDB Local is not running");
+            return;
+        }
+
+        let (store, client, table_name) = create_test_store().await;
+
+        let mut ids = Vec::new();
+        for i in 0..150 {
+            let test_box = create_test_box(&format!("Batch Box {}", i), "test_owner");
+            ids.push(test_box.id.clone());
+            store.create_box(test_box).await.unwrap();
+        }
+
+        let mut requested = ids.clone();
+        requested.push(Uuid::new_v4().to_string());
+
+        let boxes = store.get_boxes_by_ids(&requested).await.unwrap();
+        let fetched_ids: Vec<String> = boxes.into_iter().map(|b| b.id).collect();
+        assert_eq!(fetched_ids, ids);
+
+        // Clean up
+        delete_test_table(&client, &table_name)
+            .await
+            .expect("Failed to delete test table");
+    }
+
+    // Test for scanning locked boxes by lock age
+    #[tokio::test]
+    async fn dynamo_store_scan_locked_boxes_since() {
+        init_test_logging();
+        if !is_dynamodb_local_running() {
+            info!(
+                "Skipping test dynamo_store_scan_locked_boxes_since: DynamoDB Local is not running"
+            );
+            return;
+        }
+
+        let (store, client, table_name) = create_test_store().await;
+        let now = chrono::Utc::now();
+        let hours_ago = |h: i64| (now - chrono::Duration::hours(h)).to_rfc3339();
+
+        let locked_box = |name: &str, locked_at: String| {
+            let mut box_rec = create_test_box(name, "test_owner");
+            box_rec.is_locked = true;
+            box_rec.locked_at = Some(locked_at.clone());
+            box_rec.updated_at = locked_at;
+            box_rec
+        };
+
+        let recent = locked_box("Recent", hours_ago(2));
+        let old = locked_box("Old", hours_ago(500));
+        let mut deleted = locked_box("Deleted", hours_ago(2));
+        deleted.shards_deleted_at = Some(hours_ago(1));
+
+        for box_rec in [&recent, &old, &deleted] {
+            store.create_box(box_rec.clone()).await.unwrap();
+        }
+
+        let cutoff = now - chrono::Duration::hours(174);
+        let boxes = store.scan_locked_boxes_since(cutoff, true).await.unwrap();
+        let ids: Vec<&str> = boxes.iter().map(|b| b.id.as_str()).collect();
+        assert_eq!(ids, vec![recent.id.as_str()]);
+
+        let boxes = store.scan_locked_boxes_since(cutoff, false).await.unwrap();
+        assert_eq!(boxes.len(), 2);
+        assert!(boxes.iter().all(|b| b.id != old.id));
+
+        // Clean up
+        delete_test_table(&client, &table_name)
+            .await
+            .expect("Failed to delete test table");
+    }
+
     // Test for scanning locked boxes
     #[tokio::test]
     async fn dynamo_store_scan_locked_boxes() {
@@ -0,0 +1,76 @@
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+
+use crate::models::PushToken;
+use crate::push::{NotificationOptions, PROGRESS_UPDATED};
+use crate::test_utils::mock_notification_throttle_store::MockNotificationThrottleStore;
+use crate::test_utils::mock_push_sender::MockPushSender;
+use crate::throttle::{send_owner_progress_notification, OwnerProgressThrottle};
+
+fn owner_token() -> PushToken {
+    PushToken {
+        user_id: "owner".to_string(),
+        push_token: "ExponentPushToken[owner]".to_string(),
+        platform: "ios".to_string(),
+        updated_at: "2024-01-01T00:00:00Z".to_string(),
+        last_test_notification_at: None,
+        last_verified_at: None,
+        notification_prefs: None,
+        utc_offset_minutes: None,
+    }
+}
+
+fn at(time: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(time)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+#[tokio::test]
+async fn test_rapid_progress_events_push_owner_once() {
+    let throttle = OwnerProgressThrottle::new(
+        Arc::new(MockNotificationThrottleStore::new()),
+        Duration::seconds(60),
+    );
+    let sender = MockPushSender::new();
+    let tokens = [owner_token()];
+    let options = NotificationOptions::default();
+    let start = at("2030-01-01T00:00:00Z");
+
+    // Five guardians accept within a few seconds of each other
+    let mut pushed = 0;
+    for i in 0..5 {
+        let now = start + Duration::seconds(i * 5);
+        if let Some(result) = send_owner_progress_notification(
+            &throttle, &sender, &tokens, "Family", "box-1", now, &options,
+        )
+        .await
+        {
+            result.expect("send should succeed");
+            pushed += 1;
+        }
+    }
+    assert_eq!(pushed, 1);
+
+    let sent = sender.sent();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].to, "ExponentPushToken[owner]");
+    assert_eq!(sent[0].data.as_ref().unwrap()["type"], PROGRESS_UPDATED);
+    assert_eq!(sent[0].data.as_ref().unwrap()["boxId"], "box-1");
+
+    // Another box isn't held back by the first
+    assert!(send_owner_progress_notification(
+        &throttle, &sender, &tokens, "Work", "box-2", start, &options,
+    )
+    .await
+    .is_some());
+
+    // Once the window has passed the owner hears about the box again
+    let later = start + Duration::seconds(60);
+    assert!(send_owner_progress_notification(
+        &throttle, &sender, &tokens, "Family", "box-1", later, &options,
+    )
+    .await
+    .is_some());
+    assert_eq!(sender.sent().len(), 3);
+}
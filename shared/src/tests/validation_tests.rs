@@ -0,0 +1,67 @@
+use crate::error::StoreError;
+use crate::validation::{
+    normalize_unlock_instructions, validate_name, MAX_NAME_CHARS, MAX_UNLOCK_INSTRUCTIONS_CHARS,
+};
+
+#[test]
+fn test_validate_name_trims_and_strips_control_characters() {
+    assert_eq!(validate_name("name", "  Alice  ").unwrap(), "Alice");
+    assert_eq!(validate_name("name", "Al\u{0}ice\n").unwrap(), "Alice");
+    assert_eq!(
+        validate_name("name", "\tBob\u{7f} Smith").unwrap(),
+        "Bob Smith"
+    );
+}
+
+#[test]
+fn test_validate_name_rejects_empty() {
+    for value in ["", "   ", "\n\t", "\u{1b}\u{7}"] {
+        match validate_name("invitedName", value) {
+            Err(StoreError::ValidationError(msg)) => {
+                assert_eq!(msg, "invitedName must not be empty")
+            }
+            other => panic!(
+                "expected a validation error for {:?}, got {:?}",
+                value, other
+            ),
+        }
+    }
+}
+
+#[test]
+fn test_validate_name_caps_length() {
+    // Characters, not bytes, are counted
+    let longest = "é".repeat(MAX_NAME_CHARS);
+    assert_eq!(validate_name("name", &longest).unwrap(), longest);
+
+    let too_long = "a".repeat(MAX_NAME_CHARS + 1);
+    match validate_name("name", &too_long) {
+        Err(StoreError::ValidationError(msg)) => {
+            assert_eq!(msg, "name must be at most 100 characters")
+        }
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+
+    // Whitespace trimmed away doesn't count towards the limit
+    let padded = format!("  {}  ", "a".repeat(MAX_NAME_CHARS));
+    assert!(validate_name("name", &padded).is_ok());
+}
+
+#[test]
+fn test_normalize_unlock_instructions_keeps_line_breaks() {
+    assert_eq!(
+        normalize_unlock_instructions("unlockInstructions", "  Call Sam\r\nthen\u{0} Alex \n")
+            .unwrap(),
+        "Call Sam\nthen Alex"
+    );
+
+    let error = normalize_unlock_instructions("unlockInstructions", " \n ").unwrap_err();
+    assert_eq!(error.code, "EMPTY");
+
+    let longest = "a".repeat(MAX_UNLOCK_INSTRUCTIONS_CHARS);
+    assert!(normalize_unlock_instructions("unlockInstructions", &longest).is_ok());
+    let error =
+        normalize_unlock_instructions("unlockInstructions", &format!("{}a", longest)).unwrap_err();
+    assert_eq!(error.code, "TOO_LONG");
+    assert_eq!(error.field, "unlockInstructions");
+}
@@ -0,0 +1,159 @@
+use crate::error::StoreError;
+use crate::webhook::{
+    deliver_webhook, generate_webhook_secret, post_webhook, resolve_public_addrs,
+    sign_webhook_payload, validate_webhook_url, webhook_client_builder, WebhookPayload,
+    WEBHOOK_SIGNATURE_HEADER,
+};
+use reqwest::Url;
+
+const SECRET: &str = "webhook-secret-for-tests";
+
+fn payload() -> WebhookPayload {
+    WebhookPayload {
+        box_id: "box-1".to_string(),
+        event: "box_unlocked".to_string(),
+        timestamp: "2024-01-01T00:00:00Z".to_string(),
+    }
+}
+
+// Matches requests whose signature header is valid for their body
+fn signed_with(secret: &'static str) -> impl Fn(&mockito::Request) -> bool {
+    move |request| {
+        let body = request.body().unwrap();
+        request
+            .header(WEBHOOK_SIGNATURE_HEADER)
+            .first()
+            .is_some_and(|value| *value == sign_webhook_payload(secret, body).as_str())
+    }
+}
+
+#[test]
+fn test_validate_webhook_url() {
+    assert!(validate_webhook_url("https://hooks.example.com/lockbox").is_ok());
+    assert!(validate_webhook_url("http://hooks.example.com/lockbox").is_err());
+    assert!(validate_webhook_url("ftp://hooks.example.com").is_err());
+    assert!(validate_webhook_url("not a url").is_err());
+}
+
+#[test]
+fn test_validate_webhook_url_rejects_internal_hosts() {
+    for url in [
+        "https://localhost/hook",
+        "https://api.localhost/hook",
+        "https://127.0.0.1/hook",
+        "https://10.0.0.5/hook",
+        "https://172.16.0.1/hook",
+        "https://192.168.1.1/hook",
+        "https://169.254.169.254/latest/meta-data",
+        "https://100.64.0.1/hook",
+        "https://0.0.0.0/hook",
+        "https://[::1]/hook",
+        "https://[fd00::1]/hook",
+        "https://[fe80::1]/hook",
+        "https://[::ffff:127.0.0.1]/hook",
+    ] {
+        assert!(validate_webhook_url(url).is_err(), "{} was accepted", url);
+    }
+    assert!(validate_webhook_url("https://93.184.216.34/hook").is_ok());
+}
+
+#[tokio::test]
+async fn test_deliver_webhook_refuses_internal_addresses() {
+    let result = deliver_webhook("https://127.0.0.1:9/hook", SECRET, &payload()).await;
+    assert!(matches!(result, Err(StoreError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_resolved_addresses_are_checked() {
+    // Checked after the lookup, whatever the name looks like
+    let url = Url::parse("https://localhost:9/hook").unwrap();
+    let result = resolve_public_addrs(&url).await;
+    assert!(matches!(result, Err(StoreError::ValidationError(_))));
+}
+
+// Mockito serves plain HTTP on loopback, so these go through the client
+// `deliver_webhook` uses without its address checks
+async fn post(url: &str) -> crate::error::Result<()> {
+    let client = webhook_client_builder().build().unwrap();
+    post_webhook(&client, url, SECRET, &payload()).await
+}
+
+#[test]
+fn test_webhook_secrets_are_unique() {
+    let first = generate_webhook_secret().unwrap();
+    let second = generate_webhook_secret().unwrap();
+    assert_eq!(first.len(), 64);
+    assert_ne!(first, second);
+}
+
+#[tokio::test]
+async fn test_deliver_webhook_signs_payload() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server
+        .mock("POST", "/hook")
+        .match_header("content-type", "application/json")
+        .match_body(mockito::Matcher::Json(serde_json::json!({
+            "box_id": "box-1",
+            "event": "box_unlocked",
+            "timestamp": "2024-01-01T00:00:00Z",
+        })))
+        .match_request(signed_with(SECRET))
+        .expect(1)
+        .create_async()
+        .await;
+
+    post(&format!("{}/hook", server.url())).await.unwrap();
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_deliver_webhook_retries_server_errors() {
+    let mut server = mockito::Server::new_async().await;
+    let failing = server
+        .mock("POST", "/hook")
+        .with_status(503)
+        .expect(3)
+        .create_async()
+        .await;
+
+    let result = post(&format!("{}/hook", server.url())).await;
+    assert!(result.is_err());
+    failing.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_deliver_webhook_does_not_retry_client_errors() {
+    let mut server = mockito::Server::new_async().await;
+    let rejected = server
+        .mock("POST", "/hook")
+        .with_status(410)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let result = post(&format!("{}/hook", server.url())).await;
+    assert!(result.is_err());
+    rejected.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_webhook_redirects_are_not_followed() {
+    let mut server = mockito::Server::new_async().await;
+    let redirect = server
+        .mock("POST", "/hook")
+        .with_status(307)
+        .with_header("location", &format!("{}/internal", server.url()))
+        .expect(1)
+        .create_async()
+        .await;
+    let target = server
+        .mock("POST", "/internal")
+        .expect(0)
+        .create_async()
+        .await;
+
+    let result = post(&format!("{}/hook", server.url())).await;
+    assert!(result.is_err());
+    redirect.assert_async().await;
+    target.assert_async().await;
+}
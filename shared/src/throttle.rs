@@ -0,0 +1,90 @@
+//! Coalescing of owner-facing progress notifications.
+//!
+//! When several guardians accept in quick succession, pushing the owner once
+//! per acceptance floods their phone. Instead the first event in a window
+//! sends a single "progress updated" push for the box, and later events in
+//! the same window send nothing; the owner sees every change in the app.
+
+use chrono::{DateTime, Duration, Utc};
+use log::warn;
+
+use crate::models::PushToken;
+use crate::push::{ExpoPushTicket, NotificationOptions, PushError, PushSender, PROGRESS_UPDATED};
+use crate::store::SharedNotificationThrottleStore;
+
+/// Window used when `OWNER_PROGRESS_THROTTLE_SECS` is unset or invalid
+pub const DEFAULT_OWNER_PROGRESS_WINDOW_SECS: i64 = 60;
+
+/// Decides which owner progress events get to push, at most one per box per
+/// window
+pub struct OwnerProgressThrottle {
+    store: SharedNotificationThrottleStore,
+    window: Duration,
+}
+
+impl OwnerProgressThrottle {
+    pub fn new(store: SharedNotificationThrottleStore, window: Duration) -> Self {
+        Self { store, window }
+    }
+
+    /// Throttle with its window from `OWNER_PROGRESS_THROTTLE_SECS`
+    pub fn from_env(store: SharedNotificationThrottleStore) -> Self {
+        let secs = std::env::var("OWNER_PROGRESS_THROTTLE_SECS")
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v >= 0)
+            .unwrap_or(DEFAULT_OWNER_PROGRESS_WINDOW_SECS);
+        Self::new(store, Duration::seconds(secs))
+    }
+
+    /// Whether a progress push about the box may go out at `now`. If the
+    /// store can't be reached the push goes out anyway; a duplicate is
+    /// better than the owner hearing nothing.
+    pub async fn should_send(&self, box_id: &str, now: DateTime<Utc>) -> bool {
+        let key = format!("owner_progress#{}", box_id);
+        match self.store.try_claim(&key, now, self.window).await {
+            Ok(claimed) => claimed,
+            Err(e) => {
+                warn!(
+                    "Failed to check progress throttle for box_id={}, sending anyway: {:?}",
+                    box_id, e
+                );
+                true
+            }
+        }
+    }
+}
+
+/// Tells a box owner their guardians' progress changed, unless a progress
+/// push about the box already went out in the current window. Returns None
+/// when the event was coalesced into that earlier push.
+pub async fn send_owner_progress_notification(
+    throttle: &OwnerProgressThrottle,
+    sender: &dyn PushSender,
+    tokens: &[PushToken],
+    box_name: &str,
+    box_id: &str,
+    now: DateTime<Utc>,
+    options: &NotificationOptions,
+) -> Option<Result<Vec<ExpoPushTicket>, PushError>> {
+    if !throttle.should_send(box_id, now).await {
+        return None;
+    }
+
+    let title = "Progress Updated";
+    let body = format!(
+        "Your guardians are responding to \"{}\". Open the app to see who's on board.",
+        box_name
+    );
+    let data = serde_json::json!({
+        "type": PROGRESS_UPDATED,
+        "boxId": box_id,
+        "boxName": box_name
+    });
+
+    Some(
+        sender
+            .send_push_notifications(tokens, title, &body, Some(data), options)
+            .await,
+    )
+}
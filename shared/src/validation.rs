@@ -0,0 +1,65 @@
+//! Normalization of user-supplied display names, such as box names and the
+//! names owners give the guardians they invite, and of a box's unlock
+//! instructions.
+
+use crate::error::{FieldError, Result, StoreError};
+
+/// Longest name accepted, in characters, after normalization
+pub const MAX_NAME_CHARS: usize = 100;
+
+/// Longest unlock instructions accepted, in characters, after normalization
+pub const MAX_UNLOCK_INSTRUCTIONS_CHARS: usize = 2000;
+
+/// The name as it should be stored: control characters removed and
+/// surrounding whitespace trimmed. Fails with a validation error naming
+/// `field` if nothing is left or the result is longer than
+/// [`MAX_NAME_CHARS`].
+pub fn validate_name(field: &str, value: &str) -> Result<String> {
+    normalize_name(field, value).map_err(|e| StoreError::ValidationError(e.message))
+}
+
+/// Like [`validate_name`], but reports the problem as a [`FieldError`] so it
+/// can be returned alongside problems with other fields
+pub fn normalize_name(field: &str, value: &str) -> std::result::Result<String, FieldError> {
+    normalize(field, value, |c| !c.is_control(), MAX_NAME_CHARS)
+}
+
+/// Unlock instructions as they should be stored. The same rules as
+/// [`normalize_name`], except that line breaks are kept and the limit is
+/// [`MAX_UNLOCK_INSTRUCTIONS_CHARS`].
+pub fn normalize_unlock_instructions(
+    field: &str,
+    value: &str,
+) -> std::result::Result<String, FieldError> {
+    normalize(
+        field,
+        value,
+        |c| c == '\n' || !c.is_control(),
+        MAX_UNLOCK_INSTRUCTIONS_CHARS,
+    )
+}
+
+fn normalize(
+    field: &str,
+    value: &str,
+    keep: impl Fn(char) -> bool,
+    max_chars: usize,
+) -> std::result::Result<String, FieldError> {
+    let cleaned: String = value.chars().filter(|&c| keep(c)).collect();
+    let cleaned = cleaned.trim();
+    if cleaned.is_empty() {
+        return Err(FieldError::new(
+            field,
+            "EMPTY",
+            format!("{} must not be empty", field),
+        ));
+    }
+    if cleaned.chars().count() > max_chars {
+        return Err(FieldError::new(
+            field,
+            "TOO_LONG",
+            format!("{} must be at most {} characters", field, max_chars),
+        ));
+    }
+    Ok(cleaned.to_string())
+}
@@ -0,0 +1,226 @@
+//! HTTP callbacks owners can register on their boxes.
+//!
+//! Payloads are JSON, signed with HMAC-SHA256 under a per-box secret that is
+//! handed to the owner once, when the webhook is registered. Receivers check
+//! the `X-Lockbox-Signature` header (`sha256=<hex>`) against the raw body.
+
+use log::{info, warn};
+use reqwest::{redirect, Client, ClientBuilder, Url};
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::Serialize;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+use crate::error::{Result, StoreError};
+
+/// Header carrying the payload signature
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "x-lockbox-signature";
+/// Event sent once enough guardians approve an unlock request
+pub const BOX_UNLOCKED_EVENT: &str = "box_unlocked";
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+const WEBHOOK_MAX_ATTEMPTS: u32 = 3;
+const WEBHOOK_RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
+
+/// Body of a webhook call
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookPayload {
+    pub box_id: String,
+    pub event: String,
+    pub timestamp: String,
+}
+
+/// Checks a URL an owner wants called. Only HTTPS is accepted, so payloads
+/// and signatures aren't sent in the clear, and hosts that name a loopback,
+/// private or link-local address are refused so a webhook can't be pointed
+/// at our own network.
+pub fn validate_webhook_url(url: &str) -> Result<()> {
+    parse_webhook_url(url).map(|_| ())
+}
+
+fn parse_webhook_url(url: &str) -> Result<Url> {
+    let parsed = Url::parse(url)
+        .map_err(|e| StoreError::ValidationError(format!("Invalid webhook URL: {}", e)))?;
+    if parsed.scheme() != "https" {
+        return Err(StoreError::ValidationError(
+            "Webhook URL must use https".into(),
+        ));
+    }
+    let host = webhook_host(&parsed);
+    if host.is_empty() {
+        return Err(StoreError::ValidationError(
+            "Webhook URL must have a host".into(),
+        ));
+    }
+    let internal = match host.parse::<IpAddr>() {
+        Ok(ip) => !is_public_ip(ip),
+        Err(_) => {
+            let domain = host.trim_end_matches('.').to_ascii_lowercase();
+            domain == "localhost" || domain.ends_with(".localhost")
+        }
+    };
+    if internal {
+        return Err(StoreError::ValidationError(
+            "Webhook URL must not point at an internal address".into(),
+        ));
+    }
+    Ok(parsed)
+}
+
+// Host of a webhook URL, without the brackets around IPv6 literals
+fn webhook_host(url: &Url) -> &str {
+    url.host_str()
+        .unwrap_or_default()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+}
+
+// Whether an address is routable on the public internet, i.e. not loopback,
+// private, link-local, carrier-grade NAT or otherwise reserved
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_documentation()
+                || a == 0
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public_ip(v4.into()),
+            None => {
+                let first = v6.segments()[0];
+                !(v6.is_loopback()
+                    || v6.is_unspecified()
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+// Resolves the webhook host and refuses it if any address it resolves to is
+// internal. The addresses returned are the ones the request must go to, so a
+// second lookup can't swap in a different answer.
+pub(crate) async fn resolve_public_addrs(url: &Url) -> Result<Vec<SocketAddr>> {
+    let host = webhook_host(url);
+    let port = url.port_or_known_default().unwrap_or(443);
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| StoreError::Transport(format!("Failed to resolve webhook host: {}", e)))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(StoreError::Transport(
+            "Webhook host did not resolve to any address".into(),
+        ));
+    }
+    if addrs.iter().any(|addr| !is_public_ip(addr.ip())) {
+        return Err(StoreError::ValidationError(
+            "Webhook host resolves to an internal address".into(),
+        ));
+    }
+    Ok(addrs)
+}
+
+/// Generates a fresh signing secret for a webhook
+pub fn generate_webhook_secret() -> Result<String> {
+    let mut bytes = [0u8; 32];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| StoreError::InternalError("Failed to generate webhook secret".into()))?;
+    Ok(hex::encode(bytes))
+}
+
+/// Signature header value for a payload body
+pub fn sign_webhook_payload(secret: &str, body: &[u8]) -> String {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+    format!("sha256={}", hex::encode(hmac::sign(&key, body).as_ref()))
+}
+
+/// Posts a signed payload to `url`. The host is resolved first and the call
+/// only goes to the public addresses it resolved to; redirects aren't
+/// followed. Network errors, timeouts and 5xx or 429 responses are retried a
+/// couple of times; other responses are final.
+pub async fn deliver_webhook(url: &str, secret: &str, payload: &WebhookPayload) -> Result<()> {
+    let parsed = parse_webhook_url(url)?;
+    let addrs = resolve_public_addrs(&parsed).await?;
+    let mut builder = webhook_client_builder();
+    let host = webhook_host(&parsed);
+    if host.parse::<IpAddr>().is_err() {
+        builder = builder.resolve_to_addrs(host, &addrs);
+    }
+    let client = builder
+        .build()
+        .map_err(|e| StoreError::InternalError(format!("Failed to build HTTP client: {}", e)))?;
+    post_webhook(&client, url, secret, payload).await
+}
+
+pub(crate) fn webhook_client_builder() -> ClientBuilder {
+    Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .redirect(redirect::Policy::none())
+}
+
+// Sends the signed payload with `client`, retrying transient failures
+pub(crate) async fn post_webhook(
+    client: &Client,
+    url: &str,
+    secret: &str,
+    payload: &WebhookPayload,
+) -> Result<()> {
+    let body = serde_json::to_vec(payload)?;
+    let signature = sign_webhook_payload(secret, &body);
+
+    let mut attempt = 1;
+    loop {
+        let result = client
+            .post(url)
+            .header("content-type", "application/json")
+            .header(WEBHOOK_SIGNATURE_HEADER, &signature)
+            .body(body.clone())
+            .send()
+            .await;
+
+        let retryable = match result {
+            Ok(response) if response.status().is_success() => {
+                info!(
+                    "Webhook delivered: box_id={}, event={}, attempt={}",
+                    payload.box_id, payload.event, attempt
+                );
+                return Ok(());
+            }
+            Ok(response) => {
+                let status = response.status();
+                warn!(
+                    "Webhook rejected: box_id={}, status={}, attempt={}",
+                    payload.box_id, status, attempt
+                );
+                if !(status.is_server_error() || status.as_u16() == 429) {
+                    return Err(StoreError::InternalError(format!(
+                        "Webhook endpoint returned {}",
+                        status
+                    )));
+                }
+                format!("Webhook endpoint returned {}", status)
+            }
+            Err(e) => {
+                warn!(
+                    "Webhook delivery failed: box_id={}, attempt={}, error={}",
+                    payload.box_id, attempt, e
+                );
+                format!("Webhook delivery failed: {}", e)
+            }
+        };
+
+        if attempt >= WEBHOOK_MAX_ATTEMPTS {
+            return Err(StoreError::InternalError(retryable));
+        }
+        tokio::time::sleep(WEBHOOK_RETRY_BASE_DELAY * 2u32.pow(attempt - 1)).await;
+        attempt += 1;
+    }
+}